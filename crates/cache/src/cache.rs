@@ -1,23 +1,567 @@
+use std::{collections::HashSet, sync::Arc, time::Duration};
+
 use anyhow::Result;
 use chrono::NaiveDateTime;
-use serde_json::Value;
+use serde_json::{Map, Value};
+use wide::f32x8;
 
-#[derive(serde::Deserialize, serde::Serialize)]
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
 pub struct CacheEntry<T> {
     pub value: T,
     pub created_at: NaiveDateTime,
 }
 
-#[derive(serde::Deserialize, serde::Serialize)]
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
 pub struct Query {
     pub action: String,
     pub text: String,
     pub params: Option<Value>,
     pub embedding: Vec<f32>,
+    /// Provider and model that produced `embedding`, such as
+    /// `ollama/nomic-embed-text:latest`. Vectors of different models aren't
+    /// comparable even when their lengths match, so results cached with
+    /// another model are never reused
+    #[serde(default)]
+    pub embedding_model: Option<String>,
+    /// Whether `embedding` was scaled to unit length when stored, which
+    /// makes its magnitude 1 and its similarity with a query a dot product.
+    /// Entries cached earlier are compared by cosine similarity
+    #[serde(default)]
+    pub normalized: bool,
     pub results: Value,
 }
 
+impl Query {
+    /// Scales the embedding to unit length, which backends do as they store
+    /// it. Vectors of all zeros are left as they are.
+    pub fn normalize(&mut self) {
+        if self.normalized {
+            return;
+        }
+        if let Some(unit) = unit_vector(&self.embedding) {
+            self.embedding = unit;
+            self.normalized = true;
+        }
+    }
+}
+
+/// A raw API response, reused without a request while it is fresh and
+/// revalidated with a conditional request afterwards.
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+pub struct CachedResponse {
+    pub etag: Option<String>,
+    /// Seconds the response stays fresh, from Cache-Control max-age
+    #[serde(default)]
+    pub max_age: Option<u64>,
+    pub body: Value,
+    /// Set when the request found nothing, in which case the entry is only
+    /// reused while fresh and never revalidated
+    #[serde(default)]
+    pub negative: Option<NegativeKind>,
+}
+
+/// What a request that found nothing returned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NegativeKind {
+    /// HTTP 404, such as an unknown paper or author ID. The body holds the
+    /// API's error message
+    NotFound,
+    /// A successful response without results, such as a search matching
+    /// nothing
+    Empty,
+}
+
+/// Raw responses outlive the query TTL, since they are revalidated before use.
+pub const RESPONSE_RETENTION: Duration = Duration::from_secs(60 * 60 * 24 * 30);
+
+/// How long cached queries are reused unless configured otherwise.
+pub const DEFAULT_TTL: Duration = Duration::from_secs(60 * 60 * 24);
+
+/// How long a request that found nothing is answered from the cache unless
+/// configured otherwise. Short, since a paper missing now may be indexed soon.
+pub const DEFAULT_NEGATIVE_TTL: Duration = Duration::from_secs(60 * 5);
+
+/// The text a query is stored under for exact lookups: its action and
+/// parameters, which identify the API request behind a tool result. Keys of
+/// the parameter objects are serialized in order, so equal parameters give
+/// equal text.
+pub fn query_key(action: &str, params: &Value) -> String {
+    format!("{} {}", action, params)
+}
+
+/// Which entries [`Cache::clear`] removes. Entries must match every filter
+/// given, and with none everything is removed.
+#[derive(Debug, Clone, Default)]
+pub struct ClearFilter {
+    /// Only cached tool results of this action, such as `paper_search`
+    pub action: Option<String>,
+    /// Only entries mentioning an ID that contains this text: tool results
+    /// and raw responses holding it, and papers and authors whose ID holds it
+    pub id_contains: Option<String>,
+    /// Only entries stored before this time
+    pub stored_before: Option<NaiveDateTime>,
+}
+
+impl ClearFilter {
+    pub fn matches_query(&self, entry: &CacheEntry<Query>) -> bool {
+        self.action
+            .as_ref()
+            .is_none_or(|action| *action == entry.value.action)
+            && self.id_contains.as_ref().is_none_or(|id| {
+                entry.value.text.contains(id.as_str())
+                    || mentions(entry.value.params.as_ref(), id)
+                    || mentions(Some(&entry.value.results), id)
+            })
+            && self.stored_before(entry.created_at)
+    }
+
+    /// Raw responses don't record the tool they were fetched for, so none
+    /// match a filter on the action.
+    pub fn matches_response(&self, entry: &CacheEntry<CachedResponse>) -> bool {
+        self.action.is_none()
+            && self
+                .id_contains
+                .as_ref()
+                .is_none_or(|id| mentions(Some(&entry.value.body), id))
+            && self.stored_before(entry.created_at)
+    }
+
+    /// Papers and authors match on their own ID.
+    pub fn matches_record(&self, id: &str, entry: &CacheEntry<Value>) -> bool {
+        self.action.is_none()
+            && self
+                .id_contains
+                .as_ref()
+                .is_none_or(|text| id.contains(text.as_str()))
+            && self.stored_before(entry.created_at)
+    }
+
+    /// The papers linked to an author carry no date, so they are kept when
+    /// clearing by age.
+    pub fn matches_author_papers(&self, author_id: &str) -> bool {
+        self.action.is_none()
+            && self.stored_before.is_none()
+            && self
+                .id_contains
+                .as_ref()
+                .is_none_or(|text| author_id.contains(text.as_str()))
+    }
+
+    /// Memoized embeddings mention no ID and carry no date, so they are only
+    /// removed with everything else.
+    pub fn matches_embeddings(&self) -> bool {
+        self.action.is_none() && self.id_contains.is_none() && self.stored_before.is_none()
+    }
+
+    fn stored_before(&self, created_at: NaiveDateTime) -> bool {
+        self.stored_before
+            .is_none_or(|stored_before| created_at < stored_before)
+    }
+}
+
+fn mentions(value: Option<&Value>, text: &str) -> bool {
+    value.is_some_and(|value| value.to_string().contains(text))
+}
+
+/// How many entries of each kind [`Cache::clear`] removed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ClearedEntries {
+    pub queries: usize,
+    pub responses: usize,
+    pub papers: usize,
+    pub authors: usize,
+}
+
+impl ClearedEntries {
+    pub fn total(&self) -> usize {
+        self.queries + self.responses + self.papers + self.authors
+    }
+}
+
 pub trait Cache: Send + Sync {
     fn store(&self, query: Query) -> Result<()>;
     fn search_similarity(&self, query: &[f32]) -> Result<Vec<(Query, f32)>>;
+    /// The unexpired query stored with exactly this action and these
+    /// parameters, found without comparing embeddings.
+    fn get_query(&self, action: &str, params: &Value) -> Result<Option<Query>>;
+    fn get_response(&self, key: &str) -> Result<Option<CacheEntry<CachedResponse>>>;
+    fn store_response(&self, key: &str, response: CachedResponse) -> Result<()>;
+    /// Remembers papers seen in API responses, merging their fields into any
+    /// already stored under the same `paperId`.
+    fn store_papers(&self, papers: &[Value]) -> Result<()>;
+    fn get_paper(&self, paper_id: &str) -> Result<Option<CacheEntry<Value>>>;
+    /// The papers stored under each of `paper_ids`, in the same order.
+    /// Backends that can look them up together override this.
+    fn get_papers(&self, paper_ids: &[&str]) -> Result<Vec<Option<CacheEntry<Value>>>> {
+        paper_ids
+            .iter()
+            .map(|paper_id| self.get_paper(paper_id))
+            .collect()
+    }
+    /// The most recently seen papers, newest first.
+    fn recent_papers(&self, limit: usize) -> Result<Vec<CacheEntry<Value>>>;
+    /// Remembers authors seen in API responses, merged by `authorId` like
+    /// papers.
+    fn store_authors(&self, authors: &[Value]) -> Result<()>;
+    fn get_author(&self, author_id: &str) -> Result<Option<CacheEntry<Value>>>;
+    /// The most recently seen authors, newest first.
+    fn recent_authors(&self, limit: usize) -> Result<Vec<CacheEntry<Value>>>;
+    /// Links papers to an author, keeping the ones linked before.
+    fn store_author_papers(&self, author_id: &str, paper_ids: &[String]) -> Result<()>;
+    fn get_author_papers(&self, author_id: &str) -> Result<Vec<String>>;
+    /// Remembers the embedding of a text under `key`, which names the text
+    /// and the model, so the same text is never embedded twice. Embeddings
+    /// don't go stale and never expire.
+    fn store_embedding(&self, key: &str, embedding: &[f32]) -> Result<()>;
+    fn get_embedding(&self, key: &str) -> Result<Option<Vec<f32>>>;
+    /// Removes the entries matching `filter`, so the next lookups reach the
+    /// API.
+    fn clear(&self, filter: &ClearFilter) -> Result<ClearedEntries>;
+    /// Deletes tool results past the TTL and raw responses past
+    /// [`RESPONSE_RETENTION`], returning how many were removed.
+    fn purge_expired(&self) -> Result<usize>;
+}
+
+/// Stands in for a cache when caching is turned off: nothing is stored and
+/// every lookup misses.
+pub struct NoCache;
+
+impl Cache for NoCache {
+    fn store(&self, _query: Query) -> Result<()> {
+        Ok(())
+    }
+
+    fn search_similarity(&self, _query: &[f32]) -> Result<Vec<(Query, f32)>> {
+        Ok(Vec::new())
+    }
+
+    fn get_query(&self, _action: &str, _params: &Value) -> Result<Option<Query>> {
+        Ok(None)
+    }
+
+    fn get_response(&self, _key: &str) -> Result<Option<CacheEntry<CachedResponse>>> {
+        Ok(None)
+    }
+
+    fn store_response(&self, _key: &str, _response: CachedResponse) -> Result<()> {
+        Ok(())
+    }
+
+    fn store_papers(&self, _papers: &[Value]) -> Result<()> {
+        Ok(())
+    }
+
+    fn get_paper(&self, _paper_id: &str) -> Result<Option<CacheEntry<Value>>> {
+        Ok(None)
+    }
+
+    fn recent_papers(&self, _limit: usize) -> Result<Vec<CacheEntry<Value>>> {
+        Ok(Vec::new())
+    }
+
+    fn store_authors(&self, _authors: &[Value]) -> Result<()> {
+        Ok(())
+    }
+
+    fn get_author(&self, _author_id: &str) -> Result<Option<CacheEntry<Value>>> {
+        Ok(None)
+    }
+
+    fn recent_authors(&self, _limit: usize) -> Result<Vec<CacheEntry<Value>>> {
+        Ok(Vec::new())
+    }
+
+    fn store_author_papers(&self, _author_id: &str, _paper_ids: &[String]) -> Result<()> {
+        Ok(())
+    }
+
+    fn get_author_papers(&self, _author_id: &str) -> Result<Vec<String>> {
+        Ok(Vec::new())
+    }
+
+    fn store_embedding(&self, _key: &str, _embedding: &[f32]) -> Result<()> {
+        Ok(())
+    }
+
+    fn get_embedding(&self, _key: &str) -> Result<Option<Vec<f32>>> {
+        Ok(None)
+    }
+
+    fn clear(&self, _filter: &ClearFilter) -> Result<ClearedEntries> {
+        Ok(ClearedEntries::default())
+    }
+
+    fn purge_expired(&self) -> Result<usize> {
+        Ok(0)
+    }
+}
+
+/// A record of the overlay of a [`ReadOnlyCache`] merged into the base's,
+/// the overlay being newer.
+fn merge_entries(
+    base: Option<CacheEntry<Value>>,
+    overlay: Option<CacheEntry<Value>>,
+) -> Option<CacheEntry<Value>> {
+    match (base, overlay) {
+        (Some(base), Some(CacheEntry { value, created_at })) => Some(CacheEntry {
+            value: match &value {
+                Value::Object(fields) => merge_record(Some(base.value), fields),
+                _ => value,
+            },
+            created_at,
+        }),
+        (base, overlay) => overlay.or(base),
+    }
+}
+
+/// Consults a cache without ever writing to it, such as a snapshot a team
+/// shares. What would be written goes to `overlay` when there is one, which
+/// is consulted first, and is dropped otherwise.
+pub struct ReadOnlyCache {
+    base: Arc<dyn Cache>,
+    overlay: Option<Arc<dyn Cache>>,
+}
+
+impl ReadOnlyCache {
+    pub fn new(base: Arc<dyn Cache>, overlay: Option<Arc<dyn Cache>>) -> Self {
+        Self { base, overlay }
+    }
+
+    fn write(&self, what: &str, f: impl FnOnce(&dyn Cache) -> Result<()>) -> Result<()> {
+        match &self.overlay {
+            Some(overlay) => f(overlay.as_ref()),
+            None => {
+                tracing::trace!("Not caching {} in a read-only cache", what);
+                Ok(())
+            }
+        }
+    }
+
+    /// The overlay's record merged into the base's, the overlay being newer.
+    fn get_record(
+        &self,
+        get: impl Fn(&dyn Cache) -> Result<Option<CacheEntry<Value>>>,
+    ) -> Result<Option<CacheEntry<Value>>> {
+        let base = get(self.base.as_ref())?;
+        let Some(overlay) = &self.overlay else {
+            return Ok(base);
+        };
+        Ok(merge_entries(base, get(overlay.as_ref())?))
+    }
+
+    /// The most recent records of both caches, each record once.
+    fn recent_records(
+        &self,
+        id_field: &str,
+        limit: usize,
+        recent: impl Fn(&dyn Cache) -> Result<Vec<CacheEntry<Value>>>,
+    ) -> Result<Vec<CacheEntry<Value>>> {
+        let mut records = recent(self.base.as_ref())?;
+        if let Some(overlay) = &self.overlay {
+            records.extend(recent(overlay.as_ref())?);
+        }
+        records.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+
+        let mut seen = HashSet::new();
+        records.retain(
+            |record| match record.value.get(id_field).and_then(Value::as_str) {
+                Some(id) => seen.insert(id.to_string()),
+                None => true,
+            },
+        );
+        records.truncate(limit);
+        Ok(records)
+    }
+}
+
+impl Cache for ReadOnlyCache {
+    fn store(&self, query: Query) -> Result<()> {
+        self.write("a tool result", |cache| cache.store(query))
+    }
+
+    fn search_similarity(&self, query: &[f32]) -> Result<Vec<(Query, f32)>> {
+        let mut results = self.base.search_similarity(query)?;
+        if let Some(overlay) = &self.overlay {
+            results.extend(overlay.search_similarity(query)?);
+            results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        }
+        Ok(results)
+    }
+
+    fn get_query(&self, action: &str, params: &Value) -> Result<Option<Query>> {
+        if let Some(overlay) = &self.overlay {
+            if let Some(query) = overlay.get_query(action, params)? {
+                return Ok(Some(query));
+            }
+        }
+        self.base.get_query(action, params)
+    }
+
+    fn get_response(&self, key: &str) -> Result<Option<CacheEntry<CachedResponse>>> {
+        if let Some(overlay) = &self.overlay {
+            if let Some(entry) = overlay.get_response(key)? {
+                return Ok(Some(entry));
+            }
+        }
+        self.base.get_response(key)
+    }
+
+    fn store_response(&self, key: &str, response: CachedResponse) -> Result<()> {
+        self.write("a response", |cache| cache.store_response(key, response))
+    }
+
+    fn store_papers(&self, papers: &[Value]) -> Result<()> {
+        self.write("papers", |cache| cache.store_papers(papers))
+    }
+
+    fn get_paper(&self, paper_id: &str) -> Result<Option<CacheEntry<Value>>> {
+        self.get_record(|cache| cache.get_paper(paper_id))
+    }
+
+    fn get_papers(&self, paper_ids: &[&str]) -> Result<Vec<Option<CacheEntry<Value>>>> {
+        let base = self.base.get_papers(paper_ids)?;
+        let Some(overlay) = &self.overlay else {
+            return Ok(base);
+        };
+        Ok(base
+            .into_iter()
+            .zip(overlay.get_papers(paper_ids)?)
+            .map(|(base, overlay)| merge_entries(base, overlay))
+            .collect())
+    }
+
+    fn recent_papers(&self, limit: usize) -> Result<Vec<CacheEntry<Value>>> {
+        self.recent_records("paperId", limit, |cache| cache.recent_papers(limit))
+    }
+
+    fn store_authors(&self, authors: &[Value]) -> Result<()> {
+        self.write("authors", |cache| cache.store_authors(authors))
+    }
+
+    fn get_author(&self, author_id: &str) -> Result<Option<CacheEntry<Value>>> {
+        self.get_record(|cache| cache.get_author(author_id))
+    }
+
+    fn recent_authors(&self, limit: usize) -> Result<Vec<CacheEntry<Value>>> {
+        self.recent_records("authorId", limit, |cache| cache.recent_authors(limit))
+    }
+
+    fn store_author_papers(&self, author_id: &str, paper_ids: &[String]) -> Result<()> {
+        self.write("an author's papers", |cache| {
+            cache.store_author_papers(author_id, paper_ids)
+        })
+    }
+
+    fn get_author_papers(&self, author_id: &str) -> Result<Vec<String>> {
+        let mut paper_ids = self.base.get_author_papers(author_id)?;
+        if let Some(overlay) = &self.overlay {
+            for paper_id in overlay.get_author_papers(author_id)? {
+                if !paper_ids.contains(&paper_id) {
+                    paper_ids.push(paper_id);
+                }
+            }
+        }
+        Ok(paper_ids)
+    }
+
+    fn store_embedding(&self, key: &str, embedding: &[f32]) -> Result<()> {
+        self.write("an embedding", |cache| {
+            cache.store_embedding(key, embedding)
+        })
+    }
+
+    fn get_embedding(&self, key: &str) -> Result<Option<Vec<f32>>> {
+        if let Some(overlay) = &self.overlay {
+            if let Some(embedding) = overlay.get_embedding(key)? {
+                return Ok(Some(embedding));
+            }
+        }
+        self.base.get_embedding(key)
+    }
+
+    /// Clears the overlay alone.
+    fn clear(&self, filter: &ClearFilter) -> Result<ClearedEntries> {
+        match &self.overlay {
+            Some(overlay) => overlay.clear(filter),
+            None => Ok(ClearedEntries::default()),
+        }
+    }
+
+    fn purge_expired(&self) -> Result<usize> {
+        match &self.overlay {
+            Some(overlay) => overlay.purge_expired(),
+            None => Ok(0),
+        }
+    }
+}
+
+/// Cosine similarity of two embeddings, or `None` when either is all zeros.
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> Option<f32> {
+    // Vectors of different dimensions come from different models
+    if a.len() != b.len() {
+        return None;
+    }
+
+    let a_magnitude = dot_product(a, a).sqrt();
+    let b_magnitude = dot_product(b, b).sqrt();
+
+    (a_magnitude > 0.0 && b_magnitude > 0.0)
+        .then(|| dot_product(a, b) / (a_magnitude * b_magnitude))
+}
+
+/// Scales a vector to unit length, or `None` when it is all zeros.
+pub fn unit_vector(vector: &[f32]) -> Option<Vec<f32>> {
+    let magnitude = dot_product(vector, vector).sqrt();
+    (magnitude > 0.0).then(|| vector.iter().map(|x| x / magnitude).collect())
+}
+
+/// Dot product of two vectors of the same length, eight lanes at a time.
+/// Scoring every cached query is the bulk of a similarity search, and the
+/// scalar loop dominated it once a cache held a few thousand vectors.
+pub fn dot_product(a: &[f32], b: &[f32]) -> f32 {
+    let a_chunks = a.chunks_exact(8);
+    let b_chunks = b.chunks_exact(8);
+    let tail: f32 = a_chunks
+        .remainder()
+        .iter()
+        .zip(b_chunks.remainder())
+        .map(|(a, b)| a * b)
+        .sum();
+
+    let mut sums = f32x8::ZERO;
+    for (a, b) in a_chunks.zip(b_chunks) {
+        let a = f32x8::from(<[f32; 8]>::try_from(a).unwrap_or_default());
+        let b = f32x8::from(<[f32; 8]>::try_from(b).unwrap_or_default());
+        sums = a.mul_add(b, sums);
+    }
+    sums.reduce_add() + tail
+}
+
+/// Cosine similarity of a cached query with `unit`, a query embedding scaled
+/// to unit length by [`unit_vector`]. Backends scale the query once per
+/// search, leaving a dot product per normalized entry.
+pub fn similarity(unit: &[f32], query: &Query) -> Option<f32> {
+    if !query.normalized {
+        return cosine_similarity(&query.embedding, unit);
+    }
+    // Vectors of different dimensions come from different models
+    (query.embedding.len() == unit.len()).then(|| dot_product(&query.embedding, unit))
+}
+
+/// Merges a record's fields into the one already stored under its ID.
+/// Responses only carry the fields that were requested, so a search result
+/// must not erase the abstract a lookup stored. A null is kept only to record
+/// that the field was asked for.
+pub fn merge_record(existing: Option<Value>, fields: &Map<String, Value>) -> Value {
+    let mut merged = match existing {
+        Some(Value::Object(existing)) => existing,
+        _ => Map::new(),
+    };
+    for (key, value) in fields {
+        if !value.is_null() || !merged.contains_key(key) {
+            merged.insert(key.clone(), value.clone());
+        }
+    }
+    Value::Object(merged)
 }
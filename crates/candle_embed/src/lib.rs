@@ -0,0 +1,169 @@
+use std::{path::PathBuf, sync::Arc};
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use candle_core::{DType, Device, Tensor, utils};
+use candle_nn::VarBuilder;
+use candle_transformers::models::bert::{BertModel, Config, DTYPE};
+use embed::Embed;
+use hf_hub::{Repo, RepoType, api::sync::ApiBuilder};
+use tokenizers::{Tokenizer, TruncationParams};
+
+const DEFAULT_MODEL: &str = "sentence-transformers/all-MiniLM-L6-v2";
+
+/// Embeds texts in process with a BERT sentence-transformer run by candle,
+/// on a GPU when asked for and the crate is built with the `cuda` or
+/// `metal` feature.
+pub struct CandleEmbed {
+    model: Arc<Model>,
+    name: String,
+}
+
+struct Model {
+    bert: BertModel,
+    tokenizer: Tokenizer,
+    device: Device,
+    dimensions: usize,
+}
+
+pub struct CandleEmbedBuilder {
+    model: Option<String>,
+    cache_dir: Option<PathBuf>,
+    gpu: bool,
+}
+
+impl CandleEmbed {
+    pub fn builder() -> CandleEmbedBuilder {
+        CandleEmbedBuilder {
+            model: None,
+            cache_dir: None,
+            gpu: false,
+        }
+    }
+
+    /// The model texts are embedded with, as named on Hugging Face.
+    pub fn model(&self) -> &str {
+        &self.name
+    }
+}
+
+impl CandleEmbedBuilder {
+    /// The Hugging Face repository of a BERT sentence-transformer with
+    /// safetensors weights, `sentence-transformers/all-MiniLM-L6-v2` by
+    /// default.
+    pub fn with_model(&mut self, model: impl Into<String>) -> &mut Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    /// Where model files are downloaded to and loaded from.
+    pub fn with_cache_dir(&mut self, cache_dir: impl Into<PathBuf>) -> &mut Self {
+        self.cache_dir = Some(cache_dir.into());
+        self
+    }
+
+    /// Runs the model on the first CUDA or Metal device, falling back to the
+    /// CPU when neither is available.
+    pub fn with_gpu(&mut self, gpu: bool) -> &mut Self {
+        self.gpu = gpu;
+        self
+    }
+
+    /// Loads the model, downloading it first if it isn't in the cache
+    /// directory. This blocks, so async callers should run it with
+    /// `spawn_blocking`.
+    pub fn build(&self) -> Result<CandleEmbed> {
+        let name = self.model.as_deref().unwrap_or(DEFAULT_MODEL);
+        let device = self.device()?;
+
+        let mut api = ApiBuilder::new().with_progress(false);
+        if let Some(cache_dir) = &self.cache_dir {
+            api = api.with_cache_dir(cache_dir.clone());
+        }
+        let repo = api
+            .build()?
+            .repo(Repo::new(name.to_string(), RepoType::Model));
+        tracing::debug!("Loading {} for candle on {:?}", name, device);
+        let config: Config =
+            serde_json::from_str(&std::fs::read_to_string(repo.get("config.json")?)?)?;
+        let mut tokenizer = Tokenizer::from_file(repo.get("tokenizer.json")?)
+            .map_err(|err| anyhow!("Failed to load the tokenizer of {}: {}", name, err))?;
+        tokenizer
+            .with_padding(None)
+            .with_truncation(Some(TruncationParams {
+                max_length: config.max_position_embeddings,
+                ..Default::default()
+            }))
+            .map_err(|err| anyhow!("Failed to configure the tokenizer of {}: {}", name, err))?;
+        let weights = repo.get("model.safetensors")?;
+        // SAFETY: the weights file is not modified while it is mapped
+        let vb = unsafe { VarBuilder::from_mmaped_safetensors(&[weights], DTYPE, &device)? };
+        let bert = BertModel::load(vb, &config)?;
+
+        Ok(CandleEmbed {
+            model: Arc::new(Model {
+                bert,
+                tokenizer,
+                device,
+                dimensions: config.hidden_size,
+            }),
+            name: name.to_string(),
+        })
+    }
+
+    fn device(&self) -> Result<Device> {
+        if !self.gpu {
+            return Ok(Device::Cpu);
+        }
+        if utils::cuda_is_available() {
+            return Ok(Device::new_cuda(0)?);
+        }
+        if utils::metal_is_available() {
+            return Ok(Device::new_metal(0)?);
+        }
+        tracing::warn!("No GPU is available to candle, embedding on the CPU");
+        Ok(Device::Cpu)
+    }
+}
+
+impl Model {
+    /// Mean-pools the hidden states of every token of `text`.
+    fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        let encoding = self
+            .tokenizer
+            .encode(text, true)
+            .map_err(|err| anyhow!("Failed to tokenize text: {}", err))?;
+        let ids = Tensor::new(encoding.get_ids(), &self.device)?.unsqueeze(0)?;
+        let type_ids = ids.zeros_like()?;
+        let mask = Tensor::new(encoding.get_attention_mask(), &self.device)?.unsqueeze(0)?;
+        let hidden = self.bert.forward(&ids, &type_ids, Some(&mask))?;
+        let (_, tokens, _) = hidden.dims3()?;
+
+        Ok((hidden.sum(1)? / tokens as f64)?
+            .squeeze(0)?
+            .to_dtype(DType::F32)?
+            .to_vec1()?)
+    }
+}
+
+#[async_trait]
+impl Embed for CandleEmbed {
+    async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        tracing::debug!("Embedding {} characters with {}", text.len(), self.name);
+        let model = self.model.clone();
+        let text = text.to_string();
+        // Inference runs on the CPU or waits on the GPU, so it stays off the
+        // async workers
+        tokio::task::spawn_blocking(move || model.embed(&text))
+            .await?
+            .inspect_err(|err| tracing::warn!("candle failed to embed text: {}", err))
+    }
+
+    fn model_id(&self) -> String {
+        format!("candle/{}", self.name)
+    }
+
+    fn dimensions(&self) -> Option<usize> {
+        Some(self.model.dimensions)
+    }
+}
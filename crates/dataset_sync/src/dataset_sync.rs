@@ -0,0 +1,376 @@
+use std::{
+    fs,
+    io::{BufRead, BufReader},
+    path::Path,
+    sync::Arc,
+};
+
+use anyhow::{Result, anyhow};
+use chrono::NaiveDateTime;
+use flate2::read::GzDecoder;
+use futures::{AsyncReadExt, lock::Mutex};
+use heed::{
+    Database, Env, EnvOpenOptions,
+    types::{SerdeJson, Str},
+};
+use http_client::{HttpClient, Request, RequestBuilderExt, ResponseAsyncBodyExt};
+use serde_json::Value;
+
+const DEFAULT_BASE_URL: &str = "https://api.semanticscholar.org/datasets/v1";
+
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+pub struct SyncState {
+    pub dataset: String,
+    pub release_id: String,
+    pub records: u64,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Default)]
+pub struct SyncReport {
+    pub dataset: String,
+    pub from_release: Option<String>,
+    pub to_release: String,
+    pub files_processed: usize,
+    pub upserted: u64,
+    pub deleted: u64,
+}
+
+/// Mirrors Semantic Scholar datasets into a local LMDB store and keeps them up
+/// to date by applying the incremental diffs published between releases.
+pub struct DatasetSync {
+    http_client: Arc<dyn HttpClient>,
+    base_url: String,
+    env: Env,
+    records: Database<Str, SerdeJson<Value>>,
+    state: Database<Str, SerdeJson<SyncState>>,
+    sync_lock: Mutex<()>,
+}
+
+impl DatasetSync {
+    pub fn new<P: AsRef<Path>>(path: P, http_client: Arc<dyn HttpClient>) -> Result<Self> {
+        fs::create_dir_all(path.as_ref())?;
+
+        let env = unsafe {
+            EnvOpenOptions::new()
+                .map_size(64 * 1024 * 1024 * 1024)
+                .max_dbs(4)
+                .open(path.as_ref())?
+        };
+
+        let mut wtxn = env.write_txn()?;
+        let records = env.create_database(&mut wtxn, Some("records"))?;
+        let state = env.create_database(&mut wtxn, Some("state"))?;
+        wtxn.commit()?;
+
+        Ok(Self {
+            http_client,
+            base_url: DEFAULT_BASE_URL.to_string(),
+            env,
+            records,
+            state,
+            sync_lock: Mutex::new(()),
+        })
+    }
+
+    /// Fetches releases and diffs from another host serving the datasets
+    /// API, given as the root under which `/datasets/v1` lives.
+    pub fn with_api_base_url(mut self, api_base_url: &str) -> Self {
+        self.base_url = format!("{}/datasets/v1", api_base_url.trim_end_matches('/'));
+        self
+    }
+
+    /// Returns the sync state of every dataset tracked locally.
+    pub fn status(&self) -> Result<Vec<SyncState>> {
+        let rtxn = self.env.read_txn()?;
+        let mut states = Vec::new();
+        for item in self.state.iter(&rtxn)? {
+            let (_, state) = item?;
+            states.push(state);
+        }
+        Ok(states)
+    }
+
+    /// Looks up a single record of a synced dataset by its primary key.
+    pub fn get(&self, dataset: &str, key: &str) -> Result<Option<Value>> {
+        let rtxn = self.env.read_txn()?;
+        Ok(self.records.get(&rtxn, &record_key(dataset, key))?)
+    }
+
+    /// Seeds a dataset from a full release. `max_files` restricts the import
+    /// to the first N shards, which is enough for a representative slice.
+    pub async fn bootstrap(
+        &self,
+        dataset: &str,
+        release_id: &str,
+        max_files: Option<usize>,
+    ) -> Result<SyncReport> {
+        let _guard = self.sync_lock.lock().await;
+
+        let release_id = self.resolve_release(release_id).await?;
+        let response = self
+            .get_json(&format!("/release/{}/dataset/{}", release_id, dataset))
+            .await?;
+
+        let files: Vec<String> = response
+            .get("files")
+            .and_then(|f| f.as_array())
+            .ok_or_else(|| anyhow!("Unexpected dataset response for {}", dataset))?
+            .iter()
+            .filter_map(|f| f.as_str().map(String::from))
+            .take(max_files.unwrap_or(usize::MAX))
+            .collect();
+
+        let mut report = SyncReport {
+            dataset: dataset.into(),
+            to_release: release_id.clone(),
+            ..Default::default()
+        };
+
+        for file in &files {
+            tracing::info!("Importing {} shard {}", dataset, redact_url(file));
+            report.upserted += self.apply_file(dataset, file, FileKind::Update).await?;
+            report.files_processed += 1;
+        }
+
+        self.record_state(dataset, &release_id, report.upserted as i64)?;
+
+        Ok(report)
+    }
+
+    /// Brings a previously bootstrapped dataset up to `target_release` by
+    /// applying each intermediate diff in order. State is committed after every
+    /// diff, so an interrupted run resumes where it stopped.
+    pub async fn sync(&self, dataset: &str, target_release: &str) -> Result<SyncReport> {
+        let _guard = self.sync_lock.lock().await;
+
+        let current = self.dataset_state(dataset)?.ok_or_else(|| {
+            anyhow!(
+                "Dataset {} has not been bootstrapped yet. Run a bootstrap before syncing.",
+                dataset
+            )
+        })?;
+
+        let target_release = self.resolve_release(target_release).await?;
+
+        let mut report = SyncReport {
+            dataset: dataset.into(),
+            from_release: Some(current.release_id.clone()),
+            to_release: target_release.clone(),
+            ..Default::default()
+        };
+
+        if current.release_id >= target_release {
+            return Ok(report);
+        }
+
+        let response = self
+            .get_json(&format!(
+                "/diffs/{}/to/{}/{}",
+                current.release_id, target_release, dataset
+            ))
+            .await?;
+
+        let diffs = response
+            .get("diffs")
+            .and_then(|d| d.as_array())
+            .ok_or_else(|| anyhow!("Unexpected diff response for {}", dataset))?;
+
+        for diff in diffs {
+            let to_release = diff
+                .get("to_release")
+                .and_then(|r| r.as_str())
+                .ok_or_else(|| anyhow!("Diff is missing its to_release"))?;
+
+            let mut delta = 0i64;
+
+            for file in string_array(diff, "update_files") {
+                let upserted = self.apply_file(dataset, &file, FileKind::Update).await?;
+                report.upserted += upserted;
+                report.files_processed += 1;
+                delta += upserted as i64;
+            }
+
+            for file in string_array(diff, "delete_files") {
+                let deleted = self.apply_file(dataset, &file, FileKind::Delete).await?;
+                report.deleted += deleted;
+                report.files_processed += 1;
+                delta -= deleted as i64;
+            }
+
+            self.record_state(dataset, to_release, delta)?;
+            tracing::info!("Applied {} diff up to release {}", dataset, to_release);
+        }
+
+        Ok(report)
+    }
+
+    /// Downloads a gzipped JSONL shard and applies it, returning the number of
+    /// records that were newly inserted (updates) or removed (deletes).
+    async fn apply_file(&self, dataset: &str, url: &str, kind: FileKind) -> Result<u64> {
+        let bytes = self.download(url).await?;
+        let reader = BufReader::new(GzDecoder::new(bytes.as_slice()));
+        let key_field = primary_key(dataset);
+
+        let mut wtxn = self.env.write_txn()?;
+        let mut changed = 0;
+
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let record: Value = serde_json::from_str(&line)?;
+            let Some(key) = record.get(key_field).map(key_to_string) else {
+                tracing::warn!("Skipping {} record without {}", dataset, key_field);
+                continue;
+            };
+            let key = record_key(dataset, &key);
+
+            match kind {
+                FileKind::Update => {
+                    if self.records.get(&wtxn, &key)?.is_none() {
+                        changed += 1;
+                    }
+                    self.records.put(&mut wtxn, &key, &record)?;
+                }
+                FileKind::Delete => {
+                    if self.records.delete(&mut wtxn, &key)? {
+                        changed += 1;
+                    }
+                }
+            }
+        }
+
+        wtxn.commit()?;
+
+        Ok(changed)
+    }
+
+    fn dataset_state(&self, dataset: &str) -> Result<Option<SyncState>> {
+        let rtxn = self.env.read_txn()?;
+        Ok(self.state.get(&rtxn, dataset)?)
+    }
+
+    fn record_state(&self, dataset: &str, release_id: &str, delta: i64) -> Result<()> {
+        let records = self
+            .dataset_state(dataset)?
+            .map(|state| state.records)
+            .unwrap_or(0);
+
+        let state = SyncState {
+            dataset: dataset.into(),
+            release_id: release_id.into(),
+            records: records.saturating_add_signed(delta),
+            updated_at: chrono::Utc::now().naive_utc(),
+        };
+
+        let mut wtxn = self.env.write_txn()?;
+        self.state.put(&mut wtxn, dataset, &state)?;
+        wtxn.commit()?;
+
+        Ok(())
+    }
+
+    async fn resolve_release(&self, release_id: &str) -> Result<String> {
+        if release_id != "latest" {
+            return Ok(release_id.into());
+        }
+
+        self.get_json("/release/latest")
+            .await?
+            .get("release_id")
+            .and_then(|r| r.as_str())
+            .map(String::from)
+            .ok_or_else(|| anyhow!("Unable to resolve the latest release"))
+    }
+
+    async fn get_json(&self, endpoint: &str) -> Result<Value> {
+        let api_key = std::env::var("SEMANTIC_SCHOLAR_API_KEY")
+            .map_err(|_| anyhow!("Dataset sync requires SEMANTIC_SCHOLAR_API_KEY to be set"))?;
+
+        let request = Request::builder()
+            .method("GET")
+            .uri(format!("{}{}", self.base_url, endpoint))
+            .header("x-api-key", api_key)
+            .header("Accept", "application/json")
+            .end()?;
+
+        let response = self.http_client.send(request).await?;
+        let status = response.status();
+        if !status.is_success() {
+            let error_body = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(anyhow!("HTTP error {}: {}", status, error_body));
+        }
+
+        response
+            .json()
+            .await
+            .map_err(|e| anyhow!("Failed to parse JSON response: {}", e))
+    }
+
+    async fn download(&self, url: &str) -> Result<Vec<u8>> {
+        let request = Request::builder().method("GET").uri(url).end()?;
+        let mut response = self.http_client.send(request).await?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!(
+                "Failed to download {}: HTTP {}",
+                redact_url(url),
+                response.status()
+            ));
+        }
+
+        let mut bytes = Vec::new();
+        response.body_mut().read_to_end(&mut bytes).await?;
+        Ok(bytes)
+    }
+}
+
+#[derive(Clone, Copy)]
+enum FileKind {
+    Update,
+    Delete,
+}
+
+fn record_key(dataset: &str, key: &str) -> String {
+    format!("{}/{}", dataset, key)
+}
+
+fn primary_key(dataset: &str) -> &'static str {
+    match dataset {
+        "authors" => "authorid",
+        "citations" => "citationid",
+        "publication-venues" => "id",
+        _ => "corpusid",
+    }
+}
+
+fn key_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn string_array(value: &Value, field: &str) -> Vec<String> {
+    value
+        .get(field)
+        .and_then(|f| f.as_array())
+        .map(|files| {
+            files
+                .iter()
+                .filter_map(|f| f.as_str().map(String::from))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Strips the query string from pre-signed URLs so credentials never reach the logs.
+fn redact_url(url: &str) -> &str {
+    url.split('?').next().unwrap_or(url)
+}
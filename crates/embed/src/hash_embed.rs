@@ -0,0 +1,83 @@
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::Embed;
+
+const DEFAULT_DIMENSIONS: usize = 256;
+
+/// Embeds texts by hashing their words and character trigrams into a fixed
+/// number of buckets, needing no model at all. Texts sharing most of their
+/// words get similar vectors, so the query cache still matches repeated and
+/// near-identical queries, though not paraphrases.
+pub struct HashEmbed {
+    dimensions: usize,
+}
+
+impl HashEmbed {
+    pub fn new() -> Self {
+        Self::with_dimensions(DEFAULT_DIMENSIONS)
+    }
+
+    /// More dimensions make unrelated texts collide less, 256 by default.
+    pub fn with_dimensions(dimensions: usize) -> Self {
+        Self {
+            dimensions: dimensions.max(1),
+        }
+    }
+
+    fn add(&self, vector: &mut [f32], feature: &str) {
+        let hash = fnv1a(feature.as_bytes());
+        let bucket = (hash % self.dimensions as u64) as usize;
+        // A sign bit from the other end of the hash keeps collisions from
+        // only ever adding up
+        let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+        vector[bucket] += sign;
+    }
+}
+
+impl Default for HashEmbed {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// FNV-1a, whose output never changes between releases, unlike that of the
+/// standard library's hasher. Vectors are cached, so they must stay
+/// reproducible.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[async_trait]
+impl Embed for HashEmbed {
+    async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        let text = text.to_lowercase();
+        let mut vector = vec![0.0; self.dimensions];
+        for word in text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+        {
+            self.add(&mut vector, word);
+            let chars: Vec<char> = format!(" {} ", word).chars().collect();
+            for trigram in chars.windows(3) {
+                self.add(&mut vector, &trigram.iter().collect::<String>());
+            }
+        }
+
+        let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            vector.iter_mut().for_each(|x| *x /= norm);
+        }
+        Ok(vector)
+    }
+
+    fn model_id(&self) -> String {
+        format!("hash/{}", self.dimensions)
+    }
+
+    fn dimensions(&self) -> Option<usize> {
+        Some(self.dimensions)
+    }
+}
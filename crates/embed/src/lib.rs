@@ -1,7 +1,99 @@
-use anyhow::Result;
+mod hash_embed;
+mod timeout_embed;
+
+use std::sync::Arc;
+
+use anyhow::{Result, anyhow};
 use async_trait::async_trait;
 
+pub use crate::{hash_embed::HashEmbed, timeout_embed::TimeoutEmbed};
+
+/// A vector and the model that produced it. Only vectors of the same model
+/// can be compared.
+#[derive(Debug, Clone)]
+pub struct Embedding {
+    pub vector: Vec<f32>,
+    pub model_id: String,
+}
+
 #[async_trait]
 pub trait Embed: Send + Sync {
     async fn embed(&self, text: &str) -> Result<Vec<f32>>;
+
+    /// Names the provider and model, such as `ollama/nomic-embed-text:latest`.
+    /// Cached embeddings are only compared with those of the same model.
+    fn model_id(&self) -> String;
+
+    /// Length of the vectors the model returns, when known before
+    /// embedding anything.
+    fn dimensions(&self) -> Option<usize>;
+
+    /// Embeds `text`, naming the model that did. Implementations that may
+    /// answer with one of several models, such as [`FallbackEmbed`],
+    /// override it.
+    async fn embed_with_model(&self, text: &str) -> Result<Embedding> {
+        Ok(Embedding {
+            vector: self.embed(text).await?,
+            model_id: self.model_id(),
+        })
+    }
+}
+
+/// Tries providers in order, answering with the first that embeds a text, so
+/// an outage of one degrades to the next rather than failing the call.
+/// Vectors of the wrong dimensions count as failures.
+pub struct FallbackEmbed {
+    providers: Vec<Arc<dyn Embed>>,
+}
+
+impl FallbackEmbed {
+    /// The first provider is the main one, whose model and dimensions the
+    /// chain reports.
+    pub fn new(providers: Vec<Arc<dyn Embed>>) -> Result<Self> {
+        if providers.is_empty() {
+            return Err(anyhow!("A fallback chain needs at least one provider"));
+        }
+        Ok(Self { providers })
+    }
+}
+
+#[async_trait]
+impl Embed for FallbackEmbed {
+    async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        Ok(self.embed_with_model(text).await?.vector)
+    }
+
+    fn model_id(&self) -> String {
+        self.providers[0].model_id()
+    }
+
+    fn dimensions(&self) -> Option<usize> {
+        self.providers[0].dimensions()
+    }
+
+    async fn embed_with_model(&self, text: &str) -> Result<Embedding> {
+        let mut last_err = None;
+        for provider in &self.providers {
+            let err = match provider.embed_with_model(text).await {
+                Ok(embedding) => match provider.dimensions() {
+                    Some(dimensions) if embedding.vector.len() != dimensions => anyhow!(
+                        "{} returned a {}-dimensional embedding, {} expected",
+                        embedding.model_id,
+                        embedding.vector.len(),
+                        dimensions
+                    ),
+                    _ => return Ok(embedding),
+                },
+                Err(err) => err,
+            };
+            tracing::warn!(
+                "{} failed to embed text, trying the next provider: {}",
+                provider.model_id(),
+                err
+            );
+            last_err = Some(err);
+        }
+
+        Err(last_err.unwrap_or_else(|| anyhow!("No embedding provider is configured")))
+    }
 }
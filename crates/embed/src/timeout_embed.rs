@@ -0,0 +1,74 @@
+use std::{sync::Arc, time::Duration};
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+
+use crate::{Embed, Embedding};
+
+/// How long to wait before the first retry, doubled before each next one.
+const RETRY_DELAY: Duration = Duration::from_millis(250);
+
+/// Bounds how long a provider may take to embed a text, retrying a failed
+/// or timed out attempt a few times. A dead provider then delays a call by
+/// at most `(retries + 1) * timeout` plus the delays between attempts.
+pub struct TimeoutEmbed {
+    inner: Arc<dyn Embed>,
+    timeout: Duration,
+    retries: u32,
+}
+
+impl TimeoutEmbed {
+    pub fn new(inner: Arc<dyn Embed>, timeout: Duration, retries: u32) -> Self {
+        Self {
+            inner,
+            timeout,
+            retries,
+        }
+    }
+}
+
+#[async_trait]
+impl Embed for TimeoutEmbed {
+    async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        Ok(self.embed_with_model(text).await?.vector)
+    }
+
+    fn model_id(&self) -> String {
+        self.inner.model_id()
+    }
+
+    fn dimensions(&self) -> Option<usize> {
+        self.inner.dimensions()
+    }
+
+    async fn embed_with_model(&self, text: &str) -> Result<Embedding> {
+        let mut delay = RETRY_DELAY;
+        let mut attempt = 0;
+        loop {
+            let err =
+                match tokio::time::timeout(self.timeout, self.inner.embed_with_model(text)).await {
+                    Ok(Ok(embedding)) => return Ok(embedding),
+                    Ok(Err(err)) => err,
+                    Err(_) => anyhow!(
+                        "{} did not answer within {:?}",
+                        self.inner.model_id(),
+                        self.timeout
+                    ),
+                };
+            if attempt == self.retries {
+                return Err(err);
+            }
+
+            attempt += 1;
+            tracing::debug!(
+                "Retrying embedding ({}/{}) in {:?}: {}",
+                attempt,
+                self.retries,
+                delay,
+                err
+            );
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use std::{path::PathBuf, sync::Arc};
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use embed::Embed;
+use fastembed::{EmbeddingModel, InitOptions, TextEmbedding};
+
+const DEFAULT_MODEL: EmbeddingModel = EmbeddingModel::BGESmallENV15;
+
+/// Embeds texts in process with a small ONNX model, which is downloaded
+/// once and then loaded from the cache directory, needing no network
+/// access or external server.
+pub struct FastEmbed {
+    model: Arc<TextEmbedding>,
+    name: String,
+    dimensions: usize,
+}
+
+pub struct FastEmbedBuilder {
+    model: Option<String>,
+    cache_dir: Option<PathBuf>,
+}
+
+impl FastEmbed {
+    pub fn builder() -> FastEmbedBuilder {
+        FastEmbedBuilder {
+            model: None,
+            cache_dir: None,
+        }
+    }
+
+    /// The model texts are embedded with, as named on Hugging Face.
+    pub fn model(&self) -> &str {
+        &self.name
+    }
+}
+
+impl FastEmbedBuilder {
+    /// The embedding model to use, `Xenova/bge-small-en-v1.5` by default.
+    pub fn with_model(&mut self, model: impl Into<String>) -> &mut Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    /// Where model files are downloaded to and loaded from.
+    pub fn with_cache_dir(&mut self, cache_dir: impl Into<PathBuf>) -> &mut Self {
+        self.cache_dir = Some(cache_dir.into());
+        self
+    }
+
+    /// Loads the model, downloading it first if it isn't in the cache
+    /// directory. This blocks, so async callers should run it with
+    /// `spawn_blocking`.
+    pub fn build(&self) -> Result<FastEmbed> {
+        let supported = TextEmbedding::list_supported_models();
+        let info = match &self.model {
+            Some(model) => supported
+                .into_iter()
+                .find(|info| info.model_code == *model)
+                .ok_or_else(|| anyhow!("fastembed does not support the model {}", model))?,
+            None => supported
+                .into_iter()
+                .find(|info| info.model == DEFAULT_MODEL)
+                .ok_or_else(|| anyhow!("fastembed does not ship its default model"))?,
+        };
+
+        let mut options = InitOptions::new(info.model.clone()).with_show_download_progress(false);
+        if let Some(cache_dir) = &self.cache_dir {
+            options = options.with_cache_dir(cache_dir.clone());
+        }
+        tracing::debug!("Loading {} for fastembed", info.model_code);
+        let model = TextEmbedding::try_new(options)?;
+
+        Ok(FastEmbed {
+            model: Arc::new(model),
+            name: info.model_code,
+            dimensions: info.dim,
+        })
+    }
+}
+
+#[async_trait]
+impl Embed for FastEmbed {
+    async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        tracing::debug!("Embedding {} characters with {}", text.len(), self.name);
+        let model = self.model.clone();
+        let text = text.to_string();
+        // Inference runs on the CPU, so it stays off the async workers
+        tokio::task::spawn_blocking(move || model.embed(vec![text], None))
+            .await?
+            .and_then(|mut embeddings| {
+                embeddings
+                    .pop()
+                    .ok_or_else(|| anyhow!("fastembed returned no embedding"))
+            })
+            .inspect_err(|err| tracing::warn!("fastembed failed to embed text: {}", err))
+    }
+
+    fn model_id(&self) -> String {
+        format!("fastembed/{}", self.name)
+    }
+
+    fn dimensions(&self) -> Option<usize> {
+        Some(self.dimensions)
+    }
+}
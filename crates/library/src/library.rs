@@ -0,0 +1,106 @@
+use std::{fs, path::Path};
+
+use anyhow::Result;
+use chrono::NaiveDateTime;
+use heed::{
+    Database, Env, EnvOpenOptions,
+    types::{SerdeJson, Str},
+};
+use serde::{Deserialize, Serialize};
+
+/// How an entry was linked to its Semantic Scholar paper.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchMethod {
+    Doi,
+    Arxiv,
+    Title,
+}
+
+/// A paper in the user's bibliography, keyed by its citation key.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LibraryEntry {
+    pub key: String,
+    /// The Semantic Scholar paper ID, or `None` while the entry is unresolved.
+    pub paper_id: Option<String>,
+    pub title: Option<String>,
+    pub doi: Option<String>,
+    /// The BibTeX entry type, such as `article` or `inproceedings`.
+    pub entry_type: Option<String>,
+    pub matched_by: Option<MatchMethod>,
+    /// Semantic Scholar's confidence in a title match.
+    pub match_score: Option<f64>,
+    /// Labels the user filed the entry under.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub added_at: NaiveDateTime,
+}
+
+/// The user's bibliography, stored next to the cache so tools can look up
+/// papers by the citation keys the user already uses.
+pub struct Library {
+    env: Env,
+    entries: Database<Str, SerdeJson<LibraryEntry>>,
+}
+
+impl Library {
+    pub fn new<P: AsRef<Path>>(path: P) -> Result<Self> {
+        fs::create_dir_all(path.as_ref())?;
+
+        let env = unsafe {
+            EnvOpenOptions::new()
+                .map_size(64 * 1024 * 1024)
+                .max_dbs(4)
+                .open(path.as_ref())?
+        };
+
+        let mut wtxn = env.write_txn()?;
+        let entries = env.create_database(&mut wtxn, Some("entries"))?;
+        wtxn.commit()?;
+
+        Ok(Self { env, entries })
+    }
+
+    /// Adds entries, replacing any existing entry with the same key.
+    pub fn put(&self, entries: &[LibraryEntry]) -> Result<()> {
+        let mut wtxn = self.env.write_txn()?;
+        for entry in entries {
+            self.entries.put(&mut wtxn, &entry.key, entry)?;
+        }
+        wtxn.commit()?;
+        Ok(())
+    }
+
+    /// Looks up an entry by its citation key.
+    pub fn get(&self, key: &str) -> Result<Option<LibraryEntry>> {
+        let rtxn = self.env.read_txn()?;
+        Ok(self.entries.get(&rtxn, key)?)
+    }
+
+    /// Looks up the entry for a Semantic Scholar paper, if it was saved.
+    pub fn find_paper(&self, paper_id: &str) -> Result<Option<LibraryEntry>> {
+        Ok(self
+            .list()?
+            .into_iter()
+            .find(|entry| entry.paper_id.as_deref() == Some(paper_id)))
+    }
+
+    /// Returns every entry, ordered by citation key.
+    pub fn list(&self) -> Result<Vec<LibraryEntry>> {
+        let rtxn = self.env.read_txn()?;
+        let mut entries = Vec::new();
+        for item in self.entries.iter(&rtxn)? {
+            let (_, entry) = item?;
+            entries.push(entry);
+        }
+        Ok(entries)
+    }
+
+    /// Removes an entry, returning whether it existed.
+    pub fn remove(&self, key: &str) -> Result<bool> {
+        let mut wtxn = self.env.write_txn()?;
+        let removed = self.entries.delete(&mut wtxn, key)?;
+        wtxn.commit()?;
+        Ok(removed)
+    }
+}
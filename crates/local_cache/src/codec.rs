@@ -0,0 +1,36 @@
+use std::{borrow::Cow, marker::PhantomData};
+
+use heed::{BoxedError, BytesDecode, BytesEncode};
+use serde::{Serialize, de::DeserializeOwned};
+
+/// Compression level, favouring speed since every tool call writes entries.
+const LEVEL: i32 = 3;
+
+/// Every zstd frame starts with these bytes, which no JSON document does.
+const MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// Stores values as zstd-compressed JSON. Entries written before compression
+/// are plain JSON, and are still read as such.
+pub struct ZstdJson<T>(PhantomData<T>);
+
+impl<'a, T: Serialize + 'a> BytesEncode<'a> for ZstdJson<T> {
+    type EItem = T;
+
+    fn bytes_encode(item: &'a T) -> Result<Cow<'a, [u8]>, BoxedError> {
+        let json = serde_json::to_vec(item)?;
+        Ok(Cow::Owned(zstd::bulk::compress(&json, LEVEL)?))
+    }
+}
+
+impl<'a, T: DeserializeOwned + 'a> BytesDecode<'a> for ZstdJson<T> {
+    type DItem = T;
+
+    fn bytes_decode(bytes: &'a [u8]) -> Result<T, BoxedError> {
+        if bytes.starts_with(&MAGIC) {
+            let json = zstd::stream::decode_all(bytes)?;
+            Ok(serde_json::from_slice(&json)?)
+        } else {
+            Ok(serde_json::from_slice(bytes)?)
+        }
+    }
+}
@@ -1,17 +1,220 @@
-use std::{fs, path::Path, time::Duration};
+mod codec;
 
-use anyhow::Result;
-use cache::{Cache, CacheEntry, Query};
+use std::{
+    collections::HashMap,
+    fs,
+    hash::{DefaultHasher, Hash, Hasher},
+    path::Path,
+    sync::{Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard},
+    time::Duration,
+};
+
+use anyhow::{Result, anyhow};
+use cache::{
+    Cache, CacheEntry, CachedResponse, ClearFilter, ClearedEntries, DEFAULT_TTL, Query,
+    RESPONSE_RETENTION, merge_record, query_key, similarity, unit_vector,
+};
+use chrono::NaiveDateTime;
 use heed::{
-    Database, Env, EnvOpenOptions,
-    types::{SerdeJson, Str},
+    CompactionOption, Database, Env, EnvFlags, EnvOpenOptions, MdbError, RoTxn, RwTxn,
+    types::{Bytes, SerdeJson, Str},
 };
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use uuid::Uuid;
 
+use crate::codec::ZstdJson;
+
 pub struct LocalCache {
     env: Env,
-    storage: Database<Str, SerdeJson<CacheEntry<Query>>>,
+    storage: Database<Str, ZstdJson<CacheEntry<Query>>>,
+    responses: Database<Str, ZstdJson<CacheEntry<CachedResponse>>>,
+    papers: Database<Str, ZstdJson<CacheEntry<Value>>>,
+    authors: Database<Str, ZstdJson<CacheEntry<Value>>>,
+    /// IDs of each author's papers, in the order they were first seen.
+    author_papers: Database<Str, ZstdJson<Vec<String>>>,
+    /// Embeddings of texts, keyed by a hash of the text and the model.
+    embeddings: Database<Str, ZstdJson<Vec<f32>>>,
+    /// When each entry was last used and its size, keyed by [`usage_key`].
+    usage: Database<Str, SerdeJson<Usage>>,
+    /// Running totals, under [`TOTAL_BYTES`].
+    meta: Database<Str, SerdeJson<u64>>,
     ttl: Duration,
+    /// Whether entries past `ttl` or [`RESPONSE_RETENTION`] are kept
+    keep_expired: bool,
+    /// Whether the database was opened with [`LocalCache::open_read_only`]
+    read_only: bool,
+    limits: CacheLimits,
+    /// Shared by every transaction and taken exclusively to resize the map,
+    /// which LMDB only allows while no transaction of the process is open.
+    resize_lock: RwLock<()>,
+    /// When entries read since the last write were used, by [`usage_key`].
+    /// Recording each read would take a write transaction per cache hit, so
+    /// they are recorded together with the next write.
+    pending_uses: Mutex<HashMap<String, i64>>,
+}
+
+/// Bounds on what the cache holds. Past either, the least recently used
+/// entries are evicted until the cache is back under 90% of it.
+#[derive(Debug, Clone, Copy)]
+pub struct CacheLimits {
+    pub max_entries: Option<u64>,
+    /// Compressed size of the entries, which the database needs somewhat
+    /// more than to hold
+    pub max_bytes: Option<u64>,
+}
+
+impl CacheLimits {
+    /// No limit on the number of entries, and half of `map_size` for their
+    /// bytes: LMDB needs room beyond the entries themselves for its B-trees
+    /// and for pages freed by transactions still being read.
+    pub fn for_map_size(map_size: usize) -> Self {
+        Self {
+            max_entries: None,
+            max_bytes: Some(map_size as u64 / 2),
+        }
+    }
+}
+
+impl Default for CacheLimits {
+    fn default() -> Self {
+        Self::for_map_size(DEFAULT_MAP_SIZE)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Usage {
+    /// Milliseconds since the Unix epoch
+    last_used: i64,
+    bytes: u64,
+}
+
+/// The databases whose entries count towards the limits.
+#[derive(Clone, Copy)]
+enum Table {
+    Queries,
+    Responses,
+    Papers,
+    Authors,
+    AuthorPapers,
+    Embeddings,
+}
+
+impl Table {
+    const ALL: [Table; 6] = [
+        Self::Queries,
+        Self::Responses,
+        Self::Papers,
+        Self::Authors,
+        Self::AuthorPapers,
+        Self::Embeddings,
+    ];
+
+    fn prefix(&self) -> &'static str {
+        match self {
+            Self::Queries => "queries",
+            Self::Responses => "responses",
+            Self::Papers => "papers",
+            Self::Authors => "authors",
+            Self::AuthorPapers => "author_papers",
+            Self::Embeddings => "embeddings",
+        }
+    }
+
+    fn from_prefix(prefix: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|table| table.prefix() == prefix)
+    }
+}
+
+/// Size of the memory map the database is opened with, which it grows past
+/// when full.
+pub const DEFAULT_MAP_SIZE: usize = 10 * 1024 * 1024;
+
+/// Reads whose use is recorded with a write of its own once this many are
+/// waiting for one, so a cache that is only read still tracks them.
+const MAX_PENDING_USES: usize = 1024;
+
+const TOTAL_BYTES: &str = "total_bytes";
+
+fn usage_key(table: Table, key: &str) -> String {
+    format!("{}/{}", table.prefix(), key)
+}
+
+fn now_millis() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+/// How much of its memory map the cache uses.
+pub struct StorageUsage {
+    /// Bytes used by records, excluding free pages
+    pub used_bytes: u64,
+    /// The most the cache holds before its map is grown
+    pub map_size: u64,
+}
+
+/// What [`LocalCache::compact`] did.
+pub struct CompactReport {
+    /// Expired entries deleted before rewriting the database
+    pub purged: usize,
+    /// Size of the database file before and after
+    pub before_bytes: u64,
+    pub after_bytes: u64,
+}
+
+fn is_map_full(err: &anyhow::Error) -> bool {
+    matches!(
+        err.downcast_ref::<heed::Error>(),
+        Some(heed::Error::Mdb(MdbError::MapFull))
+    )
+}
+
+/// Another process grew the map past the size this one has mapped, which it
+/// must adopt before beginning another transaction.
+fn is_map_resized(err: &heed::Error) -> bool {
+    matches!(err, heed::Error::Mdb(MdbError::MapResized))
+}
+
+/// The tables of a cache database, opened or created together.
+type Tables = (
+    Database<Str, ZstdJson<CacheEntry<Query>>>,
+    Database<Str, ZstdJson<CacheEntry<CachedResponse>>>,
+    Database<Str, ZstdJson<CacheEntry<Value>>>,
+    Database<Str, ZstdJson<CacheEntry<Value>>>,
+    Database<Str, ZstdJson<Vec<String>>>,
+    Database<Str, ZstdJson<Vec<f32>>>,
+    Database<Str, SerdeJson<Usage>>,
+    Database<Str, SerdeJson<u64>>,
+);
+
+/// Opens a table of a database opened read-only, which can't create it.
+fn open_table<KC: 'static, DC: 'static>(
+    env: &Env,
+    rtxn: &RoTxn,
+    name: &str,
+) -> Result<Database<KC, DC>> {
+    env.open_database(rtxn, Some(name))?.ok_or_else(|| {
+        anyhow!(
+            "The cache database has no {} table; open it once writable to upgrade it",
+            name
+        )
+    })
+}
+
+/// Request keys can exceed the LMDB key size limit, so they are stored hashed.
+fn response_key(key: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// Queries are stored under their hashed [`query_key`], so a repeated query
+/// replaces the previous result and exact lookups are a single read. Those
+/// without parameters, cached by earlier versions, keep a random key.
+fn storage_key(query: &Query) -> String {
+    match &query.params {
+        Some(params) => response_key(&query_key(&query.action, params)),
+        None => Uuid::new_v4().to_string(),
+    }
 }
 
 impl LocalCache {
@@ -20,87 +223,868 @@ impl LocalCache {
 
         let env = unsafe {
             EnvOpenOptions::new()
-                .map_size(10 * 1024 * 1024)
+                .map_size(DEFAULT_MAP_SIZE)
                 .max_dbs(40)
                 .open(path.as_ref())?
         };
 
         let mut wtxn = env.write_txn()?;
-        let storage = env.create_database(&mut wtxn, Some("cache"))?;
+        let tables = (
+            env.create_database(&mut wtxn, Some("cache"))?,
+            env.create_database(&mut wtxn, Some("responses"))?,
+            env.create_database(&mut wtxn, Some("papers"))?,
+            env.create_database(&mut wtxn, Some("authors"))?,
+            env.create_database(&mut wtxn, Some("author_papers"))?,
+            env.create_database(&mut wtxn, Some("embeddings"))?,
+            env.create_database(&mut wtxn, Some("usage"))?,
+            env.create_database(&mut wtxn, Some("meta"))?,
+        );
         wtxn.commit()?;
 
-        Ok(LocalCache {
+        // Readers left behind by a server that was killed would otherwise keep
+        // the pages they saw from being reused
+        let stale_readers = env.clear_stale_readers()?;
+        if stale_readers > 0 {
+            tracing::debug!("Cleared {} stale cache readers", stale_readers);
+        }
+
+        tracing::debug!("Opened cache database at {}", path.as_ref().display());
+
+        let cache = Self::with_tables(env, tables, ttl, false);
+        cache.track_existing_entries()?;
+        Ok(cache)
+    }
+
+    /// Opens an existing database without ever writing to it, such as a
+    /// snapshot a team shares or one on read-only storage. Reads aren't
+    /// recorded for eviction and expired entries are skipped rather than
+    /// deleted; writes fail.
+    pub fn open_read_only<P: AsRef<Path>>(path: P, ttl: Option<Duration>) -> Result<Self> {
+        let env = unsafe {
+            EnvOpenOptions::new()
+                .map_size(DEFAULT_MAP_SIZE)
+                .max_dbs(40)
+                .flags(EnvFlags::READ_ONLY)
+                .open(path.as_ref())?
+        };
+
+        let rtxn = env.read_txn()?;
+        let tables = (
+            open_table(&env, &rtxn, "cache")?,
+            open_table(&env, &rtxn, "responses")?,
+            open_table(&env, &rtxn, "papers")?,
+            open_table(&env, &rtxn, "authors")?,
+            open_table(&env, &rtxn, "author_papers")?,
+            open_table(&env, &rtxn, "embeddings")?,
+            open_table(&env, &rtxn, "usage")?,
+            open_table(&env, &rtxn, "meta")?,
+        );
+        rtxn.commit()?;
+
+        tracing::debug!(
+            "Opened cache database at {} read-only",
+            path.as_ref().display()
+        );
+
+        Ok(Self::with_tables(env, tables, ttl, true))
+    }
+
+    fn with_tables(
+        env: Env,
+        (storage, responses, papers, authors, author_papers, embeddings, usage, meta): Tables,
+        ttl: Option<Duration>,
+        read_only: bool,
+    ) -> Self {
+        LocalCache {
             env,
             storage,
-            ttl: ttl.unwrap_or(Duration::from_secs(60 * 60 * 24)),
+            responses,
+            papers,
+            authors,
+            author_papers,
+            embeddings,
+            usage,
+            meta,
+            ttl: ttl.unwrap_or(DEFAULT_TTL),
+            keep_expired: false,
+            read_only,
+            limits: CacheLimits::default(),
+            resize_lock: RwLock::new(()),
+            pending_uses: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Resizes the memory map from the default of 10 MiB. The database is
+    /// never shrunk below the data it holds, and one opened read-only keeps
+    /// the size it has on disk.
+    pub fn with_map_size(self, map_size: usize) -> Result<Self> {
+        if self.read_only {
+            return Ok(self);
+        }
+        {
+            let _resizing = self.lock_resize()?;
+            // SAFETY: no transaction is open while the cache is being built
+            unsafe { self.env.resize(map_size)? };
+        }
+        Ok(self)
+    }
+
+    /// Evicts entries past `limits`, in place of the default of half the
+    /// database's size and no limit on the number of entries.
+    pub fn with_limits(mut self, limits: CacheLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Serves entries however old they are and never deletes them for their
+    /// age, for offline use where nothing expired can be fetched again.
+    pub fn keep_expired(mut self) -> Self {
+        self.keep_expired = true;
+        self
+    }
+
+    /// Whether an entry stored at `created_at` is older than `max_age` and
+    /// should be dropped.
+    fn is_expired(&self, created_at: NaiveDateTime, max_age: Duration) -> bool {
+        !self.keep_expired
+            && chrono::Duration::from_std(max_age)
+                .is_ok_and(|max_age| chrono::Utc::now().naive_utc() - created_at > max_age)
+    }
+
+    fn lock_txn(&self) -> Result<RwLockReadGuard<'_, ()>> {
+        self.resize_lock
+            .read()
+            .map_err(|_| anyhow!("Cache resize lock is poisoned"))
+    }
+
+    fn lock_resize(&self) -> Result<RwLockWriteGuard<'_, ()>> {
+        self.resize_lock
+            .write()
+            .map_err(|_| anyhow!("Cache resize lock is poisoned"))
+    }
+
+    /// Runs `f` in a write transaction and commits it. When the map is full
+    /// the transaction is aborted, the map doubled and `f` run again, so a
+    /// cache past its expected size keeps accepting writes.
+    ///
+    /// Other processes may have the database open too, such as servers
+    /// started by several editors. LMDB lets one of them write at a time, so
+    /// a write waits for those of other processes to commit.
+    fn write<T>(&self, mut f: impl FnMut(&mut RwTxn) -> Result<T>) -> Result<T> {
+        if self.read_only {
+            return Err(anyhow!("The cache database is opened read-only"));
+        }
+        loop {
+            // The size the map was found full at, or none when another
+            // process grew it
+            let full_size = {
+                let _txn = self.lock_txn()?;
+                let map_size = self.env.info().map_size;
+                match self.env.write_txn() {
+                    Err(err) if is_map_resized(&err) => None,
+                    write_txn => {
+                        let mut write_txn = write_txn?;
+                        let uses = self.take_pending_uses();
+                        let result = self
+                            .record_uses(&mut write_txn, &uses)
+                            .and_then(|_| f(&mut write_txn))
+                            .and_then(|value| {
+                                write_txn.commit().map(|_| value).map_err(Into::into)
+                            });
+                        match result {
+                            Err(err) if is_map_full(&err) => {
+                                self.restore_pending_uses(uses);
+                                Some(map_size)
+                            }
+                            result => return result,
+                        }
+                    }
+                }
+            };
+            match full_size {
+                Some(full_size) => self.grow(full_size)?,
+                None => self.adopt_map_size()?,
+            }
+        }
+    }
+
+    /// Begins a read transaction, adopting the map size first when another
+    /// process grew the map.
+    fn read_txn(&self) -> Result<(RwLockReadGuard<'_, ()>, RoTxn<'_>)> {
+        loop {
+            let guard = self.lock_txn()?;
+            match self.env.read_txn() {
+                Ok(read_txn) => return Ok((guard, read_txn)),
+                Err(err) if is_map_resized(&err) => {}
+                Err(err) => return Err(err.into()),
+            }
+            drop(guard);
+            self.adopt_map_size()?;
+        }
+    }
+
+    /// Maps the database at the size another process grew it to.
+    fn adopt_map_size(&self) -> Result<()> {
+        let _resizing = self.lock_resize()?;
+        // SAFETY: holding the lock exclusively means no transaction is open.
+        // A size of zero takes up the size the database has on disk
+        unsafe { self.env.resize(0)? };
+        tracing::debug!(
+            "Cache database was grown by another process, mapped it at {} MiB",
+            self.env.info().map_size / (1024 * 1024)
+        );
+        Ok(())
+    }
+
+    /// Doubles a map found full at `full_size`, unless another writer
+    /// already grew it.
+    fn grow(&self, full_size: usize) -> Result<()> {
+        let _resizing = self.lock_resize()?;
+        if self.env.info().map_size > full_size {
+            return Ok(());
+        }
+        let map_size = full_size * 2;
+        // SAFETY: holding the lock exclusively means no transaction is open
+        unsafe { self.env.resize(map_size)? };
+        tracing::info!(
+            "Cache database is full, grew it to {} MiB",
+            map_size / (1024 * 1024)
+        );
+        Ok(())
+    }
+
+    /// Records the size of entries stored before usage was tracked, as if
+    /// they had just been used.
+    fn track_existing_entries(&self) -> Result<()> {
+        self.write(|write_txn| {
+            if !self.usage.is_empty(write_txn)? {
+                return Ok(());
+            }
+
+            let now = now_millis();
+            let mut total = 0;
+            for table in Table::ALL {
+                let database = self.raw(table);
+                let entries = database
+                    .iter(write_txn)?
+                    .map(|item| item.map(|(key, value)| (key.to_string(), value.len() as u64)))
+                    .collect::<Result<Vec<_>, _>>()?;
+                for (key, bytes) in entries {
+                    total += bytes;
+                    self.usage.put(
+                        write_txn,
+                        &usage_key(table, &key),
+                        &Usage {
+                            last_used: now,
+                            bytes,
+                        },
+                    )?;
+                }
+            }
+            self.meta.put(write_txn, TOTAL_BYTES, &total)?;
+            self.enforce_limits(write_txn)
+        })
+    }
+
+    /// A table with its values left encoded, for sizing and deleting
+    /// entries whatever they hold.
+    fn raw(&self, table: Table) -> Database<Str, Bytes> {
+        match table {
+            Table::Queries => self.storage.remap_data_type(),
+            Table::Responses => self.responses.remap_data_type(),
+            Table::Papers => self.papers.remap_data_type(),
+            Table::Authors => self.authors.remap_data_type(),
+            Table::AuthorPapers => self.author_papers.remap_data_type(),
+            Table::Embeddings => self.embeddings.remap_data_type(),
+        }
+    }
+
+    /// Size of an entry as stored, after compression.
+    fn stored_size(&self, write_txn: &RwTxn, table: Table, key: &str) -> Result<u64> {
+        Ok(self
+            .raw(table)
+            .get(write_txn, key)?
+            .map_or(0, |bytes| bytes.len() as u64))
+    }
+
+    /// Marks an entry that was just written as used, recording its new size.
+    fn record_use(&self, write_txn: &mut RwTxn, table: Table, key: &str, bytes: u64) -> Result<()> {
+        let usage_key = usage_key(table, key);
+        let previous_bytes = self
+            .usage
+            .get(write_txn, &usage_key)?
+            .map_or(0, |usage| usage.bytes);
+
+        let total = self.meta.get(write_txn, TOTAL_BYTES)?.unwrap_or_default();
+        self.meta.put(
+            write_txn,
+            TOTAL_BYTES,
+            &(total + bytes).saturating_sub(previous_bytes),
+        )?;
+        self.usage.put(
+            write_txn,
+            &usage_key,
+            &Usage {
+                last_used: now_millis(),
+                bytes,
+            },
+        )?;
+        Ok(())
+    }
+
+    /// Deletes an entry along with its usage.
+    fn remove(&self, write_txn: &mut RwTxn, table: Table, key: &str) -> Result<()> {
+        self.raw(table).delete(write_txn, key)?;
+        let usage_key = usage_key(table, key);
+        if let Some(usage) = self.usage.get(write_txn, &usage_key)? {
+            let total = self.meta.get(write_txn, TOTAL_BYTES)?.unwrap_or_default();
+            self.meta
+                .put(write_txn, TOTAL_BYTES, &total.saturating_sub(usage.bytes))?;
+            self.usage.delete(write_txn, &usage_key)?;
+        }
+        Ok(())
+    }
+
+    /// Marks an entry that was read as just used. The use is recorded with
+    /// the next write, or with one of its own once enough reads are waiting.
+    fn touch(&self, table: Table, key: &str) -> Result<()> {
+        if self.read_only {
+            return Ok(());
+        }
+        let pending = {
+            let mut pending_uses = self.lock_pending_uses()?;
+            pending_uses.insert(usage_key(table, key), now_millis());
+            pending_uses.len()
+        };
+        if pending >= MAX_PENDING_USES {
+            self.write(|_| Ok(()))?;
+        }
+        Ok(())
+    }
+
+    fn lock_pending_uses(&self) -> Result<MutexGuard<'_, HashMap<String, i64>>> {
+        self.pending_uses
+            .lock()
+            .map_err(|_| anyhow!("Cache usage buffer is poisoned"))
+    }
+
+    fn take_pending_uses(&self) -> HashMap<String, i64> {
+        self.lock_pending_uses()
+            .map(|mut pending_uses| std::mem::take(&mut *pending_uses))
+            .unwrap_or_default()
+    }
+
+    /// Puts back uses a write failed to record, keeping any newer ones.
+    fn restore_pending_uses(&self, uses: HashMap<String, i64>) {
+        if let Ok(mut pending_uses) = self.lock_pending_uses() {
+            for (usage_key, last_used) in uses {
+                pending_uses.entry(usage_key).or_insert(last_used);
+            }
+        }
+    }
+
+    /// Records when entries read since the last write were used. Entries
+    /// deleted since are skipped.
+    fn record_uses(&self, write_txn: &mut RwTxn, uses: &HashMap<String, i64>) -> Result<()> {
+        for (usage_key, &last_used) in uses {
+            if let Some(usage) = self.usage.get(write_txn, usage_key)? {
+                self.usage.put(
+                    write_txn,
+                    usage_key,
+                    &Usage {
+                        last_used: last_used.max(usage.last_used),
+                        bytes: usage.bytes,
+                    },
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Evicts the least recently used entries once the cache is past one of
+    /// its limits, until it is back under 90% of them.
+    fn enforce_limits(&self, write_txn: &mut RwTxn) -> Result<()> {
+        let mut entries = self.usage.len(write_txn)?;
+        let mut bytes = self.meta.get(write_txn, TOTAL_BYTES)?.unwrap_or_default();
+        let over = |entries: u64, bytes: u64, share: f64| {
+            self.limits
+                .max_entries
+                .is_some_and(|max| entries as f64 > max as f64 * share)
+                || self
+                    .limits
+                    .max_bytes
+                    .is_some_and(|max| bytes as f64 > max as f64 * share)
+        };
+        if !over(entries, bytes, 1.0) {
+            return Ok(());
+        }
+
+        let mut usages = self
+            .usage
+            .iter(write_txn)?
+            .map(|item| item.map(|(key, usage)| (key.to_string(), usage)))
+            .collect::<Result<Vec<_>, _>>()?;
+        usages.sort_by_key(|(_, usage)| usage.last_used);
+
+        let mut evicted = 0;
+        for (usage_key, usage) in usages {
+            if !over(entries, bytes, 0.9) {
+                break;
+            }
+            let Some((table, key)) = usage_key
+                .split_once('/')
+                .and_then(|(prefix, key)| Some((Table::from_prefix(prefix)?, key)))
+            else {
+                continue;
+            };
+            self.remove(write_txn, table, key)?;
+            entries -= 1;
+            bytes = bytes.saturating_sub(usage.bytes);
+            evicted += 1;
+        }
+
+        tracing::debug!("Evicted {} least recently used cache entries", evicted);
+        Ok(())
+    }
+
+    pub fn usage(&self) -> Result<StorageUsage> {
+        let _txn = self.lock_txn()?;
+        Ok(StorageUsage {
+            used_bytes: self.env.non_free_pages_size()?,
+            map_size: self.env.info().map_size as u64,
+        })
+    }
+
+    /// Deletes expired entries and rewrites the database without the pages
+    /// they and evicted entries left free. LMDB keeps freed pages in its file
+    /// for reuse, so the file never shrinks otherwise. No other process may
+    /// have the database open, since its file is replaced.
+    pub fn compact(self) -> Result<CompactReport> {
+        if self.read_only {
+            return Err(anyhow!("The cache database is opened read-only"));
+        }
+        let purged = self.purge_expired()?;
+
+        let data_path = self.env.path().join("data.mdb");
+        let compacted_path = self.env.path().join("data.mdb.compacted");
+        let before_bytes = fs::metadata(&data_path)?.len();
+        if compacted_path.exists() {
+            fs::remove_file(&compacted_path)?;
+        }
+        {
+            let _txn = self.lock_txn()?;
+            self.env
+                .copy_to_path(&compacted_path, CompactionOption::Enabled)?;
+        }
+
+        // The environment must be closed before its file is swapped
+        self.env.prepare_for_closing().wait();
+        fs::rename(&compacted_path, &data_path)?;
+        let after_bytes = fs::metadata(&data_path)?.len();
+
+        Ok(CompactReport {
+            purged,
+            before_bytes,
+            after_bytes,
+        })
+    }
+
+    /// Stores records keyed by their `id_field`, merging their fields into
+    /// any record already stored under the same ID.
+    fn merge_records(
+        &self,
+        table: Table,
+        database: Database<Str, ZstdJson<CacheEntry<Value>>>,
+        id_field: &str,
+        records: &[Value],
+    ) -> Result<()> {
+        let now = chrono::Utc::now().naive_utc();
+
+        self.write(|write_txn| {
+            for record in records {
+                let (Some(id), Some(fields)) = (
+                    record.get(id_field).and_then(Value::as_str),
+                    record.as_object(),
+                ) else {
+                    continue;
+                };
+
+                let existing = database.get(write_txn, id)?.map(|entry| entry.value);
+                let entry = CacheEntry {
+                    created_at: now,
+                    value: merge_record(existing, fields),
+                };
+                database.put(write_txn, id, &entry)?;
+                let bytes = self.stored_size(write_txn, table, id)?;
+                self.record_use(write_txn, table, id, bytes)?;
+            }
+
+            self.enforce_limits(write_txn)
         })
     }
+
+    /// The most recently stored records, newest first.
+    fn recent_records(
+        &self,
+        database: Database<Str, ZstdJson<CacheEntry<Value>>>,
+        limit: usize,
+    ) -> Result<Vec<CacheEntry<Value>>> {
+        let (_txn, read_txn) = self.read_txn()?;
+        let mut records = Vec::new();
+        for item in database.iter(&read_txn)? {
+            let (_, entry) = item?;
+            records.push(entry);
+        }
+
+        records.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        records.truncate(limit);
+        Ok(records)
+    }
 }
 
 impl Cache for LocalCache {
-    fn store(&self, query: Query) -> Result<()> {
-        let mut write_txn = self.env.write_txn()?;
-        let key = Uuid::new_v4().to_string();
+    fn store(&self, mut query: Query) -> Result<()> {
+        query.normalize();
+        let key = storage_key(&query);
         let entry = CacheEntry {
             created_at: chrono::Utc::now().naive_utc(),
             value: query,
         };
-        self.storage.put(&mut write_txn, &key, &entry)?;
-        write_txn.commit()?;
-        Ok(())
+        self.write(|write_txn| {
+            self.storage.put(write_txn, &key, &entry)?;
+            let bytes = self.stored_size(write_txn, Table::Queries, &key)?;
+            self.record_use(write_txn, Table::Queries, &key, bytes)?;
+            self.enforce_limits(write_txn)
+        })
     }
 
     fn search_similarity(&self, embedding: &[f32]) -> Result<Vec<(Query, f32)>> {
+        let unit = unit_vector(embedding);
         let (results, keys_to_purge) = {
-            let mut read_txn = self.env.read_txn()?;
+            let (_txn, mut read_txn) = self.read_txn()?;
             let mut results = Vec::new();
             let mut keys_to_purge = Vec::new();
-            let now = chrono::Utc::now().naive_utc();
 
             for item in self.storage.iter(&mut read_txn)? {
                 let (key, entry_result) = item?;
                 let entry: CacheEntry<Query> = entry_result;
 
-                let entry_age = now - entry.created_at;
-                if entry_age > chrono::Duration::from_std(self.ttl).unwrap() {
+                if self.is_expired(entry.created_at, self.ttl) {
                     keys_to_purge.push(key.to_owned());
                     continue;
                 }
 
-                let query_embedding = &entry.value.embedding;
-                let mut dot_product = 0.0;
-                let mut query_magnitude = 0.0;
-                let mut embedding_magnitude = 0.0;
+                if let Some(similarity) = unit
+                    .as_deref()
+                    .and_then(|unit| similarity(unit, &entry.value))
+                {
+                    results.push((key.to_owned(), entry.value, similarity));
+                }
+            }
+
+            results.sort_by(|a, b| b.2.partial_cmp(&a.2).unwrap_or(std::cmp::Ordering::Equal));
 
-                for (a, b) in query_embedding.iter().zip(embedding.iter()) {
-                    dot_product += a * b;
-                    query_magnitude += a * a;
-                    embedding_magnitude += b * b;
+            (results, keys_to_purge)
+        };
+
+        // The best match is the one a cache hit reuses
+        if let Some((key, _, _)) = results.first() {
+            self.touch(Table::Queries, key)?;
+        }
+        if !keys_to_purge.is_empty() && !self.read_only {
+            tracing::debug!("Purging {} expired cached queries", keys_to_purge.len());
+            self.write(|write_txn| {
+                for key in &keys_to_purge {
+                    self.remove(write_txn, Table::Queries, key)?;
                 }
+                Ok(())
+            })?;
+        }
+
+        Ok(results
+            .into_iter()
+            .map(|(_, query, similarity)| (query, similarity))
+            .collect())
+    }
+
+    fn get_query(&self, action: &str, params: &Value) -> Result<Option<Query>> {
+        let key = response_key(&query_key(action, params));
+
+        let entry = {
+            let (_txn, read_txn) = self.read_txn()?;
+            self.storage.get(&read_txn, &key)?
+        };
+
+        let Some(entry) = entry else {
+            return Ok(None);
+        };
+
+        if self.is_expired(entry.created_at, self.ttl) {
+            if !self.read_only {
+                self.write(|write_txn| self.remove(write_txn, Table::Queries, &key))?;
+            }
+            return Ok(None);
+        }
+        // A hash collision finds another query's result
+        if entry.value.action != action || entry.value.params.as_ref() != Some(params) {
+            return Ok(None);
+        }
+
+        self.touch(Table::Queries, &key)?;
+        Ok(Some(entry.value))
+    }
+
+    fn get_response(&self, key: &str) -> Result<Option<CacheEntry<CachedResponse>>> {
+        let key = response_key(key);
+
+        let entry = {
+            let (_txn, read_txn) = self.read_txn()?;
+            self.responses.get(&read_txn, &key)?
+        };
+
+        let Some(entry) = entry else {
+            return Ok(None);
+        };
+
+        if self.is_expired(entry.created_at, RESPONSE_RETENTION) {
+            if !self.read_only {
+                tracing::debug!("Dropping cached response past its retention period");
+                self.write(|write_txn| self.remove(write_txn, Table::Responses, &key))?;
+            }
+            return Ok(None);
+        }
+
+        self.touch(Table::Responses, &key)?;
+        Ok(Some(entry))
+    }
+
+    fn store_response(&self, key: &str, response: CachedResponse) -> Result<()> {
+        let entry = CacheEntry {
+            created_at: chrono::Utc::now().naive_utc(),
+            value: response,
+        };
+        let key = response_key(key);
+        self.write(|write_txn| {
+            self.responses.put(write_txn, &key, &entry)?;
+            let bytes = self.stored_size(write_txn, Table::Responses, &key)?;
+            self.record_use(write_txn, Table::Responses, &key, bytes)?;
+            self.enforce_limits(write_txn)
+        })
+    }
+
+    fn store_papers(&self, papers: &[Value]) -> Result<()> {
+        self.merge_records(Table::Papers, self.papers, "paperId", papers)
+    }
+
+    fn get_paper(&self, paper_id: &str) -> Result<Option<CacheEntry<Value>>> {
+        let entry = {
+            let (_txn, read_txn) = self.read_txn()?;
+            self.papers.get(&read_txn, paper_id)?
+        };
+        if entry.is_some() {
+            self.touch(Table::Papers, paper_id)?;
+        }
+        Ok(entry)
+    }
+
+    fn get_papers(&self, paper_ids: &[&str]) -> Result<Vec<Option<CacheEntry<Value>>>> {
+        let entries = {
+            let (_txn, read_txn) = self.read_txn()?;
+            paper_ids
+                .iter()
+                .map(|paper_id| self.papers.get(&read_txn, paper_id))
+                .collect::<Result<Vec<_>, _>>()?
+        };
+        for (paper_id, entry) in paper_ids.iter().zip(&entries) {
+            if entry.is_some() {
+                self.touch(Table::Papers, paper_id)?;
+            }
+        }
+        Ok(entries)
+    }
+
+    fn recent_papers(&self, limit: usize) -> Result<Vec<CacheEntry<Value>>> {
+        self.recent_records(self.papers, limit)
+    }
+
+    fn store_authors(&self, authors: &[Value]) -> Result<()> {
+        self.merge_records(Table::Authors, self.authors, "authorId", authors)
+    }
+
+    fn get_author(&self, author_id: &str) -> Result<Option<CacheEntry<Value>>> {
+        let entry = {
+            let (_txn, read_txn) = self.read_txn()?;
+            self.authors.get(&read_txn, author_id)?
+        };
+        if entry.is_some() {
+            self.touch(Table::Authors, author_id)?;
+        }
+        Ok(entry)
+    }
 
-                query_magnitude = query_magnitude.sqrt();
-                embedding_magnitude = embedding_magnitude.sqrt();
+    fn recent_authors(&self, limit: usize) -> Result<Vec<CacheEntry<Value>>> {
+        self.recent_records(self.authors, limit)
+    }
 
-                if query_magnitude > 0.0 && embedding_magnitude > 0.0 {
-                    let similarity = dot_product / (query_magnitude * embedding_magnitude);
+    fn store_author_papers(&self, author_id: &str, paper_ids: &[String]) -> Result<()> {
+        self.write(|write_txn| {
+            let mut known = self
+                .author_papers
+                .get(write_txn, author_id)?
+                .unwrap_or_default();
 
-                    results.push((entry.value, similarity));
+            for paper_id in paper_ids {
+                if !known.contains(paper_id) {
+                    known.push(paper_id.clone());
                 }
             }
 
-            results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+            self.author_papers.put(write_txn, author_id, &known)?;
+            let bytes = self.stored_size(write_txn, Table::AuthorPapers, author_id)?;
+            self.record_use(write_txn, Table::AuthorPapers, author_id, bytes)?;
+            self.enforce_limits(write_txn)
+        })
+    }
 
-            (results, keys_to_purge)
+    fn get_author_papers(&self, author_id: &str) -> Result<Vec<String>> {
+        let known = {
+            let (_txn, read_txn) = self.read_txn()?;
+            self.author_papers.get(&read_txn, author_id)?
         };
+        if known.is_some() {
+            self.touch(Table::AuthorPapers, author_id)?;
+        }
+        Ok(known.unwrap_or_default())
+    }
+
+    fn store_embedding(&self, key: &str, embedding: &[f32]) -> Result<()> {
+        self.write(|write_txn| {
+            self.embeddings.put(write_txn, key, &embedding.to_vec())?;
+            let bytes = self.stored_size(write_txn, Table::Embeddings, key)?;
+            self.record_use(write_txn, Table::Embeddings, key, bytes)?;
+            self.enforce_limits(write_txn)
+        })
+    }
 
-        if !keys_to_purge.is_empty() {
-            let mut write_txn = self.env.write_txn()?;
-            for key in keys_to_purge {
-                self.storage.delete(&mut write_txn, &key)?;
+    fn get_embedding(&self, key: &str) -> Result<Option<Vec<f32>>> {
+        let embedding = {
+            let (_txn, read_txn) = self.read_txn()?;
+            self.embeddings.get(&read_txn, key)?
+        };
+        if embedding.is_some() {
+            self.touch(Table::Embeddings, key)?;
+        }
+        Ok(embedding)
+    }
+
+    fn clear(&self, filter: &ClearFilter) -> Result<ClearedEntries> {
+        self.write(|write_txn| {
+            let mut cleared = ClearedEntries::default();
+
+            let queries = self
+                .storage
+                .iter(write_txn)?
+                .filter_map(|item| match item {
+                    Ok((key, entry)) => filter.matches_query(&entry).then(|| Ok(key.to_string())),
+                    Err(err) => Some(Err(err)),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let responses = self
+                .responses
+                .iter(write_txn)?
+                .filter_map(|item| match item {
+                    Ok((key, entry)) => {
+                        filter.matches_response(&entry).then(|| Ok(key.to_string()))
+                    }
+                    Err(err) => Some(Err(err)),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let matching_records = |database: Database<Str, ZstdJson<CacheEntry<Value>>>| {
+                database
+                    .iter(write_txn)?
+                    .filter_map(|item| match item {
+                        Ok((id, entry)) => filter
+                            .matches_record(id, &entry)
+                            .then(|| Ok(id.to_string())),
+                        Err(err) => Some(Err(err)),
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            };
+            let papers = matching_records(self.papers)?;
+            let authors = matching_records(self.authors)?;
+            let author_papers = self
+                .author_papers
+                .iter(write_txn)?
+                .filter_map(|item| match item {
+                    Ok((author_id, _)) => filter
+                        .matches_author_papers(author_id)
+                        .then(|| Ok(author_id.to_string())),
+                    Err(err) => Some(Err(err)),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let embeddings = if filter.matches_embeddings() {
+                self.embeddings
+                    .iter(write_txn)?
+                    .map(|item| item.map(|(key, _)| key.to_string()))
+                    .collect::<Result<Vec<_>, _>>()?
+            } else {
+                Vec::new()
+            };
+
+            for (table, keys, count) in [
+                (Table::Queries, queries, Some(&mut cleared.queries)),
+                (Table::Responses, responses, Some(&mut cleared.responses)),
+                (Table::Papers, papers, Some(&mut cleared.papers)),
+                (Table::Authors, authors, Some(&mut cleared.authors)),
+                (Table::AuthorPapers, author_papers, None),
+                (Table::Embeddings, embeddings, None),
+            ] {
+                for key in &keys {
+                    self.remove(write_txn, table, key)?;
+                }
+                if let Some(count) = count {
+                    *count = keys.len();
+                }
             }
-            write_txn.commit()?;
+
+            Ok(cleared)
+        })
+    }
+
+    fn purge_expired(&self) -> Result<usize> {
+        // Expired entries are skipped instead
+        if self.read_only {
+            return Ok(0);
         }
+        self.write(|write_txn| {
+            let queries = self
+                .storage
+                .iter(write_txn)?
+                .filter_map(|item| match item {
+                    Ok((key, entry)) => self
+                        .is_expired(entry.created_at, self.ttl)
+                        .then(|| Ok(key.to_string())),
+                    Err(err) => Some(Err(err)),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let responses = self
+                .responses
+                .iter(write_txn)?
+                .filter_map(|item| match item {
+                    Ok((key, entry)) => self
+                        .is_expired(entry.created_at, RESPONSE_RETENTION)
+                        .then(|| Ok(key.to_string())),
+                    Err(err) => Some(Err(err)),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
 
-        Ok(results)
+            for key in &queries {
+                self.remove(write_txn, Table::Queries, key)?;
+            }
+            for key in &responses {
+                self.remove(write_txn, Table::Responses, key)?;
+            }
+            Ok(queries.len() + responses.len())
+        })
     }
 }
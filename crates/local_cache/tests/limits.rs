@@ -0,0 +1,135 @@
+use std::{fs, path::PathBuf, thread, time::Duration};
+
+use cache::Cache;
+use local_cache::{CacheLimits, LocalCache};
+use serde_json::json;
+
+/// An empty directory of its own, so tests don't see each other's caches.
+fn cache_dir(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("local_cache-{}-{}", std::process::id(), name));
+    let _ = fs::remove_dir_all(&path);
+    path
+}
+
+const EMBEDDING: [f32; 64] = [0.25; 64];
+
+/// Size of [`EMBEDDING`] as the cache stores it, as zstd-compressed JSON.
+fn embedding_bytes() -> u64 {
+    let json = serde_json::to_vec(&EMBEDDING.to_vec()).unwrap();
+    zstd::bulk::compress(&json, 3).unwrap().len() as u64
+}
+
+/// Stores the same embedding under each key, apart in time so every entry
+/// was last used at a different millisecond.
+fn store(cache: &LocalCache, keys: impl IntoIterator<Item = usize>) {
+    for n in keys {
+        thread::sleep(Duration::from_millis(2));
+        cache
+            .store_embedding(&format!("e{}", n), &EMBEDDING)
+            .unwrap();
+    }
+}
+
+/// The keys of the entries still cached, out of `e0` to `e{count - 1}`.
+/// Reading them counts as using them, so this only comes last.
+fn kept(cache: &LocalCache, count: usize) -> Vec<usize> {
+    (0..count)
+        .filter(|n| cache.get_embedding(&format!("e{}", n)).unwrap().is_some())
+        .collect()
+}
+
+#[test]
+fn past_max_entries_the_least_recently_used_go_first() {
+    let cache = LocalCache::new(cache_dir("max_entries"), None)
+        .unwrap()
+        .with_limits(CacheLimits {
+            max_entries: Some(10),
+            max_bytes: None,
+        });
+
+    store(&cache, 0..10);
+
+    // Reading e0 makes e1 the least recently used
+    thread::sleep(Duration::from_millis(2));
+    assert!(cache.get_embedding("e0").unwrap().is_some());
+    store(&cache, [10]);
+
+    // Eleven entries are past the limit, and eviction stops at nine
+    assert_eq!(kept(&cache, 11), [0, 3, 4, 5, 6, 7, 8, 9, 10]);
+}
+
+#[test]
+fn past_max_bytes_the_least_recently_used_go_first() {
+    let cache = LocalCache::new(cache_dir("max_bytes"), None)
+        .unwrap()
+        .with_limits(CacheLimits {
+            max_entries: None,
+            max_bytes: Some(10 * embedding_bytes()),
+        });
+
+    // The eleventh entry takes the cache past its bytes
+    store(&cache, 0..11);
+
+    // Back under 90% of the bytes, which nine entries take up
+    assert_eq!(kept(&cache, 11), (2..11).collect::<Vec<_>>());
+}
+
+#[test]
+fn papers_read_together_count_as_used() {
+    let cache = LocalCache::new(cache_dir("get_papers"), None)
+        .unwrap()
+        .with_limits(CacheLimits {
+            max_entries: Some(10),
+            max_bytes: None,
+        });
+
+    for n in 0..10 {
+        thread::sleep(Duration::from_millis(2));
+        cache
+            .store_papers(&[json!({ "paperId": format!("p{}", n) })])
+            .unwrap();
+    }
+
+    thread::sleep(Duration::from_millis(2));
+    let papers = cache.get_papers(&["p1", "missing", "p0"]).unwrap();
+    let ids: Vec<Option<&str>> = papers
+        .iter()
+        .map(|entry| {
+            entry
+                .as_ref()
+                .map(|entry| entry.value["paperId"].as_str().unwrap())
+        })
+        .collect();
+    assert_eq!(ids, [Some("p1"), None, Some("p0")]);
+
+    thread::sleep(Duration::from_millis(2));
+    cache.store_papers(&[json!({ "paperId": "p10" })]).unwrap();
+
+    let kept: Vec<String> = (0..11)
+        .map(|n| format!("p{}", n))
+        .filter(|paper_id| cache.get_paper(paper_id).unwrap().is_some())
+        .collect();
+    assert_eq!(
+        kept,
+        ["p0", "p1", "p4", "p5", "p6", "p7", "p8", "p9", "p10"]
+    );
+}
+
+#[test]
+fn usage_is_still_counted_after_reopening() {
+    let dir = cache_dir("reopen");
+    let limits = CacheLimits {
+        max_entries: None,
+        max_bytes: Some(10 * embedding_bytes()),
+    };
+
+    {
+        let cache = LocalCache::new(&dir, None).unwrap().with_limits(limits);
+        store(&cache, 0..8);
+    }
+
+    let cache = LocalCache::new(&dir, None).unwrap().with_limits(limits);
+    // The entries of the first session still count, and are the oldest
+    store(&cache, 8..11);
+    assert_eq!(kept(&cache, 11), (2..11).collect::<Vec<_>>());
+}
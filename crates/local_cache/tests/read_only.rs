@@ -0,0 +1,114 @@
+use std::{fs, path::PathBuf, sync::Arc, time::Duration};
+
+use cache::{Cache, CachedResponse, ClearFilter, Query, ReadOnlyCache};
+use local_cache::LocalCache;
+use serde_json::json;
+
+/// An empty directory of its own, so tests don't see each other's caches.
+fn cache_dir(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("local_cache-{}-{}", std::process::id(), name));
+    let _ = fs::remove_dir_all(&path);
+    path
+}
+
+fn query(text: &str) -> Query {
+    Query {
+        action: "paper_search".into(),
+        text: text.into(),
+        params: Some(json!({ "query": text })),
+        embedding: vec![1.0, 0.5, 0.25],
+        embedding_model: Some("hash".into()),
+        normalized: false,
+        results: json!({ "data": [] }),
+    }
+}
+
+fn response() -> CachedResponse {
+    CachedResponse {
+        etag: Some("\"v1\"".into()),
+        max_age: Some(60),
+        body: json!({ "paperId": "p1" }),
+        negative: None,
+    }
+}
+
+/// A snapshot holding an entry of every kind.
+fn snapshot(name: &str) -> PathBuf {
+    let dir = cache_dir(name);
+    let cache = LocalCache::new(&dir, None).unwrap();
+    cache.store(query("transformers")).unwrap();
+    cache.store_response("GET /paper/p1", response()).unwrap();
+    cache
+        .store_papers(&[json!({ "paperId": "p1", "title": "Attention" })])
+        .unwrap();
+    cache
+        .store_author_papers("a1", &["p1".to_string()])
+        .unwrap();
+    cache
+        .store_embedding("hash/transformers", &[1.0, 0.5])
+        .unwrap();
+    dir
+}
+
+#[test]
+fn a_session_leaves_a_read_only_base_untouched() {
+    let dir = snapshot("untouched");
+    let data = dir.join("data.mdb");
+    let before = fs::read(&data).unwrap();
+
+    {
+        // Every query has expired, so lookups would purge them if they could
+        let base = LocalCache::open_read_only(&dir, Some(Duration::ZERO)).unwrap();
+        let overlay = LocalCache::new(cache_dir("untouched-overlay"), None).unwrap();
+        let cache = ReadOnlyCache::new(Arc::new(base), Some(Arc::new(overlay)));
+
+        assert!(
+            cache
+                .search_similarity(&[1.0, 0.5, 0.25])
+                .unwrap()
+                .is_empty()
+        );
+        assert!(
+            cache
+                .get_query("paper_search", &json!({ "query": "transformers" }))
+                .unwrap()
+                .is_none()
+        );
+        assert!(cache.get_response("GET /paper/p1").unwrap().is_some());
+        assert_eq!(
+            cache.get_paper("p1").unwrap().unwrap().value["title"],
+            "Attention"
+        );
+        assert_eq!(cache.get_author_papers("a1").unwrap(), ["p1"]);
+        assert!(cache.get_embedding("hash/transformers").unwrap().is_some());
+
+        cache.store(query("graph networks")).unwrap();
+        cache
+            .store_papers(&[json!({ "paperId": "p1", "year": 2017 })])
+            .unwrap();
+        cache.purge_expired().unwrap();
+        cache.clear(&ClearFilter::default()).unwrap();
+    }
+
+    assert_eq!(fs::read(&data).unwrap(), before);
+}
+
+#[test]
+fn a_read_only_cache_refuses_writes() {
+    let dir = snapshot("refuses");
+    let cache = LocalCache::open_read_only(&dir, None).unwrap();
+
+    assert!(cache.store(query("graph networks")).is_err());
+    assert!(cache.clear(&ClearFilter::default()).is_err());
+    // The sweep has nothing to do
+    assert_eq!(cache.purge_expired().unwrap(), 0);
+    assert!(cache.get_paper("p1").unwrap().is_some());
+}
+
+#[test]
+fn opening_read_only_needs_an_existing_cache() {
+    let dir = cache_dir("missing");
+    fs::create_dir_all(&dir).unwrap();
+
+    assert!(LocalCache::open_read_only(&dir, None).is_err());
+}
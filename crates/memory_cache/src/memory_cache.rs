@@ -0,0 +1,260 @@
+use std::{
+    collections::HashMap,
+    sync::{Mutex, MutexGuard},
+    time::Duration,
+};
+
+use anyhow::{Result, anyhow};
+use cache::{
+    Cache, CacheEntry, CachedResponse, ClearFilter, ClearedEntries, DEFAULT_TTL, Query,
+    RESPONSE_RETENTION, merge_record, similarity, unit_vector,
+};
+use chrono::NaiveDateTime;
+use serde_json::Value;
+
+/// A cache held in memory and lost when the process exits, for short-lived
+/// runs and for deployments where the data directory isn't writable.
+pub struct MemoryCache {
+    inner: Mutex<Inner>,
+    ttl: Duration,
+}
+
+#[derive(Default)]
+struct Inner {
+    queries: Vec<CacheEntry<Query>>,
+    responses: HashMap<String, CacheEntry<CachedResponse>>,
+    papers: HashMap<String, CacheEntry<Value>>,
+    authors: HashMap<String, CacheEntry<Value>>,
+    author_papers: HashMap<String, Vec<String>>,
+    embeddings: HashMap<String, Vec<f32>>,
+}
+
+impl MemoryCache {
+    pub fn new(ttl: Option<Duration>) -> Self {
+        Self {
+            inner: Mutex::new(Inner::default()),
+            ttl: ttl.unwrap_or(DEFAULT_TTL),
+        }
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, Inner>> {
+        self.inner
+            .lock()
+            .map_err(|_| anyhow!("Memory cache poisoned"))
+    }
+}
+
+fn now() -> NaiveDateTime {
+    chrono::Utc::now().naive_utc()
+}
+
+fn merge_records(records: &mut HashMap<String, CacheEntry<Value>>, id_field: &str, new: &[Value]) {
+    for record in new {
+        let (Some(id), Some(fields)) = (
+            record.get(id_field).and_then(Value::as_str),
+            record.as_object(),
+        ) else {
+            continue;
+        };
+
+        let existing = records.remove(id).map(|entry| entry.value);
+        records.insert(
+            id.to_string(),
+            CacheEntry {
+                value: merge_record(existing, fields),
+                created_at: now(),
+            },
+        );
+    }
+}
+
+fn recent_records(
+    records: &HashMap<String, CacheEntry<Value>>,
+    limit: usize,
+) -> Vec<CacheEntry<Value>> {
+    let mut records: Vec<_> = records.values().cloned().collect();
+    records.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    records.truncate(limit);
+    records
+}
+
+impl Cache for MemoryCache {
+    fn store(&self, mut query: Query) -> Result<()> {
+        query.normalize();
+        let mut inner = self.lock()?;
+        // A repeated query replaces the previous result
+        if let Some(params) = &query.params {
+            inner.queries.retain(|entry| {
+                entry.value.action != query.action || entry.value.params.as_ref() != Some(params)
+            });
+        }
+        inner.queries.push(CacheEntry {
+            value: query,
+            created_at: now(),
+        });
+        Ok(())
+    }
+
+    fn search_similarity(&self, embedding: &[f32]) -> Result<Vec<(Query, f32)>> {
+        let mut inner = self.lock()?;
+        let ttl = chrono::Duration::from_std(self.ttl)?;
+        let now = now();
+        inner.queries.retain(|entry| now - entry.created_at <= ttl);
+
+        let Some(unit) = unit_vector(embedding) else {
+            return Ok(Vec::new());
+        };
+        let mut results: Vec<_> = inner
+            .queries
+            .iter()
+            .filter_map(|entry| {
+                let similarity = similarity(&unit, &entry.value)?;
+                Some((entry.value.clone(), similarity))
+            })
+            .collect();
+
+        results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        Ok(results)
+    }
+
+    fn get_query(&self, action: &str, params: &Value) -> Result<Option<Query>> {
+        let inner = self.lock()?;
+        let ttl = chrono::Duration::from_std(self.ttl)?;
+        let now = now();
+        Ok(inner
+            .queries
+            .iter()
+            .find(|entry| {
+                entry.value.action == action
+                    && entry.value.params.as_ref() == Some(params)
+                    && now - entry.created_at <= ttl
+            })
+            .map(|entry| entry.value.clone()))
+    }
+
+    fn get_response(&self, key: &str) -> Result<Option<CacheEntry<CachedResponse>>> {
+        Ok(self.lock()?.responses.get(key).cloned())
+    }
+
+    fn store_response(&self, key: &str, response: CachedResponse) -> Result<()> {
+        self.lock()?.responses.insert(
+            key.to_string(),
+            CacheEntry {
+                value: response,
+                created_at: now(),
+            },
+        );
+        Ok(())
+    }
+
+    fn store_papers(&self, papers: &[Value]) -> Result<()> {
+        merge_records(&mut self.lock()?.papers, "paperId", papers);
+        Ok(())
+    }
+
+    fn get_paper(&self, paper_id: &str) -> Result<Option<CacheEntry<Value>>> {
+        Ok(self.lock()?.papers.get(paper_id).cloned())
+    }
+
+    fn get_papers(&self, paper_ids: &[&str]) -> Result<Vec<Option<CacheEntry<Value>>>> {
+        let inner = self.lock()?;
+        Ok(paper_ids
+            .iter()
+            .map(|paper_id| inner.papers.get(*paper_id).cloned())
+            .collect())
+    }
+
+    fn recent_papers(&self, limit: usize) -> Result<Vec<CacheEntry<Value>>> {
+        Ok(recent_records(&self.lock()?.papers, limit))
+    }
+
+    fn store_authors(&self, authors: &[Value]) -> Result<()> {
+        merge_records(&mut self.lock()?.authors, "authorId", authors);
+        Ok(())
+    }
+
+    fn get_author(&self, author_id: &str) -> Result<Option<CacheEntry<Value>>> {
+        Ok(self.lock()?.authors.get(author_id).cloned())
+    }
+
+    fn recent_authors(&self, limit: usize) -> Result<Vec<CacheEntry<Value>>> {
+        Ok(recent_records(&self.lock()?.authors, limit))
+    }
+
+    fn store_author_papers(&self, author_id: &str, paper_ids: &[String]) -> Result<()> {
+        let mut inner = self.lock()?;
+        let known = inner
+            .author_papers
+            .entry(author_id.to_string())
+            .or_default();
+        for paper_id in paper_ids {
+            if !known.contains(paper_id) {
+                known.push(paper_id.clone());
+            }
+        }
+        Ok(())
+    }
+
+    fn get_author_papers(&self, author_id: &str) -> Result<Vec<String>> {
+        Ok(self
+            .lock()?
+            .author_papers
+            .get(author_id)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    fn store_embedding(&self, key: &str, embedding: &[f32]) -> Result<()> {
+        self.lock()?
+            .embeddings
+            .insert(key.to_string(), embedding.to_vec());
+        Ok(())
+    }
+
+    fn get_embedding(&self, key: &str) -> Result<Option<Vec<f32>>> {
+        Ok(self.lock()?.embeddings.get(key).cloned())
+    }
+
+    fn clear(&self, filter: &ClearFilter) -> Result<ClearedEntries> {
+        let mut inner = self.lock()?;
+        let Inner {
+            queries,
+            responses,
+            papers,
+            authors,
+            author_papers,
+            embeddings,
+        } = &mut *inner;
+
+        let before = (queries.len(), responses.len(), papers.len(), authors.len());
+        queries.retain(|entry| !filter.matches_query(entry));
+        responses.retain(|_, entry| !filter.matches_response(entry));
+        papers.retain(|id, entry| !filter.matches_record(id, entry));
+        authors.retain(|id, entry| !filter.matches_record(id, entry));
+        author_papers.retain(|author_id, _| !filter.matches_author_papers(author_id));
+        if filter.matches_embeddings() {
+            embeddings.clear();
+        }
+
+        Ok(ClearedEntries {
+            queries: before.0 - queries.len(),
+            responses: before.1 - responses.len(),
+            papers: before.2 - papers.len(),
+            authors: before.3 - authors.len(),
+        })
+    }
+
+    fn purge_expired(&self) -> Result<usize> {
+        let mut inner = self.lock()?;
+        let ttl = chrono::Duration::from_std(self.ttl)?;
+        let retention = chrono::Duration::from_std(RESPONSE_RETENTION)?;
+        let now = now();
+
+        let before = inner.queries.len() + inner.responses.len();
+        inner.queries.retain(|entry| now - entry.created_at <= ttl);
+        inner
+            .responses
+            .retain(|_, entry| now - entry.created_at <= retention);
+        Ok(before - inner.queries.len() - inner.responses.len())
+    }
+}
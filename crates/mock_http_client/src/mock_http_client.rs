@@ -0,0 +1,257 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use futures::AsyncReadExt;
+use http_client::{AsyncBody, HttpClient, Request, Response};
+use semantic_scholar_client::{RateClass, RateLimit, RateLimiter};
+use serde_json::Value;
+
+/// A response recorded from Semantic Scholar, by the name of its file in
+/// `fixtures/` without the extension.
+pub fn fixture(name: &str) -> Value {
+    let source = match name {
+        "author_batch" => include_str!("../fixtures/author_batch.json"),
+        "author_details" => include_str!("../fixtures/author_details.json"),
+        "author_papers" => include_str!("../fixtures/author_papers.json"),
+        "author_search" => include_str!("../fixtures/author_search.json"),
+        "dataset_list" => include_str!("../fixtures/dataset_list.json"),
+        "dataset_releases" => include_str!("../fixtures/dataset_releases.json"),
+        "not_found" => include_str!("../fixtures/not_found.json"),
+        "paper_batch" => include_str!("../fixtures/paper_batch.json"),
+        "paper_citations" => include_str!("../fixtures/paper_citations.json"),
+        "paper_details" => include_str!("../fixtures/paper_details.json"),
+        "paper_references" => include_str!("../fixtures/paper_references.json"),
+        "paper_search" => include_str!("../fixtures/paper_search.json"),
+        "paper_search_empty" => include_str!("../fixtures/paper_search_empty.json"),
+        "paper_title_match" => include_str!("../fixtures/paper_title_match.json"),
+        "rate_limited" => include_str!("../fixtures/rate_limited.json"),
+        "recommendations" => include_str!("../fixtures/recommendations.json"),
+        _ => panic!("No fixture named {}", name),
+    };
+    serde_json::from_str(source).unwrap_or_else(|err| panic!("Invalid fixture {}: {}", name, err))
+}
+
+/// A rate limiter that never makes a test wait.
+pub fn unlimited_rate_limiter() -> Arc<RateLimiter> {
+    let mut rate_limiter = RateLimiter::new();
+    for class in RateClass::ALL {
+        for authenticated in [false, true] {
+            rate_limiter =
+                rate_limiter.with_limit(class, authenticated, RateLimit::new(1000.0, 1000.0));
+        }
+    }
+    Arc::new(rate_limiter)
+}
+
+/// A canned response.
+#[derive(Debug, Clone)]
+pub struct MockResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl MockResponse {
+    pub fn new(status: u16, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: body.into(),
+        }
+    }
+
+    /// A 200 with `body` as JSON.
+    pub fn json(body: &Value) -> Self {
+        Self::new(200, body.to_string()).with_header("Content-Type", "application/json")
+    }
+
+    /// A 200 with the named fixture as body.
+    pub fn fixture(name: &str) -> Self {
+        Self::json(&fixture(name))
+    }
+
+    /// An error response with the named fixture as body.
+    pub fn error(status: u16, name: &str) -> Self {
+        Self::new(status, fixture(name).to_string()).with_header("Content-Type", "application/json")
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+}
+
+/// Which requests a [`MockResponse`] answers, and with what.
+#[derive(Debug, Clone)]
+pub struct Route {
+    method: String,
+    path: String,
+    query: Vec<(String, String)>,
+    /// Given in turn, the last one answering every request after it
+    responses: VecDeque<MockResponse>,
+}
+
+impl Route {
+    /// Matches requests with this method to this path, which includes the
+    /// API prefix, such as `/graph/v1/paper/search`.
+    pub fn new(method: &str, path: &str) -> Self {
+        Self {
+            method: method.to_uppercase(),
+            path: path.to_string(),
+            query: Vec::new(),
+            responses: VecDeque::new(),
+        }
+    }
+
+    pub fn get(path: &str) -> Self {
+        Self::new("GET", path)
+    }
+
+    pub fn post(path: &str) -> Self {
+        Self::new("POST", path)
+    }
+
+    /// Only matches requests with this query parameter, leaving the others
+    /// free.
+    pub fn query(mut self, name: &str, value: &str) -> Self {
+        self.query.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Answers with `response`. Given more than once, the responses are used
+    /// in turn and the last one keeps answering.
+    pub fn respond(mut self, response: MockResponse) -> Self {
+        self.responses.push_back(response);
+        self
+    }
+
+    fn matches(&self, request: &RecordedRequest) -> bool {
+        self.method == request.method
+            && self.path == request.path
+            && self.query.iter().all(|(name, value)| {
+                request
+                    .query
+                    .iter()
+                    .any(|(other_name, other_value)| name == other_name && value == other_value)
+            })
+    }
+
+    fn next_response(&mut self) -> Option<MockResponse> {
+        if self.responses.len() > 1 {
+            self.responses.pop_front()
+        } else {
+            self.responses.front().cloned()
+        }
+    }
+}
+
+/// A request the client received.
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+    /// Decoded query parameters, in the order they were sent
+    pub query: Vec<(String, String)>,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<Value>,
+}
+
+impl RecordedRequest {
+    /// The first value of a query parameter.
+    pub fn query_param(&self, name: &str) -> Option<&str> {
+        self.query
+            .iter()
+            .find(|(other_name, _)| other_name == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// An [`HttpClient`] for testing tools without reaching Semantic Scholar.
+/// Answers requests from its routes, the first matching route winning, and
+/// with a 404 when none matches. Every request is recorded, so tests can
+/// check what was sent and how often.
+#[derive(Default)]
+pub struct MockHttpClient {
+    routes: Mutex<Vec<Route>>,
+    requests: Mutex<Vec<RecordedRequest>>,
+}
+
+impl MockHttpClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_route(self, route: Route) -> Self {
+        self.routes.lock().unwrap().push(route);
+        self
+    }
+
+    /// The requests received so far, oldest first.
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    pub fn request_count(&self) -> usize {
+        self.requests.lock().unwrap().len()
+    }
+}
+
+#[async_trait]
+impl HttpClient for MockHttpClient {
+    async fn send(&self, mut request: Request) -> Result<Response> {
+        let mut body = Vec::new();
+        request.body_mut().read_to_end(&mut body).await?;
+
+        let uri = request.uri();
+        let query = uri
+            .query()
+            .unwrap_or_default()
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+                Ok((
+                    urlencoding::decode(name)?.into_owned(),
+                    urlencoding::decode(value)?.into_owned(),
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let recorded = RecordedRequest {
+            method: request.method().as_str().to_string(),
+            path: uri.path().to_string(),
+            query,
+            headers: request
+                .headers()
+                .iter()
+                .filter_map(|(name, value)| {
+                    Some((name.as_str().to_string(), value.to_str().ok()?.to_string()))
+                })
+                .collect(),
+            body: (!body.is_empty())
+                .then(|| serde_json::from_slice(&body))
+                .transpose()?,
+        };
+
+        let response = self
+            .routes
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .find(|route| route.matches(&recorded))
+            .and_then(Route::next_response)
+            .unwrap_or_else(|| MockResponse::error(404, "not_found"));
+        self.requests.lock().unwrap().push(recorded);
+
+        let mut builder = Response::builder().status(response.status);
+        for (name, value) in &response.headers {
+            builder = builder.header(name.as_str(), value.as_str());
+        }
+        builder
+            .body(AsyncBody::from(response.body))
+            .map_err(|err| anyhow!("Invalid mock response: {}", err))
+    }
+}
@@ -0,0 +1,193 @@
+use serde::{Deserialize, Serialize};
+
+/// A page of results from a paginated Semantic Scholar endpoint.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Page<T> {
+    pub total: Option<u64>,
+    pub offset: Option<u64>,
+    pub next: Option<u64>,
+    #[serde(default = "Vec::new")]
+    pub data: Vec<T>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Paper {
+    pub paper_id: Option<String>,
+    pub corpus_id: Option<u64>,
+    pub external_ids: Option<ExternalIds>,
+    pub url: Option<String>,
+    pub title: Option<String>,
+    #[serde(rename = "abstract")]
+    pub abstract_text: Option<String>,
+    pub venue: Option<String>,
+    pub publication_venue: Option<PublicationVenue>,
+    pub year: Option<u64>,
+    pub reference_count: Option<u64>,
+    pub citation_count: Option<u64>,
+    pub influential_citation_count: Option<u64>,
+    pub is_open_access: Option<bool>,
+    pub open_access_pdf: Option<OpenAccessPdf>,
+    pub fields_of_study: Option<Vec<String>>,
+    pub s2_fields_of_study: Option<Vec<S2FieldOfStudy>>,
+    pub publication_types: Option<Vec<String>>,
+    pub publication_date: Option<String>,
+    pub journal: Option<Journal>,
+    pub authors: Option<Vec<Author>>,
+    pub citations: Option<Vec<Paper>>,
+    pub references: Option<Vec<Paper>>,
+    pub embedding: Option<Embedding>,
+    pub tldr: Option<Tldr>,
+    /// Only set by the title match endpoint
+    pub match_score: Option<f64>,
+}
+
+impl Paper {
+    /// Names of the paper's authors, skipping authors without a name.
+    pub fn author_names(&self) -> Vec<&str> {
+        self.authors
+            .iter()
+            .flatten()
+            .filter_map(|author| author.name.as_deref())
+            .collect()
+    }
+
+    /// The venue name, treating an empty string as missing.
+    pub fn venue(&self) -> Option<&str> {
+        self.venue.as_deref().filter(|venue| !venue.is_empty())
+    }
+
+    /// The abstract, treating an empty string as missing.
+    pub fn abstract_text(&self) -> Option<&str> {
+        self.abstract_text
+            .as_deref()
+            .filter(|abstract_text| !abstract_text.is_empty())
+    }
+
+    /// The open-access PDF URL, treating an empty string as missing.
+    pub fn open_access_pdf_url(&self) -> Option<&str> {
+        self.open_access_pdf
+            .as_ref()
+            .and_then(|pdf| pdf.url.as_deref())
+            .filter(|url| !url.is_empty())
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ExternalIds {
+    #[serde(rename = "DOI")]
+    pub doi: Option<String>,
+    #[serde(rename = "ArXiv")]
+    pub arxiv: Option<String>,
+    #[serde(rename = "PubMed")]
+    pub pubmed: Option<String>,
+    #[serde(rename = "PubMedCentral")]
+    pub pubmed_central: Option<String>,
+    #[serde(rename = "ACL")]
+    pub acl: Option<String>,
+    #[serde(rename = "MAG")]
+    pub mag: Option<String>,
+    #[serde(rename = "DBLP")]
+    pub dblp: Option<String>,
+    #[serde(rename = "CorpusId")]
+    pub corpus_id: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct PublicationVenue {
+    pub id: Option<String>,
+    pub name: Option<String>,
+    #[serde(rename = "type")]
+    pub venue_type: Option<String>,
+    pub alternate_names: Option<Vec<String>>,
+    pub issn: Option<String>,
+    pub url: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct OpenAccessPdf {
+    pub url: Option<String>,
+    pub status: Option<String>,
+    pub license: Option<String>,
+    pub disclaimer: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct S2FieldOfStudy {
+    pub category: Option<String>,
+    pub source: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct Journal {
+    pub name: Option<String>,
+    pub volume: Option<String>,
+    pub pages: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct Embedding {
+    pub model: Option<String>,
+    #[serde(default)]
+    pub vector: Vec<f32>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct Tldr {
+    pub model: Option<String>,
+    pub text: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Author {
+    pub author_id: Option<String>,
+    pub url: Option<String>,
+    pub name: Option<String>,
+    pub aliases: Option<Vec<String>>,
+    pub affiliations: Option<Vec<String>>,
+    pub homepage: Option<String>,
+    pub paper_count: Option<u64>,
+    pub citation_count: Option<u64>,
+    pub h_index: Option<u64>,
+    pub papers: Option<Vec<Paper>>,
+}
+
+impl Author {
+    /// The homepage, treating an empty string as missing.
+    pub fn homepage(&self) -> Option<&str> {
+        self.homepage
+            .as_deref()
+            .filter(|homepage| !homepage.is_empty())
+    }
+}
+
+/// An entry of `/paper/{id}/citations`: a paper citing the requested one.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Citation {
+    #[serde(default)]
+    pub citing_paper: Paper,
+    pub contexts: Option<Vec<String>>,
+    pub intents: Option<Vec<String>>,
+    pub is_influential: Option<bool>,
+}
+
+/// An entry of `/paper/{id}/references`: a paper cited by the requested one.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Reference {
+    #[serde(default)]
+    pub cited_paper: Paper,
+    pub contexts: Option<Vec<String>>,
+    pub intents: Option<Vec<String>>,
+    pub is_influential: Option<bool>,
+}
+
+/// The response of the recommendations API.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Recommendations {
+    #[serde(default)]
+    pub recommended_papers: Vec<Paper>,
+}
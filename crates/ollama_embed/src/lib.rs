@@ -6,38 +6,83 @@ use embed::Embed;
 use http_client::{HttpClient, http::Uri};
 use ollama::{EmbedInput, EmbedRequest, Ollama, OllamaBuilder};
 
-pub struct OllamaEmbed(Ollama);
+const DEFAULT_MODEL: &str = "nomic-embed-text:latest";
 
-pub struct OllamaEmbedBuilder(OllamaBuilder);
+/// Length of the vectors the default model returns.
+const DEFAULT_MODEL_DIMENSIONS: usize = 768;
+
+pub struct OllamaEmbed {
+    client: Ollama,
+    model: String,
+    dimensions: Option<usize>,
+}
+
+pub struct OllamaEmbedBuilder {
+    client: OllamaBuilder,
+    model: Option<String>,
+    dimensions: Option<usize>,
+}
 
 impl OllamaEmbed {
     pub fn builder() -> OllamaEmbedBuilder {
-        OllamaEmbedBuilder(Ollama::builder())
+        OllamaEmbedBuilder {
+            client: Ollama::builder(),
+            model: None,
+            dimensions: None,
+        }
+    }
+
+    /// The model texts are embedded with.
+    pub fn model(&self) -> &str {
+        &self.model
     }
 }
 
 impl OllamaEmbedBuilder {
     pub fn with_http_client(&mut self, http_client: Arc<dyn HttpClient>) -> &mut Self {
-        self.0.with_http_client(http_client);
+        self.client.with_http_client(http_client);
         self
     }
 
     pub fn with_uri<U: Into<Uri>>(&mut self, uri: U) -> &mut Self {
-        self.0.with_uri(uri);
+        self.client.with_uri(uri);
+        self
+    }
+
+    /// The embedding model to use, `nomic-embed-text:latest` by default.
+    pub fn with_model(&mut self, model: impl Into<String>) -> &mut Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    /// Length of the vectors the model returns, known for the default model
+    /// only unless given.
+    pub fn with_dimensions(&mut self, dimensions: usize) -> &mut Self {
+        self.dimensions = Some(dimensions);
         self
     }
 
     pub fn build(&self) -> OllamaEmbed {
-        OllamaEmbed(self.0.build())
+        OllamaEmbed {
+            client: self.client.build(),
+            model: self
+                .model
+                .clone()
+                .unwrap_or_else(|| DEFAULT_MODEL.to_string()),
+            dimensions: self
+                .dimensions
+                .or_else(|| self.model.is_none().then_some(DEFAULT_MODEL_DIMENSIONS)),
+        }
     }
 }
 
 #[async_trait]
 impl Embed for OllamaEmbed {
     async fn embed(&self, text: &str) -> Result<Vec<f32>> {
-        self.0
+        tracing::debug!("Embedding {} characters with {}", text.len(), self.model);
+        self.client
             .embed(EmbedRequest {
-                model: "nomic-embed-text:latest".into(),
+                model: self.model.clone(),
                 input: EmbedInput::Single(text.into()),
                 truncate: Some(false),
                 options: None,
@@ -45,5 +90,14 @@ impl Embed for OllamaEmbed {
             })
             .await
             .map(|result| result.embeddings[0].to_owned())
+            .inspect_err(|err| tracing::warn!("Ollama failed to embed text: {}", err))
+    }
+
+    fn model_id(&self) -> String {
+        format!("ollama/{}", self.model)
+    }
+
+    fn dimensions(&self) -> Option<usize> {
+        self.dimensions
     }
 }
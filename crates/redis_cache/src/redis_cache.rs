@@ -0,0 +1,374 @@
+use std::{
+    collections::HashMap,
+    sync::{Mutex, MutexGuard},
+    time::Duration,
+};
+
+use anyhow::{Result, anyhow};
+use cache::{
+    Cache, CacheEntry, CachedResponse, ClearFilter, ClearedEntries, DEFAULT_TTL, Query,
+    RESPONSE_RETENTION, merge_record, query_key, similarity, unit_vector,
+};
+use chrono::NaiveDateTime;
+use redis::{Client, Commands, Connection};
+use serde_json::Value;
+use uuid::Uuid;
+
+/// Prefix of every key the cache writes, so it can share a Redis database.
+const KEY_PREFIX: &str = "semantic-scholar-mcp";
+
+/// A cache in Redis, shared by every server pointed at the same instance.
+/// Raw responses expire through Redis itself; queries past their TTL are
+/// purged on lookup as in the other backends.
+pub struct RedisCache {
+    connection: Mutex<Connection>,
+    ttl: Duration,
+    /// Whether queries past `ttl` are kept
+    keep_expired: bool,
+}
+
+impl RedisCache {
+    /// Connects to `url`, such as `redis://localhost:6379/0`.
+    pub fn new(url: &str, ttl: Option<Duration>) -> Result<Self> {
+        let connection = Client::open(url)?.get_connection()?;
+
+        tracing::debug!("Connected to Redis cache at {}", url);
+
+        Ok(Self {
+            connection: Mutex::new(connection),
+            ttl: ttl.unwrap_or(DEFAULT_TTL),
+            keep_expired: false,
+        })
+    }
+
+    /// Serves queries however old they are and never deletes them for their
+    /// age, for offline use where nothing expired can be fetched again.
+    /// Responses still expire through Redis.
+    pub fn keep_expired(mut self) -> Self {
+        self.keep_expired = true;
+        self
+    }
+
+    /// Whether a query stored at `created_at` is past the TTL.
+    fn is_expired(&self, created_at: NaiveDateTime) -> bool {
+        !self.keep_expired
+            && chrono::Duration::from_std(self.ttl)
+                .is_ok_and(|ttl| chrono::Utc::now().naive_utc() - created_at > ttl)
+    }
+
+    fn connection(&self) -> Result<MutexGuard<'_, Connection>> {
+        self.connection
+            .lock()
+            .map_err(|_| anyhow!("Redis cache poisoned"))
+    }
+
+    fn merge_records(&self, hash: &str, id_field: &str, records: &[Value]) -> Result<()> {
+        let mut connection = self.connection()?;
+        let key = key(hash);
+
+        for record in records {
+            let (Some(id), Some(fields)) = (
+                record.get(id_field).and_then(Value::as_str),
+                record.as_object(),
+            ) else {
+                continue;
+            };
+
+            let existing: Option<String> = connection.hget(&key, id)?;
+            let existing = existing
+                .map(|entry| serde_json::from_str::<CacheEntry<Value>>(&entry))
+                .transpose()?
+                .map(|entry| entry.value);
+
+            let entry = CacheEntry {
+                value: merge_record(existing, fields),
+                created_at: chrono::Utc::now().naive_utc(),
+            };
+            let _: () = connection.hset(&key, id, serde_json::to_string(&entry)?)?;
+        }
+
+        Ok(())
+    }
+
+    fn get_record(&self, hash: &str, id: &str) -> Result<Option<CacheEntry<Value>>> {
+        let entry: Option<String> = self.connection()?.hget(key(hash), id)?;
+        Ok(entry
+            .map(|entry| serde_json::from_str(&entry))
+            .transpose()?)
+    }
+
+    fn recent_records(&self, hash: &str, limit: usize) -> Result<Vec<CacheEntry<Value>>> {
+        let entries: HashMap<String, String> = self.connection()?.hgetall(key(hash))?;
+        let mut records = entries
+            .values()
+            .map(|entry| serde_json::from_str::<CacheEntry<Value>>(entry))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        records.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        records.truncate(limit);
+        Ok(records)
+    }
+}
+
+fn key(name: &str) -> String {
+    format!("{}:{}", KEY_PREFIX, name)
+}
+
+impl Cache for RedisCache {
+    fn store(&self, mut query: Query) -> Result<()> {
+        query.normalize();
+        // Queries are stored under their action and parameters, so a repeated
+        // query replaces the previous result and exact lookups are one read
+        let field = match &query.params {
+            Some(params) => query_key(&query.action, params),
+            None => Uuid::new_v4().to_string(),
+        };
+        let entry = CacheEntry {
+            value: query,
+            created_at: chrono::Utc::now().naive_utc(),
+        };
+        let _: () =
+            self.connection()?
+                .hset(key("queries"), field, serde_json::to_string(&entry)?)?;
+        Ok(())
+    }
+
+    fn search_similarity(&self, embedding: &[f32]) -> Result<Vec<(Query, f32)>> {
+        let mut connection = self.connection()?;
+        let entries: HashMap<String, String> = connection.hgetall(key("queries"))?;
+
+        let unit = unit_vector(embedding);
+        let mut results = Vec::new();
+        let mut keys_to_purge = Vec::new();
+        for (id, entry) in entries {
+            let entry: CacheEntry<Query> = serde_json::from_str(&entry)?;
+            if self.is_expired(entry.created_at) {
+                keys_to_purge.push(id);
+                continue;
+            }
+            if let Some(similarity) = unit
+                .as_deref()
+                .and_then(|unit| similarity(unit, &entry.value))
+            {
+                results.push((entry.value, similarity));
+            }
+        }
+
+        if !keys_to_purge.is_empty() {
+            tracing::debug!("Purging {} expired cached queries", keys_to_purge.len());
+            let _: () = connection.hdel(key("queries"), keys_to_purge)?;
+        }
+
+        results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        Ok(results)
+    }
+
+    fn get_query(&self, action: &str, params: &Value) -> Result<Option<Query>> {
+        let entry: Option<String> = self
+            .connection()?
+            .hget(key("queries"), query_key(action, params))?;
+        let Some(entry) = entry else {
+            return Ok(None);
+        };
+
+        let entry: CacheEntry<Query> = serde_json::from_str(&entry)?;
+        if self.is_expired(entry.created_at) {
+            return Ok(None);
+        }
+        Ok(Some(entry.value))
+    }
+
+    fn get_response(&self, response_key: &str) -> Result<Option<CacheEntry<CachedResponse>>> {
+        let entry: Option<String> = self
+            .connection()?
+            .get(key(&format!("response:{}", response_key)))?;
+        Ok(entry
+            .map(|entry| serde_json::from_str(&entry))
+            .transpose()?)
+    }
+
+    fn store_response(&self, response_key: &str, response: CachedResponse) -> Result<()> {
+        let entry = CacheEntry {
+            value: response,
+            created_at: chrono::Utc::now().naive_utc(),
+        };
+        let _: () = self.connection()?.set_ex(
+            key(&format!("response:{}", response_key)),
+            serde_json::to_string(&entry)?,
+            RESPONSE_RETENTION.as_secs(),
+        )?;
+        Ok(())
+    }
+
+    fn store_papers(&self, papers: &[Value]) -> Result<()> {
+        self.merge_records("papers", "paperId", papers)
+    }
+
+    fn get_paper(&self, paper_id: &str) -> Result<Option<CacheEntry<Value>>> {
+        self.get_record("papers", paper_id)
+    }
+
+    fn get_papers(&self, paper_ids: &[&str]) -> Result<Vec<Option<CacheEntry<Value>>>> {
+        if paper_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let entries: Vec<Option<String>> = redis::cmd("HMGET")
+            .arg(key("papers"))
+            .arg(paper_ids)
+            .query(&mut *self.connection()?)?;
+        entries
+            .into_iter()
+            .map(|entry| {
+                Ok(entry
+                    .map(|entry| serde_json::from_str(&entry))
+                    .transpose()?)
+            })
+            .collect()
+    }
+
+    fn recent_papers(&self, limit: usize) -> Result<Vec<CacheEntry<Value>>> {
+        self.recent_records("papers", limit)
+    }
+
+    fn store_authors(&self, authors: &[Value]) -> Result<()> {
+        self.merge_records("authors", "authorId", authors)
+    }
+
+    fn get_author(&self, author_id: &str) -> Result<Option<CacheEntry<Value>>> {
+        self.get_record("authors", author_id)
+    }
+
+    fn recent_authors(&self, limit: usize) -> Result<Vec<CacheEntry<Value>>> {
+        self.recent_records("authors", limit)
+    }
+
+    fn store_author_papers(&self, author_id: &str, paper_ids: &[String]) -> Result<()> {
+        let mut connection = self.connection()?;
+        let known: Option<String> = connection.hget(key("author_papers"), author_id)?;
+        let mut known: Vec<String> = match known {
+            Some(known) => serde_json::from_str(&known)?,
+            None => Vec::new(),
+        };
+
+        for paper_id in paper_ids {
+            if !known.contains(paper_id) {
+                known.push(paper_id.clone());
+            }
+        }
+
+        let _: () = connection.hset(
+            key("author_papers"),
+            author_id,
+            serde_json::to_string(&known)?,
+        )?;
+        Ok(())
+    }
+
+    fn get_author_papers(&self, author_id: &str) -> Result<Vec<String>> {
+        let known: Option<String> = self.connection()?.hget(key("author_papers"), author_id)?;
+        match known {
+            Some(known) => Ok(serde_json::from_str(&known)?),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    fn store_embedding(&self, embedding_key: &str, embedding: &[f32]) -> Result<()> {
+        let _: () = self.connection()?.hset(
+            key("embeddings"),
+            embedding_key,
+            serde_json::to_string(embedding)?,
+        )?;
+        Ok(())
+    }
+
+    fn get_embedding(&self, embedding_key: &str) -> Result<Option<Vec<f32>>> {
+        let embedding: Option<String> =
+            self.connection()?.hget(key("embeddings"), embedding_key)?;
+        match embedding {
+            Some(embedding) => Ok(Some(serde_json::from_str(&embedding)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn clear(&self, filter: &ClearFilter) -> Result<ClearedEntries> {
+        let mut connection = self.connection()?;
+        let mut cleared = ClearedEntries::default();
+
+        let queries: HashMap<String, String> = connection.hgetall(key("queries"))?;
+        let mut matching = Vec::new();
+        for (id, entry) in queries {
+            if filter.matches_query(&serde_json::from_str(&entry)?) {
+                matching.push(id);
+            }
+        }
+        cleared.queries = matching.len();
+        if !matching.is_empty() {
+            let _: () = connection.hdel(key("queries"), matching)?;
+        }
+
+        let response_keys: Vec<String> = connection
+            .scan_match::<_, String>(key("response:*"))?
+            .collect();
+        for response_key in response_keys {
+            let entry: Option<String> = connection.get(&response_key)?;
+            let Some(entry) = entry else {
+                continue;
+            };
+            if filter.matches_response(&serde_json::from_str(&entry)?) {
+                let _: () = connection.del(&response_key)?;
+                cleared.responses += 1;
+            }
+        }
+
+        for (hash, count) in [
+            ("papers", &mut cleared.papers),
+            ("authors", &mut cleared.authors),
+        ] {
+            let records: HashMap<String, String> = connection.hgetall(key(hash))?;
+            let mut matching = Vec::new();
+            for (id, entry) in records {
+                if filter.matches_record(&id, &serde_json::from_str(&entry)?) {
+                    matching.push(id);
+                }
+            }
+            *count = matching.len();
+            if !matching.is_empty() {
+                let _: () = connection.hdel(key(hash), matching)?;
+            }
+        }
+
+        let author_ids: Vec<String> = connection.hkeys(key("author_papers"))?;
+        let matching: Vec<_> = author_ids
+            .into_iter()
+            .filter(|author_id| filter.matches_author_papers(author_id))
+            .collect();
+        if !matching.is_empty() {
+            let _: () = connection.hdel(key("author_papers"), matching)?;
+        }
+
+        if filter.matches_embeddings() {
+            let _: () = connection.del(key("embeddings"))?;
+        }
+
+        Ok(cleared)
+    }
+
+    /// Responses expire through Redis, so only queries are swept.
+    fn purge_expired(&self) -> Result<usize> {
+        let mut connection = self.connection()?;
+        let entries: HashMap<String, String> = connection.hgetall(key("queries"))?;
+
+        let mut expired = Vec::new();
+        for (id, entry) in entries {
+            let entry: CacheEntry<Query> = serde_json::from_str(&entry)?;
+            if self.is_expired(entry.created_at) {
+                expired.push(id);
+            }
+        }
+
+        if !expired.is_empty() {
+            let _: () = connection.hdel(key("queries"), &expired)?;
+        }
+        Ok(expired.len())
+    }
+}
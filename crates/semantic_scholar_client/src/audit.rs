@@ -0,0 +1,95 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::Path,
+    sync::{Mutex, OnceLock},
+    time::Duration,
+};
+
+use anyhow::{Result, anyhow};
+use serde_json::json;
+
+/// Query parameters masked in audited URLs, matched by substring.
+const SECRET_PARAMETERS: &[&str] = &["key", "token", "secret", "password"];
+
+static AUDIT_LOG: OnceLock<Mutex<File>> = OnceLock::new();
+
+/// Starts recording every request sent upstream to `path`, one JSON object
+/// per line, for institutions that must account for their API usage. Entries
+/// are appended, so the log survives restarts.
+pub fn set_audit_log(path: &Path) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+
+    AUDIT_LOG
+        .set(Mutex::new(file))
+        .map_err(|_| anyhow!("Audit log is already set"))
+}
+
+/// One request sent upstream. Credentials never reach the log: keys sent in
+/// headers are only noted as present, and secret-looking query parameters
+/// are masked.
+pub struct AuditEntry<'a> {
+    pub method: &'a str,
+    pub url: &'a str,
+    /// The HTTP status, or the error that kept the request from completing
+    pub outcome: std::result::Result<u16, String>,
+    pub duration: Duration,
+    /// How many times the request had been retried before this attempt
+    pub retries: u32,
+    pub authenticated: bool,
+}
+
+/// Appends an entry to the audit log, if one was set.
+pub fn record_audit(entry: AuditEntry) {
+    let Some(log) = AUDIT_LOG.get() else {
+        return;
+    };
+
+    let (status, error) = match entry.outcome {
+        Ok(status) => (Some(status), None),
+        Err(error) => (None, Some(error)),
+    };
+    let line = json!({
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "method": entry.method,
+        "url": redact_url(entry.url),
+        "status": status,
+        "error": error,
+        "durationMs": entry.duration.as_millis() as u64,
+        "retries": entry.retries,
+        "authenticated": entry.authenticated,
+    });
+
+    let Ok(mut file) = log.lock() else {
+        return;
+    };
+    if let Err(err) = writeln!(file, "{}", line) {
+        tracing::warn!("Failed to write to the audit log: {}", err);
+    }
+}
+
+fn redact_url(url: &str) -> String {
+    let Some((base, query)) = url.split_once('?') else {
+        return url.to_string();
+    };
+
+    let query = query
+        .split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some((name, _))
+                if SECRET_PARAMETERS
+                    .iter()
+                    .any(|secret| name.to_lowercase().contains(secret)) =>
+            {
+                format!("{}=REDACTED", name)
+            }
+            _ => pair.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("&");
+
+    format!("{}?{}", base, query)
+}
@@ -0,0 +1,154 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use cache::Cache;
+use http_client::HttpClient;
+use models::{Citation, Page, Paper, Recommendations};
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value, json};
+
+use crate::{
+    error::Error,
+    ids::normalize_paper_id,
+    rate_limit::RateLimiter,
+    request::{encode_path_segment, make_request},
+    settings::api_base_url,
+};
+
+/// Typed access to the Semantic Scholar API for applications that don't go
+/// through the MCP server. Requests share the rate limits, retries, circuit
+/// breaker and response cache the server's tools use.
+pub struct SemanticScholarClient {
+    http_client: Arc<dyn HttpClient>,
+    rate_limiter: Arc<RateLimiter>,
+    cache: Option<Arc<dyn Cache>>,
+}
+
+impl SemanticScholarClient {
+    /// Builds a client with the rate limits configured by the environment and
+    /// no cache.
+    pub fn new(http_client: Arc<dyn HttpClient>) -> Self {
+        Self {
+            http_client,
+            rate_limiter: Arc::new(RateLimiter::from_env()),
+            cache: None,
+        }
+    }
+
+    /// Shares a rate limiter with other clients, so they draw from the same
+    /// quota.
+    pub fn with_rate_limiter(mut self, rate_limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = rate_limiter;
+        self
+    }
+
+    /// Serves fresh responses from `cache` and stores the papers and authors
+    /// found in every response.
+    pub fn with_cache(mut self, cache: Arc<dyn Cache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    pub fn rate_limiter(&self) -> &Arc<RateLimiter> {
+        &self.rate_limiter
+    }
+
+    /// Searches papers by relevance to `query`, returning up to `limit` of
+    /// them with the given fields.
+    pub async fn search_papers(
+        &self,
+        query: &str,
+        fields: &[&str],
+        limit: usize,
+    ) -> Result<Page<Paper>> {
+        let mut params = params(fields);
+        params.insert("query".to_string(), json!(query));
+        params.insert("limit".to_string(), json!(limit));
+
+        self.get("/paper/search", Value::Object(params), None).await
+    }
+
+    /// Fetches a paper by its Semantic Scholar ID or a prefixed external ID,
+    /// such as `DOI:10.1145/...` or `ARXIV:1706.03762`.
+    pub async fn get_paper(&self, paper_id: &str, fields: &[&str]) -> Result<Paper> {
+        let endpoint = format!(
+            "/paper/{}",
+            encode_path_segment(&normalize_paper_id(paper_id))
+        );
+
+        self.get(&endpoint, Value::Object(params(fields)), None)
+            .await
+    }
+
+    /// Fetches a page of the papers citing a paper.
+    pub async fn get_citations(
+        &self,
+        paper_id: &str,
+        fields: &[&str],
+        offset: usize,
+        limit: usize,
+    ) -> Result<Page<Citation>> {
+        let endpoint = format!(
+            "/paper/{}/citations",
+            encode_path_segment(&normalize_paper_id(paper_id))
+        );
+        let mut params = params(fields);
+        params.insert("offset".to_string(), json!(offset));
+        params.insert("limit".to_string(), json!(limit));
+
+        self.get(&endpoint, Value::Object(params), None).await
+    }
+
+    /// Recommends up to `limit` recent papers similar to a paper.
+    pub async fn recommendations(
+        &self,
+        paper_id: &str,
+        fields: &[&str],
+        limit: usize,
+    ) -> Result<Recommendations> {
+        let endpoint = format!(
+            "/recommendations/v1/papers/forpaper/{}",
+            encode_path_segment(&normalize_paper_id(paper_id))
+        );
+        let mut params = params(fields);
+        params.insert("limit".to_string(), json!(limit));
+
+        self.get(&endpoint, Value::Object(params), Some(&api_base_url()))
+            .await
+    }
+
+    async fn get<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        params: Value,
+        base_url: Option<&str>,
+    ) -> Result<T> {
+        let response = make_request(
+            &self.http_client,
+            &self.rate_limiter,
+            self.cache.as_deref(),
+            endpoint,
+            Some(&params),
+            None,
+            base_url,
+        )
+        .await?;
+
+        serde_json::from_value(response).map_err(|err| {
+            Error::UpstreamError(format!(
+                "Failed to parse response from {}: {}",
+                endpoint, err
+            ))
+            .into()
+        })
+    }
+}
+
+/// Query parameters asking for `fields`, or the API's defaults when empty.
+fn params(fields: &[&str]) -> Map<String, Value> {
+    let mut params = Map::new();
+    if !fields.is_empty() {
+        params.insert("fields".to_string(), json!(fields));
+    }
+    params
+}
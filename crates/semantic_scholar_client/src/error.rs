@@ -0,0 +1,56 @@
+use std::time::Duration;
+
+/// The kinds of failure a request can report. Functions still return
+/// `anyhow::Result`, so these travel as the root cause of an `anyhow::Error`
+/// and are recovered with `downcast_ref`.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// Semantic Scholar kept answering 429 after every retry, or asked to
+    /// wait longer than a request holds on for.
+    #[error("{message}")]
+    RateLimited {
+        message: String,
+        retry_after: Option<Duration>,
+    },
+    /// The requested paper, author or dataset does not exist.
+    #[error("Resource not found: {0}")]
+    NotFound(String),
+    /// The request was made with arguments it can't act on.
+    #[error("{0}")]
+    InvalidArgument(String),
+    /// Semantic Scholar failed, was unreachable, or returned something unexpected.
+    #[error("{0}")]
+    UpstreamError(String),
+    /// Reading from or writing to the local cache failed.
+    #[error("Cache error: {0}")]
+    CacheError(String),
+    /// Generating an embedding for the semantic cache failed.
+    #[error("Embedding error: {0}")]
+    EmbedError(String),
+    /// The server is offline and nothing cached answers the request.
+    #[error("Not available offline: {0}")]
+    NotCached(String),
+}
+
+impl Error {
+    pub fn cache(err: anyhow::Error) -> Self {
+        Error::CacheError(err.to_string())
+    }
+
+    pub fn embed(err: anyhow::Error) -> Self {
+        Error::EmbedError(err.to_string())
+    }
+
+    /// A stable name for the error kind, for clients that branch on it.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::RateLimited { .. } => "rate_limited",
+            Error::NotFound(_) => "not_found",
+            Error::InvalidArgument(_) => "invalid_argument",
+            Error::UpstreamError(_) => "upstream_error",
+            Error::CacheError(_) => "cache_error",
+            Error::EmbedError(_) => "embed_error",
+            Error::NotCached(_) => "not_cached",
+        }
+    }
+}
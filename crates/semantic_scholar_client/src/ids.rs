@@ -0,0 +1,192 @@
+/// Prefixes the Graph API accepts in front of external paper identifiers.
+const PAPER_ID_PREFIXES: &[&str] = &[
+    "CorpusId", "DOI", "ARXIV", "MAG", "ACL", "PMID", "PMCID", "URL",
+];
+
+/// Turns whatever identifier a user has for a paper into one the Graph API
+/// accepts. Prefixes are written the way the API expects them, so
+/// `doi:10.1145/...` and `DOI:10.1145/...` are treated the same, and bare
+/// DOIs and arXiv IDs get theirs. arXiv IDs lose their version however they
+/// are written. Links to doi.org, arxiv.org,
+/// semanticscholar.org, PubMed, PubMed Central and the ACL Anthology become
+/// the ID they point at; other links are passed on as `URL:`. Anything else
+/// is only trimmed.
+pub fn normalize_paper_id(paper_id: &str) -> String {
+    let paper_id = paper_id.trim();
+
+    if let Some((prefix, id)) = paper_id.split_once(':') {
+        if let Some(canonical) = PAPER_ID_PREFIXES
+            .iter()
+            .find(|canonical| canonical.eq_ignore_ascii_case(prefix.trim()))
+        {
+            let id = id.trim();
+            return match *canonical {
+                // What follows is tidied like a bare DOI or arXiv ID, so a
+                // version or a link is dropped the same way
+                "DOI" | "ARXIV" => {
+                    let bare = normalize_paper_id(id);
+                    match bare.split_once(':') {
+                        Some((prefix, _)) if prefix == *canonical => bare,
+                        _ if *canonical == "ARXIV" => {
+                            format!("ARXIV:{}", strip_arxiv_version(id))
+                        }
+                        _ => format!("DOI:{}", id),
+                    }
+                }
+                _ => format!("{}:{}", canonical, id),
+            };
+        }
+    }
+
+    if let Some((host, path)) = split_url(paper_id) {
+        if let Some(id) = paper_id_from_url(&host, path) {
+            return id;
+        }
+        if paper_id.contains("://") {
+            return format!("URL:{}", paper_id);
+        }
+    }
+
+    if is_doi(paper_id) {
+        return format!("DOI:{}", paper_id);
+    }
+
+    if is_arxiv_id(paper_id) {
+        return format!("ARXIV:{}", strip_arxiv_version(paper_id));
+    }
+
+    paper_id.to_string()
+}
+
+/// Trims an author identifier, taking the ID out of a semanticscholar.org
+/// author link.
+pub fn normalize_author_id(author_id: &str) -> String {
+    let author_id = author_id.trim();
+
+    if let Some((host, path)) = split_url(author_id) {
+        if host.ends_with("semanticscholar.org") && path.starts_with("author/") {
+            if let Some(id) = path.rsplit('/').find(|segment| is_digits(segment)) {
+                return id.to_string();
+            }
+        }
+    }
+
+    author_id.to_string()
+}
+
+/// Splits a link into its lowercase host, without `www.`, and its path,
+/// without query, fragment or slashes around it. The scheme may be left out.
+fn split_url(url: &str) -> Option<(String, &str)> {
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+        .unwrap_or(url);
+    let rest = rest.split(['?', '#']).next().unwrap_or_default();
+    let (host, path) = rest.split_once('/')?;
+    let host = host.to_ascii_lowercase();
+    let host = host.strip_prefix("www.").unwrap_or(&host).to_string();
+
+    // A bare DOI such as 10.1145/3292500 looks like a host and path too
+    if !host.contains('.') || host.starts_with("10.") {
+        return None;
+    }
+
+    Some((host, path.trim_matches('/')))
+}
+
+/// The paper a link points at, for the sites whose links carry an ID.
+fn paper_id_from_url(host: &str, path: &str) -> Option<String> {
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+
+    match host {
+        "doi.org" | "dx.doi.org" => {
+            let doi = urlencoding::decode(path).ok()?;
+            is_doi(&doi).then(|| format!("DOI:{}", doi))
+        }
+        "arxiv.org" | "export.arxiv.org" => {
+            let (_, id) = path.split_once('/')?;
+            let id = id.strip_suffix(".pdf").unwrap_or(id);
+            is_arxiv_id(id).then(|| format!("ARXIV:{}", strip_arxiv_version(id)))
+        }
+        "semanticscholar.org" | "api.semanticscholar.org" => match segments.as_slice() {
+            ["arxiv", id @ ..] => {
+                let id = id.join("/");
+                is_arxiv_id(&id).then(|| format!("ARXIV:{}", strip_arxiv_version(&id)))
+            }
+            ["paper", .., id] if is_paper_sha(id) => Some(id.to_string()),
+            _ => None,
+        },
+        "pubmed.ncbi.nlm.nih.gov" => match segments.as_slice() {
+            [pmid, ..] if is_digits(pmid) => Some(format!("PMID:{}", pmid)),
+            _ => None,
+        },
+        "ncbi.nlm.nih.gov" | "pmc.ncbi.nlm.nih.gov" => match segments.as_slice() {
+            ["pubmed", pmid, ..] if is_digits(pmid) => Some(format!("PMID:{}", pmid)),
+            ["pmc", "articles", pmcid, ..] | ["articles", pmcid, ..] => {
+                let number = pmcid
+                    .strip_prefix("PMC")
+                    .or_else(|| pmcid.strip_prefix("pmc"))?;
+                is_digits(number).then(|| format!("PMCID:{}", number))
+            }
+            _ => None,
+        },
+        "aclanthology.org" | "aclweb.org" => {
+            let id = segments.last()?;
+            let id = id.strip_suffix(".pdf").unwrap_or(id);
+            (id.contains('-') || id.contains('.')).then(|| format!("ACL:{}", id))
+        }
+        _ => None,
+    }
+}
+
+fn is_digits(value: &str) -> bool {
+    !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Whether a value is a Semantic Scholar paper ID, a 40 character SHA.
+fn is_paper_sha(value: &str) -> bool {
+    value.len() == 40 && value.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Whether a value is a DOI: `10.`, a registrant code, a slash and a suffix.
+fn is_doi(value: &str) -> bool {
+    let Some((registrant, suffix)) = value.split_once('/') else {
+        return false;
+    };
+    registrant
+        .strip_prefix("10.")
+        .is_some_and(|code| code.split('.').all(is_digits))
+        && !suffix.is_empty()
+        && !value.contains(char::is_whitespace)
+}
+
+/// Whether a value is an arXiv ID, either `2101.00001` or the older
+/// `hep-th/9901001`, with or without a version.
+fn is_arxiv_id(value: &str) -> bool {
+    let id = strip_arxiv_version(value);
+
+    if let Some((yymm, number)) = id.split_once('.') {
+        if yymm.len() == 4 && is_digits(yymm) && (4..=5).contains(&number.len()) {
+            return is_digits(number);
+        }
+    }
+
+    if let Some((archive, number)) = id.split_once('/') {
+        let archive = archive.split('.').next().unwrap_or(archive);
+        return !archive.is_empty()
+            && archive.bytes().all(|b| b.is_ascii_lowercase() || b == b'-')
+            && number.len() == 7
+            && is_digits(number);
+    }
+
+    false
+}
+
+/// Drops a trailing version such as `v2`, as the API knows papers by their
+/// unversioned ID.
+fn strip_arxiv_version(id: &str) -> &str {
+    match id.rsplit_once('v') {
+        Some((base, version)) if is_digits(version) && !base.is_empty() => base,
+        _ => id,
+    }
+}
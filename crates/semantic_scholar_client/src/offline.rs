@@ -0,0 +1,28 @@
+use std::{cell::Cell, future::Future};
+
+use chrono::NaiveDateTime;
+
+tokio::task_local! {
+    static CACHED_AT: Cell<Option<NaiveDateTime>>;
+}
+
+/// Runs a request handler, returning along with its output when the oldest
+/// cached data it was answered from was stored.
+pub async fn track_cache_age<F: Future>(handler: F) -> (F::Output, Option<NaiveDateTime>) {
+    CACHED_AT
+        .scope(Cell::new(None), async {
+            let output = handler.await;
+            (output, CACHED_AT.with(Cell::get))
+        })
+        .await
+}
+
+/// Records that the current request was answered from data cached at
+/// `cached_at`. Does nothing outside of [`track_cache_age`].
+pub fn served_from_cache(cached_at: NaiveDateTime) {
+    let _ = CACHED_AT.try_with(|oldest| {
+        if oldest.get().is_none_or(|oldest| cached_at < oldest) {
+            oldest.set(Some(cached_at));
+        }
+    });
+}
@@ -0,0 +1,848 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    time::Duration,
+};
+
+use anyhow::{Result, anyhow};
+use futures::{StreamExt, lock::Mutex, stream};
+use serde::Deserialize;
+use tokio::time::{Instant, sleep};
+
+use crate::error::Error;
+
+/// Groups of endpoints that share a quota in the Semantic Scholar API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RateClass {
+    /// `/paper/search*` and `/author/search`
+    Search,
+    /// `/paper/batch` and `/author/batch`
+    Batch,
+    /// The recommendations API
+    Recommendations,
+    /// The datasets API
+    Datasets,
+    /// Every other graph API endpoint
+    Graph,
+}
+
+impl RateClass {
+    pub const ALL: [RateClass; 5] = [
+        Self::Search,
+        Self::Batch,
+        Self::Recommendations,
+        Self::Datasets,
+        Self::Graph,
+    ];
+
+    pub fn for_endpoint(endpoint: &str, base_url: &str) -> Self {
+        if base_url.contains("/datasets/") {
+            return Self::Datasets;
+        }
+
+        if endpoint.starts_with("/recommendations/") {
+            return Self::Recommendations;
+        }
+
+        match endpoint {
+            "/paper/batch" | "/author/batch" => Self::Batch,
+            "/author/search" => Self::Search,
+            _ if endpoint.starts_with("/paper/search") => Self::Search,
+            _ => Self::Graph,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Search => "SEARCH",
+            Self::Batch => "BATCH",
+            Self::Recommendations => "RECOMMENDATIONS",
+            Self::Datasets => "DATASETS",
+            Self::Graph => "GRAPH",
+        }
+    }
+
+    /// The default limit for this class, following the published quotas.
+    /// Unauthenticated calls draw from a pool shared by every anonymous user,
+    /// so they are kept at one request per second throughout.
+    fn default_limit(&self, authenticated: bool) -> RateLimit {
+        match (self, authenticated) {
+            (Self::Graph | Self::Datasets, true) => RateLimit::new(10.0, 1.0),
+            _ => RateLimit::new(1.0, 1.0),
+        }
+    }
+}
+
+/// Refill rate and burst size of a token bucket.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    /// Tokens added per second
+    pub requests_per_second: f64,
+    /// Maximum number of tokens the bucket holds
+    pub burst: f64,
+}
+
+impl RateLimit {
+    /// The slowest refill allowed, one request an hour, so the wait for a
+    /// token stays within what a timer can represent.
+    const MIN_REQUESTS_PER_SECOND: f64 = 1.0 / 3600.0;
+
+    pub fn new(requests_per_second: f64, burst: f64) -> Self {
+        Self {
+            requests_per_second: requests_per_second.max(Self::MIN_REQUESTS_PER_SECOND),
+            burst: burst.max(1.0),
+        }
+    }
+}
+
+enum CircuitState {
+    Closed { consecutive_failures: u32 },
+    Open { until: Instant },
+    HalfOpen { probing_since: Instant },
+}
+
+/// Stops calls to the API for a cooldown period after repeated server errors or
+/// network failures, so an outage fails fast instead of retrying every request.
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    cooldown: Duration,
+    state: std::sync::Mutex<CircuitState>,
+}
+
+impl CircuitBreaker {
+    /// The longest requests are paused for, a day.
+    pub const MAX_COOLDOWN: Duration = Duration::from_secs(24 * 60 * 60);
+
+    pub fn new(failure_threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            cooldown: cooldown.min(Self::MAX_COOLDOWN),
+            state: std::sync::Mutex::new(CircuitState::Closed {
+                consecutive_failures: 0,
+            }),
+        }
+    }
+
+    /// Fails while the circuit is open. Once the cooldown has elapsed a single
+    /// trial request is let through to probe whether the API has recovered,
+    /// and the others keep failing until its outcome is recorded. A probe
+    /// that records nothing within a cooldown, because it was cancelled, is
+    /// given up on and another one let through.
+    pub fn check(&self) -> Result<()> {
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|_| anyhow!("Circuit breaker poisoned"))?;
+
+        match *state {
+            CircuitState::Open { until } => {
+                let now = Instant::now();
+                if now < until {
+                    return Err(Error::UpstreamError(format!(
+                        "Semantic Scholar API is unavailable after repeated failures; requests are paused for another {}s",
+                        (until - now).as_secs() + 1
+                    ))
+                    .into());
+                }
+                tracing::info!("Circuit breaker cooldown elapsed, probing Semantic Scholar API");
+                *state = CircuitState::HalfOpen { probing_since: now };
+                Ok(())
+            }
+            CircuitState::HalfOpen { probing_since } => {
+                let now = Instant::now();
+                if now.duration_since(probing_since) < self.cooldown {
+                    return Err(Error::UpstreamError(String::from(
+                        "Semantic Scholar API is unavailable after repeated failures; requests are paused until a trial request succeeds",
+                    ))
+                    .into());
+                }
+                tracing::info!("Circuit breaker probe went unanswered, probing again");
+                *state = CircuitState::HalfOpen { probing_since: now };
+                Ok(())
+            }
+            CircuitState::Closed { .. } => Ok(()),
+        }
+    }
+
+    /// Describes the current state, for status reporting.
+    pub fn status(&self) -> String {
+        let Ok(state) = self.state.lock() else {
+            return String::from("unknown");
+        };
+
+        match *state {
+            CircuitState::Closed {
+                consecutive_failures: 0,
+            } => String::from("closed"),
+            CircuitState::Closed {
+                consecutive_failures,
+            } => format!(
+                "closed ({} of {} consecutive failures before opening)",
+                consecutive_failures, self.failure_threshold
+            ),
+            CircuitState::Open { until } => {
+                let remaining = until.saturating_duration_since(Instant::now());
+                format!(
+                    "open, requests paused for another {}s",
+                    remaining.as_secs() + 1
+                )
+            }
+            CircuitState::HalfOpen { .. } => String::from("half-open, probing for recovery"),
+        }
+    }
+
+    pub fn record_success(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            if matches!(*state, CircuitState::HalfOpen { .. }) {
+                tracing::info!("Semantic Scholar API recovered, closing circuit breaker");
+            }
+            *state = CircuitState::Closed {
+                consecutive_failures: 0,
+            };
+        }
+    }
+
+    /// Records an upstream failure, returning true if the circuit is now open.
+    pub fn record_failure(&self) -> bool {
+        let Ok(mut state) = self.state.lock() else {
+            return false;
+        };
+
+        let trip = match *state {
+            CircuitState::Closed {
+                consecutive_failures,
+            } => consecutive_failures + 1 >= self.failure_threshold,
+            CircuitState::HalfOpen { .. } => true,
+            CircuitState::Open { .. } => return true,
+        };
+
+        if trip {
+            tracing::warn!(
+                "Semantic Scholar API is failing, pausing requests for {}s",
+                self.cooldown.as_secs()
+            );
+            *state = CircuitState::Open {
+                until: Instant::now() + self.cooldown,
+            };
+        } else if let CircuitState::Closed {
+            consecutive_failures,
+        } = &mut *state
+        {
+            *consecutive_failures += 1;
+        }
+
+        trip
+    }
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self::new(5, Duration::from_secs(30))
+    }
+}
+
+/// Counters describing how the API has responded since startup.
+#[derive(Default)]
+pub struct RequestStats {
+    inner: std::sync::Mutex<RequestStatsInner>,
+}
+
+#[derive(Default)]
+struct RequestStatsInner {
+    requests: u64,
+    cached: u64,
+    rate_limited: u64,
+    server_errors: u64,
+    network_errors: u64,
+    recent: VecDeque<Instant>,
+    last_rate_limited: Option<Instant>,
+    backoff_until: Option<Instant>,
+}
+
+/// A point-in-time copy of [`RequestStats`].
+pub struct RequestStatsSnapshot {
+    pub requests: u64,
+    pub requests_last_minute: usize,
+    pub cached: u64,
+    pub rate_limited: u64,
+    pub server_errors: u64,
+    pub network_errors: u64,
+    pub last_rate_limited: Option<Duration>,
+    pub backoff_remaining: Option<Duration>,
+}
+
+impl RequestStats {
+    const WINDOW: Duration = Duration::from_secs(60);
+
+    fn update(&self, f: impl FnOnce(&mut RequestStatsInner)) {
+        if let Ok(mut inner) = self.inner.lock() {
+            f(&mut inner);
+        }
+    }
+
+    pub fn record_request(&self) {
+        self.update(|inner| {
+            let now = Instant::now();
+            inner.requests += 1;
+            inner.recent.push_back(now);
+            while inner
+                .recent
+                .front()
+                .is_some_and(|sent| now.duration_since(*sent) > Self::WINDOW)
+            {
+                inner.recent.pop_front();
+            }
+        });
+    }
+
+    pub fn record_cached(&self) {
+        self.update(|inner| inner.cached += 1);
+    }
+
+    pub fn record_rate_limited(&self) {
+        self.update(|inner| {
+            inner.rate_limited += 1;
+            inner.last_rate_limited = Some(Instant::now());
+        });
+    }
+
+    pub fn record_server_error(&self) {
+        self.update(|inner| inner.server_errors += 1);
+    }
+
+    pub fn record_network_error(&self) {
+        self.update(|inner| inner.network_errors += 1);
+    }
+
+    pub fn record_backoff(&self, wait: Duration) {
+        self.update(|inner| inner.backoff_until = Some(Instant::now() + wait));
+    }
+
+    pub fn snapshot(&self) -> RequestStatsSnapshot {
+        let now = Instant::now();
+        let inner = self.inner.lock().ok();
+        let inner = inner.as_deref();
+
+        RequestStatsSnapshot {
+            requests: inner.map_or(0, |inner| inner.requests),
+            requests_last_minute: inner.map_or(0, |inner| {
+                inner
+                    .recent
+                    .iter()
+                    .filter(|sent| now.duration_since(**sent) <= Self::WINDOW)
+                    .count()
+            }),
+            cached: inner.map_or(0, |inner| inner.cached),
+            rate_limited: inner.map_or(0, |inner| inner.rate_limited),
+            server_errors: inner.map_or(0, |inner| inner.server_errors),
+            network_errors: inner.map_or(0, |inner| inner.network_errors),
+            last_rate_limited: inner
+                .and_then(|inner| inner.last_rate_limited)
+                .map(|at| now.duration_since(at)),
+            backoff_remaining: inner
+                .and_then(|inner| inner.backoff_until)
+                .filter(|until| *until > now)
+                .map(|until| until - now),
+        }
+    }
+}
+
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+/// Rate limits from a configuration file, keyed like the environment
+/// variables read by [`RateLimiter::from_env`], which take precedence.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitSettings {
+    pub requests_per_second: Option<f64>,
+    pub burst: Option<f64>,
+    /// Limits for one class, such as `search` or `recommendations`
+    pub classes: HashMap<String, ClassRateLimitSettings>,
+    pub circuit_breaker_threshold: Option<u32>,
+    pub circuit_breaker_cooldown_secs: Option<f64>,
+    /// Requests a tool fetching many pages keeps in flight at once
+    pub max_concurrency: Option<usize>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClassRateLimitSettings {
+    pub requests_per_second: Option<f64>,
+    pub burst: Option<f64>,
+}
+
+impl RateLimitSettings {
+    /// Checks every value the way those read from the environment are, so a
+    /// typo fails at startup rather than in the first throttled request.
+    pub fn validate(&self) -> Result<()> {
+        let check = |name: &str, value: Option<f64>, kind: SettingKind| match value {
+            Some(value) if !kind.accepts(value) => Err(anyhow!(
+                "rate_limit.{} must be {}, got {}",
+                name,
+                kind.describe(),
+                value
+            )),
+            _ => Ok(()),
+        };
+
+        check(
+            "requests_per_second",
+            self.requests_per_second,
+            SettingKind::Positive,
+        )?;
+        check("burst", self.burst, SettingKind::Positive)?;
+        check(
+            "circuit_breaker_threshold",
+            self.circuit_breaker_threshold.map(f64::from),
+            SettingKind::Count,
+        )?;
+        check(
+            "circuit_breaker_cooldown_secs",
+            self.circuit_breaker_cooldown_secs,
+            SettingKind::Cooldown,
+        )?;
+        check(
+            "max_concurrency",
+            self.max_concurrency.map(|n| n as f64),
+            SettingKind::Count,
+        )?;
+        for (class, limit) in &self.classes {
+            check(
+                &format!("classes.{}.requests_per_second", class),
+                limit.requests_per_second,
+                SettingKind::Positive,
+            )?;
+            check(
+                &format!("classes.{}.burst", class),
+                limit.burst,
+                SettingKind::Positive,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The values a rate limit setting can take.
+#[derive(Clone, Copy)]
+enum SettingKind {
+    Positive,
+    /// A whole number that fits in a `u32`
+    Count,
+    /// Seconds, up to [`CircuitBreaker::MAX_COOLDOWN`]
+    Cooldown,
+}
+
+impl SettingKind {
+    fn accepts(&self, value: f64) -> bool {
+        let positive = value > 0.0 && value.is_finite();
+        match self {
+            Self::Positive => positive,
+            Self::Count => positive && value.fract() == 0.0 && value <= f64::from(u32::MAX),
+            Self::Cooldown => positive && value <= CircuitBreaker::MAX_COOLDOWN.as_secs_f64(),
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Self::Positive => String::from("a positive number"),
+            Self::Count => format!("a whole number from 1 to {}", u32::MAX),
+            Self::Cooldown => format!(
+                "a positive number of seconds up to {}",
+                CircuitBreaker::MAX_COOLDOWN.as_secs()
+            ),
+        }
+    }
+}
+
+/// Requests kept in flight at once by [`RateLimiter::concurrently`] unless
+/// configured otherwise.
+const DEFAULT_MAX_CONCURRENCY: usize = 4;
+
+pub struct RateLimiter {
+    limits: HashMap<(RateClass, bool), RateLimit>,
+    buckets: Mutex<HashMap<RateClass, Bucket>>,
+    circuit_breaker: CircuitBreaker,
+    stats: RequestStats,
+    max_concurrency: usize,
+}
+
+impl RateLimiter {
+    pub fn new() -> Self {
+        Self {
+            limits: HashMap::new(),
+            buckets: Mutex::new(HashMap::new()),
+            circuit_breaker: CircuitBreaker::default(),
+            stats: RequestStats::default(),
+            max_concurrency: DEFAULT_MAX_CONCURRENCY,
+        }
+    }
+
+    /// Builds a limiter using the default quotas, overridden by the
+    /// `SEMANTIC_SCHOLAR_RATE_LIMIT_RPS` and `SEMANTIC_SCHOLAR_RATE_LIMIT_BURST`
+    /// environment variables for every class, or by their per-class variants
+    /// such as `SEMANTIC_SCHOLAR_RATE_LIMIT_SEARCH_RPS`. The circuit breaker is
+    /// configured by `SEMANTIC_SCHOLAR_CIRCUIT_BREAKER_THRESHOLD` and
+    /// `SEMANTIC_SCHOLAR_CIRCUIT_BREAKER_COOLDOWN_SECS`, and the requests kept
+    /// in flight at once by `SEMANTIC_SCHOLAR_MAX_CONCURRENCY`.
+    pub fn from_env() -> Self {
+        Self::from_settings(&RateLimitSettings::default())
+    }
+
+    /// Like [`RateLimiter::from_env`], with the settings of a configuration
+    /// file in place of the defaults. Environment variables still win.
+    pub fn from_settings(settings: &RateLimitSettings) -> Self {
+        let read = |name: &str, configured: Option<f64>, kind: SettingKind| -> Option<f64> {
+            let configured = configured.filter(|value| kind.accepts(*value));
+            let Ok(value) = std::env::var(name) else {
+                return configured;
+            };
+            match value.trim().parse::<f64>() {
+                Ok(value) if kind.accepts(value) => Some(value),
+                _ => {
+                    tracing::warn!(
+                        "Ignoring invalid value for {}, which must be {}: {}",
+                        name,
+                        kind.describe(),
+                        value
+                    );
+                    configured
+                }
+            }
+        };
+
+        for class in settings.classes.keys() {
+            if !RateClass::ALL
+                .iter()
+                .any(|known| known.name().eq_ignore_ascii_case(class))
+            {
+                tracing::warn!("Ignoring rate limit for unknown class {}", class);
+            }
+        }
+
+        let rps = read(
+            "SEMANTIC_SCHOLAR_RATE_LIMIT_RPS",
+            settings.requests_per_second,
+            SettingKind::Positive,
+        );
+        let burst = read(
+            "SEMANTIC_SCHOLAR_RATE_LIMIT_BURST",
+            settings.burst,
+            SettingKind::Positive,
+        );
+
+        let default_breaker = CircuitBreaker::default();
+        let mut limiter = Self::new().with_circuit_breaker(CircuitBreaker::new(
+            read(
+                "SEMANTIC_SCHOLAR_CIRCUIT_BREAKER_THRESHOLD",
+                settings.circuit_breaker_threshold.map(f64::from),
+                SettingKind::Count,
+            )
+            .map(|threshold| threshold as u32)
+            .unwrap_or(default_breaker.failure_threshold),
+            read(
+                "SEMANTIC_SCHOLAR_CIRCUIT_BREAKER_COOLDOWN_SECS",
+                settings.circuit_breaker_cooldown_secs,
+                SettingKind::Cooldown,
+            )
+            .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+            .unwrap_or(default_breaker.cooldown),
+        ));
+
+        if let Some(max_concurrency) = read(
+            "SEMANTIC_SCHOLAR_MAX_CONCURRENCY",
+            settings.max_concurrency.map(|n| n as f64),
+            SettingKind::Count,
+        ) {
+            limiter = limiter.with_max_concurrency(max_concurrency as usize);
+        }
+
+        for class in RateClass::ALL {
+            let configured = settings
+                .classes
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(class.name()))
+                .map(|(_, limit)| limit.clone())
+                .unwrap_or_default();
+            let class_rps = read(
+                &format!("SEMANTIC_SCHOLAR_RATE_LIMIT_{}_RPS", class.name()),
+                configured.requests_per_second,
+                SettingKind::Positive,
+            )
+            .or(rps);
+            let class_burst = read(
+                &format!("SEMANTIC_SCHOLAR_RATE_LIMIT_{}_BURST", class.name()),
+                configured.burst,
+                SettingKind::Positive,
+            )
+            .or(burst);
+
+            if class_rps.is_none() && class_burst.is_none() {
+                continue;
+            }
+
+            for authenticated in [true, false] {
+                let default = class.default_limit(authenticated);
+                limiter = limiter.with_limit(
+                    class,
+                    authenticated,
+                    RateLimit::new(
+                        class_rps.unwrap_or(default.requests_per_second),
+                        class_burst.unwrap_or(default.burst),
+                    ),
+                );
+            }
+        }
+
+        limiter
+    }
+
+    /// Overrides the token bucket used for a rate class.
+    pub fn with_limit(mut self, class: RateClass, authenticated: bool, limit: RateLimit) -> Self {
+        self.limits.insert((class, authenticated), limit);
+        self
+    }
+
+    pub fn with_circuit_breaker(mut self, circuit_breaker: CircuitBreaker) -> Self {
+        self.circuit_breaker = circuit_breaker;
+        self
+    }
+
+    /// Keeps at most this many requests in flight in
+    /// [`RateLimiter::concurrently`]. One fetches strictly in turn.
+    pub fn with_max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = max_concurrency.max(1);
+        self
+    }
+
+    pub fn max_concurrency(&self) -> usize {
+        self.max_concurrency
+    }
+
+    pub fn circuit_breaker(&self) -> &CircuitBreaker {
+        &self.circuit_breaker
+    }
+
+    pub fn stats(&self) -> &RequestStats {
+        &self.stats
+    }
+
+    pub fn limit(&self, class: RateClass, authenticated: bool) -> RateLimit {
+        self.limits
+            .get(&(class, authenticated))
+            .copied()
+            .unwrap_or_else(|| class.default_limit(authenticated))
+    }
+
+    pub async fn acquire(&self, class: RateClass, authenticated: bool) -> Result<()> {
+        let limit = self.limit(class, authenticated);
+
+        // A request told to back off holds back the ones running alongside
+        // it, which would otherwise be answered 429 too
+        if let Some(backoff) = self.stats.snapshot().backoff_remaining {
+            sleep(backoff).await;
+        }
+
+        // Reserve a token while holding the lock, then wait for it outside so
+        // that callers of other classes are not held up
+        let wait = {
+            let mut buckets = self.buckets.lock().await;
+            let now = Instant::now();
+
+            let bucket = buckets.entry(class).or_insert(Bucket {
+                tokens: limit.burst,
+                last_refill: now,
+            });
+
+            let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+            bucket.tokens = (bucket.tokens + elapsed * limit.requests_per_second).min(limit.burst);
+            bucket.last_refill = now;
+            bucket.tokens -= 1.0;
+
+            if bucket.tokens >= 0.0 {
+                None
+            } else {
+                match Duration::try_from_secs_f64(-bucket.tokens / limit.requests_per_second) {
+                    Ok(wait) => Some(wait),
+                    Err(_) => {
+                        // Give the token back, as the request is not sent
+                        bucket.tokens += 1.0;
+                        return Err(anyhow!(
+                            "Rate limit of {} requests per second is too low to wait for",
+                            limit.requests_per_second
+                        ));
+                    }
+                }
+            }
+        };
+
+        if let Some(wait) = wait {
+            sleep(wait).await;
+        }
+
+        Ok(())
+    }
+
+    /// Runs `fetch` for every item, keeping at most
+    /// [`RateLimiter::max_concurrency`] of them in flight, and returns the
+    /// results in the order of the items. Every request still waits for its
+    /// token, so the quotas hold however many run at once.
+    pub async fn concurrently<I, F, Fut>(&self, items: I, fetch: F) -> Vec<Fut::Output>
+    where
+        I: IntoIterator,
+        F: FnMut(I::Item) -> Fut,
+        Fut: Future,
+    {
+        stream::iter(items)
+            .map(fetch)
+            .buffered(self.max_concurrency)
+            .collect()
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_tiny_rate_is_raised_to_the_slowest_allowed() {
+        let limit = RateLimit::new(1e-300, 1.0);
+        assert_eq!(
+            limit.requests_per_second,
+            RateLimit::MIN_REQUESTS_PER_SECOND
+        );
+    }
+
+    #[test]
+    fn settings_out_of_range_are_rejected() {
+        for cooldown in [f64::INFINITY, 1e30, 0.0, -1.0] {
+            let settings = RateLimitSettings {
+                circuit_breaker_cooldown_secs: Some(cooldown),
+                ..Default::default()
+            };
+            assert!(settings.validate().is_err(), "cooldown {}", cooldown);
+        }
+
+        let settings = RateLimitSettings {
+            circuit_breaker_threshold: Some(0),
+            ..Default::default()
+        };
+        assert!(settings.validate().is_err());
+
+        let settings = RateLimitSettings {
+            circuit_breaker_threshold: Some(3),
+            circuit_breaker_cooldown_secs: Some(86_400.0),
+            ..Default::default()
+        };
+        assert!(settings.validate().is_ok());
+
+        assert!(!SettingKind::Count.accepts(2.5));
+        assert!(!SettingKind::Count.accepts(1e30));
+        assert!(SettingKind::Count.accepts(5.0));
+    }
+
+    /// How long acquiring a token for [`RateClass::Graph`] waited.
+    async fn wait_for_token(limiter: &RateLimiter) -> Duration {
+        let start = Instant::now();
+        limiter.acquire(RateClass::Graph, false).await.unwrap();
+        start.elapsed()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn a_bucket_lets_a_burst_through_then_refills_at_its_rate() {
+        let limiter =
+            RateLimiter::new().with_limit(RateClass::Graph, false, RateLimit::new(2.0, 3.0));
+
+        for _ in 0..3 {
+            assert_eq!(wait_for_token(&limiter).await, Duration::ZERO);
+        }
+        // Past the burst, each token takes half a second to come in
+        for _ in 0..2 {
+            let waited = wait_for_token(&limiter).await;
+            assert!(
+                waited >= Duration::from_millis(500) && waited < Duration::from_millis(510),
+                "waited {:?}",
+                waited
+            );
+        }
+
+        // A long pause fills the bucket only up to its burst
+        tokio::time::advance(Duration::from_secs(60)).await;
+        for _ in 0..3 {
+            assert_eq!(wait_for_token(&limiter).await, Duration::ZERO);
+        }
+        assert!(wait_for_token(&limiter).await >= Duration::from_millis(500));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn consecutive_failures_open_the_circuit_for_a_cooldown() {
+        let breaker = CircuitBreaker::new(3, Duration::from_secs(30));
+
+        assert!(!breaker.record_failure());
+        assert!(!breaker.record_failure());
+        assert!(breaker.check().is_ok());
+        // A success starts the count again
+        breaker.record_success();
+        assert!(!breaker.record_failure());
+        assert!(!breaker.record_failure());
+        assert!(breaker.record_failure());
+        assert!(breaker.check().is_err());
+
+        tokio::time::advance(Duration::from_secs(29)).await;
+        assert!(breaker.check().is_err());
+        tokio::time::advance(Duration::from_secs(1)).await;
+        assert!(breaker.check().is_ok());
+        assert_eq!(breaker.status(), "half-open, probing for recovery");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn a_half_open_circuit_lets_one_probe_through() {
+        let breaker = CircuitBreaker::new(1, Duration::from_secs(30));
+        assert!(breaker.record_failure());
+        tokio::time::advance(Duration::from_secs(30)).await;
+
+        assert!(breaker.check().is_ok());
+        assert!(breaker.check().is_err());
+        assert!(breaker.check().is_err());
+
+        breaker.record_success();
+        assert!(breaker.check().is_ok());
+        assert!(breaker.check().is_ok());
+        assert_eq!(breaker.status(), "closed");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn a_failed_probe_opens_the_circuit_again() {
+        let breaker = CircuitBreaker::new(3, Duration::from_secs(30));
+        for _ in 0..3 {
+            breaker.record_failure();
+        }
+        tokio::time::advance(Duration::from_secs(30)).await;
+        assert!(breaker.check().is_ok());
+
+        // One failure is enough while probing
+        assert!(breaker.record_failure());
+        assert!(breaker.check().is_err());
+        tokio::time::advance(Duration::from_secs(29)).await;
+        assert!(breaker.check().is_err());
+        tokio::time::advance(Duration::from_secs(1)).await;
+        assert!(breaker.check().is_ok());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn an_unanswered_probe_is_given_up_after_a_cooldown() {
+        let breaker = CircuitBreaker::new(1, Duration::from_secs(30));
+        assert!(breaker.record_failure());
+        tokio::time::advance(Duration::from_secs(30)).await;
+        assert!(breaker.check().is_ok());
+
+        // The probe never records its outcome, so after another cooldown
+        // the next caller probes in its place
+        tokio::time::advance(Duration::from_secs(29)).await;
+        assert!(breaker.check().is_err());
+        tokio::time::advance(Duration::from_secs(1)).await;
+        assert!(breaker.check().is_ok());
+    }
+}
@@ -0,0 +1,741 @@
+use std::{
+    collections::HashMap,
+    io::Read,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use cache::{Cache, CachedResponse, NegativeKind};
+use flate2::read::{GzDecoder, ZlibDecoder};
+use futures::AsyncReadExt;
+use futures_timer::Delay;
+use http_client::{HttpClient, Request, RequestBuilderExt};
+use serde_json::{Map, Value};
+
+use crate::{
+    audit::{AuditEntry, record_audit},
+    error::Error,
+    offline::served_from_cache,
+    rate_limit::{RateClass, RateLimiter},
+    settings::{api_base_url, is_offline, negative_max_age},
+};
+
+/// The longest a request waits on a Retry-After header before retrying.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(30);
+
+/// Stores every paper and author found in a response, including the ones
+/// nested in citation and reference edges, so they can later be served as
+/// resources. Papers listed for an author are linked to them.
+fn record_response(cache: &dyn Cache, endpoint: &str, body: &Value) {
+    fn paper_ids(papers: &Value) -> Vec<String> {
+        papers
+            .as_array()
+            .map(|papers| {
+                papers
+                    .iter()
+                    .filter_map(|paper| paper.get("paperId").and_then(Value::as_str))
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn collect(
+        value: &Value,
+        papers: &mut Vec<Value>,
+        authors: &mut Vec<Value>,
+        links: &mut Vec<(String, Vec<String>)>,
+    ) {
+        match value {
+            Value::Object(fields) => {
+                if fields.get("paperId").is_some_and(Value::is_string) {
+                    papers.push(value.clone());
+                } else if let Some(author_id) = fields.get("authorId").and_then(Value::as_str) {
+                    authors.push(value.clone());
+                    if let Some(author_papers) = fields.get("papers") {
+                        links.push((author_id.to_string(), paper_ids(author_papers)));
+                    }
+                }
+                fields
+                    .values()
+                    .for_each(|value| collect(value, papers, authors, links));
+            }
+            Value::Array(values) => values
+                .iter()
+                .for_each(|value| collect(value, papers, authors, links)),
+            _ => {}
+        }
+    }
+
+    let mut papers = Vec::new();
+    let mut authors = Vec::new();
+    let mut links = Vec::new();
+    collect(body, &mut papers, &mut authors, &mut links);
+
+    if let Some(author_id) = endpoint
+        .strip_prefix("/author/")
+        .and_then(|path| path.strip_suffix("/papers"))
+    {
+        let author_id = urlencoding::decode(author_id)
+            .map(|id| id.into_owned())
+            .unwrap_or_else(|_| author_id.to_string());
+        links.push((author_id, paper_ids(&body["data"])));
+    }
+
+    let result = (|| -> Result<()> {
+        if !papers.is_empty() {
+            cache.store_papers(&papers)?;
+        }
+        if !authors.is_empty() {
+            cache.store_authors(&authors)?;
+        }
+        for (author_id, paper_ids) in links {
+            if !paper_ids.is_empty() {
+                cache.store_author_papers(&author_id, &paper_ids)?;
+            }
+        }
+        Ok(())
+    })();
+
+    if let Err(err) = result {
+        tracing::warn!("Failed to store papers and authors in cache: {}", err);
+    }
+}
+
+/// Paper fields filled in from the papers seen before. Small ones only, so
+/// enriched results don't grow much.
+const ENRICHED_FIELDS: &[&str] = &[
+    "year",
+    "venue",
+    "url",
+    "citationCount",
+    "influentialCitationCount",
+    "referenceCount",
+];
+
+/// Fills in fields the papers in a response lack with what other responses
+/// said about them, such as the citation count of a paper found by a search
+/// that didn't ask for it. The papers are looked up together, so a page of a
+/// thousand citations is one lookup.
+fn enrich_papers(cache: &dyn Cache, body: &mut Value) -> Result<()> {
+    fn lacks_fields(fields: &Map<String, Value>) -> bool {
+        ENRICHED_FIELDS
+            .iter()
+            .any(|field| fields.get(*field).is_none_or(Value::is_null))
+    }
+
+    fn collect<'a>(value: &'a Value, paper_ids: &mut Vec<&'a str>) {
+        match value {
+            Value::Object(fields) => {
+                if let Some(paper_id) = fields.get("paperId").and_then(Value::as_str) {
+                    if lacks_fields(fields) && !paper_ids.contains(&paper_id) {
+                        paper_ids.push(paper_id);
+                    }
+                }
+                fields.values().for_each(|value| collect(value, paper_ids));
+            }
+            Value::Array(values) => values.iter().for_each(|value| collect(value, paper_ids)),
+            _ => {}
+        }
+    }
+
+    fn fill(value: &mut Value, papers: &HashMap<String, Value>) {
+        match value {
+            Value::Object(fields) => {
+                let paper = fields
+                    .get("paperId")
+                    .and_then(Value::as_str)
+                    .and_then(|paper_id| papers.get(paper_id));
+                if let Some(paper) = paper {
+                    for field in ENRICHED_FIELDS {
+                        if fields.get(*field).is_some_and(|value| !value.is_null()) {
+                            continue;
+                        }
+                        if let Some(known) = paper.get(*field).filter(|value| !value.is_null()) {
+                            fields.insert(field.to_string(), known.clone());
+                        }
+                    }
+                }
+                fields.values_mut().for_each(|value| fill(value, papers));
+            }
+            Value::Array(values) => values.iter_mut().for_each(|value| fill(value, papers)),
+            _ => {}
+        }
+    }
+
+    let mut paper_ids = Vec::new();
+    collect(body, &mut paper_ids);
+    if paper_ids.is_empty() {
+        return Ok(());
+    }
+
+    let papers: HashMap<String, Value> = paper_ids
+        .iter()
+        .zip(cache.get_papers(&paper_ids)?)
+        .filter_map(|(paper_id, entry)| Some((paper_id.to_string(), entry?.value)))
+        .collect();
+    fill(body, &papers);
+    Ok(())
+}
+
+/// Whether a response is a page of results holding none.
+pub fn is_empty_result(body: &Value) -> bool {
+    body.get("data")
+        .and_then(Value::as_array)
+        .is_some_and(Vec::is_empty)
+}
+
+/// Sends a request to the Semantic Scholar API, retrying on rate limits and
+/// transient failures. When `body` is given the request is sent as a POST with
+/// a JSON body, otherwise as a GET. With a cache, fresh responses are served
+/// from it and papers in the response are filled in from what it knows.
+pub async fn make_request(
+    http_client: &Arc<dyn HttpClient>,
+    rate_limiter: &Arc<RateLimiter>,
+    cache: Option<&dyn Cache>,
+    endpoint: &str,
+    params: Option<&Value>,
+    body: Option<&Value>,
+    base_url: Option<&str>,
+) -> Result<Value> {
+    let mut response = send_request(
+        http_client,
+        rate_limiter,
+        cache,
+        endpoint,
+        params,
+        body,
+        base_url,
+    )
+    .await?;
+    if let Some(cache) = cache {
+        if let Err(err) = enrich_papers(cache, &mut response) {
+            tracing::warn!("Failed to enrich papers from cache: {}", err);
+        }
+    }
+    Ok(response)
+}
+
+async fn send_request(
+    http_client: &Arc<dyn HttpClient>,
+    rate_limiter: &Arc<RateLimiter>,
+    cache: Option<&dyn Cache>,
+    endpoint: &str,
+    params: Option<&Value>,
+    body: Option<&Value>,
+    base_url: Option<&str>,
+) -> Result<Value> {
+    let graph_base_url = format!("{}/graph/v1", api_base_url());
+    let base_url = base_url.unwrap_or(&graph_base_url);
+    let api_key = std::env::var("SEMANTIC_SCHOLAR_API_KEY").ok();
+    let circuit_breaker = rate_limiter.circuit_breaker();
+    let stats = rate_limiter.stats();
+    let compression = compression_enabled();
+    let contact = std::env::var("SEMANTIC_SCHOLAR_CONTACT")
+        .ok()
+        .filter(|contact| !contact.trim().is_empty());
+    let user_agent = user_agent(contact.as_deref());
+
+    let url = if let Some(params) = params {
+        let query_string = build_query_string(params)?;
+        format!("{}{}?{}", base_url, endpoint, query_string)
+    } else {
+        format!("{}{}", base_url, endpoint)
+    };
+
+    let method = if body.is_some() { "POST" } else { "GET" };
+
+    // Fresh responses are served without touching the network, stale ones are
+    // revalidated with their ETag
+    let response_key = match body {
+        Some(body) => format!("{} {} {}", method, url, body),
+        None => format!("{} {}", method, url),
+    };
+    let cached_entry = cache.and_then(|cache| match cache.get_response(&response_key) {
+        Ok(entry) => entry,
+        Err(err) => {
+            tracing::warn!("Failed to read cached response: {}", err);
+            None
+        }
+    });
+
+    // Offline, whatever was cached is served however old it is
+    if is_offline() {
+        let Some(entry) = cached_entry else {
+            return Err(Error::NotCached(format!("{} {}", method, url)).into());
+        };
+        tracing::debug!("Serving cached response for {} offline", endpoint);
+        stats.record_cached();
+        served_from_cache(entry.created_at);
+        if entry.value.negative == Some(NegativeKind::NotFound) {
+            let message = entry.value.body.as_str().unwrap_or_default();
+            return Err(Error::NotFound(message.to_string()).into());
+        }
+        return Ok(entry.value.body);
+    }
+
+    if let Some(entry) = &cached_entry {
+        if let Some(max_age) = entry.value.max_age {
+            let age = chrono::Utc::now().naive_utc() - entry.created_at;
+            if age < chrono::Duration::seconds(max_age as i64) {
+                stats.record_cached();
+                served_from_cache(entry.created_at);
+                if entry.value.negative == Some(NegativeKind::NotFound) {
+                    tracing::debug!("Serving cached not found for {}", endpoint);
+                    let message = entry.value.body.as_str().unwrap_or_default();
+                    return Err(Error::NotFound(message.to_string()).into());
+                }
+                tracing::debug!("Serving fresh cached response for {}", endpoint);
+                return Ok(entry.value.body.clone());
+            }
+        }
+    }
+
+    // Negative entries are refetched once expired rather than revalidated, and
+    // never stand in for a response while the API is down
+    let mut cached_response = cached_entry
+        .map(|entry| entry.value)
+        .filter(|cached_response| cached_response.negative.is_none());
+
+    if let Err(err) = circuit_breaker.check() {
+        // A stale response beats no response while the API is down
+        if let Some(cached_response) = cached_response {
+            tracing::warn!("{}; serving cached response for {}", err, endpoint);
+            stats.record_cached();
+            return Ok(cached_response.body);
+        }
+        return Err(err);
+    }
+
+    rate_limiter
+        .acquire(
+            RateClass::for_endpoint(endpoint, base_url),
+            api_key.is_some(),
+        )
+        .await?;
+
+    let max_retries = 5;
+    let mut retry_delay = Duration::from_millis(100);
+
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+
+        let mut request_builder = Request::builder().method(method).uri(url.as_str());
+
+        if let Some(key) = &api_key {
+            request_builder = request_builder.header("x-api-key", key);
+        }
+
+        if let Some(etag) = cached_response
+            .as_ref()
+            .and_then(|cached_response| cached_response.etag.as_deref())
+        {
+            request_builder = request_builder.header("If-None-Match", etag);
+        }
+
+        if let Some(contact) = &contact {
+            request_builder = request_builder.header("From", contact.trim());
+        }
+
+        let mut request_builder = request_builder
+            .header("Accept", "application/json")
+            .header("User-Agent", user_agent.as_str());
+
+        if compression {
+            request_builder = request_builder.header("Accept-Encoding", "gzip, deflate");
+        }
+
+        let request = match body {
+            Some(body) => request_builder
+                .header("Content-Type", "application/json")
+                .body(serde_json::to_vec(body)?.into())?,
+            None => request_builder.end()?,
+        };
+        stats.record_request();
+        let started = Instant::now();
+        let response = http_client.send(request).await;
+        record_audit(AuditEntry {
+            method,
+            url: &url,
+            outcome: match &response {
+                Ok(response) => Ok(response.status().as_u16()),
+                Err(err) => Err(err.to_string()),
+            },
+            duration: started.elapsed(),
+            retries: attempts - 1,
+            authenticated: api_key.is_some(),
+        });
+
+        match response {
+            Ok(mut response) => {
+                let status = response.status();
+
+                let cache_control = CacheControl::from_headers(
+                    response
+                        .headers()
+                        .get("Cache-Control")
+                        .and_then(|value| value.to_str().ok()),
+                );
+
+                if status == 304 {
+                    if let Some(mut cached_response) = cached_response.take() {
+                        circuit_breaker.record_success();
+                        tracing::debug!("{} not modified, refreshing cached response", endpoint);
+                        if cache_control.max_age.is_some() || cache_control.no_cache {
+                            cached_response.max_age = cache_control.max_age();
+                        }
+                        let body = cached_response.body.clone();
+                        if let Some(cache) = cache {
+                            // Storing again renews the entry's age
+                            if let Err(err) = cache.store_response(&response_key, cached_response) {
+                                tracing::warn!("Failed to refresh cached response: {}", err);
+                            }
+                        }
+                        return Ok(body);
+                    }
+                }
+
+                let etag = response
+                    .headers()
+                    .get("ETag")
+                    .and_then(|value| value.to_str().ok())
+                    .map(str::to_string);
+                let encoding = response
+                    .headers()
+                    .get("Content-Encoding")
+                    .and_then(|value| value.to_str().ok())
+                    .map(str::to_string);
+                let mut bytes = Vec::new();
+                let response_body = response
+                    .body_mut()
+                    .read_to_end(&mut bytes)
+                    .await
+                    .map_err(anyhow::Error::from)
+                    .and_then(|_| decode_body(bytes, encoding.as_deref()));
+
+                if status.is_success() {
+                    circuit_breaker.record_success();
+                    let body: Value = serde_json::from_slice(&response_body?).map_err(|e| {
+                        Error::UpstreamError(format!("Failed to parse JSON response: {}", e))
+                    })?;
+
+                    // Empty results are kept briefly, so repeating a search
+                    // that matched nothing doesn't spend the rate limit
+                    let (max_age, negative) =
+                        match is_empty_result(&body).then(negative_max_age).flatten() {
+                            Some(max_age) => (Some(max_age), Some(NegativeKind::Empty)),
+                            None => (cache_control.max_age(), None),
+                        };
+                    if let Some(cache) = cache {
+                        record_response(cache, endpoint, &body);
+                    }
+
+                    // Responses without an ETag or max-age are stored too: they
+                    // are refetched online, but offline they are all there is
+                    if let (Some(cache), false) = (cache, cache_control.no_store) {
+                        let cached_response = CachedResponse {
+                            etag,
+                            max_age,
+                            body: body.clone(),
+                            negative,
+                        };
+                        if let Err(err) = cache.store_response(&response_key, cached_response) {
+                            tracing::warn!("Failed to store response in cache: {}", err);
+                        }
+                    }
+
+                    return Ok(body);
+                } else {
+                    let retry_after = response
+                        .headers()
+                        .get("Retry-After")
+                        .and_then(|value| value.to_str().ok())
+                        .and_then(parse_retry_after);
+                    let error_body = response_body
+                        .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+                        .unwrap_or_else(|_| "Unknown error".to_string());
+
+                    // Server errors count towards tripping the circuit breaker,
+                    // anything else shows the API is reachable
+                    if status == 429 {
+                        stats.record_rate_limited();
+                    }
+
+                    let circuit_open = if status.is_server_error() {
+                        stats.record_server_error();
+                        circuit_breaker.record_failure()
+                    } else {
+                        circuit_breaker.record_success();
+                        false
+                    };
+
+                    if circuit_open {
+                        return Err(Error::UpstreamError(format!(
+                            "Semantic Scholar API is unavailable (HTTP {}); pausing requests after repeated failures",
+                            status
+                        ))
+                        .into());
+                    }
+
+                    if status == 429 || status == 503 || status == 502 {
+                        // Waiting longer than this would hold the tool call
+                        // open, so the caller is told when to try again instead
+                        if let Some(wait) = retry_after.filter(|wait| *wait > MAX_RETRY_AFTER) {
+                            let message = format!(
+                                "Semantic Scholar responded with {} and asked to wait {}s before retrying",
+                                status,
+                                wait.as_secs()
+                            );
+                            if status == 429 {
+                                return Err(Error::RateLimited {
+                                    message,
+                                    retry_after,
+                                }
+                                .into());
+                            }
+                            return Err(Error::UpstreamError(message).into());
+                        }
+
+                        // Rate limiting or server errors - we can retry these
+                        if attempts <= max_retries {
+                            match retry_after {
+                                Some(wait) => {
+                                    tracing::info!(
+                                        "Semantic Scholar responded with {}, retrying {} in {:.1}s as requested by Retry-After (attempt {}/{})",
+                                        status,
+                                        endpoint,
+                                        wait.as_secs_f64(),
+                                        attempts,
+                                        max_retries
+                                    );
+                                    stats.record_backoff(wait);
+                                    Delay::new(wait).await;
+                                }
+                                None => {
+                                    tracing::debug!(
+                                        "Semantic Scholar responded with {}, retrying {} in {:.1}s (attempt {}/{})",
+                                        status,
+                                        endpoint,
+                                        retry_delay.as_secs_f64(),
+                                        attempts,
+                                        max_retries
+                                    );
+                                    stats.record_backoff(retry_delay);
+                                    Delay::new(retry_delay).await;
+                                    // Exponential backoff
+                                    retry_delay = retry_delay * 2;
+                                }
+                            }
+                            continue;
+                        } else if status == 429 {
+                            return Err(Error::RateLimited {
+                                message: format!(
+                                    "Rate limit exceeded after {} retries. Consider using an API key for higher limits.",
+                                    max_retries
+                                ),
+                                retry_after,
+                            }
+                            .into());
+                        } else {
+                            return Err(Error::UpstreamError(format!(
+                                "Semantic Scholar API returned HTTP {} after {} retries",
+                                status, max_retries
+                            ))
+                            .into());
+                        }
+                    } else if status == 404 {
+                        // Remembered briefly, so retrying an unknown ID
+                        // doesn't spend the rate limit
+                        if let (Some(cache), Some(max_age), false) =
+                            (cache, negative_max_age(), cache_control.no_store)
+                        {
+                            let cached_response = CachedResponse {
+                                etag: None,
+                                max_age: Some(max_age),
+                                body: Value::String(error_body.clone()),
+                                negative: Some(NegativeKind::NotFound),
+                            };
+                            if let Err(err) = cache.store_response(&response_key, cached_response) {
+                                tracing::warn!("Failed to store not found in cache: {}", err);
+                            }
+                        }
+                        return Err(Error::NotFound(error_body).into());
+                    } else if status == 400 {
+                        return Err(Error::InvalidArgument(format!(
+                            "Semantic Scholar rejected the request: {}",
+                            error_body
+                        ))
+                        .into());
+                    } else {
+                        return Err(Error::UpstreamError(format!(
+                            "HTTP error {}: {}",
+                            status, error_body
+                        ))
+                        .into());
+                    }
+                }
+            }
+            Err(e) => {
+                stats.record_network_error();
+
+                if circuit_breaker.record_failure() {
+                    return Err(Error::UpstreamError(format!(
+                        "Semantic Scholar API is unreachable ({}); pausing requests after repeated failures",
+                        e
+                    ))
+                    .into());
+                }
+
+                // Network errors might be transient, so we retry
+                if attempts <= max_retries {
+                    stats.record_backoff(retry_delay);
+                    Delay::new(retry_delay).await;
+                    // Exponential backoff
+                    retry_delay = retry_delay * 2;
+                    continue;
+                } else {
+                    return Err(Error::UpstreamError(format!(
+                        "Request failed after {} attempts: {}",
+                        max_retries, e
+                    ))
+                    .into());
+                }
+            }
+        }
+    }
+}
+
+/// The Cache-Control directives that decide how a response is cached.
+#[derive(Default)]
+struct CacheControl {
+    no_store: bool,
+    no_cache: bool,
+    max_age: Option<u64>,
+}
+
+impl CacheControl {
+    fn from_headers(value: Option<&str>) -> Self {
+        let mut cache_control = Self::default();
+
+        for directive in value.unwrap_or_default().split(',') {
+            let directive = directive.trim().to_lowercase();
+            match directive.split_once('=') {
+                Some(("max-age", seconds)) => {
+                    cache_control.max_age = seconds.trim_matches('"').parse().ok();
+                }
+                None if directive == "no-store" => {
+                    cache_control.no_store = true;
+                }
+                None if directive == "no-cache" => cache_control.no_cache = true,
+                _ => {}
+            }
+        }
+
+        cache_control
+    }
+
+    /// How long the response may be reused without revalidation. `no-cache`
+    /// responses can be stored but must always be revalidated.
+    fn max_age(&self) -> Option<u64> {
+        if self.no_cache {
+            None
+        } else {
+            self.max_age.filter(|max_age| *max_age > 0)
+        }
+    }
+}
+
+/// Uses `SEMANTIC_SCHOLAR_USER_AGENT` when set, otherwise identifies the server
+/// by name. A contact address is appended in the conventional mailto form.
+fn user_agent(contact: Option<&str>) -> String {
+    let user_agent = std::env::var("SEMANTIC_SCHOLAR_USER_AGENT")
+        .ok()
+        .filter(|user_agent| !user_agent.trim().is_empty())
+        .unwrap_or_else(|| String::from("semantic-scholar-mcp"));
+
+    match contact {
+        Some(contact) => format!("{} (mailto:{})", user_agent.trim(), contact.trim()),
+        None => user_agent.trim().to_string(),
+    }
+}
+
+/// Response compression is requested unless `SEMANTIC_SCHOLAR_COMPRESSION` is
+/// set to `0`, `false` or `off`.
+fn compression_enabled() -> bool {
+    std::env::var("SEMANTIC_SCHOLAR_COMPRESSION")
+        .map(|value| !matches!(value.trim().to_lowercase().as_str(), "0" | "false" | "off"))
+        .unwrap_or(true)
+}
+
+/// Decompresses a response body according to its Content-Encoding.
+fn decode_body(bytes: Vec<u8>, encoding: Option<&str>) -> Result<Vec<u8>> {
+    let mut decoded = Vec::new();
+
+    match encoding.map(|e| e.trim().to_lowercase()).as_deref() {
+        Some("gzip") | Some("x-gzip") => {
+            GzDecoder::new(bytes.as_slice()).read_to_end(&mut decoded)?;
+        }
+        Some("deflate") => {
+            ZlibDecoder::new(bytes.as_slice()).read_to_end(&mut decoded)?;
+        }
+        _ => return Ok(bytes),
+    }
+
+    Ok(decoded)
+}
+
+/// Parses a Retry-After header value, which is either a number of seconds or
+/// an HTTP date.
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let wait = date.with_timezone(&chrono::Utc) - chrono::Utc::now();
+
+    // A date in the past means the client may retry immediately
+    Some(wait.to_std().unwrap_or(Duration::ZERO))
+}
+
+fn build_query_string(params: &Value) -> Result<String> {
+    let mut query_parts = Vec::new();
+
+    if let Some(obj) = params.as_object() {
+        for (key, value) in obj {
+            match value {
+                Value::String(s) => {
+                    query_parts.push(format!("{}={}", key, urlencoding::encode(s)));
+                }
+                Value::Number(n) => {
+                    query_parts.push(format!("{}={}", key, n));
+                }
+                Value::Bool(b) => {
+                    query_parts.push(format!("{}={}", key, b));
+                }
+                Value::Array(arr) => {
+                    let joined = arr
+                        .iter()
+                        .filter_map(|v| v.as_str())
+                        .collect::<Vec<_>>()
+                        .join(",");
+                    query_parts.push(format!("{}={}", key, urlencoding::encode(&joined)));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    Ok(query_parts.join("&"))
+}
+
+/// Percent-encodes an identifier for use as a single URL path segment.
+/// Slashes in DOIs and URLs are encoded so they don't split the path, while
+/// the `:` after an ID prefix is kept since it is valid in a segment.
+pub fn encode_path_segment(segment: &str) -> String {
+    urlencoding::encode(segment).replace("%3A", ":")
+}
@@ -0,0 +1,22 @@
+mod audit;
+mod client;
+mod error;
+mod ids;
+mod offline;
+mod rate_limit;
+mod request;
+mod settings;
+
+pub use crate::{
+    audit::{AuditEntry, record_audit, set_audit_log},
+    client::SemanticScholarClient,
+    error::Error,
+    ids::{normalize_author_id, normalize_paper_id},
+    offline::{served_from_cache, track_cache_age},
+    rate_limit::{
+        CircuitBreaker, ClassRateLimitSettings, RateClass, RateLimit, RateLimitSettings,
+        RateLimiter, RequestStats, RequestStatsSnapshot,
+    },
+    request::{encode_path_segment, is_empty_result, make_request},
+    settings::{ClientSettings, api_base_url, is_offline, with_client_settings},
+};
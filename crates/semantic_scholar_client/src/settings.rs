@@ -0,0 +1,106 @@
+use std::{future::Future, sync::Arc, time::Duration};
+
+use cache::DEFAULT_NEGATIVE_TTL;
+
+const DEFAULT_API_BASE_URL: &str = "https://api.semanticscholar.org";
+
+/// How requests to Semantic Scholar are made: where they go, whether they
+/// may be sent at all, and how long empty answers are cached. Applied to
+/// everything a future sends with [`with_client_settings`], so clients
+/// sharing a process can each use their own.
+#[derive(Debug, Clone)]
+pub struct ClientSettings {
+    api_base_url: String,
+    offline: bool,
+    negative_cache_ttl: Duration,
+}
+
+impl Default for ClientSettings {
+    fn default() -> Self {
+        Self {
+            api_base_url: DEFAULT_API_BASE_URL.to_string(),
+            offline: false,
+            negative_cache_ttl: DEFAULT_NEGATIVE_TTL,
+        }
+    }
+}
+
+impl ClientSettings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Points every request at another host serving the Semantic Scholar
+    /// API, such as a caching proxy.
+    pub fn with_api_base_url(mut self, url: &str) -> Self {
+        self.api_base_url = url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Answers from the cache alone, without sending anything to Semantic
+    /// Scholar. Cached results are served however old they are, and queries
+    /// are matched with a looser similarity threshold.
+    pub fn with_offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    /// Sets how long requests that found nothing, such as an unknown ID or a
+    /// search without results, are answered from the cache. Zero turns
+    /// negative caching off.
+    pub fn with_negative_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.negative_cache_ttl = ttl;
+        self
+    }
+
+    /// The root of the Semantic Scholar API, under which the graph,
+    /// recommendations and datasets APIs live.
+    pub fn api_base_url(&self) -> &str {
+        &self.api_base_url
+    }
+
+    pub fn is_offline(&self) -> bool {
+        self.offline
+    }
+
+    pub fn negative_cache_ttl(&self) -> Duration {
+        self.negative_cache_ttl
+    }
+}
+
+tokio::task_local! {
+    static CLIENT_SETTINGS: Arc<ClientSettings>;
+}
+
+/// Runs `future` with `settings` applying to every request it makes. Outside
+/// of it, the defaults apply.
+pub async fn with_client_settings<F: Future>(
+    settings: Arc<ClientSettings>,
+    future: F,
+) -> F::Output {
+    CLIENT_SETTINGS.scope(settings, future).await
+}
+
+fn current<T>(read: impl FnOnce(&ClientSettings) -> T) -> T {
+    match CLIENT_SETTINGS.try_with(Arc::clone) {
+        Ok(settings) => read(&settings),
+        Err(_) => read(&ClientSettings::default()),
+    }
+}
+
+/// The root of the Semantic Scholar API for the current request.
+pub fn api_base_url() -> String {
+    current(|settings| settings.api_base_url.clone())
+}
+
+/// Whether the current request may only be answered from the cache.
+pub fn is_offline() -> bool {
+    current(ClientSettings::is_offline)
+}
+
+/// Seconds a negative entry stays fresh, or `None` when negative caching is
+/// off.
+pub(crate) fn negative_max_age() -> Option<u64> {
+    let ttl = current(ClientSettings::negative_cache_ttl);
+    (!ttl.is_zero()).then(|| ttl.as_secs())
+}
@@ -0,0 +1,127 @@
+use std::{sync::Arc, time::Duration};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use context_server::{Tool, ToolContent, ToolExecutor};
+use serde_json::Value;
+
+use crate::{
+    arguments::{NoArguments, input_schema},
+    utils::{RateClass, RateLimiter},
+};
+
+pub struct ApiStatusTool {
+    rate_limiter: Arc<RateLimiter>,
+}
+
+impl ApiStatusTool {
+    pub fn new(rate_limiter: Arc<RateLimiter>) -> Self {
+        Self { rate_limiter }
+    }
+
+    fn format_duration(duration: Duration) -> String {
+        let seconds = duration.as_secs();
+        if seconds < 60 {
+            format!("{}s", seconds)
+        } else if seconds < 60 * 60 {
+            format!("{}m {}s", seconds / 60, seconds % 60)
+        } else {
+            format!("{}h {}m", seconds / 3600, (seconds % 3600) / 60)
+        }
+    }
+
+    fn format_status(&self) -> String {
+        let authenticated = std::env::var("SEMANTIC_SCHOLAR_API_KEY").is_ok();
+        let stats = self.rate_limiter.stats().snapshot();
+        let circuit = self.rate_limiter.circuit_breaker().status();
+
+        let mut result = String::from("Semantic Scholar API status:\n\n");
+
+        result.push_str(&format!(
+            "API key: {}\n",
+            if authenticated {
+                "in use"
+            } else {
+                "not configured (sharing the anonymous quota)"
+            }
+        ));
+        result.push_str(&format!(
+            "Requests in the last minute: {}\n",
+            stats.requests_last_minute
+        ));
+        result.push_str(&format!("Requests since startup: {}\n", stats.requests));
+        result.push_str(&format!(
+            "Responses served from the HTTP cache: {}\n",
+            stats.cached
+        ));
+
+        match stats.last_rate_limited {
+            Some(ago) => result.push_str(&format!(
+                "Rate limited (429) responses: {} (last {} ago)\n",
+                stats.rate_limited,
+                Self::format_duration(ago)
+            )),
+            None => result.push_str("Rate limited (429) responses: 0\n"),
+        }
+
+        result.push_str(&format!("Server errors: {}\n", stats.server_errors));
+        result.push_str(&format!("Network errors: {}\n", stats.network_errors));
+
+        match stats.backoff_remaining {
+            Some(remaining) => result.push_str(&format!(
+                "Backoff: waiting {:.1}s before retrying\n",
+                remaining.as_secs_f64()
+            )),
+            None => result.push_str("Backoff: none\n"),
+        }
+
+        result.push_str(&format!("Circuit breaker: {}\n", circuit));
+
+        result.push_str("\nRate limits:\n");
+        for class in RateClass::ALL {
+            let limit = self.rate_limiter.limit(class, authenticated);
+            result.push_str(&format!(
+                "- {:?}: {} requests/second, burst {}\n",
+                class, limit.requests_per_second, limit.burst
+            ));
+        }
+
+        let recently_rate_limited = stats
+            .last_rate_limited
+            .is_some_and(|ago| ago < Duration::from_secs(60));
+
+        if circuit.starts_with("open") {
+            result.push_str(
+                "\nThe API is currently unavailable. Rely on cached results until the circuit closes.\n",
+            );
+        } else if recently_rate_limited || stats.backoff_remaining.is_some() {
+            result.push_str(
+                "\nThe API rate limited requests in the last minute. Consider slowing down or relying on cached results.\n",
+            );
+        }
+
+        result
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for ApiStatusTool {
+    async fn execute(&self, _arguments: Option<Value>) -> Result<Vec<ToolContent>> {
+        tracing::debug!("Executing ApiStatusTool");
+
+        Ok(vec![ToolContent::Text {
+            text: self.format_status(),
+        }])
+    }
+
+    fn to_tool(&self) -> Tool {
+        Tool {
+            name: "api_status".into(),
+            description: Some(
+                "Report recent Semantic Scholar API usage: request counts, rate limiting (429) responses, backoff and circuit breaker state, and whether an API key is in use"
+                    .into(),
+            ),
+            input_schema: input_schema::<NoArguments>(),
+        }
+    }
+}
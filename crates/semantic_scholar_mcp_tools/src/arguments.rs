@@ -0,0 +1,404 @@
+use anyhow::Result;
+use schemars::{JsonSchema, r#gen::SchemaSettings, schema::Schema};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::{Value, json};
+
+use crate::{error::invalid_argument, fields_of_study::FIELDS_OF_STUDY, settings};
+
+/// Validates tool arguments against the tool's input schema, then
+/// deserializes them into their typed representation. Missing arguments are
+/// treated as an empty object, so tools whose parameters are all optional can
+/// be called without any.
+pub fn parse_arguments<T: DeserializeOwned + JsonSchema>(arguments: Option<Value>) -> Result<T> {
+    let arguments = arguments.unwrap_or_else(|| json!({}));
+
+    let mut errors = Vec::new();
+    validate_value(&input_schema::<T>(), &arguments, "", &mut errors);
+
+    if !errors.is_empty() {
+        return Err(invalid_argument(format!(
+            "Invalid arguments: {}",
+            errors.join("; ")
+        )));
+    }
+
+    serde_json::from_value(arguments)
+        .map_err(|err| invalid_argument(format!("Invalid arguments: {}", err)))
+}
+
+/// Checks a value against the subset of JSON Schema that `input_schema`
+/// produces, describing every violation with the path of the offending field.
+fn validate_value(schema: &Value, value: &Value, path: &str, errors: &mut Vec<String>) {
+    if let Some(expected) = schema.get("type").and_then(Value::as_str) {
+        if !matches_type(expected, value) {
+            errors.push(format!(
+                "`{}` must be {}, got {}",
+                display_path(path),
+                describe_type(expected),
+                value
+            ));
+            return;
+        }
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            let allowed: Vec<&str> = allowed.iter().filter_map(Value::as_str).collect();
+            let mut message = format!("`{}` does not accept {}", display_path(path), value);
+            if let Some(suggestion) = value
+                .as_str()
+                .and_then(|value| closest_match(value, allowed.iter().copied()))
+            {
+                message.push_str(&format!(" (did you mean '{}'?)", suggestion));
+            }
+            message.push_str(&format!(". Allowed values: {}", allowed.join(", ")));
+            errors.push(message);
+            return;
+        }
+    }
+
+    if let Some(number) = value.as_f64() {
+        if let Some(minimum) = schema.get("minimum").and_then(Value::as_f64) {
+            if number < minimum {
+                errors.push(format!(
+                    "`{}` must be at least {}, got {}",
+                    display_path(path),
+                    minimum,
+                    value
+                ));
+            }
+        }
+        if let Some(maximum) = schema.get("maximum").and_then(Value::as_f64) {
+            if number > maximum {
+                errors.push(format!(
+                    "`{}` must be at most {}, got {}",
+                    display_path(path),
+                    maximum,
+                    value
+                ));
+            }
+        }
+    }
+
+    match value {
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    validate_value(item_schema, item, &format!("{}[{}]", path, i), errors);
+                }
+            }
+        }
+        Value::Object(object) => {
+            let properties = schema.get("properties").and_then(Value::as_object);
+
+            for required in schema
+                .get("required")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+            {
+                if object.get(required).is_none_or(Value::is_null) {
+                    errors.push(format!("`{}` is required", child_path(path, required)));
+                }
+            }
+
+            for (key, value) in object {
+                match properties.and_then(|properties| properties.get(key)) {
+                    // Optional parameters may be passed as null to leave them unset
+                    Some(_) if value.is_null() => {}
+                    Some(property) => {
+                        validate_value(property, value, &child_path(path, key), errors)
+                    }
+                    None if schema.get("additionalProperties") == Some(&Value::Bool(false)) => {
+                        let known: Vec<&str> = properties
+                            .map(|properties| properties.keys().map(String::as_str).collect())
+                            .unwrap_or_default();
+                        let mut message =
+                            format!("`{}` is not a known parameter", child_path(path, key));
+                        if let Some(suggestion) = closest_match(key, known.iter().copied()) {
+                            message.push_str(&format!(" (did you mean '{}'?)", suggestion));
+                        }
+                        if !known.is_empty() {
+                            message.push_str(&format!(". Known parameters: {}", known.join(", ")));
+                        }
+                        errors.push(message);
+                    }
+                    None => {}
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn matches_type(expected: &str, value: &Value) -> bool {
+    match expected {
+        "string" => value.is_string(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "boolean" => value.is_boolean(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+fn describe_type(expected: &str) -> &str {
+    match expected {
+        "string" => "a string",
+        "integer" => "an integer",
+        "number" => "a number",
+        "boolean" => "true or false",
+        "array" => "an array",
+        "object" => "an object",
+        other => other,
+    }
+}
+
+fn display_path(path: &str) -> &str {
+    if path.is_empty() { "arguments" } else { path }
+}
+
+fn child_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+/// Finds the candidate closest to a misspelled value, if any is close enough
+/// to be a plausible suggestion.
+pub fn closest_match<'a>(
+    value: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Option<&'a str> {
+    let value = value.to_lowercase();
+
+    candidates
+        .into_iter()
+        .map(|candidate| {
+            let candidate_lower = candidate.to_lowercase();
+            // Treat prefixes and substrings ("bio", "computer") as very close matches
+            let distance = if candidate_lower.contains(&value) || value.contains(&candidate_lower) {
+                0
+            } else {
+                levenshtein(&value, &candidate_lower)
+            };
+            (candidate, distance)
+        })
+        .filter(|(candidate, distance)| *distance <= candidate.len().max(value.len()) / 3)
+        .min_by_key(|(_, distance)| *distance)
+        .map(|(candidate, _)| candidate)
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let cost = if a_char == *b_char { 0 } else { 1 };
+            current.push(
+                (previous[j] + cost)
+                    .min(previous[j + 1] + 1)
+                    .min(current[j] + 1),
+            );
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+/// Builds a tool's input schema from the type its arguments deserialize into,
+/// so the advertised schema and the parsing can't drift apart.
+pub fn input_schema<T: JsonSchema>() -> Value {
+    let generator = SchemaSettings::draft07()
+        .with(|settings| {
+            settings.inline_subschemas = true;
+            settings.option_add_null_type = false;
+        })
+        .into_generator();
+
+    let mut schema = serde_json::to_value(generator.into_root_schema_for::<T>())
+        .unwrap_or_else(|_| json!({ "type": "object" }));
+
+    if let Some(object) = schema.as_object_mut() {
+        object.remove("$schema");
+        object.remove("title");
+        object.remove("definitions");
+        object.entry("properties").or_insert_with(|| json!({}));
+    }
+
+    schema
+}
+
+/// Arguments of tools that take no parameters.
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct NoArguments {}
+
+/// Schema for a list of fields of study, enumerating the valid taxonomy.
+pub fn fields_of_study_schema(_: &mut schemars::r#gen::SchemaGenerator) -> Schema {
+    serde_json::from_value(json!({
+        "type": "array",
+        "items": {
+            "type": "string",
+            "enum": FIELDS_OF_STUDY
+        }
+    }))
+    .expect("fields of study schema is valid")
+}
+
+/// The configured default paper fields, or the tool's own.
+pub fn default_paper_fields(builtin: &[PaperField]) -> Vec<PaperField> {
+    settings::current(|settings| settings.paper_fields.clone()).unwrap_or_else(|| builtin.to_vec())
+}
+
+/// The configured default author fields, or the tool's own.
+pub fn default_author_fields(builtin: &[AuthorField]) -> Vec<AuthorField> {
+    settings::current(|settings| settings.author_fields.clone()).unwrap_or_else(|| builtin.to_vec())
+}
+
+/// Paper fields that can be requested from the Graph API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum PaperField {
+    #[serde(rename = "title")]
+    Title,
+    #[serde(rename = "abstract")]
+    Abstract,
+    #[serde(rename = "year")]
+    Year,
+    #[serde(rename = "citationCount")]
+    CitationCount,
+    #[serde(rename = "referenceCount")]
+    ReferenceCount,
+    #[serde(rename = "authors")]
+    Authors,
+    #[serde(rename = "url")]
+    Url,
+    #[serde(rename = "citations")]
+    Citations,
+    #[serde(rename = "references")]
+    References,
+    #[serde(rename = "venue")]
+    Venue,
+    #[serde(rename = "journal")]
+    Journal,
+    #[serde(rename = "influentialCitationCount")]
+    InfluentialCitationCount,
+    #[serde(rename = "corpusId")]
+    CorpusId,
+    #[serde(rename = "externalIds")]
+    ExternalIds,
+    #[serde(rename = "fieldsOfStudy")]
+    FieldsOfStudy,
+    #[serde(rename = "isOpenAccess")]
+    IsOpenAccess,
+    #[serde(rename = "openAccessPdf")]
+    OpenAccessPdf,
+    #[serde(rename = "paperId")]
+    PaperId,
+    #[serde(rename = "publicationDate")]
+    PublicationDate,
+    #[serde(rename = "publicationTypes")]
+    PublicationTypes,
+    #[serde(rename = "publicationVenue")]
+    PublicationVenue,
+    #[serde(rename = "s2FieldsOfStudy")]
+    S2FieldsOfStudy,
+    #[serde(rename = "tldr")]
+    Tldr,
+    #[serde(rename = "embedding")]
+    Embedding,
+    #[serde(rename = "embedding.specter_v1")]
+    EmbeddingSpecterV1,
+    #[serde(rename = "embedding.specter_v2")]
+    EmbeddingSpecterV2,
+}
+
+/// Author fields that can be requested from the Graph API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum AuthorField {
+    #[serde(rename = "name")]
+    Name,
+    #[serde(rename = "aliases")]
+    Aliases,
+    #[serde(rename = "url")]
+    Url,
+    #[serde(rename = "authorId")]
+    AuthorId,
+    #[serde(rename = "affiliations")]
+    Affiliations,
+    #[serde(rename = "homepage")]
+    Homepage,
+    #[serde(rename = "paperCount")]
+    PaperCount,
+    #[serde(rename = "citationCount")]
+    CitationCount,
+    #[serde(rename = "hIndex")]
+    HIndex,
+    #[serde(rename = "papers")]
+    Papers,
+    #[serde(rename = "papers.year")]
+    PapersYear,
+    #[serde(rename = "papers.authors")]
+    PapersAuthors,
+    #[serde(rename = "papers.abstract")]
+    PapersAbstract,
+    #[serde(rename = "papers.venue")]
+    PapersVenue,
+    #[serde(rename = "papers.citations")]
+    PapersCitations,
+}
+
+/// Fields that can be requested for the papers on either side of a citation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum CitationField {
+    #[serde(rename = "contexts")]
+    Contexts,
+    #[serde(rename = "intents")]
+    Intents,
+    #[serde(rename = "isInfluential")]
+    IsInfluential,
+    #[serde(rename = "title")]
+    Title,
+    #[serde(rename = "abstract")]
+    Abstract,
+    #[serde(rename = "year")]
+    Year,
+    #[serde(rename = "authors")]
+    Authors,
+    #[serde(rename = "venue")]
+    Venue,
+    #[serde(rename = "citationCount")]
+    CitationCount,
+    #[serde(rename = "influentialCitationCount")]
+    InfluentialCitationCount,
+    #[serde(rename = "url")]
+    Url,
+    #[serde(rename = "paperId")]
+    PaperId,
+}
+
+/// Publication types accepted by the search filters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum PublicationType {
+    Review,
+    JournalArticle,
+    CaseReport,
+    ClinicalTrial,
+    Conference,
+    Dataset,
+    Editorial,
+    LettersAndComments,
+    MetaAnalysis,
+    News,
+    Study,
+    Book,
+    BookSection,
+}
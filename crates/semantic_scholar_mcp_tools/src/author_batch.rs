@@ -0,0 +1,217 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use cache::Cache;
+use context_server::{Tool, ToolContent, ToolExecutor};
+use embed::Embed;
+use http_client::HttpClient;
+use models::Author;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::{
+    arguments::{AuthorField, default_author_fields, input_schema, parse_arguments},
+    content::ToolOutput,
+    error::invalid_argument,
+    format::{Formatter, OutputFormat, render},
+    query_cache::{CacheKey, CacheLookup, cached_response, store_response},
+    result_formatter::{
+        BatchResults, DefaultEntryRenderer, EntryRenderer, RenderOptions, ResultFormatter,
+    },
+    utils::{RateLimiter, make_request, normalize_author_id},
+};
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct AuthorBatchArguments {
+    /// List of Semantic Scholar author IDs or author links (max: 1000)
+    author_ids: Vec<String>,
+    /// List of fields to return for each author. Default: name, affiliations, paperCount, citationCount, hIndex, url
+    fields: Option<Vec<AuthorField>>,
+    /// Maximum length of the output in characters. Results past it are left out, with a note saying how many. Default: the server's limit, if any
+    #[schemars(range(min = 1))]
+    max_output_chars: Option<usize>,
+    #[serde(default)]
+    output_format: OutputFormat,
+}
+
+pub struct AuthorBatchTool {
+    http_client: Arc<dyn HttpClient>,
+    rate_limiter: Arc<RateLimiter>,
+    cache: Arc<dyn Cache>,
+    embed: Arc<dyn Embed>,
+    renderer: Arc<dyn EntryRenderer>,
+    formatter: Arc<dyn ResultFormatter<BatchResults<Author>>>,
+}
+
+impl AuthorBatchTool {
+    pub fn new(
+        http_client: Arc<dyn HttpClient>,
+        rate_limiter: Arc<RateLimiter>,
+        cache: Arc<dyn Cache>,
+        embed: Arc<dyn Embed>,
+    ) -> Self {
+        Self {
+            http_client,
+            rate_limiter,
+            cache,
+            embed,
+            renderer: Arc::new(DefaultEntryRenderer::new()),
+            formatter: Arc::new(AuthorBatchFormatter),
+        }
+    }
+
+    /// Writes the entries through `renderer` rather than the default.
+    pub fn with_renderer(mut self, renderer: Arc<dyn EntryRenderer>) -> Self {
+        self.renderer = renderer;
+        self
+    }
+
+    pub fn with_formatter(
+        mut self,
+        formatter: Arc<dyn ResultFormatter<BatchResults<Author>>>,
+    ) -> Self {
+        self.formatter = formatter;
+        self
+    }
+}
+
+/// Lays out the output of `author_batch`.
+pub struct AuthorBatchFormatter;
+
+impl ResultFormatter<BatchResults<Author>> for AuthorBatchFormatter {
+    fn format(
+        &self,
+        f: &mut dyn Formatter,
+        renderer: &dyn EntryRenderer,
+        batch: &BatchResults<Author>,
+        _options: &RenderOptions,
+    ) -> Result<()> {
+        let found = batch.results.iter().flatten().count();
+        f.heading(&format!(
+            "Retrieved {} of {} requested authors:",
+            found,
+            batch.requested.len()
+        ));
+
+        let mut failures = Vec::new();
+
+        for (i, (requested_id, author)) in batch.requested.iter().zip(&batch.results).enumerate() {
+            let Some(author) = author else {
+                failures.push(requested_id.as_str());
+                continue;
+            };
+
+            f.item(i + 1, &renderer.author_title(author));
+            renderer.author(f, author);
+        }
+
+        if !failures.is_empty() {
+            f.note(&format!(
+                "Could not resolve {} author ID(s): {}",
+                failures.len(),
+                failures.join(", ")
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for AuthorBatchTool {
+    async fn execute(&self, arguments: Option<Value>) -> Result<Vec<ToolContent>> {
+        tracing::debug!("Executing AuthorBatchTool");
+        let args: AuthorBatchArguments = parse_arguments(arguments)?;
+
+        let mut author_ids: Vec<String> = args
+            .author_ids
+            .iter()
+            .map(|s| normalize_author_id(s))
+            .filter(|s| !s.is_empty())
+            .collect();
+
+        if author_ids.is_empty() {
+            return Err(invalid_argument("Must provide at least one author ID"));
+        }
+
+        // Sort the IDs so that the same set of authors always maps to the same cache entry
+        author_ids.sort();
+        author_ids.dedup();
+
+        if author_ids.len() > 1000 {
+            return Err(invalid_argument(
+                "Cannot request more than 1000 authors at once",
+            ));
+        }
+
+        let fields = args.fields.unwrap_or_else(|| {
+            default_author_fields(&[
+                AuthorField::Name,
+                AuthorField::Affiliations,
+                AuthorField::PaperCount,
+                AuthorField::CitationCount,
+                AuthorField::HIndex,
+                AuthorField::Url,
+            ])
+        });
+
+        let params = json!({ "fields": fields });
+        let request_body = json!({ "ids": author_ids });
+
+        let query_text = format!("author_batch:{}", author_ids.join(","));
+
+        let cache_params = json!({ "ids": author_ids, "fields": fields });
+
+        let key = CacheKey::new("author_batch", &query_text).params(&cache_params);
+        let result = match cached_response(self.cache.as_ref(), self.embed.as_ref(), &key).await? {
+            CacheLookup::Hit(result) => result,
+            CacheLookup::Miss(embedding) => {
+                let result = make_request(
+                    &self.http_client,
+                    &self.rate_limiter,
+                    Some(self.cache.as_ref()),
+                    "/author/batch",
+                    Some(&params),
+                    Some(&request_body),
+                    None,
+                )
+                .await?;
+                store_response(self.cache.as_ref(), &key, embedding, &result);
+                result
+            }
+        };
+
+        let batch = BatchResults {
+            requested: author_ids,
+            results: serde_json::from_value(result.clone())?,
+        };
+        let formatted_result = render(
+            args.output_format,
+            &result,
+            None,
+            args.max_output_chars,
+            |f| {
+                self.formatter
+                    .format(f, self.renderer.as_ref(), &batch, &RenderOptions::default())
+            },
+        )?;
+
+        let output = ToolOutput::new(formatted_result, args.output_format, &result);
+
+        Ok(output.into_contents())
+    }
+
+    fn to_tool(&self) -> Tool {
+        Tool {
+            name: "author_batch".into(),
+            description: Some(
+                "Get details for multiple authors at once from Semantic Scholar in a single request"
+                    .into(),
+            ),
+            input_schema: input_schema::<AuthorBatchArguments>(),
+        }
+    }
+}
@@ -1,13 +1,41 @@
-use anyhow::{Result, anyhow};
+use anyhow::Result;
 use async_trait::async_trait;
-use cache::{Cache, Query};
+use cache::Cache;
 use context_server::{Tool, ToolContent, ToolExecutor};
 use embed::Embed;
 use http_client::HttpClient;
+use models::Author;
+use schemars::JsonSchema;
+use serde::Deserialize;
 use serde_json::{Value, json};
 use std::sync::Arc;
 
-use crate::{RateLimiter, utils::make_request};
+use crate::{
+    RateLimiter,
+    arguments::{AuthorField, input_schema, parse_arguments},
+    content::ToolOutput,
+    error::invalid_argument,
+    format::{Formatter, OutputFormat, render},
+    query_cache::{CacheKey, CacheLookup, cached_response, store_response},
+    utils::{encode_path_segment, make_request, normalize_author_id, truncate_text},
+};
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct AuthorDetailsArguments {
+    /// Semantic Scholar author ID, or a link to the author on semanticscholar.org
+    author_id: String,
+    /// List of fields to return. Default: name and affiliations
+    fields: Option<Vec<AuthorField>>,
+    /// Truncate the abstracts of the listed papers to this many characters. Default: no truncation
+    #[schemars(range(min = 1))]
+    abstract_length: Option<u64>,
+    /// Maximum length of the output in characters. Results past it are left out, with a note saying how many. Default: the server's limit, if any
+    #[schemars(range(min = 1))]
+    max_output_chars: Option<usize>,
+    #[serde(default)]
+    output_format: OutputFormat,
+}
 
 pub struct AuthorDetailsTool {
     http_client: Arc<dyn HttpClient>,
@@ -31,219 +59,157 @@ impl AuthorDetailsTool {
         }
     }
 
-    fn format_author_details(&self, response: &Value) -> Result<String> {
-        if response.get("error").is_some() {
-            let message = response["error"]["message"]
-                .as_str()
-                .unwrap_or("Unknown error");
-            return Ok(format!("Error: {}", message));
+    fn format_author_details(
+        &self,
+        f: &mut dyn Formatter,
+        author: &Author,
+        abstract_length: Option<usize>,
+    ) -> Result<()> {
+        let name = author.name.as_deref().unwrap_or("Unknown name");
+        let author_id = author.author_id.as_deref().unwrap_or("Unknown ID");
+
+        f.heading(&format!("Author: {}", name));
+        f.field("Author ID", author_id);
+
+        if let Some(affiliations) = &author.affiliations {
+            if !affiliations.is_empty() {
+                f.list("Affiliations", affiliations);
+            }
         }
 
-        let name = response
-            .get("name")
-            .and_then(|n| n.as_str())
-            .unwrap_or("Unknown name");
-        let author_id = response
-            .get("authorId")
-            .and_then(|id| id.as_str())
-            .unwrap_or("Unknown ID");
-
-        let mut result = format!("Author: {}\n", name);
-        result.push_str(&format!("Author ID: {}\n\n", author_id));
-
-        if let Some(affiliations) = response.get("affiliations").and_then(|a| a.as_array()) {
-            let affiliation_list: Vec<&str> =
-                affiliations.iter().filter_map(|aff| aff.as_str()).collect();
-
-            if !affiliation_list.is_empty() {
-                result.push_str("Affiliations:\n");
-                for aff in affiliation_list {
-                    result.push_str(&format!("- {}\n", aff));
-                }
-                result.push_str("\n");
+        if let Some(aliases) = &author.aliases {
+            if !aliases.is_empty() {
+                f.list("Also known as", aliases);
             }
         }
 
-        if let Some(aliases) = response.get("aliases").and_then(|a| a.as_array()) {
-            let alias_list: Vec<&str> = aliases.iter().filter_map(|alias| alias.as_str()).collect();
+        if let Some(paper_count) = author.paper_count {
+            f.field("Papers", &paper_count.to_string());
+        }
 
-            if !alias_list.is_empty() {
-                result.push_str("Also known as:\n");
-                for alias in alias_list {
-                    result.push_str(&format!("- {}\n", alias));
-                }
-                result.push_str("\n");
-            }
+        if let Some(citation_count) = author.citation_count {
+            f.field("Citations", &citation_count.to_string());
         }
 
-        result.push_str("Research Metrics:\n");
+        if let Some(h_index) = author.h_index {
+            f.field("h-index", &h_index.to_string());
+        }
 
-        if let Some(paper_count) = response.get("paperCount").and_then(|p| p.as_u64()) {
-            result.push_str(&format!("- Papers: {}\n", paper_count));
+        if let Some(homepage) = author.homepage() {
+            f.link("Homepage", homepage);
         }
 
-        if let Some(citation_count) = response.get("citationCount").and_then(|c| c.as_u64()) {
-            result.push_str(&format!("- Citations: {}\n", citation_count));
+        if let Some(url) = &author.url {
+            f.link("Semantic Scholar URL", url);
         }
 
-        if let Some(h_index) = response.get("hIndex").and_then(|h| h.as_u64()) {
-            result.push_str(&format!("- h-index: {}\n", h_index));
+        let Some(papers) = &author.papers else {
+            f.note("Paper information not included in the response. Use the 'fields' parameter to include 'papers'. Alternatively, use the author_papers tool to get all papers by this author.");
+            return Ok(());
+        };
+
+        if papers.is_empty() {
+            return Ok(());
         }
 
-        result.push_str("\n");
+        f.heading(&format!(
+            "Representative Papers (showing up to 10 of {}):",
+            papers.len()
+        ));
+
+        for (i, paper) in papers.iter().take(10).enumerate() {
+            let paper_title = paper.title.as_deref().unwrap_or("Unknown title");
+            let paper_id = paper.paper_id.as_deref().unwrap_or("Unknown ID");
+
+            f.item(i + 1, &format!("{} (ID: {})", paper_title, paper_id));
 
-        if let Some(homepage) = response.get("homepage").and_then(|h| h.as_str()) {
-            if !homepage.is_empty() {
-                result.push_str(&format!("Homepage: {}\n", homepage));
+            if let Some(year) = paper.year {
+                f.field("Year", &year.to_string());
             }
-        }
 
-        if let Some(url) = response.get("url").and_then(|u| u.as_str()) {
-            result.push_str(&format!("Semantic Scholar URL: {}\n\n", url));
-        }
+            if let Some(venue) = paper.venue() {
+                f.field("Venue", venue);
+            }
 
-        if let Some(papers) = response.get("papers").and_then(|p| p.as_array()) {
-            if !papers.is_empty() {
-                result.push_str(&format!(
-                    "Representative Papers (showing up to 10 of {}):\n\n",
-                    papers.len()
-                ));
-
-                for (i, paper) in papers.iter().take(10).enumerate() {
-                    let paper_title = paper
-                        .get("title")
-                        .and_then(|t| t.as_str())
-                        .unwrap_or("Unknown title");
-                    let paper_id = paper
-                        .get("paperId")
-                        .and_then(|id| id.as_str())
-                        .unwrap_or("Unknown ID");
-
-                    result.push_str(&format!("{}. {} (ID: {})\n", i + 1, paper_title, paper_id));
-
-                    if let Some(year) = paper.get("year").and_then(|y| y.as_u64()) {
-                        result.push_str(&format!("   Year: {}\n", year));
-                    }
-
-                    if let Some(venue) = paper.get("venue").and_then(|v| v.as_str()) {
-                        if !venue.is_empty() {
-                            result.push_str(&format!("   Venue: {}\n", venue));
-                        }
-                    }
-
-                    if let Some(citation_count) =
-                        paper.get("citationCount").and_then(|c| c.as_u64())
-                    {
-                        result.push_str(&format!("   Citations: {}\n", citation_count));
-                    }
-
-                    if let Some(authors) = paper.get("authors").and_then(|a| a.as_array()) {
-                        let author_names: Vec<&str> = authors
-                            .iter()
-                            .filter_map(|author| author.get("name").and_then(|n| n.as_str()))
-                            .collect();
-
-                        if !author_names.is_empty() {
-                            result.push_str(&format!("   Authors: {}\n", author_names.join(", ")));
-                        }
-                    }
-
-                    if let Some(abstract_text) = paper.get("abstract").and_then(|a| a.as_str()) {
-                        if !abstract_text.is_empty() {
-                            let summary = abstract_text.to_string();
-                            result.push_str(&format!("   Abstract: {}\n", summary));
-                        }
-                    }
-
-                    if i < papers.len().min(10) - 1 {
-                        result.push_str("\n");
-                    }
-                }
-
-                if papers.len() > 10 {
-                    result.push_str(&format!("\n... and {} more papers\n", papers.len() - 10));
-                    result
-                        .push_str("Use the author_papers tool to see all papers by this author.\n");
-                }
+            if let Some(citation_count) = paper.citation_count {
+                f.field("Citations", &citation_count.to_string());
             }
-        } else {
-            result.push_str("Paper information not included in the response. Use the 'fields' parameter to include 'papers'.\n");
-            result.push_str(
-                "Alternatively, use the author_papers tool to get all papers by this author.\n",
-            );
+
+            let author_names = paper.author_names();
+            if !author_names.is_empty() {
+                f.field("Authors", &author_names.join(", "));
+            }
+
+            if let Some(abstract_text) = paper.abstract_text() {
+                f.field("Abstract", &truncate_text(abstract_text, abstract_length));
+            }
+        }
+
+        if papers.len() > 10 {
+            f.note(&format!(
+                "... and {} more papers. Use the author_papers tool to see all papers by this author.",
+                papers.len() - 10
+            ));
         }
 
-        Ok(result)
+        Ok(())
     }
 }
 
 #[async_trait]
 impl ToolExecutor for AuthorDetailsTool {
     async fn execute(&self, arguments: Option<Value>) -> Result<Vec<ToolContent>> {
-        log::debug!("Executing AuthorDetailsTool");
-        let args = arguments.ok_or_else(|| anyhow!("Missing arguments"))?;
-
-        let author_id = args
-            .get("author_id")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow!("Missing or invalid author_id parameter"))?;
+        tracing::debug!("Executing AuthorDetailsTool");
+        let AuthorDetailsArguments {
+            author_id,
+            fields,
+            abstract_length,
+            max_output_chars,
+            output_format,
+        } = parse_arguments(arguments)?;
+        let abstract_length = abstract_length.map(|length| length as usize);
 
         if author_id.trim().is_empty() {
-            return Err(anyhow!("Author ID cannot be empty"));
+            return Err(invalid_argument("Author ID cannot be empty"));
         }
 
-        let fields = args.get("fields").cloned();
+        let author_id = normalize_author_id(&author_id);
 
         let params = match fields {
-            Some(fields_value) => json!({"fields": fields_value}),
+            Some(fields) => json!({"fields": fields}),
             None => json!({}),
         };
 
-        // Generate an embedding for the query
-        let embedding = self.embed.embed(&author_id).await?;
-
-        // Check if we have a cached result for a similar query
-        let similar_queries = self.cache.search_similarity(&embedding)?;
-
-        // Check for any cached queries with high similarity and matching action/params
-        for (cached_query, similarity) in similar_queries.iter() {
-            if similarity > &0.95 && cached_query.action == "author_details" {
-                // Check if parameters match
-                if cached_query.params == Some(params.clone()) {
-                    log::debug!("Found cached result with similarity {}", similarity);
-                    return Ok(vec![ToolContent::Text {
-                        text: serde_json::from_value(cached_query.results.clone())?,
-                    }]);
-                }
+        // The ID is part of the endpoint rather than the query parameters
+        let key = CacheKey::new("author_details", &author_id)
+            .param("author_id", &author_id)
+            .params(&params);
+        let result = match cached_response(self.cache.as_ref(), self.embed.as_ref(), &key).await? {
+            CacheLookup::Hit(result) => result,
+            CacheLookup::Miss(embedding) => {
+                let result = make_request(
+                    &self.http_client,
+                    &self.rate_limiter,
+                    Some(self.cache.as_ref()),
+                    &format!("/author/{}", encode_path_segment(&author_id)),
+                    Some(&params),
+                    None,
+                    None,
+                )
+                .await?;
+                store_response(self.cache.as_ref(), &key, embedding, &result);
+                result
             }
-        }
-
-        let result = make_request(
-            &self.http_client,
-            &self.rate_limiter,
-            &format!("/author/{}", author_id),
-            Some(&params),
-            None,
-        )
-        .await?;
-
-        let formatted_result = self.format_author_details(&result)?;
-
-        let query = Query {
-            action: "author_details".into(),
-            text: author_id.into(),
-            embedding,
-            params: Some(params),
-            results: json!(formatted_result),
         };
 
-        if let Err(err) = self.cache.store(query) {
-            log::warn!("Failed to store query in cache: {}", err);
-        }
+        let author: Author = serde_json::from_value(result.clone())?;
+        let formatted_result = render(output_format, &result, None, max_output_chars, |f| {
+            self.format_author_details(f, &author, abstract_length)
+        })?;
+
+        let output = ToolOutput::new(formatted_result, output_format, &result);
 
-        Ok(vec![ToolContent::Text {
-            text: formatted_result,
-        }])
+        Ok(output.into_contents())
     }
 
     fn to_tool(&self) -> Tool {
@@ -252,28 +218,7 @@ impl ToolExecutor for AuthorDetailsTool {
             description: Some(
                 "Get detailed information about an author by their ID in Semantic Scholar".into(),
             ),
-            input_schema: json!({
-                "type": "object",
-                "properties": {
-                    "author_id": {
-                        "type": "string",
-                        "description": "Semantic Scholar author ID"
-                    },
-                    "fields": {
-                        "type": "array",
-                        "description": "List of fields to return. Default: name and affiliations",
-                        "items": {
-                            "type": "string",
-                            "enum": [
-                                "name", "aliases", "url", "authorId", "affiliations", "homepage",
-                                "paperCount", "citationCount", "hIndex", "papers", "papers.year",
-                                "papers.authors", "papers.abstract", "papers.venue", "papers.citations"
-                            ]
-                        }
-                    }
-                },
-                "required": ["author_id"]
-            }),
+            input_schema: input_schema::<AuthorDetailsArguments>(),
         }
     }
 }
@@ -1,19 +1,57 @@
-use anyhow::{Result, anyhow};
+use anyhow::Result;
 use async_trait::async_trait;
-use cache::{Cache, Query};
+use cache::Cache;
 use context_server::{Tool, ToolContent, ToolExecutor};
 use embed::Embed;
 use http_client::HttpClient;
+use models::{Page, Paper};
+use schemars::JsonSchema;
+use serde::Deserialize;
 use serde_json::{Value, json};
 use std::sync::Arc;
 
-use crate::{RateLimiter, utils::make_request};
+use crate::{
+    RateLimiter,
+    arguments::{PaperField, input_schema, parse_arguments},
+    content::ToolOutput,
+    error::invalid_argument,
+    format::{Formatter, OutputFormat, render},
+    pagination::Pagination,
+    query_cache::{CacheKey, CacheLookup, cached_response, store_response},
+    result_formatter::{DefaultEntryRenderer, EntryRenderer, RenderOptions, ResultFormatter},
+    utils::{encode_path_segment, make_request, normalize_author_id},
+};
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct AuthorPapersArguments {
+    /// Semantic Scholar author ID, or a link to the author on semanticscholar.org
+    author_id: String,
+    /// List of fields to return for each paper. Default: title and year
+    fields: Option<Vec<PaperField>>,
+    /// Number of papers to skip for pagination. Default: 0
+    #[serde(default)]
+    offset: u64,
+    /// Maximum number of papers to return. Default: 100, Maximum: 1000
+    #[schemars(range(max = 1000))]
+    limit: Option<u64>,
+    /// Truncate abstracts to this many characters. Default: no truncation
+    #[schemars(range(min = 1))]
+    abstract_length: Option<u64>,
+    /// Maximum length of the output in characters. Results past it are left out, with a note saying how many. Default: the server's limit, if any
+    #[schemars(range(min = 1))]
+    max_output_chars: Option<usize>,
+    #[serde(default)]
+    output_format: OutputFormat,
+}
 
 pub struct AuthorPapersTool {
     http_client: Arc<dyn HttpClient>,
     rate_limiter: Arc<RateLimiter>,
     cache: Arc<dyn Cache>,
     embed: Arc<dyn Embed>,
+    renderer: Arc<dyn EntryRenderer>,
+    formatter: Arc<dyn ResultFormatter<Page<Paper>>>,
 }
 
 impl AuthorPapersTool {
@@ -28,195 +66,134 @@ impl AuthorPapersTool {
             rate_limiter,
             cache,
             embed,
+            renderer: Arc::new(DefaultEntryRenderer::new()),
+            formatter: Arc::new(AuthorPapersFormatter),
         }
     }
 
-    fn format_author_papers(&self, response: &Value) -> Result<String> {
-        if response.get("error").is_some() {
-            let message = response["error"]["message"]
-                .as_str()
-                .unwrap_or("Unknown error");
-            return Ok(format!("Error: {}", message));
-        }
-
-        let offset = response.get("offset").and_then(|o| o.as_u64()).unwrap_or(0);
-        let next_offset = response.get("next").and_then(|n| n.as_u64());
-
-        if let Some(data) = response.get("data").and_then(|d| d.as_array()) {
-            if data.is_empty() {
-                return Ok(String::from("No papers found for this author."));
-            }
-
-            let mut result = format!(
-                "Found {} papers by this author (offset: {}):\n\n",
-                data.len(),
-                offset
-            );
-
-            for (i, paper) in data.iter().enumerate() {
-                let title = paper
-                    .get("title")
-                    .and_then(|t| t.as_str())
-                    .unwrap_or("Unknown title");
-                let paper_id = paper
-                    .get("paperId")
-                    .and_then(|p| p.as_str())
-                    .unwrap_or("Unknown ID");
-
-                result.push_str(&format!(
-                    "{}. {} (ID: {})\n",
-                    i + 1 + offset as usize,
-                    title,
-                    paper_id
-                ));
-
-                if let Some(year) = paper.get("year").and_then(|y| y.as_u64()) {
-                    result.push_str(&format!("   Year: {}\n", year));
-                }
-
-                if let Some(venue) = paper.get("venue").and_then(|v| v.as_str()) {
-                    if !venue.is_empty() {
-                        result.push_str(&format!("   Venue: {}\n", venue));
-                    }
-                }
-
-                if let Some(citation_count) = paper.get("citationCount").and_then(|c| c.as_u64()) {
-                    result.push_str(&format!("   Citations: {}\n", citation_count));
-                }
+    /// Writes the entries through `renderer` rather than the default.
+    pub fn with_renderer(mut self, renderer: Arc<dyn EntryRenderer>) -> Self {
+        self.renderer = renderer;
+        self
+    }
 
-                if let Some(authors) = paper.get("authors").and_then(|a| a.as_array()) {
-                    let author_names: Vec<&str> = authors
-                        .iter()
-                        .filter_map(|author| author.get("name").and_then(|n| n.as_str()))
-                        .collect();
+    pub fn with_formatter(mut self, formatter: Arc<dyn ResultFormatter<Page<Paper>>>) -> Self {
+        self.formatter = formatter;
+        self
+    }
+}
 
-                    if !author_names.is_empty() {
-                        result.push_str(&format!("   Authors: {}\n", author_names.join(", ")));
-                    }
-                }
+/// Lays out the output of `author_papers`.
+pub struct AuthorPapersFormatter;
+
+impl ResultFormatter<Page<Paper>> for AuthorPapersFormatter {
+    fn format(
+        &self,
+        f: &mut dyn Formatter,
+        renderer: &dyn EntryRenderer,
+        page: &Page<Paper>,
+        options: &RenderOptions,
+    ) -> Result<()> {
+        if page.data.is_empty() {
+            f.note("No papers found for this author.");
+            return Ok(());
+        }
 
-                if let Some(abstract_text) = paper.get("abstract").and_then(|a| a.as_str()) {
-                    if !abstract_text.is_empty() {
-                        let summary = abstract_text.to_string();
-                        result.push_str(&format!("   Abstract: {}\n", summary));
-                    }
-                }
+        let offset = page.offset.unwrap_or(0);
 
-                if let Some(url) = paper.get("url").and_then(|u| u.as_str()) {
-                    result.push_str(&format!("   URL: {}\n", url));
-                }
+        f.heading(&format!(
+            "Found {} papers by this author (offset: {}):",
+            page.data.len(),
+            offset
+        ));
 
-                if let Some(is_open_access) = paper.get("isOpenAccess").and_then(|o| o.as_bool()) {
-                    if is_open_access {
-                        if let Some(pdf) = paper.get("openAccessPdf") {
-                            if let Some(pdf_url) = pdf.get("url").and_then(|u| u.as_str()) {
-                                result.push_str(&format!("   Open Access PDF: {}\n", pdf_url));
-                            }
-                        }
-                    }
-                }
+        for (i, paper) in page.data.iter().enumerate() {
+            f.item(i + 1 + offset as usize, &renderer.paper_title(paper));
+            renderer.paper(f, paper, options);
 
-                if i < data.len() - 1 {
-                    result.push_str("\n");
+            if paper.is_open_access == Some(true) {
+                if let Some(pdf_url) = paper.open_access_pdf_url() {
+                    renderer.link(f, "Open Access PDF", pdf_url);
                 }
             }
+        }
 
-            if let Some(next) = next_offset {
-                result.push_str(&format!("\nFor more papers, use offset={}", next));
-            }
-
-            Ok(result)
-        } else {
-            Ok(String::from(
-                "No papers found or unexpected API response format.",
-            ))
+        if let Some(next) = page.next {
+            f.note(&format!("For more papers, use offset={}", next));
         }
+
+        Ok(())
     }
 }
 
 #[async_trait]
 impl ToolExecutor for AuthorPapersTool {
     async fn execute(&self, arguments: Option<Value>) -> Result<Vec<ToolContent>> {
-        log::debug!("Executing AuthorPapersTool");
-        let args = arguments.ok_or_else(|| anyhow!("Missing arguments"))?;
-
-        let author_id = args
-            .get("author_id")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow!("Missing or invalid author_id parameter"))?;
+        tracing::debug!("Executing AuthorPapersTool");
+        let AuthorPapersArguments {
+            author_id,
+            fields,
+            offset,
+            limit,
+            abstract_length,
+            max_output_chars,
+            output_format,
+        } = parse_arguments(arguments)?;
+        let options = RenderOptions {
+            abstract_length: abstract_length.map(|length| length as usize),
+        };
 
         if author_id.trim().is_empty() {
-            return Err(anyhow!("Author ID cannot be empty"));
+            return Err(invalid_argument("Author ID cannot be empty"));
         }
 
-        let fields = args.get("fields").cloned();
-
-        let offset = args.get("offset").and_then(|v| v.as_u64()).unwrap_or(0);
-
-        let limit = args.get("limit").and_then(|v| v.as_u64()).unwrap_or(100);
+        let author_id = normalize_author_id(&author_id);
 
-        if limit > 1000 {
-            return Err(anyhow!("Limit cannot exceed 1000"));
-        }
+        let limit = limit.unwrap_or(100);
 
         // Build params object for the API request
         let mut params_map = serde_json::Map::new();
         params_map.insert("offset".to_string(), json!(offset));
         params_map.insert("limit".to_string(), json!(limit));
 
-        if let Some(f) = fields.clone() {
-            params_map.insert("fields".to_string(), f);
+        if let Some(f) = fields {
+            params_map.insert("fields".to_string(), json!(f));
         }
 
         let params = Value::Object(params_map);
 
-        // Generate an embedding for the query
-        let embedding = self.embed.embed(&author_id).await?;
-
-        // Check if we have a cached result for a similar query
-        let similar_queries = self.cache.search_similarity(&embedding)?;
-
-        // Check for any cached queries with high similarity and matching action/params
-        for (cached_query, similarity) in similar_queries.iter() {
-            if similarity > &0.95 && cached_query.action == "author_papers" {
-                // Check if parameters match
-                if cached_query.params == Some(params.clone()) {
-                    log::debug!("Found cached result with similarity {}", similarity);
-                    return Ok(vec![ToolContent::Text {
-                        text: serde_json::from_value(cached_query.results.clone())?,
-                    }]);
-                }
+        // The ID is part of the endpoint rather than the query parameters
+        let key = CacheKey::new("author_papers", &author_id)
+            .param("author_id", &author_id)
+            .params(&params);
+        let result = match cached_response(self.cache.as_ref(), self.embed.as_ref(), &key).await? {
+            CacheLookup::Hit(result) => result,
+            CacheLookup::Miss(embedding) => {
+                let result = make_request(
+                    &self.http_client,
+                    &self.rate_limiter,
+                    Some(self.cache.as_ref()),
+                    &format!("/author/{}/papers", encode_path_segment(&author_id)),
+                    Some(&params),
+                    None,
+                    None,
+                )
+                .await?;
+                store_response(self.cache.as_ref(), &key, embedding, &result);
+                result
             }
-        }
-
-        // Otherwise make the API request
-        let result = make_request(
-            &self.http_client,
-            &self.rate_limiter,
-            &format!("/author/{}/papers", author_id),
-            Some(&params),
-            None,
-        )
-        .await?;
-
-        let formatted_result = self.format_author_papers(&result)?;
-
-        // Store the result in cache
-        let query = Query {
-            action: "author_papers".into(),
-            text: author_id.into(),
-            embedding,
-            params: Some(params),
-            results: json!(formatted_result),
         };
 
-        if let Err(err) = self.cache.store(query) {
-            log::warn!("Failed to store query in cache: {}", err);
-        }
+        let page: Page<Paper> = serde_json::from_value(result.clone())?;
+        let formatted_result = render(output_format, &result, None, max_output_chars, |f| {
+            self.formatter
+                .format(f, self.renderer.as_ref(), &page, &options)
+        })?;
+
+        let output = ToolOutput::new(formatted_result, output_format, &result)
+            .with_pagination(Pagination::from_page(&page));
 
-        Ok(vec![ToolContent::Text {
-            text: formatted_result,
-        }])
+        Ok(output.into_contents())
     }
 
     fn to_tool(&self) -> Tool {
@@ -226,37 +203,7 @@ impl ToolExecutor for AuthorPapersTool {
                 "Get papers written by a specific author in Semantic Scholar with pagination support"
                     .into(),
             ),
-            input_schema: json!({
-                "type": "object",
-                "properties": {
-                    "author_id": {
-                        "type": "string",
-                        "description": "Semantic Scholar author ID"
-                    },
-                    "fields": {
-                        "type": "array",
-                        "description": "List of fields to return for each paper. Default: title and year",
-                        "items": {
-                            "type": "string",
-                            "enum": [
-                                "title", "abstract", "year", "venue", "authors", "url", "paperId",
-                                "citationCount", "influentialCitationCount", "isOpenAccess",
-                                "openAccessPdf", "fieldsOfStudy", "s2FieldsOfStudy",
-                                "publicationTypes", "publicationDate", "journal", "externalIds"
-                            ]
-                        }
-                    },
-                    "offset": {
-                        "type": "integer",
-                        "description": "Number of papers to skip for pagination. Default: 0"
-                    },
-                    "limit": {
-                        "type": "integer",
-                        "description": "Maximum number of papers to return. Default: 100, Maximum: 1000"
-                    }
-                },
-                "required": ["author_id"]
-            }),
+            input_schema: input_schema::<AuthorPapersArguments>(),
         }
     }
 }
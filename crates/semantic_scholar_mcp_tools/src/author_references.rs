@@ -1,22 +1,75 @@
-use anyhow::{Result, anyhow};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use anyhow::Result;
 use async_trait::async_trait;
-use cache::{Cache, Query};
+use cache::Cache;
 use context_server::{Tool, ToolContent, ToolExecutor};
 use embed::Embed;
 use http_client::HttpClient;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
-use std::sync::Arc;
 
-use crate::utils::{RateLimiter, make_request};
+use crate::{
+    arguments::{input_schema, parse_arguments},
+    content::ToolOutput,
+    error::invalid_argument,
+    format::{Formatter, OutputFormat, render},
+    progress::Progress,
+    query_cache::{CacheKey, CacheLookup, cached_response, store_response},
+    utils::{RateLimiter, encode_path_segment, make_request, normalize_author_id},
+};
+
+/// Which side of an author's citation network to compute.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+enum NetworkDirection {
+    #[default]
+    CitedBy,
+    Cites,
+    Both,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct AuthorCitationNetworkArguments {
+    /// Semantic Scholar author ID, or a link to the author on semanticscholar.org
+    author_id: String,
+    /// Which side of the network to compute. Default: cited_by
+    direction: Option<NetworkDirection>,
+    /// Number of the author's most-cited papers to analyse. Default: 20, Maximum: 100
+    #[schemars(range(min = 1, max = 100))]
+    max_papers: Option<u64>,
+    /// Maximum number of citations/references to fetch per paper. Default: 100, Maximum: 1000
+    #[schemars(range(max = 1000))]
+    per_paper_limit: Option<u64>,
+    /// Number of authors to list for each direction. Default: 20
+    top: Option<u64>,
+    /// Maximum length of the output in characters. Results past it are left out, with a note saying how many. Default: the server's limit, if any
+    #[schemars(range(min = 1))]
+    max_output_chars: Option<usize>,
+    #[serde(default)]
+    output_format: OutputFormat,
+}
+
+#[derive(Default)]
+struct AuthorTally {
+    name: String,
+    count: u64,
+    papers: u64,
+}
 
-pub struct PaperReferencesTool {
+pub struct AuthorCitationNetworkTool {
     http_client: Arc<dyn HttpClient>,
     rate_limiter: Arc<RateLimiter>,
     cache: Arc<dyn Cache>,
     embed: Arc<dyn Embed>,
 }
 
-impl PaperReferencesTool {
+impl AuthorCitationNetworkTool {
     pub fn new(
         http_client: Arc<dyn HttpClient>,
         rate_limiter: Arc<RateLimiter>,
@@ -31,246 +84,348 @@ impl PaperReferencesTool {
         }
     }
 
-    fn format_references(&self, response: &Value) -> Result<String> {
-        if response.get("error").is_some() {
-            let message = response["error"]["message"]
-                .as_str()
-                .unwrap_or("Unknown error");
-            return Ok(format!("Error: {}", message));
-        }
-
-        let offset = response.get("offset").and_then(|o| o.as_u64()).unwrap_or(0);
-        let next_offset = response.get("next").and_then(|n| n.as_u64());
-
-        if let Some(data) = response.get("data").and_then(|d| d.as_array()) {
-            if data.is_empty() {
-                return Ok(String::from("No references found for this paper."));
-            }
-
-            let mut result = format!(
-                "Found {} referenced papers (offset: {}):\n\n",
-                data.len(),
-                offset
-            );
-
-            for (i, paper) in data.iter().enumerate() {
-                let title = paper
-                    .get("title")
-                    .and_then(|t| t.as_str())
-                    .unwrap_or("Unknown title");
-                let paper_id = paper
-                    .get("paperId")
-                    .and_then(|p| p.as_str())
-                    .unwrap_or("Unknown ID");
-
-                result.push_str(&format!(
-                    "{}. {} (ID: {})\n",
-                    i + 1 + offset as usize,
-                    title,
-                    paper_id
-                ));
-
-                if let Some(is_influential) = paper.get("isInfluential").and_then(|i| i.as_bool()) {
-                    if is_influential {
-                        result.push_str("   [INFLUENTIAL REFERENCE]\n");
-                    }
-                }
-
-                if let Some(authors) = paper.get("authors").and_then(|a| a.as_array()) {
-                    let author_names: Vec<&str> = authors
-                        .iter()
-                        .filter_map(|author| author.get("name").and_then(|n| n.as_str()))
-                        .collect();
-
-                    if !author_names.is_empty() {
-                        result.push_str(&format!("   Authors: {}\n", author_names.join(", ")));
-                    }
-                }
+    /// Pages through the links (citations or references) of each paper and
+    /// counts how often each linked author appears, skipping the author themselves.
+    async fn tally_linked_authors(
+        &self,
+        author_id: &str,
+        paper_ids: &[String],
+        link: &str,
+        per_paper_limit: u64,
+        progress: &Progress,
+    ) -> Result<HashMap<String, AuthorTally>> {
+        let nested_key = if link == "citations" {
+            "citingPaper"
+        } else {
+            "citedPaper"
+        };
 
-                if let Some(year) = paper.get("year").and_then(|y| y.as_u64()) {
-                    result.push_str(&format!("   Year: {}\n", year));
+        let mut tallies: HashMap<String, AuthorTally> = HashMap::new();
+
+        // Papers are fetched a few at a time, and tallied in order once all are in
+        let responses = self
+            .rate_limiter
+            .concurrently(paper_ids, |paper_id| async move {
+                let response = make_request(
+                    &self.http_client,
+                    &self.rate_limiter,
+                    Some(self.cache.as_ref()),
+                    &format!("/paper/{}/{}", encode_path_segment(paper_id), link),
+                    Some(&json!({ "fields": "authors", "limit": per_paper_limit })),
+                    None,
+                    None,
+                )
+                .await;
+                progress.advance(&format!("Fetched {} of paper {}", link, paper_id));
+                (paper_id, response)
+            })
+            .await;
+
+        for (paper_id, response) in responses {
+            let response = match response {
+                Ok(response) => response,
+                Err(err) => {
+                    tracing::warn!("Failed to fetch {} for paper {}: {}", link, paper_id, err);
+                    continue;
                 }
-
-                if let Some(venue) = paper.get("venue").and_then(|v| v.as_str()) {
-                    if !venue.is_empty() {
-                        result.push_str(&format!("   Venue: {}\n", venue));
+            };
+
+            // Tracks which linked authors were already seen for this paper
+            let mut seen_on_paper = HashSet::new();
+
+            for item in response
+                .get("data")
+                .and_then(|d| d.as_array())
+                .into_iter()
+                .flatten()
+            {
+                let paper = item.get(nested_key).unwrap_or(item);
+
+                for author in paper
+                    .get("authors")
+                    .and_then(|a| a.as_array())
+                    .into_iter()
+                    .flatten()
+                {
+                    let Some(id) = author.get("authorId").and_then(|id| id.as_str()) else {
+                        continue;
+                    };
+
+                    if id == author_id {
+                        continue;
                     }
-                }
 
-                if let Some(citation_count) = paper.get("citationCount").and_then(|c| c.as_u64()) {
-                    result.push_str(&format!("   Citations: {}\n", citation_count));
-                }
-
-                if let Some(contexts) = paper.get("contexts").and_then(|c| c.as_array()) {
-                    if !contexts.is_empty() {
-                        result.push_str("   Citation contexts:\n");
-
-                        for (idx, context) in contexts.iter().take(3).enumerate() {
-                            if let Some(text) = context.as_str() {
-                                result.push_str(&format!("     {}. \"{}\"\n", idx + 1, text));
-                            }
-                        }
-
-                        if contexts.len() > 3 {
-                            result.push_str(&format!(
-                                "     ... and {} more contexts\n",
-                                contexts.len() - 3
-                            ));
-                        }
+                    let tally = tallies.entry(id.to_string()).or_default();
+                    tally.count += 1;
+                    if seen_on_paper.insert(id.to_string()) {
+                        tally.papers += 1;
                     }
-                }
-
-                if let Some(intents) = paper.get("intents").and_then(|i| i.as_array()) {
-                    let intent_types: Vec<&str> = intents
-                        .iter()
-                        .filter_map(|intent| intent.as_str())
-                        .collect();
-
-                    if !intent_types.is_empty() {
-                        result.push_str(&format!(
-                            "   Citation intents: {}\n",
-                            intent_types.join(", ")
-                        ));
+                    if tally.name.is_empty() {
+                        tally.name = author
+                            .get("name")
+                            .and_then(|n| n.as_str())
+                            .unwrap_or("Unknown name")
+                            .to_string();
                     }
                 }
-
-                if let Some(url) = paper.get("url").and_then(|u| u.as_str()) {
-                    result.push_str(&format!("   URL: {}\n", url));
-                }
-
-                if i < data.len() - 1 {
-                    result.push_str("\n");
-                }
-            }
-
-            if let Some(next) = next_offset {
-                result.push_str(&format!("\nFor more references, use offset={}", next));
             }
-
-            Ok(result)
-        } else {
-            Ok(String::from(
-                "No references found or unexpected API response format.",
-            ))
         }
+
+        Ok(tallies)
     }
-}
 
-#[async_trait]
-impl ToolExecutor for PaperReferencesTool {
-    async fn execute(&self, arguments: Option<Value>) -> Result<Vec<ToolContent>> {
-        log::debug!("Executing PaperReferencesTool");
-        let args = arguments.ok_or_else(|| anyhow!("Missing arguments"))?;
+    /// Orders tallied authors by how often they appear, most frequent first.
+    fn rank(&self, tallies: HashMap<String, AuthorTally>) -> Vec<(String, AuthorTally)> {
+        let mut ranked: Vec<(String, AuthorTally)> = tallies.into_iter().collect();
+        ranked.sort_by(|a, b| {
+            b.1.count
+                .cmp(&a.1.count)
+                .then_with(|| a.1.name.cmp(&b.1.name))
+        });
+        ranked
+    }
 
-        let paper_id = args
-            .get("paper_id")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow!("Missing or invalid paper_id parameter"))?;
+    fn tally_json(&self, ranked: &[(String, AuthorTally)], top: usize) -> Value {
+        json!({
+            "distinctAuthors": ranked.len(),
+            "data": ranked
+                .iter()
+                .take(top)
+                .map(|(author_id, tally)| json!({
+                    "authorId": author_id,
+                    "name": tally.name,
+                    "citations": tally.count,
+                    "papers": tally.papers,
+                }))
+                .collect::<Vec<_>>(),
+        })
+    }
 
-        if paper_id.trim().is_empty() {
-            return Err(anyhow!("Paper ID cannot be empty"));
+    /// Lists a tally as built by [`Self::tally_json`].
+    fn format_tally(&self, f: &mut dyn Formatter, heading: &str, tally: &Value) {
+        let ranked = tally["data"]
+            .as_array()
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        if ranked.is_empty() {
+            f.heading(&format!("{}: none found", heading));
+            return;
         }
 
-        let fields = args.get("fields").cloned();
-
-        let offset = args.get("offset").and_then(|v| v.as_u64()).unwrap_or(0);
-
-        let limit = args.get("limit").and_then(|v| v.as_u64()).unwrap_or(100);
-
-        if limit > 1000 {
-            return Err(anyhow!("Limit cannot exceed 1000"));
+        f.heading(&format!(
+            "{} ({} distinct authors, showing top {}):",
+            heading,
+            tally["distinctAuthors"].as_u64().unwrap_or_default(),
+            ranked.len()
+        ));
+
+        for (i, author) in ranked.iter().enumerate() {
+            f.item(
+                i + 1,
+                &format!(
+                    "{} (ID: {})",
+                    author["name"].as_str().unwrap_or("Unknown name"),
+                    author["authorId"].as_str().unwrap_or_default()
+                ),
+            );
+            f.field(
+                "Citations",
+                &format!(
+                    "{} across {} of this author's papers",
+                    author["citations"].as_u64().unwrap_or_default(),
+                    author["papers"].as_u64().unwrap_or_default()
+                ),
+            );
         }
+    }
 
-        let mut params_map = serde_json::Map::new();
-        params_map.insert("offset".to_string(), json!(offset));
-        params_map.insert("limit".to_string(), json!(limit));
-
-        if let Some(f) = fields {
-            params_map.insert("fields".to_string(), f);
+    fn format_network(&self, f: &mut dyn Formatter, network: &Value) -> Result<()> {
+        let papers_analysed = network["papersAnalysed"].as_u64().unwrap_or_default();
+        if papers_analysed == 0 {
+            f.note("No papers found for this author.");
+            return Ok(());
         }
 
-        let params = Value::Object(params_map);
-
-        // Generate an embedding for the query
-        let embedding = self.embed.embed(&paper_id).await?;
+        f.heading(&format!(
+            "Citation network for author {} (based on their {} most-cited papers):",
+            network["authorId"].as_str().unwrap_or_default(),
+            papers_analysed
+        ));
 
-        // Check if we have a cached result for a similar query
-        let similar_queries = self.cache.search_similarity(&embedding)?;
+        if network["citedBy"].is_object() {
+            self.format_tally(f, "Authors who cite them most", &network["citedBy"]);
+        }
 
-        // Check for any cached queries with high similarity and matching action/params
-        for (cached_query, similarity) in similar_queries.iter() {
-            if similarity > &0.95 && cached_query.action == "paper_references" {
-                // Check if parameters match
-                if cached_query.params == Some(params.clone()) {
-                    log::debug!("Found cached result with similarity {}", similarity);
-                    return Ok(vec![ToolContent::Text {
-                        text: serde_json::from_value(cached_query.results.clone())?,
-                    }]);
-                }
-            }
+        if network["cites"].is_object() {
+            self.format_tally(f, "Authors they cite most", &network["cites"]);
         }
 
-        let result = make_request(
+        Ok(())
+    }
+
+    /// Samples the author's most-cited papers and tallies the authors linked
+    /// to them in `direction`, keeping the `top` of each side.
+    async fn fetch_network(
+        &self,
+        author_id: &str,
+        direction: NetworkDirection,
+        max_papers: u64,
+        per_paper_limit: u64,
+        top: usize,
+    ) -> Result<Value> {
+        // Most-cited papers carry most of the signal, so sample those first
+        let papers = make_request(
             &self.http_client,
             &self.rate_limiter,
-            &format!("/paper/{}/references", paper_id),
-            Some(&params),
+            Some(self.cache.as_ref()),
+            &format!("/author/{}/papers", encode_path_segment(author_id)),
+            Some(&json!({ "fields": "paperId,citationCount", "limit": 1000 })),
+            None,
             None,
         )
         .await?;
 
-        let formatted_result = self.format_references(&result)?;
+        let mut papers: Vec<(String, u64)> = papers
+            .get("data")
+            .and_then(|d| d.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|paper| {
+                let paper_id = paper.get("paperId").and_then(|p| p.as_str())?;
+                let citation_count = paper
+                    .get("citationCount")
+                    .and_then(|c| c.as_u64())
+                    .unwrap_or(0);
+                Some((paper_id.to_string(), citation_count))
+            })
+            .collect();
+
+        papers.sort_by(|a, b| b.1.cmp(&a.1));
+
+        let paper_ids: Vec<String> = papers
+            .into_iter()
+            .take(max_papers as usize)
+            .map(|(paper_id, _)| paper_id)
+            .collect();
+
+        let directions = if direction == NetworkDirection::Both {
+            2
+        } else {
+            1
+        };
+        let progress = Progress::new((paper_ids.len() * directions) as u64);
+
+        let citing = if !paper_ids.is_empty()
+            && matches!(
+                direction,
+                NetworkDirection::CitedBy | NetworkDirection::Both
+            ) {
+            let tallies = self
+                .tally_linked_authors(
+                    author_id,
+                    &paper_ids,
+                    "citations",
+                    per_paper_limit,
+                    &progress,
+                )
+                .await?;
+            Some(self.rank(tallies))
+        } else {
+            None
+        };
 
-        let query = Query {
-            action: "paper_references".into(),
-            text: paper_id.into(),
-            embedding,
-            params: Some(params),
-            results: json!(formatted_result),
+        let cited = if !paper_ids.is_empty()
+            && matches!(direction, NetworkDirection::Cites | NetworkDirection::Both)
+        {
+            let tallies = self
+                .tally_linked_authors(
+                    author_id,
+                    &paper_ids,
+                    "references",
+                    per_paper_limit,
+                    &progress,
+                )
+                .await?;
+            Some(self.rank(tallies))
+        } else {
+            None
         };
 
-        if let Err(err) = self.cache.store(query) {
-            log::warn!("Failed to store query in cache: {}", err);
+        let response = json!({
+            "authorId": author_id,
+            "papersAnalysed": paper_ids.len(),
+            "citedBy": citing.as_deref().map(|ranked| self.tally_json(ranked, top)),
+            "cites": cited.as_deref().map(|ranked| self.tally_json(ranked, top)),
+        });
+
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for AuthorCitationNetworkTool {
+    async fn execute(&self, arguments: Option<Value>) -> Result<Vec<ToolContent>> {
+        tracing::debug!("Executing AuthorCitationNetworkTool");
+        let args: AuthorCitationNetworkArguments = parse_arguments(arguments)?;
+        let author_id = normalize_author_id(&args.author_id);
+        let author_id = author_id.as_str();
+
+        if author_id.is_empty() {
+            return Err(invalid_argument("Author ID cannot be empty"));
         }
 
-        Ok(vec![ToolContent::Text {
-            text: formatted_result,
-        }])
+        let direction = args.direction.unwrap_or_default();
+
+        let max_papers = args.max_papers.unwrap_or(20);
+
+        let per_paper_limit = args.per_paper_limit.unwrap_or(100);
+
+        let top = args.top.unwrap_or(20) as usize;
+
+        let params = json!({
+            "direction": direction,
+            "max_papers": max_papers,
+            "per_paper_limit": per_paper_limit,
+            "top": top,
+        });
+
+        // The ID is part of the endpoint rather than the query parameters
+        let key = CacheKey::new("author_citation_network", &author_id)
+            .param("author_id", &author_id)
+            .params(&params);
+        let response = match cached_response(self.cache.as_ref(), self.embed.as_ref(), &key).await?
+        {
+            CacheLookup::Hit(response) => response,
+            CacheLookup::Miss(embedding) => {
+                let response = self
+                    .fetch_network(author_id, direction, max_papers, per_paper_limit, top)
+                    .await?;
+                store_response(self.cache.as_ref(), &key, embedding, &response);
+                response
+            }
+        };
+
+        let formatted_result = render(
+            args.output_format,
+            &response,
+            None,
+            args.max_output_chars,
+            |f| self.format_network(f, &response),
+        )?;
+
+        let output = ToolOutput::new(formatted_result, args.output_format, &response);
+
+        Ok(output.into_contents())
     }
 
     fn to_tool(&self) -> Tool {
         Tool {
-            name: "paper_references".into(),
-            description: Some("Get papers cited by a specific paper in Semantic Scholar".into()),
-            input_schema: json!({
-                "type": "object",
-                "properties": {
-                    "paper_id": {
-                        "type": "string",
-                        "description": "Paper identifier in one of the following formats: Semantic Scholar ID, DOI:doi, ARXIV:id, MAG:id, ACL:id, PMID:id, PMCID:id, URL:url"
-                    },
-                    "fields": {
-                        "type": "array",
-                        "description": "List of fields to return for each referenced paper. Default: paperId and title",
-                        "items": {
-                            "type": "string",
-                            "enum": [
-                                "title", "abstract", "year", "venue", "authors", "url", "paperId",
-                                "citationCount", "influentialCitationCount", "contexts", "intents", "isInfluential"
-                            ]
-                        }
-                    },
-                    "offset": {
-                        "type": "integer",
-                        "description": "Number of references to skip for pagination. Default: 0"
-                    },
-                    "limit": {
-                        "type": "integer",
-                        "description": "Maximum number of references to return. Default: 100, Maximum: 1000"
-                    }
-                },
-                "required": ["paper_id"]
-            }),
+            name: "author_citation_network".into(),
+            description: Some(
+                "Find which authors most frequently cite a given author, and whom that author cites most, by tallying the citations and references of their papers"
+                    .into(),
+            ),
+            input_schema: input_schema::<AuthorCitationNetworkArguments>(),
         }
     }
 }
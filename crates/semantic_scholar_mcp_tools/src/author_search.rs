@@ -1,19 +1,52 @@
-use anyhow::{Result, anyhow};
+use anyhow::Result;
 use async_trait::async_trait;
-use cache::{Cache, Query};
+use cache::Cache;
 use context_server::{Tool, ToolContent, ToolExecutor};
 use embed::Embed;
 use http_client::HttpClient;
+use models::{Author, Page};
+use schemars::JsonSchema;
+use serde::Deserialize;
 use serde_json::{Value, json};
 use std::sync::Arc;
 
-use crate::utils::{RateLimiter, make_request};
+use crate::{
+    arguments::{AuthorField, input_schema, parse_arguments},
+    content::ToolOutput,
+    error::invalid_argument,
+    format::{Formatter, OutputFormat, render},
+    query_cache::{CacheKey, CacheLookup, cached_response, store_response},
+    result_formatter::{DefaultEntryRenderer, EntryRenderer, RenderOptions, ResultFormatter},
+    utils::{RateLimiter, make_request},
+};
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct AuthorSearchArguments {
+    /// The name text to search for. The query will be matched against author names and their known aliases.
+    query: String,
+    /// List of fields to return for each author. Default: name and authorId
+    fields: Option<Vec<AuthorField>>,
+    /// Number of authors to skip for pagination. Default: 0
+    #[serde(default)]
+    offset: u64,
+    /// Maximum number of authors to return. Default: 100, Maximum: 1000
+    #[schemars(range(max = 1000))]
+    limit: Option<u64>,
+    /// Maximum length of the output in characters. Results past it are left out, with a note saying how many. Default: the server's limit, if any
+    #[schemars(range(min = 1))]
+    max_output_chars: Option<usize>,
+    #[serde(default)]
+    output_format: OutputFormat,
+}
 
 pub struct AuthorSearchTool {
     http_client: Arc<dyn HttpClient>,
     rate_limiter: Arc<RateLimiter>,
     cache: Arc<dyn Cache>,
     embed: Arc<dyn Embed>,
+    renderer: Arc<dyn EntryRenderer>,
+    formatter: Arc<dyn ResultFormatter<Page<Author>>>,
 }
 
 impl AuthorSearchTool {
@@ -28,177 +61,102 @@ impl AuthorSearchTool {
             rate_limiter,
             cache,
             embed,
+            renderer: Arc::new(DefaultEntryRenderer::new()),
+            formatter: Arc::new(AuthorSearchFormatter),
         }
     }
 
-    fn format_author_search(&self, response: &Value) -> Result<String> {
-        if response.get("error").is_some() {
-            let message = response["error"]["message"]
-                .as_str()
-                .unwrap_or("Unknown error");
-            return Ok(format!("Error: {}", message));
-        }
-
-        let total = response.get("total").and_then(|t| t.as_u64()).unwrap_or(0);
-        let offset = response.get("offset").and_then(|o| o.as_u64()).unwrap_or(0);
-        let next_offset = response.get("next").and_then(|n| n.as_u64());
-
-        if let Some(data) = response.get("data").and_then(|d| d.as_array()) {
-            if data.is_empty() {
-                return Ok(String::from("No authors found matching your query."));
-            }
-
-            let mut result = format!(
-                "Found {} total authors matching your query. Showing results {}-{}:\n\n",
-                total,
-                offset + 1,
-                offset + data.len() as u64
-            );
-
-            for (i, author) in data.iter().enumerate() {
-                let name = author
-                    .get("name")
-                    .and_then(|n| n.as_str())
-                    .unwrap_or("Unknown name");
-                let author_id = author
-                    .get("authorId")
-                    .and_then(|id| id.as_str())
-                    .unwrap_or("Unknown ID");
-
-                result.push_str(&format!(
-                    "{}. {} (ID: {})\n",
-                    i + 1 + offset as usize,
-                    name,
-                    author_id
-                ));
-
-                if let Some(affiliations) = author.get("affiliations").and_then(|a| a.as_array()) {
-                    let affiliation_list: Vec<&str> =
-                        affiliations.iter().filter_map(|aff| aff.as_str()).collect();
-
-                    if !affiliation_list.is_empty() {
-                        result.push_str(&format!(
-                            "   Affiliations: {}\n",
-                            affiliation_list.join(", ")
-                        ));
-                    }
-                }
-
-                if let Some(aliases) = author.get("aliases").and_then(|a| a.as_array()) {
-                    let alias_list: Vec<&str> =
-                        aliases.iter().filter_map(|alias| alias.as_str()).collect();
-
-                    if !alias_list.is_empty() {
-                        result.push_str(&format!("   Also known as: {}\n", alias_list.join(", ")));
-                    }
-                }
-
-                if let Some(paper_count) = author.get("paperCount").and_then(|p| p.as_u64()) {
-                    result.push_str(&format!("   Papers: {}\n", paper_count));
-                }
+    /// Writes the entries through `renderer` rather than the default.
+    pub fn with_renderer(mut self, renderer: Arc<dyn EntryRenderer>) -> Self {
+        self.renderer = renderer;
+        self
+    }
 
-                if let Some(citation_count) = author.get("citationCount").and_then(|c| c.as_u64()) {
-                    result.push_str(&format!("   Citations: {}\n", citation_count));
-                }
+    pub fn with_formatter(mut self, formatter: Arc<dyn ResultFormatter<Page<Author>>>) -> Self {
+        self.formatter = formatter;
+        self
+    }
+}
 
-                if let Some(h_index) = author.get("hIndex").and_then(|h| h.as_u64()) {
-                    result.push_str(&format!("   h-index: {}\n", h_index));
-                }
+/// Lays out the output of `author_search`.
+pub struct AuthorSearchFormatter;
+
+impl ResultFormatter<Page<Author>> for AuthorSearchFormatter {
+    fn format(
+        &self,
+        f: &mut dyn Formatter,
+        renderer: &dyn EntryRenderer,
+        page: &Page<Author>,
+        _options: &RenderOptions,
+    ) -> Result<()> {
+        if page.data.is_empty() {
+            f.note("No authors found matching your query.");
+            return Ok(());
+        }
 
-                if let Some(homepage) = author.get("homepage").and_then(|h| h.as_str()) {
-                    if !homepage.is_empty() {
-                        result.push_str(&format!("   Homepage: {}\n", homepage));
+        let total = page.total.unwrap_or(0);
+        let offset = page.offset.unwrap_or(0);
+
+        f.heading(&format!(
+            "Found {} total authors matching your query. Showing results {}-{}:",
+            total,
+            offset + 1,
+            offset + page.data.len() as u64
+        ));
+
+        for (i, author) in page.data.iter().enumerate() {
+            f.item(i + 1 + offset as usize, &renderer.author_title(author));
+            renderer.author(f, author);
+
+            if let Some(papers) = &author.papers {
+                if !papers.is_empty() {
+                    let mut shown: Vec<String> = papers
+                        .iter()
+                        .take(3)
+                        .map(|paper| renderer.paper_line(paper))
+                        .collect();
+
+                    if papers.len() > 3 {
+                        shown.push(format!("... and {} more papers", papers.len() - 3));
                     }
-                }
-
-                if let Some(url) = author.get("url").and_then(|u| u.as_str()) {
-                    result.push_str(&format!("   Semantic Scholar URL: {}\n", url));
-                }
 
-                if let Some(papers) = author.get("papers").and_then(|p| p.as_array()) {
-                    if !papers.is_empty() {
-                        result.push_str(&format!(
-                            "   Representative papers (showing up to 3 of {}):\n",
+                    f.list(
+                        &format!(
+                            "Representative papers (showing up to 3 of {})",
                             papers.len()
-                        ));
-
-                        for (pi, paper) in papers.iter().take(3).enumerate() {
-                            let paper_title = paper
-                                .get("title")
-                                .and_then(|t| t.as_str())
-                                .unwrap_or("Unknown title");
-                            let paper_id = paper
-                                .get("paperId")
-                                .and_then(|id| id.as_str())
-                                .unwrap_or("Unknown ID");
-
-                            result.push_str(&format!(
-                                "     {}. {} (ID: {})\n",
-                                pi + 1,
-                                paper_title,
-                                paper_id
-                            ));
-
-                            if let Some(year) = paper.get("year").and_then(|y| y.as_u64()) {
-                                result.push_str(&format!("        Year: {}\n", year));
-                            }
-
-                            if let Some(venue) = paper.get("venue").and_then(|v| v.as_str()) {
-                                if !venue.is_empty() {
-                                    result.push_str(&format!("        Venue: {}\n", venue));
-                                }
-                            }
-                        }
-
-                        if papers.len() > 3 {
-                            result.push_str(&format!(
-                                "     ... and {} more papers\n",
-                                papers.len() - 3
-                            ));
-                        }
-                    }
+                        ),
+                        &shown,
+                    );
                 }
-
-                if i < data.len() - 1 {
-                    result.push_str("\n");
-                }
-            }
-
-            if let Some(next) = next_offset {
-                result.push_str(&format!("\nFor more authors, use offset={}", next));
             }
+        }
 
-            Ok(result)
-        } else {
-            Ok(String::from(
-                "No authors found or unexpected API response format.",
-            ))
+        if let Some(next) = page.next {
+            f.note(&format!("For more authors, use offset={}", next));
         }
+
+        Ok(())
     }
 }
 
 #[async_trait]
 impl ToolExecutor for AuthorSearchTool {
     async fn execute(&self, arguments: Option<Value>) -> Result<Vec<ToolContent>> {
-        log::debug!("Executing AuthorSearchTool");
-        let args = arguments.ok_or_else(|| anyhow!("Missing arguments"))?;
-
-        let query = args
-            .get("query")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow!("Missing or invalid query parameter"))?;
+        tracing::debug!("Executing AuthorSearchTool");
+        let AuthorSearchArguments {
+            query,
+            fields,
+            offset,
+            limit,
+            max_output_chars,
+            output_format,
+        } = parse_arguments(arguments)?;
 
         if query.trim().is_empty() {
-            return Err(anyhow!("Query string cannot be empty"));
+            return Err(invalid_argument("Query string cannot be empty"));
         }
 
-        let fields = args.get("fields").cloned();
-        let offset = args.get("offset").and_then(|v| v.as_u64()).unwrap_or(0);
-        let limit = args.get("limit").and_then(|v| v.as_u64()).unwrap_or(100);
-
-        if limit > 1000 {
-            return Err(anyhow!("Limit cannot exceed 1000"));
-        }
+        let limit = limit.unwrap_or(100);
 
         let mut params_map = serde_json::Map::new();
         params_map.insert("query".to_string(), json!(query));
@@ -206,92 +164,46 @@ impl ToolExecutor for AuthorSearchTool {
         params_map.insert("limit".to_string(), json!(limit));
 
         if let Some(f) = fields {
-            params_map.insert("fields".to_string(), f);
+            params_map.insert("fields".to_string(), json!(f));
         }
 
         let params = Value::Object(params_map);
 
-        // Generate an embedding for the query
-        let embedding = self.embed.embed(&query).await?;
-
-        // Check if we have a cached result for a similar query
-        let similar_queries = self.cache.search_similarity(&embedding)?;
-
-        // Check for any cached queries with high similarity and matching action/params
-        for (cached_query, similarity) in similar_queries.iter() {
-            if similarity > &0.95 && cached_query.action == "author_search" {
-                // Check if parameters match
-                if cached_query.params == Some(params.clone()) {
-                    log::debug!("Found cached result with similarity {}", similarity);
-                    return Ok(vec![ToolContent::Text {
-                        text: serde_json::from_value(cached_query.results.clone())?,
-                    }]);
-                }
+        let key = CacheKey::new("author_search", &query).params(&params);
+        let result = match cached_response(self.cache.as_ref(), self.embed.as_ref(), &key).await? {
+            CacheLookup::Hit(result) => result,
+            CacheLookup::Miss(embedding) => {
+                let result = make_request(
+                    &self.http_client,
+                    &self.rate_limiter,
+                    Some(self.cache.as_ref()),
+                    "/author/search",
+                    Some(&params),
+                    None,
+                    None,
+                )
+                .await?;
+                store_response(self.cache.as_ref(), &key, embedding, &result);
+                result
             }
-        }
-
-        let result = make_request(
-            &self.http_client,
-            &self.rate_limiter,
-            "/author/search",
-            Some(&params),
-            None,
-        )
-        .await?;
-
-        let formatted_result = self.format_author_search(&result)?;
-
-        let query = Query {
-            action: "author_search".into(),
-            text: query.into(),
-            embedding,
-            params: Some(params),
-            results: json!(formatted_result),
         };
 
-        if let Err(err) = self.cache.store(query) {
-            log::warn!("Failed to store query in cache: {}", err);
-        }
+        let page: Page<Author> = serde_json::from_value(result.clone())?;
+        let formatted_result = render(output_format, &result, None, max_output_chars, |f| {
+            self.formatter
+                .format(f, self.renderer.as_ref(), &page, &RenderOptions::default())
+        })?;
+
+        let output = ToolOutput::new(formatted_result, output_format, &result);
 
-        Ok(vec![ToolContent::Text {
-            text: formatted_result,
-        }])
+        Ok(output.into_contents())
     }
 
     fn to_tool(&self) -> Tool {
         Tool {
             name: "author_search".into(),
             description: Some("Search for authors by name on Semantic Scholar".into()),
-            input_schema: json!({
-                "type": "object",
-                "properties": {
-                    "query": {
-                        "type": "string",
-                        "description": "The name text to search for. The query will be matched against author names and their known aliases."
-                    },
-                    "fields": {
-                        "type": "array",
-                        "description": "List of fields to return for each author. Default: name and authorId",
-                        "items": {
-                            "type": "string",
-                            "enum": [
-                                "name", "aliases", "url", "authorId", "affiliations", "homepage",
-                                "paperCount", "citationCount", "hIndex", "papers", "papers.year",
-                                "papers.authors", "papers.abstract", "papers.venue", "papers.citations"
-                            ]
-                        }
-                    },
-                    "offset": {
-                        "type": "integer",
-                        "description": "Number of authors to skip for pagination. Default: 0"
-                    },
-                    "limit": {
-                        "type": "integer",
-                        "description": "Maximum number of authors to return. Default: 100, Maximum: 1000"
-                    }
-                },
-                "required": ["query"]
-            }),
+            input_schema: input_schema::<AuthorSearchArguments>(),
         }
     }
 }
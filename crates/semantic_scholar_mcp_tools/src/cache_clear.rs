@@ -0,0 +1,92 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use cache::{Cache, ClearFilter};
+use context_server::{Tool, ToolContent, ToolExecutor};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{
+    arguments::{input_schema, parse_arguments},
+    error::invalid_argument,
+};
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct CacheClearArguments {
+    /// Only clear cached results of this tool, such as paper_search or paper_details
+    action: Option<String>,
+    /// Only clear entries mentioning an ID containing this text, such as a paper or author ID. Clears the cached paper or author record along with tool results and raw API responses holding the ID
+    id: Option<String>,
+    /// Only clear entries stored more than this many hours ago
+    older_than_hours: Option<u64>,
+    /// Clear the whole cache. Required when no other filter is given
+    #[serde(default)]
+    all: bool,
+}
+
+pub struct CacheClearTool {
+    cache: Arc<dyn Cache>,
+}
+
+impl CacheClearTool {
+    pub fn new(cache: Arc<dyn Cache>) -> Self {
+        Self { cache }
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for CacheClearTool {
+    async fn execute(&self, arguments: Option<Value>) -> Result<Vec<ToolContent>> {
+        let args: CacheClearArguments = parse_arguments(arguments)?;
+        tracing::debug!("Executing CacheClearTool");
+
+        let filter = ClearFilter {
+            action: args.action.filter(|action| !action.trim().is_empty()),
+            id_contains: args.id.filter(|id| !id.trim().is_empty()),
+            stored_before: args.older_than_hours.map(|hours| {
+                chrono::Utc::now().naive_utc() - chrono::Duration::hours(hours as i64)
+            }),
+        };
+
+        let filtered = filter.action.is_some()
+            || filter.id_contains.is_some()
+            || filter.stored_before.is_some();
+        if !filtered && !args.all {
+            return Err(invalid_argument(
+                "Give action, id or older_than_hours to clear part of the cache, or set all to true to clear all of it",
+            ));
+        }
+
+        let cleared = self.cache.clear(&filter)?;
+        tracing::info!("Cleared {} cache entries", cleared.total());
+
+        let text = if cleared.total() == 0 {
+            String::from("No cache entries matched.")
+        } else {
+            format!(
+                "Cleared {} cache entries:\n- Tool results: {}\n- Raw API responses: {}\n- Papers: {}\n- Authors: {}\n\nThe next lookups reach the Semantic Scholar API.",
+                cleared.total(),
+                cleared.queries,
+                cleared.responses,
+                cleared.papers,
+                cleared.authors
+            )
+        };
+
+        Ok(vec![ToolContent::Text { text }])
+    }
+
+    fn to_tool(&self) -> Tool {
+        Tool {
+            name: "cache_clear".into(),
+            description: Some(
+                "Clear cached results so the next lookups fetch fresh data, such as current citation counts. Clear everything, or only the results of one tool, entries mentioning a paper or author ID, or entries older than some hours"
+                    .into(),
+            ),
+            input_schema: input_schema::<CacheClearArguments>(),
+        }
+    }
+}
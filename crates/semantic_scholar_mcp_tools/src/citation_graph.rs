@@ -0,0 +1,524 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use anyhow::Result;
+use cache::Cache;
+use http_client::HttpClient;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+use crate::{
+    arguments::PaperField,
+    error::invalid_argument,
+    progress::Progress,
+    utils::{RateLimiter, encode_path_segment, make_request, normalize_paper_id},
+};
+
+/// Most papers a walk may start from.
+const MAX_SEEDS: usize = 20;
+
+/// Which links of each paper a walk follows.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphDirection {
+    /// Papers citing each paper
+    Citations,
+    /// Papers each paper cites
+    References,
+    #[default]
+    Both,
+}
+
+impl GraphDirection {
+    fn links(&self) -> &'static [Link] {
+        match self {
+            GraphDirection::Citations => &[Link::Citations],
+            GraphDirection::References => &[Link::References],
+            GraphDirection::Both => &[Link::Citations, Link::References],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Link {
+    Citations,
+    References,
+}
+
+impl Link {
+    fn endpoint(&self) -> &'static str {
+        match self {
+            Link::Citations => "citations",
+            Link::References => "references",
+        }
+    }
+
+    /// The member of each returned record holding the linked paper.
+    fn nested_key(&self) -> &'static str {
+        match self {
+            Link::Citations => "citingPaper",
+            Link::References => "citedPaper",
+        }
+    }
+}
+
+/// A paper reached by a walk, with the fields requested for it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphNode {
+    pub paper_id: String,
+    /// Hops from the nearest seed, 0 for the seeds themselves
+    pub hop: u32,
+    #[serde(flatten)]
+    pub fields: Map<String, Value>,
+}
+
+impl GraphNode {
+    pub fn title(&self) -> &str {
+        self.fields
+            .get("title")
+            .and_then(Value::as_str)
+            .unwrap_or("Unknown title")
+    }
+
+    /// The node as a paper record, for writing it like any other paper.
+    pub fn paper(&self) -> Value {
+        let mut paper = self.fields.clone();
+        paper.insert("paperId".into(), json!(self.paper_id));
+        Value::Object(paper)
+    }
+}
+
+/// A citation: `source` cites `target`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct GraphEdge {
+    pub source: String,
+    pub target: String,
+}
+
+/// A citation found while expanding a paper, with the paper at its other end.
+pub struct Linked {
+    pub edge: GraphEdge,
+    pub paper: GraphNode,
+}
+
+/// The papers a walk reached and the citations between them. Only citations
+/// between two papers of the graph are kept.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CitationGraph {
+    /// The papers the walk started from, by Semantic Scholar ID
+    pub seeds: Vec<String>,
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+    /// Seed IDs that didn't resolve to a paper
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unresolved: Vec<String>,
+}
+
+impl CitationGraph {
+    pub fn node(&self, paper_id: &str) -> Option<&GraphNode> {
+        self.nodes.iter().find(|node| node.paper_id == paper_id)
+    }
+
+    /// The edges as pairs of node positions, citing paper first.
+    fn edge_indices(&self) -> Vec<(usize, usize)> {
+        let index: HashMap<&str, usize> = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| (node.paper_id.as_str(), i))
+            .collect();
+        self.edges
+            .iter()
+            .filter_map(|edge| {
+                Some((
+                    *index.get(edge.source.as_str())?,
+                    *index.get(edge.target.as_str())?,
+                ))
+            })
+            .collect()
+    }
+
+    /// The PageRank of each node, in the order of `nodes`, with rank flowing
+    /// from each paper to the papers it cites. Papers citing nothing within
+    /// the graph spread their rank over every paper. The ranks sum to 1.
+    pub fn pagerank(&self, damping: f64) -> Vec<f64> {
+        let n = self.nodes.len();
+        if n == 0 {
+            return Vec::new();
+        }
+        let edges = self.edge_indices();
+        let mut out_degree = vec![0usize; n];
+        for &(source, _) in &edges {
+            out_degree[source] += 1;
+        }
+
+        let mut rank = vec![1.0 / n as f64; n];
+        for _ in 0..MAX_ITERATIONS {
+            let dangling: f64 = (0..n)
+                .filter(|&i| out_degree[i] == 0)
+                .map(|i| rank[i])
+                .sum();
+            let base = (1.0 - damping + damping * dangling) / n as f64;
+            let mut next = vec![base; n];
+            for &(source, target) in &edges {
+                next[target] += damping * rank[source] / out_degree[source] as f64;
+            }
+
+            let change: f64 = rank.iter().zip(&next).map(|(a, b)| (a - b).abs()).sum();
+            rank = next;
+            if change < TOLERANCE {
+                break;
+            }
+        }
+        rank
+    }
+
+    /// The HITS hub and authority scores of each node, in the order of
+    /// `nodes`: a good authority is cited by good hubs, and a good hub cites
+    /// good authorities. Each set of scores has unit length.
+    pub fn hits(&self) -> (Vec<f64>, Vec<f64>) {
+        let n = self.nodes.len();
+        let edges = self.edge_indices();
+        let mut hubs = vec![1.0; n];
+        let mut authorities = vec![1.0; n];
+        normalize(&mut hubs);
+        normalize(&mut authorities);
+
+        for _ in 0..MAX_ITERATIONS {
+            let mut next_authorities = vec![0.0; n];
+            for &(source, target) in &edges {
+                next_authorities[target] += hubs[source];
+            }
+            normalize(&mut next_authorities);
+
+            let mut next_hubs = vec![0.0; n];
+            for &(source, target) in &edges {
+                next_hubs[source] += next_authorities[target];
+            }
+            normalize(&mut next_hubs);
+
+            let change: f64 = hubs
+                .iter()
+                .zip(&next_hubs)
+                .chain(authorities.iter().zip(&next_authorities))
+                .map(|(a, b)| (a - b).abs())
+                .sum();
+            hubs = next_hubs;
+            authorities = next_authorities;
+            if change < TOLERANCE {
+                break;
+            }
+        }
+        (hubs, authorities)
+    }
+}
+
+/// Iterations after which PageRank and HITS stop, converged or not.
+const MAX_ITERATIONS: usize = 100;
+/// Total change in scores below which an iteration counts as converged.
+const TOLERANCE: f64 = 1e-9;
+
+/// Scales scores to unit length, leaving all-zero scores as they are.
+fn normalize(scores: &mut [f64]) {
+    let norm = scores.iter().map(|score| score * score).sum::<f64>().sqrt();
+    if norm > 0.0 {
+        for score in scores {
+            *score /= norm;
+        }
+    }
+}
+
+/// Normalizes the IDs of the papers a walk starts from, checking there are
+/// some but not too many.
+pub fn seed_ids(paper_ids: &[String]) -> Result<Vec<String>> {
+    let paper_ids: Vec<String> = paper_ids
+        .iter()
+        .map(|s| normalize_paper_id(s))
+        .filter(|s| !s.is_empty())
+        .collect();
+
+    if paper_ids.is_empty() {
+        return Err(invalid_argument("Must provide at least one paper ID"));
+    }
+
+    if paper_ids.len() > MAX_SEEDS {
+        return Err(invalid_argument(format!(
+            "Cannot start from more than {} papers at once",
+            MAX_SEEDS
+        )));
+    }
+
+    Ok(paper_ids)
+}
+
+/// How far a walk reaches from its seeds.
+#[derive(Debug, Clone)]
+pub struct WalkOptions {
+    pub direction: GraphDirection,
+    pub hops: u32,
+    /// Citations or references fetched for each paper
+    pub per_paper_limit: u64,
+    /// New papers kept at each hop, the most cited first
+    pub per_hop_limit: usize,
+    /// Fields fetched for every paper, besides its ID, title and citation count
+    pub fields: Vec<PaperField>,
+}
+
+/// Walks the citation graph out from a set of papers, one hop at a time.
+pub struct GraphWalker {
+    http_client: Arc<dyn HttpClient>,
+    rate_limiter: Arc<RateLimiter>,
+    cache: Arc<dyn Cache>,
+}
+
+impl GraphWalker {
+    pub fn new(
+        http_client: Arc<dyn HttpClient>,
+        rate_limiter: Arc<RateLimiter>,
+        cache: Arc<dyn Cache>,
+    ) -> Self {
+        Self {
+            http_client,
+            rate_limiter,
+            cache,
+        }
+    }
+
+    /// Fetches the seeds, then at each hop the citations and/or references of
+    /// the papers added by the previous one. Of the papers a hop finds, the
+    /// `per_hop_limit` most cited join the graph and are expanded next.
+    pub async fn walk(&self, seeds: &[String], options: &WalkOptions) -> Result<CitationGraph> {
+        let fields = Self::fields(&options.fields);
+        let mut graph = CitationGraph::default();
+
+        let mut index: HashMap<String, usize> = HashMap::new();
+        for (seed, node) in seeds.iter().zip(self.resolve(seeds, &fields).await?) {
+            match node {
+                Some(node) if !index.contains_key(&node.paper_id) => {
+                    index.insert(node.paper_id.clone(), graph.nodes.len());
+                    graph.seeds.push(node.paper_id.clone());
+                    graph.nodes.push(node);
+                }
+                Some(_) => {}
+                None => graph.unresolved.push(seed.clone()),
+            }
+        }
+
+        let mut edges: HashSet<GraphEdge> = HashSet::new();
+        let mut frontier = graph.seeds.clone();
+        let progress = Progress::new(0);
+
+        for hop in 1..=options.hops {
+            if frontier.is_empty() {
+                break;
+            }
+
+            let links = self
+                .expand(
+                    &frontier,
+                    options.direction,
+                    options.per_paper_limit,
+                    &fields,
+                    hop,
+                    &progress,
+                )
+                .await;
+
+            // Papers found at this hop, and the citations leading to them
+            let mut found: HashMap<String, GraphNode> = HashMap::new();
+            let mut pending: Vec<GraphEdge> = Vec::new();
+
+            for Linked { edge, paper } in links {
+                if index.contains_key(&paper.paper_id) {
+                    edges.insert(edge);
+                } else {
+                    found.entry(paper.paper_id.clone()).or_insert(paper);
+                    pending.push(edge);
+                }
+            }
+
+            let found = most_cited(found.into_values().collect(), options.per_hop_limit);
+
+            frontier = found.iter().map(|node| node.paper_id.clone()).collect();
+            for node in found {
+                index.insert(node.paper_id.clone(), graph.nodes.len());
+                graph.nodes.push(node);
+            }
+
+            // Citations to papers that didn't make the cut are dropped
+            edges.extend(pending.into_iter().filter(|edge| {
+                index.contains_key(&edge.source) && index.contains_key(&edge.target)
+            }));
+        }
+
+        let mut edges: Vec<GraphEdge> = edges.into_iter().collect();
+        edges.sort_by_key(|edge| (index[&edge.source], index[&edge.target]));
+        graph.edges = edges;
+
+        Ok(graph)
+    }
+
+    /// Looks papers up through the batch endpoint, as nodes at hop 0 in the
+    /// order of the IDs, with None for IDs that don't resolve.
+    pub async fn resolve(
+        &self,
+        paper_ids: &[String],
+        fields: &[PaperField],
+    ) -> Result<Vec<Option<GraphNode>>> {
+        let resolved = make_request(
+            &self.http_client,
+            &self.rate_limiter,
+            Some(self.cache.as_ref()),
+            "/paper/batch",
+            Some(&json!({ "fields": fields })),
+            Some(&json!({ "ids": paper_ids })),
+            None,
+        )
+        .await?;
+
+        Ok(resolved
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|paper| Self::node(paper, 0))
+            .collect())
+    }
+
+    /// Fetches the citations and/or references of each paper, a few papers at
+    /// a time, returning every citation found with the paper at its other
+    /// end as a node at `hop`. Papers whose links fail to load are skipped
+    /// with a warning.
+    pub async fn expand(
+        &self,
+        paper_ids: &[String],
+        direction: GraphDirection,
+        per_paper_limit: u64,
+        fields: &[PaperField],
+        hop: u32,
+        progress: &Progress,
+    ) -> Vec<Linked> {
+        let requests: Vec<(&String, Link)> = paper_ids
+            .iter()
+            .flat_map(|paper_id| direction.links().iter().map(move |link| (paper_id, *link)))
+            .collect();
+        progress.add_steps(requests.len() as u64);
+
+        let responses = self
+            .rate_limiter
+            .concurrently(requests, |(paper_id, link)| async move {
+                let response = make_request(
+                    &self.http_client,
+                    &self.rate_limiter,
+                    Some(self.cache.as_ref()),
+                    &format!(
+                        "/paper/{}/{}",
+                        encode_path_segment(paper_id),
+                        link.endpoint()
+                    ),
+                    Some(&json!({ "fields": fields, "limit": per_paper_limit })),
+                    None,
+                    None,
+                )
+                .await;
+                progress.advance(&format!(
+                    "Hop {}: fetched {} of paper {}",
+                    hop,
+                    link.endpoint(),
+                    paper_id
+                ));
+                (paper_id, link, response)
+            })
+            .await;
+
+        let mut links = Vec::new();
+        for (paper_id, link, response) in responses {
+            let response = match response {
+                Ok(response) => response,
+                Err(err) => {
+                    tracing::warn!(
+                        "Failed to fetch {} for paper {}: {}",
+                        link.endpoint(),
+                        paper_id,
+                        err
+                    );
+                    continue;
+                }
+            };
+
+            for record in response
+                .get("data")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+            {
+                let Some(paper) = record
+                    .get(link.nested_key())
+                    .and_then(|paper| Self::node(paper, hop))
+                else {
+                    continue;
+                };
+
+                let edge = match link {
+                    Link::Citations => GraphEdge {
+                        source: paper.paper_id.clone(),
+                        target: paper_id.clone(),
+                    },
+                    Link::References => GraphEdge {
+                        source: paper_id.clone(),
+                        target: paper.paper_id.clone(),
+                    },
+                };
+                links.push(Linked { edge, paper });
+            }
+        }
+        links
+    }
+
+    /// The fields requested for every paper: the ID, title and citation
+    /// count walks rely on, followed by the requested ones.
+    pub fn fields(requested: &[PaperField]) -> Vec<PaperField> {
+        let mut fields = vec![
+            PaperField::PaperId,
+            PaperField::Title,
+            PaperField::CitationCount,
+        ];
+        for field in requested {
+            if !fields.contains(field) {
+                fields.push(*field);
+            }
+        }
+        fields
+    }
+
+    fn node(paper: &Value, hop: u32) -> Option<GraphNode> {
+        let mut fields = paper.as_object()?.clone();
+        let paper_id = fields.remove("paperId")?.as_str()?.to_string();
+        Some(GraphNode {
+            paper_id,
+            hop,
+            fields,
+        })
+    }
+}
+
+/// The `limit` most cited of a set of papers, most cited first.
+pub fn most_cited(mut nodes: Vec<GraphNode>, limit: usize) -> Vec<GraphNode> {
+    nodes.sort_by(|a, b| {
+        citation_count(b)
+            .cmp(&citation_count(a))
+            .then_with(|| a.paper_id.cmp(&b.paper_id))
+    });
+    nodes.truncate(limit);
+    nodes
+}
+
+fn citation_count(node: &GraphNode) -> u64 {
+    node.fields
+        .get("citationCount")
+        .and_then(Value::as_u64)
+        .unwrap_or(0)
+}
@@ -0,0 +1,364 @@
+use std::{collections::HashMap, sync::Arc};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use cache::Cache;
+use context_server::{Tool, ToolContent, ToolExecutor};
+use embed::Embed;
+use http_client::HttpClient;
+use models::Paper;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::{
+    arguments::{PaperField, input_schema, parse_arguments},
+    citation_graph::{GraphDirection, GraphNode, GraphWalker, Linked, most_cited},
+    content::ToolOutput,
+    error::{Error, invalid_argument},
+    format::{Formatter, OutputFormat, render},
+    progress::Progress,
+    query_cache::{CacheKey, CacheLookup, cached_response, store_response},
+    result_formatter::{DefaultEntryRenderer, EntryRenderer, RenderOptions, ResultFormatter},
+    utils::{RateLimiter, normalize_paper_id},
+};
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct CitationPathArguments {
+    /// The paper at the start of the chain, which builds on the other: it cites a paper that cites a paper ... that cites to_paper_id. Accepts any of the formats paper_details accepts
+    from_paper_id: String,
+    /// The paper at the end of the chain, the earlier work being built on
+    to_paper_id: String,
+    /// Maximum number of citations in the chain. Default: 4, Maximum: 6
+    #[schemars(range(min = 1, max = 6))]
+    max_depth: Option<u32>,
+    /// Maximum number of citations/references to fetch per paper. Default: 100, Maximum: 1000
+    #[schemars(range(min = 1, max = 1000))]
+    per_paper_limit: Option<u64>,
+    /// Maximum number of papers to expand at each step of the search, the most cited first. Default: 50, Maximum: 500
+    #[schemars(range(min = 1, max = 500))]
+    frontier_limit: Option<u64>,
+    /// Fields to return for each paper of the chain, besides its ID, title and citation count. Default: year and authors
+    fields: Option<Vec<PaperField>>,
+    /// Maximum length of the output in characters. Results past it are left out, with a note saying how many. Default: the server's limit, if any
+    #[schemars(range(min = 1))]
+    max_output_chars: Option<usize>,
+    #[serde(default)]
+    output_format: OutputFormat,
+}
+
+/// The chain of citations found between two papers, if any.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CitationPath {
+    pub from: String,
+    pub to: String,
+    pub max_depth: u32,
+    /// The papers of the chain, from the citing end, each citing the next;
+    /// empty when no chain was found
+    pub path: Vec<Value>,
+    /// How many papers the search reached
+    pub papers_searched: usize,
+}
+
+/// One end of a bidirectional search.
+struct SearchSide {
+    /// For every paper reached, the paper it was reached from
+    parents: HashMap<String, Option<String>>,
+    frontier: Vec<String>,
+}
+
+impl SearchSide {
+    fn new(paper_id: &str) -> Self {
+        Self {
+            parents: HashMap::from([(paper_id.to_string(), None)]),
+            frontier: vec![paper_id.to_string()],
+        }
+    }
+
+    /// The papers from `paper_id` back to where this side started.
+    fn trail(&self, paper_id: &str) -> Vec<String> {
+        let mut trail = vec![paper_id.to_string()];
+        let mut current = paper_id;
+        while let Some(Some(parent)) = self.parents.get(current) {
+            trail.push(parent.clone());
+            current = parent;
+        }
+        trail
+    }
+}
+
+pub struct CitationPathTool {
+    walker: GraphWalker,
+    cache: Arc<dyn Cache>,
+    embed: Arc<dyn Embed>,
+    renderer: Arc<dyn EntryRenderer>,
+    formatter: Arc<dyn ResultFormatter<CitationPath>>,
+}
+
+impl CitationPathTool {
+    pub fn new(
+        http_client: Arc<dyn HttpClient>,
+        rate_limiter: Arc<RateLimiter>,
+        cache: Arc<dyn Cache>,
+        embed: Arc<dyn Embed>,
+    ) -> Self {
+        Self {
+            walker: GraphWalker::new(http_client, rate_limiter, cache.clone()),
+            cache,
+            embed,
+            renderer: Arc::new(DefaultEntryRenderer::new()),
+            formatter: Arc::new(CitationPathFormatter),
+        }
+    }
+
+    /// Writes the entries through `renderer` rather than the default.
+    pub fn with_renderer(mut self, renderer: Arc<dyn EntryRenderer>) -> Self {
+        self.renderer = renderer;
+        self
+    }
+
+    pub fn with_formatter(mut self, formatter: Arc<dyn ResultFormatter<CitationPath>>) -> Self {
+        self.formatter = formatter;
+        self
+    }
+
+    /// Searches forward from `from` along references and backward from `to`
+    /// along citations, a step at a time on whichever side has fewer papers
+    /// to expand, until the two meet or the chain would grow past
+    /// `max_depth` citations.
+    async fn search(
+        &self,
+        from: &str,
+        to: &str,
+        max_depth: u32,
+        per_paper_limit: u64,
+        frontier_limit: usize,
+        fields: &[PaperField],
+    ) -> Result<CitationPath> {
+        let fields = GraphWalker::fields(fields);
+        let resolved = self
+            .walker
+            .resolve(&[from.to_string(), to.to_string()], &fields)
+            .await?;
+        let mut ends = Vec::new();
+        for (paper_id, node) in [from, to].into_iter().zip(resolved) {
+            ends.push(
+                node.ok_or_else(|| Error::NotFound(format!("Paper not found: {}", paper_id)))?,
+            );
+        }
+        let (from, to) = (ends[0].paper_id.clone(), ends[1].paper_id.clone());
+        if from == to {
+            return Err(invalid_argument("The two papers must be different"));
+        }
+        let mut nodes: HashMap<String, GraphNode> = ends
+            .into_iter()
+            .map(|node| (node.paper_id.clone(), node))
+            .collect();
+
+        let mut forward = SearchSide::new(&from);
+        let mut backward = SearchSide::new(&to);
+        let progress = Progress::new(0);
+        let mut meeting = None;
+        let mut depth = 0;
+
+        while meeting.is_none() && depth < max_depth {
+            let forward_turn = match (forward.frontier.is_empty(), backward.frontier.is_empty()) {
+                (true, true) => break,
+                (false, true) => true,
+                (true, false) => false,
+                (false, false) => forward.frontier.len() <= backward.frontier.len(),
+            };
+            depth += 1;
+
+            let (side, other, direction) = if forward_turn {
+                (&mut forward, &backward, GraphDirection::References)
+            } else {
+                (&mut backward, &forward, GraphDirection::Citations)
+            };
+
+            let links = self
+                .walker
+                .expand(
+                    &side.frontier,
+                    direction,
+                    per_paper_limit,
+                    &fields,
+                    depth,
+                    &progress,
+                )
+                .await;
+
+            let mut found = Vec::new();
+            for Linked { edge, paper } in links {
+                // The paper expanded, and the one it led to
+                let (expanded, reached) = if forward_turn {
+                    (edge.source, edge.target)
+                } else {
+                    (edge.target, edge.source)
+                };
+                if side.parents.contains_key(&reached) {
+                    continue;
+                }
+                side.parents.insert(reached.clone(), Some(expanded));
+                if meeting.is_none() && other.parents.contains_key(&reached) {
+                    meeting = Some(reached.clone());
+                }
+                nodes.entry(reached).or_insert_with(|| paper.clone());
+                found.push(paper);
+            }
+
+            side.frontier = most_cited(found, frontier_limit)
+                .into_iter()
+                .map(|node| node.paper_id)
+                .collect();
+        }
+
+        let path = match meeting {
+            Some(meeting) => {
+                let mut path = forward.trail(&meeting);
+                path.reverse();
+                path.extend(backward.trail(&meeting).into_iter().skip(1));
+                path.iter()
+                    .filter_map(|paper_id| nodes.get(paper_id))
+                    .map(GraphNode::paper)
+                    .collect()
+            }
+            None => Vec::new(),
+        };
+
+        Ok(CitationPath {
+            from,
+            to,
+            max_depth,
+            path,
+            papers_searched: nodes.len(),
+        })
+    }
+}
+
+/// Lays out the output of `citation_path`: the chain from the citing paper
+/// to the cited one.
+pub struct CitationPathFormatter;
+
+impl ResultFormatter<CitationPath> for CitationPathFormatter {
+    fn format(
+        &self,
+        f: &mut dyn Formatter,
+        renderer: &dyn EntryRenderer,
+        result: &CitationPath,
+        options: &RenderOptions,
+    ) -> Result<()> {
+        if result.path.is_empty() {
+            f.note(&format!(
+                "No chain of at most {} citations found from {} to {}, after searching {} papers. A larger max_depth, per_paper_limit or frontier_limit may find one, or the later paper may not build on the earlier one at all.",
+                result.max_depth, result.from, result.to, result.papers_searched
+            ));
+            return Ok(());
+        }
+
+        let papers: Vec<Paper> = result
+            .path
+            .iter()
+            .map(|paper| serde_json::from_value(paper.clone()))
+            .collect::<Result<_, _>>()?;
+
+        f.heading(&format!(
+            "Citation chain of {} step(s), found after searching {} papers:",
+            papers.len() - 1,
+            result.papers_searched
+        ));
+
+        for (i, paper) in papers.iter().enumerate() {
+            f.item(i + 1, &renderer.paper_title(paper));
+            renderer.paper(f, paper, options);
+            if let Some(next) = papers.get(i + 1) {
+                f.field("Cites", &renderer.paper_title(next));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for CitationPathTool {
+    async fn execute(&self, arguments: Option<Value>) -> Result<Vec<ToolContent>> {
+        tracing::debug!("Executing CitationPathTool");
+        let args: CitationPathArguments = parse_arguments(arguments)?;
+
+        let from = normalize_paper_id(&args.from_paper_id);
+        let to = normalize_paper_id(&args.to_paper_id);
+        if from.is_empty() || to.is_empty() {
+            return Err(invalid_argument("Paper IDs cannot be empty"));
+        }
+        if from == to {
+            return Err(invalid_argument("The two papers must be different"));
+        }
+
+        let max_depth = args.max_depth.unwrap_or(4);
+        let per_paper_limit = args.per_paper_limit.unwrap_or(100);
+        let frontier_limit = args.frontier_limit.unwrap_or(50) as usize;
+        let fields = args
+            .fields
+            .unwrap_or_else(|| vec![PaperField::Year, PaperField::Authors]);
+
+        let params = json!({
+            "from": from,
+            "to": to,
+            "max_depth": max_depth,
+            "per_paper_limit": per_paper_limit,
+            "frontier_limit": frontier_limit,
+            "fields": fields,
+        });
+
+        let key = CacheKey::new("citation_path", &format!("{} {}", from, to)).params(&params);
+        let response = match cached_response(self.cache.as_ref(), self.embed.as_ref(), &key).await?
+        {
+            CacheLookup::Hit(response) => response,
+            CacheLookup::Miss(embedding) => {
+                let path = self
+                    .search(
+                        &from,
+                        &to,
+                        max_depth,
+                        per_paper_limit,
+                        frontier_limit,
+                        &fields,
+                    )
+                    .await?;
+                let response = serde_json::to_value(&path)?;
+                store_response(self.cache.as_ref(), &key, embedding, &response);
+                response
+            }
+        };
+
+        let path: CitationPath = serde_json::from_value(response.clone())?;
+        let formatted_result = render(
+            args.output_format,
+            &response,
+            None,
+            args.max_output_chars,
+            |f| {
+                self.formatter
+                    .format(f, self.renderer.as_ref(), &path, &RenderOptions::default())
+            },
+        )?;
+
+        let output = ToolOutput::new(formatted_result, args.output_format, &response);
+
+        Ok(output.into_contents())
+    }
+
+    fn to_tool(&self) -> Tool {
+        Tool {
+            name: "citation_path".into(),
+            description: Some(
+                "Find the shortest chain of citations leading from one paper to another, showing how a later work builds on an earlier one through the papers in between"
+                    .into(),
+            ),
+            input_schema: input_schema::<CitationPathArguments>(),
+        }
+    }
+}
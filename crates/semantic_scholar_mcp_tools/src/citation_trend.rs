@@ -0,0 +1,427 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use cache::Cache;
+use chrono::Datelike;
+use context_server::{Tool, ToolContent, ToolExecutor};
+use embed::Embed;
+use http_client::HttpClient;
+use models::{Citation, Page};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::{
+    arguments::{input_schema, parse_arguments},
+    content::ToolOutput,
+    error::invalid_argument,
+    format::{Formatter, OutputFormat, render},
+    progress::Progress,
+    query_cache::{CacheKey, CacheLookup, cached_response, store_response},
+    result_formatter::{DefaultEntryRenderer, EntryRenderer, RenderOptions, ResultFormatter},
+    utils::{RateLimiter, encode_path_segment, make_request, normalize_paper_id},
+};
+
+/// The most citations a single page of the citations endpoint returns.
+const PAGE_LIMIT: u64 = 1000;
+
+/// Years in each of the two windows compared to tell the trend.
+const TREND_WINDOW: u64 = 3;
+
+/// Fewer citations than this across both windows say little about a trend.
+const MIN_TREND_CITATIONS: u64 = 10;
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct CitationTrendArguments {
+    /// The paper to analyse, in any of the formats paper_details accepts
+    paper_id: String,
+    /// Maximum number of citations to fetch, a page of 1000 at a time. Default: 5000, Maximum: 10000
+    #[schemars(range(min = 1, max = 10000))]
+    max_citations: Option<u64>,
+    /// Maximum length of the output in characters. Results past it are left out, with a note saying how many. Default: the server's limit, if any
+    #[schemars(range(min = 1))]
+    max_output_chars: Option<usize>,
+    #[serde(default)]
+    output_format: OutputFormat,
+}
+
+/// Where a paper's citations are heading, from the last complete years
+/// against the years before them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Trend {
+    /// At least twice as many citations as before
+    Burst,
+    /// At least a quarter more citations than before
+    Rising,
+    Steady,
+    /// At most four fifths of the citations before
+    Declining,
+    /// At most half the citations before
+    Fading,
+    /// Too few citations to tell
+    Insufficient,
+}
+
+impl Trend {
+    fn from_windows(recent: u64, earlier: u64) -> Self {
+        if recent + earlier < MIN_TREND_CITATIONS {
+            return Trend::Insufficient;
+        }
+        if earlier == 0 {
+            return Trend::Burst;
+        }
+        let ratio = recent as f64 / earlier as f64;
+        if ratio >= 2.0 {
+            Trend::Burst
+        } else if ratio >= 1.25 {
+            Trend::Rising
+        } else if ratio > 0.8 {
+            Trend::Steady
+        } else if ratio > 0.5 {
+            Trend::Declining
+        } else {
+            Trend::Fading
+        }
+    }
+
+    fn describe(&self) -> &'static str {
+        match self {
+            Trend::Burst => "bursting",
+            Trend::Rising => "rising",
+            Trend::Steady => "steady",
+            Trend::Declining => "declining",
+            Trend::Fading => "fading",
+            Trend::Insufficient => "too few citations to tell",
+        }
+    }
+}
+
+/// The citations of a paper in a span of years.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct YearWindow {
+    pub from: u64,
+    pub to: u64,
+    pub citations: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct YearCount {
+    pub year: u64,
+    pub citations: u64,
+}
+
+/// A paper's citations counted by the year of the citing paper.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CitationTrend {
+    pub paper_id: String,
+    pub title: Option<String>,
+    pub year: Option<u64>,
+    pub citation_count: Option<u64>,
+    /// Citations fetched and counted, which may be fewer than the total
+    pub counted: u64,
+    /// Counted citations whose citing paper has no year
+    pub unknown_year: u64,
+    /// Every year from the first citation to the current one, which is
+    /// still under way
+    pub by_year: Vec<YearCount>,
+    pub peak_year: Option<u64>,
+    /// The last complete years
+    pub recent: YearWindow,
+    /// The years before them
+    pub earlier: YearWindow,
+    /// Citations in the recent window over those in the earlier one
+    pub ratio: Option<f64>,
+    pub trend: Trend,
+}
+
+impl CitationTrend {
+    fn new(paper: &Value, years: &BTreeMap<u64, u64>, counted: u64, current_year: u64) -> Self {
+        let unknown_year = counted - years.values().sum::<u64>();
+
+        let by_year: Vec<YearCount> = match years.keys().next() {
+            Some(&first) => (first..=current_year.max(first))
+                .map(|year| YearCount {
+                    year,
+                    citations: years.get(&year).copied().unwrap_or(0),
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+        let peak_year = by_year
+            .iter()
+            .filter(|count| count.citations > 0)
+            .max_by_key(|count| (count.citations, count.year))
+            .map(|count| count.year);
+
+        let window = |to: u64| {
+            let from = to.saturating_sub(TREND_WINDOW - 1);
+            YearWindow {
+                from,
+                to,
+                citations: years.range(from..=to).map(|(_, count)| count).sum(),
+            }
+        };
+        let recent = window(current_year.saturating_sub(1));
+        let earlier = window(recent.from.saturating_sub(1));
+
+        Self {
+            paper_id: paper["paperId"].as_str().unwrap_or_default().to_string(),
+            title: paper["title"].as_str().map(str::to_string),
+            year: paper["year"].as_u64(),
+            citation_count: paper["citationCount"].as_u64(),
+            counted,
+            unknown_year,
+            by_year,
+            peak_year,
+            ratio: (earlier.citations > 0)
+                .then(|| recent.citations as f64 / earlier.citations as f64),
+            trend: Trend::from_windows(recent.citations, earlier.citations),
+            recent,
+            earlier,
+        }
+    }
+}
+
+pub struct CitationTrendTool {
+    http_client: Arc<dyn HttpClient>,
+    rate_limiter: Arc<RateLimiter>,
+    cache: Arc<dyn Cache>,
+    embed: Arc<dyn Embed>,
+    renderer: Arc<dyn EntryRenderer>,
+    formatter: Arc<dyn ResultFormatter<CitationTrend>>,
+}
+
+impl CitationTrendTool {
+    pub fn new(
+        http_client: Arc<dyn HttpClient>,
+        rate_limiter: Arc<RateLimiter>,
+        cache: Arc<dyn Cache>,
+        embed: Arc<dyn Embed>,
+    ) -> Self {
+        Self {
+            http_client,
+            rate_limiter,
+            cache,
+            embed,
+            renderer: Arc::new(DefaultEntryRenderer::new()),
+            formatter: Arc::new(CitationTrendFormatter),
+        }
+    }
+
+    /// Writes the entries through `renderer` rather than the default.
+    pub fn with_renderer(mut self, renderer: Arc<dyn EntryRenderer>) -> Self {
+        self.renderer = renderer;
+        self
+    }
+
+    pub fn with_formatter(mut self, formatter: Arc<dyn ResultFormatter<CitationTrend>>) -> Self {
+        self.formatter = formatter;
+        self
+    }
+
+    async fn request(&self, endpoint: &str, params: Value) -> Result<Value> {
+        make_request(
+            &self.http_client,
+            &self.rate_limiter,
+            Some(self.cache.as_ref()),
+            endpoint,
+            Some(&params),
+            None,
+            None,
+        )
+        .await
+    }
+
+    /// Fetches the paper, then pages through its citations, counting them by
+    /// the year of the citing paper.
+    async fn trend(&self, paper_id: &str, max_citations: u64) -> Result<CitationTrend> {
+        let endpoint = format!("/paper/{}", encode_path_segment(paper_id));
+        let paper = self
+            .request(
+                &endpoint,
+                json!({ "fields": "paperId,title,year,citationCount" }),
+            )
+            .await?;
+
+        let expected = paper["citationCount"]
+            .as_u64()
+            .unwrap_or(max_citations)
+            .min(max_citations);
+        let progress = Progress::new(expected.div_ceil(PAGE_LIMIT));
+
+        let mut years: BTreeMap<u64, u64> = BTreeMap::new();
+        let mut counted = 0;
+        let mut offset = Some(0);
+        while let Some(current) = offset.filter(|_| counted < max_citations) {
+            let limit = PAGE_LIMIT.min(max_citations - counted);
+            let result = self
+                .request(
+                    &format!("{}/citations", endpoint),
+                    json!({ "fields": "year", "offset": current, "limit": limit }),
+                )
+                .await?;
+            let page: Page<Citation> = serde_json::from_value(result)?;
+
+            for citation in &page.data {
+                if let Some(year) = citation.citing_paper.year {
+                    *years.entry(year).or_insert(0) += 1;
+                }
+            }
+            counted += page.data.len() as u64;
+            progress.advance(&format!("Fetched {} citations", counted));
+
+            offset = page.next.filter(|_| !page.data.is_empty());
+        }
+
+        let current_year = chrono::Utc::now().year() as u64;
+        Ok(CitationTrend::new(&paper, &years, counted, current_year))
+    }
+}
+
+/// Lays out the output of `citation_trend`: the citations of each year and
+/// the trend they point to.
+pub struct CitationTrendFormatter;
+
+impl ResultFormatter<CitationTrend> for CitationTrendFormatter {
+    fn format(
+        &self,
+        f: &mut dyn Formatter,
+        _renderer: &dyn EntryRenderer,
+        trend: &CitationTrend,
+        _options: &RenderOptions,
+    ) -> Result<()> {
+        let title = trend.title.as_deref().unwrap_or("Unknown title");
+        if trend.by_year.is_empty() {
+            f.note(&format!(
+                "No dated citations found for {} (ID: {}).",
+                title, trend.paper_id
+            ));
+            return Ok(());
+        }
+
+        f.heading(&format!(
+            "Citations of {} (ID: {}) by year:",
+            title, trend.paper_id
+        ));
+        let width = trend
+            .by_year
+            .iter()
+            .map(|count| count.citations)
+            .max()
+            .unwrap_or(0);
+        for count in &trend.by_year {
+            // Bars of up to 40 characters, scaled to the busiest year
+            let bar = if width > 0 {
+                "#".repeat((count.citations * 40).div_ceil(width) as usize)
+            } else {
+                String::new()
+            };
+            f.field(
+                &count.year.to_string(),
+                format!("{} {}", count.citations, bar).trim_end(),
+            );
+        }
+
+        let mut summary = format!(
+            "{}: {} citations in {}-{} against {} in {}-{}",
+            trend.trend.describe(),
+            trend.recent.citations,
+            trend.recent.from,
+            trend.recent.to,
+            trend.earlier.citations,
+            trend.earlier.from,
+            trend.earlier.to
+        );
+        if let Some(ratio) = trend.ratio {
+            summary.push_str(&format!(" ({:.2}x)", ratio));
+        }
+        f.field("Trend", &summary);
+        if let Some(peak_year) = trend.peak_year {
+            f.field("Peak Year", &peak_year.to_string());
+        }
+
+        let mut notes = vec![format!(
+            "{} is still under way, so its count is partial.",
+            trend.by_year.last().map(|count| count.year).unwrap_or(0)
+        )];
+        if let Some(total) = trend.citation_count.filter(|&total| total > trend.counted) {
+            notes.push(format!(
+                "Only {} of the paper's {} citations were fetched; a larger max_citations counts more of them.",
+                trend.counted, total
+            ));
+        }
+        if trend.unknown_year > 0 {
+            notes.push(format!(
+                "{} citing paper(s) have no year and are not counted.",
+                trend.unknown_year
+            ));
+        }
+        f.note(&notes.join(" "));
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for CitationTrendTool {
+    async fn execute(&self, arguments: Option<Value>) -> Result<Vec<ToolContent>> {
+        tracing::debug!("Executing CitationTrendTool");
+        let args: CitationTrendArguments = parse_arguments(arguments)?;
+
+        if args.paper_id.trim().is_empty() {
+            return Err(invalid_argument("Paper ID cannot be empty"));
+        }
+        let paper_id = normalize_paper_id(&args.paper_id);
+        let max_citations = args.max_citations.unwrap_or(5000);
+
+        // Keyed by year too, as the windows move with the calendar
+        let params = json!({
+            "max_citations": max_citations,
+            "current_year": chrono::Utc::now().year(),
+        });
+        let key = CacheKey::new("citation_trend", &paper_id)
+            .param("paper_id", &paper_id)
+            .params(&params);
+        let response = match cached_response(self.cache.as_ref(), self.embed.as_ref(), &key).await?
+        {
+            CacheLookup::Hit(response) => response,
+            CacheLookup::Miss(embedding) => {
+                let trend = self.trend(&paper_id, max_citations).await?;
+                let response = serde_json::to_value(&trend)?;
+                store_response(self.cache.as_ref(), &key, embedding, &response);
+                response
+            }
+        };
+
+        let trend: CitationTrend = serde_json::from_value(response.clone())?;
+        let formatted_result = render(
+            args.output_format,
+            &response,
+            None,
+            args.max_output_chars,
+            |f| {
+                self.formatter
+                    .format(f, self.renderer.as_ref(), &trend, &RenderOptions::default())
+            },
+        )?;
+
+        let output = ToolOutput::new(formatted_result, args.output_format, &response);
+
+        Ok(output.into_contents())
+    }
+
+    fn to_tool(&self) -> Tool {
+        Tool {
+            name: "citation_trend".into(),
+            description: Some(
+                "Count a paper's citations by year and tell whether it is gaining or fading: compares its citations in the last three complete years with the three before, flagging bursts and declines"
+                    .into(),
+            ),
+            input_schema: input_schema::<CitationTrendArguments>(),
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use std::{collections::HashSet, sync::Arc};
+
+use anyhow::Result;
+use cache::Cache;
+use context_server::Tool;
+use serde_json::{Value, json};
+
+use crate::{error::invalid_argument, session::Session};
+
+/// The most values a completion returns, as the MCP specification allows.
+const MAX_VALUES: usize = 100;
+
+/// How many recently seen papers and authors are searched for IDs.
+const RECENT_LIMIT: usize = 1000;
+
+/// Answers `completion/complete` requests. Paper and author arguments are
+/// completed with the IDs of papers and authors seen earlier, those the
+/// client's own session returned first, matched by ID prefix or by title and
+/// name. Tool arguments with a fixed set of values,
+/// such as `fields` or `from_pool`, are completed from the tool's schema
+/// through the `ref/tool` reference, an extension of the specification.
+pub struct Completions {
+    cache: Arc<dyn Cache>,
+    tools: Vec<Tool>,
+}
+
+/// Which kind of identifier an argument holds.
+enum IdKind {
+    Paper,
+    Author,
+}
+
+impl IdKind {
+    fn of_argument(name: &str) -> Option<Self> {
+        let name = name.to_lowercase().replace('_', "");
+        if name.contains("paperid") {
+            Some(IdKind::Paper)
+        } else if name.contains("authorid") {
+            Some(IdKind::Author)
+        } else {
+            None
+        }
+    }
+}
+
+impl Completions {
+    pub fn new(cache: Arc<dyn Cache>, tools: Vec<Tool>) -> Self {
+        Self { cache, tools }
+    }
+
+    pub fn complete(&self, session: &Session, params: &Value) -> Result<Value> {
+        let reference = &params["ref"];
+        let argument = params["argument"]["name"]
+            .as_str()
+            .ok_or_else(|| invalid_argument("Completion requests need an argument name"))?;
+        let value = params["argument"]["value"].as_str().unwrap_or_default();
+
+        let candidates = match reference["type"].as_str() {
+            Some("ref/resource") => {
+                let uri = reference["uri"].as_str().unwrap_or_default();
+                if uri.starts_with("paper://") {
+                    self.ids(session, IdKind::Paper, value)?
+                } else if uri.starts_with("author://") {
+                    self.ids(session, IdKind::Author, value)?
+                } else {
+                    Vec::new()
+                }
+            }
+            Some("ref/prompt") => match IdKind::of_argument(argument) {
+                Some(kind) => self.ids(session, kind, value)?,
+                None => Vec::new(),
+            },
+            Some("ref/tool") => {
+                let tool = reference["name"].as_str().unwrap_or_default();
+                self.tool_argument(session, tool, argument, value)?
+            }
+            other => {
+                return Err(invalid_argument(format!(
+                    "Unknown completion reference type {}",
+                    other.unwrap_or("(none)")
+                )));
+            }
+        };
+
+        let total = candidates.len();
+        let values: Vec<String> = candidates.into_iter().take(MAX_VALUES).collect();
+
+        Ok(json!({
+            "completion": {
+                "values": values,
+                "total": total,
+                "hasMore": total > values.len(),
+            }
+        }))
+    }
+
+    /// The values a tool's schema allows for an argument, or the IDs seen
+    /// earlier for ID arguments.
+    fn tool_argument(
+        &self,
+        session: &Session,
+        tool: &str,
+        argument: &str,
+        value: &str,
+    ) -> Result<Vec<String>> {
+        let Some(tool) = self.tools.iter().find(|candidate| candidate.name == tool) else {
+            return Err(invalid_argument(format!("Unknown tool {}", tool)));
+        };
+
+        let schema = &tool.input_schema["properties"][argument];
+        let allowed = schema["enum"]
+            .as_array()
+            .or_else(|| schema["items"]["enum"].as_array());
+
+        match allowed {
+            Some(allowed) => {
+                let value = value.to_lowercase();
+                Ok(allowed
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .filter(|allowed| allowed.to_lowercase().starts_with(&value))
+                    .map(str::to_string)
+                    .collect())
+            }
+            None => match IdKind::of_argument(argument) {
+                Some(kind) => self.ids(session, kind, value),
+                None => Ok(Vec::new()),
+            },
+        }
+    }
+
+    /// IDs of papers or authors seen by the session and then by any client,
+    /// newest first, whose ID starts with the value or whose title or name
+    /// contains it.
+    fn ids(&self, session: &Session, kind: IdKind, value: &str) -> Result<Vec<String>> {
+        let (seen, records, id_field, label_field) = match kind {
+            IdKind::Paper => (
+                session.seen_papers(),
+                self.cache.recent_papers(RECENT_LIMIT)?,
+                "paperId",
+                "title",
+            ),
+            IdKind::Author => (
+                session.seen_authors(),
+                self.cache.recent_authors(RECENT_LIMIT)?,
+                "authorId",
+                "name",
+            ),
+        };
+
+        let mut candidates = Vec::new();
+        for id in seen {
+            let record = match kind {
+                IdKind::Paper => self.cache.get_paper(&id)?,
+                IdKind::Author => self.cache.get_author(&id)?,
+            };
+            let label = record
+                .and_then(|entry| entry.value[label_field].as_str().map(str::to_string))
+                .unwrap_or_default();
+            candidates.push((id, label));
+        }
+        candidates.extend(records.iter().filter_map(|entry| {
+            let id = entry.value[id_field].as_str()?;
+            let label = entry.value[label_field].as_str().unwrap_or_default();
+            Some((id.to_string(), label.to_string()))
+        }));
+
+        let needle = value.trim().to_lowercase();
+        let mut returned = HashSet::new();
+        Ok(candidates
+            .into_iter()
+            .filter(|(id, label)| {
+                id.to_lowercase().starts_with(&needle) || label.to_lowercase().contains(&needle)
+            })
+            .filter_map(|(id, _)| returned.insert(id.clone()).then_some(id))
+            .collect())
+    }
+}
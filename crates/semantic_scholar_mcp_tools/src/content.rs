@@ -0,0 +1,97 @@
+use context_server::ToolContent;
+use serde_json::{Map, Value, json};
+
+use crate::{
+    format::{OutputFormat, Rendered},
+    output_limit::{Truncation, trim_results},
+    pagination::Pagination,
+};
+
+/// What a tool returns: the rendered text for the model, followed by a JSON
+/// block with the data it was rendered from, so programmatic clients get
+/// parseable results from the same call.
+pub struct ToolOutput {
+    text: String,
+    /// The response the text was rendered from. Left out of json output,
+    /// whose text already is the response.
+    data: Option<Value>,
+    pagination: Option<Pagination>,
+    truncation: Option<Truncation>,
+}
+
+impl ToolOutput {
+    /// When the text was kept under a length, the data is trimmed to the
+    /// results the text shows, and further until it fits the same length,
+    /// as the block takes up as much of the client's context as the text.
+    pub fn new(rendered: Rendered, format: OutputFormat, data: &Value) -> Self {
+        let Rendered {
+            text,
+            max_chars,
+            omitted,
+        } = rendered;
+        let mut data = (format != OutputFormat::Json).then(|| data.clone());
+
+        let truncation = max_chars.and_then(|max_chars| {
+            let omitted = match &mut data {
+                Some(data) => trim_results(data, omitted, max_chars),
+                // The text of json output is the data
+                None => omitted,
+            };
+            (omitted > 0).then_some(Truncation {
+                max_output_chars: max_chars,
+                omitted,
+            })
+        });
+
+        Self {
+            text,
+            data,
+            pagination: None,
+            truncation,
+        }
+    }
+
+    /// Text written directly rather than rendered in a chosen format, such
+    /// as the report of a tool that changes something, with the data it
+    /// reports.
+    pub fn from_text(text: String, data: &Value) -> Self {
+        Self {
+            text,
+            data: Some(data.clone()),
+            pagination: None,
+            truncation: None,
+        }
+    }
+
+    pub fn with_pagination(mut self, pagination: Pagination) -> Self {
+        self.pagination = Some(pagination);
+        self
+    }
+
+    /// The structured block accompanying the text, if there is anything to
+    /// report: `{"data": ..., "pagination": ..., "truncated": ...}`.
+    fn structured(&self) -> Option<Value> {
+        let mut structured = Map::new();
+        if let Some(data) = &self.data {
+            structured.insert("data".into(), data.clone());
+        }
+        if let Some(pagination) = &self.pagination {
+            structured.insert("pagination".into(), json!(pagination));
+        }
+        if let Some(truncation) = &self.truncation {
+            structured.insert("truncated".into(), json!(truncation));
+        }
+        (!structured.is_empty()).then_some(Value::Object(structured))
+    }
+
+    pub fn into_contents(self) -> Vec<ToolContent> {
+        let structured = self.structured();
+        let mut contents = vec![ToolContent::Text { text: self.text }];
+        if let Some(structured) = structured {
+            contents.push(ToolContent::Text {
+                text: structured.to_string(),
+            });
+        }
+        contents
+    }
+}
@@ -0,0 +1,369 @@
+use std::sync::Arc;
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use context_server::{Tool, ToolContent, ToolExecutor};
+use http_client::HttpClient;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{
+    arguments::{input_schema, parse_arguments},
+    content::ToolOutput,
+    error::invalid_argument,
+    format::{Formatter, OutputFormat, render},
+    utils::{RateLimiter, api_base_url, encode_path_segment, make_request},
+};
+
+fn datasets_base_url() -> String {
+    format!("{}/datasets/v1", api_base_url())
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct DatasetReleasesArguments {
+    /// Maximum number of releases to list, most recent first. Default: 10
+    limit: Option<u64>,
+    /// Maximum length of the output in characters. Results past it are left out, with a note saying how many. Default: the server's limit, if any
+    #[schemars(range(min = 1))]
+    max_output_chars: Option<usize>,
+    #[serde(default)]
+    output_format: OutputFormat,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct DatasetListArguments {
+    /// Release identifier (e.g. '2024-01-02') or 'latest'. Default: latest
+    release_id: Option<String>,
+    /// Maximum length of the output in characters. Results past it are left out, with a note saying how many. Default: the server's limit, if any
+    #[schemars(range(min = 1))]
+    max_output_chars: Option<usize>,
+    #[serde(default)]
+    output_format: OutputFormat,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct DatasetDownloadLinksArguments {
+    /// Release identifier (e.g. '2024-01-02') or 'latest'. Default: latest
+    release_id: Option<String>,
+    /// Name of the dataset, as listed by dataset_list (e.g. 'papers', 'abstracts', 'citations')
+    dataset_name: String,
+    /// Maximum length of the output in characters. Results past it are left out, with a note saying how many. Default: the server's limit, if any
+    #[schemars(range(min = 1))]
+    max_output_chars: Option<usize>,
+    #[serde(default)]
+    output_format: OutputFormat,
+}
+
+fn release_id_argument(release_id: Option<&str>) -> Result<&str> {
+    let release_id = release_id.unwrap_or("latest");
+
+    if release_id.trim().is_empty() {
+        return Err(invalid_argument("Release ID cannot be empty"));
+    }
+
+    Ok(release_id)
+}
+
+pub struct DatasetReleasesTool {
+    http_client: Arc<dyn HttpClient>,
+    rate_limiter: Arc<RateLimiter>,
+}
+
+impl DatasetReleasesTool {
+    pub fn new(http_client: Arc<dyn HttpClient>, rate_limiter: Arc<RateLimiter>) -> Self {
+        Self {
+            http_client,
+            rate_limiter,
+        }
+    }
+
+    fn format_releases(&self, f: &mut dyn Formatter, response: &Value, limit: usize) -> Result<()> {
+        let Some(releases) = response.as_array() else {
+            f.note("No releases found or unexpected API response format.");
+            return Ok(());
+        };
+
+        if releases.is_empty() {
+            f.note("No dataset releases available.");
+            return Ok(());
+        }
+
+        let mut release_ids: Vec<&str> = releases.iter().filter_map(|r| r.as_str()).collect();
+        // Release IDs are ISO dates, so a reverse lexical sort puts the newest first
+        release_ids.sort_unstable_by(|a, b| b.cmp(a));
+
+        let shown: Vec<String> = release_ids
+            .iter()
+            .take(limit)
+            .map(|release_id| release_id.to_string())
+            .collect();
+
+        f.list(
+            &format!(
+                "Found {} dataset releases. Showing the {} most recent",
+                release_ids.len(),
+                shown.len()
+            ),
+            &shown,
+        );
+
+        f.note("(Use the dataset_list tool with a release ID to see its datasets)");
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for DatasetReleasesTool {
+    async fn execute(&self, arguments: Option<Value>) -> Result<Vec<ToolContent>> {
+        tracing::debug!("Executing DatasetReleasesTool");
+        let args: DatasetReleasesArguments = parse_arguments(arguments)?;
+        let limit = args.limit.unwrap_or(10) as usize;
+
+        let result = make_request(
+            &self.http_client,
+            &self.rate_limiter,
+            None,
+            "/release/",
+            None,
+            None,
+            Some(&datasets_base_url()),
+        )
+        .await?;
+
+        let formatted_result = render(
+            args.output_format,
+            &result,
+            None,
+            args.max_output_chars,
+            |f| self.format_releases(f, &result, limit),
+        )?;
+
+        Ok(ToolOutput::new(formatted_result, args.output_format, &result).into_contents())
+    }
+
+    fn to_tool(&self) -> Tool {
+        Tool {
+            name: "dataset_releases".into(),
+            description: Some("List the available Semantic Scholar dataset releases".into()),
+            input_schema: input_schema::<DatasetReleasesArguments>(),
+        }
+    }
+}
+
+pub struct DatasetListTool {
+    http_client: Arc<dyn HttpClient>,
+    rate_limiter: Arc<RateLimiter>,
+}
+
+impl DatasetListTool {
+    pub fn new(http_client: Arc<dyn HttpClient>, rate_limiter: Arc<RateLimiter>) -> Self {
+        Self {
+            http_client,
+            rate_limiter,
+        }
+    }
+
+    fn format_datasets(&self, f: &mut dyn Formatter, response: &Value) -> Result<()> {
+        let release_id = response
+            .get("release_id")
+            .and_then(|r| r.as_str())
+            .unwrap_or("Unknown release");
+
+        let Some(datasets) = response.get("datasets").and_then(|d| d.as_array()) else {
+            f.note("No datasets found or unexpected API response format.");
+            return Ok(());
+        };
+
+        if datasets.is_empty() {
+            f.note(&format!("Release {} contains no datasets.", release_id));
+            return Ok(());
+        }
+
+        f.heading(&format!(
+            "Release {} contains {} datasets:",
+            release_id,
+            datasets.len()
+        ));
+
+        for (i, dataset) in datasets.iter().enumerate() {
+            let name = dataset
+                .get("name")
+                .and_then(|n| n.as_str())
+                .unwrap_or("Unknown dataset");
+
+            f.item(i + 1, name);
+
+            if let Some(description) = dataset.get("description").and_then(|d| d.as_str()) {
+                if !description.is_empty() {
+                    f.field("Description", description.trim());
+                }
+            }
+        }
+
+        f.note(
+            "(Use the dataset_download_links tool with a release ID and dataset name to get download URLs)",
+        );
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for DatasetListTool {
+    async fn execute(&self, arguments: Option<Value>) -> Result<Vec<ToolContent>> {
+        tracing::debug!("Executing DatasetListTool");
+        let args: DatasetListArguments = parse_arguments(arguments)?;
+        let release_id = release_id_argument(args.release_id.as_deref())?;
+
+        let result = make_request(
+            &self.http_client,
+            &self.rate_limiter,
+            None,
+            &format!("/release/{}", encode_path_segment(release_id)),
+            None,
+            None,
+            Some(&datasets_base_url()),
+        )
+        .await?;
+
+        let formatted_result = render(
+            args.output_format,
+            &result,
+            None,
+            args.max_output_chars,
+            |f| self.format_datasets(f, &result),
+        )?;
+
+        Ok(ToolOutput::new(formatted_result, args.output_format, &result).into_contents())
+    }
+
+    fn to_tool(&self) -> Tool {
+        Tool {
+            name: "dataset_list".into(),
+            description: Some("List the datasets contained in a Semantic Scholar release".into()),
+            input_schema: input_schema::<DatasetListArguments>(),
+        }
+    }
+}
+
+pub struct DatasetDownloadLinksTool {
+    http_client: Arc<dyn HttpClient>,
+    rate_limiter: Arc<RateLimiter>,
+}
+
+impl DatasetDownloadLinksTool {
+    pub fn new(http_client: Arc<dyn HttpClient>, rate_limiter: Arc<RateLimiter>) -> Self {
+        Self {
+            http_client,
+            rate_limiter,
+        }
+    }
+
+    fn format_download_links(
+        &self,
+        f: &mut dyn Formatter,
+        release_id: &str,
+        response: &Value,
+    ) -> Result<()> {
+        let name = response
+            .get("name")
+            .and_then(|n| n.as_str())
+            .unwrap_or("Unknown dataset");
+
+        let Some(files) = response.get("files").and_then(|files| files.as_array()) else {
+            f.note("No download links found or unexpected API response format.");
+            return Ok(());
+        };
+
+        if files.is_empty() {
+            f.note(&format!(
+                "Dataset {} in release {} has no downloadable files.",
+                name, release_id
+            ));
+            return Ok(());
+        }
+
+        f.heading(&format!(
+            "Dataset {} (release {}) is split into {} files:",
+            name,
+            release_id,
+            files.len()
+        ));
+
+        if let Some(description) = response.get("description").and_then(|d| d.as_str()) {
+            if !description.is_empty() {
+                f.field("Description", description.trim());
+            }
+        }
+
+        for (i, file) in files.iter().filter_map(|file| file.as_str()).enumerate() {
+            f.item(i + 1, "File");
+            f.link("URL", file);
+        }
+
+        f.note("Note: Download links are pre-signed and expire after a short time.");
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for DatasetDownloadLinksTool {
+    async fn execute(&self, arguments: Option<Value>) -> Result<Vec<ToolContent>> {
+        tracing::debug!("Executing DatasetDownloadLinksTool");
+        let args: DatasetDownloadLinksArguments = parse_arguments(arguments)?;
+        let release_id = release_id_argument(args.release_id.as_deref())?;
+        let dataset_name = args.dataset_name.as_str();
+
+        if dataset_name.trim().is_empty() {
+            return Err(invalid_argument("Dataset name cannot be empty"));
+        }
+
+        if std::env::var("SEMANTIC_SCHOLAR_API_KEY").is_err() {
+            return Err(anyhow!(
+                "Dataset download links require an API key. Set SEMANTIC_SCHOLAR_API_KEY and retry."
+            ));
+        }
+
+        let result = make_request(
+            &self.http_client,
+            &self.rate_limiter,
+            None,
+            &format!(
+                "/release/{}/dataset/{}",
+                encode_path_segment(release_id),
+                encode_path_segment(dataset_name)
+            ),
+            None,
+            None,
+            Some(&datasets_base_url()),
+        )
+        .await?;
+
+        let formatted_result = render(
+            args.output_format,
+            &result,
+            None,
+            args.max_output_chars,
+            |f| self.format_download_links(f, release_id, &result),
+        )?;
+
+        Ok(ToolOutput::new(formatted_result, args.output_format, &result).into_contents())
+    }
+
+    fn to_tool(&self) -> Tool {
+        Tool {
+            name: "dataset_download_links".into(),
+            description: Some(
+                "Get pre-signed download links for a Semantic Scholar dataset (requires an API key)"
+                    .into(),
+            ),
+            input_schema: input_schema::<DatasetDownloadLinksArguments>(),
+        }
+    }
+}
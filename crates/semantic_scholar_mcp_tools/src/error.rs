@@ -0,0 +1,52 @@
+pub use semantic_scholar_client::Error;
+use serde_json::{Value, json};
+
+/// JSON-RPC code for malformed or out-of-range tool arguments.
+const INVALID_PARAMS: i64 = -32602;
+/// JSON-RPC code for failures that don't have a more specific kind.
+const INTERNAL_ERROR: i64 = -32603;
+
+/// The JSON-RPC error code to report for an error. Invalid arguments use the
+/// standard invalid params code; the rest use the implementation-defined range.
+fn code(error: &Error) -> i64 {
+    match error {
+        Error::InvalidArgument(_) => INVALID_PARAMS,
+        Error::RateLimited { .. } => -32001,
+        Error::NotFound(_) => -32002,
+        Error::UpstreamError(_) => -32003,
+        Error::CacheError(_) => -32004,
+        Error::EmbedError(_) => -32005,
+        Error::NotCached(_) => -32006,
+    }
+}
+
+/// Shorthand for rejecting a tool's arguments.
+pub fn invalid_argument(message: impl Into<String>) -> anyhow::Error {
+    Error::InvalidArgument(message.into()).into()
+}
+
+/// Builds the `error` member of a JSON-RPC response for a failed request,
+/// using the code and kind of the underlying [`Error`] when there is one.
+pub fn rpc_error(err: &anyhow::Error) -> Value {
+    let Some(error) = err.downcast_ref::<Error>() else {
+        return json!({
+            "code": INTERNAL_ERROR,
+            "message": err.to_string(),
+        });
+    };
+
+    let mut data = json!({ "kind": error.kind() });
+    if let Error::RateLimited {
+        retry_after: Some(retry_after),
+        ..
+    } = error
+    {
+        data["retry_after_secs"] = json!(retry_after.as_secs());
+    }
+
+    json!({
+        "code": code(error),
+        "message": err.to_string(),
+        "data": data,
+    })
+}
@@ -0,0 +1,194 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use cache::Cache;
+use http_client::HttpClient;
+use models::Paper;
+use serde_json::{Value, json};
+
+use crate::{
+    arguments::PaperField,
+    error::invalid_argument,
+    utils::{RateLimiter, make_request, normalize_paper_id},
+};
+
+/// The fields a citation record is built from, requested by every exporter.
+pub const EXPORT_FIELDS: &[PaperField] = &[
+    PaperField::PaperId,
+    PaperField::Title,
+    PaperField::Authors,
+    PaperField::Year,
+    PaperField::PublicationDate,
+    PaperField::PublicationTypes,
+    PaperField::Venue,
+    PaperField::Journal,
+    PaperField::ExternalIds,
+    PaperField::Url,
+    PaperField::Abstract,
+];
+
+/// The most papers a single export can fetch, matching the batch endpoint.
+const MAX_EXPORT_PAPERS: usize = 500;
+
+/// Fetches the papers to export with a single batch request. Each requested
+/// ID is returned alongside its paper, or `None` when it could not be resolved,
+/// in the order they were requested.
+pub async fn fetch_papers(
+    http_client: &Arc<dyn HttpClient>,
+    rate_limiter: &Arc<RateLimiter>,
+    cache: &Arc<dyn Cache>,
+    paper_ids: &[String],
+) -> Result<Vec<(String, Option<Paper>)>> {
+    let paper_ids: Vec<String> = paper_ids
+        .iter()
+        .map(|s| normalize_paper_id(s))
+        .filter(|s| !s.is_empty())
+        .collect();
+
+    if paper_ids.is_empty() {
+        return Err(invalid_argument("Must provide at least one paper ID"));
+    }
+
+    if paper_ids.len() > MAX_EXPORT_PAPERS {
+        return Err(invalid_argument(format!(
+            "Cannot export more than {} papers at once",
+            MAX_EXPORT_PAPERS
+        )));
+    }
+
+    if let Some(papers) = stored_papers(cache.as_ref(), &paper_ids)? {
+        tracing::debug!("Exporting {} papers seen before", papers.len());
+        return Ok(paper_ids.into_iter().zip(papers).collect());
+    }
+
+    let params = json!({ "fields": EXPORT_FIELDS });
+    let request_body = json!({ "ids": paper_ids });
+
+    let result = make_request(
+        http_client,
+        rate_limiter,
+        Some(cache.as_ref()),
+        "/paper/batch",
+        Some(&params),
+        Some(&request_body),
+        None,
+    )
+    .await?;
+
+    let papers: Vec<Option<Paper>> = serde_json::from_value(result)?;
+
+    Ok(paper_ids.into_iter().zip(papers).collect())
+}
+
+/// The requested papers as seen in earlier responses, when every one of them
+/// was seen with every exported field. IDs other than a `paperId`, such as
+/// DOIs, are never found, and the papers are fetched.
+fn stored_papers(cache: &dyn Cache, paper_ids: &[String]) -> Result<Option<Vec<Option<Paper>>>> {
+    let fields = EXPORT_FIELDS
+        .iter()
+        .map(serde_json::to_value)
+        .collect::<Result<Vec<Value>, _>>()?;
+
+    let mut papers = Vec::with_capacity(paper_ids.len());
+    for paper_id in paper_ids {
+        let Some(entry) = cache.get_paper(paper_id)? else {
+            return Ok(None);
+        };
+        let complete = fields
+            .iter()
+            .filter_map(Value::as_str)
+            .all(|field| entry.value.get(field).is_some());
+        if !complete {
+            return Ok(None);
+        }
+        papers.push(Some(serde_json::from_value(entry.value)?));
+    }
+
+    Ok(Some(papers))
+}
+
+/// Splits an author's display name into given names and family name. The
+/// API only provides the full name, so the last word is taken as the family
+/// name.
+pub fn split_name(name: &str) -> (Option<&str>, &str) {
+    let name = name.trim();
+    match name.rsplit_once(char::is_whitespace) {
+        Some((given, family)) => (Some(given.trim_end()), family),
+        None => (None, name),
+    }
+}
+
+/// Splits a page range such as `123-145` or `123–145` into its first and
+/// last page.
+pub fn split_pages(pages: &str) -> (&str, Option<&str>) {
+    match pages.split_once(['-', '–']) {
+        Some((first, last)) => (first.trim(), Some(last.trim()).filter(|s| !s.is_empty())),
+        None => (pages.trim(), None),
+    }
+}
+
+/// Collapses line breaks and runs of whitespace, for formats that expect each
+/// value on a single line.
+pub fn single_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// A note listing the requested IDs that could not be resolved, if any.
+pub fn unresolved_note(papers: &[(String, Option<Paper>)]) -> Option<String> {
+    let failures: Vec<&str> = papers
+        .iter()
+        .filter(|(_, paper)| paper.is_none())
+        .map(|(paper_id, _)| paper_id.as_str())
+        .collect();
+
+    if failures.is_empty() {
+        return None;
+    }
+
+    Some(format!(
+        "Could not resolve {} paper ID(s): {}",
+        failures.len(),
+        failures.join(", ")
+    ))
+}
+
+/// Title words skipped when picking the one a citation key ends with.
+const KEY_STOP_WORDS: &[&str] = &[
+    "a", "an", "the", "on", "of", "in", "for", "to", "and", "with", "is", "are", "from", "via",
+    "towards", "toward",
+];
+
+/// A citation key in the usual author-year-word form, such as
+/// `vaswani2017attention`: the first author's family name, the year and the
+/// first title word that isn't a stop word, keeping only ASCII letters and
+/// digits.
+pub fn citation_key(paper: &Paper) -> String {
+    let clean = |text: &str| -> String {
+        text.chars()
+            .filter(char::is_ascii_alphanumeric)
+            .collect::<String>()
+            .to_lowercase()
+    };
+
+    let author = paper
+        .author_names()
+        .first()
+        .map(|name| clean(split_name(name).1))
+        .unwrap_or_default();
+    let year = paper.year.map(|year| year.to_string()).unwrap_or_default();
+    let word = paper
+        .title
+        .as_deref()
+        .unwrap_or_default()
+        .split_whitespace()
+        .map(clean)
+        .find(|word| !word.is_empty() && !KEY_STOP_WORDS.contains(&word.as_str()))
+        .unwrap_or_default();
+
+    let key = format!("{}{}{}", author, year, word);
+    if key.is_empty() {
+        "paper".to_string()
+    } else {
+        key
+    }
+}
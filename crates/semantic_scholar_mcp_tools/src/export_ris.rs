@@ -0,0 +1,212 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use cache::Cache;
+use context_server::{Tool, ToolContent, ToolExecutor};
+use http_client::HttpClient;
+use models::Paper;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{
+    arguments::{input_schema, parse_arguments},
+    export::{fetch_papers, single_line, split_name, split_pages, unresolved_note},
+    utils::RateLimiter,
+};
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct ExportRisArguments {
+    /// List of paper identifiers to export (max: 500). Each may be a Semantic Scholar ID, DOI:doi, ARXIV:id, MAG:id, ACL:id, PMID:id, PMCID:id or URL:url, or a bare DOI, arXiv ID or paper link
+    paper_ids: Vec<String>,
+    /// If true, include each paper's abstract in an AB field. Default: false
+    #[serde(default)]
+    include_abstract: bool,
+}
+
+pub struct ExportRisTool {
+    http_client: Arc<dyn HttpClient>,
+    rate_limiter: Arc<RateLimiter>,
+    cache: Arc<dyn Cache>,
+}
+
+impl ExportRisTool {
+    pub fn new(
+        http_client: Arc<dyn HttpClient>,
+        rate_limiter: Arc<RateLimiter>,
+        cache: Arc<dyn Cache>,
+    ) -> Self {
+        Self {
+            http_client,
+            rate_limiter,
+            cache,
+        }
+    }
+}
+
+/// A paper as an RIS record, with `keywords` written as KW tags.
+pub(crate) fn ris_record(paper: &Paper, include_abstract: bool, keywords: &[String]) -> String {
+    let mut record = String::new();
+    let mut tag = |tag: &str, value: &str| {
+        let value = single_line(value);
+        if !value.is_empty() {
+            record.push_str(&format!("{}  - {}\n", tag, value));
+        }
+    };
+
+    tag("TY", ris_type(paper));
+
+    if let Some(title) = &paper.title {
+        tag("TI", title);
+    }
+
+    for name in paper.author_names() {
+        match split_name(name) {
+            (Some(given), family) => tag("AU", &format!("{}, {}", family, given)),
+            (None, family) => tag("AU", family),
+        }
+    }
+
+    if let Some(year) = paper.year {
+        tag("PY", &year.to_string());
+    }
+
+    if let Some(date) = &paper.publication_date {
+        tag("DA", &date.replace('-', "/"));
+    }
+
+    let journal = paper.journal.as_ref();
+    let journal_name = journal
+        .and_then(|journal| journal.name.as_deref())
+        .filter(|name| !name.is_empty());
+    if let Some(venue) = journal_name.or(paper.venue()) {
+        tag("T2", venue);
+    }
+
+    if let Some(volume) = journal.and_then(|journal| journal.volume.as_deref()) {
+        tag("VL", volume);
+    }
+
+    if let Some(pages) = journal.and_then(|journal| journal.pages.as_deref()) {
+        let (start, end) = split_pages(pages);
+        tag("SP", start);
+        if let Some(end) = end {
+            tag("EP", end);
+        }
+    }
+
+    if let Some(doi) = paper
+        .external_ids
+        .as_ref()
+        .and_then(|ids| ids.doi.as_deref())
+    {
+        tag("DO", doi);
+    }
+
+    if let Some(url) = &paper.url {
+        tag("UR", url);
+    }
+
+    if include_abstract {
+        if let Some(abstract_text) = paper.abstract_text() {
+            tag("AB", abstract_text);
+        }
+    }
+
+    for keyword in keywords {
+        tag("KW", keyword);
+    }
+
+    if let Some(paper_id) = &paper.paper_id {
+        tag("ID", paper_id);
+    }
+
+    record.push_str("ER  - \n");
+    record
+}
+
+/// The RIS reference type for a paper, taken from the first publication type
+/// with an RIS equivalent. Papers with a journal but no usable type are
+/// treated as journal articles.
+fn ris_type(paper: &Paper) -> &'static str {
+    if let Some(ris_type) = paper
+        .publication_types
+        .iter()
+        .flatten()
+        .find_map(|publication_type| ris_publication_type(publication_type))
+    {
+        return ris_type;
+    }
+
+    let has_journal = paper
+        .journal
+        .as_ref()
+        .and_then(|journal| journal.name.as_deref())
+        .is_some_and(|name| !name.is_empty());
+
+    if has_journal { "JOUR" } else { "GEN" }
+}
+
+fn ris_publication_type(publication_type: &str) -> Option<&'static str> {
+    match publication_type {
+        "Conference" => Some("CONF"),
+        "JournalArticle" | "Review" => Some("JOUR"),
+        "BookSection" => Some("CHAP"),
+        "Book" => Some("BOOK"),
+        "Dataset" => Some("DATA"),
+        "News" => Some("NEWS"),
+        _ => None,
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for ExportRisTool {
+    async fn execute(&self, arguments: Option<Value>) -> Result<Vec<ToolContent>> {
+        tracing::debug!("Executing ExportRisTool");
+        let args: ExportRisArguments = parse_arguments(arguments)?;
+
+        let papers = fetch_papers(
+            &self.http_client,
+            &self.rate_limiter,
+            &self.cache,
+            &args.paper_ids,
+        )
+        .await?;
+
+        let records: Vec<String> = papers
+            .iter()
+            .filter_map(|(_, paper)| paper.as_ref())
+            .map(|paper| ris_record(paper, args.include_abstract, &[]))
+            .collect();
+
+        if records.is_empty() {
+            return Ok(vec![ToolContent::Text {
+                text: unresolved_note(&papers).unwrap_or_default(),
+            }]);
+        }
+
+        let mut contents = vec![ToolContent::Text {
+            text: records.join("\n"),
+        }];
+
+        // Kept out of the RIS text so it can be imported as is
+        if let Some(note) = unresolved_note(&papers) {
+            contents.push(ToolContent::Text { text: note });
+        }
+
+        Ok(contents)
+    }
+
+    fn to_tool(&self) -> Tool {
+        Tool {
+            name: "export_ris".into(),
+            description: Some(
+                "Export papers as RIS records for import into EndNote, Mendeley, Zotero and other reference managers"
+                    .into(),
+            ),
+            input_schema: input_schema::<ExportRisArguments>(),
+        }
+    }
+}
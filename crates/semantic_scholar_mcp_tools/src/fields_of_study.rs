@@ -0,0 +1,101 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use context_server::{Tool, ToolContent, ToolExecutor};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::arguments::{closest_match, input_schema, parse_arguments};
+
+/// The s2FieldsOfStudy categories accepted by the `fieldsOfStudy` search filter.
+pub const FIELDS_OF_STUDY: &[&str] = &[
+    "Computer Science",
+    "Medicine",
+    "Chemistry",
+    "Biology",
+    "Materials Science",
+    "Physics",
+    "Geology",
+    "Psychology",
+    "Art",
+    "History",
+    "Geography",
+    "Sociology",
+    "Business",
+    "Political Science",
+    "Economics",
+    "Philosophy",
+    "Mathematics",
+    "Engineering",
+    "Environmental Science",
+    "Agricultural and Food Sciences",
+    "Education",
+    "Law",
+    "Linguistics",
+];
+
+fn closest_field_of_study(field: &str) -> Option<&'static str> {
+    closest_match(field, FIELDS_OF_STUDY.iter().copied())
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct FieldsOfStudyArguments {
+    /// Optional list of candidate values to validate. If omitted, all valid fields of study are listed
+    #[serde(default)]
+    validate: Vec<String>,
+}
+
+#[derive(Default)]
+pub struct FieldsOfStudyTool;
+
+impl FieldsOfStudyTool {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for FieldsOfStudyTool {
+    async fn execute(&self, arguments: Option<Value>) -> Result<Vec<ToolContent>> {
+        tracing::debug!("Executing FieldsOfStudyTool");
+
+        let args: FieldsOfStudyArguments = parse_arguments(arguments)?;
+        let candidates: Vec<&str> = args.validate.iter().map(String::as_str).collect();
+
+        if candidates.is_empty() {
+            let mut result = format!("{} valid fields of study:\n", FIELDS_OF_STUDY.len());
+            for field in FIELDS_OF_STUDY {
+                result.push_str(&format!("- {}\n", field));
+            }
+            return Ok(vec![ToolContent::Text { text: result }]);
+        }
+
+        let mut result = String::from("Validation results:\n");
+        for candidate in candidates {
+            if FIELDS_OF_STUDY.contains(&candidate) {
+                result.push_str(&format!("- {}: valid\n", candidate));
+            } else if let Some(suggestion) = closest_field_of_study(candidate) {
+                result.push_str(&format!(
+                    "- {}: invalid (did you mean '{}'?)\n",
+                    candidate, suggestion
+                ));
+            } else {
+                result.push_str(&format!("- {}: invalid\n", candidate));
+            }
+        }
+
+        Ok(vec![ToolContent::Text { text: result }])
+    }
+
+    fn to_tool(&self) -> Tool {
+        Tool {
+            name: "fields_of_study".into(),
+            description: Some(
+                "List the valid Semantic Scholar fields of study, or check candidate values and suggest corrections"
+                    .into(),
+            ),
+            input_schema: input_schema::<FieldsOfStudyArguments>(),
+        }
+    }
+}
@@ -0,0 +1,453 @@
+use std::borrow::Cow;
+
+use anyhow::Result;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{
+    output_limit::{self, trim_results},
+    table::render_table,
+};
+
+/// How to present the results: 'text' for readable text, 'json' for the compact JSON
+/// response from Semantic Scholar, 'markdown', or 'csv' and 'tsv' for a table with
+/// one row per result. Default: text
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+    Markdown,
+    Csv,
+    Tsv,
+}
+
+/// Output rendered under a length limit.
+pub struct Rendered {
+    pub text: String,
+    /// The limit the text was kept under, if any
+    pub max_chars: Option<usize>,
+    /// Results left out to stay under the limit
+    pub omitted: usize,
+}
+
+/// Renders a tool's results in the requested format. JSON output is the API
+/// response itself and tables are built from its fields, with the given
+/// columns or every field of the results; text and markdown are laid out by
+/// `write` through a [`Formatter`], so each tool describes its output once
+/// for both.
+///
+/// The output is kept to about `max_output_chars` characters, or the
+/// configured limit when the call sets none. Text and markdown stop listing
+/// entries once the limit is reached and cut long fields to what is left,
+/// tables drop their last rows and JSON the last records of its list of
+/// results. Text and tables end with a note saying how many results were left
+/// out.
+pub fn render(
+    format: OutputFormat,
+    response: &Value,
+    columns: Option<&[String]>,
+    max_output_chars: Option<usize>,
+    write: impl FnOnce(&mut dyn Formatter) -> Result<()>,
+) -> Result<Rendered> {
+    let max_chars = output_limit::max_output_chars(max_output_chars);
+    let limit = max_chars.unwrap_or(usize::MAX);
+
+    let (text, omitted) = match format {
+        OutputFormat::Json => {
+            let text = serde_json::to_string(response)?;
+            if text.chars().count() <= limit {
+                (text, 0)
+            } else {
+                let mut response = response.clone();
+                let omitted = trim_results(&mut response, 0, limit);
+                (serde_json::to_string(&response)?, omitted)
+            }
+        }
+        OutputFormat::Text => write_limited(TextFormatter::default(), limit, write)?,
+        OutputFormat::Markdown => write_limited(MarkdownFormatter::default(), limit, write)?,
+        OutputFormat::Csv | OutputFormat::Tsv => {
+            let delimiter = if format == OutputFormat::Csv {
+                ','
+            } else {
+                '\t'
+            };
+            let (mut text, omitted) = render_table(response, columns, delimiter, limit);
+            if omitted > 0 {
+                text.push_str(&format!("# {}\n", truncation_note(limit, omitted)));
+            }
+            (text, omitted)
+        }
+    };
+
+    Ok(Rendered {
+        text,
+        max_chars,
+        omitted,
+    })
+}
+
+fn write_limited<F: FormatterOutput>(
+    formatter: F,
+    max_chars: usize,
+    write: impl FnOnce(&mut dyn Formatter) -> Result<()>,
+) -> Result<(String, usize)> {
+    let mut limited = Limited {
+        inner: formatter,
+        max_chars,
+        omitted: 0,
+        skipping: false,
+        cut: false,
+    };
+    write(&mut limited)?;
+    Ok(limited.finish())
+}
+
+fn truncation_note(max_chars: usize, omitted: usize) -> String {
+    format!(
+        "Output truncated to {} characters: {} more results not shown. \
+         Ask for fewer results or a larger max_output_chars to see them.",
+        max_chars, omitted
+    )
+}
+
+/// Lays out the parts of a tool's output. Results are either a single record
+/// described by top-level fields, or a numbered list of entries started with
+/// [`Formatter::item`], whose fields belong to the entry until the next
+/// heading, section or note.
+pub trait Formatter {
+    /// A line introducing the output, such as a title or a count of results.
+    fn heading(&mut self, text: &str);
+    /// Starts the next entry of a numbered list of results.
+    fn item(&mut self, number: usize, title: &str);
+    /// A short marker on the current entry, such as "INFLUENTIAL CITATION".
+    fn tag(&mut self, text: &str);
+    /// A labelled value.
+    fn field(&mut self, label: &str, value: &str);
+    /// A labelled URL.
+    fn link(&mut self, label: &str, url: &str);
+    /// A labelled list of values.
+    fn list(&mut self, label: &str, values: &[String]);
+    /// A labelled block of prose, such as an abstract.
+    fn section(&mut self, label: &str, text: &str);
+    /// A remark about the output as a whole, such as how to fetch more results.
+    fn note(&mut self, text: &str);
+}
+
+/// A formatter writing to a string, which [`Limited`] keeps under a length.
+trait FormatterOutput: Formatter {
+    /// How many characters were written so far.
+    fn written(&self) -> usize;
+    fn finish(self) -> String;
+}
+
+/// Keeps the output of another formatter under a number of characters.
+/// Once the limit is reached, further entries are counted rather than
+/// written, and fields, lists and sections are cut to the room that is left.
+/// Headings and notes are always written, so the output still says where the
+/// results came from and how to fetch more.
+struct Limited<F> {
+    inner: F,
+    max_chars: usize,
+    /// Entries and list values left out
+    omitted: usize,
+    /// Whether the current entry is being left out
+    skipping: bool,
+    /// Whether a field was cut short
+    cut: bool,
+}
+
+impl<F: FormatterOutput> Limited<F> {
+    fn remaining(&self) -> usize {
+        self.max_chars.saturating_sub(self.inner.written())
+    }
+
+    /// The part of `text` that still fits, or None once nothing does.
+    fn fit<'a>(&mut self, text: &'a str) -> Option<Cow<'a, str>> {
+        let remaining = self.remaining();
+        if remaining == 0 {
+            self.cut = true;
+            return None;
+        }
+        let text = cut_to(text, remaining);
+        self.cut |= matches!(text, Cow::Owned(_));
+        Some(text)
+    }
+
+    fn finish(mut self) -> (String, usize) {
+        if self.omitted > 0 {
+            let note = truncation_note(self.max_chars, self.omitted);
+            self.inner.note(&note);
+        } else if self.cut {
+            self.inner.note(&format!(
+                "Output truncated to {} characters. \
+                 Ask for a larger max_output_chars to see all of it.",
+                self.max_chars
+            ));
+        }
+        (self.inner.finish(), self.omitted)
+    }
+}
+
+/// Shortens text to at most `max_chars` characters, the ellipsis marking the
+/// cut included. The cut falls on a grapheme boundary, so no letter loses its
+/// accents.
+fn cut_to(text: &str, max_chars: usize) -> Cow<'_, str> {
+    if text.chars().count() <= max_chars {
+        return Cow::Borrowed(text);
+    }
+
+    let room = max_chars.saturating_sub("...".len());
+    let mut end = 0;
+    let mut chars = 0;
+    for (index, grapheme) in text.grapheme_indices(true) {
+        chars += grapheme.chars().count();
+        if chars > room {
+            break;
+        }
+        end = index + grapheme.len();
+    }
+    Cow::Owned(format!("{}...", text[..end].trim_end()))
+}
+
+impl<F: FormatterOutput> Formatter for Limited<F> {
+    fn heading(&mut self, text: &str) {
+        self.skipping = false;
+        self.inner.heading(text);
+    }
+
+    fn item(&mut self, number: usize, title: &str) {
+        self.skipping = self.remaining() == 0;
+        if self.skipping {
+            self.omitted += 1;
+        } else {
+            self.inner.item(number, title);
+        }
+    }
+
+    fn tag(&mut self, text: &str) {
+        if !self.skipping {
+            self.inner.tag(text);
+        }
+    }
+
+    fn field(&mut self, label: &str, value: &str) {
+        if self.skipping {
+            return;
+        }
+        if let Some(value) = self.fit(value) {
+            self.inner.field(label, &value);
+        }
+    }
+
+    fn link(&mut self, label: &str, url: &str) {
+        if self.skipping {
+            return;
+        }
+        // A cut link is no use, so it is written whole or not at all
+        if self.remaining() >= url.chars().count() {
+            self.inner.link(label, url);
+        } else {
+            self.cut = true;
+        }
+    }
+
+    fn list(&mut self, label: &str, values: &[String]) {
+        if self.skipping {
+            return;
+        }
+        let mut room = self.remaining();
+        let shown = values
+            .iter()
+            .take_while(|value| {
+                let length = value.chars().count();
+                let fits = length <= room;
+                room = room.saturating_sub(length);
+                fits
+            })
+            .count();
+        self.omitted += values.len() - shown;
+        if shown > 0 {
+            self.inner.list(label, &values[..shown]);
+        }
+    }
+
+    fn section(&mut self, label: &str, text: &str) {
+        self.skipping = false;
+        if let Some(text) = self.fit(text) {
+            self.inner.section(label, &text);
+        }
+    }
+
+    fn note(&mut self, text: &str) {
+        self.skipping = false;
+        self.inner.note(text);
+    }
+}
+
+#[derive(Default)]
+struct TextFormatter {
+    output: String,
+    in_item: bool,
+}
+
+impl TextFormatter {
+    fn indent(&self) -> &'static str {
+        if self.in_item { "   " } else { "" }
+    }
+}
+
+impl FormatterOutput for TextFormatter {
+    fn written(&self) -> usize {
+        self.output.chars().count()
+    }
+
+    fn finish(self) -> String {
+        self.output.trim().to_string()
+    }
+}
+
+impl Formatter for TextFormatter {
+    fn heading(&mut self, text: &str) {
+        self.in_item = false;
+        if !self.output.is_empty() {
+            self.output.push('\n');
+        }
+        self.output.push_str(&format!("{}\n", text));
+    }
+
+    fn item(&mut self, number: usize, title: &str) {
+        self.in_item = true;
+        self.output.push_str(&format!("\n{}. {}\n", number, title));
+    }
+
+    fn tag(&mut self, text: &str) {
+        self.output
+            .push_str(&format!("{}[{}]\n", self.indent(), text.to_uppercase()));
+    }
+
+    fn field(&mut self, label: &str, value: &str) {
+        self.output
+            .push_str(&format!("{}{}: {}\n", self.indent(), label, value));
+    }
+
+    fn link(&mut self, label: &str, url: &str) {
+        self.field(label, url);
+    }
+
+    fn list(&mut self, label: &str, values: &[String]) {
+        let (indent, bullet) = if self.in_item {
+            ("   ", "     - ")
+        } else {
+            ("", "- ")
+        };
+        self.output.push_str(&format!("{}{}:\n", indent, label));
+        for value in values {
+            self.output.push_str(&format!("{}{}\n", bullet, value));
+        }
+    }
+
+    fn section(&mut self, label: &str, text: &str) {
+        self.in_item = false;
+        self.output.push_str(&format!("\n{}:\n{}\n", label, text));
+    }
+
+    fn note(&mut self, text: &str) {
+        self.in_item = false;
+        self.output.push_str(&format!("\n{}\n", text));
+    }
+}
+
+#[derive(Default)]
+struct MarkdownFormatter {
+    output: String,
+    in_item: bool,
+}
+
+impl MarkdownFormatter {
+    fn indent(&self) -> &'static str {
+        if self.in_item { "   " } else { "" }
+    }
+}
+
+impl FormatterOutput for MarkdownFormatter {
+    fn written(&self) -> usize {
+        self.output.chars().count()
+    }
+
+    fn finish(self) -> String {
+        self.output.trim().to_string()
+    }
+}
+
+impl Formatter for MarkdownFormatter {
+    fn heading(&mut self, text: &str) {
+        self.in_item = false;
+        self.output
+            .push_str(&format!("\n## {}\n\n", escape_markdown(text)));
+    }
+
+    fn item(&mut self, number: usize, title: &str) {
+        self.in_item = true;
+        self.output
+            .push_str(&format!("\n{}. **{}**\n", number, escape_markdown(title)));
+    }
+
+    fn tag(&mut self, text: &str) {
+        self.output
+            .push_str(&format!("{}- _{}_\n", self.indent(), escape_markdown(text)));
+    }
+
+    fn field(&mut self, label: &str, value: &str) {
+        self.output.push_str(&format!(
+            "{}- **{}:** {}\n",
+            self.indent(),
+            label,
+            escape_markdown(value)
+        ));
+    }
+
+    fn link(&mut self, label: &str, url: &str) {
+        self.output
+            .push_str(&format!("{}- **{}:** <{}>\n", self.indent(), label, url));
+    }
+
+    fn list(&mut self, label: &str, values: &[String]) {
+        let indent = self.indent();
+        self.output
+            .push_str(&format!("{}- **{}:**\n", indent, label));
+        for value in values {
+            self.output
+                .push_str(&format!("{}  - {}\n", indent, escape_markdown(value)));
+        }
+    }
+
+    fn section(&mut self, label: &str, text: &str) {
+        self.in_item = false;
+        self.output
+            .push_str(&format!("\n### {}\n\n{}\n", label, escape_markdown(text)));
+    }
+
+    fn note(&mut self, text: &str) {
+        self.in_item = false;
+        self.output
+            .push_str(&format!("\n> {}\n", escape_markdown(text)));
+    }
+}
+
+/// Escapes the characters that would otherwise start emphasis, code or links
+/// when titles and abstracts are embedded in markdown.
+pub fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '*' | '_' | '`' | '[' | ']' | '<' | '>' | '#') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
@@ -0,0 +1,308 @@
+use std::{collections::HashMap, sync::Arc};
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use cache::Cache;
+use context_server::{Tool, ToolContent, ToolExecutor};
+use hayagriva::{
+    BibliographyDriver, BibliographyRequest, BufWriteFormat, CitationItem, CitationRequest,
+    archive::{ArchivedStyle, locales},
+    citationberg::{IndependentStyle, Style},
+    io::from_yaml_str,
+};
+use http_client::HttpClient;
+use models::Paper;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+use crate::{
+    arguments::{input_schema, parse_arguments},
+    export::{fetch_papers, single_line, split_name, unresolved_note},
+    utils::RateLimiter,
+};
+
+/// Citation style to render the papers in. 'chicago' is the Chicago
+/// author-date style and 'harvard' the Elsevier variant of Harvard. Default: apa
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+enum CitationStyle {
+    #[default]
+    Apa,
+    Mla,
+    Chicago,
+    Ieee,
+    Harvard,
+    Nature,
+}
+
+impl CitationStyle {
+    fn archived_style(&self) -> ArchivedStyle {
+        match self {
+            CitationStyle::Apa => ArchivedStyle::AmericanPsychologicalAssociation,
+            CitationStyle::Mla => ArchivedStyle::ModernLanguageAssociation,
+            CitationStyle::Chicago => ArchivedStyle::ChicagoAuthorDate,
+            CitationStyle::Ieee => ArchivedStyle::InstituteOfElectricalAndElectronicsEngineers,
+            CitationStyle::Harvard => ArchivedStyle::ElsevierHarvard,
+            CitationStyle::Nature => ArchivedStyle::Nature,
+        }
+    }
+
+    /// The CSL style bundled with hayagriva.
+    fn csl(&self) -> Result<IndependentStyle> {
+        match self.archived_style().get() {
+            Style::Independent(style) => Ok(style),
+            Style::Dependent(_) => Err(anyhow!("Citation style {:?} is not self-contained", self)),
+        }
+    }
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct FormatCitationArguments {
+    /// List of paper identifiers to cite (max: 500). Each may be a Semantic Scholar ID, DOI:doi, ARXIV:id, MAG:id, ACL:id, PMID:id, PMCID:id or URL:url, or a bare DOI, arXiv ID or paper link
+    paper_ids: Vec<String>,
+    #[serde(default)]
+    style: CitationStyle,
+}
+
+pub struct FormatCitationTool {
+    http_client: Arc<dyn HttpClient>,
+    rate_limiter: Arc<RateLimiter>,
+    cache: Arc<dyn Cache>,
+}
+
+impl FormatCitationTool {
+    pub fn new(
+        http_client: Arc<dyn HttpClient>,
+        rate_limiter: Arc<RateLimiter>,
+        cache: Arc<dyn Cache>,
+    ) -> Self {
+        Self {
+            http_client,
+            rate_limiter,
+            cache,
+        }
+    }
+
+    /// Renders each paper's in-text citation and bibliography entry with the
+    /// CSL processor, in the order the papers were given.
+    fn format_citations(
+        &self,
+        papers: &[&Paper],
+        style: CitationStyle,
+    ) -> Result<Vec<(String, String)>> {
+        let entries: Map<String, Value> = papers
+            .iter()
+            .enumerate()
+            .map(|(i, paper)| (entry_key(i), csl_entry(paper)))
+            .collect();
+
+        // hayagriva reads its YAML library format, of which JSON is a subset
+        let library = from_yaml_str(&Value::Object(entries).to_string())
+            .map_err(|err| anyhow!("Failed to build citation entries: {}", err))?;
+
+        let csl = style.csl()?;
+        let locales = locales();
+
+        let mut driver = BibliographyDriver::new();
+        for i in 0..papers.len() {
+            let entry = library
+                .get(&entry_key(i))
+                .ok_or_else(|| anyhow!("Citation entry {} is missing", i))?;
+            driver.citation(CitationRequest::from_items(
+                vec![CitationItem::with_entry(entry)],
+                &csl,
+                &locales,
+            ));
+        }
+
+        let rendered = driver.finish(BibliographyRequest {
+            style: &csl,
+            locale: None,
+            locale_files: &locales,
+        });
+
+        let mut in_text = Vec::new();
+        for citation in &rendered.citations {
+            let mut text = String::new();
+            citation
+                .citation
+                .write_buf(&mut text, BufWriteFormat::Plain)?;
+            in_text.push(text);
+        }
+
+        // Styles such as APA sort their bibliography, so entries are matched
+        // back to the papers by key
+        let mut references = HashMap::new();
+        for item in rendered
+            .bibliography
+            .map(|bibliography| bibliography.items)
+            .unwrap_or_default()
+        {
+            let mut text = String::new();
+            // Numbered styles such as IEEE put the label in its own field
+            if let Some(first_field) = &item.first_field {
+                first_field.write_buf(&mut text, BufWriteFormat::Plain)?;
+                text.push(' ');
+            }
+            item.content.write_buf(&mut text, BufWriteFormat::Plain)?;
+            references.insert(item.key, text);
+        }
+
+        Ok(in_text
+            .into_iter()
+            .enumerate()
+            .map(|(i, in_text)| {
+                let reference = references.remove(&entry_key(i)).unwrap_or_default();
+                (in_text, reference)
+            })
+            .collect())
+    }
+}
+
+fn entry_key(index: usize) -> String {
+    format!("paper{}", index + 1)
+}
+
+/// Describes a paper as a hayagriva library entry. Journal and conference
+/// papers are articles whose parent is the periodical or proceedings they
+/// appeared in.
+fn csl_entry(paper: &Paper) -> Value {
+    let publication_types = paper.publication_types.as_deref().unwrap_or_default();
+    let has_type = |name: &str| publication_types.iter().any(|t| t == name);
+
+    let journal = paper.journal.as_ref();
+    let journal_name = journal
+        .and_then(|journal| journal.name.as_deref())
+        .filter(|name| !name.is_empty());
+    let venue = journal_name.or(paper.venue());
+
+    let (entry_type, parent_type) = if has_type("Conference") {
+        ("article", Some("proceedings"))
+    } else if has_type("BookSection") {
+        ("chapter", Some("book"))
+    } else if has_type("Book") {
+        ("book", None)
+    } else if has_type("JournalArticle") || has_type("Review") || journal_name.is_some() {
+        ("article", Some("periodical"))
+    } else {
+        ("misc", None)
+    };
+
+    let mut entry = json!({ "type": entry_type });
+
+    if let Some(title) = &paper.title {
+        entry["title"] = json!(single_line(title));
+    }
+
+    let authors: Vec<String> = paper
+        .author_names()
+        .into_iter()
+        .map(|name| match split_name(name) {
+            (Some(given), family) => format!("{}, {}", family, given),
+            (None, family) => family.to_string(),
+        })
+        .collect();
+    if !authors.is_empty() {
+        entry["author"] = json!(authors);
+    }
+
+    if let Some(date) = &paper.publication_date {
+        entry["date"] = json!(date);
+    } else if let Some(year) = paper.year {
+        entry["date"] = json!(year.to_string());
+    }
+
+    if let Some(pages) = journal.and_then(|journal| journal.pages.as_deref()) {
+        entry["page-range"] = json!(pages.trim());
+    }
+
+    if let Some(doi) = paper
+        .external_ids
+        .as_ref()
+        .and_then(|ids| ids.doi.as_deref())
+    {
+        entry["doi"] = json!(doi);
+    }
+
+    if let Some(url) = &paper.url {
+        entry["url"] = json!(url);
+    }
+
+    match (parent_type, venue) {
+        (Some(parent_type), Some(venue)) => {
+            let mut parent = json!({ "type": parent_type, "title": venue });
+            if let Some(volume) = journal.and_then(|journal| journal.volume.as_deref()) {
+                parent["volume"] = json!(volume.trim());
+            }
+            entry["parent"] = parent;
+        }
+        (None, Some(venue)) => {
+            entry["publisher"] = json!(venue);
+        }
+        _ => {}
+    }
+
+    entry
+}
+
+#[async_trait]
+impl ToolExecutor for FormatCitationTool {
+    async fn execute(&self, arguments: Option<Value>) -> Result<Vec<ToolContent>> {
+        tracing::debug!("Executing FormatCitationTool");
+        let args: FormatCitationArguments = parse_arguments(arguments)?;
+
+        let papers = fetch_papers(
+            &self.http_client,
+            &self.rate_limiter,
+            &self.cache,
+            &args.paper_ids,
+        )
+        .await?;
+
+        let found: Vec<&Paper> = papers
+            .iter()
+            .filter_map(|(_, paper)| paper.as_ref())
+            .collect();
+
+        if found.is_empty() {
+            return Ok(vec![ToolContent::Text {
+                text: unresolved_note(&papers).unwrap_or_default(),
+            }]);
+        }
+
+        let citations = self.format_citations(&found, args.style)?;
+
+        let mut text = String::new();
+        for (paper, (in_text, reference)) in found.iter().zip(citations) {
+            if !text.is_empty() {
+                text.push('\n');
+            }
+            text.push_str(&format!("{}\n", reference));
+            text.push_str(&format!("   In-text: {}\n", in_text));
+            if let Some(paper_id) = &paper.paper_id {
+                text.push_str(&format!("   Paper ID: {}\n", paper_id));
+            }
+        }
+
+        if let Some(note) = unresolved_note(&papers) {
+            text.push_str(&format!("\n{}\n", note));
+        }
+
+        Ok(vec![ToolContent::Text {
+            text: text.trim_end().to_string(),
+        }])
+    }
+
+    fn to_tool(&self) -> Tool {
+        Tool {
+            name: "format_citation".into(),
+            description: Some(
+                "Format papers as citations in APA, MLA, Chicago, IEEE, Harvard or Nature style using a CSL processor"
+                    .into(),
+            ),
+            input_schema: input_schema::<FormatCitationArguments>(),
+        }
+    }
+}
@@ -0,0 +1,232 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use cache::Cache;
+use context_server::{Tool, ToolContent, ToolExecutor};
+use embed::Embed;
+use http_client::HttpClient;
+use models::Paper;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::{
+    arguments::{PaperField, input_schema, parse_arguments},
+    citation_graph::{CitationGraph, GraphDirection, GraphWalker, WalkOptions, seed_ids},
+    content::ToolOutput,
+    format::{Formatter, OutputFormat, render},
+    query_cache::{CacheKey, CacheLookup, cached_response, store_response},
+    result_formatter::{DefaultEntryRenderer, EntryRenderer, RenderOptions, ResultFormatter},
+    utils::RateLimiter,
+};
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct GraphExpandArguments {
+    /// Papers to start from, in any of the formats paper_details accepts. Maximum: 20
+    paper_ids: Vec<String>,
+    /// Which links to follow from each paper: 'citations' (papers citing it), 'references' (papers it cites) or 'both'. Default: both
+    direction: Option<GraphDirection>,
+    /// Number of hops to walk out from the starting papers. Default: 1, Maximum: 3
+    #[schemars(range(min = 1, max = 3))]
+    hops: Option<u32>,
+    /// Maximum number of citations/references to fetch per paper. Default: 50, Maximum: 1000
+    #[schemars(range(min = 1, max = 1000))]
+    per_paper_limit: Option<u64>,
+    /// Maximum number of new papers to keep at each hop, the most cited first. Default: 100, Maximum: 1000
+    #[schemars(range(min = 1, max = 1000))]
+    per_hop_limit: Option<u64>,
+    /// Fields to return for each paper, besides its ID, title and citation count. Default: year
+    fields: Option<Vec<PaperField>>,
+    /// Maximum length of the output in characters. Results past it are left out, with a note saying how many. Default: the server's limit, if any
+    #[schemars(range(min = 1))]
+    max_output_chars: Option<usize>,
+    #[serde(default)]
+    output_format: OutputFormat,
+}
+
+pub struct GraphExpandTool {
+    walker: GraphWalker,
+    cache: Arc<dyn Cache>,
+    embed: Arc<dyn Embed>,
+    renderer: Arc<dyn EntryRenderer>,
+    formatter: Arc<dyn ResultFormatter<CitationGraph>>,
+}
+
+impl GraphExpandTool {
+    pub fn new(
+        http_client: Arc<dyn HttpClient>,
+        rate_limiter: Arc<RateLimiter>,
+        cache: Arc<dyn Cache>,
+        embed: Arc<dyn Embed>,
+    ) -> Self {
+        Self {
+            walker: GraphWalker::new(http_client, rate_limiter, cache.clone()),
+            cache,
+            embed,
+            renderer: Arc::new(DefaultEntryRenderer::new()),
+            formatter: Arc::new(GraphExpandFormatter),
+        }
+    }
+
+    /// Writes the entries through `renderer` rather than the default.
+    pub fn with_renderer(mut self, renderer: Arc<dyn EntryRenderer>) -> Self {
+        self.renderer = renderer;
+        self
+    }
+
+    pub fn with_formatter(mut self, formatter: Arc<dyn ResultFormatter<CitationGraph>>) -> Self {
+        self.formatter = formatter;
+        self
+    }
+}
+
+/// Lays out the output of `graph_expand`: the papers of each hop, with the
+/// citations linking them to the rest of the graph.
+pub struct GraphExpandFormatter;
+
+impl ResultFormatter<CitationGraph> for GraphExpandFormatter {
+    fn format(
+        &self,
+        f: &mut dyn Formatter,
+        renderer: &dyn EntryRenderer,
+        graph: &CitationGraph,
+        options: &RenderOptions,
+    ) -> Result<()> {
+        if graph.nodes.is_empty() {
+            f.note("None of the given paper IDs could be resolved.");
+            return Ok(());
+        }
+
+        f.heading(&format!(
+            "Citation graph of {} papers and {} citations, from {} starting paper(s):",
+            graph.nodes.len(),
+            graph.edges.len(),
+            graph.seeds.len()
+        ));
+
+        let mut number = 0;
+        let last_hop = graph.nodes.iter().map(|node| node.hop).max().unwrap_or(0);
+        for hop in 0..=last_hop {
+            let nodes: Vec<_> = graph.nodes.iter().filter(|node| node.hop == hop).collect();
+            if hop == 0 {
+                f.heading("Starting papers:");
+            } else {
+                f.heading(&format!("Hop {} ({} papers):", hop, nodes.len()));
+            }
+
+            for node in nodes {
+                number += 1;
+                let paper: Paper = serde_json::from_value(node.paper())?;
+                f.item(number, &renderer.paper_title(&paper));
+                renderer.paper(f, &paper, options);
+
+                let linked = |ids: Vec<&str>| -> Vec<String> {
+                    ids.into_iter()
+                        .filter_map(|id| graph.node(id))
+                        .map(|node| format!("{} (ID: {})", node.title(), node.paper_id))
+                        .collect()
+                };
+                let cites = linked(
+                    graph
+                        .edges
+                        .iter()
+                        .filter(|edge| edge.source == node.paper_id)
+                        .map(|edge| edge.target.as_str())
+                        .collect(),
+                );
+                let cited_by = linked(
+                    graph
+                        .edges
+                        .iter()
+                        .filter(|edge| edge.target == node.paper_id)
+                        .map(|edge| edge.source.as_str())
+                        .collect(),
+                );
+                if !cites.is_empty() {
+                    f.list("Cites", &cites);
+                }
+                if !cited_by.is_empty() {
+                    f.list("Cited by", &cited_by);
+                }
+            }
+        }
+
+        if !graph.unresolved.is_empty() {
+            f.note(&format!(
+                "Could not resolve {} paper ID(s): {}",
+                graph.unresolved.len(),
+                graph.unresolved.join(", ")
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for GraphExpandTool {
+    async fn execute(&self, arguments: Option<Value>) -> Result<Vec<ToolContent>> {
+        tracing::debug!("Executing GraphExpandTool");
+        let args: GraphExpandArguments = parse_arguments(arguments)?;
+
+        let paper_ids = seed_ids(&args.paper_ids)?;
+
+        let options = WalkOptions {
+            direction: args.direction.unwrap_or_default(),
+            hops: args.hops.unwrap_or(1),
+            per_paper_limit: args.per_paper_limit.unwrap_or(50),
+            per_hop_limit: args.per_hop_limit.unwrap_or(100) as usize,
+            fields: args.fields.unwrap_or_else(|| vec![PaperField::Year]),
+        };
+
+        let params = json!({
+            "ids": paper_ids,
+            "direction": options.direction,
+            "hops": options.hops,
+            "per_paper_limit": options.per_paper_limit,
+            "per_hop_limit": options.per_hop_limit,
+            "fields": options.fields,
+        });
+
+        let key = CacheKey::new("graph_expand", &paper_ids.join(",")).params(&params);
+        let response = match cached_response(self.cache.as_ref(), self.embed.as_ref(), &key).await?
+        {
+            CacheLookup::Hit(response) => response,
+            CacheLookup::Miss(embedding) => {
+                let graph = self.walker.walk(&paper_ids, &options).await?;
+                let response = serde_json::to_value(&graph)?;
+                store_response(self.cache.as_ref(), &key, embedding, &response);
+                response
+            }
+        };
+
+        let graph: CitationGraph = serde_json::from_value(response.clone())?;
+        let formatted_result = render(
+            args.output_format,
+            &response,
+            None,
+            args.max_output_chars,
+            |f| {
+                self.formatter
+                    .format(f, self.renderer.as_ref(), &graph, &RenderOptions::default())
+            },
+        )?;
+
+        let output = ToolOutput::new(formatted_result, args.output_format, &response);
+
+        Ok(output.into_contents())
+    }
+
+    fn to_tool(&self) -> Tool {
+        Tool {
+            name: "graph_expand".into(),
+            description: Some(
+                "Walk the citation graph out from one or more papers, following citations and/or references for up to 3 hops, and return the papers reached and the citations between them as a node and edge list"
+                    .into(),
+            ),
+            input_schema: input_schema::<GraphExpandArguments>(),
+        }
+    }
+}
@@ -0,0 +1,439 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use cache::Cache;
+use context_server::{Tool, ToolContent, ToolExecutor};
+use http_client::HttpClient;
+use models::Author;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+use crate::{
+    arguments::{PaperField, input_schema, parse_arguments},
+    citation_graph::{CitationGraph, GraphDirection, GraphWalker, WalkOptions, seed_ids},
+    utils::RateLimiter,
+};
+
+/// Papers with more authors than this are left out of co-authorship graphs,
+/// as they would link every one of their authors to all the others.
+const MAX_COAUTHORS: usize = 50;
+
+/// Which graph to export from the papers a walk reached.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+enum GraphKind {
+    /// Papers linked by the citations between them
+    #[default]
+    Citation,
+    /// Authors of those papers, linked when they wrote one together
+    Coauthorship,
+}
+
+/// How to write the graph.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+enum GraphFormat {
+    #[default]
+    Graphml,
+    Dot,
+    Json,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct GraphExportArguments {
+    /// Papers to start from, in any of the formats paper_details accepts. Maximum: 20
+    paper_ids: Vec<String>,
+    /// Which links to follow from each paper: 'citations' (papers citing it), 'references' (papers it cites) or 'both'. Default: both
+    direction: Option<GraphDirection>,
+    /// Number of hops to walk out from the starting papers. Default: 1, Maximum: 3
+    #[schemars(range(min = 1, max = 3))]
+    hops: Option<u32>,
+    /// Maximum number of citations/references to fetch per paper. Default: 50, Maximum: 1000
+    #[schemars(range(min = 1, max = 1000))]
+    per_paper_limit: Option<u64>,
+    /// Maximum number of new papers to keep at each hop, the most cited first. Default: 100, Maximum: 1000
+    #[schemars(range(min = 1, max = 1000))]
+    per_hop_limit: Option<u64>,
+    /// 'citation' for the papers and the citations between them, or 'coauthorship' for their authors, linked when they wrote one of the papers together. Default: citation
+    #[serde(default)]
+    graph: GraphKind,
+    /// 'graphml' for Gephi, Cytoscape and yEd, 'dot' for Graphviz, or 'json' for a node-link document that networkx reads with node_link_graph. Default: graphml
+    #[serde(default)]
+    format: GraphFormat,
+}
+
+/// A graph ready to be written, with the attributes of its nodes and edges.
+struct ExportGraph {
+    directed: bool,
+    nodes: Vec<ExportNode>,
+    edges: Vec<ExportEdge>,
+}
+
+struct ExportNode {
+    id: String,
+    label: String,
+    attributes: Map<String, Value>,
+}
+
+struct ExportEdge {
+    source: String,
+    target: String,
+    /// How many papers two co-authors wrote together
+    weight: Option<u64>,
+}
+
+impl ExportGraph {
+    fn citations(graph: &CitationGraph) -> Self {
+        let nodes = graph
+            .nodes
+            .iter()
+            .map(|node| {
+                let mut attributes = Map::new();
+                for key in ["year", "citationCount"] {
+                    if let Some(value) = node.fields.get(key).filter(|value| !value.is_null()) {
+                        attributes.insert(key.into(), value.clone());
+                    }
+                }
+                attributes.insert("hop".into(), json!(node.hop));
+                ExportNode {
+                    id: node.paper_id.clone(),
+                    label: node.title().to_string(),
+                    attributes,
+                }
+            })
+            .collect();
+
+        let edges = graph
+            .edges
+            .iter()
+            .map(|edge| ExportEdge {
+                source: edge.source.clone(),
+                target: edge.target.clone(),
+                weight: None,
+            })
+            .collect();
+
+        Self {
+            directed: true,
+            nodes,
+            edges,
+        }
+    }
+
+    fn coauthorship(graph: &CitationGraph) -> Self {
+        // Keyed by ID, so the output comes out the same on every export
+        let mut authors: BTreeMap<String, (String, u64)> = BTreeMap::new();
+        let mut pairs: BTreeMap<(String, String), u64> = BTreeMap::new();
+
+        for node in &graph.nodes {
+            let paper_authors: Vec<Author> = node
+                .fields
+                .get("authors")
+                .and_then(|authors| serde_json::from_value(authors.clone()).ok())
+                .unwrap_or_default();
+            let mut ids: Vec<&str> = paper_authors
+                .iter()
+                .filter_map(|author| {
+                    let id = author.author_id.as_deref()?;
+                    let entry = authors
+                        .entry(id.to_string())
+                        .or_insert_with(|| (author.name.clone().unwrap_or_default(), 0));
+                    entry.1 += 1;
+                    Some(id)
+                })
+                .collect();
+            ids.sort_unstable();
+            ids.dedup();
+
+            if ids.len() > MAX_COAUTHORS {
+                continue;
+            }
+            for (i, a) in ids.iter().enumerate() {
+                for b in &ids[i + 1..] {
+                    *pairs.entry((a.to_string(), b.to_string())).or_default() += 1;
+                }
+            }
+        }
+
+        let nodes = authors
+            .into_iter()
+            .map(|(id, (name, papers))| ExportNode {
+                id,
+                label: name,
+                attributes: Map::from_iter([("papers".to_string(), json!(papers))]),
+            })
+            .collect();
+
+        let edges = pairs
+            .into_iter()
+            .map(|((source, target), weight)| ExportEdge {
+                source,
+                target,
+                weight: Some(weight),
+            })
+            .collect();
+
+        Self {
+            directed: false,
+            nodes,
+            edges,
+        }
+    }
+
+    /// The names of the node attributes, with whether all their values are
+    /// integers, in the order they first appear.
+    fn attribute_keys(&self) -> Vec<(&str, bool)> {
+        let mut keys: Vec<(&str, bool)> = Vec::new();
+        for (key, value) in self.nodes.iter().flat_map(|node| &node.attributes) {
+            let integer = value.is_u64() || value.is_i64();
+            match keys.iter_mut().find(|(k, _)| *k == key.as_str()) {
+                Some((_, all_integers)) => *all_integers &= integer,
+                None => keys.push((key.as_str(), integer)),
+            }
+        }
+        keys
+    }
+
+    fn to_graphml(&self) -> String {
+        let mut output = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n  \
+             <key id=\"label\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>\n",
+        );
+        let keys = self.attribute_keys();
+        for (key, integer) in &keys {
+            output.push_str(&format!(
+                "  <key id=\"{0}\" for=\"node\" attr.name=\"{0}\" attr.type=\"{1}\"/>\n",
+                xml_escape(key),
+                if *integer { "long" } else { "string" }
+            ));
+        }
+        let weighted = self.edges.iter().any(|edge| edge.weight.is_some());
+        if weighted {
+            output.push_str(
+                "  <key id=\"weight\" for=\"edge\" attr.name=\"weight\" attr.type=\"long\"/>\n",
+            );
+        }
+
+        output.push_str(&format!(
+            "  <graph id=\"G\" edgedefault=\"{}\">\n",
+            if self.directed {
+                "directed"
+            } else {
+                "undirected"
+            }
+        ));
+        for node in &self.nodes {
+            output.push_str(&format!("    <node id=\"{}\">\n", xml_escape(&node.id)));
+            output.push_str(&format!(
+                "      <data key=\"label\">{}</data>\n",
+                xml_escape(&node.label)
+            ));
+            for (key, value) in &node.attributes {
+                output.push_str(&format!(
+                    "      <data key=\"{}\">{}</data>\n",
+                    xml_escape(key),
+                    xml_escape(&attribute_text(value))
+                ));
+            }
+            output.push_str("    </node>\n");
+        }
+        for edge in &self.edges {
+            match edge.weight {
+                Some(weight) => output.push_str(&format!(
+                    "    <edge source=\"{}\" target=\"{}\">\n      <data key=\"weight\">{}</data>\n    </edge>\n",
+                    xml_escape(&edge.source),
+                    xml_escape(&edge.target),
+                    weight
+                )),
+                None => output.push_str(&format!(
+                    "    <edge source=\"{}\" target=\"{}\"/>\n",
+                    xml_escape(&edge.source),
+                    xml_escape(&edge.target)
+                )),
+            }
+        }
+        output.push_str("  </graph>\n</graphml>\n");
+        output
+    }
+
+    fn to_dot(&self) -> String {
+        let (keyword, arrow) = if self.directed {
+            ("digraph", "->")
+        } else {
+            ("graph", "--")
+        };
+        let mut output = format!("{} G {{\n", keyword);
+
+        for node in &self.nodes {
+            let mut attributes = vec![format!("label={}", dot_quote(&node.label))];
+            attributes.extend(
+                node.attributes
+                    .iter()
+                    .map(|(key, value)| format!("{}={}", key, dot_quote(&attribute_text(value)))),
+            );
+            output.push_str(&format!(
+                "  {} [{}];\n",
+                dot_quote(&node.id),
+                attributes.join(", ")
+            ));
+        }
+        for edge in &self.edges {
+            let weight = edge
+                .weight
+                .map(|weight| format!(" [weight={}]", weight))
+                .unwrap_or_default();
+            output.push_str(&format!(
+                "  {} {} {}{};\n",
+                dot_quote(&edge.source),
+                arrow,
+                dot_quote(&edge.target),
+                weight
+            ));
+        }
+        output.push_str("}\n");
+        output
+    }
+
+    /// The node-link layout networkx reads with `node_link_graph`.
+    fn to_json(&self) -> Value {
+        json!({
+            "directed": self.directed,
+            "multigraph": false,
+            "graph": {},
+            "nodes": self.nodes.iter().map(|node| {
+                let mut object = Map::new();
+                object.insert("id".into(), json!(node.id));
+                object.insert("label".into(), json!(node.label));
+                object.extend(node.attributes.clone());
+                Value::Object(object)
+            }).collect::<Vec<_>>(),
+            "edges": self.edges.iter().map(|edge| {
+                let mut object = Map::new();
+                object.insert("source".into(), json!(edge.source));
+                object.insert("target".into(), json!(edge.target));
+                if let Some(weight) = edge.weight {
+                    object.insert("weight".into(), json!(weight));
+                }
+                Value::Object(object)
+            }).collect::<Vec<_>>(),
+        })
+    }
+}
+
+fn attribute_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        value => value.to_string(),
+    }
+}
+
+fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // Control characters other than whitespace are not allowed in XML 1.0
+            c if c.is_control() && !matches!(c, '\t' | '\n' | '\r') => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn dot_quote(text: &str) -> String {
+    format!(
+        "\"{}\"",
+        text.replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace(['\n', '\r'], " ")
+    )
+}
+
+pub struct GraphExportTool {
+    walker: GraphWalker,
+}
+
+impl GraphExportTool {
+    pub fn new(
+        http_client: Arc<dyn HttpClient>,
+        rate_limiter: Arc<RateLimiter>,
+        cache: Arc<dyn Cache>,
+    ) -> Self {
+        Self {
+            walker: GraphWalker::new(http_client, rate_limiter, cache),
+        }
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for GraphExportTool {
+    async fn execute(&self, arguments: Option<Value>) -> Result<Vec<ToolContent>> {
+        tracing::debug!("Executing GraphExportTool");
+        let args: GraphExportArguments = parse_arguments(arguments)?;
+        let paper_ids = seed_ids(&args.paper_ids)?;
+
+        let fields = match args.graph {
+            GraphKind::Citation => vec![PaperField::Year],
+            GraphKind::Coauthorship => vec![PaperField::Authors],
+        };
+        let options = WalkOptions {
+            direction: args.direction.unwrap_or_default(),
+            hops: args.hops.unwrap_or(1),
+            per_paper_limit: args.per_paper_limit.unwrap_or(50),
+            per_hop_limit: args.per_hop_limit.unwrap_or(100) as usize,
+            fields,
+        };
+
+        let graph = self.walker.walk(&paper_ids, &options).await?;
+
+        if graph.nodes.is_empty() {
+            return Ok(vec![ToolContent::Text {
+                text: "None of the given paper IDs could be resolved.".into(),
+            }]);
+        }
+
+        let export = match args.graph {
+            GraphKind::Citation => ExportGraph::citations(&graph),
+            GraphKind::Coauthorship => ExportGraph::coauthorship(&graph),
+        };
+        let text = match args.format {
+            GraphFormat::Graphml => export.to_graphml(),
+            GraphFormat::Dot => export.to_dot(),
+            GraphFormat::Json => serde_json::to_string_pretty(&export.to_json())?,
+        };
+
+        let mut contents = vec![ToolContent::Text { text }];
+
+        // Kept out of the export so it can be opened as is
+        if !graph.unresolved.is_empty() {
+            contents.push(ToolContent::Text {
+                text: format!(
+                    "Could not resolve {} paper ID(s): {}",
+                    graph.unresolved.len(),
+                    graph.unresolved.join(", ")
+                ),
+            });
+        }
+
+        Ok(contents)
+    }
+
+    fn to_tool(&self) -> Tool {
+        Tool {
+            name: "graph_export".into(),
+            description: Some(
+                "Walk the citation graph out from one or more papers and export it, or the co-authorship graph of the papers reached, as GraphML, DOT or node-link JSON for Gephi, Cytoscape, Graphviz or networkx"
+                    .into(),
+            ),
+            input_schema: input_schema::<GraphExportArguments>(),
+        }
+    }
+}
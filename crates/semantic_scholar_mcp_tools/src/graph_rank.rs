@@ -0,0 +1,322 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use cache::Cache;
+use context_server::{Tool, ToolContent, ToolExecutor};
+use embed::Embed;
+use http_client::HttpClient;
+use models::Paper;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::{
+    arguments::{PaperField, input_schema, parse_arguments},
+    citation_graph::{
+        CitationGraph, GraphDirection, GraphNode, GraphWalker, WalkOptions, seed_ids,
+    },
+    content::ToolOutput,
+    error::invalid_argument,
+    format::{Formatter, OutputFormat, render},
+    query_cache::{CacheKey, CacheLookup, cached_response, store_response},
+    result_formatter::{DefaultEntryRenderer, EntryRenderer, RenderOptions, ResultFormatter},
+    utils::RateLimiter,
+};
+
+/// How to score the papers of the graph.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum RankAlgorithm {
+    /// Papers cited by papers that are themselves central rank highest
+    #[default]
+    Pagerank,
+    /// Papers are scored as authorities, cited by good hubs, and as hubs,
+    /// citing good authorities; ranked by authority
+    Hits,
+}
+
+impl RankAlgorithm {
+    fn name(&self) -> &'static str {
+        match self {
+            RankAlgorithm::Pagerank => "PageRank",
+            RankAlgorithm::Hits => "HITS authority",
+        }
+    }
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct GraphRankArguments {
+    /// Papers to start from, in any of the formats paper_details accepts. Maximum: 20
+    paper_ids: Vec<String>,
+    /// Which links to follow from each paper: 'citations' (papers citing it), 'references' (papers it cites) or 'both'. Default: both
+    direction: Option<GraphDirection>,
+    /// Number of hops to walk out from the starting papers. Default: 1, Maximum: 3
+    #[schemars(range(min = 1, max = 3))]
+    hops: Option<u32>,
+    /// Maximum number of citations/references to fetch per paper. Default: 50, Maximum: 1000
+    #[schemars(range(min = 1, max = 1000))]
+    per_paper_limit: Option<u64>,
+    /// Maximum number of new papers to keep at each hop, the most cited first. Default: 100, Maximum: 1000
+    #[schemars(range(min = 1, max = 1000))]
+    per_hop_limit: Option<u64>,
+    /// 'pagerank' or 'hits'. Default: pagerank
+    #[serde(default)]
+    algorithm: RankAlgorithm,
+    /// Probability of following a citation rather than jumping to a random paper, for PageRank. Default: 0.85
+    #[schemars(range(min = 0.0, max = 1.0))]
+    damping: Option<f64>,
+    /// Number of top-ranked papers to return. Default: 20, Maximum: 1000
+    #[schemars(range(min = 1, max = 1000))]
+    top: Option<usize>,
+    /// Fields to return for each paper, besides its ID, title and citation count. Default: year
+    fields: Option<Vec<PaperField>>,
+    /// Maximum length of the output in characters. Results past it are left out, with a note saying how many. Default: the server's limit, if any
+    #[schemars(range(min = 1))]
+    max_output_chars: Option<usize>,
+    #[serde(default)]
+    output_format: OutputFormat,
+}
+
+/// A paper of the graph with its scores.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RankedPaper {
+    #[serde(flatten)]
+    pub node: GraphNode,
+    /// PageRank, or HITS authority score
+    pub score: f64,
+    /// HITS hub score
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hub_score: Option<f64>,
+    /// Papers of the graph citing this one
+    pub cited_in_graph: usize,
+}
+
+/// The most central papers of a walked citation graph.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphRanking {
+    pub algorithm: RankAlgorithm,
+    pub seeds: Vec<String>,
+    /// Size of the graph the papers were ranked in
+    pub node_count: usize,
+    pub edge_count: usize,
+    /// The top papers, highest score first
+    pub ranked: Vec<RankedPaper>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unresolved: Vec<String>,
+}
+
+impl GraphRanking {
+    fn new(graph: CitationGraph, algorithm: RankAlgorithm, damping: f64, top: usize) -> Self {
+        let (scores, hub_scores) = match algorithm {
+            RankAlgorithm::Pagerank => (graph.pagerank(damping), None),
+            RankAlgorithm::Hits => {
+                let (hubs, authorities) = graph.hits();
+                (authorities, Some(hubs))
+            }
+        };
+
+        let mut ranked: Vec<RankedPaper> = graph
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| RankedPaper {
+                node: node.clone(),
+                score: scores[i],
+                hub_score: hub_scores.as_ref().map(|hubs| hubs[i]),
+                cited_in_graph: graph
+                    .edges
+                    .iter()
+                    .filter(|edge| edge.target == node.paper_id)
+                    .count(),
+            })
+            .collect();
+        ranked.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.node.paper_id.cmp(&b.node.paper_id))
+        });
+        ranked.truncate(top);
+
+        Self {
+            algorithm,
+            seeds: graph.seeds,
+            node_count: graph.nodes.len(),
+            edge_count: graph.edges.len(),
+            ranked,
+            unresolved: graph.unresolved,
+        }
+    }
+}
+
+pub struct GraphRankTool {
+    walker: GraphWalker,
+    cache: Arc<dyn Cache>,
+    embed: Arc<dyn Embed>,
+    renderer: Arc<dyn EntryRenderer>,
+    formatter: Arc<dyn ResultFormatter<GraphRanking>>,
+}
+
+impl GraphRankTool {
+    pub fn new(
+        http_client: Arc<dyn HttpClient>,
+        rate_limiter: Arc<RateLimiter>,
+        cache: Arc<dyn Cache>,
+        embed: Arc<dyn Embed>,
+    ) -> Self {
+        Self {
+            walker: GraphWalker::new(http_client, rate_limiter, cache.clone()),
+            cache,
+            embed,
+            renderer: Arc::new(DefaultEntryRenderer::new()),
+            formatter: Arc::new(GraphRankFormatter),
+        }
+    }
+
+    /// Writes the entries through `renderer` rather than the default.
+    pub fn with_renderer(mut self, renderer: Arc<dyn EntryRenderer>) -> Self {
+        self.renderer = renderer;
+        self
+    }
+
+    pub fn with_formatter(mut self, formatter: Arc<dyn ResultFormatter<GraphRanking>>) -> Self {
+        self.formatter = formatter;
+        self
+    }
+}
+
+/// Lays out the output of `graph_rank`: the top papers with their scores.
+pub struct GraphRankFormatter;
+
+impl ResultFormatter<GraphRanking> for GraphRankFormatter {
+    fn format(
+        &self,
+        f: &mut dyn Formatter,
+        renderer: &dyn EntryRenderer,
+        ranking: &GraphRanking,
+        options: &RenderOptions,
+    ) -> Result<()> {
+        if ranking.node_count == 0 {
+            f.note("None of the given paper IDs could be resolved.");
+            return Ok(());
+        }
+
+        f.heading(&format!(
+            "Top {} of {} papers and {} citations by {}:",
+            ranking.ranked.len(),
+            ranking.node_count,
+            ranking.edge_count,
+            ranking.algorithm.name()
+        ));
+
+        for (i, ranked) in ranking.ranked.iter().enumerate() {
+            let paper: Paper = serde_json::from_value(ranked.node.paper())?;
+            f.item(i + 1, &renderer.paper_title(&paper));
+            f.field(
+                match ranking.algorithm {
+                    RankAlgorithm::Pagerank => "PageRank",
+                    RankAlgorithm::Hits => "Authority",
+                },
+                &format!("{:.4}", ranked.score),
+            );
+            if let Some(hub_score) = ranked.hub_score {
+                f.field("Hub", &format!("{:.4}", hub_score));
+            }
+            f.field("Cited in Graph", &ranked.cited_in_graph.to_string());
+            renderer.paper(f, &paper, options);
+        }
+
+        if !ranking.unresolved.is_empty() {
+            f.note(&format!(
+                "Could not resolve {} paper ID(s): {}",
+                ranking.unresolved.len(),
+                ranking.unresolved.join(", ")
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for GraphRankTool {
+    async fn execute(&self, arguments: Option<Value>) -> Result<Vec<ToolContent>> {
+        tracing::debug!("Executing GraphRankTool");
+        let args: GraphRankArguments = parse_arguments(arguments)?;
+
+        let paper_ids = seed_ids(&args.paper_ids)?;
+
+        let damping = args.damping.unwrap_or(0.85);
+        if !(0.0..=1.0).contains(&damping) {
+            return Err(invalid_argument("damping must be between 0 and 1"));
+        }
+        let top = args.top.unwrap_or(20);
+
+        let options = WalkOptions {
+            direction: args.direction.unwrap_or_default(),
+            hops: args.hops.unwrap_or(1),
+            per_paper_limit: args.per_paper_limit.unwrap_or(50),
+            per_hop_limit: args.per_hop_limit.unwrap_or(100) as usize,
+            fields: args.fields.unwrap_or_else(|| vec![PaperField::Year]),
+        };
+
+        let params = json!({
+            "ids": paper_ids,
+            "direction": options.direction,
+            "hops": options.hops,
+            "per_paper_limit": options.per_paper_limit,
+            "per_hop_limit": options.per_hop_limit,
+            "fields": options.fields,
+            "algorithm": args.algorithm,
+            "damping": damping,
+            "top": top,
+        });
+
+        let key = CacheKey::new("graph_rank", &paper_ids.join(",")).params(&params);
+        let response = match cached_response(self.cache.as_ref(), self.embed.as_ref(), &key).await?
+        {
+            CacheLookup::Hit(response) => response,
+            CacheLookup::Miss(embedding) => {
+                let graph = self.walker.walk(&paper_ids, &options).await?;
+                let ranking = GraphRanking::new(graph, args.algorithm, damping, top);
+                let response = serde_json::to_value(&ranking)?;
+                store_response(self.cache.as_ref(), &key, embedding, &response);
+                response
+            }
+        };
+
+        let ranking: GraphRanking = serde_json::from_value(response.clone())?;
+        let formatted_result = render(
+            args.output_format,
+            &response,
+            None,
+            args.max_output_chars,
+            |f| {
+                self.formatter.format(
+                    f,
+                    self.renderer.as_ref(),
+                    &ranking,
+                    &RenderOptions::default(),
+                )
+            },
+        )?;
+
+        let output = ToolOutput::new(formatted_result, args.output_format, &response);
+
+        Ok(output.into_contents())
+    }
+
+    fn to_tool(&self) -> Tool {
+        Tool {
+            name: "graph_rank".into(),
+            description: Some(
+                "Walk the citation graph out from one or more papers, as graph_expand does, and rank the papers reached by PageRank or HITS over the citations between them, to find the most central papers of a topic regardless of their overall citation counts"
+                    .into(),
+            ),
+            input_schema: input_schema::<GraphRankArguments>(),
+        }
+    }
+}
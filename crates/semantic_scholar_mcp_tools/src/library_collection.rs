@@ -0,0 +1,506 @@
+use std::{collections::HashSet, sync::Arc};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use cache::Cache;
+use context_server::{Tool, ToolContent, ToolExecutor};
+use http_client::HttpClient;
+use library::{Library, LibraryEntry};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::{
+    arguments::{input_schema, parse_arguments},
+    content::ToolOutput,
+    error::invalid_argument,
+    export::{citation_key, fetch_papers, unresolved_note},
+    format::{Formatter, OutputFormat, render},
+    utils::{RateLimiter, normalize_paper_id},
+};
+
+/// Trims tags and drops empty and repeated ones, keeping their order.
+fn clean_tags(tags: Option<Vec<String>>) -> Vec<String> {
+    let mut cleaned: Vec<String> = Vec::new();
+    for tag in tags.unwrap_or_default() {
+        let tag = tag.trim();
+        if !tag.is_empty() && !cleaned.iter().any(|existing| existing == tag) {
+            cleaned.push(tag.to_string());
+        }
+    }
+    cleaned
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct LibraryAddArguments {
+    /// Papers to save, in any of the formats paper_details accepts (max: 500)
+    paper_ids: Vec<String>,
+    /// Tags to file the papers under, added to those of papers already saved
+    tags: Option<Vec<String>>,
+}
+
+pub struct LibraryAddTool {
+    http_client: Arc<dyn HttpClient>,
+    rate_limiter: Arc<RateLimiter>,
+    cache: Arc<dyn Cache>,
+    library: Arc<Library>,
+}
+
+impl LibraryAddTool {
+    pub fn new(
+        http_client: Arc<dyn HttpClient>,
+        rate_limiter: Arc<RateLimiter>,
+        cache: Arc<dyn Cache>,
+        library: Arc<Library>,
+    ) -> Self {
+        Self {
+            http_client,
+            rate_limiter,
+            cache,
+            library,
+        }
+    }
+
+    /// A citation key for a new entry that no other entry uses, with a letter,
+    /// or past 'z' a number, appended when the plain key is taken.
+    fn unique_key(&self, base: &str, taken: &HashSet<String>) -> Result<String> {
+        let suffixes = std::iter::once(String::new())
+            .chain(('a'..='z').map(String::from))
+            .chain((27..).map(|n: u32| n.to_string()));
+        for suffix in suffixes {
+            let key = format!("{}{}", base, suffix);
+            if !taken.contains(&key) && self.library.get(&key)?.is_none() {
+                return Ok(key);
+            }
+        }
+        unreachable!("the suffixes never run out")
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for LibraryAddTool {
+    async fn execute(&self, arguments: Option<Value>) -> Result<Vec<ToolContent>> {
+        tracing::debug!("Executing LibraryAddTool");
+        let args: LibraryAddArguments = parse_arguments(arguments)?;
+        let tags = clean_tags(args.tags);
+
+        let papers = fetch_papers(
+            &self.http_client,
+            &self.rate_limiter,
+            &self.cache,
+            &args.paper_ids,
+        )
+        .await?;
+
+        let added_at = chrono::Utc::now().naive_utc();
+        let mut entries: Vec<LibraryEntry> = Vec::new();
+        let mut keys: HashSet<String> = HashSet::new();
+        let mut already_saved: Vec<String> = Vec::new();
+
+        for paper in papers.iter().filter_map(|(_, paper)| paper.as_ref()) {
+            let Some(paper_id) = paper.paper_id.as_deref() else {
+                continue;
+            };
+            if entries
+                .iter()
+                .any(|entry| entry.paper_id.as_deref() == Some(paper_id))
+            {
+                continue;
+            }
+
+            let entry = match self.library.find_paper(paper_id)? {
+                Some(mut entry) => {
+                    already_saved.push(entry.key.clone());
+                    for tag in &tags {
+                        if !entry.tags.contains(tag) {
+                            entry.tags.push(tag.clone());
+                        }
+                    }
+                    entry
+                }
+                None => LibraryEntry {
+                    key: self.unique_key(&citation_key(paper), &keys)?,
+                    paper_id: Some(paper_id.to_string()),
+                    title: paper.title.clone(),
+                    doi: paper.external_ids.as_ref().and_then(|ids| ids.doi.clone()),
+                    entry_type: None,
+                    matched_by: None,
+                    match_score: None,
+                    tags: tags.clone(),
+                    added_at,
+                },
+            };
+            keys.insert(entry.key.clone());
+            entries.push(entry);
+        }
+
+        self.library.put(&entries)?;
+
+        let mut result = format!(
+            "Saved {} papers to the library: {} new, {} already saved.\n",
+            entries.len(),
+            entries.len() - already_saved.len(),
+            already_saved.len()
+        );
+        if !tags.is_empty() {
+            result.push_str(&format!("Tagged: {}\n", tags.join(", ")));
+        }
+        for entry in &entries {
+            result.push_str(&format!(
+                "- {}: {}\n",
+                entry.key,
+                entry.title.as_deref().unwrap_or("Unknown title")
+            ));
+        }
+        if let Some(note) = unresolved_note(&papers) {
+            result.push_str(&format!("\n{}\n", note));
+        }
+
+        let added: Vec<&str> = entries
+            .iter()
+            .map(|entry| entry.key.as_str())
+            .filter(|key| !already_saved.iter().any(|saved| saved == key))
+            .collect();
+        let missing: Vec<&str> = papers
+            .iter()
+            .filter(|(_, paper)| paper.is_none())
+            .map(|(paper_id, _)| paper_id.as_str())
+            .collect();
+        let data = json!({
+            "added": added,
+            "already_saved": already_saved,
+            "tags": tags,
+            "missing": missing,
+        });
+
+        Ok(ToolOutput::from_text(result.trim_end().to_string(), &data).into_contents())
+    }
+
+    fn to_tool(&self) -> Tool {
+        Tool {
+            name: "library_add".into(),
+            description: Some(
+                "Save papers to the local library, optionally filed under tags, to build a collection that lasts across sessions"
+                    .into(),
+            ),
+            input_schema: input_schema::<LibraryAddArguments>(),
+        }
+    }
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct LibraryListArguments {
+    /// Only list papers filed under this tag
+    tag: Option<String>,
+    /// Maximum length of the output in characters. Results past it are left out, with a note saying how many. Default: the server's limit, if any
+    #[schemars(range(min = 1))]
+    max_output_chars: Option<usize>,
+    #[serde(default)]
+    output_format: OutputFormat,
+}
+
+pub struct LibraryListTool {
+    library: Arc<Library>,
+}
+
+impl LibraryListTool {
+    pub fn new(library: Arc<Library>) -> Self {
+        Self { library }
+    }
+
+    fn format_entries(
+        &self,
+        f: &mut dyn Formatter,
+        entries: &[LibraryEntry],
+        tag: Option<&str>,
+    ) -> Result<()> {
+        if entries.is_empty() {
+            match tag {
+                Some(tag) => f.note(&format!("No papers in the library are tagged '{}'.", tag)),
+                None => f.note("The library is empty."),
+            }
+            return Ok(());
+        }
+
+        match tag {
+            Some(tag) => f.heading(&format!(
+                "{} papers in the library tagged '{}':",
+                entries.len(),
+                tag
+            )),
+            None => f.heading(&format!("{} papers in the library:", entries.len())),
+        }
+
+        for (i, entry) in entries.iter().enumerate() {
+            f.item(
+                i + 1,
+                &format!(
+                    "{} (ID: {})",
+                    entry.title.as_deref().unwrap_or("Unknown title"),
+                    entry.paper_id.as_deref().unwrap_or("unresolved")
+                ),
+            );
+            f.field("Key", &entry.key);
+            if !entry.tags.is_empty() {
+                f.field("Tags", &entry.tags.join(", "));
+            }
+            f.field(
+                "Saved",
+                &entry.added_at.format("%Y-%m-%d %H:%M").to_string(),
+            );
+            if let Some(doi) = &entry.doi {
+                f.field("DOI", doi);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for LibraryListTool {
+    async fn execute(&self, arguments: Option<Value>) -> Result<Vec<ToolContent>> {
+        tracing::debug!("Executing LibraryListTool");
+        let args: LibraryListArguments = parse_arguments(arguments)?;
+        let tag = args
+            .tag
+            .map(|tag| tag.trim().to_string())
+            .filter(|tag| !tag.is_empty());
+
+        let mut entries: Vec<LibraryEntry> = self
+            .library
+            .list()?
+            .into_iter()
+            .filter(|entry| tag.as_ref().is_none_or(|tag| entry.tags.contains(tag)))
+            .collect();
+        // Most recently saved first
+        entries.sort_by(|a, b| b.added_at.cmp(&a.added_at).then_with(|| a.key.cmp(&b.key)));
+
+        let result = json!({ "data": entries });
+        let formatted_result = render(
+            args.output_format,
+            &result,
+            None,
+            args.max_output_chars,
+            |f| self.format_entries(f, &entries, tag.as_deref()),
+        )?;
+
+        Ok(ToolOutput::new(formatted_result, args.output_format, &result).into_contents())
+    }
+
+    fn to_tool(&self) -> Tool {
+        Tool {
+            name: "library_list".into(),
+            description: Some(
+                "List the papers saved in the local library, most recently saved first, with their citation keys, tags and saved dates"
+                    .into(),
+            ),
+            input_schema: input_schema::<LibraryListArguments>(),
+        }
+    }
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct LibraryRemoveArguments {
+    /// Entries to remove, by citation key or by the ID of their paper in any of the formats paper_details accepts
+    entries: Vec<String>,
+    /// Remove only these tags from the entries, keeping the entries saved
+    tags: Option<Vec<String>>,
+}
+
+pub struct LibraryRemoveTool {
+    http_client: Arc<dyn HttpClient>,
+    rate_limiter: Arc<RateLimiter>,
+    cache: Arc<dyn Cache>,
+    library: Arc<Library>,
+}
+
+impl LibraryRemoveTool {
+    pub fn new(
+        http_client: Arc<dyn HttpClient>,
+        rate_limiter: Arc<RateLimiter>,
+        cache: Arc<dyn Cache>,
+        library: Arc<Library>,
+    ) -> Self {
+        Self {
+            http_client,
+            rate_limiter,
+            cache,
+            library,
+        }
+    }
+
+    /// The entry a citation key, Semantic Scholar ID or DOI refers to, found
+    /// without asking the API.
+    fn find(&self, reference: &str) -> Result<Option<LibraryEntry>> {
+        if let Some(entry) = self.library.get(reference)? {
+            return Ok(Some(entry));
+        }
+
+        let paper_id = normalize_paper_id(reference);
+        if let Some(doi) = paper_id.strip_prefix("DOI:") {
+            return Ok(self.library.list()?.into_iter().find(|entry| {
+                entry
+                    .doi
+                    .as_deref()
+                    .is_some_and(|entry_doi| entry_doi.eq_ignore_ascii_case(doi))
+            }));
+        }
+        self.library.find_paper(&paper_id)
+    }
+
+    /// The entries the other identifiers, such as arXiv IDs or links, refer
+    /// to, found by resolving them to Semantic Scholar IDs. References that
+    /// don't resolve, or can't while the API is unreachable, are left out.
+    async fn resolve(&self, references: &[&str]) -> Result<Vec<(String, LibraryEntry)>> {
+        let paper_ids: Vec<String> = references.iter().map(|s| s.to_string()).collect();
+        let papers = match fetch_papers(
+            &self.http_client,
+            &self.rate_limiter,
+            &self.cache,
+            &paper_ids,
+        )
+        .await
+        {
+            Ok(papers) => papers,
+            Err(err) => {
+                tracing::warn!("Failed to resolve library references: {}", err);
+                return Ok(Vec::new());
+            }
+        };
+
+        let mut found = Vec::new();
+        for (reference, (_, paper)) in references.iter().zip(papers) {
+            let Some(paper_id) = paper.and_then(|paper| paper.paper_id) else {
+                continue;
+            };
+            if let Some(entry) = self.library.find_paper(&paper_id)? {
+                found.push((reference.to_string(), entry));
+            }
+        }
+        Ok(found)
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for LibraryRemoveTool {
+    async fn execute(&self, arguments: Option<Value>) -> Result<Vec<ToolContent>> {
+        tracing::debug!("Executing LibraryRemoveTool");
+        let args: LibraryRemoveArguments = parse_arguments(arguments)?;
+
+        let references: Vec<&str> = args
+            .entries
+            .iter()
+            .map(|reference| reference.trim())
+            .filter(|reference| !reference.is_empty())
+            .collect();
+        if references.is_empty() {
+            return Err(invalid_argument(
+                "Must provide at least one entry to remove",
+            ));
+        }
+        let tags = args.tags.map(|tags| clean_tags(Some(tags)));
+        if tags.as_ref().is_some_and(Vec::is_empty) {
+            return Err(invalid_argument(
+                "Must provide at least one tag to remove, or leave tags out to remove the entries",
+            ));
+        }
+
+        let mut found = Vec::new();
+        let mut unmatched = Vec::new();
+        for reference in references {
+            match self.find(reference)? {
+                Some(entry) => found.push(entry),
+                None => unmatched.push(reference),
+            }
+        }
+
+        let resolved = if unmatched.is_empty() {
+            Vec::new()
+        } else {
+            self.resolve(&unmatched).await?
+        };
+        let missing: Vec<&str> = unmatched
+            .into_iter()
+            .filter(|reference| !resolved.iter().any(|(resolved, _)| resolved == reference))
+            .collect();
+        found.extend(resolved.into_iter().map(|(_, entry)| entry));
+
+        let mut seen: HashSet<String> = HashSet::new();
+        let mut changed: Vec<String> = Vec::new();
+        let mut untouched: Vec<String> = Vec::new();
+        for mut entry in found {
+            // The same entry may be named twice, by key and by paper ID
+            if !seen.insert(entry.key.clone()) {
+                continue;
+            }
+            match &tags {
+                Some(tags) => {
+                    let count = entry.tags.len();
+                    entry.tags.retain(|tag| !tags.contains(tag));
+                    if entry.tags.len() == count {
+                        untouched.push(entry.key);
+                        continue;
+                    }
+                    self.library.put(std::slice::from_ref(&entry))?;
+                }
+                None => {
+                    self.library.remove(&entry.key)?;
+                }
+            }
+            changed.push(entry.key);
+        }
+
+        let mut result = match &tags {
+            Some(tags) => format!(
+                "Removed the tags {} from {} entries: {}\n",
+                tags.join(", "),
+                changed.len(),
+                changed.join(", ")
+            ),
+            None => format!(
+                "Removed {} entries from the library: {}\n",
+                changed.len(),
+                changed.join(", ")
+            ),
+        };
+        if !untouched.is_empty() {
+            result.push_str(&format!(
+                "\nNot tagged with them: {}\n",
+                untouched.join(", ")
+            ));
+        }
+        if !missing.is_empty() {
+            result.push_str(&format!("\nNot in the library: {}\n", missing.join(", ")));
+        }
+
+        let data = match &tags {
+            Some(tags) => json!({
+                "tags": tags,
+                "untagged": changed,
+                "not_tagged": untouched,
+                "missing": missing,
+            }),
+            None => json!({
+                "removed": changed,
+                "missing": missing,
+            }),
+        };
+
+        Ok(ToolOutput::from_text(result.trim_end().to_string(), &data).into_contents())
+    }
+
+    fn to_tool(&self) -> Tool {
+        Tool {
+            name: "library_remove".into(),
+            description: Some(
+                "Remove papers from the local library by citation key or paper ID, or only take tags off them"
+                    .into(),
+            ),
+            input_schema: input_schema::<LibraryRemoveArguments>(),
+        }
+    }
+}
@@ -0,0 +1,326 @@
+use std::{collections::HashMap, sync::Arc};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use cache::Cache;
+use context_server::{Tool, ToolContent, ToolExecutor};
+use http_client::HttpClient;
+use library::{Library, LibraryEntry};
+use models::{ExternalIds, Paper};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    arguments::{input_schema, parse_arguments},
+    export::{fetch_papers, single_line, split_name, split_pages},
+    export_ris::ris_record,
+    utils::RateLimiter,
+};
+
+/// The most papers fetched per batch request, matching the batch endpoint.
+const BATCH_SIZE: usize = 500;
+
+/// How to write the library.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+enum LibraryExportFormat {
+    #[default]
+    Bibtex,
+    Ris,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct LibraryExportArguments {
+    /// 'bibtex' or 'ris'. Default: bibtex
+    #[serde(default)]
+    format: LibraryExportFormat,
+    /// Only export papers filed under this tag. Default: the whole library
+    tag: Option<String>,
+    /// If true, include each paper's abstract. Default: false
+    #[serde(default)]
+    include_abstract: bool,
+}
+
+pub struct LibraryExportTool {
+    http_client: Arc<dyn HttpClient>,
+    rate_limiter: Arc<RateLimiter>,
+    cache: Arc<dyn Cache>,
+    library: Arc<Library>,
+}
+
+impl LibraryExportTool {
+    pub fn new(
+        http_client: Arc<dyn HttpClient>,
+        rate_limiter: Arc<RateLimiter>,
+        cache: Arc<dyn Cache>,
+        library: Arc<Library>,
+    ) -> Self {
+        Self {
+            http_client,
+            rate_limiter,
+            cache,
+            library,
+        }
+    }
+
+    /// The full records of the entries' papers, by paper ID. Papers seen
+    /// before with every exported field come from the cache; the rest are
+    /// fetched. Batches that fail to load are skipped with a warning, leaving
+    /// their entries to be written from what the library stored.
+    async fn fetch_entries(&self, entries: &[LibraryEntry]) -> HashMap<String, Paper> {
+        let paper_ids: Vec<String> = entries
+            .iter()
+            .filter_map(|entry| entry.paper_id.clone())
+            .collect();
+
+        let mut papers = HashMap::new();
+        for chunk in paper_ids.chunks(BATCH_SIZE) {
+            match fetch_papers(&self.http_client, &self.rate_limiter, &self.cache, chunk).await {
+                Ok(fetched) => {
+                    papers.extend(
+                        fetched
+                            .into_iter()
+                            .filter_map(|(paper_id, paper)| Some((paper_id, paper?))),
+                    );
+                }
+                Err(err) => tracing::warn!("Failed to fetch library papers: {}", err),
+            }
+        }
+        papers
+    }
+}
+
+/// The paper as far as the library knows it, for entries whose paper is
+/// unresolved or couldn't be fetched.
+fn stored_paper(entry: &LibraryEntry) -> Paper {
+    Paper {
+        paper_id: entry.paper_id.clone(),
+        title: entry.title.clone(),
+        external_ids: entry.doi.clone().map(|doi| ExternalIds {
+            doi: Some(doi),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+/// The BibTeX entry type: the one the entry was imported with, or else the
+/// first publication type with a BibTeX equivalent. Papers with a journal but
+/// no usable type are treated as articles.
+fn bibtex_type(entry: &LibraryEntry, paper: &Paper) -> String {
+    if let Some(entry_type) = &entry.entry_type {
+        return entry_type.to_lowercase();
+    }
+
+    let publication_type =
+        paper.publication_types.iter().flatten().find_map(
+            |publication_type| match publication_type.as_str() {
+                "Conference" => Some("inproceedings"),
+                "JournalArticle" | "Review" => Some("article"),
+                "BookSection" => Some("incollection"),
+                "Book" => Some("book"),
+                _ => None,
+            },
+        );
+    let has_journal = paper
+        .journal
+        .as_ref()
+        .and_then(|journal| journal.name.as_deref())
+        .is_some_and(|name| !name.is_empty());
+
+    publication_type
+        .unwrap_or(if has_journal { "article" } else { "misc" })
+        .to_string()
+}
+
+/// Escapes the characters BibTeX and LaTeX treat specially.
+fn bibtex_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in single_line(text).chars() {
+        match c {
+            '\\' => escaped.push_str("\\textbackslash{}"),
+            '~' => escaped.push_str("\\textasciitilde{}"),
+            '^' => escaped.push_str("\\textasciicircum{}"),
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// A paper as a BibTeX entry under the library's citation key, with its
+/// tags as keywords.
+fn bibtex_entry(entry: &LibraryEntry, paper: &Paper, include_abstract: bool) -> String {
+    let entry_type = bibtex_type(entry, paper);
+    let mut fields: Vec<(&str, String)> = Vec::new();
+
+    if let Some(title) = &paper.title {
+        // Doubled braces keep the title's capitalization
+        fields.push(("title", format!("{{{}}}", bibtex_escape(title))));
+    }
+
+    let authors: Vec<String> = paper
+        .author_names()
+        .into_iter()
+        .map(|name| match split_name(name) {
+            (Some(given), family) => format!("{}, {}", family, given),
+            (None, family) => family.to_string(),
+        })
+        .collect();
+    if !authors.is_empty() {
+        fields.push(("author", bibtex_escape(&authors.join(" and "))));
+    }
+
+    if let Some(year) = paper.year {
+        fields.push(("year", year.to_string()));
+    }
+
+    let journal = paper.journal.as_ref();
+    let journal_name = journal
+        .and_then(|journal| journal.name.as_deref())
+        .filter(|name| !name.is_empty());
+    if let Some(venue) = journal_name.or(paper.venue()) {
+        let field = match entry_type.as_str() {
+            "article" => "journal",
+            "inproceedings" | "incollection" => "booktitle",
+            _ => "howpublished",
+        };
+        fields.push((field, bibtex_escape(venue)));
+    }
+
+    if let Some(volume) = journal.and_then(|journal| journal.volume.as_deref()) {
+        fields.push(("volume", bibtex_escape(volume)));
+    }
+
+    if let Some(pages) = journal.and_then(|journal| journal.pages.as_deref()) {
+        let pages = match split_pages(pages) {
+            (start, Some(end)) => format!("{}--{}", start, end),
+            (start, None) => start.to_string(),
+        };
+        fields.push(("pages", bibtex_escape(&pages)));
+    }
+
+    if let Some(doi) = paper
+        .external_ids
+        .as_ref()
+        .and_then(|ids| ids.doi.as_deref())
+    {
+        // DOIs and URLs are read verbatim, so only braces would break them
+        fields.push(("doi", doi.replace(['{', '}'], "")));
+    }
+
+    if let Some(url) = &paper.url {
+        fields.push(("url", url.replace(['{', '}'], "")));
+    }
+
+    if include_abstract {
+        if let Some(abstract_text) = paper.abstract_text() {
+            fields.push(("abstract", bibtex_escape(abstract_text)));
+        }
+    }
+
+    if !entry.tags.is_empty() {
+        fields.push(("keywords", bibtex_escape(&entry.tags.join(", "))));
+    }
+
+    let fields: Vec<String> = fields
+        .into_iter()
+        .map(|(name, value)| format!("  {} = {{{}}}", name, value))
+        .collect();
+    format!(
+        "@{}{{{},\n{}\n}}\n",
+        entry_type,
+        entry.key,
+        fields.join(",\n")
+    )
+}
+
+#[async_trait]
+impl ToolExecutor for LibraryExportTool {
+    async fn execute(&self, arguments: Option<Value>) -> Result<Vec<ToolContent>> {
+        tracing::debug!("Executing LibraryExportTool");
+        let args: LibraryExportArguments = parse_arguments(arguments)?;
+        let tag = args
+            .tag
+            .map(|tag| tag.trim().to_string())
+            .filter(|tag| !tag.is_empty());
+
+        let entries: Vec<LibraryEntry> = self
+            .library
+            .list()?
+            .into_iter()
+            .filter(|entry| tag.as_ref().is_none_or(|tag| entry.tags.contains(tag)))
+            .collect();
+
+        if entries.is_empty() {
+            let text = match &tag {
+                Some(tag) => format!("No papers in the library are tagged '{}'.", tag),
+                None => "The library is empty.".to_string(),
+            };
+            return Ok(vec![ToolContent::Text { text }]);
+        }
+
+        let papers = self.fetch_entries(&entries).await;
+
+        let mut incomplete = Vec::new();
+        let records: Vec<String> = entries
+            .iter()
+            .map(|entry| {
+                let fetched = entry
+                    .paper_id
+                    .as_ref()
+                    .and_then(|paper_id| papers.get(paper_id));
+                let stored;
+                let paper = match fetched {
+                    Some(paper) => paper,
+                    None => {
+                        incomplete.push(entry.key.as_str());
+                        stored = stored_paper(entry);
+                        &stored
+                    }
+                };
+                match args.format {
+                    LibraryExportFormat::Bibtex => {
+                        bibtex_entry(entry, paper, args.include_abstract)
+                    }
+                    LibraryExportFormat::Ris => {
+                        ris_record(paper, args.include_abstract, &entry.tags)
+                    }
+                }
+            })
+            .collect();
+
+        let mut contents = vec![ToolContent::Text {
+            text: records.join("\n"),
+        }];
+
+        // Kept out of the export so it can be imported as is
+        if !incomplete.is_empty() {
+            contents.push(ToolContent::Text {
+                text: format!(
+                    "{} entries were written from what the library stored, as their papers are unresolved or could not be fetched: {}",
+                    incomplete.len(),
+                    incomplete.join(", ")
+                ),
+            });
+        }
+
+        Ok(contents)
+    }
+
+    fn to_tool(&self) -> Tool {
+        Tool {
+            name: "library_export".into(),
+            description: Some(
+                "Export the local library, or the papers filed under a tag, as BibTeX or RIS, with each paper's details fetched fresh so the library doubles as a bibliography"
+                    .into(),
+            ),
+            input_schema: input_schema::<LibraryExportArguments>(),
+        }
+    }
+}
@@ -0,0 +1,339 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use biblatex::{Bibliography, ChunksExt, Entry};
+use cache::Cache;
+use context_server::{Tool, ToolContent, ToolExecutor};
+use http_client::HttpClient;
+use library::{Library, LibraryEntry, MatchMethod};
+use models::{Page, Paper};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::{
+    arguments::{input_schema, parse_arguments},
+    error::{Error, invalid_argument},
+    export::{fetch_papers, single_line},
+    progress::Progress,
+    utils::{RateLimiter, make_request},
+};
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct LibraryImportBibtexArguments {
+    /// Path of a .bib file to import. Provide either path or content
+    path: Option<String>,
+    /// BibTeX source to import. Provide either path or content
+    content: Option<String>,
+    /// If true, entries without a resolvable DOI or arXiv ID are matched by title. Default: true
+    #[serde(default = "default_match_titles")]
+    match_titles: bool,
+}
+
+fn default_match_titles() -> bool {
+    true
+}
+
+/// What the import needs from a BibTeX entry to find its paper.
+struct Candidate {
+    key: String,
+    entry_type: String,
+    title: Option<String>,
+    doi: Option<String>,
+    arxiv: Option<String>,
+}
+
+impl Candidate {
+    fn from_entry(entry: &Entry) -> Self {
+        let field = |name: &str| {
+            entry
+                .get(name)
+                .map(|chunks| single_line(&chunks.format_verbatim()))
+                .filter(|value| !value.is_empty())
+        };
+
+        let doi = field("doi").map(|doi| {
+            let doi = doi
+                .trim_start_matches("https://doi.org/")
+                .trim_start_matches("http://dx.doi.org/");
+            doi.to_string()
+        });
+
+        // biblatex writes eprinttype, BibTeX archivePrefix
+        let is_arxiv = field("eprinttype")
+            .or_else(|| field("archiveprefix"))
+            .is_some_and(|archive| archive.eq_ignore_ascii_case("arxiv"));
+        let arxiv = if is_arxiv { field("eprint") } else { None };
+
+        Self {
+            key: entry.key.clone(),
+            entry_type: entry.entry_type.to_string(),
+            title: field("title"),
+            doi,
+            arxiv,
+        }
+    }
+
+    /// The external ID the Graph API can look the paper up by, if any.
+    fn external_id(&self) -> Option<(String, MatchMethod)> {
+        if let Some(doi) = &self.doi {
+            return Some((format!("DOI:{}", doi), MatchMethod::Doi));
+        }
+        self.arxiv
+            .as_ref()
+            .map(|arxiv| (format!("ARXIV:{}", arxiv), MatchMethod::Arxiv))
+    }
+}
+
+/// The most IDs resolved per batch request.
+const BATCH_SIZE: usize = 500;
+
+pub struct LibraryImportBibtexTool {
+    http_client: Arc<dyn HttpClient>,
+    rate_limiter: Arc<RateLimiter>,
+    cache: Arc<dyn Cache>,
+    library: Arc<Library>,
+}
+
+impl LibraryImportBibtexTool {
+    pub fn new(
+        http_client: Arc<dyn HttpClient>,
+        rate_limiter: Arc<RateLimiter>,
+        cache: Arc<dyn Cache>,
+        library: Arc<Library>,
+    ) -> Self {
+        Self {
+            http_client,
+            rate_limiter,
+            cache,
+            library,
+        }
+    }
+
+    /// Resolves the entries that have a DOI or arXiv ID with batch requests.
+    async fn resolve_external_ids(
+        &self,
+        entries: &mut [LibraryEntry],
+        candidates: &[Candidate],
+        progress: &Progress,
+    ) {
+        let lookups: Vec<(usize, String, MatchMethod)> = candidates
+            .iter()
+            .enumerate()
+            .filter_map(|(i, candidate)| {
+                candidate
+                    .external_id()
+                    .map(|(external_id, method)| (i, external_id, method))
+            })
+            .collect();
+
+        let batches = self
+            .rate_limiter
+            .concurrently(lookups.chunks(BATCH_SIZE), |chunk| async move {
+                let ids: Vec<String> = chunk.iter().map(|(_, id, _)| id.clone()).collect();
+                let papers =
+                    fetch_papers(&self.http_client, &self.rate_limiter, &self.cache, &ids).await;
+                progress.advance(&format!(
+                    "Resolved a batch of {} entries by DOI or arXiv ID",
+                    chunk.len()
+                ));
+                (chunk, papers)
+            })
+            .await;
+
+        for (chunk, papers) in batches {
+            let papers = match papers {
+                Ok(papers) => papers,
+                Err(err) => {
+                    tracing::warn!("Failed to resolve BibTeX entries by ID: {}", err);
+                    continue;
+                }
+            };
+
+            for ((i, _, method), (_, paper)) in chunk.iter().zip(papers) {
+                if let Some(paper_id) = paper.and_then(|paper| paper.paper_id) {
+                    entries[*i].paper_id = Some(paper_id);
+                    entries[*i].matched_by = Some(*method);
+                }
+            }
+        }
+    }
+
+    /// Finds the closest paper to a title with the title match endpoint.
+    async fn match_title(&self, title: &str) -> Result<Option<Paper>> {
+        let params = json!({ "query": title, "fields": "paperId,title" });
+
+        let result = make_request(
+            &self.http_client,
+            &self.rate_limiter,
+            Some(self.cache.as_ref()),
+            "/paper/search/match",
+            Some(&params),
+            None,
+            None,
+        )
+        .await;
+
+        match result {
+            Ok(result) => {
+                let page: Page<Paper> = serde_json::from_value(result)?;
+                Ok(page.data.into_iter().next())
+            }
+            // The endpoint answers 404 when nothing matches closely enough
+            Err(err) if matches!(err.downcast_ref::<Error>(), Some(Error::NotFound(_))) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for LibraryImportBibtexTool {
+    async fn execute(&self, arguments: Option<Value>) -> Result<Vec<ToolContent>> {
+        tracing::debug!("Executing LibraryImportBibtexTool");
+        let args: LibraryImportBibtexArguments = parse_arguments(arguments)?;
+
+        let source = match (args.path, args.content) {
+            (Some(path), None) => std::fs::read_to_string(&path)
+                .map_err(|err| invalid_argument(format!("Failed to read {}: {}", path, err)))?,
+            (None, Some(content)) => content,
+            _ => return Err(invalid_argument("Provide exactly one of path or content")),
+        };
+
+        let bibliography = Bibliography::parse(&source)
+            .map_err(|err| invalid_argument(format!("Failed to parse BibTeX: {}", err)))?;
+
+        let candidates: Vec<Candidate> = bibliography.iter().map(Candidate::from_entry).collect();
+
+        if candidates.is_empty() {
+            return Err(invalid_argument("The BibTeX source contains no entries"));
+        }
+
+        let added_at = chrono::Utc::now().naive_utc();
+        let mut entries: Vec<LibraryEntry> = candidates
+            .iter()
+            .map(|candidate| LibraryEntry {
+                key: candidate.key.clone(),
+                paper_id: None,
+                title: candidate.title.clone(),
+                doi: candidate.doi.clone(),
+                entry_type: Some(candidate.entry_type.clone()),
+                matched_by: None,
+                match_score: None,
+                tags: Vec::new(),
+                added_at,
+            })
+            .collect();
+
+        // One step per batch lookup, then one per title to match
+        let batches = candidates
+            .iter()
+            .filter(|candidate| candidate.external_id().is_some())
+            .count()
+            .div_ceil(BATCH_SIZE);
+        let progress = Progress::new(batches as u64);
+        self.resolve_external_ids(&mut entries, &candidates, &progress)
+            .await;
+
+        let mut title_matches = Vec::new();
+
+        if args.match_titles {
+            let to_match: Vec<(usize, String)> = entries
+                .iter()
+                .enumerate()
+                .filter(|(_, entry)| entry.paper_id.is_none())
+                .filter_map(|(i, entry)| Some((i, entry.title.clone()?)))
+                .collect();
+            progress.add_steps(to_match.len() as u64);
+
+            let matches = self
+                .rate_limiter
+                .concurrently(to_match, |(i, title)| {
+                    let key = &entries[i].key;
+                    let progress = &progress;
+                    async move {
+                        let matched = self.match_title(&title).await;
+                        progress.advance(&format!("Matched {} by title", key));
+                        (i, matched)
+                    }
+                })
+                .await;
+
+            for (i, matched) in matches {
+                let entry = &mut entries[i];
+                match matched {
+                    Ok(Some(paper)) => {
+                        entry.paper_id = paper.paper_id;
+                        entry.matched_by = Some(MatchMethod::Title);
+                        entry.match_score = paper.match_score;
+                        title_matches.push((
+                            entry.key.clone(),
+                            paper.title.unwrap_or_else(|| "Unknown title".into()),
+                        ));
+                    }
+                    Ok(None) => {}
+                    Err(err) => tracing::warn!("Failed to match title of {}: {}", entry.key, err),
+                }
+            }
+        }
+
+        // Re-imported entries keep the tags they were filed under
+        for entry in &mut entries {
+            if let Some(existing) = self.library.get(&entry.key)? {
+                entry.tags = existing.tags;
+            }
+        }
+        self.library.put(&entries)?;
+
+        let count = |method: MatchMethod| {
+            entries
+                .iter()
+                .filter(|entry| entry.matched_by == Some(method))
+                .count()
+        };
+        let unresolved: Vec<&str> = entries
+            .iter()
+            .filter(|entry| entry.paper_id.is_none())
+            .map(|entry| entry.key.as_str())
+            .collect();
+
+        let mut result = format!(
+            "Imported {} BibTeX entries into the library: {} matched by DOI, {} by arXiv ID, {} by title, {} unresolved.\n",
+            entries.len(),
+            count(MatchMethod::Doi),
+            count(MatchMethod::Arxiv),
+            count(MatchMethod::Title),
+            unresolved.len()
+        );
+
+        if !title_matches.is_empty() {
+            result.push_str("\nMatched by title (worth checking):\n");
+            for (key, title) in &title_matches {
+                result.push_str(&format!("- {}: {}\n", key, title));
+            }
+        }
+
+        if !unresolved.is_empty() {
+            result.push_str(&format!(
+                "\nUnresolved entries are kept without a paper ID: {}\n",
+                unresolved.join(", ")
+            ));
+        }
+
+        Ok(vec![ToolContent::Text {
+            text: result.trim_end().to_string(),
+        }])
+    }
+
+    fn to_tool(&self) -> Tool {
+        Tool {
+            name: "library_import_bibtex".into(),
+            description: Some(
+                "Import a BibTeX bibliography into the local library, resolving each entry to its Semantic Scholar paper by DOI, arXiv ID or title"
+                    .into(),
+            ),
+            input_schema: input_schema::<LibraryImportBibtexArguments>(),
+        }
+    }
+}
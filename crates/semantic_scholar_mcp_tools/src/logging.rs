@@ -0,0 +1,162 @@
+use std::{
+    fmt::{self, Write},
+    sync::OnceLock,
+};
+
+use anyhow::Result;
+use serde_json::json;
+use tracing::{
+    Event, Level, Subscriber,
+    field::{Field, Visit},
+    level_filters::LevelFilter,
+};
+use tracing_subscriber::{Layer, layer::Context};
+
+use crate::{
+    error::invalid_argument,
+    notifier::{current_session, notify},
+    session::Session,
+};
+
+/// The levels clients can ask for with `logging/setLevel`, from the most to
+/// the least verbose.
+const MCP_LEVELS: &[&str] = &[
+    "debug",
+    "info",
+    "notice",
+    "warning",
+    "error",
+    "critical",
+    "alert",
+    "emergency",
+];
+
+/// Events below the client's level still reach stderr from this level up.
+const STDERR_LEVEL: Level = Level::WARN;
+
+/// Level stderr shows both in and out of requests once set, for operators
+/// who asked for more detail with a log filter.
+static STDERR_OVERRIDE: OnceLock<LevelFilter> = OnceLock::new();
+
+/// Sends events raised while a request is handled to its client as
+/// `notifications/message`, so clients without a terminal see the cache hits
+/// and retries behind a result. Each client gets events from the level its
+/// session asked for.
+pub struct McpLayer;
+
+impl<S: Subscriber> Layer<S> for McpLayer {
+    fn on_event(&self, event: &Event<'_>, _context: Context<'_, S>) {
+        let Some(session) = current_session() else {
+            return;
+        };
+
+        let level = *event.metadata().level();
+        if level > session.log_level() {
+            return;
+        }
+
+        let mut message = MessageVisitor::default();
+        event.record(&mut message);
+
+        notify(
+            "notifications/message",
+            json!({
+                "level": mcp_level(level),
+                "logger": event.metadata().target(),
+                "data": message.0,
+            }),
+        );
+    }
+}
+
+/// Whether an event should be written to stderr: informational events
+/// outside of requests, such as the address the server listens on, and
+/// warnings and errors the client of the current request doesn't receive.
+pub fn show_on_stderr(level: &Level) -> bool {
+    let stderr_level = STDERR_OVERRIDE.get();
+    match current_session() {
+        Some(session) => {
+            *level <= stderr_level.copied().unwrap_or(STDERR_LEVEL.into())
+                && *level > session.log_level()
+        }
+        None => *level <= stderr_level.copied().unwrap_or(LevelFilter::INFO),
+    }
+}
+
+/// Shows events up to `level` on stderr, in place of informational events
+/// outside of requests and warnings inside them. Events the client of the
+/// current request receives are still left out.
+pub fn set_stderr_level(level: LevelFilter) {
+    let _ = STDERR_OVERRIDE.set(level);
+}
+
+/// Formats an event's message followed by its other fields.
+#[derive(Default)]
+struct MessageVisitor(String);
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if !self.0.is_empty() {
+            self.0.push(' ');
+        }
+        let _ = if field.name() == "message" {
+            write!(self.0, "{:?}", value)
+        } else {
+            write!(self.0, "{}={:?}", field.name(), value)
+        };
+    }
+}
+
+/// Reads back a level stored as an integer.
+pub(crate) fn level_filter(value: usize) -> LevelFilter {
+    match value {
+        0 => LevelFilter::OFF,
+        1 => LevelFilter::ERROR,
+        2 => LevelFilter::WARN,
+        3 => LevelFilter::INFO,
+        4 => LevelFilter::DEBUG,
+        _ => LevelFilter::TRACE,
+    }
+}
+
+/// Stores a level as an integer, the inverse of [`level_filter`].
+pub(crate) fn level_index(filter: LevelFilter) -> usize {
+    match filter.into_level() {
+        None => 0,
+        Some(Level::ERROR) => 1,
+        Some(Level::WARN) => 2,
+        Some(Level::INFO) => 3,
+        Some(Level::DEBUG) => 4,
+        Some(_) => 5,
+    }
+}
+
+/// Applies a `logging/setLevel` request: events below the level are no
+/// longer sent to the session's client.
+pub fn set_client_level(session: &Session, level: &str) -> Result<()> {
+    let filter = match level {
+        "debug" => LevelFilter::TRACE,
+        "info" | "notice" => LevelFilter::INFO,
+        "warning" => LevelFilter::WARN,
+        "error" | "critical" | "alert" | "emergency" => LevelFilter::ERROR,
+        _ => {
+            return Err(invalid_argument(format!(
+                "Unknown log level '{}'. Allowed values: {}",
+                level,
+                MCP_LEVELS.join(", ")
+            )));
+        }
+    };
+
+    session.set_log_level(filter);
+    Ok(())
+}
+
+fn mcp_level(level: Level) -> &'static str {
+    match level {
+        Level::ERROR => "error",
+        Level::WARN => "warning",
+        Level::INFO => "info",
+        _ => "debug",
+    }
+}
@@ -0,0 +1,198 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use cache::Cache;
+use context_server::{Tool, ToolContent, ToolExecutor};
+use http_client::HttpClient;
+use models::Paper;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    arguments::{input_schema, parse_arguments},
+    export::{fetch_papers, single_line, split_name, unresolved_note},
+    format::escape_markdown,
+    utils::RateLimiter,
+};
+
+/// Order of the entries in the reference list: 'input' keeps the order the
+/// IDs were given in, 'author' sorts by the first author's family name and
+/// 'year' lists the oldest papers first. Default: input
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+enum BibliographyOrder {
+    #[default]
+    Input,
+    Author,
+    Year,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct MarkdownBibliographyArguments {
+    /// List of paper identifiers to include (max: 500). Each may be a Semantic Scholar ID, DOI:doi, ARXIV:id, MAG:id, ACL:id, PMID:id, PMCID:id or URL:url, or a bare DOI, arXiv ID or paper link
+    paper_ids: Vec<String>,
+    #[serde(default)]
+    order: BibliographyOrder,
+    /// List at most this many authors per paper before 'et al.'. Default: all authors
+    #[schemars(range(min = 1))]
+    max_authors: Option<u64>,
+}
+
+pub struct MarkdownBibliographyTool {
+    http_client: Arc<dyn HttpClient>,
+    rate_limiter: Arc<RateLimiter>,
+    cache: Arc<dyn Cache>,
+}
+
+impl MarkdownBibliographyTool {
+    pub fn new(
+        http_client: Arc<dyn HttpClient>,
+        rate_limiter: Arc<RateLimiter>,
+        cache: Arc<dyn Cache>,
+    ) -> Self {
+        Self {
+            http_client,
+            rate_limiter,
+            cache,
+        }
+    }
+
+    fn format_entry(&self, paper: &Paper, max_authors: Option<usize>) -> String {
+        let title = single_line(paper.title.as_deref().unwrap_or("Unknown title"));
+        let mut entry = match &paper.url {
+            Some(url) => format!("[{}]({})", escape_markdown(&title), link_target(url)),
+            None => escape_markdown(&title),
+        };
+        entry.push('.');
+
+        let author_names = paper.author_names();
+        if !author_names.is_empty() {
+            let shown = max_authors.unwrap_or(author_names.len());
+            let mut authors = author_names
+                .iter()
+                .take(shown)
+                .copied()
+                .collect::<Vec<_>>()
+                .join(", ");
+            if author_names.len() > shown {
+                authors.push_str(" et al.");
+            }
+            entry.push_str(&format!(" {}.", escape_markdown(&authors)));
+        }
+
+        let journal_name = paper
+            .journal
+            .as_ref()
+            .and_then(|journal| journal.name.as_deref())
+            .filter(|name| !name.is_empty());
+        match (journal_name.or(paper.venue()), paper.year) {
+            (Some(venue), Some(year)) => {
+                entry.push_str(&format!(" *{}*, {}.", escape_markdown(venue), year))
+            }
+            (Some(venue), None) => entry.push_str(&format!(" *{}*.", escape_markdown(venue))),
+            (None, Some(year)) => entry.push_str(&format!(" {}.", year)),
+            (None, None) => {}
+        }
+
+        if let Some(doi) = paper
+            .external_ids
+            .as_ref()
+            .and_then(|ids| ids.doi.as_deref())
+        {
+            entry.push_str(&format!(
+                " DOI: [{}](https://doi.org/{})",
+                escape_markdown(doi),
+                link_target(doi)
+            ));
+        }
+
+        entry
+    }
+}
+
+/// Escapes the characters that would end a markdown link target early.
+fn link_target(url: &str) -> String {
+    url.replace(' ', "%20")
+        .replace('(', "%28")
+        .replace(')', "%29")
+}
+
+/// The family name of a paper's first author, used to sort by author.
+fn first_author_family_name(paper: &Paper) -> Option<String> {
+    paper
+        .author_names()
+        .first()
+        .map(|name| split_name(name).1.to_lowercase())
+}
+
+#[async_trait]
+impl ToolExecutor for MarkdownBibliographyTool {
+    async fn execute(&self, arguments: Option<Value>) -> Result<Vec<ToolContent>> {
+        tracing::debug!("Executing MarkdownBibliographyTool");
+        let args: MarkdownBibliographyArguments = parse_arguments(arguments)?;
+        let max_authors = args.max_authors.map(|max_authors| max_authors as usize);
+
+        let papers = fetch_papers(
+            &self.http_client,
+            &self.rate_limiter,
+            &self.cache,
+            &args.paper_ids,
+        )
+        .await?;
+
+        let mut found: Vec<&Paper> = papers
+            .iter()
+            .filter_map(|(_, paper)| paper.as_ref())
+            .collect();
+
+        // Stable sorts, so papers that compare equal keep the order they were given in,
+        // and papers missing the field go last
+        match args.order {
+            BibliographyOrder::Input => {}
+            BibliographyOrder::Author => found.sort_by_cached_key(|paper| {
+                let family_name = first_author_family_name(paper);
+                (family_name.is_none(), family_name, paper.year)
+            }),
+            BibliographyOrder::Year => {
+                found.sort_by_key(|paper| (paper.year.is_none(), paper.year))
+            }
+        }
+
+        let entries: Vec<String> = found
+            .iter()
+            .enumerate()
+            .map(|(i, paper)| format!("{}. {}", i + 1, self.format_entry(paper, max_authors)))
+            .collect();
+
+        if entries.is_empty() {
+            return Ok(vec![ToolContent::Text {
+                text: unresolved_note(&papers).unwrap_or_default(),
+            }]);
+        }
+
+        let mut contents = vec![ToolContent::Text {
+            text: entries.join("\n"),
+        }];
+
+        // Kept out of the list so it can be pasted as is
+        if let Some(note) = unresolved_note(&papers) {
+            contents.push(ToolContent::Text { text: note });
+        }
+
+        Ok(contents)
+    }
+
+    fn to_tool(&self) -> Tool {
+        Tool {
+            name: "markdown_bibliography".into(),
+            description: Some(
+                "Render papers as a markdown reference list with linked titles, authors, venue, year and DOI"
+                    .into(),
+            ),
+            input_schema: input_schema::<MarkdownBibliographyArguments>(),
+        }
+    }
+}
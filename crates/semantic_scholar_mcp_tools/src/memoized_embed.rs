@@ -0,0 +1,73 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use cache::Cache;
+use embed::{Embed, Embedding};
+use sha2::{Digest, Sha256};
+
+/// Remembers the embedding of every text in the cache, so a text embedded
+/// once, such as a repeated search, never reaches the provider again.
+/// Embeddings are deterministic, so they are kept whatever the TTL.
+pub struct MemoizedEmbed {
+    inner: Arc<dyn Embed>,
+    cache: Arc<dyn Cache>,
+}
+
+impl MemoizedEmbed {
+    pub fn new(inner: Arc<dyn Embed>, cache: Arc<dyn Cache>) -> Self {
+        Self { inner, cache }
+    }
+}
+
+/// Texts can exceed key size limits, so they are stored hashed along with
+/// the model, whose embeddings of the same text differ from another's.
+fn memo_key(model_id: &str, text: &str) -> String {
+    let digest = Sha256::new()
+        .chain_update(model_id)
+        .chain_update([0])
+        .chain_update(text)
+        .finalize();
+    format!("{:x}", digest)
+}
+
+#[async_trait]
+impl Embed for MemoizedEmbed {
+    async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        Ok(self.embed_with_model(text).await?.vector)
+    }
+
+    fn model_id(&self) -> String {
+        self.inner.model_id()
+    }
+
+    fn dimensions(&self) -> Option<usize> {
+        self.inner.dimensions()
+    }
+
+    /// Only embeddings of the main model are looked up, so while a fallback
+    /// answers, the main provider is still tried first.
+    async fn embed_with_model(&self, text: &str) -> Result<Embedding> {
+        let model_id = self.inner.model_id();
+        match self.cache.get_embedding(&memo_key(&model_id, text)) {
+            Ok(Some(vector)) => {
+                tracing::debug!(
+                    "Reusing the memoized embedding of {} characters",
+                    text.len()
+                );
+                return Ok(Embedding { vector, model_id });
+            }
+            Ok(None) => {}
+            Err(err) => tracing::warn!("Failed to read a memoized embedding: {}", err),
+        }
+
+        let embedding = self.inner.embed_with_model(text).await?;
+        if let Err(err) = self
+            .cache
+            .store_embedding(&memo_key(&embedding.model_id, text), &embedding.vector)
+        {
+            tracing::warn!("Failed to memoize an embedding: {}", err);
+        }
+        Ok(embedding)
+    }
+}
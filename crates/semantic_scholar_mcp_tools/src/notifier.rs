@@ -0,0 +1,73 @@
+use std::{future::Future, sync::Arc};
+
+use futures::channel::mpsc::UnboundedSender;
+use serde_json::{Value, json};
+
+use crate::session::Session;
+
+/// Sends notifications to the client whose request is being handled, along
+/// with the `progressToken` it sent in the request's `_meta`, if any, and
+/// gives the request access to its client's session.
+#[derive(Clone)]
+pub struct Notifier {
+    session: Arc<Session>,
+    outgoing: UnboundedSender<Value>,
+    progress_token: Option<Value>,
+}
+
+impl Notifier {
+    pub fn new(
+        session: Arc<Session>,
+        outgoing: UnboundedSender<Value>,
+        progress_token: Option<Value>,
+    ) -> Self {
+        Self {
+            session,
+            outgoing,
+            progress_token,
+        }
+    }
+
+    fn send(&self, method: &str, params: Value) {
+        let notification = json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params,
+        });
+
+        // The client may already have gone away
+        let _ = self.outgoing.unbounded_send(notification);
+    }
+}
+
+tokio::task_local! {
+    static NOTIFIER: Notifier;
+}
+
+/// Runs a request handler, sending the notifications raised while it runs
+/// to the client that made the request.
+pub async fn with_notifier<F: Future>(notifier: Notifier, handler: F) -> F::Output {
+    NOTIFIER.scope(notifier, handler).await
+}
+
+/// Sends a notification to the client of the current request. Returns false
+/// outside of a request, where there is no client to notify.
+pub fn notify(method: &str, params: Value) -> bool {
+    NOTIFIER
+        .try_with(|notifier| notifier.send(method, params))
+        .is_ok()
+}
+
+/// The progress token of the current request, if the client asked for
+/// progress.
+pub fn progress_token() -> Option<Value> {
+    NOTIFIER
+        .try_with(|notifier| notifier.progress_token.clone())
+        .ok()
+        .flatten()
+}
+
+/// The session of the client that made the current request.
+pub fn current_session() -> Option<Arc<Session>> {
+    NOTIFIER.try_with(|notifier| notifier.session.clone()).ok()
+}
@@ -0,0 +1,166 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use cache::Cache;
+use context_server::{Tool, ToolContent, ToolExecutor};
+use embed::Embed;
+use http_client::HttpClient;
+use models::Paper;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::{
+    arguments::{input_schema, parse_arguments},
+    content::ToolOutput,
+    error::invalid_argument,
+    format::{Formatter, OutputFormat, render},
+    query_cache::{CacheKey, CacheLookup, cached_response, store_response},
+    utils::{RateLimiter, encode_path_segment, make_request, normalize_paper_id},
+};
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct OpenAccessPdfArguments {
+    /// Paper identifier in one of the following formats: Semantic Scholar ID, DOI:doi, ARXIV:id, MAG:id, ACL:id, PMID:id, PMCID:id, URL:url. Bare DOIs and arXiv IDs, and links to doi.org, arxiv.org, semanticscholar.org or PubMed, are also accepted
+    paper_id: String,
+    /// Maximum length of the output in characters. Results past it are left out, with a note saying how many. Default: the server's limit, if any
+    #[schemars(range(min = 1))]
+    max_output_chars: Option<usize>,
+    #[serde(default)]
+    output_format: OutputFormat,
+}
+
+pub struct OpenAccessPdfTool {
+    http_client: Arc<dyn HttpClient>,
+    rate_limiter: Arc<RateLimiter>,
+    cache: Arc<dyn Cache>,
+    embed: Arc<dyn Embed>,
+}
+
+impl OpenAccessPdfTool {
+    pub fn new(
+        http_client: Arc<dyn HttpClient>,
+        rate_limiter: Arc<RateLimiter>,
+        cache: Arc<dyn Cache>,
+        embed: Arc<dyn Embed>,
+    ) -> Self {
+        Self {
+            http_client,
+            rate_limiter,
+            cache,
+            embed,
+        }
+    }
+
+    fn format_open_access_pdf(&self, f: &mut dyn Formatter, paper: &Paper) -> Result<()> {
+        let title = paper.title.as_deref().unwrap_or("Unknown title");
+        let paper_id = paper.paper_id.as_deref().unwrap_or("Unknown ID");
+
+        f.heading(&format!("Paper: {}", title));
+        f.field("Paper ID", paper_id);
+
+        let (Some(pdf), Some(pdf_url)) = (&paper.open_access_pdf, paper.open_access_pdf_url())
+        else {
+            f.field("Open Access", "No");
+            f.field("PDF URL", "none");
+
+            if let Some(doi) = paper
+                .external_ids
+                .as_ref()
+                .and_then(|ids| ids.doi.as_deref())
+            {
+                f.note(&format!(
+                    "No open-access copy is known to Semantic Scholar. The publisher version may be available at https://doi.org/{}",
+                    doi
+                ));
+            } else {
+                f.note("No open-access copy is known to Semantic Scholar.");
+            }
+
+            return Ok(());
+        };
+
+        f.field("Open Access", "Yes");
+        f.link("PDF URL", pdf_url);
+
+        if let Some(status) = &pdf.status {
+            f.field("Status", status);
+        }
+
+        if let Some(license) = &pdf.license {
+            f.field("License", license);
+        }
+
+        if let Some(disclaimer) = pdf.disclaimer.as_deref().filter(|d| !d.is_empty()) {
+            f.section("Disclaimer", disclaimer);
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for OpenAccessPdfTool {
+    async fn execute(&self, arguments: Option<Value>) -> Result<Vec<ToolContent>> {
+        tracing::debug!("Executing OpenAccessPdfTool");
+        let OpenAccessPdfArguments {
+            paper_id,
+            max_output_chars,
+            output_format,
+        } = parse_arguments(arguments)?;
+
+        if paper_id.trim().is_empty() {
+            return Err(invalid_argument("Paper ID cannot be empty"));
+        }
+
+        let paper_id = normalize_paper_id(&paper_id);
+
+        let params = json!({
+            "fields": "title,isOpenAccess,openAccessPdf,externalIds",
+        });
+
+        // The ID is part of the endpoint rather than the query parameters
+        let key = CacheKey::new("open_access_pdf", &paper_id)
+            .param("paper_id", &paper_id)
+            .params(&params);
+        let result = match cached_response(self.cache.as_ref(), self.embed.as_ref(), &key).await? {
+            CacheLookup::Hit(result) => result,
+            CacheLookup::Miss(embedding) => {
+                let result = make_request(
+                    &self.http_client,
+                    &self.rate_limiter,
+                    Some(self.cache.as_ref()),
+                    &format!("/paper/{}", encode_path_segment(&paper_id)),
+                    Some(&params),
+                    None,
+                    None,
+                )
+                .await?;
+                store_response(self.cache.as_ref(), &key, embedding, &result);
+                result
+            }
+        };
+
+        let paper: Paper = serde_json::from_value(result.clone())?;
+        let formatted_result = render(output_format, &result, None, max_output_chars, |f| {
+            self.format_open_access_pdf(f, &paper)
+        })?;
+
+        let output = ToolOutput::new(formatted_result, output_format, &result);
+
+        Ok(output.into_contents())
+    }
+
+    fn to_tool(&self) -> Tool {
+        Tool {
+            name: "open_access_pdf".into(),
+            description: Some(
+                "Resolve the open-access PDF of a paper on Semantic Scholar, with its license and access status"
+                    .into(),
+            ),
+            input_schema: input_schema::<OpenAccessPdfArguments>(),
+        }
+    }
+}
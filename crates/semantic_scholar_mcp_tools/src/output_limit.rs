@@ -0,0 +1,65 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::settings;
+
+/// The limit for a call: its own, or the configured one.
+pub fn max_output_chars(requested: Option<usize>) -> Option<usize> {
+    requested.or_else(|| settings::current(|settings| settings.max_output_chars))
+}
+
+/// How a tool's output was cut to stay under its limit, reported in the
+/// structured block next to pagination.
+#[derive(Debug, Clone, Serialize)]
+pub struct Truncation {
+    pub max_output_chars: usize,
+    /// Results left out of the data
+    pub omitted: usize,
+}
+
+/// The list of results in a response: the response itself for batches, the
+/// `data` of a page, or otherwise its longest list, such as the citations of
+/// a paper.
+pub fn result_list(response: &mut Value) -> Option<&mut Vec<Value>> {
+    match response {
+        Value::Array(values) => Some(values),
+        Value::Object(object) => {
+            let key = if object.get("data").is_some_and(Value::is_array) {
+                "data".to_string()
+            } else {
+                object
+                    .iter()
+                    .filter_map(|(key, value)| Some((key, value.as_array()?.len())))
+                    .max_by_key(|(_, len)| *len)
+                    .map(|(key, _)| key.clone())?
+            };
+            object.get_mut(&key)?.as_array_mut()
+        }
+        _ => None,
+    }
+}
+
+/// Drops the last `skip` results of a response, then as many more as it
+/// takes for the response to serialize to at most `max_chars` characters.
+/// Returns how many were dropped in all.
+pub fn trim_results(response: &mut Value, skip: usize, max_chars: usize) -> usize {
+    let mut len = serialized_len(response);
+    let Some(results) = result_list(response) else {
+        return 0;
+    };
+
+    let mut dropped = 0;
+    while dropped < skip || len > max_chars {
+        let Some(result) = results.pop() else {
+            break;
+        };
+        // Every result but the first is preceded by a comma
+        len = len.saturating_sub(serialized_len(&result) + usize::from(!results.is_empty()));
+        dropped += 1;
+    }
+    dropped
+}
+
+fn serialized_len(value: &Value) -> usize {
+    serde_json::to_string(value).map_or(0, |json| json.chars().count())
+}
@@ -0,0 +1,25 @@
+use models::Page;
+use serde::{Deserialize, Serialize};
+
+/// Where a page of results sits in the full list, reported alongside the
+/// prose so clients can fetch the next page without parsing sentences.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Pagination {
+    pub offset: u64,
+    pub returned: usize,
+    pub total: Option<u64>,
+    pub next_offset: Option<u64>,
+    pub has_more: bool,
+}
+
+impl Pagination {
+    pub fn from_page<T>(page: &Page<T>) -> Self {
+        Self {
+            offset: page.offset.unwrap_or(0),
+            returned: page.data.len(),
+            total: page.total,
+            next_offset: page.next,
+            has_more: page.next.is_some(),
+        }
+    }
+}
@@ -0,0 +1,233 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use cache::Cache;
+use context_server::{Tool, ToolContent, ToolExecutor};
+use embed::Embed;
+use http_client::HttpClient;
+use models::Paper;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::{
+    arguments::{PaperField, default_paper_fields, input_schema, parse_arguments},
+    content::ToolOutput,
+    error::invalid_argument,
+    format::{Formatter, OutputFormat, render},
+    query_cache::{CacheKey, CacheLookup, cached_response, store_response},
+    result_formatter::{
+        BatchResults, DefaultEntryRenderer, EntryRenderer, RenderOptions, ResultFormatter,
+    },
+    utils::{RateLimiter, make_request, normalize_paper_id},
+};
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct PaperBatchArguments {
+    /// List of paper identifiers (max: 500). Each may be a Semantic Scholar ID, DOI:doi, ARXIV:id, MAG:id, ACL:id, PMID:id, PMCID:id or URL:url, or a bare DOI, arXiv ID or paper link
+    paper_ids: Vec<String>,
+    /// List of fields to return for each paper. Default: title, year, authors, venue, citationCount, url
+    fields: Option<Vec<PaperField>>,
+    /// Truncate abstracts to this many characters. Default: no truncation
+    #[schemars(range(min = 1))]
+    abstract_length: Option<u64>,
+    /// Maximum length of the output in characters. Results past it are left out, with a note saying how many. Default: the server's limit, if any
+    #[schemars(range(min = 1))]
+    max_output_chars: Option<usize>,
+    #[serde(default)]
+    output_format: OutputFormat,
+}
+
+pub struct PaperBatchTool {
+    http_client: Arc<dyn HttpClient>,
+    rate_limiter: Arc<RateLimiter>,
+    cache: Arc<dyn Cache>,
+    embed: Arc<dyn Embed>,
+    renderer: Arc<dyn EntryRenderer>,
+    formatter: Arc<dyn ResultFormatter<BatchResults<Paper>>>,
+}
+
+impl PaperBatchTool {
+    pub fn new(
+        http_client: Arc<dyn HttpClient>,
+        rate_limiter: Arc<RateLimiter>,
+        cache: Arc<dyn Cache>,
+        embed: Arc<dyn Embed>,
+    ) -> Self {
+        Self {
+            http_client,
+            rate_limiter,
+            cache,
+            embed,
+            renderer: Arc::new(DefaultEntryRenderer::new()),
+            formatter: Arc::new(PaperBatchFormatter),
+        }
+    }
+
+    /// Writes the entries through `renderer` rather than the default.
+    pub fn with_renderer(mut self, renderer: Arc<dyn EntryRenderer>) -> Self {
+        self.renderer = renderer;
+        self
+    }
+
+    pub fn with_formatter(
+        mut self,
+        formatter: Arc<dyn ResultFormatter<BatchResults<Paper>>>,
+    ) -> Self {
+        self.formatter = formatter;
+        self
+    }
+}
+
+/// Lays out the output of `paper_batch`.
+pub struct PaperBatchFormatter;
+
+impl ResultFormatter<BatchResults<Paper>> for PaperBatchFormatter {
+    fn format(
+        &self,
+        f: &mut dyn Formatter,
+        renderer: &dyn EntryRenderer,
+        batch: &BatchResults<Paper>,
+        options: &RenderOptions,
+    ) -> Result<()> {
+        let found = batch.results.iter().flatten().count();
+        f.heading(&format!(
+            "Retrieved {} of {} requested papers:",
+            found,
+            batch.requested.len()
+        ));
+
+        let mut failures = Vec::new();
+
+        for (i, (requested_id, paper)) in batch.requested.iter().zip(&batch.results).enumerate() {
+            let Some(paper) = paper else {
+                failures.push(requested_id.as_str());
+                continue;
+            };
+
+            f.item(i + 1, &renderer.paper_title(paper));
+
+            if paper.paper_id.as_ref() != Some(requested_id) {
+                f.field("Requested as", requested_id);
+            }
+
+            renderer.paper(f, paper, options);
+
+            if let Some(embedding) = &paper.embedding {
+                let model = embedding.model.as_deref().unwrap_or("unknown model");
+                f.field(
+                    "Embedding",
+                    &format!("{} ({} dimensions)", model, embedding.vector.len()),
+                );
+            }
+        }
+
+        if !failures.is_empty() {
+            f.note(&format!(
+                "Could not resolve {} paper ID(s): {}",
+                failures.len(),
+                failures.join(", ")
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for PaperBatchTool {
+    async fn execute(&self, arguments: Option<Value>) -> Result<Vec<ToolContent>> {
+        tracing::debug!("Executing PaperBatchTool");
+        let args: PaperBatchArguments = parse_arguments(arguments)?;
+
+        let paper_ids: Vec<String> = args
+            .paper_ids
+            .iter()
+            .map(|s| normalize_paper_id(s))
+            .filter(|s| !s.is_empty())
+            .collect();
+
+        if paper_ids.is_empty() {
+            return Err(invalid_argument("Must provide at least one paper ID"));
+        }
+
+        if paper_ids.len() > 500 {
+            return Err(invalid_argument(
+                "Cannot request more than 500 papers at once",
+            ));
+        }
+
+        let fields = args.fields.unwrap_or_else(|| {
+            default_paper_fields(&[
+                PaperField::Title,
+                PaperField::Year,
+                PaperField::Authors,
+                PaperField::Venue,
+                PaperField::CitationCount,
+                PaperField::Url,
+            ])
+        });
+
+        let options = RenderOptions {
+            abstract_length: args.abstract_length.map(|length| length as usize),
+        };
+
+        let params = json!({ "fields": fields });
+        let request_body = json!({ "ids": paper_ids });
+
+        let query_text = format!("paper_batch:{}", paper_ids.join(","));
+
+        let cache_params = json!({ "ids": paper_ids, "fields": fields });
+
+        let key = CacheKey::new("paper_batch", &query_text).params(&cache_params);
+        let result = match cached_response(self.cache.as_ref(), self.embed.as_ref(), &key).await? {
+            CacheLookup::Hit(result) => result,
+            CacheLookup::Miss(embedding) => {
+                let result = make_request(
+                    &self.http_client,
+                    &self.rate_limiter,
+                    Some(self.cache.as_ref()),
+                    "/paper/batch",
+                    Some(&params),
+                    Some(&request_body),
+                    None,
+                )
+                .await?;
+                store_response(self.cache.as_ref(), &key, embedding, &result);
+                result
+            }
+        };
+
+        let batch = BatchResults {
+            requested: paper_ids,
+            results: serde_json::from_value(result.clone())?,
+        };
+        let formatted_result = render(
+            args.output_format,
+            &result,
+            None,
+            args.max_output_chars,
+            |f| {
+                self.formatter
+                    .format(f, self.renderer.as_ref(), &batch, &options)
+            },
+        )?;
+
+        let output = ToolOutput::new(formatted_result, args.output_format, &result);
+
+        Ok(output.into_contents())
+    }
+
+    fn to_tool(&self) -> Tool {
+        Tool {
+            name: "paper_batch".into(),
+            description: Some(
+                "Get details for multiple papers at once from Semantic Scholar in a single request"
+                    .into(),
+            ),
+            input_schema: input_schema::<PaperBatchArguments>(),
+        }
+    }
+}
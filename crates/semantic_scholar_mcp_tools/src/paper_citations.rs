@@ -1,19 +1,82 @@
-use anyhow::{Result, anyhow};
+use anyhow::Result;
 use async_trait::async_trait;
-use cache::{Cache, Query};
+use cache::Cache;
 use context_server::{Tool, ToolContent, ToolExecutor};
 use embed::Embed;
 use http_client::HttpClient;
+use models::{Citation, Page};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
 use std::sync::Arc;
 
-use crate::utils::{RateLimiter, make_request};
+use crate::{
+    arguments::{CitationField, input_schema, parse_arguments},
+    content::ToolOutput,
+    error::invalid_argument,
+    format::{Formatter, OutputFormat, render},
+    pagination::Pagination,
+    query_cache::{CacheKey, CacheLookup, cached_response, store_response},
+    result_formatter::{
+        DefaultEntryRenderer, EntryRenderer, FilteredPage, RenderOptions, ResultFormatter,
+    },
+    utils::{RateLimiter, encode_path_segment, make_request, normalize_paper_id},
+};
+
+/// Why a citing paper cites the paper, as classified by Semantic Scholar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+enum CitationIntent {
+    Methodology,
+    Background,
+    Result,
+}
+
+impl CitationIntent {
+    fn as_str(&self) -> &'static str {
+        match self {
+            CitationIntent::Methodology => "methodology",
+            CitationIntent::Background => "background",
+            CitationIntent::Result => "result",
+        }
+    }
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct PaperCitationsArguments {
+    /// Paper identifier in one of the following formats: Semantic Scholar ID, DOI:doi, ARXIV:id, MAG:id, ACL:id, PMID:id, PMCID:id, URL:url. Bare DOIs and arXiv IDs, and links to doi.org, arxiv.org, semanticscholar.org or PubMed, are also accepted
+    paper_id: String,
+    /// List of fields to return for each citing paper. Default: paperId and title
+    fields: Option<Vec<CitationField>>,
+    /// Only keep citations with at least one of these intents. Filtering is applied to each fetched page
+    #[serde(default)]
+    intents: Vec<CitationIntent>,
+    /// If true, only keep influential citations. Filtering is applied to each fetched page
+    #[serde(default)]
+    influential_only: bool,
+    /// Number of citations to skip for pagination. Default: 0
+    #[serde(default)]
+    offset: u64,
+    /// Maximum number of citations to return. Default: 100, Maximum: 1000
+    #[schemars(range(max = 1000))]
+    limit: Option<u64>,
+    /// Columns of csv and tsv output, as field names such as 'title', 'year', 'authors' or 'externalIds.DOI'. Default: every returned field
+    columns: Option<Vec<String>>,
+    /// Maximum length of the output in characters. Results past it are left out, with a note saying how many. Default: the server's limit, if any
+    #[schemars(range(min = 1))]
+    max_output_chars: Option<usize>,
+    #[serde(default)]
+    output_format: OutputFormat,
+}
 
 pub struct PaperCitationsTool {
     http_client: Arc<dyn HttpClient>,
     rate_limiter: Arc<RateLimiter>,
     cache: Arc<dyn Cache>,
     embed: Arc<dyn Embed>,
+    renderer: Arc<dyn EntryRenderer>,
+    formatter: Arc<dyn ResultFormatter<FilteredPage<Citation>>>,
 }
 
 impl PaperCitationsTool {
@@ -28,155 +91,170 @@ impl PaperCitationsTool {
             rate_limiter,
             cache,
             embed,
+            renderer: Arc::new(DefaultEntryRenderer::new()),
+            formatter: Arc::new(PaperCitationsFormatter),
         }
     }
 
-    fn format_citations(&self, response: &Value) -> Result<String> {
-        if response.get("error").is_some() {
-            let message = response["error"]["message"]
-                .as_str()
-                .unwrap_or("Unknown error");
-            return Ok(format!("Error: {}", message));
-        }
+    /// Drops citations that don't match the requested intents or influence flag
+    /// from the response, returning how many were removed.
+    fn filter_citations(
+        &self,
+        response: &mut Value,
+        intents: &[CitationIntent],
+        influential_only: bool,
+    ) -> usize {
+        let Some(data) = response.get_mut("data").and_then(Value::as_array_mut) else {
+            return 0;
+        };
 
-        let offset = response.get("offset").and_then(|o| o.as_u64()).unwrap_or(0);
-        let next_offset = response.get("next").and_then(|n| n.as_u64());
+        let before = data.len();
 
-        if let Some(data) = response.get("data").and_then(|d| d.as_array()) {
-            if data.is_empty() {
-                return Ok(String::from("No citations found for this paper."));
+        data.retain(|citation| {
+            let Ok(citation) = Citation::deserialize(citation) else {
+                return false;
+            };
+
+            if influential_only && citation.is_influential != Some(true) {
+                return false;
             }
 
-            let mut result = format!(
-                "Found {} citing papers (offset: {}):\n\n",
-                data.len(),
-                offset
-            );
-
-            for (i, paper) in data.iter().enumerate() {
-                let title = paper
-                    .get("title")
-                    .and_then(|t| t.as_str())
-                    .unwrap_or("Unknown title");
-                let paper_id = paper
-                    .get("paperId")
-                    .and_then(|p| p.as_str())
-                    .unwrap_or("Unknown ID");
-
-                result.push_str(&format!(
-                    "{}. {} (ID: {})\n",
-                    i + 1 + offset as usize,
-                    title,
-                    paper_id
-                ));
-
-                if let Some(is_influential) = paper.get("isInfluential").and_then(|i| i.as_bool()) {
-                    if is_influential {
-                        result.push_str("   [INFLUENTIAL CITATION]\n");
-                    }
-                }
+            if intents.is_empty() {
+                return true;
+            }
 
-                if let Some(authors) = paper.get("authors").and_then(|a| a.as_array()) {
-                    let author_names: Vec<&str> = authors
-                        .iter()
-                        .filter_map(|author| author.get("name").and_then(|n| n.as_str()))
-                        .collect();
+            citation
+                .intents
+                .iter()
+                .flatten()
+                .any(|intent| intents.iter().any(|i| i.as_str() == intent))
+        });
 
-                    if !author_names.is_empty() {
-                        result.push_str(&format!("   Authors: {}\n", author_names.join(", ")));
-                    }
-                }
+        before - data.len()
+    }
 
-                if let Some(year) = paper.get("year").and_then(|y| y.as_u64()) {
-                    result.push_str(&format!("   Year: {}\n", year));
-                }
+    /// Writes the entries through `renderer` rather than the default.
+    pub fn with_renderer(mut self, renderer: Arc<dyn EntryRenderer>) -> Self {
+        self.renderer = renderer;
+        self
+    }
 
-                if let Some(venue) = paper.get("venue").and_then(|v| v.as_str()) {
-                    if !venue.is_empty() {
-                        result.push_str(&format!("   Venue: {}\n", venue));
-                    }
-                }
+    pub fn with_formatter(
+        mut self,
+        formatter: Arc<dyn ResultFormatter<FilteredPage<Citation>>>,
+    ) -> Self {
+        self.formatter = formatter;
+        self
+    }
+}
+
+/// Lays out the output of `paper_citations`.
+pub struct PaperCitationsFormatter;
+
+impl ResultFormatter<FilteredPage<Citation>> for PaperCitationsFormatter {
+    fn format(
+        &self,
+        f: &mut dyn Formatter,
+        renderer: &dyn EntryRenderer,
+        citations: &FilteredPage<Citation>,
+        options: &RenderOptions,
+    ) -> Result<()> {
+        let page = &citations.page;
+        if page.data.is_empty() {
+            f.note("No citations found for this paper.");
+        } else {
+            let offset = page.offset.unwrap_or(0);
+
+            f.heading(&format!(
+                "Found {} citing papers (offset: {}):",
+                page.data.len(),
+                offset
+            ));
+
+            for (i, citation) in page.data.iter().enumerate() {
+                let paper = &citation.citing_paper;
 
-                if let Some(citation_count) = paper.get("citationCount").and_then(|c| c.as_u64()) {
-                    result.push_str(&format!("   Citations: {}\n", citation_count));
+                f.item(i + 1 + offset as usize, &renderer.paper_title(paper));
+
+                if citation.is_influential == Some(true) {
+                    f.tag("Influential citation");
                 }
 
-                if let Some(contexts) = paper.get("contexts").and_then(|c| c.as_array()) {
-                    if !contexts.is_empty() {
-                        result.push_str("   Citation contexts:\n");
+                renderer.paper(f, paper, options);
 
-                        for (idx, context) in contexts.iter().take(3).enumerate() {
-                            if let Some(text) = context.as_str() {
-                                result.push_str(&format!("     {}. \"{}\"\n", idx + 1, text));
-                            }
-                        }
+                if let Some(contexts) = &citation.contexts {
+                    if !contexts.is_empty() {
+                        let mut shown: Vec<String> = contexts
+                            .iter()
+                            .take(3)
+                            .map(|context| format!("\"{}\"", context))
+                            .collect();
 
                         if contexts.len() > 3 {
-                            result.push_str(&format!(
-                                "     ... and {} more contexts\n",
-                                contexts.len() - 3
-                            ));
+                            shown.push(format!("... and {} more contexts", contexts.len() - 3));
                         }
-                    }
-                }
 
-                if let Some(intents) = paper.get("intents").and_then(|i| i.as_array()) {
-                    let intent_types: Vec<&str> = intents
-                        .iter()
-                        .filter_map(|intent| intent.as_str())
-                        .collect();
-
-                    if !intent_types.is_empty() {
-                        result.push_str(&format!(
-                            "   Citation intents: {}\n",
-                            intent_types.join(", ")
-                        ));
+                        f.list("Citation contexts", &shown);
                     }
                 }
 
-                if let Some(url) = paper.get("url").and_then(|u| u.as_str()) {
-                    result.push_str(&format!("   URL: {}\n", url));
-                }
-
-                if i < data.len() - 1 {
-                    result.push_str("\n");
+                if let Some(intents) = &citation.intents {
+                    if !intents.is_empty() {
+                        f.field("Citation intents", &intents.join(", "));
+                    }
                 }
             }
 
-            if let Some(next) = next_offset {
-                result.push_str(&format!("\nFor more citations, use offset={}", next));
+            if let Some(next) = page.next {
+                f.note(&format!("For more citations, use offset={}", next));
             }
+        }
 
-            Ok(result)
-        } else {
-            Ok(String::from(
-                "No citations found or unexpected API response format.",
-            ))
+        if citations.hidden > 0 {
+            f.note(&format!(
+                "{} citation(s) on this page were hidden by the intent/influence filters",
+                citations.hidden
+            ));
         }
+
+        Ok(())
     }
 }
 
 #[async_trait]
 impl ToolExecutor for PaperCitationsTool {
     async fn execute(&self, arguments: Option<Value>) -> Result<Vec<ToolContent>> {
-        log::debug!("Executing PaperCitationsTool");
-        let args = arguments.ok_or_else(|| anyhow!("Missing arguments"))?;
-
-        let paper_id = args
-            .get("paper_id")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow!("Missing or invalid paper_id parameter"))?;
+        tracing::debug!("Executing PaperCitationsTool");
+        let PaperCitationsArguments {
+            paper_id,
+            mut fields,
+            intents,
+            influential_only,
+            offset,
+            limit,
+            columns,
+            max_output_chars,
+            output_format,
+        } = parse_arguments(arguments)?;
 
         if paper_id.trim().is_empty() {
-            return Err(anyhow!("Paper ID cannot be empty"));
+            return Err(invalid_argument("Paper ID cannot be empty"));
         }
 
-        let fields = args.get("fields").cloned();
-        let offset = args.get("offset").and_then(|v| v.as_u64()).unwrap_or(0);
-        let limit = args.get("limit").and_then(|v| v.as_u64()).unwrap_or(100);
+        let paper_id = normalize_paper_id(&paper_id);
+
+        let limit = limit.unwrap_or(100);
 
-        if limit > 1000 {
-            return Err(anyhow!("Limit cannot exceed 1000"));
+        // Filtering happens client-side, so make sure the fields it relies on are requested
+        if !intents.is_empty() || influential_only {
+            let requested =
+                fields.get_or_insert_with(|| vec![CitationField::PaperId, CitationField::Title]);
+
+            for required in [CitationField::Intents, CitationField::IsInfluential] {
+                if !requested.contains(&required) {
+                    requested.push(required);
+                }
+            }
         }
 
         let mut params_map = serde_json::Map::new();
@@ -184,91 +262,69 @@ impl ToolExecutor for PaperCitationsTool {
         params_map.insert("limit".to_string(), json!(limit));
 
         if let Some(f) = fields {
-            params_map.insert("fields".to_string(), f);
+            params_map.insert("fields".to_string(), json!(f));
         }
 
         let params = Value::Object(params_map);
 
-        // Generate an embedding for the query
-        let embedding = self.embed.embed(&paper_id).await?;
-
-        // Check if we have a cached result for a similar query
-        let similar_queries = self.cache.search_similarity(&embedding)?;
-
-        // Check for any cached queries with high similarity and matching action/params
-        for (cached_query, similarity) in similar_queries.iter() {
-            if similarity > &0.95 && cached_query.action == "paper_citations" {
-                // Check if parameters match
-                if cached_query.params == Some(params.clone()) {
-                    log::debug!("Found cached result with similarity {}", similarity);
-                    return Ok(vec![ToolContent::Text {
-                        text: serde_json::from_value(cached_query.results.clone())?,
-                    }]);
+        // Filtering happens on read, so one cached page serves every filter
+        // The ID is part of the endpoint rather than the query parameters
+        let key = CacheKey::new("paper_citations", &paper_id)
+            .param("paper_id", &paper_id)
+            .params(&params);
+        let mut result =
+            match cached_response(self.cache.as_ref(), self.embed.as_ref(), &key).await? {
+                CacheLookup::Hit(result) => result,
+                CacheLookup::Miss(embedding) => {
+                    let result = make_request(
+                        &self.http_client,
+                        &self.rate_limiter,
+                        Some(self.cache.as_ref()),
+                        &format!("/paper/{}/citations", encode_path_segment(&paper_id)),
+                        Some(&params),
+                        None,
+                        None,
+                    )
+                    .await?;
+                    store_response(self.cache.as_ref(), &key, embedding, &result);
+                    result
                 }
-            }
-        }
+            };
 
-        let result = make_request(
-            &self.http_client,
-            &self.rate_limiter,
-            &format!("/paper/{}/citations", paper_id),
-            Some(&params),
-            None,
-        )
-        .await?;
-
-        let formatted_result = self.format_citations(&result)?;
-
-        let query = Query {
-            action: "paper_citations".into(),
-            text: paper_id.into(),
-            embedding,
-            params: Some(params),
-            results: json!(formatted_result),
-        };
-
-        if let Err(err) = self.cache.store(query) {
-            log::warn!("Failed to store query in cache: {}", err);
-        }
+        let removed = self.filter_citations(&mut result, &intents, influential_only);
 
-        Ok(vec![ToolContent::Text {
-            text: formatted_result,
-        }])
+        let page: Page<Citation> = serde_json::from_value(result.clone())?;
+        let pagination = Pagination::from_page(&page);
+        let citations = FilteredPage {
+            page,
+            hidden: removed,
+        };
+        let formatted_result = render(
+            output_format,
+            &result,
+            columns.as_deref(),
+            max_output_chars,
+            |f| {
+                self.formatter.format(
+                    f,
+                    self.renderer.as_ref(),
+                    &citations,
+                    &RenderOptions::default(),
+                )
+            },
+        )?;
+
+        let output =
+            ToolOutput::new(formatted_result, output_format, &result).with_pagination(pagination);
+
+        Ok(output.into_contents())
     }
 
     fn to_tool(&self) -> Tool {
         Tool {
             name: "paper_citations".into(),
             description: Some("Get papers that cite a specific paper in Semantic Scholar".into()),
-            input_schema: json!({
-                "type": "object",
-                "properties": {
-                    "paper_id": {
-                        "type": "string",
-                        "description": "Paper identifier in one of the following formats: Semantic Scholar ID, DOI:doi, ARXIV:id, MAG:id, ACL:id, PMID:id, PMCID:id, URL:url"
-                    },
-                    "fields": {
-                        "type": "array",
-                        "description": "List of fields to return for each citing paper. Default: paperId and title",
-                        "items": {
-                            "type": "string",
-                            "enum": [
-                                "title", "abstract", "year", "venue", "authors", "url", "paperId",
-                                "citationCount", "influentialCitationCount", "contexts", "intents", "isInfluential"
-                            ]
-                        }
-                    },
-                    "offset": {
-                        "type": "integer",
-                        "description": "Number of citations to skip for pagination. Default: 0"
-                    },
-                    "limit": {
-                        "type": "integer",
-                        "description": "Maximum number of citations to return. Default: 100, Maximum: 1000"
-                    }
-                },
-                "required": ["paper_id"]
-            }),
+            input_schema: input_schema::<PaperCitationsArguments>(),
         }
     }
 }
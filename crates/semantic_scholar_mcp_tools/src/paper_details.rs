@@ -1,14 +1,41 @@
 use std::sync::Arc;
 
-use anyhow::{Result, anyhow};
+use anyhow::Result;
 use async_trait::async_trait;
-use cache::{Cache, Query};
+use cache::Cache;
 use context_server::{Tool, ToolContent, ToolExecutor};
 use embed::Embed;
 use http_client::HttpClient;
+use models::Paper;
+use schemars::JsonSchema;
+use serde::Deserialize;
 use serde_json::{Value, json};
 
-use crate::utils::{RateLimiter, make_request};
+use crate::{
+    arguments::{PaperField, input_schema, parse_arguments},
+    content::ToolOutput,
+    error::invalid_argument,
+    format::{Formatter, OutputFormat, render},
+    query_cache::{CacheKey, CacheLookup, cached_response, store_response},
+    utils::{RateLimiter, encode_path_segment, make_request, normalize_paper_id, truncate_text},
+};
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct PaperDetailsArguments {
+    /// Paper identifier in one of the following formats: Semantic Scholar ID, DOI:doi, ARXIV:id, MAG:id, ACL:id, PMID:id, PMCID:id, URL:url. Bare DOIs and arXiv IDs, and links to doi.org, arxiv.org, semanticscholar.org or PubMed, are also accepted
+    paper_id: String,
+    /// List of fields to return. Default: title and abstract
+    fields: Option<Vec<PaperField>>,
+    /// Truncate abstracts to this many characters. Default: no truncation
+    #[schemars(range(min = 1))]
+    abstract_length: Option<u64>,
+    /// Maximum length of the output in characters. Results past it are left out, with a note saying how many. Default: the server's limit, if any
+    #[schemars(range(min = 1))]
+    max_output_chars: Option<usize>,
+    #[serde(default)]
+    output_format: OutputFormat,
+}
 
 pub struct PaperDetailsTool {
     http_client: Arc<dyn HttpClient>,
@@ -32,213 +59,188 @@ impl PaperDetailsTool {
         }
     }
 
-    fn format_paper_details(&self, response: &Value) -> Result<String> {
-        if response.get("error").is_some() {
-            let message = response["error"]["message"]
-                .as_str()
-                .unwrap_or("Unknown error");
-            return Ok(format!("Error: {}", message));
-        }
+    fn format_paper_details(
+        &self,
+        f: &mut dyn Formatter,
+        paper: &Paper,
+        abstract_length: Option<usize>,
+    ) -> Result<()> {
+        let title = paper.title.as_deref().unwrap_or("Unknown title");
+        let paper_id = paper.paper_id.as_deref().unwrap_or("Unknown ID");
 
-        let title = response
-            .get("title")
-            .and_then(|t| t.as_str())
-            .unwrap_or("Unknown title");
-        let paper_id = response
-            .get("paperId")
-            .and_then(|p| p.as_str())
-            .unwrap_or("Unknown ID");
-
-        let mut result = format!("Paper Details: {}\n", title);
-        result.push_str(&format!("Paper ID: {}\n\n", paper_id));
-
-        if let Some(authors) = response.get("authors").and_then(|a| a.as_array()) {
-            result.push_str("Authors:\n");
-            for author in authors {
-                let name = author
-                    .get("name")
-                    .and_then(|n| n.as_str())
-                    .unwrap_or("Unknown");
-                let author_id = author
-                    .get("authorId")
-                    .and_then(|id| id.as_str())
-                    .unwrap_or("Unknown");
-                result.push_str(&format!("- {} (ID: {})\n", name, author_id));
-            }
-            result.push_str("\n");
-        }
+        f.heading(&format!("Paper Details: {}", title));
+        f.field("Paper ID", paper_id);
 
-        if let Some(year) = response.get("year").and_then(|y| y.as_u64()) {
-            result.push_str(&format!("Year: {}\n", year));
+        if let Some(authors) = &paper.authors {
+            let authors: Vec<String> = authors
+                .iter()
+                .map(|author| {
+                    format!(
+                        "{} (ID: {})",
+                        author.name.as_deref().unwrap_or("Unknown"),
+                        author.author_id.as_deref().unwrap_or("Unknown")
+                    )
+                })
+                .collect();
+            f.list("Authors", &authors);
         }
 
-        if let Some(venue) = response.get("venue").and_then(|v| v.as_str()) {
-            if !venue.is_empty() {
-                result.push_str(&format!("Venue: {}\n", venue));
-            }
+        if let Some(year) = paper.year {
+            f.field("Year", &year.to_string());
         }
 
-        if let Some(publication_date) = response.get("publicationDate").and_then(|d| d.as_str()) {
-            result.push_str(&format!("Publication Date: {}\n", publication_date));
+        if let Some(venue) = paper.venue() {
+            f.field("Venue", venue);
         }
 
-        if let Some(citation_count) = response.get("citationCount").and_then(|c| c.as_u64()) {
-            result.push_str(&format!("Citation Count: {}\n", citation_count));
+        if let Some(publication_date) = &paper.publication_date {
+            f.field("Publication Date", publication_date);
         }
 
-        if let Some(influential_citation_count) = response
-            .get("influentialCitationCount")
-            .and_then(|c| c.as_u64())
-        {
-            result.push_str(&format!(
-                "Influential Citation Count: {}\n",
-                influential_citation_count
-            ));
+        if let Some(citation_count) = paper.citation_count {
+            f.field("Citation Count", &citation_count.to_string());
         }
 
-        if let Some(fields_of_study) = response.get("fieldsOfStudy").and_then(|f| f.as_array()) {
-            let fields: Vec<&str> = fields_of_study
-                .iter()
-                .filter_map(|field| field.as_str())
-                .collect();
+        if let Some(influential_citation_count) = paper.influential_citation_count {
+            f.field(
+                "Influential Citation Count",
+                &influential_citation_count.to_string(),
+            );
+        }
 
-            if !fields.is_empty() {
-                result.push_str(&format!("Fields of Study: {}\n", fields.join(", ")));
+        if let Some(fields_of_study) = &paper.fields_of_study {
+            if !fields_of_study.is_empty() {
+                f.field("Fields of Study", &fields_of_study.join(", "));
             }
         }
 
-        if let Some(is_open_access) = response.get("isOpenAccess").and_then(|o| o.as_bool()) {
-            result.push_str(&format!(
-                "Open Access: {}\n",
-                if is_open_access { "Yes" } else { "No" }
-            ));
+        if let Some(is_open_access) = paper.is_open_access {
+            f.field("Open Access", if is_open_access { "Yes" } else { "No" });
 
             if is_open_access {
-                if let Some(pdf) = response.get("openAccessPdf") {
-                    if let Some(url) = pdf.get("url").and_then(|u| u.as_str()) {
-                        result.push_str(&format!("Open Access PDF: {}\n", url));
-                    }
+                if let Some(url) = paper.open_access_pdf_url() {
+                    f.link("Open Access PDF", url);
                 }
             }
         }
 
-        if let Some(abstract_text) = response.get("abstract").and_then(|a| a.as_str()) {
-            if !abstract_text.is_empty() {
-                result.push_str(&format!("\nAbstract:\n{}\n", abstract_text));
-            }
-        }
-
-        if let Some(tldr) = response.get("tldr") {
-            if let Some(text) = tldr.get("text").and_then(|t| t.as_str()) {
-                result.push_str(&format!("\nTL;DR:\n{}\n", text));
-            }
+        if let Some(url) = &paper.url {
+            f.link("Semantic Scholar URL", url);
         }
 
-        if let Some(url) = response.get("url").and_then(|u| u.as_str()) {
-            result.push_str(&format!("\nSemantic Scholar URL: {}\n", url));
-        }
+        if let Some(external_ids) = &paper.external_ids {
+            let mut ids = Vec::new();
 
-        if let Some(external_ids) = response.get("externalIds") {
-            result.push_str("\nExternal IDs:\n");
+            if let Some(doi) = &external_ids.doi {
+                ids.push(format!("DOI: {}", doi));
+            }
 
-            if let Some(doi) = external_ids.get("DOI").and_then(|d| d.as_str()) {
-                result.push_str(&format!("DOI: {}\n", doi));
+            if let Some(arxiv) = &external_ids.arxiv {
+                ids.push(format!("ArXiv: {}", arxiv));
             }
 
-            if let Some(arxiv) = external_ids.get("ArXiv").and_then(|a| a.as_str()) {
-                result.push_str(&format!("ArXiv: {}\n", arxiv));
+            if let Some(pmid) = &external_ids.pubmed {
+                ids.push(format!("PubMed: {}", pmid));
             }
 
-            if let Some(pmid) = external_ids.get("PubMed").and_then(|p| p.as_str()) {
-                result.push_str(&format!("PubMed: {}\n", pmid));
+            if let Some(acl) = &external_ids.acl {
+                ids.push(format!("ACL: {}", acl));
             }
 
-            if let Some(acl) = external_ids.get("ACL").and_then(|a| a.as_str()) {
-                result.push_str(&format!("ACL: {}\n", acl));
+            if !ids.is_empty() {
+                f.list("External IDs", &ids);
             }
         }
 
-        if let Some(citations) = response.get("citations").and_then(|c| c.as_array()) {
-            result.push_str(&format!("\nCitations: {} papers\n", citations.len()));
-            result.push_str("(Use the paper_citations tool with this paper ID to see details)\n");
+        if let Some(abstract_text) = paper.abstract_text() {
+            f.section("Abstract", &truncate_text(abstract_text, abstract_length));
+        }
+
+        if let Some(text) = paper.tldr.as_ref().and_then(|tldr| tldr.text.as_deref()) {
+            f.section("TL;DR", text);
+        }
+
+        if let Some(embedding) = &paper.embedding {
+            let model = embedding.model.as_deref().unwrap_or("unknown model");
+            f.note(&format!(
+                "Embedding: {} ({} dimensions). Use the paper_similarity_rank tool to compare papers by embedding",
+                model,
+                embedding.vector.len()
+            ));
+        }
+
+        if let Some(citations) = &paper.citations {
+            f.note(&format!(
+                "Citations: {} papers. Use the paper_citations tool with this paper ID to see details",
+                citations.len()
+            ));
         }
 
-        if let Some(references) = response.get("references").and_then(|r| r.as_array()) {
-            result.push_str(&format!("\nReferences: {} papers\n", references.len()));
-            result.push_str("(Use the paper_references tool with this paper ID to see details)\n");
+        if let Some(references) = &paper.references {
+            f.note(&format!(
+                "References: {} papers. Use the paper_references tool with this paper ID to see details",
+                references.len()
+            ));
         }
 
-        Ok(result)
+        Ok(())
     }
 }
 
 #[async_trait]
 impl ToolExecutor for PaperDetailsTool {
     async fn execute(&self, arguments: Option<Value>) -> Result<Vec<ToolContent>> {
-        log::debug!("Executing PaperDetailsTool");
-        let args = arguments.ok_or_else(|| anyhow!("Missing arguments"))?;
-
-        let paper_id = args
-            .get("paper_id")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow!("Missing or invalid paper_id parameter"))?;
+        tracing::debug!("Executing PaperDetailsTool");
+        let PaperDetailsArguments {
+            paper_id,
+            fields,
+            abstract_length,
+            max_output_chars,
+            output_format,
+        } = parse_arguments(arguments)?;
+        let abstract_length = abstract_length.map(|length| length as usize);
 
         if paper_id.trim().is_empty() {
-            return Err(anyhow!("Paper ID cannot be empty"));
+            return Err(invalid_argument("Paper ID cannot be empty"));
         }
 
-        let fields = args.get("fields").cloned();
+        let paper_id = normalize_paper_id(&paper_id);
 
         let params = match fields {
-            Some(fields_value) => json!({"fields": fields_value}),
+            Some(fields) => json!({"fields": fields}),
             None => json!({}),
         };
 
-        // Generate an embedding for the query
-        let embedding = self.embed.embed(&paper_id).await?;
-
-        // Check if we have a cached result for a similar query
-        let similar_queries = self.cache.search_similarity(&embedding)?;
-
-        // Check for any cached queries with high similarity and matching action/params
-        for (cached_query, similarity) in similar_queries.iter() {
-            if similarity > &0.95 && cached_query.action == "paper_details" {
-                // Check if parameters match
-                if cached_query.params == Some(params.clone()) {
-                    log::debug!("Found cached result with similarity {}", similarity);
-                    return Ok(vec![ToolContent::Text {
-                        text: serde_json::from_value(cached_query.results.clone())?,
-                    }]);
-                }
+        // The ID is part of the endpoint rather than the query parameters
+        let key = CacheKey::new("paper_details", &paper_id)
+            .param("paper_id", &paper_id)
+            .params(&params);
+        let result = match cached_response(self.cache.as_ref(), self.embed.as_ref(), &key).await? {
+            CacheLookup::Hit(result) => result,
+            CacheLookup::Miss(embedding) => {
+                let result = make_request(
+                    &self.http_client,
+                    &self.rate_limiter,
+                    Some(self.cache.as_ref()),
+                    &format!("/paper/{}", encode_path_segment(&paper_id)),
+                    Some(&params),
+                    None,
+                    None,
+                )
+                .await?;
+                store_response(self.cache.as_ref(), &key, embedding, &result);
+                result
             }
-        }
-
-        let result = make_request(
-            &self.http_client,
-            &self.rate_limiter,
-            &format!("/paper/{}", paper_id),
-            Some(&params),
-            None,
-        )
-        .await?;
-
-        let formatted_result = self.format_paper_details(&result)?;
-
-        let query = Query {
-            action: "paper_details".into(),
-            text: paper_id.into(),
-            embedding,
-            params: Some(params),
-            results: json!(formatted_result),
         };
 
-        if let Err(err) = self.cache.store(query) {
-            log::warn!("Failed to store query in cache: {}", err);
-        }
+        let paper: Paper = serde_json::from_value(result.clone())?;
+        let formatted_result = render(output_format, &result, None, max_output_chars, |f| {
+            self.format_paper_details(f, &paper, abstract_length)
+        })?;
+
+        let output = ToolOutput::new(formatted_result, output_format, &result);
 
-        Ok(vec![ToolContent::Text {
-            text: formatted_result,
-        }])
+        Ok(output.into_contents())
     }
 
     fn to_tool(&self) -> Tool {
@@ -247,30 +249,7 @@ impl ToolExecutor for PaperDetailsTool {
             description: Some(
                 "Get detailed information about a specific paper from Semantic Scholar".into(),
             ),
-            input_schema: json!({
-                "type": "object",
-                "properties": {
-                    "paper_id": {
-                        "type": "string",
-                        "description": "Paper identifier in one of the following formats: Semantic Scholar ID, DOI:doi, ARXIV:id, MAG:id, ACL:id, PMID:id, PMCID:id, URL:url"
-                    },
-                    "fields": {
-                        "type": "array",
-                        "description": "List of fields to return. Default: title and abstract",
-                        "items": {
-                            "type": "string",
-                            "enum": [
-                                "title", "abstract", "year", "citationCount", "authors", "url",
-                                "citations", "references", "venue", "influentialCitationCount",
-                                "corpusId", "externalIds", "fieldsOfStudy", "isOpenAccess",
-                                "openAccessPdf", "paperId", "publicationDate", "publicationTypes",
-                                "publicationVenue", "s2FieldsOfStudy", "tldr"
-                            ]
-                        }
-                    }
-                },
-                "required": ["paper_id"]
-            }),
+            input_schema: input_schema::<PaperDetailsArguments>(),
         }
     }
 }
@@ -0,0 +1,336 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use cache::Cache;
+use context_server::{Tool, ToolContent, ToolExecutor};
+use embed::Embed;
+use http_client::HttpClient;
+use models::Reference;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+use crate::{
+    arguments::{CitationField, input_schema, parse_arguments},
+    content::ToolOutput,
+    error::invalid_argument,
+    format::{Formatter, OutputFormat, render},
+    query_cache::{CacheKey, CacheLookup, cached_response, store_response},
+    result_formatter::{
+        DefaultEntryRenderer, EntryRenderer, FilteredPage, RenderOptions, ResultFormatter,
+    },
+    utils::{RateLimiter, encode_path_segment, make_request, normalize_paper_id},
+};
+
+/// Field the references on a page are sorted by, in descending order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+enum ReferenceSort {
+    Year,
+    CitationCount,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct PaperReferencesArguments {
+    /// Paper identifier in one of the following formats: Semantic Scholar ID, DOI:doi, ARXIV:id, MAG:id, ACL:id, PMID:id, PMCID:id, URL:url. Bare DOIs and arXiv IDs, and links to doi.org, arxiv.org, semanticscholar.org or PubMed, are also accepted
+    paper_id: String,
+    /// List of fields to return for each referenced paper. Default: paperId and title
+    fields: Option<Vec<CitationField>>,
+    /// Sort each fetched page of references in descending order by this field
+    sort: Option<ReferenceSort>,
+    /// Only keep references published in or after this year. Filtering is applied to each fetched page
+    min_year: Option<u64>,
+    /// Only keep references published in or before this year. Filtering is applied to each fetched page
+    max_year: Option<u64>,
+    /// Number of references to skip for pagination. Default: 0
+    #[serde(default)]
+    offset: u64,
+    /// Maximum number of references to return. Default: 100, Maximum: 1000
+    #[schemars(range(max = 1000))]
+    limit: Option<u64>,
+    /// Columns of csv and tsv output, as field names such as 'title', 'year', 'authors' or 'externalIds.DOI'. Default: every returned field
+    columns: Option<Vec<String>>,
+    /// Maximum length of the output in characters. Results past it are left out, with a note saying how many. Default: the server's limit, if any
+    #[schemars(range(min = 1))]
+    max_output_chars: Option<usize>,
+    #[serde(default)]
+    output_format: OutputFormat,
+}
+
+pub struct PaperReferencesTool {
+    http_client: Arc<dyn HttpClient>,
+    rate_limiter: Arc<RateLimiter>,
+    cache: Arc<dyn Cache>,
+    embed: Arc<dyn Embed>,
+    renderer: Arc<dyn EntryRenderer>,
+    formatter: Arc<dyn ResultFormatter<FilteredPage<Reference>>>,
+}
+
+impl PaperReferencesTool {
+    pub fn new(
+        http_client: Arc<dyn HttpClient>,
+        rate_limiter: Arc<RateLimiter>,
+        cache: Arc<dyn Cache>,
+        embed: Arc<dyn Embed>,
+    ) -> Self {
+        Self {
+            http_client,
+            rate_limiter,
+            cache,
+            embed,
+            renderer: Arc::new(DefaultEntryRenderer::new()),
+            formatter: Arc::new(PaperReferencesFormatter),
+        }
+    }
+
+    /// Applies the year range and sort order to the references in a response,
+    /// returning how many references were dropped by the year range.
+    fn filter_and_sort_references(
+        &self,
+        response: &mut Value,
+        min_year: Option<u64>,
+        max_year: Option<u64>,
+        sort: Option<ReferenceSort>,
+    ) -> usize {
+        let Some(data) = response.get_mut("data").and_then(Value::as_array_mut) else {
+            return 0;
+        };
+
+        let before = data.len();
+
+        // Keep each entry next to its typed form so the response can be rewritten in the new order
+        let mut references: Vec<(Reference, Value)> = data
+            .drain(..)
+            .filter_map(|entry| {
+                Reference::deserialize(&entry)
+                    .ok()
+                    .map(|reference| (reference, entry))
+            })
+            .collect();
+
+        if min_year.is_some() || max_year.is_some() {
+            references.retain(|(reference, _)| {
+                let Some(year) = reference.cited_paper.year else {
+                    return false;
+                };
+                min_year.is_none_or(|min_year| year >= min_year)
+                    && max_year.is_none_or(|max_year| year <= max_year)
+            });
+        }
+
+        if let Some(sort) = sort {
+            let key = |reference: &Reference| match sort {
+                ReferenceSort::Year => reference.cited_paper.year,
+                ReferenceSort::CitationCount => reference.cited_paper.citation_count,
+            };
+            // Descending, with papers missing the field last
+            references.sort_by(|(a, _), (b, _)| key(b).cmp(&key(a)));
+        }
+
+        data.extend(references.into_iter().map(|(_, entry)| entry));
+
+        before - data.len()
+    }
+
+    /// Writes the entries through `renderer` rather than the default.
+    pub fn with_renderer(mut self, renderer: Arc<dyn EntryRenderer>) -> Self {
+        self.renderer = renderer;
+        self
+    }
+
+    pub fn with_formatter(
+        mut self,
+        formatter: Arc<dyn ResultFormatter<FilteredPage<Reference>>>,
+    ) -> Self {
+        self.formatter = formatter;
+        self
+    }
+}
+
+/// Lays out the output of `paper_references`.
+pub struct PaperReferencesFormatter;
+
+impl ResultFormatter<FilteredPage<Reference>> for PaperReferencesFormatter {
+    fn format(
+        &self,
+        f: &mut dyn Formatter,
+        renderer: &dyn EntryRenderer,
+        references: &FilteredPage<Reference>,
+        options: &RenderOptions,
+    ) -> Result<()> {
+        let page = &references.page;
+        if page.data.is_empty() {
+            f.note("No references found for this paper.");
+        } else {
+            let offset = page.offset.unwrap_or(0);
+
+            f.heading(&format!(
+                "Found {} referenced papers (offset: {}):",
+                page.data.len(),
+                offset
+            ));
+
+            for (i, reference) in page.data.iter().enumerate() {
+                let paper = &reference.cited_paper;
+
+                f.item(i + 1 + offset as usize, &renderer.paper_title(paper));
+
+                if reference.is_influential == Some(true) {
+                    f.tag("Influential reference");
+                }
+
+                renderer.paper(f, paper, options);
+
+                if let Some(contexts) = &reference.contexts {
+                    if !contexts.is_empty() {
+                        let mut shown: Vec<String> = contexts
+                            .iter()
+                            .take(3)
+                            .map(|context| format!("\"{}\"", context))
+                            .collect();
+
+                        if contexts.len() > 3 {
+                            shown.push(format!("... and {} more contexts", contexts.len() - 3));
+                        }
+
+                        f.list("Citation contexts", &shown);
+                    }
+                }
+
+                if let Some(intents) = &reference.intents {
+                    if !intents.is_empty() {
+                        f.field("Citation intents", &intents.join(", "));
+                    }
+                }
+            }
+
+            if let Some(next) = page.next {
+                f.note(&format!("For more references, use offset={}", next));
+            }
+        }
+
+        if references.hidden > 0 {
+            f.note(&format!(
+                "{} reference(s) on this page were outside the requested year range",
+                references.hidden
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for PaperReferencesTool {
+    async fn execute(&self, arguments: Option<Value>) -> Result<Vec<ToolContent>> {
+        tracing::debug!("Executing PaperReferencesTool");
+        let PaperReferencesArguments {
+            paper_id,
+            mut fields,
+            sort,
+            min_year,
+            max_year,
+            offset,
+            limit,
+            columns,
+            max_output_chars,
+            output_format,
+        } = parse_arguments(arguments)?;
+
+        if paper_id.trim().is_empty() {
+            return Err(invalid_argument("Paper ID cannot be empty"));
+        }
+
+        let paper_id = normalize_paper_id(&paper_id);
+
+        let limit = limit.unwrap_or(100);
+
+        if let (Some(min_year), Some(max_year)) = (min_year, max_year) {
+            if min_year > max_year {
+                return Err(invalid_argument("min_year cannot be greater than max_year"));
+            }
+        }
+
+        let client_side = sort.is_some() || min_year.is_some() || max_year.is_some();
+
+        // Sorting and filtering happen client-side, so make sure the fields they rely on are requested
+        if client_side {
+            let requested =
+                fields.get_or_insert_with(|| vec![CitationField::PaperId, CitationField::Title]);
+
+            for required in [CitationField::Year, CitationField::CitationCount] {
+                if !requested.contains(&required) {
+                    requested.push(required);
+                }
+            }
+        }
+
+        let mut params_map = serde_json::Map::new();
+        params_map.insert("offset".to_string(), json!(offset));
+        params_map.insert("limit".to_string(), json!(limit));
+
+        if let Some(f) = fields {
+            params_map.insert("fields".to_string(), json!(f));
+        }
+
+        let params = Value::Object(params_map);
+
+        // Filtering and sorting happen on read, so one cached page serves them all
+        // The ID is part of the endpoint rather than the query parameters
+        let key = CacheKey::new("paper_references", &paper_id)
+            .param("paper_id", &paper_id)
+            .params(&params);
+        let mut result =
+            match cached_response(self.cache.as_ref(), self.embed.as_ref(), &key).await? {
+                CacheLookup::Hit(result) => result,
+                CacheLookup::Miss(embedding) => {
+                    let result = make_request(
+                        &self.http_client,
+                        &self.rate_limiter,
+                        Some(self.cache.as_ref()),
+                        &format!("/paper/{}/references", encode_path_segment(&paper_id)),
+                        Some(&params),
+                        None,
+                        None,
+                    )
+                    .await?;
+                    store_response(self.cache.as_ref(), &key, embedding, &result);
+                    result
+                }
+            };
+
+        let removed = self.filter_and_sort_references(&mut result, min_year, max_year, sort);
+
+        let references = FilteredPage {
+            page: serde_json::from_value(result.clone())?,
+            hidden: removed,
+        };
+        let formatted_result = render(
+            output_format,
+            &result,
+            columns.as_deref(),
+            max_output_chars,
+            |f| {
+                self.formatter.format(
+                    f,
+                    self.renderer.as_ref(),
+                    &references,
+                    &RenderOptions::default(),
+                )
+            },
+        )?;
+
+        let output = ToolOutput::new(formatted_result, output_format, &result);
+
+        Ok(output.into_contents())
+    }
+
+    fn to_tool(&self) -> Tool {
+        Tool {
+            name: "paper_references".into(),
+            description: Some("Get papers cited by a specific paper in Semantic Scholar".into()),
+            input_schema: input_schema::<PaperReferencesArguments>(),
+        }
+    }
+}
@@ -1,20 +1,76 @@
 use std::sync::Arc;
 
-use anyhow::{Result, anyhow};
+use anyhow::Result;
 use async_trait::async_trait;
-use cache::{Cache, Query};
+use cache::Cache;
 use context_server::{Tool, ToolContent, ToolExecutor};
 use embed::Embed;
 use http_client::HttpClient;
+use models::{Page, Paper};
+use schemars::JsonSchema;
+use serde::Deserialize;
 use serde_json::{Value, json};
 
-use crate::utils::{RateLimiter, make_request};
+use crate::{
+    arguments::{
+        PaperField, PublicationType, default_paper_fields, fields_of_study_schema, input_schema,
+        parse_arguments,
+    },
+    content::ToolOutput,
+    error::invalid_argument,
+    format::{Formatter, OutputFormat, render},
+    pagination::Pagination,
+    query_cache::{CacheKey, CacheLookup, cached_response, store_response},
+    result_formatter::{DefaultEntryRenderer, EntryRenderer, RenderOptions, ResultFormatter},
+    utils::{RateLimiter, make_request},
+};
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct PaperSearchArguments {
+    /// A text query to search for. The query will be matched against paper titles, abstracts, venue names, and author names.
+    query: String,
+    /// List of fields to return for each paper. Default: title, abstract, year, citationCount, authors, url
+    fields: Option<Vec<PaperField>>,
+    /// Filter by publication types
+    publication_types: Option<Vec<PublicationType>>,
+    /// If true, only include papers with a public PDF
+    open_access_pdf: Option<bool>,
+    /// Minimum number of citations required
+    min_citation_count: Option<u64>,
+    /// Filter by publication year. Formats: '2019', '2016-2020', '2010-', '-2015'
+    year: Option<String>,
+    /// Filter by publication venues
+    venue: Option<Vec<String>>,
+    /// Filter by fields of study. Use the fields_of_study tool to list the valid values
+    #[serde(default)]
+    #[schemars(schema_with = "fields_of_study_schema")]
+    fields_of_study: Option<Vec<String>>,
+    /// Number of results to skip for pagination
+    #[serde(default)]
+    offset: u64,
+    /// Maximum number of results to return (max: 100)
+    #[schemars(range(max = 100))]
+    limit: Option<u64>,
+    /// Truncate abstracts to this many characters. Default: no truncation
+    #[schemars(range(min = 1))]
+    abstract_length: Option<u64>,
+    /// Columns of csv and tsv output, as field names such as 'title', 'year', 'authors' or 'externalIds.DOI'. Default: every returned field
+    columns: Option<Vec<String>>,
+    /// Maximum length of the output in characters. Results past it are left out, with a note saying how many. Default: the server's limit, if any
+    #[schemars(range(min = 1))]
+    max_output_chars: Option<usize>,
+    #[serde(default)]
+    output_format: OutputFormat,
+}
 
 pub struct PaperSearchTool {
     http_client: Arc<dyn HttpClient>,
     rate_limiter: Arc<RateLimiter>,
     cache: Arc<dyn Cache>,
     embed: Arc<dyn Embed>,
+    renderer: Arc<dyn EntryRenderer>,
+    formatter: Arc<dyn ResultFormatter<Page<Paper>>>,
 }
 
 impl PaperSearchTool {
@@ -29,190 +85,137 @@ impl PaperSearchTool {
             rate_limiter,
             cache,
             embed,
+            renderer: Arc::new(DefaultEntryRenderer::new()),
+            formatter: Arc::new(PaperSearchFormatter),
         }
     }
 
-    fn format_search_results(&self, response: &Value) -> Result<String> {
-        if response.get("error").is_some() {
-            let message = response["error"]["message"]
-                .as_str()
-                .unwrap_or("Unknown error");
-            return Ok(format!("Error: {}", message));
-        }
-
-        if let Some(data) = response.get("data").and_then(|d| d.as_array()) {
-            if data.is_empty() {
-                return Ok(String::from("No papers found matching your criteria."));
-            }
-
-            let total = response.get("total").and_then(|t| t.as_u64()).unwrap_or(0);
-            let offset = response.get("offset").and_then(|o| o.as_u64()).unwrap_or(0);
-
-            let mut result = format!(
-                "Found {} total papers matching your query. Showing results {}-{}:\n\n",
-                total,
-                offset + 1,
-                offset + data.len() as u64
-            );
-
-            for (i, paper) in data.iter().enumerate() {
-                let title = paper
-                    .get("title")
-                    .and_then(|t| t.as_str())
-                    .unwrap_or("Unknown title");
-
-                result.push_str(&format!("{}. {}\n", i + 1, title));
-
-                if let Some(authors) = paper.get("authors").and_then(|a| a.as_array()) {
-                    let author_names: Vec<&str> = authors
-                        .iter()
-                        .filter_map(|author| author.get("name").and_then(|n| n.as_str()))
-                        .collect();
-
-                    if !author_names.is_empty() {
-                        result.push_str(&format!("   Authors: {}\n", author_names.join(", ")));
-                    }
-                }
-
-                if let Some(year) = paper.get("year").and_then(|y| y.as_u64()) {
-                    result.push_str(&format!("   Year: {}\n", year));
-                }
-
-                if let Some(venue) = paper.get("venue").and_then(|v| v.as_str()) {
-                    if !venue.is_empty() {
-                        result.push_str(&format!("   Venue: {}\n", venue));
-                    }
-                }
-
-                if let Some(citation_count) = paper.get("citationCount").and_then(|c| c.as_u64()) {
-                    result.push_str(&format!("   Citations: {}\n", citation_count));
-                }
+    /// Writes the paper entries through `renderer` rather than the default.
+    pub fn with_renderer(mut self, renderer: Arc<dyn EntryRenderer>) -> Self {
+        self.renderer = renderer;
+        self
+    }
 
-                if let Some(abstract_text) = paper.get("abstract").and_then(|a| a.as_str()) {
-                    if !abstract_text.is_empty() {
-                        let summary = abstract_text.to_string();
-                        result.push_str(&format!("   Abstract: {}\n", summary));
-                    }
-                }
+    pub fn with_formatter(mut self, formatter: Arc<dyn ResultFormatter<Page<Paper>>>) -> Self {
+        self.formatter = formatter;
+        self
+    }
+}
 
-                if let Some(url) = paper.get("url").and_then(|u| u.as_str()) {
-                    result.push_str(&format!("   URL: {}\n", url));
-                }
+/// Lays out the output of `paper_search`.
+pub struct PaperSearchFormatter;
+
+impl ResultFormatter<Page<Paper>> for PaperSearchFormatter {
+    fn format(
+        &self,
+        f: &mut dyn Formatter,
+        renderer: &dyn EntryRenderer,
+        page: &Page<Paper>,
+        options: &RenderOptions,
+    ) -> Result<()> {
+        if page.data.is_empty() {
+            f.note("No papers found matching your criteria.");
+            return Ok(());
+        }
 
-                if let Some(paper_id) = paper.get("paperId").and_then(|p| p.as_str()) {
-                    result.push_str(&format!("   Paper ID: {}\n", paper_id));
-                }
+        let total = page.total.unwrap_or(0);
+        let offset = page.offset.unwrap_or(0);
 
-                if i < data.len() - 1 {
-                    result.push_str("\n");
-                }
-            }
+        f.heading(&format!(
+            "Found {} total papers matching your query. Showing results {}-{}:",
+            total,
+            offset + 1,
+            offset + page.data.len() as u64
+        ));
 
-            if let Some(next) = response.get("next").and_then(|n| n.as_u64()) {
-                result.push_str(&format!("\nFor more results, use offset={}", next));
-            }
+        for (i, paper) in page.data.iter().enumerate() {
+            f.item(i + 1, &renderer.paper_title(paper));
+            renderer.paper(f, paper, options);
+        }
 
-            Ok(result)
-        } else {
-            Ok(String::from(
-                "No results found or unexpected API response format.",
-            ))
+        if let Some(next) = page.next {
+            f.note(&format!("For more results, use offset={}", next));
         }
+
+        Ok(())
     }
 }
 
 #[async_trait]
 impl ToolExecutor for PaperSearchTool {
     async fn execute(&self, arguments: Option<Value>) -> Result<Vec<ToolContent>> {
-        log::debug!("Executing PaperSearchTool");
-        let args = arguments.ok_or_else(|| anyhow!("Missing arguments"))?;
-
-        let query = args
-            .get("query")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow!("Missing or invalid query parameter"))?;
+        tracing::debug!("Executing PaperSearchTool");
+        let args: PaperSearchArguments = parse_arguments(arguments)?;
+        let query = args.query.as_str();
 
         if query.trim().is_empty() {
-            return Err(anyhow!("Query string cannot be empty"));
+            return Err(invalid_argument("Query string cannot be empty"));
         }
 
-        let fields = args.get("fields").cloned().unwrap_or_else(|| {
-            json!([
-                "title",
-                "abstract",
-                "year",
-                "citationCount",
-                "authors",
-                "url"
+        let fields = args.fields.unwrap_or_else(|| {
+            default_paper_fields(&[
+                PaperField::Title,
+                PaperField::Abstract,
+                PaperField::Year,
+                PaperField::CitationCount,
+                PaperField::Authors,
+                PaperField::Url,
             ])
         });
 
-        let offset = args.get("offset").and_then(|v| v.as_u64()).unwrap_or(0);
-
-        let limit = args.get("limit").and_then(|v| v.as_u64()).unwrap_or(10);
-
-        if limit > 100 {
-            return Err(anyhow!("Limit cannot exceed 100"));
-        }
+        let limit = args.limit.unwrap_or(10);
+        let options = RenderOptions {
+            abstract_length: args.abstract_length.map(|length| length as usize),
+        };
 
         let params = json!({
             "query": query,
             "fields": fields,
-            "offset": offset,
+            "offset": args.offset,
             "limit": limit,
-            "publication_types": args.get("publication_types"),
-            "open_access_pdf": args.get("open_access_pdf"),
-            "min_citation_count": args.get("min_citation_count"),
-            "year": args.get("year"),
-            "venue": args.get("venue"),
-            "fields_of_study": args.get("fields_of_study")
+            "publication_types": args.publication_types,
+            "open_access_pdf": args.open_access_pdf,
+            "min_citation_count": args.min_citation_count,
+            "year": args.year,
+            "venue": args.venue,
+            "fieldsOfStudy": args.fields_of_study
         });
 
-        // Generate an embedding for the query
-        let embedding = self.embed.embed(&query).await?;
-
-        // Check if we have a cached result for a similar query
-        let similar_queries = self.cache.search_similarity(&embedding)?;
-
-        // Check for any cached queries with high similarity and matching action/params
-        for (cached_query, similarity) in similar_queries.iter() {
-            if similarity > &0.95 && cached_query.action == "paper_search" {
-                // Check if parameters match
-                if cached_query.params == Some(params.clone()) {
-                    log::debug!("Found cached result with similarity {}", similarity);
-                    return Ok(vec![ToolContent::Text {
-                        text: serde_json::from_value(cached_query.results.clone())?,
-                    }]);
-                }
+        let key = CacheKey::new("paper_search", &query).params(&params);
+        let result = match cached_response(self.cache.as_ref(), self.embed.as_ref(), &key).await? {
+            CacheLookup::Hit(result) => result,
+            CacheLookup::Miss(embedding) => {
+                let result = make_request(
+                    &self.http_client,
+                    &self.rate_limiter,
+                    Some(self.cache.as_ref()),
+                    "/paper/search",
+                    Some(&params),
+                    None,
+                    None,
+                )
+                .await?;
+                store_response(self.cache.as_ref(), &key, embedding, &result);
+                result
             }
-        }
-
-        let result = make_request(
-            &self.http_client,
-            &self.rate_limiter,
-            "/paper/search",
-            Some(&params),
-            None,
-        )
-        .await?;
-
-        let formatted_result = self.format_search_results(&result)?;
-
-        let query = Query {
-            action: "paper_search".into(),
-            text: query.into(),
-            embedding,
-            params: Some(params),
-            results: json!(formatted_result),
         };
 
-        if let Err(err) = self.cache.store(query) {
-            log::warn!("Failed to store query in cache: {}", err);
-        }
-
-        Ok(vec![ToolContent::Text {
-            text: formatted_result,
-        }])
+        let page: Page<Paper> = serde_json::from_value(result.clone())?;
+        let formatted_result = render(
+            args.output_format,
+            &result,
+            args.columns.as_deref(),
+            args.max_output_chars,
+            |f| {
+                self.formatter
+                    .format(f, self.renderer.as_ref(), &page, &options)
+            },
+        )?;
+
+        let output = ToolOutput::new(formatted_result, args.output_format, &result)
+            .with_pagination(Pagination::from_page(&page));
+
+        Ok(output.into_contents())
     }
 
     fn to_tool(&self) -> Tool {
@@ -221,76 +224,7 @@ impl ToolExecutor for PaperSearchTool {
             description: Some(
                 "Search for papers on Semantic Scholar using relevance-based ranking".into(),
             ),
-            input_schema: json!({
-                "type": "object",
-                "properties": {
-                    "query": {
-                        "type": "string",
-                        "description": "A text query to search for. The query will be matched against paper titles, abstracts, venue names, and author names."
-                    },
-                    "fields": {
-                        "type": "array",
-                        "description": "List of fields to return for each paper. Default: title, abstract, year, citationCount, authors, url",
-                        "items": {
-                            "type": "string",
-                            "enum": [
-                                "title", "abstract", "year", "citationCount", "authors", "url",
-                                "citations", "references", "venue", "influentialCitationCount",
-                                "corpusId", "externalIds", "fieldsOfStudy", "isOpenAccess",
-                                "openAccessPdf", "paperId", "publicationDate", "publicationTypes",
-                                "publicationVenue", "s2FieldsOfStudy", "tldr"
-                            ]
-                        }
-                    },
-                    "publication_types": {
-                        "type": "array",
-                        "description": "Filter by publication types",
-                        "items": {
-                            "type": "string",
-                            "enum": [
-                                "Review", "JournalArticle", "CaseReport", "ClinicalTrial",
-                                "Conference", "Dataset", "Editorial", "LettersAndComments",
-                                "MetaAnalysis", "News", "Study", "Book", "BookSection"
-                            ]
-                        }
-                    },
-                    "open_access_pdf": {
-                        "type": "boolean",
-                        "description": "If true, only include papers with a public PDF"
-                    },
-                    "min_citation_count": {
-                        "type": "integer",
-                        "description": "Minimum number of citations required"
-                    },
-                    "year": {
-                        "type": "string",
-                        "description": "Filter by publication year. Formats: '2019', '2016-2020', '2010-', '-2015'"
-                    },
-                    "venue": {
-                        "type": "array",
-                        "description": "Filter by publication venues",
-                        "items": {
-                            "type": "string"
-                        }
-                    },
-                    "fields_of_study": {
-                        "type": "array",
-                        "description": "Filter by fields of study",
-                        "items": {
-                            "type": "string"
-                        }
-                    },
-                    "offset": {
-                        "type": "integer",
-                        "description": "Number of results to skip for pagination"
-                    },
-                    "limit": {
-                        "type": "integer",
-                        "description": "Maximum number of results to return (max: 100)"
-                    }
-                },
-                "required": ["query"]
-            }),
+            input_schema: input_schema::<PaperSearchArguments>(),
         }
     }
 }
@@ -0,0 +1,329 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use cache::{Cache, cosine_similarity};
+use context_server::{Tool, ToolContent, ToolExecutor};
+use embed::Embed;
+use http_client::HttpClient;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::{
+    arguments::{input_schema, parse_arguments},
+    content::ToolOutput,
+    error::invalid_argument,
+    format::{Formatter, OutputFormat, render},
+    query_cache::{CacheKey, CacheLookup, cached_response, store_response},
+    utils::{RateLimiter, make_request, normalize_paper_id},
+};
+
+/// SPECTER embedding used to compare papers.
+#[derive(Debug, Clone, Copy, Default, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+enum SpecterModel {
+    SpecterV1,
+    #[default]
+    SpecterV2,
+}
+
+impl SpecterModel {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SpecterModel::SpecterV1 => "specter_v1",
+            SpecterModel::SpecterV2 => "specter_v2",
+        }
+    }
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct PaperSimilarityRankArguments {
+    /// Seed paper identifier in one of the following formats: Semantic Scholar ID, DOI:doi, ARXIV:id, MAG:id, ACL:id, PMID:id, PMCID:id, URL:url. Bare DOIs and arXiv IDs, and links to doi.org, arxiv.org, semanticscholar.org or PubMed, are also accepted
+    paper_id: String,
+    /// List of paper identifiers to rank against the seed paper (max: 499)
+    candidate_paper_ids: Vec<String>,
+    /// Which SPECTER embedding to compare. Default: specter_v2
+    model: Option<SpecterModel>,
+    /// Maximum number of ranked papers to return. Default: 20
+    limit: Option<u64>,
+    /// Maximum length of the output in characters. Results past it are left out, with a note saying how many. Default: the server's limit, if any
+    #[schemars(range(min = 1))]
+    max_output_chars: Option<usize>,
+    #[serde(default)]
+    output_format: OutputFormat,
+}
+
+pub struct PaperSimilarityRankTool {
+    http_client: Arc<dyn HttpClient>,
+    rate_limiter: Arc<RateLimiter>,
+    cache: Arc<dyn Cache>,
+    embed: Arc<dyn Embed>,
+}
+
+impl PaperSimilarityRankTool {
+    pub fn new(
+        http_client: Arc<dyn HttpClient>,
+        rate_limiter: Arc<RateLimiter>,
+        cache: Arc<dyn Cache>,
+        embed: Arc<dyn Embed>,
+    ) -> Self {
+        Self {
+            http_client,
+            rate_limiter,
+            cache,
+            embed,
+        }
+    }
+
+    /// Orders the candidates of a batch response (seed first) by similarity
+    /// to the seed, or explains why they can't be ranked.
+    fn rank<'a>(&self, response: &'a Value) -> std::result::Result<Ranking<'a>, &'static str> {
+        let Some(data) = response.as_array() else {
+            return Err("No papers found or unexpected API response format.");
+        };
+
+        let Some(seed) = data.first().filter(|seed| !seed.is_null()) else {
+            return Err("The seed paper could not be found.");
+        };
+
+        let Some(seed_vector) = specter_vector(seed) else {
+            return Err("No SPECTER embedding is available for the seed paper.");
+        };
+
+        let mut ranked = Vec::new();
+        let mut missing = 0;
+
+        for paper in data.iter().skip(1) {
+            match specter_vector(paper).and_then(|vector| cosine_similarity(&seed_vector, &vector))
+            {
+                Some(similarity) => ranked.push((paper, similarity)),
+                None => missing += 1,
+            }
+        }
+
+        if ranked.is_empty() {
+            return Err(
+                "None of the candidate papers have a SPECTER embedding to compare against.",
+            );
+        }
+
+        ranked.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+
+        Ok(Ranking {
+            seed,
+            ranked,
+            missing,
+        })
+    }
+
+    /// The ranking without the embedding vectors, for JSON output.
+    fn ranking_json(&self, model: &str, ranking: &Ranking, limit: usize) -> Value {
+        json!({
+            "model": model,
+            "seed": {
+                "paperId": ranking.seed.get("paperId"),
+                "title": ranking.seed.get("title"),
+            },
+            "skipped": ranking.missing,
+            "data": ranking
+                .ranked
+                .iter()
+                .take(limit)
+                .map(|(paper, similarity)| json!({
+                    "paperId": paper.get("paperId"),
+                    "title": paper.get("title"),
+                    "year": paper.get("year"),
+                    "similarity": similarity,
+                }))
+                .collect::<Vec<_>>(),
+        })
+    }
+
+    fn format_ranking(
+        &self,
+        f: &mut dyn Formatter,
+        model: &str,
+        ranking: &Ranking,
+        limit: usize,
+    ) -> Result<()> {
+        let seed_title = ranking
+            .seed
+            .get("title")
+            .and_then(|t| t.as_str())
+            .unwrap_or("Unknown title");
+
+        f.heading(&format!(
+            "Ranked {} candidate papers by {} similarity to \"{}\":",
+            ranking.ranked.len(),
+            model,
+            seed_title
+        ));
+
+        for (i, (paper, similarity)) in ranking.ranked.iter().take(limit).enumerate() {
+            let title = paper
+                .get("title")
+                .and_then(|t| t.as_str())
+                .unwrap_or("Unknown title");
+            let paper_id = paper
+                .get("paperId")
+                .and_then(|p| p.as_str())
+                .unwrap_or("Unknown ID");
+
+            f.item(i + 1, &format!("{} (ID: {})", title, paper_id));
+            f.field("Similarity", &format!("{:.4}", similarity));
+
+            if let Some(year) = paper.get("year").and_then(|y| y.as_u64()) {
+                f.field("Year", &year.to_string());
+            }
+        }
+
+        if ranking.missing > 0 {
+            f.note(&format!(
+                "{} candidate(s) were skipped because they were not found or have no embedding.",
+                ranking.missing
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Candidates ordered by similarity to the seed paper.
+struct Ranking<'a> {
+    seed: &'a Value,
+    ranked: Vec<(&'a Value, f32)>,
+    missing: usize,
+}
+
+fn specter_vector(paper: &Value) -> Option<Vec<f32>> {
+    paper
+        .get("embedding")
+        .and_then(|e| e.get("vector"))
+        .and_then(|v| v.as_array())
+        .map(|vector| {
+            vector
+                .iter()
+                .filter_map(|x| x.as_f64())
+                .map(|x| x as f32)
+                .collect()
+        })
+}
+
+#[async_trait]
+impl ToolExecutor for PaperSimilarityRankTool {
+    async fn execute(&self, arguments: Option<Value>) -> Result<Vec<ToolContent>> {
+        tracing::debug!("Executing PaperSimilarityRankTool");
+        let args: PaperSimilarityRankArguments = parse_arguments(arguments)?;
+        let paper_id = normalize_paper_id(&args.paper_id);
+
+        if paper_id.trim().is_empty() {
+            return Err(invalid_argument("Paper ID cannot be empty"));
+        }
+
+        let mut candidate_ids: Vec<String> = args
+            .candidate_paper_ids
+            .iter()
+            .map(|s| normalize_paper_id(s))
+            .filter(|s| !s.is_empty())
+            .collect();
+
+        candidate_ids.sort();
+        candidate_ids.dedup();
+
+        if candidate_ids.is_empty() {
+            return Err(invalid_argument(
+                "Must provide at least one candidate paper ID",
+            ));
+        }
+
+        if candidate_ids.len() > 499 {
+            return Err(invalid_argument(
+                "Cannot rank more than 499 candidate papers at once",
+            ));
+        }
+
+        let model = args.model.unwrap_or_default().as_str();
+
+        let limit = args.limit.unwrap_or(20) as usize;
+
+        let params = json!({
+            "paper_id": paper_id,
+            "candidate_paper_ids": candidate_ids,
+            "model": model,
+        });
+
+        let query_text = format!(
+            "paper_similarity_rank:{}:{}",
+            paper_id,
+            candidate_ids.join(",")
+        );
+
+        // Ranking happens on read, so the cached embeddings serve any limit
+        let key = CacheKey::new("paper_similarity_rank", &query_text).params(&params);
+        let result = match cached_response(self.cache.as_ref(), self.embed.as_ref(), &key).await? {
+            CacheLookup::Hit(result) => result,
+            CacheLookup::Miss(embedding) => {
+                let mut ids = vec![paper_id.to_string()];
+                ids.extend(candidate_ids.iter().cloned());
+
+                let result = make_request(
+                    &self.http_client,
+                    &self.rate_limiter,
+                    Some(self.cache.as_ref()),
+                    "/paper/batch",
+                    Some(&json!({ "fields": format!("title,year,embedding.{}", model) })),
+                    Some(&json!({ "ids": ids })),
+                    None,
+                )
+                .await?;
+                store_response(self.cache.as_ref(), &key, embedding, &result);
+                result
+            }
+        };
+
+        let (formatted_result, response) = match self.rank(&result) {
+            Ok(ranking) => {
+                let response = self.ranking_json(model, &ranking, limit);
+                let formatted_result = render(
+                    args.output_format,
+                    &response,
+                    None,
+                    args.max_output_chars,
+                    |f| self.format_ranking(f, model, &ranking, limit),
+                )?;
+                (formatted_result, response)
+            }
+            Err(message) => {
+                let response = json!({ "message": message });
+                let formatted_result = render(
+                    args.output_format,
+                    &response,
+                    None,
+                    args.max_output_chars,
+                    |f| {
+                        f.note(message);
+                        Ok(())
+                    },
+                )?;
+                (formatted_result, response)
+            }
+        };
+
+        let output = ToolOutput::new(formatted_result, args.output_format, &response);
+
+        Ok(output.into_contents())
+    }
+
+    fn to_tool(&self) -> Tool {
+        Tool {
+            name: "paper_similarity_rank".into(),
+            description: Some(
+                "Rank a set of candidate papers by SPECTER embedding similarity to a seed paper"
+                    .into(),
+            ),
+            input_schema: input_schema::<PaperSimilarityRankArguments>(),
+        }
+    }
+}
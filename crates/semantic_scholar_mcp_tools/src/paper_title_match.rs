@@ -0,0 +1,183 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use cache::Cache;
+use context_server::{Tool, ToolContent, ToolExecutor};
+use embed::Embed;
+use http_client::HttpClient;
+use models::{Page, Paper};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::{
+    arguments::{PaperField, input_schema, parse_arguments},
+    content::ToolOutput,
+    error::invalid_argument,
+    format::{Formatter, OutputFormat, render},
+    query_cache::{CacheKey, CacheLookup, cached_response, store_response},
+    result_formatter::{DefaultEntryRenderer, EntryRenderer, RenderOptions, ResultFormatter},
+    utils::{RateLimiter, make_request},
+};
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct PaperTitleMatchArguments {
+    /// The (near-)exact paper title to match, e.g. copied from a PDF or a reference list
+    title: String,
+    /// List of fields to return for the matched paper. Default: title, year, authors, venue, url
+    fields: Option<Vec<PaperField>>,
+    /// Maximum length of the output in characters. Results past it are left out, with a note saying how many. Default: the server's limit, if any
+    #[schemars(range(min = 1))]
+    max_output_chars: Option<usize>,
+    #[serde(default)]
+    output_format: OutputFormat,
+}
+
+pub struct PaperTitleMatchTool {
+    http_client: Arc<dyn HttpClient>,
+    rate_limiter: Arc<RateLimiter>,
+    cache: Arc<dyn Cache>,
+    embed: Arc<dyn Embed>,
+    renderer: Arc<dyn EntryRenderer>,
+    formatter: Arc<dyn ResultFormatter<Page<Paper>>>,
+}
+
+impl PaperTitleMatchTool {
+    pub fn new(
+        http_client: Arc<dyn HttpClient>,
+        rate_limiter: Arc<RateLimiter>,
+        cache: Arc<dyn Cache>,
+        embed: Arc<dyn Embed>,
+    ) -> Self {
+        Self {
+            http_client,
+            rate_limiter,
+            cache,
+            embed,
+            renderer: Arc::new(DefaultEntryRenderer::new()),
+            formatter: Arc::new(PaperTitleMatchFormatter),
+        }
+    }
+
+    /// Writes the entries through `renderer` rather than the default.
+    pub fn with_renderer(mut self, renderer: Arc<dyn EntryRenderer>) -> Self {
+        self.renderer = renderer;
+        self
+    }
+
+    pub fn with_formatter(mut self, formatter: Arc<dyn ResultFormatter<Page<Paper>>>) -> Self {
+        self.formatter = formatter;
+        self
+    }
+}
+
+/// Lays out the output of `paper_title_match`.
+pub struct PaperTitleMatchFormatter;
+
+impl ResultFormatter<Page<Paper>> for PaperTitleMatchFormatter {
+    fn format(
+        &self,
+        f: &mut dyn Formatter,
+        renderer: &dyn EntryRenderer,
+        page: &Page<Paper>,
+        options: &RenderOptions,
+    ) -> Result<()> {
+        let Some(paper) = page.data.first() else {
+            f.note("No paper found matching this title.");
+            return Ok(());
+        };
+
+        f.heading(&format!(
+            "Best match: {}",
+            paper.title.as_deref().unwrap_or("Unknown title")
+        ));
+        f.field(
+            "Paper ID",
+            paper.paper_id.as_deref().unwrap_or("Unknown ID"),
+        );
+
+        if let Some(match_score) = paper.match_score {
+            f.field("Match Score", &format!("{:.2}", match_score));
+        }
+
+        renderer.paper(f, paper, options);
+
+        f.note("(Use the paper_details tool with this paper ID to see the full record)");
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for PaperTitleMatchTool {
+    async fn execute(&self, arguments: Option<Value>) -> Result<Vec<ToolContent>> {
+        tracing::debug!("Executing PaperTitleMatchTool");
+        let PaperTitleMatchArguments {
+            title,
+            fields,
+            max_output_chars,
+            output_format,
+        } = parse_arguments(arguments)?;
+
+        if title.trim().is_empty() {
+            return Err(invalid_argument("Title cannot be empty"));
+        }
+
+        let fields = fields.unwrap_or_else(|| {
+            vec![
+                PaperField::Title,
+                PaperField::Year,
+                PaperField::Authors,
+                PaperField::Venue,
+                PaperField::Url,
+            ]
+        });
+
+        let params = json!({
+            "query": title,
+            "fields": fields,
+        });
+
+        let key = CacheKey::new("paper_title_match", &title).params(&params);
+        let result = match cached_response(self.cache.as_ref(), self.embed.as_ref(), &key).await? {
+            CacheLookup::Hit(result) => result,
+            CacheLookup::Miss(embedding) => {
+                let result = make_request(
+                    &self.http_client,
+                    &self.rate_limiter,
+                    Some(self.cache.as_ref()),
+                    "/paper/search/match",
+                    Some(&params),
+                    None,
+                    None,
+                )
+                .await?;
+                store_response(self.cache.as_ref(), &key, embedding, &result);
+                result
+            }
+        };
+
+        let page: Page<Paper> = serde_json::from_value(result.clone())?;
+        let formatted_result = render(output_format, &result, None, max_output_chars, |f| {
+            self.formatter
+                .format(f, self.renderer.as_ref(), &page, &RenderOptions::default())
+        })?;
+
+        let output = ToolOutput::new(formatted_result, output_format, &result);
+
+        Ok(output.into_contents())
+    }
+
+    fn to_tool(&self) -> Tool {
+        Tool {
+            name: "paper_title_match".into(),
+            description: Some(
+                "Resolve a paper title to the single closest matching paper on Semantic Scholar, with a match score"
+                    .into(),
+            ),
+            input_schema: input_schema::<PaperTitleMatchArguments>(),
+        }
+    }
+}
@@ -0,0 +1,358 @@
+use std::{collections::HashSet, sync::Arc};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use cache::Cache;
+use chrono::Datelike;
+use context_server::{ComputedPrompt, Prompt, PromptArgument, PromptExecutor};
+use http_client::HttpClient;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::{
+    arguments::parse_arguments,
+    error::{Error, invalid_argument},
+    prompt::{json_resource, parse_count, text_message},
+    utils::{RateLimiter, encode_path_segment, make_request, normalize_paper_id},
+};
+
+const SUBMISSION_FIELDS: &str = "paperId,title,abstract,tldr,year,publicationDate,venue,authors,fieldsOfStudy,citationCount,influentialCitationCount,referenceCount";
+const PRIOR_WORK_FIELDS: &str = "paperId,title,abstract,year,venue,authors,citationCount";
+
+/// How many prior-work search results are attached by default.
+const DEFAULT_MAX_PRIOR_WORK: usize = 15;
+
+/// The most results a single search page returns.
+const MAX_SEARCH_LIMIT: usize = 100;
+
+/// Search queries built from an abstract are cut to this many words, since
+/// longer queries rarely match anything.
+const QUERY_WORDS: usize = 12;
+
+const RUBRIC: &str = "Structure the review with these sections:
+1. Summary: the problem, the approach and the main claims, in your own words
+2. Novelty: how the contribution differs from the attached prior work, naming the closest papers
+3. Soundness: whether the methodology and evidence support the claims, and what is missing
+4. Significance: who would build on this work, taking the citation statistics of the area into account
+5. Related work coverage: closely related prior work the submission does not cite
+6. Clarity: how well the paper is organised and written, as far as the available text shows
+7. Questions for the authors
+8. Recommendation: accept, minor revision, major revision or reject, with a confidence from 1 to 5";
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct PeerReviewArguments {
+    paper_id: Option<String>,
+    title: Option<String>,
+    #[serde(rename = "abstract")]
+    abstract_text: Option<String>,
+    max_prior_work: Option<String>,
+}
+
+pub struct PeerReviewPrompt {
+    http_client: Arc<dyn HttpClient>,
+    rate_limiter: Arc<RateLimiter>,
+    cache: Arc<dyn Cache>,
+}
+
+impl PeerReviewPrompt {
+    pub fn new(
+        http_client: Arc<dyn HttpClient>,
+        rate_limiter: Arc<RateLimiter>,
+        cache: Arc<dyn Cache>,
+    ) -> Self {
+        Self {
+            http_client,
+            rate_limiter,
+            cache,
+        }
+    }
+
+    async fn request(&self, endpoint: &str, params: Value) -> Result<Value> {
+        make_request(
+            &self.http_client,
+            &self.rate_limiter,
+            Some(self.cache.as_ref()),
+            endpoint,
+            Some(&params),
+            None,
+            None,
+        )
+        .await
+    }
+
+    /// IDs of the papers the submission cites, to tell which prior work it
+    /// already covers.
+    async fn cited_ids(&self, paper_id: &str) -> HashSet<String> {
+        let result = self
+            .request(
+                &format!("/paper/{}/references", encode_path_segment(paper_id)),
+                json!({ "fields": "paperId", "limit": 1000 }),
+            )
+            .await;
+
+        match result {
+            Ok(result) => result["data"]
+                .as_array()
+                .map(|references| {
+                    references
+                        .iter()
+                        .filter_map(|reference| reference["citedPaper"]["paperId"].as_str())
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default(),
+            Err(err) => {
+                tracing::warn!("Failed to fetch references of {}: {}", paper_id, err);
+                HashSet::new()
+            }
+        }
+    }
+
+    /// Searches for earlier papers on the same topic, leaving out the
+    /// submission itself.
+    async fn prior_work(
+        &self,
+        query: &str,
+        year: Option<u64>,
+        exclude: Option<&str>,
+        max_prior_work: usize,
+    ) -> Result<Vec<Value>> {
+        let mut params = json!({
+            "query": query,
+            "fields": PRIOR_WORK_FIELDS,
+            "limit": (max_prior_work + 1).min(MAX_SEARCH_LIMIT),
+        });
+        if let Some(year) = year {
+            params["year"] = json!(format!("-{}", year));
+        }
+
+        let result = match self.request("/paper/search", params).await {
+            Ok(result) => result,
+            // Nothing matched the query
+            Err(err) if matches!(err.downcast_ref::<Error>(), Some(Error::NotFound(_))) => {
+                return Ok(Vec::new());
+            }
+            Err(err) => return Err(err),
+        };
+
+        Ok(result["data"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|paper| exclude.is_none() || paper["paperId"].as_str() != exclude)
+            .take(max_prior_work)
+            .collect())
+    }
+}
+
+/// A search query from the title, or the opening words of the abstract.
+fn search_query(title: Option<&str>, abstract_text: Option<&str>) -> Option<String> {
+    if let Some(title) = title {
+        return Some(title.to_string());
+    }
+
+    let words: Vec<&str> = abstract_text?
+        .split_whitespace()
+        .take(QUERY_WORDS)
+        .collect();
+    Some(words.join(" ")).filter(|query| !query.is_empty())
+}
+
+/// Citation counts of the submission, normalised by its age, next to those
+/// of the prior work it is compared with.
+fn citation_statistics(submission: &Value, prior_work: &[Value]) -> Value {
+    let mut statistics = json!({});
+
+    if let Some(citations) = submission["citationCount"].as_u64() {
+        statistics["citationCount"] = json!(citations);
+        statistics["influentialCitationCount"] = submission["influentialCitationCount"].clone();
+        statistics["referenceCount"] = submission["referenceCount"].clone();
+
+        if let Some(year) = submission["year"].as_u64() {
+            let current_year = chrono::Utc::now().year() as u64;
+            let years = current_year.saturating_sub(year) + 1;
+            statistics["citationsPerYear"] = json!(citations as f64 / years as f64);
+        }
+    }
+
+    let mut counts: Vec<u64> = prior_work
+        .iter()
+        .filter_map(|paper| paper["citationCount"].as_u64())
+        .collect();
+    if !counts.is_empty() {
+        counts.sort_unstable();
+        statistics["priorWork"] = json!({
+            "papers": counts.len(),
+            "medianCitationCount": counts[counts.len() / 2],
+            "maxCitationCount": counts[counts.len() - 1],
+        });
+    }
+
+    statistics
+}
+
+fn instructions(title: &str, has_prior_work: bool, is_published: bool) -> String {
+    let mut text = format!(
+        "Act as an expert peer reviewer for the submission \"{}\". Its metadata and citation statistics are attached as JSON resources",
+        title
+    );
+    if has_prior_work {
+        text.push_str(", together with related prior work found by search");
+        if is_published {
+            text.push_str(". Prior work marked citedBySubmission is already cited");
+        }
+    }
+    text.push_str(".\n\n");
+    text.push_str(RUBRIC);
+    text.push_str("\n\nBase the review on the attached material, say where it is not enough to judge a point, and do not invent papers.\n");
+    text
+}
+
+#[async_trait]
+impl PromptExecutor for PeerReviewPrompt {
+    fn name(&self) -> &str {
+        "peer_review"
+    }
+
+    async fn compute(&self, arguments: Option<Value>) -> Result<ComputedPrompt> {
+        tracing::debug!("Computing PeerReviewPrompt");
+        let args: PeerReviewArguments = parse_arguments(arguments)?;
+
+        let max_prior_work = parse_count(
+            args.max_prior_work.as_deref(),
+            "max_prior_work",
+            DEFAULT_MAX_PRIOR_WORK,
+        )?;
+        let non_empty = |value: Option<String>| {
+            value
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+
+        let submission = match non_empty(args.paper_id) {
+            Some(paper_id) => {
+                self.request(
+                    &format!(
+                        "/paper/{}",
+                        encode_path_segment(&normalize_paper_id(&paper_id))
+                    ),
+                    json!({ "fields": SUBMISSION_FIELDS }),
+                )
+                .await?
+            }
+            None => {
+                let title = non_empty(args.title);
+                let abstract_text = non_empty(args.abstract_text);
+                if abstract_text.is_none() {
+                    return Err(invalid_argument(
+                        "Provide a paper_id, or the abstract of the paper to review",
+                    ));
+                }
+                json!({ "title": title, "abstract": abstract_text })
+            }
+        };
+
+        let paper_id = submission["paperId"].as_str();
+        let title = submission["title"].as_str();
+
+        let mut prior_work = match search_query(title, submission["abstract"].as_str()) {
+            Some(query) => {
+                self.prior_work(
+                    &query,
+                    submission["year"].as_u64(),
+                    paper_id,
+                    max_prior_work,
+                )
+                .await?
+            }
+            None => Vec::new(),
+        };
+
+        // An unpublished submission has no references to compare against
+        if let Some(paper_id) = paper_id.filter(|_| !prior_work.is_empty()) {
+            let cited = self.cited_ids(paper_id).await;
+            for paper in &mut prior_work {
+                let is_cited = paper["paperId"]
+                    .as_str()
+                    .is_some_and(|paper_id| cited.contains(paper_id));
+                paper["citedBySubmission"] = json!(is_cited);
+            }
+        }
+
+        let statistics = citation_statistics(&submission, &prior_work);
+        let submission_uri = match paper_id {
+            Some(paper_id) => format!("paper://{}", paper_id),
+            None => "paper://submission".to_string(),
+        };
+
+        let mut messages = vec![text_message(instructions(
+            title.unwrap_or("the attached paper"),
+            !prior_work.is_empty(),
+            paper_id.is_some(),
+        ))];
+        messages.push(json_resource(submission_uri.clone(), &submission)?);
+        messages.push(json_resource(
+            format!("{}/statistics", submission_uri),
+            &statistics,
+        )?);
+        if !prior_work.is_empty() {
+            messages.push(json_resource(
+                format!("{}/prior-work", submission_uri),
+                &json!(prior_work),
+            )?);
+        }
+
+        Ok(ComputedPrompt {
+            description: Some(format!(
+                "Peer review of {} with {} prior work papers",
+                title.unwrap_or("the submission"),
+                prior_work.len()
+            )),
+            messages,
+        })
+    }
+
+    fn to_prompt(&self) -> Prompt {
+        Prompt {
+            name: self.name().into(),
+            description: Some(
+                "Start a structured peer review of a paper, with related prior work, citation statistics and a review rubric"
+                    .into(),
+            ),
+            arguments: Some(vec![
+                PromptArgument {
+                    name: "paper_id".into(),
+                    description: Some(
+                        "The paper to review: a Semantic Scholar ID, DOI:doi, ARXIV:id or another supported identifier. Leave out to review an unpublished abstract"
+                            .into(),
+                    ),
+                    required: Some(false),
+                },
+                PromptArgument {
+                    name: "title".into(),
+                    description: Some("Title of an unpublished paper, used with abstract".into()),
+                    required: Some(false),
+                },
+                PromptArgument {
+                    name: "abstract".into(),
+                    description: Some(
+                        "Abstract of an unpublished paper to review when there is no paper_id"
+                            .into(),
+                    ),
+                    required: Some(false),
+                },
+                PromptArgument {
+                    name: "max_prior_work".into(),
+                    description: Some(format!(
+                        "How many prior work papers to attach. Default: {}",
+                        DEFAULT_MAX_PRIOR_WORK
+                    )),
+                    required: Some(false),
+                },
+            ]),
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde_json::json;
+
+use crate::notifier::{notify, progress_token};
+
+/// Counts the steps of a long-running fetch, such as the requests of a
+/// batch, notifying the client as each one completes.
+pub struct Progress {
+    total: AtomicU64,
+    done: AtomicU64,
+}
+
+impl Progress {
+    pub fn new(total: u64) -> Self {
+        Self {
+            total: AtomicU64::new(total),
+            done: AtomicU64::new(0),
+        }
+    }
+
+    /// Adds steps that only became known once earlier ones were done.
+    pub fn add_steps(&self, steps: u64) {
+        self.total.fetch_add(steps, Ordering::Relaxed);
+    }
+
+    /// Marks one more step as done.
+    pub fn advance(&self, message: &str) {
+        let done = self.done.fetch_add(1, Ordering::Relaxed) + 1;
+        let total = self.total.load(Ordering::Relaxed).max(done);
+
+        // Progress is only reported to clients that asked for it
+        if let Some(token) = progress_token() {
+            notify(
+                "notifications/progress",
+                json!({
+                    "progressToken": token,
+                    "progress": done,
+                    "total": total,
+                    "message": message,
+                }),
+            );
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use anyhow::Result;
+use context_server::{PromptContent, PromptMessage, ResourceContent, Role};
+use serde_json::Value;
+
+use crate::error::invalid_argument;
+
+pub fn text_message(text: String) -> PromptMessage {
+    PromptMessage {
+        role: Role::User,
+        content: PromptContent::Text { text },
+    }
+}
+
+/// Attaches fetched data to a prompt as an embedded JSON resource.
+pub fn json_resource(uri: String, value: &Value) -> Result<PromptMessage> {
+    Ok(PromptMessage {
+        role: Role::User,
+        content: PromptContent::Resource {
+            resource: ResourceContent {
+                uri,
+                mime_type: Some("application/json".into()),
+                text: Some(serde_json::to_string_pretty(value)?),
+                blob: None,
+            },
+        },
+    })
+}
+
+/// Prompt arguments are always strings, so numeric ones are parsed here.
+pub fn parse_count(value: Option<&str>, name: &str, default: usize) -> Result<usize> {
+    match value.map(str::trim) {
+        Some(value) => value
+            .parse::<usize>()
+            .ok()
+            .filter(|count| *count > 0)
+            .ok_or_else(|| invalid_argument(format!("{} must be a positive number", name))),
+        None => Ok(default),
+    }
+}
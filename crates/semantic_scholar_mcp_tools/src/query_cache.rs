@@ -0,0 +1,291 @@
+use std::collections::HashMap;
+
+use anyhow::{Result, anyhow};
+use cache::{Cache, Query};
+use chrono::NaiveDateTime;
+use embed::{Embed, Embedding};
+use semantic_scholar_client::{is_empty_result, is_offline, served_from_cache};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+use crate::{error::Error, settings};
+
+/// How similar a cached query's embedding must be for its response to be
+/// reused, unless configured otherwise.
+const SIMILARITY_THRESHOLD: f32 = 0.95;
+
+/// The highest threshold used offline, where a close answer beats none.
+const OFFLINE_SIMILARITY_THRESHOLD: f32 = 0.85;
+
+/// How cached queries are matched, from the `[cache.similarity]` section of
+/// a configuration file. A lower threshold serves more calls from the cache
+/// at the risk of answering with a result for a slightly different query.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SimilaritySettings {
+    /// Cosine similarity above which a cached result is reused
+    pub threshold: Option<f32>,
+    /// How many of the most similar cached queries are considered
+    pub top_k: Option<usize>,
+    /// Settings for one tool, such as `paper_search`
+    pub tools: HashMap<String, ToolSimilaritySettings>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ToolSimilaritySettings {
+    pub threshold: Option<f32>,
+    pub top_k: Option<usize>,
+}
+
+impl SimilaritySettings {
+    /// Checks that every threshold is a cosine similarity.
+    pub fn validate(&self) -> Result<()> {
+        for (action, threshold) in self
+            .tools
+            .iter()
+            .filter_map(|(action, tool)| Some((action.as_str(), tool.threshold?)))
+            .chain(self.threshold.map(|threshold| ("every tool", threshold)))
+        {
+            if !(-1.0..=1.0).contains(&threshold) {
+                return Err(anyhow!(
+                    "Similarity threshold for {} must be between -1 and 1, got {}",
+                    action,
+                    threshold
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn threshold(&self, action: &str) -> f32 {
+        self.tools
+            .get(action)
+            .and_then(|tool| tool.threshold)
+            .or(self.threshold)
+            .unwrap_or(SIMILARITY_THRESHOLD)
+    }
+
+    fn top_k(&self, action: &str) -> usize {
+        self.tools
+            .get(action)
+            .and_then(|tool| tool.top_k)
+            .or(self.top_k)
+            .unwrap_or(usize::MAX)
+    }
+}
+
+/// Whether a result cached with `model` can be compared with a query
+/// embedded with `current`. Results cached before models were recorded are
+/// compared when their vectors have the same dimensions.
+fn same_model(model: Option<&String>, current: &str) -> bool {
+    model.is_none_or(|cached| cached == current)
+}
+
+/// Identifies a tool call in the query cache: the tool, the parameters of the
+/// API requests behind it and the text its embedding is computed from. Two
+/// calls share a cached result only when their actions and parameters are
+/// equal, so parameters are canonicalized: members that are null are
+/// dropped, making an omitted argument and an explicit null the same call.
+#[derive(Debug, Clone)]
+pub struct CacheKey {
+    action: &'static str,
+    text: String,
+    params: Map<String, Value>,
+}
+
+impl CacheKey {
+    pub fn new(action: &'static str, text: &str) -> Self {
+        Self {
+            action,
+            text: text.to_string(),
+            params: Map::new(),
+        }
+    }
+
+    /// Adds the members of a JSON object of parameters.
+    pub fn params(mut self, params: &Value) -> Self {
+        if let Some(params) = params.as_object() {
+            for (name, value) in params {
+                self = self.param(name, value);
+            }
+        }
+        self
+    }
+
+    /// Adds one parameter, unless it is null.
+    pub fn param(mut self, name: &str, value: impl Serialize) -> Self {
+        match serde_json::to_value(value) {
+            Ok(Value::Null) => {}
+            Ok(value) => {
+                self.params.insert(name.to_string(), value);
+            }
+            Err(err) => tracing::warn!("Failed to serialize cache parameter {}: {}", name, err),
+        }
+        self
+    }
+
+    fn params_value(&self) -> Value {
+        Value::Object(self.params.clone())
+    }
+}
+
+/// What a tool found in the cache for a call.
+pub enum CacheLookup {
+    /// The API response cached for the same or a similar query
+    Hit(Value),
+    /// Nothing usable was cached. Holds the query's embedding, to store the
+    /// response with once fetched, unless the query could not be embedded
+    Miss(Option<Embedding>),
+}
+
+/// The API response a tool cached for the same parameters, or for a query
+/// whose text is similar to that of `key`. Tools cache responses before formatting
+/// them, so one entry serves every output format, abstract length and column
+/// selection. A call repeating cached parameters is answered without
+/// embedding its text at all.
+pub async fn cached_response(
+    cache: &dyn Cache,
+    embed: &dyn Embed,
+    key: &CacheKey,
+) -> Result<CacheLookup> {
+    let action = key.action;
+    let params = &key.params_value();
+    if let Some(cached_query) = cache.get_query(action, params).map_err(Error::cache)? {
+        check_cached_dimensions(embed, &cached_query)?;
+        if let Some(response) = cached_result(&cached_query) {
+            tracing::debug!("Found cached result with the same parameters");
+            return Ok(CacheLookup::Hit(response));
+        }
+    }
+
+    // A call goes on without the similarity lookup rather than failing
+    // while the embedding provider is down
+    let embedding = match embed.embed_with_model(&key.text).await {
+        Ok(embedding) => embedding,
+        Err(err) => {
+            tracing::warn!(
+                "Skipping the similarity lookup, as embedding failed: {}",
+                err
+            );
+            return Ok(CacheLookup::Miss(None));
+        }
+    };
+    // A fallback provider checks the dimensions of its own vectors
+    if let Some(dimensions) = embed.dimensions() {
+        if embedding.model_id == embed.model_id() && embedding.vector.len() != dimensions {
+            return Err(Error::embed(anyhow!(
+                "{} returned a {}-dimensional embedding, {} expected; fix `dimensions` in the [embedding] section",
+                embedding.model_id,
+                embedding.vector.len(),
+                dimensions
+            )));
+        }
+    }
+    let similar_queries = cache
+        .search_similarity(&embedding.vector)
+        .map_err(Error::cache)?;
+    let (mut threshold, top_k) = settings::current(|settings| {
+        let similarity = settings.similarity();
+        (similarity.threshold(action), similarity.top_k(action))
+    });
+    if is_offline() {
+        threshold = threshold.min(OFFLINE_SIMILARITY_THRESHOLD);
+    }
+
+    for (cached_query, similarity) in similar_queries.into_iter().take(top_k) {
+        if similarity <= threshold {
+            break;
+        }
+        if cached_query.action != action || cached_query.params.as_ref() != Some(params) {
+            continue;
+        }
+        if !same_model(cached_query.embedding_model.as_ref(), &embedding.model_id) {
+            tracing::debug!(
+                "Skipping result cached with embedding model {:?}",
+                cached_query.embedding_model
+            );
+            continue;
+        }
+        check_cached_dimensions(embed, &cached_query)?;
+        if let Some(response) = cached_result(&cached_query) {
+            tracing::debug!("Found cached result with similarity {}", similarity);
+            return Ok(CacheLookup::Hit(response));
+        }
+    }
+
+    Ok(CacheLookup::Miss(Some(embedding)))
+}
+
+/// Fails when a result cached with the current model holds an embedding of
+/// other dimensions than the model returns, which happens when a model name
+/// is reused for another model. Comparing such vectors would never match.
+fn check_cached_dimensions(embed: &dyn Embed, cached_query: &Query) -> Result<(), Error> {
+    let Some(dimensions) = embed.dimensions() else {
+        return Ok(());
+    };
+    if cached_query.embedding.len() == dimensions
+        || cached_query.embedding_model.as_deref() != Some(embed.model_id().as_str())
+    {
+        return Ok(());
+    }
+
+    Err(Error::cache(anyhow!(
+        "Results cached with {} hold {}-dimensional embeddings, but it returns {}; clear the cache with cache_clear",
+        embed.model_id(),
+        cached_query.embedding.len(),
+        dimensions
+    )))
+}
+
+/// The response held by a cached query, noting when it was cached. Entries
+/// cached as formatted text hold no response and are refetched.
+fn cached_result(cached_query: &Query) -> Option<Value> {
+    let response = cached_query.results.get("response")?;
+    if let Some(cached_at) = cached_query
+        .results
+        .get("cached_at")
+        .and_then(|cached_at| serde_json::from_value::<NaiveDateTime>(cached_at.clone()).ok())
+    {
+        served_from_cache(cached_at);
+    }
+    Some(response.clone())
+}
+
+/// Caches the API response a tool formats its output from. A failure to
+/// store it is logged rather than failing the call. Pages without results are
+/// left to the short-lived negative entries of the response cache, so a
+/// search that matched nothing is retried within minutes rather than a day.
+/// Without an embedding, the response is stored with an empty vector that no
+/// query is similar to, for exact lookups alone.
+pub fn store_response(
+    cache: &dyn Cache,
+    key: &CacheKey,
+    embedding: Option<Embedding>,
+    response: &Value,
+) {
+    if is_empty_result(response) {
+        return;
+    }
+
+    let (embedding, embedding_model) = match embedding {
+        Some(embedding) => (embedding.vector, Some(embedding.model_id)),
+        None => (Vec::new(), None),
+    };
+    let query = Query {
+        action: key.action.to_string(),
+        text: key.text.clone(),
+        embedding,
+        embedding_model,
+        normalized: false,
+        params: Some(key.params_value()),
+        results: json!({
+            "response": response,
+            "cached_at": chrono::Utc::now().naive_utc(),
+        }),
+    };
+
+    if let Err(err) = cache.store(query) {
+        tracing::warn!("Failed to store query in cache: {}", err);
+    }
+}
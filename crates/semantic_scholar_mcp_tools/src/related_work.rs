@@ -0,0 +1,255 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use cache::Cache;
+use context_server::{ComputedPrompt, Prompt, PromptArgument, PromptExecutor};
+use http_client::HttpClient;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::{
+    arguments::parse_arguments,
+    prompt::{json_resource, parse_count, text_message},
+    utils::{RateLimiter, api_base_url, encode_path_segment, make_request, normalize_paper_id},
+};
+
+const SEED_FIELDS: &str = "paperId,title,abstract,tldr,year,venue,authors,externalIds";
+const REFERENCE_FIELDS: &str =
+    "paperId,title,abstract,year,venue,authors,citationCount,isInfluential,intents,contexts";
+const RECOMMENDATION_FIELDS: &str = "paperId,title,abstract,year,venue,authors,citationCount";
+
+/// How many references and recommendations are attached by default.
+const DEFAULT_MAX_PAPERS: usize = 30;
+
+/// The most papers the recommendations endpoint returns.
+const MAX_RECOMMENDATIONS: usize = 500;
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct RelatedWorkArguments {
+    paper_id: String,
+    focus: Option<String>,
+    max_papers: Option<String>,
+}
+
+pub struct RelatedWorkPrompt {
+    http_client: Arc<dyn HttpClient>,
+    rate_limiter: Arc<RateLimiter>,
+    cache: Arc<dyn Cache>,
+}
+
+impl RelatedWorkPrompt {
+    pub fn new(
+        http_client: Arc<dyn HttpClient>,
+        rate_limiter: Arc<RateLimiter>,
+        cache: Arc<dyn Cache>,
+    ) -> Self {
+        Self {
+            http_client,
+            rate_limiter,
+            cache,
+        }
+    }
+
+    async fn request(
+        &self,
+        endpoint: &str,
+        params: Value,
+        base_url: Option<&str>,
+    ) -> Result<Value> {
+        make_request(
+            &self.http_client,
+            &self.rate_limiter,
+            Some(self.cache.as_ref()),
+            endpoint,
+            Some(&params),
+            None,
+            base_url,
+        )
+        .await
+    }
+
+    /// The papers the seed cites, influential ones first, then by citations.
+    async fn references(&self, paper_id: &str, max_papers: usize) -> Result<Vec<Value>> {
+        let result = self
+            .request(
+                &format!("/paper/{}/references", encode_path_segment(paper_id)),
+                json!({ "fields": REFERENCE_FIELDS, "limit": 1000 }),
+                None,
+            )
+            .await?;
+
+        let mut references: Vec<Value> = result["data"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|reference| reference["citedPaper"]["paperId"].is_string())
+            .collect();
+
+        let rank = |reference: &Value| {
+            (
+                reference["isInfluential"].as_bool().unwrap_or(false),
+                reference["citedPaper"]["citationCount"]
+                    .as_u64()
+                    .unwrap_or(0),
+            )
+        };
+        references.sort_by_key(|reference| std::cmp::Reverse(rank(reference)));
+        references.truncate(max_papers);
+        Ok(references)
+    }
+
+    /// Papers similar to the seed, which it may not cite yet. Not every paper
+    /// has recommendations, so a failure leaves the list empty.
+    async fn recommendations(&self, paper_id: &str, max_papers: usize) -> Vec<Value> {
+        let result = self
+            .request(
+                &format!(
+                    "/recommendations/v1/papers/forpaper/{}",
+                    encode_path_segment(paper_id)
+                ),
+                json!({
+                    "fields": RECOMMENDATION_FIELDS,
+                    "limit": max_papers.min(MAX_RECOMMENDATIONS),
+                }),
+                Some(&api_base_url()),
+            )
+            .await;
+
+        match result {
+            Ok(result) => result["recommendedPapers"]
+                .as_array()
+                .cloned()
+                .unwrap_or_default(),
+            Err(err) => {
+                tracing::warn!("Failed to fetch recommendations for {}: {}", paper_id, err);
+                Vec::new()
+            }
+        }
+    }
+}
+
+fn instructions(seed: &Value, focus: Option<&str>, has_recommendations: bool) -> String {
+    let title = seed["title"].as_str().unwrap_or("the seed paper");
+
+    let mut text = format!(
+        "Draft the related-work section for \"{}\". The paper, the works it cites and, where available, similar papers it may have missed are attached as JSON resources.\n\n",
+        title
+    );
+
+    text.push_str("Guidelines:\n");
+    text.push_str("- Group the prior work into three to six themes and write one paragraph per theme, rather than summarising papers one by one\n");
+    text.push_str("- For each theme, explain how the seed paper relates to or departs from that line of work\n");
+    text.push_str("- Give the most weight to references marked isInfluential, and use their citation contexts to see what the paper draws on them for\n");
+    if has_recommendations {
+        text.push_str("- The recommended papers are not cited yet: mention the ones that clearly belong to a theme, and list them separately as suggested additions\n");
+    }
+    text.push_str("- Cite papers as (first author surname, year) and end with a reference list including each paper's Semantic Scholar ID\n");
+    text.push_str(
+        "- Only discuss papers that appear in the attached resources; do not invent citations\n",
+    );
+
+    if let Some(focus) = focus {
+        text.push_str(&format!("\nFocus: {}\n", focus));
+    }
+
+    text
+}
+
+#[async_trait]
+impl PromptExecutor for RelatedWorkPrompt {
+    fn name(&self) -> &str {
+        "related_work"
+    }
+
+    async fn compute(&self, arguments: Option<Value>) -> Result<ComputedPrompt> {
+        tracing::debug!("Computing RelatedWorkPrompt");
+        let args: RelatedWorkArguments = parse_arguments(arguments)?;
+
+        let paper_id = normalize_paper_id(&args.paper_id);
+        let max_papers = parse_count(args.max_papers.as_deref(), "max_papers", DEFAULT_MAX_PAPERS)?;
+        let focus = args
+            .focus
+            .as_deref()
+            .map(str::trim)
+            .filter(|focus| !focus.is_empty());
+
+        let seed = self
+            .request(
+                &format!("/paper/{}", encode_path_segment(&paper_id)),
+                json!({ "fields": SEED_FIELDS }),
+                None,
+            )
+            .await?;
+        let seed_id = seed["paperId"].as_str().unwrap_or(&paper_id).to_string();
+
+        let references = self.references(&seed_id, max_papers).await?;
+        let recommendations = self.recommendations(&seed_id, max_papers).await;
+
+        let mut messages = vec![text_message(instructions(
+            &seed,
+            focus,
+            !recommendations.is_empty(),
+        ))];
+        messages.push(json_resource(format!("paper://{}", seed_id), &seed)?);
+        messages.push(json_resource(
+            format!("paper://{}/references", seed_id),
+            &json!(references),
+        )?);
+        if !recommendations.is_empty() {
+            messages.push(json_resource(
+                format!("paper://{}/recommendations", seed_id),
+                &json!(recommendations),
+            )?);
+        }
+
+        Ok(ComputedPrompt {
+            description: Some(format!(
+                "Related work for {} with {} references and {} recommendations",
+                seed["title"].as_str().unwrap_or(&seed_id),
+                references.len(),
+                recommendations.len()
+            )),
+            messages,
+        })
+    }
+
+    fn to_prompt(&self) -> Prompt {
+        Prompt {
+            name: self.name().into(),
+            description: Some(
+                "Draft a related-work section for a paper from its references and similar papers it may have missed"
+                    .into(),
+            ),
+            arguments: Some(vec![
+                PromptArgument {
+                    name: "paper_id".into(),
+                    description: Some(
+                        "The draft or seed paper: a Semantic Scholar ID, DOI:doi, ARXIV:id or another supported identifier"
+                            .into(),
+                    ),
+                    required: Some(true),
+                },
+                PromptArgument {
+                    name: "focus".into(),
+                    description: Some(
+                        "What the related-work section should emphasise, such as a method or application"
+                            .into(),
+                    ),
+                    required: Some(false),
+                },
+                PromptArgument {
+                    name: "max_papers".into(),
+                    description: Some(format!(
+                        "How many references and recommendations to attach. Default: {}",
+                        DEFAULT_MAX_PAPERS
+                    )),
+                    required: Some(false),
+                },
+            ]),
+        }
+    }
+}
@@ -0,0 +1,377 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use cache::Cache;
+use context_server::{Resource, ResourceContent, ResourceExecutor, ResourceTemplate};
+use http_client::HttpClient;
+use serde_json::{Value, json};
+
+use crate::{
+    error::invalid_argument,
+    format::escape_markdown,
+    utils::{
+        RateLimiter, encode_path_segment, make_request, normalize_author_id, normalize_paper_id,
+    },
+};
+
+const PAPER_SCHEME: &str = "paper://";
+const AUTHOR_SCHEME: &str = "author://";
+
+/// How many recently seen papers are listed.
+const RECENT_PAPERS_LIMIT: usize = 100;
+
+/// The metadata a paper resource carries. Papers seen in search results lack
+/// most of it and are fetched again in full the first time they are read.
+const RESOURCE_FIELDS: &[&str] = &[
+    "paperId",
+    "externalIds",
+    "url",
+    "title",
+    "abstract",
+    "venue",
+    "publicationVenue",
+    "year",
+    "referenceCount",
+    "citationCount",
+    "influentialCitationCount",
+    "isOpenAccess",
+    "openAccessPdf",
+    "fieldsOfStudy",
+    "s2FieldsOfStudy",
+    "publicationTypes",
+    "publicationDate",
+    "journal",
+    "authors",
+    "tldr",
+];
+
+/// The profile fields fetched for authors that were never seen in full.
+const AUTHOR_FIELDS: &str =
+    "authorId,name,url,affiliations,homepage,paperCount,citationCount,hIndex";
+
+/// The paper fields fetched for authors without any known papers.
+const AUTHOR_PAPER_FIELDS: &str = "paperId,title,year,venue,citationCount";
+
+/// Serves the papers seen in API responses as `paper://{paperId}` resources,
+/// so clients can attach them as context without calling a tool again.
+pub struct PaperResources {
+    http_client: Arc<dyn HttpClient>,
+    rate_limiter: Arc<RateLimiter>,
+    cache: Arc<dyn Cache>,
+}
+
+impl PaperResources {
+    pub fn new(
+        http_client: Arc<dyn HttpClient>,
+        rate_limiter: Arc<RateLimiter>,
+        cache: Arc<dyn Cache>,
+    ) -> Self {
+        Self {
+            http_client,
+            rate_limiter,
+            cache,
+        }
+    }
+
+    /// The stored paper if it has every resource field, fetching and storing
+    /// the full record otherwise.
+    async fn paper(&self, paper_id: &str) -> Result<Value> {
+        if let Some(entry) = self.cache.get_paper(paper_id)? {
+            if RESOURCE_FIELDS
+                .iter()
+                .all(|field| entry.value.get(field).is_some())
+            {
+                return Ok(entry.value);
+            }
+        }
+
+        let endpoint = format!("/paper/{}", encode_path_segment(paper_id));
+        let params = json!({ "fields": RESOURCE_FIELDS.join(",") });
+
+        // make_request records the response, merging it with what was stored
+        let paper = make_request(
+            &self.http_client,
+            &self.rate_limiter,
+            Some(self.cache.as_ref()),
+            &endpoint,
+            Some(&params),
+            None,
+            None,
+        )
+        .await?;
+
+        Ok(paper)
+    }
+}
+
+fn paper_resource(paper: &Value) -> Option<Resource> {
+    let paper_id = paper.get("paperId")?.as_str()?;
+    let title = paper
+        .get("title")
+        .and_then(Value::as_str)
+        .unwrap_or("Unknown title");
+
+    let authors: Vec<&str> = paper
+        .get("authors")
+        .and_then(Value::as_array)
+        .map(|authors| {
+            authors
+                .iter()
+                .filter_map(|author| author.get("name").and_then(Value::as_str))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut description = match authors.as_slice() {
+        [] => String::new(),
+        [author] => author.to_string(),
+        [author, ..] => format!("{} et al.", author),
+    };
+    if let Some(year) = paper.get("year").and_then(Value::as_u64) {
+        if !description.is_empty() {
+            description.push_str(", ");
+        }
+        description.push_str(&year.to_string());
+    }
+
+    Some(Resource {
+        uri: format!("{}{}", PAPER_SCHEME, paper_id),
+        name: title.to_string(),
+        description: Some(description).filter(|description| !description.is_empty()),
+        mime_type: Some("application/json".into()),
+    })
+}
+
+#[async_trait]
+impl ResourceExecutor for PaperResources {
+    async fn list(&self) -> Result<Vec<Resource>> {
+        tracing::debug!("Listing paper resources");
+        let papers = self.cache.recent_papers(RECENT_PAPERS_LIMIT)?;
+
+        Ok(papers
+            .iter()
+            .filter_map(|entry| paper_resource(&entry.value))
+            .collect())
+    }
+
+    fn templates(&self) -> Vec<ResourceTemplate> {
+        vec![ResourceTemplate {
+            uri_template: format!("{}{{paperId}}", PAPER_SCHEME),
+            name: "Paper".into(),
+            description: Some(
+                "Full metadata of a paper by Semantic Scholar ID, DOI:doi, ARXIV:id or another supported identifier"
+                    .into(),
+            ),
+            mime_type: Some("application/json".into()),
+        }]
+    }
+
+    async fn read(&self, uri: &str) -> Result<Option<ResourceContent>> {
+        let Some(paper_id) = uri.strip_prefix(PAPER_SCHEME) else {
+            return Ok(None);
+        };
+        tracing::debug!("Reading paper resource {}", paper_id);
+
+        let paper_id = normalize_paper_id(paper_id);
+        if paper_id.is_empty() {
+            return Err(invalid_argument("paper:// resources need a paper ID"));
+        }
+
+        let paper = self.paper(&paper_id).await?;
+
+        Ok(Some(ResourceContent {
+            uri: uri.to_string(),
+            mime_type: Some("application/json".into()),
+            text: Some(serde_json::to_string_pretty(&paper)?),
+            blob: None,
+        }))
+    }
+}
+
+/// Resolves `author://{authorId}` into a markdown profile built from the
+/// author and papers remembered from `author_details` and `author_papers`
+/// responses. Whatever was never seen is fetched once and remembered.
+pub struct AuthorResources {
+    http_client: Arc<dyn HttpClient>,
+    rate_limiter: Arc<RateLimiter>,
+    cache: Arc<dyn Cache>,
+}
+
+impl AuthorResources {
+    pub fn new(
+        http_client: Arc<dyn HttpClient>,
+        rate_limiter: Arc<RateLimiter>,
+        cache: Arc<dyn Cache>,
+    ) -> Self {
+        Self {
+            http_client,
+            rate_limiter,
+            cache,
+        }
+    }
+
+    /// The stored author, fetching the profile if only their name was seen.
+    async fn author(&self, author_id: &str) -> Result<Value> {
+        if let Some(entry) = self.cache.get_author(author_id)? {
+            if entry.value.get("paperCount").is_some() {
+                return Ok(entry.value);
+            }
+        }
+
+        let endpoint = format!("/author/{}", encode_path_segment(author_id));
+        let params = json!({ "fields": AUTHOR_FIELDS });
+        make_request(
+            &self.http_client,
+            &self.rate_limiter,
+            Some(self.cache.as_ref()),
+            &endpoint,
+            Some(&params),
+            None,
+            None,
+        )
+        .await
+    }
+
+    /// The author's stored papers, most cited first.
+    async fn papers(&self, author_id: &str) -> Result<Vec<Value>> {
+        if self.cache.get_author_papers(author_id)?.is_empty() {
+            let endpoint = format!("/author/{}/papers", encode_path_segment(author_id));
+            let params = json!({ "fields": AUTHOR_PAPER_FIELDS, "limit": 100 });
+            make_request(
+                &self.http_client,
+                &self.rate_limiter,
+                Some(self.cache.as_ref()),
+                &endpoint,
+                Some(&params),
+                None,
+                None,
+            )
+            .await?;
+        }
+
+        let mut papers = Vec::new();
+        for paper_id in self.cache.get_author_papers(author_id)? {
+            if let Some(entry) = self.cache.get_paper(&paper_id)? {
+                papers.push(entry.value);
+            }
+        }
+
+        let citations = |paper: &Value| paper.get("citationCount").and_then(Value::as_u64);
+        papers.sort_by(|a, b| citations(b).cmp(&citations(a)));
+        Ok(papers)
+    }
+}
+
+fn author_profile(author: &Value, papers: &[Value]) -> String {
+    let text = |field: &str| author.get(field).and_then(Value::as_str);
+    let count = |field: &str| author.get(field).and_then(Value::as_u64);
+
+    let mut profile = format!(
+        "# {}\n\n",
+        escape_markdown(text("name").unwrap_or("Unknown author"))
+    );
+
+    if let Some(author_id) = text("authorId") {
+        profile.push_str(&format!("- Author ID: {}\n", author_id));
+    }
+
+    let affiliations: Vec<&str> = author
+        .get("affiliations")
+        .and_then(Value::as_array)
+        .map(|affiliations| affiliations.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    if !affiliations.is_empty() {
+        profile.push_str(&format!(
+            "- Affiliations: {}\n",
+            escape_markdown(&affiliations.join(", "))
+        ));
+    }
+
+    for (label, field) in [
+        ("Papers", "paperCount"),
+        ("Citations", "citationCount"),
+        ("h-index", "hIndex"),
+    ] {
+        if let Some(value) = count(field) {
+            profile.push_str(&format!("- {}: {}\n", label, value));
+        }
+    }
+
+    if let Some(homepage) = text("homepage") {
+        profile.push_str(&format!("- Homepage: <{}>\n", homepage));
+    }
+    if let Some(url) = text("url") {
+        profile.push_str(&format!("- Semantic Scholar: <{}>\n", url));
+    }
+
+    if !papers.is_empty() {
+        profile.push_str("\n## Papers\n\n");
+        for paper in papers {
+            let paper_text = |field: &str| paper.get(field).and_then(Value::as_str);
+            let mut line = format!(
+                "- {}",
+                escape_markdown(paper_text("title").unwrap_or("Unknown title"))
+            );
+            if let Some(year) = paper.get("year").and_then(Value::as_u64) {
+                line.push_str(&format!(" ({})", year));
+            }
+            if let Some(venue) = paper_text("venue").filter(|venue| !venue.is_empty()) {
+                line.push_str(&format!(", {}", escape_markdown(venue)));
+            }
+            if let Some(citations) = paper.get("citationCount").and_then(Value::as_u64) {
+                line.push_str(&format!(", {} citations", citations));
+            }
+            if let Some(paper_id) = paper_text("paperId") {
+                line.push_str(&format!(" [{}{}]", PAPER_SCHEME, paper_id));
+            }
+            profile.push_str(&line);
+            profile.push('\n');
+        }
+    }
+
+    profile
+}
+
+#[async_trait]
+impl ResourceExecutor for AuthorResources {
+    async fn list(&self) -> Result<Vec<Resource>> {
+        // Authors are only reachable through the template
+        Ok(Vec::new())
+    }
+
+    fn templates(&self) -> Vec<ResourceTemplate> {
+        vec![ResourceTemplate {
+            uri_template: format!("{}{{authorId}}", AUTHOR_SCHEME),
+            name: "Author".into(),
+            description: Some(
+                "Profile of an author by Semantic Scholar author ID, with their affiliations, citation metrics and known papers"
+                    .into(),
+            ),
+            mime_type: Some("text/markdown".into()),
+        }]
+    }
+
+    async fn read(&self, uri: &str) -> Result<Option<ResourceContent>> {
+        let Some(author_id) = uri.strip_prefix(AUTHOR_SCHEME) else {
+            return Ok(None);
+        };
+        tracing::debug!("Reading author resource {}", author_id);
+
+        let author_id = normalize_author_id(author_id);
+        let author_id = author_id.as_str();
+        if author_id.is_empty() {
+            return Err(invalid_argument("author:// resources need an author ID"));
+        }
+
+        let author = self.author(author_id).await?;
+        let papers = self.papers(author_id).await?;
+
+        Ok(Some(ResourceContent {
+            uri: uri.to_string(),
+            mime_type: Some("text/markdown".into()),
+            text: Some(author_profile(&author, &papers)),
+            blob: None,
+        }))
+    }
+}
@@ -0,0 +1,189 @@
+use anyhow::Result;
+use models::{Author, Page, Paper};
+
+use crate::{format::Formatter, utils::truncate_text};
+
+/// Settings of a single tool call that change how its results are written.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RenderOptions {
+    /// Truncate abstracts to this many characters
+    pub abstract_length: Option<usize>,
+}
+
+/// Lays out the text and markdown output of one tool, given the results it
+/// fetched. Entries for papers and authors are written through the
+/// [`EntryRenderer`], so the formatter only adds what is particular to the
+/// tool, such as headings, tags and pagination notes.
+pub trait ResultFormatter<T>: Send + Sync {
+    fn format(
+        &self,
+        f: &mut dyn Formatter,
+        renderer: &dyn EntryRenderer,
+        result: &T,
+        options: &RenderOptions,
+    ) -> Result<()>;
+}
+
+/// Writes the entries for papers and authors listed by every tool, so a
+/// change to how they are presented is made in one place.
+pub trait EntryRenderer: Send + Sync {
+    /// The title of a paper's entry.
+    fn paper_title(&self, paper: &Paper) -> String;
+    /// The fields of a paper's entry, after it was started with its title.
+    fn paper(&self, f: &mut dyn Formatter, paper: &Paper, options: &RenderOptions);
+    /// A paper summarized on a single line, for lists within an entry.
+    fn paper_line(&self, paper: &Paper) -> String;
+    /// The title of an author's entry.
+    fn author_title(&self, author: &Author) -> String;
+    /// The fields of an author's entry, after it was started with its title.
+    fn author(&self, f: &mut dyn Formatter, author: &Author);
+    /// A labelled URL. Formatters write links through this, so renderers can
+    /// leave them out.
+    fn link(&self, f: &mut dyn Formatter, label: &str, url: &str);
+}
+
+/// The renderer tools use unless given another.
+#[derive(Debug, Clone)]
+pub struct DefaultEntryRenderer {
+    show_urls: bool,
+    abstract_length: Option<usize>,
+}
+
+impl DefaultEntryRenderer {
+    pub fn new() -> Self {
+        Self {
+            show_urls: true,
+            abstract_length: None,
+        }
+    }
+
+    /// Leaves every URL out of the output.
+    pub fn without_urls(mut self) -> Self {
+        self.show_urls = false;
+        self
+    }
+
+    /// Truncates abstracts to this many characters when a call doesn't ask
+    /// for a length of its own.
+    pub fn with_abstract_length(mut self, abstract_length: usize) -> Self {
+        self.abstract_length = Some(abstract_length);
+        self
+    }
+}
+
+impl Default for DefaultEntryRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EntryRenderer for DefaultEntryRenderer {
+    fn paper_title(&self, paper: &Paper) -> String {
+        format!(
+            "{} (ID: {})",
+            paper.title.as_deref().unwrap_or("Unknown title"),
+            paper.paper_id.as_deref().unwrap_or("Unknown ID")
+        )
+    }
+
+    fn paper(&self, f: &mut dyn Formatter, paper: &Paper, options: &RenderOptions) {
+        let author_names = paper.author_names();
+        if !author_names.is_empty() {
+            f.field("Authors", &author_names.join(", "));
+        }
+
+        if let Some(year) = paper.year {
+            f.field("Year", &year.to_string());
+        }
+
+        if let Some(venue) = paper.venue() {
+            f.field("Venue", venue);
+        }
+
+        if let Some(citation_count) = paper.citation_count {
+            f.field("Citations", &citation_count.to_string());
+        }
+
+        if let Some(abstract_text) = paper.abstract_text() {
+            let abstract_length = options.abstract_length.or(self.abstract_length);
+            f.field("Abstract", &truncate_text(abstract_text, abstract_length));
+        }
+
+        if let Some(url) = &paper.url {
+            self.link(f, "URL", url);
+        }
+    }
+
+    fn paper_line(&self, paper: &Paper) -> String {
+        let mut line = self.paper_title(paper);
+        if let Some(year) = paper.year {
+            line.push_str(&format!(", {}", year));
+        }
+        if let Some(venue) = paper.venue() {
+            line.push_str(&format!(", {}", venue));
+        }
+        line
+    }
+
+    fn author_title(&self, author: &Author) -> String {
+        format!(
+            "{} (ID: {})",
+            author.name.as_deref().unwrap_or("Unknown name"),
+            author.author_id.as_deref().unwrap_or("Unknown ID")
+        )
+    }
+
+    fn author(&self, f: &mut dyn Formatter, author: &Author) {
+        if let Some(affiliations) = &author.affiliations {
+            if !affiliations.is_empty() {
+                f.field("Affiliations", &affiliations.join(", "));
+            }
+        }
+
+        if let Some(aliases) = &author.aliases {
+            if !aliases.is_empty() {
+                f.field("Also known as", &aliases.join(", "));
+            }
+        }
+
+        if let Some(paper_count) = author.paper_count {
+            f.field("Papers", &paper_count.to_string());
+        }
+
+        if let Some(citation_count) = author.citation_count {
+            f.field("Citations", &citation_count.to_string());
+        }
+
+        if let Some(h_index) = author.h_index {
+            f.field("h-index", &h_index.to_string());
+        }
+
+        if let Some(homepage) = author.homepage() {
+            self.link(f, "Homepage", homepage);
+        }
+
+        if let Some(url) = &author.url {
+            self.link(f, "Semantic Scholar URL", url);
+        }
+    }
+
+    fn link(&self, f: &mut dyn Formatter, label: &str, url: &str) {
+        if self.show_urls {
+            f.link(label, url);
+        }
+    }
+}
+
+/// A page of results some of which a tool's filters hid.
+pub struct FilteredPage<T> {
+    pub page: Page<T>,
+    /// How many results of the page were hidden
+    pub hidden: usize,
+}
+
+/// The results of a batch lookup, in the order they were requested.
+pub struct BatchResults<T> {
+    pub requested: Vec<String>,
+    /// One per requested ID, `None` for the ones that didn't resolve
+    pub results: Vec<Option<T>>,
+}
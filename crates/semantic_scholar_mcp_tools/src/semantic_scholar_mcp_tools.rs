@@ -1,15 +1,126 @@
+mod api_status;
+mod arguments;
+mod author_batch;
 mod author_details;
 mod author_papers;
 mod author_references;
 mod author_search;
+mod cache_clear;
+mod citation_graph;
+mod citation_path;
+mod citation_trend;
+mod completion;
+mod content;
+mod datasets;
+mod error;
+mod export;
+mod export_ris;
+mod fields_of_study;
+mod format;
+mod format_citation;
+mod graph_expand;
+mod graph_export;
+mod graph_rank;
+mod library_collection;
+mod library_export;
+mod library_import;
+mod logging;
+mod markdown_bibliography;
+mod memoized_embed;
+mod notifier;
+mod open_access_pdf;
+mod output_limit;
+mod pagination;
+mod paper_batch;
 mod paper_citations;
 mod paper_details;
 mod paper_recommendation;
+mod paper_references;
 mod paper_search;
+mod paper_similarity_rank;
+mod paper_title_match;
+mod peer_review;
+mod progress;
+mod prompt;
+mod query_cache;
+mod related_work;
+mod resources;
+mod result_formatter;
+mod session;
+mod settings;
+mod snowball;
+mod sync;
+mod table;
+mod tool_groups;
+mod topic_recommendation;
 mod utils;
+mod venue_details;
+mod zotero;
+
+pub use semantic_scholar_client::{
+    ClientSettings, SemanticScholarClient, is_offline, set_audit_log, track_cache_age,
+    with_client_settings,
+};
 
 pub use crate::{
-    author_details::*, author_papers::*, author_references::*, author_search::*,
-    paper_citations::*, paper_details::*, paper_recommendation::*, paper_search::*,
-    utils::RateLimiter,
+    api_status::*,
+    arguments::{AuthorField, PaperField},
+    author_batch::*,
+    author_details::*,
+    author_papers::*,
+    author_references::*,
+    author_search::*,
+    cache_clear::*,
+    citation_graph::{
+        CitationGraph, GraphDirection, GraphEdge, GraphNode, GraphWalker, WalkOptions,
+    },
+    citation_path::*,
+    citation_trend::*,
+    completion::Completions,
+    datasets::*,
+    error::{Error, rpc_error},
+    export_ris::*,
+    fields_of_study::*,
+    format::{Formatter, OutputFormat},
+    format_citation::*,
+    graph_expand::*,
+    graph_export::*,
+    graph_rank::*,
+    library_collection::*,
+    library_export::*,
+    library_import::*,
+    logging::{McpLayer, set_client_level, set_stderr_level, show_on_stderr},
+    markdown_bibliography::*,
+    memoized_embed::MemoizedEmbed,
+    notifier::{Notifier, with_notifier},
+    open_access_pdf::*,
+    paper_batch::*,
+    paper_citations::*,
+    paper_details::*,
+    paper_recommendation::*,
+    paper_references::*,
+    paper_search::*,
+    paper_similarity_rank::*,
+    paper_title_match::*,
+    peer_review::*,
+    progress::Progress,
+    query_cache::{SimilaritySettings, ToolSimilaritySettings},
+    related_work::*,
+    resources::*,
+    result_formatter::{
+        BatchResults, DefaultEntryRenderer, EntryRenderer, FilteredPage, RenderOptions,
+        ResultFormatter,
+    },
+    session::Session,
+    settings::{ServerSettings, with_settings},
+    snowball::*,
+    sync::*,
+    tool_groups::{EMBEDDINGS, RECOMMENDATIONS, ToolGroups},
+    topic_recommendation::*,
+    utils::{
+        CircuitBreaker, ClassRateLimitSettings, RateClass, RateLimit, RateLimitSettings,
+        RateLimiter, RequestStats, RequestStatsSnapshot, api_base_url,
+    },
+    venue_details::*,
+    zotero::*,
 };
@@ -0,0 +1,107 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+use futures::channel::mpsc::UnboundedSender;
+use serde_json::{Value, json};
+use tracing::level_filters::LevelFilter;
+
+use crate::logging::{level_filter, level_index};
+
+/// How many paper and author IDs a session remembers.
+const SEEN_LIMIT: usize = 1000;
+
+/// The state of one connected client. Clients share the cache, the rate
+/// limiter and the tools, but each has its own log level and remembers the
+/// papers and authors its requests returned.
+pub struct Session {
+    id: String,
+    /// Where notifications outside of any request go, for transports that
+    /// keep a stream open to the client.
+    outgoing: Option<UnboundedSender<Value>>,
+    log_level: AtomicUsize,
+    papers: Mutex<VecDeque<String>>,
+    authors: Mutex<VecDeque<String>>,
+}
+
+impl Session {
+    pub fn new(id: impl Into<String>, outgoing: Option<UnboundedSender<Value>>) -> Self {
+        Self {
+            id: id.into(),
+            outgoing,
+            log_level: AtomicUsize::new(level_index(LevelFilter::INFO)),
+            papers: Mutex::new(VecDeque::new()),
+            authors: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Sends a notification outside of any request. Returns false when the
+    /// client can't be reached.
+    pub fn notify(&self, method: &str, params: Value) -> bool {
+        let Some(outgoing) = &self.outgoing else {
+            return false;
+        };
+
+        outgoing
+            .unbounded_send(json!({
+                "jsonrpc": "2.0",
+                "method": method,
+                "params": params,
+            }))
+            .is_ok()
+    }
+
+    /// The least severe level of the log messages sent to the client.
+    pub fn log_level(&self) -> LevelFilter {
+        level_filter(self.log_level.load(Ordering::Relaxed))
+    }
+
+    pub fn set_log_level(&self, level: LevelFilter) {
+        self.log_level.store(level_index(level), Ordering::Relaxed);
+    }
+
+    /// Remembers the papers and authors in a response, most recent last.
+    pub fn remember(&self, paper_ids: &[&str], author_ids: &[&str]) {
+        remember(&self.papers, paper_ids);
+        remember(&self.authors, author_ids);
+    }
+
+    /// IDs of the papers this session has seen, newest first.
+    pub fn seen_papers(&self) -> Vec<String> {
+        seen(&self.papers)
+    }
+
+    /// IDs of the authors this session has seen, newest first.
+    pub fn seen_authors(&self) -> Vec<String> {
+        seen(&self.authors)
+    }
+}
+
+fn remember(ids: &Mutex<VecDeque<String>>, new_ids: &[&str]) {
+    let Ok(mut ids) = ids.lock() else {
+        return;
+    };
+
+    for id in new_ids {
+        ids.retain(|known| known != id);
+        ids.push_back(id.to_string());
+    }
+
+    while ids.len() > SEEN_LIMIT {
+        ids.pop_front();
+    }
+}
+
+fn seen(ids: &Mutex<VecDeque<String>>) -> Vec<String> {
+    ids.lock()
+        .map(|ids| ids.iter().rev().cloned().collect())
+        .unwrap_or_default()
+}
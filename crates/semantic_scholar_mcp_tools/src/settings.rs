@@ -0,0 +1,103 @@
+use std::{future::Future, sync::Arc, time::Duration};
+
+use semantic_scholar_client::{ClientSettings, with_client_settings};
+
+use crate::{
+    arguments::{AuthorField, PaperField},
+    query_cache::SimilaritySettings,
+};
+
+/// What a server's tools are configured with: how their requests are made,
+/// the defaults of calls that leave them out, and how cached queries are
+/// matched. Applied to a request handler with [`with_settings`], so servers
+/// sharing a process can each have their own.
+#[derive(Debug, Clone, Default)]
+pub struct ServerSettings {
+    client: Arc<ClientSettings>,
+    pub(crate) paper_fields: Option<Vec<PaperField>>,
+    pub(crate) author_fields: Option<Vec<AuthorField>>,
+    pub(crate) max_output_chars: Option<usize>,
+    similarity: SimilaritySettings,
+}
+
+impl ServerSettings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Points every request at another host serving the Semantic Scholar
+    /// API, such as a caching proxy.
+    pub fn with_api_base_url(self, url: &str) -> Self {
+        self.update_client(|client| client.with_api_base_url(url))
+    }
+
+    /// Answers from the cache alone, without sending anything to Semantic
+    /// Scholar.
+    pub fn with_offline(self, offline: bool) -> Self {
+        self.update_client(|client| client.with_offline(offline))
+    }
+
+    /// Sets how long requests that found nothing are answered from the
+    /// cache. Zero turns negative caching off.
+    pub fn with_negative_cache_ttl(self, ttl: Duration) -> Self {
+        self.update_client(|client| client.with_negative_cache_ttl(ttl))
+    }
+
+    /// Replaces the fields that paper listings return when a call names
+    /// none.
+    pub fn with_default_paper_fields(mut self, fields: Vec<PaperField>) -> Self {
+        self.paper_fields = Some(fields);
+        self
+    }
+
+    /// Replaces the fields that author listings return when a call names
+    /// none.
+    pub fn with_default_author_fields(mut self, fields: Vec<AuthorField>) -> Self {
+        self.author_fields = Some(fields);
+        self
+    }
+
+    /// Limits the output of every tool call that doesn't set
+    /// `max_output_chars` itself.
+    pub fn with_max_output_chars(mut self, max_chars: usize) -> Self {
+        self.max_output_chars = Some(max_chars);
+        self
+    }
+
+    pub fn with_similarity(mut self, similarity: SimilaritySettings) -> Self {
+        self.similarity = similarity;
+        self
+    }
+
+    fn update_client(mut self, update: impl FnOnce(ClientSettings) -> ClientSettings) -> Self {
+        self.client = Arc::new(update(ClientSettings::clone(&self.client)));
+        self
+    }
+
+    pub fn client(&self) -> &ClientSettings {
+        &self.client
+    }
+
+    pub fn similarity(&self) -> &SimilaritySettings {
+        &self.similarity
+    }
+}
+
+tokio::task_local! {
+    static SETTINGS: Arc<ServerSettings>;
+}
+
+/// Runs a request handler with `settings` applying to the tools it calls and
+/// the requests they make. Outside of it, the defaults apply.
+pub async fn with_settings<F: Future>(settings: Arc<ServerSettings>, handler: F) -> F::Output {
+    let client = settings.client.clone();
+    with_client_settings(client, SETTINGS.scope(settings, handler)).await
+}
+
+/// Reads the settings of the current request.
+pub(crate) fn current<T>(read: impl FnOnce(&ServerSettings) -> T) -> T {
+    match SETTINGS.try_with(Arc::clone) {
+        Ok(settings) => read(&settings),
+        Err(_) => read(&ServerSettings::default()),
+    }
+}
@@ -0,0 +1,451 @@
+use std::{collections::HashSet, sync::Arc};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use cache::Cache;
+use context_server::{Tool, ToolContent, ToolExecutor};
+use embed::Embed;
+use http_client::HttpClient;
+use models::Paper;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::{
+    arguments::{PaperField, input_schema, parse_arguments},
+    citation_graph::{GraphDirection, GraphNode, GraphWalker, most_cited, seed_ids},
+    content::ToolOutput,
+    error::invalid_argument,
+    format::{Formatter, OutputFormat, render},
+    progress::Progress,
+    query_cache::{CacheKey, CacheLookup, cached_response, store_response},
+    result_formatter::{DefaultEntryRenderer, EntryRenderer, RenderOptions, ResultFormatter},
+    utils::RateLimiter,
+};
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct SnowballArguments {
+    /// The seed set, in any of the formats paper_details accepts. Maximum: 20
+    paper_ids: Vec<String>,
+    /// 'citations' for forward snowballing (papers citing the included ones), 'references' for backward snowballing (papers they cite) or 'both'. Default: both
+    direction: Option<GraphDirection>,
+    /// Number of rounds, each snowballing from the papers the previous one included. Default: 1, Maximum: 3
+    #[schemars(range(min = 1, max = 3))]
+    rounds: Option<u32>,
+    /// Maximum number of citations/references to fetch per paper. Default: 100, Maximum: 1000
+    #[schemars(range(min = 1, max = 1000))]
+    per_paper_limit: Option<u64>,
+    /// Maximum number of papers to include at each round, the most cited of those passing the filters first. Default: 100, Maximum: 1000
+    #[schemars(range(min = 1, max = 1000))]
+    per_round_limit: Option<u64>,
+    /// Only include papers published in these years. Formats: '2019', '2016-2020', '2010-', '-2015'
+    year: Option<String>,
+    /// Only include papers whose venue contains one of these names, ignoring case
+    venue: Option<Vec<String>>,
+    /// Only include papers whose title or abstract contains one of these keywords, ignoring case
+    keywords: Option<Vec<String>>,
+    /// Fields to return for each included paper, besides its ID, title, citation count, year and venue. Default: authors
+    fields: Option<Vec<PaperField>>,
+    /// Maximum length of the output in characters. Results past it are left out, with a note saying how many. Default: the server's limit, if any
+    #[schemars(range(min = 1))]
+    max_output_chars: Option<usize>,
+    #[serde(default)]
+    output_format: OutputFormat,
+}
+
+/// A range of publication years, either end open.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct YearRange {
+    from: Option<u64>,
+    to: Option<u64>,
+}
+
+impl YearRange {
+    /// Parses the year formats the search endpoints accept: '2019',
+    /// '2016-2020', '2010-' and '-2015'.
+    fn parse(year: &str) -> Result<Self> {
+        let invalid = || {
+            invalid_argument(format!(
+                "Invalid year '{}'. Formats: '2019', '2016-2020', '2010-', '-2015'",
+                year
+            ))
+        };
+        let bound = |s: &str| -> Result<Option<u64>> {
+            let s = s.trim();
+            if s.is_empty() {
+                Ok(None)
+            } else {
+                s.parse().map(Some).map_err(|_| invalid())
+            }
+        };
+
+        let range = match year.split_once('-') {
+            Some((from, to)) => Self {
+                from: bound(from)?,
+                to: bound(to)?,
+            },
+            None => {
+                let year = bound(year)?.ok_or_else(invalid)?;
+                Self {
+                    from: Some(year),
+                    to: Some(year),
+                }
+            }
+        };
+        if range.from.is_none() && range.to.is_none() {
+            return Err(invalid());
+        }
+        Ok(range)
+    }
+
+    fn contains(&self, year: u64) -> bool {
+        self.from.is_none_or(|from| year >= from) && self.to.is_none_or(|to| year <= to)
+    }
+}
+
+/// The screening criteria papers must meet to be included.
+struct Screen {
+    year: Option<YearRange>,
+    venues: Vec<String>,
+    keywords: Vec<String>,
+}
+
+impl Screen {
+    fn in_years(&self, node: &GraphNode) -> bool {
+        match self.year {
+            // Papers without a year can't be shown to be in range
+            Some(range) => field_u64(node, "year").is_some_and(|year| range.contains(year)),
+            None => true,
+        }
+    }
+
+    fn in_venues(&self, node: &GraphNode) -> bool {
+        if self.venues.is_empty() {
+            return true;
+        }
+        let venue = field_str(node, "venue").to_lowercase();
+        self.venues.iter().any(|name| venue.contains(name))
+    }
+
+    fn matches_keywords(&self, node: &GraphNode) -> bool {
+        if self.keywords.is_empty() {
+            return true;
+        }
+        let text = format!("{} {}", node.title(), field_str(node, "abstract")).to_lowercase();
+        self.keywords.iter().any(|keyword| text.contains(keyword))
+    }
+}
+
+fn field_u64(node: &GraphNode, key: &str) -> Option<u64> {
+    node.fields.get(key).and_then(Value::as_u64)
+}
+
+fn field_str<'a>(node: &'a GraphNode, key: &str) -> &'a str {
+    node.fields.get(key).and_then(Value::as_str).unwrap_or("")
+}
+
+/// How far snowballing goes, and what it includes.
+struct SnowballOptions {
+    direction: GraphDirection,
+    rounds: u32,
+    per_paper_limit: u64,
+    per_round_limit: usize,
+    screen: Screen,
+    /// Fields fetched for included papers, besides those screened on
+    fields: Vec<PaperField>,
+}
+
+/// How many papers made it through each stage of a round's screening.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScreeningFunnel {
+    pub round: u32,
+    /// Citations and references fetched
+    pub fetched: usize,
+    /// Papers not seen in an earlier round or earlier in this one
+    pub new: usize,
+    pub in_years: usize,
+    pub in_venues: usize,
+    pub matching_keywords: usize,
+    /// Papers passing every filter that were kept, up to the round's limit
+    pub included: usize,
+}
+
+/// The papers snowballing from a seed set included, round by round.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnowballResult {
+    pub seeds: Vec<String>,
+    pub funnel: Vec<ScreeningFunnel>,
+    /// The included papers, with the round that included them as their hop
+    pub papers: Vec<GraphNode>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unresolved: Vec<String>,
+}
+
+pub struct SnowballTool {
+    walker: GraphWalker,
+    cache: Arc<dyn Cache>,
+    embed: Arc<dyn Embed>,
+    renderer: Arc<dyn EntryRenderer>,
+    formatter: Arc<dyn ResultFormatter<SnowballResult>>,
+}
+
+impl SnowballTool {
+    pub fn new(
+        http_client: Arc<dyn HttpClient>,
+        rate_limiter: Arc<RateLimiter>,
+        cache: Arc<dyn Cache>,
+        embed: Arc<dyn Embed>,
+    ) -> Self {
+        Self {
+            walker: GraphWalker::new(http_client, rate_limiter, cache.clone()),
+            cache,
+            embed,
+            renderer: Arc::new(DefaultEntryRenderer::new()),
+            formatter: Arc::new(SnowballFormatter),
+        }
+    }
+
+    /// Writes the entries through `renderer` rather than the default.
+    pub fn with_renderer(mut self, renderer: Arc<dyn EntryRenderer>) -> Self {
+        self.renderer = renderer;
+        self
+    }
+
+    pub fn with_formatter(mut self, formatter: Arc<dyn ResultFormatter<SnowballResult>>) -> Self {
+        self.formatter = formatter;
+        self
+    }
+
+    /// Fetches the citations and/or references of the papers included so
+    /// far, round after round, screening the papers not seen before. Papers
+    /// screened out are remembered, so a later round doesn't screen them
+    /// again.
+    async fn snowball(
+        &self,
+        seeds: &[String],
+        options: &SnowballOptions,
+    ) -> Result<SnowballResult> {
+        let screen = &options.screen;
+        let mut screened = vec![PaperField::Year, PaperField::Venue];
+        if !screen.keywords.is_empty() {
+            screened.push(PaperField::Abstract);
+        }
+        screened.extend_from_slice(&options.fields);
+        let fields = GraphWalker::fields(&screened);
+
+        let mut result = SnowballResult::default();
+        let mut seen: HashSet<String> = HashSet::new();
+        for (seed, node) in seeds.iter().zip(self.walker.resolve(seeds, &fields).await?) {
+            match node {
+                Some(node) => {
+                    if seen.insert(node.paper_id.clone()) {
+                        result.seeds.push(node.paper_id);
+                    }
+                }
+                None => result.unresolved.push(seed.clone()),
+            }
+        }
+
+        let mut included = result.seeds.clone();
+        let progress = Progress::new(0);
+
+        for round in 1..=options.rounds {
+            if included.is_empty() {
+                break;
+            }
+
+            let links = self
+                .walker
+                .expand(
+                    &included,
+                    options.direction,
+                    options.per_paper_limit,
+                    &fields,
+                    round,
+                    &progress,
+                )
+                .await;
+
+            let mut funnel = ScreeningFunnel {
+                round,
+                fetched: links.len(),
+                ..Default::default()
+            };
+            let mut passed = Vec::new();
+            for link in links {
+                let paper = link.paper;
+                if !seen.insert(paper.paper_id.clone()) {
+                    continue;
+                }
+                funnel.new += 1;
+                if !screen.in_years(&paper) {
+                    continue;
+                }
+                funnel.in_years += 1;
+                if !screen.in_venues(&paper) {
+                    continue;
+                }
+                funnel.in_venues += 1;
+                if !screen.matches_keywords(&paper) {
+                    continue;
+                }
+                funnel.matching_keywords += 1;
+                passed.push(paper);
+            }
+
+            let passed = most_cited(passed, options.per_round_limit);
+            funnel.included = passed.len();
+            included = passed.iter().map(|node| node.paper_id.clone()).collect();
+            result.papers.extend(passed);
+            result.funnel.push(funnel);
+        }
+
+        Ok(result)
+    }
+}
+
+/// Lays out the output of `snowball_search`: the screening funnel and the
+/// papers included at each round.
+pub struct SnowballFormatter;
+
+impl ResultFormatter<SnowballResult> for SnowballFormatter {
+    fn format(
+        &self,
+        f: &mut dyn Formatter,
+        renderer: &dyn EntryRenderer,
+        result: &SnowballResult,
+        options: &RenderOptions,
+    ) -> Result<()> {
+        if result.seeds.is_empty() {
+            f.note("None of the given paper IDs could be resolved.");
+            return Ok(());
+        }
+
+        f.heading(&format!(
+            "Snowballing from {} seed paper(s) included {} papers in {} round(s):",
+            result.seeds.len(),
+            result.papers.len(),
+            result.funnel.len()
+        ));
+
+        let mut number = 0;
+        for funnel in &result.funnel {
+            f.heading(&format!(
+                "Round {}: {} fetched, {} new, {} in years, {} in venues, {} matching keywords, {} included",
+                funnel.round,
+                funnel.fetched,
+                funnel.new,
+                funnel.in_years,
+                funnel.in_venues,
+                funnel.matching_keywords,
+                funnel.included
+            ));
+
+            for node in result.papers.iter().filter(|node| node.hop == funnel.round) {
+                number += 1;
+                let paper: Paper = serde_json::from_value(node.paper())?;
+                f.item(number, &renderer.paper_title(&paper));
+                renderer.paper(f, &paper, options);
+            }
+        }
+
+        if !result.unresolved.is_empty() {
+            f.note(&format!(
+                "Could not resolve {} paper ID(s): {}",
+                result.unresolved.len(),
+                result.unresolved.join(", ")
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for SnowballTool {
+    async fn execute(&self, arguments: Option<Value>) -> Result<Vec<ToolContent>> {
+        tracing::debug!("Executing SnowballTool");
+        let args: SnowballArguments = parse_arguments(arguments)?;
+
+        let paper_ids = seed_ids(&args.paper_ids)?;
+
+        let lowercase = |values: Option<Vec<String>>| -> Vec<String> {
+            values
+                .unwrap_or_default()
+                .iter()
+                .map(|value| value.trim().to_lowercase())
+                .filter(|value| !value.is_empty())
+                .collect()
+        };
+        let options = SnowballOptions {
+            direction: args.direction.unwrap_or_default(),
+            rounds: args.rounds.unwrap_or(1),
+            per_paper_limit: args.per_paper_limit.unwrap_or(100),
+            per_round_limit: args.per_round_limit.unwrap_or(100) as usize,
+            screen: Screen {
+                year: args.year.as_deref().map(YearRange::parse).transpose()?,
+                venues: lowercase(args.venue),
+                keywords: lowercase(args.keywords),
+            },
+            fields: args.fields.unwrap_or_else(|| vec![PaperField::Authors]),
+        };
+
+        let params = json!({
+            "ids": paper_ids,
+            "direction": options.direction,
+            "rounds": options.rounds,
+            "per_paper_limit": options.per_paper_limit,
+            "per_round_limit": options.per_round_limit,
+            "year": options.screen.year,
+            "venue": options.screen.venues,
+            "keywords": options.screen.keywords,
+            "fields": options.fields,
+        });
+
+        let key = CacheKey::new("snowball_search", &paper_ids.join(",")).params(&params);
+        let response = match cached_response(self.cache.as_ref(), self.embed.as_ref(), &key).await?
+        {
+            CacheLookup::Hit(response) => response,
+            CacheLookup::Miss(embedding) => {
+                let result = self.snowball(&paper_ids, &options).await?;
+                let response = serde_json::to_value(&result)?;
+                store_response(self.cache.as_ref(), &key, embedding, &response);
+                response
+            }
+        };
+
+        let result: SnowballResult = serde_json::from_value(response.clone())?;
+        let formatted_result = render(
+            args.output_format,
+            &response,
+            None,
+            args.max_output_chars,
+            |f| {
+                self.formatter.format(
+                    f,
+                    self.renderer.as_ref(),
+                    &result,
+                    &RenderOptions::default(),
+                )
+            },
+        )?;
+
+        let output = ToolOutput::new(formatted_result, args.output_format, &response);
+
+        Ok(output.into_contents())
+    }
+
+    fn to_tool(&self) -> Tool {
+        Tool {
+            name: "snowball_search".into(),
+            description: Some(
+                "Snowball from a seed set of papers for a systematic review: collect their citations (forward) and/or references (backward) round after round, screen out duplicates and papers outside the given years, venues or keywords, and report how many papers passed each stage of every round"
+                    .into(),
+            ),
+            input_schema: input_schema::<SnowballArguments>(),
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use context_server::{Tool, ToolContent, ToolExecutor};
+use dataset_sync::{DatasetSync, SyncReport};
+use schemars::JsonSchema;
+use semantic_scholar_client::is_offline;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{
+    arguments::{NoArguments, input_schema, parse_arguments},
+    error::{Error, invalid_argument},
+};
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct SyncRunArguments {
+    /// Name of the dataset to sync, as listed by dataset_list (e.g. 'papers', 'authors', 'abstracts')
+    dataset: String,
+    /// Release to sync to, or 'latest'. Default: latest
+    release_id: Option<String>,
+    /// If true, import the dataset from a full release instead of applying diffs. Required the first time a dataset is synced
+    #[serde(default)]
+    bootstrap: bool,
+    /// When bootstrapping, only import the first N shards of the dataset
+    max_files: Option<u64>,
+}
+
+pub struct SyncStatusTool {
+    dataset_sync: Arc<DatasetSync>,
+}
+
+impl SyncStatusTool {
+    pub fn new(dataset_sync: Arc<DatasetSync>) -> Self {
+        Self { dataset_sync }
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for SyncStatusTool {
+    async fn execute(&self, _arguments: Option<Value>) -> Result<Vec<ToolContent>> {
+        tracing::debug!("Executing SyncStatusTool");
+        let states = self.dataset_sync.status()?;
+
+        if states.is_empty() {
+            return Ok(vec![ToolContent::Text {
+                text: String::from(
+                    "No datasets are synced locally. Use the sync_run tool with bootstrap=true to import one.",
+                ),
+            }]);
+        }
+
+        let mut result = format!("{} dataset(s) synced locally:\n\n", states.len());
+        for state in states {
+            result.push_str(&format!("- {}\n", state.dataset));
+            result.push_str(&format!("   Release: {}\n", state.release_id));
+            result.push_str(&format!("   Records: {}\n", state.records));
+            result.push_str(&format!("   Last synced: {}\n", state.updated_at));
+        }
+
+        Ok(vec![ToolContent::Text { text: result }])
+    }
+
+    fn to_tool(&self) -> Tool {
+        Tool {
+            name: "sync_status".into(),
+            description: Some(
+                "Show which Semantic Scholar datasets are mirrored locally and the release they are synced to"
+                    .into(),
+            ),
+            input_schema: input_schema::<NoArguments>(),
+        }
+    }
+}
+
+pub struct SyncRunTool {
+    dataset_sync: Arc<DatasetSync>,
+}
+
+impl SyncRunTool {
+    pub fn new(dataset_sync: Arc<DatasetSync>) -> Self {
+        Self { dataset_sync }
+    }
+
+    fn format_report(&self, report: &SyncReport) -> String {
+        let mut result = match &report.from_release {
+            Some(from_release) if from_release >= &report.to_release => {
+                return format!(
+                    "Dataset {} is already up to date at release {}.",
+                    report.dataset, from_release
+                );
+            }
+            Some(from_release) => format!(
+                "Synced dataset {} from release {} to {}.\n",
+                report.dataset, from_release, report.to_release
+            ),
+            None => format!(
+                "Bootstrapped dataset {} from release {}.\n",
+                report.dataset, report.to_release
+            ),
+        };
+
+        result.push_str(&format!("Files processed: {}\n", report.files_processed));
+        result.push_str(&format!("Records added: {}\n", report.upserted));
+        result.push_str(&format!("Records deleted: {}\n", report.deleted));
+
+        result
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for SyncRunTool {
+    async fn execute(&self, arguments: Option<Value>) -> Result<Vec<ToolContent>> {
+        tracing::debug!("Executing SyncRunTool");
+        let args: SyncRunArguments = parse_arguments(arguments)?;
+        let dataset = args.dataset.as_str();
+
+        if dataset.trim().is_empty() {
+            return Err(invalid_argument("Dataset name cannot be empty"));
+        }
+
+        if is_offline() {
+            return Err(Error::NotCached(format!(
+                "syncing {} downloads from the datasets API",
+                dataset
+            ))
+            .into());
+        }
+
+        let release_id = args.release_id.as_deref().unwrap_or("latest");
+
+        let report = if args.bootstrap {
+            let max_files = args.max_files.map(|v| v as usize);
+            self.dataset_sync
+                .bootstrap(dataset, release_id, max_files)
+                .await?
+        } else {
+            self.dataset_sync.sync(dataset, release_id).await?
+        };
+
+        Ok(vec![ToolContent::Text {
+            text: self.format_report(&report),
+        }])
+    }
+
+    fn to_tool(&self) -> Tool {
+        Tool {
+            name: "sync_run".into(),
+            description: Some(
+                "Import a Semantic Scholar dataset locally, or apply the incremental diffs needed to bring it up to a newer release (requires an API key)"
+                    .into(),
+            ),
+            input_schema: input_schema::<SyncRunArguments>(),
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use serde_json::{Map, Value};
+
+/// Lays out an API response as delimited rows, one per record, with a header
+/// row of column names. The records are the `data` of a page, the elements of
+/// a batch response, or the response itself for a single record.
+///
+/// Columns are field names, with nested fields reached through dotted paths
+/// such as `externalIds.DOI`. Without an explicit list, every top-level field
+/// found in the records becomes a column.
+///
+/// Rows stop being added before the table grows past `max_chars`
+/// characters; the table is returned with how many rows were left out.
+pub fn render_table(
+    response: &Value,
+    columns: Option<&[String]>,
+    delimiter: char,
+    max_chars: usize,
+) -> (String, usize) {
+    let rows: Vec<Map<String, Value>> = records(response).into_iter().map(flatten).collect();
+
+    let columns: Vec<String> = match columns {
+        Some(columns) => columns.to_vec(),
+        None => {
+            let mut columns: Vec<String> = Vec::new();
+            for row in &rows {
+                for key in row.keys() {
+                    if !columns.contains(key) {
+                        columns.push(key.clone());
+                    }
+                }
+            }
+            columns
+        }
+    };
+
+    let mut output = String::new();
+    write_row(&mut output, columns.iter().map(String::as_str), delimiter);
+    let mut written = output.chars().count();
+
+    let mut row_output = String::new();
+    for (i, row) in rows.iter().enumerate() {
+        let cells: Vec<String> = columns
+            .iter()
+            .map(|column| lookup(row, column).map(cell).unwrap_or_default())
+            .collect();
+        row_output.clear();
+        write_row(&mut row_output, cells.iter().map(String::as_str), delimiter);
+
+        let row_chars = row_output.chars().count();
+        if written + row_chars > max_chars {
+            return (output, rows.len() - i);
+        }
+        output.push_str(&row_output);
+        written += row_chars;
+    }
+
+    (output, 0)
+}
+
+fn records(response: &Value) -> Vec<&Value> {
+    match response.get("data").unwrap_or(response) {
+        Value::Array(values) => values.iter().filter(|value| !value.is_null()).collect(),
+        value => vec![value],
+    }
+}
+
+/// Citation and reference records wrap the paper in `citingPaper` or
+/// `citedPaper`; its fields are lifted next to the edge's own fields, such as
+/// `isInfluential` and `intents`, so both are addressed the same way.
+fn flatten(record: &Value) -> Map<String, Value> {
+    let Some(object) = record.as_object() else {
+        return Map::new();
+    };
+
+    let mut row = Map::new();
+    for (key, value) in object {
+        match (key.as_str(), value) {
+            ("citingPaper" | "citedPaper", Value::Object(paper)) => {
+                row.extend(
+                    paper
+                        .iter()
+                        .map(|(key, value)| (key.clone(), value.clone())),
+                );
+            }
+            _ => {
+                row.insert(key.clone(), value.clone());
+            }
+        }
+    }
+    row
+}
+
+fn lookup<'a>(row: &'a Map<String, Value>, column: &str) -> Option<&'a Value> {
+    let mut segments = column.split('.');
+    let mut value = row.get(segments.next()?)?;
+    for segment in segments {
+        value = value.get(segment)?;
+    }
+    Some(value)
+}
+
+/// The text of a cell. Lists are joined with semicolons, and authors and
+/// other named entries are listed by name.
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Array(values) => values
+            .iter()
+            .map(|value| match value.get("name").and_then(Value::as_str) {
+                Some(name) => name.to_string(),
+                None => cell(value),
+            })
+            .collect::<Vec<_>>()
+            .join("; "),
+        value => value.to_string(),
+    }
+}
+
+fn write_row<'a>(output: &mut String, cells: impl Iterator<Item = &'a str>, delimiter: char) {
+    let cells: Vec<String> = cells.map(|cell| escape(cell, delimiter)).collect();
+    output.push_str(&cells.join(&delimiter.to_string()));
+    output.push('\n');
+}
+
+/// Quotes CSV cells that contain the delimiter, quotes or line breaks. TSV has
+/// no quoting, so tabs and line breaks are replaced with spaces instead.
+fn escape(cell: &str, delimiter: char) -> String {
+    if delimiter == '\t' {
+        return cell.replace(['\t', '\n', '\r'], " ");
+    }
+
+    if cell.contains([delimiter, '"', '\n', '\r']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
@@ -0,0 +1,144 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{Result, anyhow};
+use http_client::HttpClient;
+use serde_json::json;
+
+use crate::utils::{RateLimiter, api_base_url, make_request};
+
+/// Tools that embed their queries for the semantic cache. They reach the API
+/// directly while the embedding provider is down, so only configuration
+/// disables them.
+pub const EMBEDDINGS: &str = "embeddings";
+/// Tools backed by the recommendations API.
+pub const RECOMMENDATIONS: &str = "recommendations";
+
+/// A paper the recommendations API is probed with.
+const PROBE_PAPER_ID: &str = "649def34f8be52c8b66281af98ae884c09aef38b";
+
+/// Named groups of tools that can be disabled while the server runs, for
+/// instance while a service they depend on is down. A tool is listed and
+/// callable only while every group it belongs to is enabled.
+#[derive(Default)]
+pub struct ToolGroups {
+    groups: BTreeMap<String, Vec<String>>,
+    disabled: Mutex<HashSet<String>>,
+    /// Groups disabled by configuration, which health checks leave alone.
+    pinned: HashSet<String>,
+}
+
+impl ToolGroups {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds tools to a group, creating it if needed.
+    pub fn add<I, S>(&mut self, group: &str, tools: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.groups
+            .entry(group.to_string())
+            .or_default()
+            .extend(tools.into_iter().map(Into::into));
+        self
+    }
+
+    /// Disables a group for as long as the server runs.
+    pub fn pin_disabled(&mut self, group: &str) -> Result<&mut Self> {
+        self.check_group(group)?;
+        self.pinned.insert(group.to_string());
+        if let Ok(mut disabled) = self.disabled.lock() {
+            disabled.insert(group.to_string());
+        }
+        Ok(self)
+    }
+
+    pub fn group_names(&self) -> impl Iterator<Item = &str> {
+        self.groups.keys().map(String::as_str)
+    }
+
+    fn check_group(&self, group: &str) -> Result<()> {
+        if self.groups.contains_key(group) {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "Unknown tool group '{}'. Known groups: {}",
+                group,
+                self.group_names().collect::<Vec<_>>().join(", ")
+            ))
+        }
+    }
+
+    /// Enables or disables a group, returning whether the set of available
+    /// tools changed. Pinned groups stay disabled.
+    pub fn set_enabled(&self, group: &str, enabled: bool) -> bool {
+        if !self.groups.contains_key(group) || self.pinned.contains(group) {
+            return false;
+        }
+
+        let Ok(mut disabled) = self.disabled.lock() else {
+            return false;
+        };
+
+        let changed = if enabled {
+            disabled.remove(group)
+        } else {
+            disabled.insert(group.to_string())
+        };
+
+        if changed {
+            tracing::warn!(
+                "Tool group '{}' {}",
+                group,
+                if enabled { "enabled" } else { "disabled" }
+            );
+        }
+
+        changed
+    }
+
+    pub fn is_enabled(&self, tool: &str) -> bool {
+        let Ok(disabled) = self.disabled.lock() else {
+            return true;
+        };
+
+        !self
+            .groups
+            .iter()
+            .any(|(group, tools)| disabled.contains(group) && tools.iter().any(|t| t == tool))
+    }
+
+    /// Probes the recommendations API, enabling or disabling the
+    /// recommendation tools. Returns whether the set of available tools
+    /// changed.
+    pub async fn check_health(
+        &self,
+        http_client: &Arc<dyn HttpClient>,
+        rate_limiter: &Arc<RateLimiter>,
+    ) -> bool {
+        let recommendations_up = match make_request(
+            http_client,
+            rate_limiter,
+            None,
+            &format!("/recommendations/v1/papers/forpaper/{}", PROBE_PAPER_ID),
+            Some(&json!({ "limit": 1, "fields": "paperId" })),
+            None,
+            Some(&api_base_url()),
+        )
+        .await
+        {
+            Ok(_) => true,
+            Err(err) => {
+                tracing::debug!("Recommendations health check failed: {}", err);
+                false
+            }
+        };
+
+        self.set_enabled(RECOMMENDATIONS, recommendations_up)
+    }
+}
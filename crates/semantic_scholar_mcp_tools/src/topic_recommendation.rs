@@ -0,0 +1,282 @@
+use std::{collections::HashSet, sync::Arc};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use cache::Cache;
+use context_server::{Tool, ToolContent, ToolExecutor};
+use embed::Embed;
+use http_client::HttpClient;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::{
+    arguments::{input_schema, parse_arguments},
+    content::ToolOutput,
+    error::invalid_argument,
+    format::{Formatter, OutputFormat, render},
+    query_cache::{CacheKey, CacheLookup, cached_response, store_response},
+    utils::{RateLimiter, api_base_url, make_request},
+};
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct TopicRecommendationArguments {
+    /// A natural-language description of the research topic
+    topic: String,
+    /// Number of top search results to use as seed papers. Default: 5, Maximum: 20
+    #[schemars(range(min = 1, max = 20))]
+    seed_count: Option<u64>,
+    /// Maximum number of recommendations to return. Default: 20, Maximum: 500
+    #[schemars(range(max = 500))]
+    limit: Option<u64>,
+    /// Maximum length of the output in characters. Results past it are left out, with a note saying how many. Default: the server's limit, if any
+    #[schemars(range(min = 1))]
+    max_output_chars: Option<usize>,
+    #[serde(default)]
+    output_format: OutputFormat,
+}
+
+pub struct TopicRecommendationTool {
+    http_client: Arc<dyn HttpClient>,
+    rate_limiter: Arc<RateLimiter>,
+    cache: Arc<dyn Cache>,
+    embed: Arc<dyn Embed>,
+}
+
+impl TopicRecommendationTool {
+    pub fn new(
+        http_client: Arc<dyn HttpClient>,
+        rate_limiter: Arc<RateLimiter>,
+        cache: Arc<dyn Cache>,
+        embed: Arc<dyn Embed>,
+    ) -> Self {
+        Self {
+            http_client,
+            rate_limiter,
+            cache,
+            embed,
+        }
+    }
+
+    fn format_recommendations(
+        &self,
+        f: &mut dyn Formatter,
+        seeds: &[Value],
+        recommended: &[Value],
+    ) -> Result<()> {
+        if seeds.is_empty() {
+            f.note("No papers found matching this topic.");
+            return Ok(());
+        }
+
+        let seed_titles: Vec<String> = seeds
+            .iter()
+            .map(|paper| {
+                let title = paper
+                    .get("title")
+                    .and_then(|t| t.as_str())
+                    .unwrap_or("Unknown title");
+                let paper_id = paper
+                    .get("paperId")
+                    .and_then(|p| p.as_str())
+                    .unwrap_or("Unknown ID");
+                format!("{} (ID: {})", title, paper_id)
+            })
+            .collect();
+
+        f.list(
+            &format!("Seed papers ({} found for this topic)", seeds.len()),
+            &seed_titles,
+        );
+
+        if recommended.is_empty() {
+            f.note("No additional recommendations found for these seed papers.");
+            return Ok(());
+        }
+
+        f.heading(&format!("Found {} recommended papers:", recommended.len()));
+
+        for (i, paper) in recommended.iter().enumerate() {
+            let title = paper
+                .get("title")
+                .and_then(|t| t.as_str())
+                .unwrap_or("Unknown title");
+            let paper_id = paper
+                .get("paperId")
+                .and_then(|p| p.as_str())
+                .unwrap_or("Unknown ID");
+
+            f.item(i + 1, &format!("{} (ID: {})", title, paper_id));
+
+            if let Some(year) = paper.get("year").and_then(|y| y.as_u64()) {
+                f.field("Year", &year.to_string());
+            }
+
+            if let Some(venue) = paper.get("venue").and_then(|v| v.as_str()) {
+                if !venue.is_empty() {
+                    f.field("Venue", venue);
+                }
+            }
+
+            if let Some(authors) = paper.get("authors").and_then(|a| a.as_array()) {
+                let author_names: Vec<&str> = authors
+                    .iter()
+                    .filter_map(|author| author.get("name").and_then(|n| n.as_str()))
+                    .collect();
+
+                if !author_names.is_empty() {
+                    f.field("Authors", &author_names.join(", "));
+                }
+            }
+
+            if let Some(citation_count) = paper.get("citationCount").and_then(|c| c.as_u64()) {
+                f.field("Citations", &citation_count.to_string());
+            }
+
+            if let Some(url) = paper.get("url").and_then(|u| u.as_str()) {
+                f.link("URL", url);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Searches for the topic's seed papers and merges the recommendations
+    /// seeded by them.
+    async fn fetch_recommendations(
+        &self,
+        topic: &str,
+        seed_count: u64,
+        limit: u64,
+    ) -> Result<Value> {
+        let fields = "title,year,venue,authors,citationCount,url";
+
+        let search_result = make_request(
+            &self.http_client,
+            &self.rate_limiter,
+            Some(self.cache.as_ref()),
+            "/paper/search",
+            Some(&json!({
+                "query": topic,
+                "fields": "title,paperId",
+                "limit": seed_count,
+            })),
+            None,
+            None,
+        )
+        .await?;
+
+        let seeds: Vec<Value> = search_result
+            .get("data")
+            .and_then(|d| d.as_array())
+            .cloned()
+            .unwrap_or_default();
+
+        let seed_ids: Vec<&str> = seeds
+            .iter()
+            .filter_map(|paper| paper.get("paperId").and_then(|p| p.as_str()))
+            .collect();
+
+        let mut recommended = Vec::new();
+
+        if !seed_ids.is_empty() {
+            let recommendations = make_request(
+                &self.http_client,
+                &self.rate_limiter,
+                Some(self.cache.as_ref()),
+                "/recommendations/v1/papers",
+                Some(&json!({ "fields": fields, "limit": limit })),
+                Some(&json!({ "positivePaperIds": seed_ids, "negativePaperIds": [] })),
+                Some(&api_base_url()),
+            )
+            .await?;
+
+            // Merge and deduplicate, dropping anything already used as a seed
+            let mut seen: HashSet<String> = seed_ids.iter().map(|id| id.to_string()).collect();
+
+            for paper in recommendations
+                .get("recommendedPapers")
+                .and_then(|r| r.as_array())
+                .into_iter()
+                .flatten()
+            {
+                if let Some(paper_id) = paper.get("paperId").and_then(|p| p.as_str()) {
+                    if seen.insert(paper_id.to_string()) {
+                        recommended.push(paper.clone());
+                    }
+                }
+            }
+        }
+
+        Ok(json!({
+            "seedPapers": seeds,
+            "recommendedPapers": recommended,
+        }))
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for TopicRecommendationTool {
+    async fn execute(&self, arguments: Option<Value>) -> Result<Vec<ToolContent>> {
+        tracing::debug!("Executing TopicRecommendationTool");
+        let args: TopicRecommendationArguments = parse_arguments(arguments)?;
+        let topic = args.topic.as_str();
+
+        if topic.trim().is_empty() {
+            return Err(invalid_argument("Topic cannot be empty"));
+        }
+
+        let seed_count = args.seed_count.unwrap_or(5);
+
+        let limit = args.limit.unwrap_or(20);
+
+        let params = json!({
+            "topic": topic,
+            "seed_count": seed_count,
+            "limit": limit,
+        });
+
+        let key = CacheKey::new("topic_recommendations", &topic).params(&params);
+        let response = match cached_response(self.cache.as_ref(), self.embed.as_ref(), &key).await?
+        {
+            CacheLookup::Hit(response) => response,
+            CacheLookup::Miss(embedding) => {
+                let response = self.fetch_recommendations(topic, seed_count, limit).await?;
+                store_response(self.cache.as_ref(), &key, embedding, &response);
+                response
+            }
+        };
+
+        let seeds = response["seedPapers"]
+            .as_array()
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let recommended = response["recommendedPapers"]
+            .as_array()
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let formatted_result = render(
+            args.output_format,
+            &response,
+            None,
+            args.max_output_chars,
+            |f| self.format_recommendations(f, seeds, recommended),
+        )?;
+
+        let output = ToolOutput::new(formatted_result, args.output_format, &response);
+
+        Ok(output.into_contents())
+    }
+
+    fn to_tool(&self) -> Tool {
+        Tool {
+            name: "topic_recommendations".into(),
+            description: Some(
+                "Get paper recommendations from a natural-language topic description, by searching for seed papers and asking Semantic Scholar for papers similar to them"
+                    .into(),
+            ),
+            input_schema: input_schema::<TopicRecommendationArguments>(),
+        }
+    }
+}
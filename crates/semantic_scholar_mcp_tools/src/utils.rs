@@ -1,160 +1,86 @@
-use std::{
-    collections::HashMap,
-    sync::Arc,
-    time::{Duration, Instant},
+use std::{borrow::Cow, sync::Arc};
+
+use anyhow::Result;
+use cache::Cache;
+use http_client::HttpClient;
+pub use semantic_scholar_client::{
+    CircuitBreaker, ClassRateLimitSettings, RateClass, RateLimit, RateLimitSettings, RateLimiter,
+    RequestStats, RequestStatsSnapshot, api_base_url, encode_path_segment, is_empty_result,
+    normalize_author_id, normalize_paper_id,
 };
-
-use anyhow::{Result, anyhow};
-use futures::lock::Mutex;
-use futures_timer::Delay;
-use http_client::{HttpClient, Request, RequestBuilderExt, ResponseAsyncBodyExt};
 use serde_json::Value;
-
-pub struct RateLimiter {
-    last_call_time: Mutex<HashMap<String, Instant>>,
-}
-
-impl RateLimiter {
-    pub fn new() -> Self {
-        Self {
-            last_call_time: Mutex::new(HashMap::new()),
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::notifier::current_session;
+
+/// Remembers the papers and authors in a response in the session of the
+/// client that made the request, cached responses included.
+fn remember_in_session(body: &Value) {
+    fn collect<'a>(value: &'a Value, papers: &mut Vec<&'a str>, authors: &mut Vec<&'a str>) {
+        match value {
+            Value::Object(fields) => {
+                if let Some(paper_id) = fields.get("paperId").and_then(Value::as_str) {
+                    papers.push(paper_id);
+                } else if let Some(author_id) = fields.get("authorId").and_then(Value::as_str) {
+                    authors.push(author_id);
+                }
+                fields
+                    .values()
+                    .for_each(|value| collect(value, papers, authors));
+            }
+            Value::Array(values) => values
+                .iter()
+                .for_each(|value| collect(value, papers, authors)),
+            _ => {}
         }
     }
 
-    pub async fn acquire(&self, endpoint: &str) -> Result<()> {
-        let mut last_call_map = self.last_call_time.lock().await;
-
-        let rate_limit = Duration::from_secs(1);
-
-        if let Some(last_call) = last_call_map.get(endpoint) {
-            let elapsed = last_call.elapsed();
-            if elapsed < rate_limit {
-                let sleep_time = rate_limit - elapsed;
-                Delay::new(sleep_time).await;
-            }
-        }
+    let Some(session) = current_session() else {
+        return;
+    };
 
-        last_call_map.insert(endpoint.to_string(), Instant::now());
-        Ok(())
-    }
+    let mut papers = Vec::new();
+    let mut authors = Vec::new();
+    collect(body, &mut papers, &mut authors);
+    session.remember(&papers, &authors);
 }
 
+/// Sends a request through [`semantic_scholar_client::make_request`],
+/// remembering the papers and authors in the response in the session of the
+/// client that made the request.
 pub async fn make_request(
     http_client: &Arc<dyn HttpClient>,
     rate_limiter: &Arc<RateLimiter>,
+    cache: Option<&dyn Cache>,
     endpoint: &str,
     params: Option<&Value>,
+    body: Option<&Value>,
     base_url: Option<&str>,
 ) -> Result<Value> {
-    rate_limiter.acquire(endpoint).await?;
-
-    let base_url = base_url.unwrap_or("https://api.semanticscholar.org/graph/v1");
-    let url = if let Some(params) = params {
-        let query_string = build_query_string(params)?;
-        format!("{}{}?{}", base_url, endpoint, query_string)
-    } else {
-        format!("{}{}", base_url, endpoint)
-    };
-
-    let api_key = std::env::var("SEMANTIC_SCHOLAR_API_KEY").ok();
-
-    let max_retries = 5;
-    let mut retry_delay = Duration::from_millis(100);
-
-    let mut attempts = 0;
-    loop {
-        attempts += 1;
-
-        let mut request_builder = Request::builder().method("GET").uri(url.as_str());
-
-        if let Some(key) = &api_key {
-            request_builder = request_builder.header("x-api-key", key);
-        }
-
-        let request = request_builder.header("Accept", "application/json").end()?;
-        let response = http_client.send(request).await;
-
-        match response {
-            Ok(response) => {
-                let status = response.status();
-                if status.is_success() {
-                    let body: Value = response
-                        .json()
-                        .await
-                        .map_err(|e| anyhow!("Failed to parse JSON response: {}", e))?;
-                    return Ok(body);
-                } else {
-                    let error_body = response
-                        .text()
-                        .await
-                        .unwrap_or_else(|_| "Unknown error".to_string());
-
-                    if status == 429 || status == 503 || status == 502 {
-                        // Rate limiting or server errors - we can retry these
-                        if attempts <= max_retries {
-                            Delay::new(retry_delay).await;
-                            // Exponential backoff
-                            retry_delay = retry_delay * 2;
-                            continue;
-                        } else {
-                            return Err(anyhow!(
-                                "Rate limit exceeded after {} retries. Consider using an API key for higher limits.",
-                                max_retries
-                            ));
-                        }
-                    } else if status == 404 {
-                        return Err(anyhow!("Resource not found: {}", error_body));
-                    } else {
-                        return Err(anyhow!("HTTP error {}: {}", status, error_body));
-                    }
-                }
-            }
-            Err(e) => {
-                // Network errors might be transient, so we retry
-                if attempts <= max_retries {
-                    Delay::new(retry_delay).await;
-                    // Exponential backoff
-                    retry_delay = retry_delay * 2;
-                    continue;
-                } else {
-                    return Err(anyhow!(
-                        "Request failed after {} attempts: {}",
-                        max_retries,
-                        e
-                    ));
-                }
-            }
-        }
-    }
+    let response = semantic_scholar_client::make_request(
+        http_client,
+        rate_limiter,
+        cache,
+        endpoint,
+        params,
+        body,
+        base_url,
+    )
+    .await?;
+    remember_in_session(&response);
+    Ok(response)
 }
 
-fn build_query_string(params: &Value) -> Result<String> {
-    let mut query_parts = Vec::new();
+/// Shortens text to at most `max_chars` user-perceived characters, cutting on
+/// a grapheme boundary and marking the cut with an ellipsis. `None` leaves the
+/// text untouched.
+pub fn truncate_text(text: &str, max_chars: Option<usize>) -> Cow<'_, str> {
+    let Some(max_chars) = max_chars else {
+        return Cow::Borrowed(text);
+    };
 
-    if let Some(obj) = params.as_object() {
-        for (key, value) in obj {
-            match value {
-                Value::String(s) => {
-                    query_parts.push(format!("{}={}", key, urlencoding::encode(s)));
-                }
-                Value::Number(n) => {
-                    query_parts.push(format!("{}={}", key, n));
-                }
-                Value::Bool(b) => {
-                    query_parts.push(format!("{}={}", key, b));
-                }
-                Value::Array(arr) => {
-                    let joined = arr
-                        .iter()
-                        .filter_map(|v| v.as_str())
-                        .collect::<Vec<_>>()
-                        .join(",");
-                    query_parts.push(format!("{}={}", key, urlencoding::encode(&joined)));
-                }
-                _ => {}
-            }
-        }
+    match text.grapheme_indices(true).nth(max_chars) {
+        Some((end, _)) => Cow::Owned(format!("{}...", text[..end].trim_end())),
+        None => Cow::Borrowed(text),
     }
-
-    Ok(query_parts.join("&"))
 }
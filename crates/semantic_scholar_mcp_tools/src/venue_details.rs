@@ -0,0 +1,297 @@
+use std::{collections::HashMap, sync::Arc};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use cache::Cache;
+use context_server::{Tool, ToolContent, ToolExecutor};
+use embed::Embed;
+use http_client::HttpClient;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::{
+    arguments::{input_schema, parse_arguments},
+    content::ToolOutput,
+    error::invalid_argument,
+    format::{Formatter, OutputFormat, render},
+    query_cache::{CacheKey, CacheLookup, cached_response, store_response},
+    utils::{RateLimiter, make_request},
+};
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct VenueDetailsArguments {
+    /// Venue name as it appears on Semantic Scholar, e.g. 'Nature' or 'Neural Information Processing Systems'
+    venue: String,
+    /// Optional publicationVenue ID, used to disambiguate venues sharing the same name
+    venue_id: Option<String>,
+    /// Restrict the sampled papers by publication year. Formats: '2019', '2016-2020', '2010-', '-2015'
+    year: Option<String>,
+    /// Number of papers to sample for the statistics. Default: 200, Maximum: 1000
+    #[schemars(range(max = 1000))]
+    sample_size: Option<u64>,
+    /// Maximum length of the output in characters. Results past it are left out, with a note saying how many. Default: the server's limit, if any
+    #[schemars(range(min = 1))]
+    max_output_chars: Option<usize>,
+    #[serde(default)]
+    output_format: OutputFormat,
+}
+
+pub struct VenueDetailsTool {
+    http_client: Arc<dyn HttpClient>,
+    rate_limiter: Arc<RateLimiter>,
+    cache: Arc<dyn Cache>,
+    embed: Arc<dyn Embed>,
+}
+
+impl VenueDetailsTool {
+    pub fn new(
+        http_client: Arc<dyn HttpClient>,
+        rate_limiter: Arc<RateLimiter>,
+        cache: Arc<dyn Cache>,
+        embed: Arc<dyn Embed>,
+    ) -> Self {
+        Self {
+            http_client,
+            rate_limiter,
+            cache,
+            embed,
+        }
+    }
+
+    fn format_venue_details(
+        &self,
+        f: &mut dyn Formatter,
+        venue: &str,
+        venue_id: Option<&str>,
+        sample_size: usize,
+        response: &Value,
+    ) -> Result<()> {
+        if response.get("error").is_some() {
+            let message = response["error"]["message"]
+                .as_str()
+                .unwrap_or("Unknown error");
+            f.note(&format!("Error: {}", message));
+            return Ok(());
+        }
+
+        let Some(data) = response.get("data").and_then(|d| d.as_array()) else {
+            f.note("No venue found or unexpected API response format.");
+            return Ok(());
+        };
+        let data = &data[..data.len().min(sample_size)];
+
+        // Keep only the papers whose publication venue matches the requested one,
+        // falling back to the most common venue in the sample when no ID is given
+        let mut venue_counts: HashMap<&str, (usize, &Value)> = HashMap::new();
+        for paper in data {
+            if let Some(publication_venue) = paper.get("publicationVenue") {
+                if let Some(id) = publication_venue.get("id").and_then(|i| i.as_str()) {
+                    venue_counts.entry(id).or_insert((0, publication_venue)).0 += 1;
+                }
+            }
+        }
+
+        let publication_venue = match venue_id {
+            Some(venue_id) => venue_counts.get(venue_id).map(|(_, v)| *v),
+            None => venue_counts
+                .values()
+                .max_by_key(|(count, _)| *count)
+                .map(|(_, v)| *v),
+        };
+
+        let Some(publication_venue) = publication_venue else {
+            f.note(&format!("No venue metadata found for \"{}\".", venue));
+            return Ok(());
+        };
+
+        let id = publication_venue
+            .get("id")
+            .and_then(|i| i.as_str())
+            .unwrap_or("Unknown ID");
+        let name = publication_venue
+            .get("name")
+            .and_then(|n| n.as_str())
+            .unwrap_or(venue);
+
+        f.heading(&format!("Venue: {}", name));
+        f.field("Venue ID", id);
+
+        if let Some(venue_type) = publication_venue.get("type").and_then(|t| t.as_str()) {
+            f.field("Type", venue_type);
+        }
+
+        if let Some(aliases) = publication_venue
+            .get("alternate_names")
+            .and_then(|a| a.as_array())
+        {
+            let alias_list: Vec<&str> = aliases.iter().filter_map(|a| a.as_str()).collect();
+            if !alias_list.is_empty() {
+                f.field("Also known as", &alias_list.join(", "));
+            }
+        }
+
+        if let Some(issn) = publication_venue.get("issn").and_then(|i| i.as_str()) {
+            f.field("ISSN", issn);
+        }
+
+        if let Some(url) = publication_venue.get("url").and_then(|u| u.as_str()) {
+            f.link("URL", url);
+        }
+
+        let papers: Vec<&Value> = data
+            .iter()
+            .filter(|paper| {
+                paper
+                    .get("publicationVenue")
+                    .and_then(|v| v.get("id"))
+                    .and_then(|i| i.as_str())
+                    == Some(id)
+            })
+            .collect();
+
+        if papers.is_empty() {
+            return Ok(());
+        }
+
+        let mut citations: Vec<u64> = papers
+            .iter()
+            .filter_map(|paper| paper.get("citationCount").and_then(|c| c.as_u64()))
+            .collect();
+        citations.sort_unstable();
+
+        let influential: u64 = papers
+            .iter()
+            .filter_map(|paper| {
+                paper
+                    .get("influentialCitationCount")
+                    .and_then(|c| c.as_u64())
+            })
+            .sum();
+
+        let open_access = papers
+            .iter()
+            .filter(|paper| {
+                paper
+                    .get("isOpenAccess")
+                    .and_then(|o| o.as_bool())
+                    .unwrap_or(false)
+            })
+            .count();
+
+        let years: Vec<u64> = papers
+            .iter()
+            .filter_map(|paper| paper.get("year").and_then(|y| y.as_u64()))
+            .collect();
+
+        f.heading(&format!(
+            "Statistics (from a sample of {} papers):",
+            papers.len()
+        ));
+
+        if let (Some(min_year), Some(max_year)) = (years.iter().min(), years.iter().max()) {
+            f.field("Years covered", &format!("{}-{}", min_year, max_year));
+        }
+
+        if !citations.is_empty() {
+            let total: u64 = citations.iter().sum();
+            f.field(
+                "Mean citations per paper",
+                &format!("{:.1}", total as f64 / citations.len() as f64),
+            );
+            f.field(
+                "Median citations per paper",
+                &citations[citations.len() / 2].to_string(),
+            );
+            f.field(
+                "Most cited paper in sample",
+                &format!("{} citations", citations[citations.len() - 1]),
+            );
+            f.field(
+                "Influential citations per paper",
+                &format!("{:.1}", influential as f64 / citations.len() as f64),
+            );
+        }
+
+        f.field(
+            "Open access share",
+            &format!("{:.0}%", open_access as f64 * 100.0 / papers.len() as f64),
+        );
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for VenueDetailsTool {
+    async fn execute(&self, arguments: Option<Value>) -> Result<Vec<ToolContent>> {
+        tracing::debug!("Executing VenueDetailsTool");
+        let args: VenueDetailsArguments = parse_arguments(arguments)?;
+        let venue = args.venue.as_str();
+
+        if venue.trim().is_empty() {
+            return Err(invalid_argument("Venue cannot be empty"));
+        }
+
+        let venue_id = args.venue_id.as_deref();
+
+        let sample_size = args.sample_size.unwrap_or(200);
+
+        let mut params_map = serde_json::Map::new();
+        params_map.insert("venue".to_string(), json!(venue));
+        params_map.insert(
+            "fields".to_string(),
+            json!("publicationVenue,year,citationCount,influentialCitationCount,isOpenAccess"),
+        );
+
+        if let Some(year) = &args.year {
+            params_map.insert("year".to_string(), json!(year));
+        }
+
+        let params = Value::Object(params_map);
+
+        // The bulk search endpoint accepts a venue filter without a text query
+        let key = CacheKey::new("venue_details", &venue).params(&params);
+        let result = match cached_response(self.cache.as_ref(), self.embed.as_ref(), &key).await? {
+            CacheLookup::Hit(result) => result,
+            CacheLookup::Miss(embedding) => {
+                let result = make_request(
+                    &self.http_client,
+                    &self.rate_limiter,
+                    Some(self.cache.as_ref()),
+                    "/paper/search/bulk",
+                    Some(&params),
+                    None,
+                    None,
+                )
+                .await?;
+                store_response(self.cache.as_ref(), &key, embedding, &result);
+                result
+            }
+        };
+
+        let formatted_result = render(
+            args.output_format,
+            &result,
+            None,
+            args.max_output_chars,
+            |f| self.format_venue_details(f, venue, venue_id, sample_size as usize, &result),
+        )?;
+
+        let output = ToolOutput::new(formatted_result, args.output_format, &result);
+
+        Ok(output.into_contents())
+    }
+
+    fn to_tool(&self) -> Tool {
+        Tool {
+            name: "venue_details".into(),
+            description: Some(
+                "Get metadata about a publication venue (aliases, ISSN, URL, type) plus citation statistics computed from a sample of its papers"
+                    .into(),
+            ),
+            input_schema: input_schema::<VenueDetailsArguments>(),
+        }
+    }
+}
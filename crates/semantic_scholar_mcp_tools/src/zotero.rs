@@ -0,0 +1,381 @@
+use std::{sync::Arc, time::Instant};
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use cache::Cache;
+use context_server::{Tool, ToolContent, ToolExecutor};
+use http_client::{HttpClient, Request, RequestBuilderExt, ResponseAsyncBodyExt};
+use models::Paper;
+use schemars::JsonSchema;
+use semantic_scholar_client::{AuditEntry, is_offline, record_audit};
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::{
+    arguments::{input_schema, parse_arguments},
+    error::Error,
+    export::{fetch_papers, single_line, split_name, unresolved_note},
+    utils::RateLimiter,
+};
+
+const ZOTERO_BASE_URL: &str = "https://api.zotero.org";
+
+/// The most items the Zotero Web API accepts in a single write request.
+const ZOTERO_BATCH_SIZE: usize = 50;
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct ZoteroPushArguments {
+    /// List of paper identifiers to add to the Zotero library (max: 500). Each may be a Semantic Scholar ID, DOI:doi, ARXIV:id, MAG:id, ACL:id, PMID:id, PMCID:id or URL:url, or a bare DOI, arXiv ID or paper link
+    paper_ids: Vec<String>,
+    /// Key of the Zotero collection to add the papers to. Default: the library root
+    collection: Option<String>,
+    /// Tags to attach to every added item
+    #[serde(default)]
+    tags: Vec<String>,
+    /// If true, copy each paper's abstract into the item. Default: true
+    #[serde(default = "default_include_abstract")]
+    include_abstract: bool,
+}
+
+fn default_include_abstract() -> bool {
+    true
+}
+
+/// Where items are written, read from the environment: `ZOTERO_API_KEY`
+/// together with either `ZOTERO_USER_ID` for a personal library or
+/// `ZOTERO_GROUP_ID` for a group library.
+struct ZoteroLibrary {
+    api_key: String,
+    path: String,
+}
+
+impl ZoteroLibrary {
+    fn from_env() -> Result<Self> {
+        let var = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+
+        let api_key = var("ZOTERO_API_KEY")
+            .ok_or_else(|| anyhow!("Pushing to Zotero requires ZOTERO_API_KEY to be set"))?;
+
+        let path = match (var("ZOTERO_USER_ID"), var("ZOTERO_GROUP_ID")) {
+            (_, Some(group_id)) => format!("/groups/{}", group_id),
+            (Some(user_id), None) => format!("/users/{}", user_id),
+            (None, None) => {
+                return Err(anyhow!(
+                    "Pushing to Zotero requires ZOTERO_USER_ID or ZOTERO_GROUP_ID to be set"
+                ));
+            }
+        };
+
+        Ok(Self { api_key, path })
+    }
+}
+
+/// The outcome of adding one paper: the key of the created Zotero item, or
+/// why it was rejected.
+type PushResult = std::result::Result<String, String>;
+
+pub struct ZoteroPushTool {
+    http_client: Arc<dyn HttpClient>,
+    rate_limiter: Arc<RateLimiter>,
+    cache: Arc<dyn Cache>,
+}
+
+impl ZoteroPushTool {
+    pub fn new(
+        http_client: Arc<dyn HttpClient>,
+        rate_limiter: Arc<RateLimiter>,
+        cache: Arc<dyn Cache>,
+    ) -> Self {
+        Self {
+            http_client,
+            rate_limiter,
+            cache,
+        }
+    }
+
+    /// Creates the items in batches, returning one result per item in order.
+    async fn create_items(
+        &self,
+        library: &ZoteroLibrary,
+        items: &[Value],
+    ) -> Result<Vec<PushResult>> {
+        let mut results = Vec::with_capacity(items.len());
+
+        let url = format!("{}{}/items", ZOTERO_BASE_URL, library.path);
+        for batch in items.chunks(ZOTERO_BATCH_SIZE) {
+            let request = Request::builder()
+                .method("POST")
+                .uri(url.as_str())
+                .header("Zotero-API-Key", library.api_key.as_str())
+                .header("Zotero-API-Version", "3")
+                .header("Content-Type", "application/json")
+                .body(serde_json::to_vec(batch)?.into())?;
+
+            let started = Instant::now();
+            let response = self.http_client.send(request).await;
+            record_audit(AuditEntry {
+                method: "POST",
+                url: &url,
+                outcome: match &response {
+                    Ok(response) => Ok(response.status().as_u16()),
+                    Err(err) => Err(err.to_string()),
+                },
+                duration: started.elapsed(),
+                retries: 0,
+                authenticated: true,
+            });
+            let response = response
+                .map_err(|err| Error::UpstreamError(format!("Zotero request failed: {}", err)))?;
+
+            let status = response.status();
+            if !status.is_success() {
+                let error_body = response
+                    .text()
+                    .await
+                    .unwrap_or_else(|_| "Unknown error".to_string());
+                // 403 means the key is invalid or can't write to the library
+                return Err(Error::UpstreamError(format!(
+                    "Zotero returned HTTP {}: {}",
+                    status, error_body
+                ))
+                .into());
+            }
+
+            let body: Value = response
+                .json()
+                .await
+                .map_err(|err| anyhow!("Failed to parse Zotero response: {}", err))?;
+
+            // Results are keyed by the item's position in the batch
+            for i in 0..batch.len() {
+                let index = i.to_string();
+                let result = if let Some(key) = body["success"][&index].as_str() {
+                    Ok(key.to_string())
+                } else if let Some(key) = body["unchanged"][&index].as_str() {
+                    Ok(key.to_string())
+                } else {
+                    Err(body["failed"][&index]["message"]
+                        .as_str()
+                        .unwrap_or("No result reported")
+                        .to_string())
+                };
+                results.push(result);
+            }
+        }
+
+        Ok(results)
+    }
+}
+
+/// Describes a paper as a Zotero item. Fields an item type doesn't have,
+/// such as a book's DOI, go to `extra` where Zotero still recognises them.
+fn zotero_item(
+    paper: &Paper,
+    collection: Option<&str>,
+    tags: &[String],
+    include_abstract: bool,
+) -> Value {
+    let publication_types = paper.publication_types.as_deref().unwrap_or_default();
+    let has_type = |name: &str| publication_types.iter().any(|t| t == name);
+
+    let journal = paper.journal.as_ref();
+    let journal_name = journal
+        .and_then(|journal| journal.name.as_deref())
+        .filter(|name| !name.is_empty());
+    let venue = journal_name.or(paper.venue());
+    let external_ids = paper.external_ids.as_ref();
+    let arxiv = external_ids.and_then(|ids| ids.arxiv.as_deref());
+
+    let (item_type, venue_field) = if has_type("Conference") {
+        ("conferencePaper", Some("proceedingsTitle"))
+    } else if has_type("BookSection") {
+        ("bookSection", Some("bookTitle"))
+    } else if has_type("Book") {
+        ("book", None)
+    } else if has_type("JournalArticle") || has_type("Review") || journal_name.is_some() {
+        ("journalArticle", Some("publicationTitle"))
+    } else if arxiv.is_some() {
+        ("preprint", None)
+    } else {
+        ("document", None)
+    };
+
+    let mut item = json!({
+        "itemType": item_type,
+        "title": single_line(paper.title.as_deref().unwrap_or("Untitled")),
+    });
+
+    item["creators"] = paper
+        .author_names()
+        .into_iter()
+        .map(|name| match split_name(name) {
+            (Some(given), family) => json!({
+                "creatorType": "author",
+                "firstName": given,
+                "lastName": family,
+            }),
+            (None, family) => json!({ "creatorType": "author", "name": family }),
+        })
+        .collect();
+
+    if let Some(date) = &paper.publication_date {
+        item["date"] = json!(date);
+    } else if let Some(year) = paper.year {
+        item["date"] = json!(year.to_string());
+    }
+
+    if let (Some(field), Some(venue)) = (venue_field, venue) {
+        item[field] = json!(venue);
+    }
+
+    if item_type == "journalArticle" {
+        if let Some(volume) = journal.and_then(|journal| journal.volume.as_deref()) {
+            item["volume"] = json!(volume.trim());
+        }
+    }
+
+    if matches!(
+        item_type,
+        "journalArticle" | "conferencePaper" | "bookSection"
+    ) {
+        if let Some(pages) = journal.and_then(|journal| journal.pages.as_deref()) {
+            item["pages"] = json!(pages.trim());
+        }
+    }
+
+    if item_type == "preprint" {
+        if let Some(arxiv) = arxiv {
+            item["repository"] = json!("arXiv");
+            item["archiveID"] = json!(format!("arXiv:{}", arxiv));
+        }
+    }
+
+    let mut extra = Vec::new();
+
+    if let Some(doi) = external_ids.and_then(|ids| ids.doi.as_deref()) {
+        if matches!(item_type, "journalArticle" | "conferencePaper" | "preprint") {
+            item["DOI"] = json!(doi);
+        } else {
+            extra.push(format!("DOI: {}", doi));
+        }
+    }
+
+    if let Some(url) = &paper.url {
+        item["url"] = json!(url);
+    }
+
+    if include_abstract {
+        if let Some(abstract_text) = paper.abstract_text() {
+            item["abstractNote"] = json!(abstract_text);
+        }
+    }
+
+    if let Some(paper_id) = &paper.paper_id {
+        extra.push(format!("Semantic Scholar ID: {}", paper_id));
+    }
+
+    if !extra.is_empty() {
+        item["extra"] = json!(extra.join("\n"));
+    }
+
+    item["tags"] = tags.iter().map(|tag| json!({ "tag": tag })).collect();
+
+    if let Some(collection) = collection {
+        item["collections"] = json!([collection]);
+    }
+
+    item
+}
+
+#[async_trait]
+impl ToolExecutor for ZoteroPushTool {
+    async fn execute(&self, arguments: Option<Value>) -> Result<Vec<ToolContent>> {
+        tracing::debug!("Executing ZoteroPushTool");
+        let args: ZoteroPushArguments = parse_arguments(arguments)?;
+
+        // Fail on missing configuration before fetching anything
+        let library = ZoteroLibrary::from_env()?;
+        if is_offline() {
+            return Err(
+                Error::NotCached(String::from("pushing to Zotero needs the network")).into(),
+            );
+        }
+
+        let collection = args
+            .collection
+            .as_deref()
+            .map(str::trim)
+            .filter(|collection| !collection.is_empty());
+
+        let papers = fetch_papers(
+            &self.http_client,
+            &self.rate_limiter,
+            &self.cache,
+            &args.paper_ids,
+        )
+        .await?;
+
+        let found: Vec<&Paper> = papers
+            .iter()
+            .filter_map(|(_, paper)| paper.as_ref())
+            .collect();
+
+        if found.is_empty() {
+            return Ok(vec![ToolContent::Text {
+                text: unresolved_note(&papers).unwrap_or_default(),
+            }]);
+        }
+
+        let items: Vec<Value> = found
+            .iter()
+            .map(|paper| zotero_item(paper, collection, &args.tags, args.include_abstract))
+            .collect();
+
+        let results = self.create_items(&library, &items).await?;
+
+        let added = results.iter().filter(|result| result.is_ok()).count();
+        let mut text = format!(
+            "Added {} of {} papers to the Zotero library:\n",
+            added,
+            found.len()
+        );
+
+        for (i, (paper, result)) in found.iter().zip(results).enumerate() {
+            let title = paper.title.as_deref().unwrap_or("Unknown title");
+            match result {
+                Ok(key) => {
+                    text.push_str(&format!("\n{}. {} (Zotero key: {})\n", i + 1, title, key))
+                }
+                Err(message) => {
+                    text.push_str(&format!("\n{}. {} [FAILED]\n", i + 1, title));
+                    text.push_str(&format!("   Error: {}\n", message));
+                }
+            }
+        }
+
+        if let Some(note) = unresolved_note(&papers) {
+            text.push_str(&format!("\n{}\n", note));
+        }
+
+        Ok(vec![ToolContent::Text {
+            text: text.trim_end().to_string(),
+        }])
+    }
+
+    fn to_tool(&self) -> Tool {
+        Tool {
+            name: "zotero_push".into(),
+            description: Some(
+                "Add papers to a Zotero library through the Zotero Web API, optionally into a collection and with tags"
+                    .into(),
+            ),
+            input_schema: input_schema::<ZoteroPushArguments>(),
+        }
+    }
+}
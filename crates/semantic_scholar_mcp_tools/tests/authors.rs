@@ -0,0 +1,254 @@
+mod common;
+
+use context_server::ToolExecutor;
+use mock_http_client::{MockHttpClient, MockResponse, Route, fixture};
+use semantic_scholar_mcp_tools::{
+    AuthorBatchTool, AuthorCitationNetworkTool, AuthorDetailsTool, AuthorPapersTool,
+    AuthorSearchTool,
+};
+use serde_json::json;
+
+use crate::common::{Harness, structured, text};
+
+const VASWANI_ID: &str = "40348417";
+
+#[tokio::test]
+async fn author_search_lists_matching_authors() {
+    let harness = Harness::new(
+        MockHttpClient::new().with_route(
+            Route::get("/graph/v1/author/search")
+                .query("query", "Ashish Vaswani")
+                .respond(MockResponse::fixture("author_search")),
+        ),
+    );
+
+    let contents = AuthorSearchTool::new(
+        harness.http_client(),
+        harness.rate_limiter.clone(),
+        harness.cache.clone(),
+        harness.embed.clone(),
+    )
+    .execute(Some(json!({ "query": "Ashish Vaswani", "limit": 1 })))
+    .await
+    .unwrap();
+
+    let text = text(&contents);
+    assert!(text.contains("Found 3 total authors matching your query. Showing results 1-1"));
+    assert!(text.contains(&format!("Ashish Vaswani (ID: {})", VASWANI_ID)));
+    assert!(text.contains("Google Brain"));
+    assert!(text.contains("For more authors, use offset=1"));
+    assert_eq!(structured(&contents)["data"], fixture("author_search"));
+}
+
+#[tokio::test]
+async fn author_search_reports_no_authors() {
+    let harness = Harness::new(MockHttpClient::new().with_route(
+        Route::get("/graph/v1/author/search").respond(MockResponse::json(
+            &json!({ "total": 0, "offset": 0, "data": [] }),
+        )),
+    ));
+
+    let contents = AuthorSearchTool::new(
+        harness.http_client(),
+        harness.rate_limiter.clone(),
+        harness.cache.clone(),
+        harness.embed.clone(),
+    )
+    .execute(Some(json!({ "query": "nobody at all" })))
+    .await
+    .unwrap();
+
+    assert!(text(&contents).contains("No authors found matching your query."));
+}
+
+#[tokio::test]
+async fn author_details_renders_the_author() {
+    let harness = Harness::new(
+        MockHttpClient::new().with_route(
+            Route::get(&format!("/graph/v1/author/{}", VASWANI_ID))
+                .respond(MockResponse::fixture("author_details")),
+        ),
+    );
+
+    let contents = AuthorDetailsTool::new(
+        harness.http_client(),
+        harness.rate_limiter.clone(),
+        harness.cache.clone(),
+        harness.embed.clone(),
+    )
+    .execute(Some(json!({ "author_id": VASWANI_ID })))
+    .await
+    .unwrap();
+
+    let text = text(&contents);
+    assert!(text.contains("Author: Ashish Vaswani"));
+    assert!(text.contains(VASWANI_ID));
+}
+
+#[tokio::test]
+async fn author_papers_paginates() {
+    let harness = Harness::new(
+        MockHttpClient::new().with_route(
+            Route::get(&format!("/graph/v1/author/{}/papers", VASWANI_ID))
+                .respond(MockResponse::fixture("author_papers")),
+        ),
+    );
+
+    let contents = AuthorPapersTool::new(
+        harness.http_client(),
+        harness.rate_limiter.clone(),
+        harness.cache.clone(),
+        harness.embed.clone(),
+    )
+    .execute(Some(json!({ "author_id": VASWANI_ID, "limit": 2 })))
+    .await
+    .unwrap();
+
+    let text = text(&contents);
+    assert!(text.contains("Found 2 papers by this author (offset: 0)"));
+    assert!(text.contains("Image Transformer"));
+    assert!(text.contains("For more papers, use offset=2"));
+
+    let pagination = &structured(&contents)["pagination"];
+    assert_eq!(pagination["returned"], 2);
+    assert_eq!(pagination["has_more"], true);
+}
+
+#[tokio::test]
+async fn author_papers_reports_no_papers() {
+    let harness = Harness::new(
+        MockHttpClient::new().with_route(
+            Route::get(&format!("/graph/v1/author/{}/papers", VASWANI_ID))
+                .respond(MockResponse::json(&json!({ "offset": 0, "data": [] }))),
+        ),
+    );
+
+    let contents = AuthorPapersTool::new(
+        harness.http_client(),
+        harness.rate_limiter.clone(),
+        harness.cache.clone(),
+        harness.embed.clone(),
+    )
+    .execute(Some(json!({ "author_id": VASWANI_ID })))
+    .await
+    .unwrap();
+
+    assert!(text(&contents).contains("No papers found for this author."));
+    assert_eq!(structured(&contents)["pagination"]["has_more"], false);
+}
+
+#[tokio::test]
+async fn author_batch_lists_every_author() {
+    let harness = Harness::new(MockHttpClient::new().with_route(
+        Route::post("/graph/v1/author/batch").respond(MockResponse::fixture("author_batch")),
+    ));
+
+    let contents = AuthorBatchTool::new(
+        harness.http_client(),
+        harness.rate_limiter.clone(),
+        harness.cache.clone(),
+        harness.embed.clone(),
+    )
+    .execute(Some(json!({ "author_ids": [VASWANI_ID, "1846258"] })))
+    .await
+    .unwrap();
+
+    let text = text(&contents);
+    assert!(text.contains("Retrieved 2 of 2 requested authors"));
+    assert!(text.contains("Noam M. Shazeer"));
+    assert!(!text.contains("Could not resolve"));
+}
+
+#[tokio::test]
+async fn author_citation_network_tallies_citing_authors() {
+    let attention_id = "204e3073870fae3d05bcbc2f6a8e263d9b72e776";
+    let harness = Harness::new(
+        MockHttpClient::new()
+            .with_route(
+                Route::get(&format!("/graph/v1/author/{}/papers", VASWANI_ID))
+                    .respond(MockResponse::fixture("author_papers")),
+            )
+            .with_route(
+                Route::get(&format!("/graph/v1/paper/{}/citations", attention_id)).respond(
+                    MockResponse::json(&json!({
+                        "data": [
+                            {
+                                "citingPaper": {
+                                    "authors": [
+                                        { "authorId": "39172707", "name": "Jacob Devlin" },
+                                        { "authorId": VASWANI_ID, "name": "Ashish Vaswani" },
+                                        { "authorId": "39172707", "name": "Jacob Devlin" },
+                                    ],
+                                },
+                            },
+                            {
+                                "citingPaper": {
+                                    "authors": [
+                                        { "authorId": "31035595", "name": "Tom B. Brown" },
+                                    ],
+                                },
+                            },
+                        ],
+                    })),
+                ),
+            ),
+    );
+
+    // The most-cited paper only, and no route for its references
+    let contents = AuthorCitationNetworkTool::new(
+        harness.http_client(),
+        harness.rate_limiter.clone(),
+        harness.cache.clone(),
+        harness.embed.clone(),
+    )
+    .execute(Some(json!({
+        "author_id": VASWANI_ID,
+        "direction": "both",
+        "max_papers": 1,
+    })))
+    .await
+    .unwrap();
+
+    let text = text(&contents);
+    assert!(text.contains(&format!(
+        "Citation network for author {} (based on their 1 most-cited papers):",
+        VASWANI_ID
+    )));
+    assert!(text.contains("Authors who cite them most (2 distinct authors, showing top 2):"));
+    assert!(text.contains("1. Jacob Devlin (ID: 39172707)"));
+    assert!(text.contains("Citations: 2 across 1 of this author's papers"));
+    assert!(text.contains("2. Tom B. Brown (ID: 31035595)"));
+    assert!(!text.contains("Ashish Vaswani"));
+    assert!(text.contains("Authors they cite most: none found"));
+
+    let requests = harness.client.requests();
+    assert_eq!(requests.len(), 3);
+    assert!(requests.iter().all(|request| {
+        !request
+            .path
+            .contains("1db9bd18681b96473f3c82b21edc9240b44dc329")
+    }));
+}
+
+#[tokio::test]
+async fn author_citation_network_without_papers() {
+    let harness = Harness::new(
+        MockHttpClient::new().with_route(
+            Route::get(&format!("/graph/v1/author/{}/papers", VASWANI_ID))
+                .respond(MockResponse::json(&json!({ "offset": 0, "data": [] }))),
+        ),
+    );
+
+    let contents = AuthorCitationNetworkTool::new(
+        harness.http_client(),
+        harness.rate_limiter.clone(),
+        harness.cache.clone(),
+        harness.embed.clone(),
+    )
+    .execute(Some(json!({ "author_id": VASWANI_ID })))
+    .await
+    .unwrap();
+
+    assert!(text(&contents).contains("No papers found for this author."));
+    assert_eq!(harness.client.request_count(), 1);
+}
@@ -0,0 +1,134 @@
+mod common;
+
+use context_server::ToolExecutor;
+use mock_http_client::{MockHttpClient, MockResponse, Route, fixture};
+use semantic_scholar_mcp_tools::{CacheClearTool, PaperDetailsTool, PaperSearchTool};
+use serde_json::json;
+
+use crate::common::{Harness, error_kind, text};
+
+const ATTENTION_ID: &str = "204e3073870fae3d05bcbc2f6a8e263d9b72e776";
+const MISSING_ID: &str = "0000000000000000000000000000000000000000";
+
+fn paper_search(harness: &Harness) -> PaperSearchTool {
+    PaperSearchTool::new(
+        harness.http_client(),
+        harness.rate_limiter.clone(),
+        harness.cache.clone(),
+        harness.embed.clone(),
+    )
+}
+
+fn paper_details(harness: &Harness) -> PaperDetailsTool {
+    PaperDetailsTool::new(
+        harness.http_client(),
+        harness.rate_limiter.clone(),
+        harness.cache.clone(),
+        harness.embed.clone(),
+    )
+}
+
+#[tokio::test]
+async fn repeated_call_is_answered_from_the_cache() {
+    let harness = Harness::new(MockHttpClient::new().with_route(
+        Route::get("/graph/v1/paper/search").respond(MockResponse::fixture("paper_search")),
+    ));
+    let tool = paper_search(&harness);
+    let arguments = json!({ "query": "transformers", "limit": 2 });
+
+    let first = tool.execute(Some(arguments.clone())).await.unwrap();
+    let second = tool.execute(Some(arguments)).await.unwrap();
+
+    assert_eq!(text(&first), text(&second));
+    assert_eq!(harness.client.request_count(), 1);
+}
+
+#[tokio::test]
+async fn another_page_is_fetched() {
+    let harness = Harness::new(MockHttpClient::new().with_route(
+        Route::get("/graph/v1/paper/search").respond(MockResponse::fixture("paper_search")),
+    ));
+    let tool = paper_search(&harness);
+
+    tool.execute(Some(json!({ "query": "transformers", "limit": 2 })))
+        .await
+        .unwrap();
+    tool.execute(Some(
+        json!({ "query": "transformers", "limit": 2, "offset": 2 }),
+    ))
+    .await
+    .unwrap();
+
+    let requests = harness.client.requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[1].query_param("offset"), Some("2"));
+}
+
+#[tokio::test]
+async fn not_found_is_remembered() {
+    let harness = Harness::new(
+        MockHttpClient::new().with_route(
+            Route::get(&format!("/graph/v1/paper/{}", MISSING_ID))
+                .respond(MockResponse::error(404, "not_found")),
+        ),
+    );
+    let tool = paper_details(&harness);
+    let arguments = json!({ "paper_id": MISSING_ID });
+
+    let first = tool.execute(Some(arguments.clone())).await.unwrap_err();
+    let second = tool.execute(Some(arguments)).await.unwrap_err();
+
+    assert_eq!(error_kind(&first), "not_found");
+    assert_eq!(error_kind(&second), "not_found");
+    assert_eq!(harness.client.request_count(), 1);
+}
+
+#[tokio::test]
+async fn cleared_cache_reaches_the_api_again() {
+    let harness = Harness::new(MockHttpClient::new().with_route(
+        Route::get("/graph/v1/paper/search").respond(MockResponse::fixture("paper_search")),
+    ));
+    let tool = paper_search(&harness);
+    let arguments = json!({ "query": "transformers" });
+
+    tool.execute(Some(arguments.clone())).await.unwrap();
+    CacheClearTool::new(harness.cache.clone())
+        .execute(Some(json!({ "all": true })))
+        .await
+        .unwrap();
+    tool.execute(Some(arguments)).await.unwrap();
+
+    assert_eq!(harness.client.request_count(), 2);
+}
+
+#[tokio::test]
+async fn papers_are_filled_in_from_earlier_responses() {
+    // A search that didn't ask for citation counts
+    let mut search = fixture("paper_search");
+    for paper in search["data"].as_array_mut().unwrap() {
+        paper.as_object_mut().unwrap().remove("citationCount");
+    }
+
+    let harness = Harness::new(
+        MockHttpClient::new()
+            .with_route(
+                Route::get(&format!("/graph/v1/paper/{}", ATTENTION_ID))
+                    .respond(MockResponse::fixture("paper_details")),
+            )
+            .with_route(Route::get("/graph/v1/paper/search").respond(MockResponse::json(&search))),
+    );
+
+    paper_details(&harness)
+        .execute(Some(json!({ "paper_id": ATTENTION_ID })))
+        .await
+        .unwrap();
+    let contents = paper_search(&harness)
+        .execute(Some(json!({ "query": "transformers" })))
+        .await
+        .unwrap();
+
+    let text = text(&contents);
+    assert!(text.contains("149862"));
+    // Nothing was known about the other paper
+    assert!(!text.contains("104530"));
+}
@@ -0,0 +1,52 @@
+mod common;
+
+use context_server::ToolExecutor;
+use mock_http_client::{MockHttpClient, MockResponse, Route};
+use semantic_scholar_mcp_tools::CitationTrendTool;
+use serde_json::json;
+
+use crate::common::{Harness, structured, text};
+
+const ATTENTION_ID: &str = "204e3073870fae3d05bcbc2f6a8e263d9b72e776";
+
+#[tokio::test]
+async fn citation_trend_pages_through_citations_and_counts_them_by_year() {
+    let client = MockHttpClient::new()
+        .with_route(
+            Route::get(&format!("/graph/v1/paper/{}", ATTENTION_ID))
+                .respond(MockResponse::fixture("paper_details")),
+        )
+        .with_route(
+            Route::get(&format!("/graph/v1/paper/{}/citations", ATTENTION_ID))
+                .respond(MockResponse::fixture("paper_citations")),
+        );
+    let harness = Harness::new(client);
+
+    let contents = CitationTrendTool::new(
+        harness.http_client(),
+        harness.rate_limiter.clone(),
+        harness.cache.clone(),
+        harness.embed.clone(),
+    )
+    .execute(Some(
+        json!({ "paper_id": ATTENTION_ID, "max_citations": 4 }),
+    ))
+    .await
+    .unwrap();
+
+    let trend = &structured(&contents)["data"];
+    assert_eq!(trend["counted"], 4);
+    assert_eq!(trend["byYear"][0], json!({ "year": 2019, "citations": 2 }));
+    assert_eq!(trend["byYear"][1], json!({ "year": 2020, "citations": 2 }));
+    assert_eq!(trend["trend"], "insufficient");
+
+    // The second page asks only for what is left of max_citations
+    let requests = harness.client.requests();
+    assert_eq!(requests.len(), 3);
+    assert_eq!(requests[2].query_param("offset"), Some("2"));
+    assert_eq!(requests[2].query_param("limit"), Some("2"));
+
+    let text = text(&contents);
+    assert!(text.contains("2019: 2 ####"));
+    assert!(text.contains("Only 4 of the paper's 149862 citations were fetched"));
+}
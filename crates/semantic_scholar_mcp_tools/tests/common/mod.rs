@@ -0,0 +1,73 @@
+#![allow(dead_code)]
+
+use std::sync::Arc;
+
+use cache::Cache;
+use context_server::ToolContent;
+use embed::{Embed, HashEmbed};
+use http_client::HttpClient;
+use library::Library;
+use memory_cache::MemoryCache;
+use mock_http_client::{MockHttpClient, unlimited_rate_limiter};
+use semantic_scholar_client::RateLimiter;
+use semantic_scholar_mcp_tools::Error;
+use serde_json::Value;
+
+/// What a tool is built from, with the HTTP client answering from fixtures
+/// and a cache of its own, so tests don't see each other's responses.
+pub struct Harness {
+    pub client: Arc<MockHttpClient>,
+    pub rate_limiter: Arc<RateLimiter>,
+    pub cache: Arc<dyn Cache>,
+    pub embed: Arc<dyn Embed>,
+}
+
+impl Harness {
+    pub fn new(client: MockHttpClient) -> Self {
+        Self {
+            client: Arc::new(client),
+            rate_limiter: unlimited_rate_limiter(),
+            cache: Arc::new(MemoryCache::new(None)),
+            embed: Arc::new(HashEmbed::new()),
+        }
+    }
+
+    pub fn http_client(&self) -> Arc<dyn HttpClient> {
+        self.client.clone()
+    }
+}
+
+/// An empty library in a directory of its own, so tests don't see each
+/// other's entries.
+pub fn library(name: &str) -> Arc<Library> {
+    let path = std::env::temp_dir().join(format!(
+        "semantic_scholar_mcp_tools-{}-{}",
+        std::process::id(),
+        name
+    ));
+    let _ = std::fs::remove_dir_all(&path);
+    Arc::new(Library::new(path).unwrap())
+}
+
+/// The text a tool rendered for the model.
+pub fn text(contents: &[ToolContent]) -> &str {
+    match contents.first() {
+        Some(ToolContent::Text { text }) => text,
+        _ => panic!("Expected the rendered text first"),
+    }
+}
+
+/// The JSON block following the rendered text.
+pub fn structured(contents: &[ToolContent]) -> Value {
+    match contents.get(1) {
+        Some(ToolContent::Text { text }) => serde_json::from_str(text).unwrap(),
+        _ => panic!("Expected a JSON block after the rendered text"),
+    }
+}
+
+/// The kind of error a tool failed with.
+pub fn error_kind(err: &anyhow::Error) -> &'static str {
+    err.downcast_ref::<Error>()
+        .map(Error::kind)
+        .unwrap_or_else(|| panic!("Expected a tool error, got: {}", err))
+}
@@ -0,0 +1,222 @@
+mod common;
+
+use std::sync::{Arc, Once};
+
+use context_server::ToolExecutor;
+use dataset_sync::DatasetSync;
+use mock_http_client::{MockHttpClient, MockResponse, Route};
+use semantic_scholar_mcp_tools::{
+    DatasetDownloadLinksTool, DatasetListTool, DatasetReleasesTool, SyncRunTool, SyncStatusTool,
+};
+use serde_json::json;
+
+use crate::common::{Harness, text};
+
+/// Download links and syncing need an API key, which the tests share.
+fn set_api_key() {
+    static API_KEY: Once = Once::new();
+    // Only ever set, and to the same value, so tests reading it agree
+    API_KEY.call_once(|| unsafe { std::env::set_var("SEMANTIC_SCHOLAR_API_KEY", "test-key") });
+}
+
+/// An empty dataset store in a directory of its own, downloading through
+/// the harness's client.
+fn dataset_sync(harness: &Harness, name: &str) -> Arc<DatasetSync> {
+    let path = std::env::temp_dir().join(format!(
+        "semantic_scholar_mcp_tools-{}-{}",
+        std::process::id(),
+        name
+    ));
+    let _ = std::fs::remove_dir_all(&path);
+    Arc::new(DatasetSync::new(path, harness.http_client()).unwrap())
+}
+
+#[tokio::test]
+async fn dataset_releases_lists_the_newest_first() {
+    let harness = Harness::new(MockHttpClient::new().with_route(
+        Route::get("/datasets/v1/release/").respond(MockResponse::fixture("dataset_releases")),
+    ));
+
+    let contents = DatasetReleasesTool::new(harness.http_client(), harness.rate_limiter.clone())
+        .execute(Some(json!({ "limit": 2 })))
+        .await
+        .unwrap();
+
+    let text = text(&contents);
+    assert!(text.contains("Found 3 dataset releases. Showing the 2 most recent"));
+    let newest = text.find("2023-04-04").unwrap();
+    let older = text.find("2023-03-28").unwrap();
+    assert!(newest < older);
+    assert!(!text.contains("2023-03-14"));
+}
+
+#[tokio::test]
+async fn dataset_releases_can_be_empty() {
+    let harness =
+        Harness::new(MockHttpClient::new().with_route(
+            Route::get("/datasets/v1/release/").respond(MockResponse::json(&json!([]))),
+        ));
+
+    let contents = DatasetReleasesTool::new(harness.http_client(), harness.rate_limiter.clone())
+        .execute(None)
+        .await
+        .unwrap();
+
+    assert!(text(&contents).contains("No dataset releases available."));
+}
+
+#[tokio::test]
+async fn dataset_list_describes_each_dataset() {
+    let harness = Harness::new(MockHttpClient::new().with_route(
+        Route::get("/datasets/v1/release/latest").respond(MockResponse::fixture("dataset_list")),
+    ));
+
+    let contents = DatasetListTool::new(harness.http_client(), harness.rate_limiter.clone())
+        .execute(Some(json!({})))
+        .await
+        .unwrap();
+
+    let text = text(&contents);
+    assert!(text.contains("Release 2023-04-04 contains 2 datasets:"));
+    assert!(text.contains("abstracts"));
+    assert!(text.contains("The core attributes of a paper."));
+}
+
+#[tokio::test]
+async fn dataset_download_links_lists_the_files() {
+    set_api_key();
+    let harness = Harness::new(MockHttpClient::new().with_route(
+        Route::get("/datasets/v1/release/2023-04-04/dataset/papers").respond(MockResponse::json(
+            &json!({
+                "name": "papers",
+                "description": "The core attributes of a paper.",
+                "README": "",
+                "files": [
+                    "https://ai2-s2ag.s3.amazonaws.com/papers/part0.jsonl.gz?X-Amz-Signature=a",
+                    "https://ai2-s2ag.s3.amazonaws.com/papers/part1.jsonl.gz?X-Amz-Signature=b",
+                ],
+            }),
+        )),
+    ));
+
+    let contents =
+        DatasetDownloadLinksTool::new(harness.http_client(), harness.rate_limiter.clone())
+            .execute(Some(json!({
+                "release_id": "2023-04-04",
+                "dataset_name": "papers",
+            })))
+            .await
+            .unwrap();
+
+    let text = text(&contents);
+    assert!(text.contains("Dataset papers (release 2023-04-04) is split into 2 files:"));
+    assert!(text.contains("Description: The core attributes of a paper."));
+    assert!(text.contains("part0.jsonl.gz?X-Amz-Signature=a"));
+    assert!(text.contains("part1.jsonl.gz?X-Amz-Signature=b"));
+    assert!(text.contains("pre-signed and expire"));
+
+    let requests = harness.client.requests();
+    assert!(
+        requests[0]
+            .headers
+            .iter()
+            .any(|(name, value)| name.eq_ignore_ascii_case("x-api-key") && value == "test-key")
+    );
+}
+
+#[tokio::test]
+async fn dataset_download_links_can_be_empty() {
+    set_api_key();
+    let harness = Harness::new(MockHttpClient::new().with_route(
+        Route::get("/datasets/v1/release/latest/dataset/abstracts").respond(MockResponse::json(
+            &json!({ "name": "abstracts", "files": [] }),
+        )),
+    ));
+
+    let contents =
+        DatasetDownloadLinksTool::new(harness.http_client(), harness.rate_limiter.clone())
+            .execute(Some(json!({ "dataset_name": "abstracts" })))
+            .await
+            .unwrap();
+
+    assert!(
+        text(&contents).contains("Dataset abstracts in release latest has no downloadable files.")
+    );
+}
+
+#[tokio::test]
+async fn sync_status_reports_nothing_synced() {
+    let harness = Harness::new(MockHttpClient::new());
+
+    let contents = SyncStatusTool::new(dataset_sync(&harness, "sync-status-empty"))
+        .execute(None)
+        .await
+        .unwrap();
+
+    assert!(text(&contents).contains("No datasets are synced locally."));
+}
+
+#[tokio::test]
+async fn sync_run_bootstraps_a_dataset() {
+    set_api_key();
+    let harness = Harness::new(
+        MockHttpClient::new()
+            .with_route(
+                Route::get("/datasets/v1/release/latest")
+                    .respond(MockResponse::fixture("dataset_list")),
+            )
+            .with_route(
+                Route::get("/datasets/v1/release/2023-04-04/dataset/papers").respond(
+                    MockResponse::json(&json!({ "name": "papers", "files": [] })),
+                ),
+            ),
+    );
+    let dataset_sync = dataset_sync(&harness, "sync-run-bootstrap");
+
+    let contents = SyncRunTool::new(dataset_sync.clone())
+        .execute(Some(json!({ "dataset": "papers", "bootstrap": true })))
+        .await
+        .unwrap();
+
+    let report = text(&contents);
+    assert!(report.contains("Bootstrapped dataset papers from release 2023-04-04."));
+    assert!(report.contains("Files processed: 0"));
+
+    let contents = SyncStatusTool::new(dataset_sync.clone())
+        .execute(None)
+        .await
+        .unwrap();
+
+    let status = text(&contents);
+    assert!(status.contains("1 dataset(s) synced locally:"));
+    assert!(status.contains("Release: 2023-04-04"));
+    assert!(status.contains("Records: 0"));
+
+    // Syncing to the release it is at needs nothing from the API
+    let requests = harness.client.request_count();
+    let contents = SyncRunTool::new(dataset_sync)
+        .execute(Some(
+            json!({ "dataset": "papers", "release_id": "2023-04-04" }),
+        ))
+        .await
+        .unwrap();
+
+    assert!(
+        text(&contents).contains("Dataset papers is already up to date at release 2023-04-04.")
+    );
+    assert_eq!(harness.client.request_count(), requests);
+}
+
+#[tokio::test]
+async fn sync_run_needs_a_bootstrap_first() {
+    set_api_key();
+    let harness = Harness::new(MockHttpClient::new());
+
+    let err = SyncRunTool::new(dataset_sync(&harness, "sync-run-no-bootstrap"))
+        .execute(Some(json!({ "dataset": "papers" })))
+        .await
+        .unwrap_err();
+
+    assert!(err.to_string().contains("has not been bootstrapped yet"));
+    assert_eq!(harness.client.request_count(), 0);
+}
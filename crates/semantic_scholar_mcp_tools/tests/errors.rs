@@ -0,0 +1,234 @@
+mod common;
+
+use std::sync::Arc;
+
+use context_server::ToolExecutor;
+use mock_http_client::{MockHttpClient, MockResponse, Route};
+use semantic_scholar_mcp_tools::{
+    ApiStatusTool, AuthorDetailsTool, PaperDetailsTool, PaperSearchTool, ServerSettings, rpc_error,
+    with_settings,
+};
+use serde_json::json;
+
+use crate::common::{Harness, error_kind, text};
+
+const MISSING_ID: &str = "0000000000000000000000000000000000000000";
+
+fn paper_details(harness: &Harness) -> PaperDetailsTool {
+    PaperDetailsTool::new(
+        harness.http_client(),
+        harness.rate_limiter.clone(),
+        harness.cache.clone(),
+        harness.embed.clone(),
+    )
+}
+
+fn paper_search(harness: &Harness) -> PaperSearchTool {
+    PaperSearchTool::new(
+        harness.http_client(),
+        harness.rate_limiter.clone(),
+        harness.cache.clone(),
+        harness.embed.clone(),
+    )
+}
+
+#[tokio::test]
+async fn unknown_paper_is_not_found() {
+    let harness = Harness::new(
+        MockHttpClient::new().with_route(
+            Route::get(&format!("/graph/v1/paper/{}", MISSING_ID))
+                .respond(MockResponse::error(404, "not_found")),
+        ),
+    );
+
+    let err = paper_details(&harness)
+        .execute(Some(json!({ "paper_id": MISSING_ID })))
+        .await
+        .unwrap_err();
+
+    assert_eq!(error_kind(&err), "not_found");
+    assert!(err.to_string().contains("not found"));
+}
+
+#[tokio::test]
+async fn unknown_author_is_not_found() {
+    // Nothing is routed, so every request is answered with a 404
+    let harness = Harness::new(MockHttpClient::new());
+
+    let err = AuthorDetailsTool::new(
+        harness.http_client(),
+        harness.rate_limiter.clone(),
+        harness.cache.clone(),
+        harness.embed.clone(),
+    )
+    .execute(Some(json!({ "author_id": "1" })))
+    .await
+    .unwrap_err();
+
+    assert_eq!(error_kind(&err), "not_found");
+}
+
+#[tokio::test]
+async fn rejected_request_is_an_invalid_argument() {
+    let harness = Harness::new(MockHttpClient::new().with_route(
+        Route::get("/graph/v1/paper/search").respond(MockResponse::new(
+            400,
+            json!({ "error": "Unrecognized or unsupported fields: [foo]" }).to_string(),
+        )),
+    ));
+
+    let err = paper_search(&harness)
+        .execute(Some(json!({ "query": "transformers" })))
+        .await
+        .unwrap_err();
+
+    assert_eq!(error_kind(&err), "invalid_argument");
+    assert!(
+        err.to_string()
+            .contains("Unrecognized or unsupported fields")
+    );
+    assert_eq!(harness.client.request_count(), 1);
+}
+
+#[tokio::test]
+async fn server_error_is_an_upstream_error() {
+    let harness = Harness::new(
+        MockHttpClient::new().with_route(
+            Route::get("/graph/v1/paper/search")
+                .respond(MockResponse::new(500, "Internal Server Error")),
+        ),
+    );
+
+    let err = paper_search(&harness)
+        .execute(Some(json!({ "query": "transformers" })))
+        .await
+        .unwrap_err();
+
+    assert_eq!(error_kind(&err), "upstream_error");
+    assert!(err.to_string().contains("500"));
+}
+
+#[tokio::test]
+async fn rate_limited_request_is_retried() {
+    let harness = Harness::new(
+        MockHttpClient::new().with_route(
+            Route::get("/graph/v1/paper/search")
+                .respond(MockResponse::error(429, "rate_limited").with_header("Retry-After", "0"))
+                .respond(MockResponse::fixture("paper_search")),
+        ),
+    );
+
+    let contents = paper_search(&harness)
+        .execute(Some(json!({ "query": "transformers" })))
+        .await
+        .unwrap();
+
+    assert!(text(&contents).contains("Attention is All you Need"));
+    assert_eq!(harness.client.request_count(), 2);
+    assert_eq!(harness.rate_limiter.stats().snapshot().rate_limited, 1);
+}
+
+#[tokio::test]
+async fn rate_limited_request_gives_up_after_retrying() {
+    let harness = Harness::new(
+        MockHttpClient::new().with_route(
+            Route::get("/graph/v1/paper/search")
+                .respond(MockResponse::error(429, "rate_limited").with_header("Retry-After", "0")),
+        ),
+    );
+
+    let err = paper_search(&harness)
+        .execute(Some(json!({ "query": "transformers" })))
+        .await
+        .unwrap_err();
+
+    assert_eq!(error_kind(&err), "rate_limited");
+    // The first attempt and five retries
+    assert_eq!(harness.client.request_count(), 6);
+}
+
+#[tokio::test]
+async fn a_long_retry_after_is_passed_on_instead_of_waited_for() {
+    let harness =
+        Harness::new(MockHttpClient::new().with_route(
+            Route::get("/graph/v1/paper/search").respond(
+                MockResponse::error(429, "rate_limited").with_header("Retry-After", "3600"),
+            ),
+        ));
+
+    let err = paper_search(&harness)
+        .execute(Some(json!({ "query": "transformers" })))
+        .await
+        .unwrap_err();
+
+    assert_eq!(error_kind(&err), "rate_limited");
+    assert_eq!(rpc_error(&err)["data"]["retry_after_secs"], 3600);
+    assert_eq!(harness.client.request_count(), 1);
+    assert_eq!(
+        harness.rate_limiter.stats().snapshot().backoff_remaining,
+        None
+    );
+}
+
+#[tokio::test]
+async fn api_status_reports_requests_and_rate_limiting() {
+    let harness = Harness::new(
+        MockHttpClient::new().with_route(
+            Route::get("/graph/v1/paper/search")
+                .respond(MockResponse::error(429, "rate_limited").with_header("Retry-After", "0"))
+                .respond(MockResponse::fixture("paper_search")),
+        ),
+    );
+    paper_search(&harness)
+        .execute(Some(json!({ "query": "transformers" })))
+        .await
+        .unwrap();
+
+    let contents = ApiStatusTool::new(harness.rate_limiter.clone())
+        .execute(None)
+        .await
+        .unwrap();
+
+    let text = text(&contents);
+    assert!(text.contains("Requests since startup: 2"));
+    assert!(text.contains("Rate limited (429) responses: 1 (last "));
+    assert!(text.contains("Backoff: none"));
+    assert!(text.contains("Circuit breaker: closed"));
+    assert!(text.contains("The API rate limited requests in the last minute."));
+}
+
+#[tokio::test]
+async fn api_status_starts_quiet() {
+    let harness = Harness::new(MockHttpClient::new());
+
+    let contents = ApiStatusTool::new(harness.rate_limiter.clone())
+        .execute(None)
+        .await
+        .unwrap();
+
+    let text = text(&contents);
+    assert!(text.contains("Requests since startup: 0"));
+    assert!(text.contains("Rate limited (429) responses: 0\n"));
+    assert!(!text.contains("rate limited requests in the last minute"));
+}
+
+#[tokio::test]
+async fn offline_settings_keep_requests_from_being_sent() {
+    let harness = Harness::new(MockHttpClient::new().with_route(
+        Route::get("/graph/v1/paper/search").respond(MockResponse::fixture("paper_search")),
+    ));
+    let tool = paper_search(&harness);
+    let offline = Arc::new(ServerSettings::new().with_offline(true));
+
+    let (offline, online) = tokio::join!(
+        with_settings(
+            offline,
+            tool.execute(Some(json!({ "query": "graph networks" })))
+        ),
+        tool.execute(Some(json!({ "query": "transformers" }))),
+    );
+
+    assert_eq!(error_kind(&offline.unwrap_err()), "not_cached");
+    assert!(text(&online.unwrap()).contains("Attention is All you Need"));
+    assert_eq!(harness.client.request_count(), 1);
+}
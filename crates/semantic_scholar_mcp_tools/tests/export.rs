@@ -0,0 +1,264 @@
+mod common;
+
+use context_server::ToolExecutor;
+use mock_http_client::{MockHttpClient, MockResponse, Route};
+use semantic_scholar_mcp_tools::{
+    ExportRisTool, FormatCitationTool, MarkdownBibliographyTool, ZoteroPushTool,
+};
+use serde_json::{Value, json};
+
+use crate::common::{Harness, text};
+
+const ATTENTION_ID: &str = "204e3073870fae3d05bcbc2f6a8e263d9b72e776";
+const BERT_ID: &str = "df2b0e26d0599ce3e70df8a9da02e51594e0e992";
+const MISSING_ID: &str = "0000000000000000000000000000000000000000";
+
+/// A client answering batch lookups with Attention, and nothing for the
+/// second ID.
+fn batch_client() -> MockHttpClient {
+    MockHttpClient::new().with_route(
+        Route::post("/graph/v1/paper/batch").respond(MockResponse::fixture("paper_batch")),
+    )
+}
+
+fn bert_and_attention() -> Value {
+    json!([
+        {
+            "paperId": BERT_ID,
+            "title": "BERT: Pre-training of Deep Bidirectional Transformers",
+            "year": 2019,
+            "journal": { "name": "NAACL", "pages": "4171-4186" },
+            "authors": [{ "authorId": "39172707", "name": "Jacob Devlin" }],
+        },
+        {
+            "paperId": ATTENTION_ID,
+            "title": "Attention is All you Need",
+            "year": 2017,
+            "venue": "Neural Information Processing Systems",
+            "externalIds": { "DOI": "10.5555/3295222.3295349" },
+            "authors": [{ "authorId": "40348417", "name": "Ashish Vaswani" }],
+        },
+    ])
+}
+
+#[tokio::test]
+async fn export_ris_writes_a_record_per_paper() {
+    let harness = Harness::new(batch_client());
+
+    let contents = ExportRisTool::new(
+        harness.http_client(),
+        harness.rate_limiter.clone(),
+        harness.cache.clone(),
+    )
+    .execute(Some(json!({
+        "paper_ids": [ATTENTION_ID, MISSING_ID],
+        "include_abstract": true,
+    })))
+    .await
+    .unwrap();
+
+    let ris = text(&contents);
+    assert!(ris.starts_with("TY  - GEN\n"));
+    assert!(ris.contains("TI  - Attention is All you Need\n"));
+    assert!(ris.contains("AU  - Vaswani, Ashish\n"));
+    assert!(ris.contains("AU  - Shazeer, Noam M.\n"));
+    assert!(ris.contains("PY  - 2017\n"));
+    assert!(ris.contains("T2  - Neural Information Processing Systems\n"));
+    assert!(ris.contains("AB  - The dominant sequence transduction models"));
+    assert!(ris.contains(&format!("ID  - {}\n", ATTENTION_ID)));
+    assert!(ris.ends_with("ER  - \n"));
+    // The note stays out of the RIS so it can be imported as is
+    assert!(!ris.contains(MISSING_ID));
+    assert_eq!(
+        text(&contents[1..]),
+        format!("Could not resolve 1 paper ID(s): {}", MISSING_ID)
+    );
+}
+
+#[tokio::test]
+async fn export_ris_leaves_out_the_abstract_by_default() {
+    let harness = Harness::new(batch_client());
+
+    let contents = ExportRisTool::new(
+        harness.http_client(),
+        harness.rate_limiter.clone(),
+        harness.cache.clone(),
+    )
+    .execute(Some(json!({ "paper_ids": [ATTENTION_ID, MISSING_ID] })))
+    .await
+    .unwrap();
+
+    assert!(!text(&contents).contains("AB  - "));
+}
+
+fn markdown_bibliography(harness: &Harness) -> MarkdownBibliographyTool {
+    MarkdownBibliographyTool::new(
+        harness.http_client(),
+        harness.rate_limiter.clone(),
+        harness.cache.clone(),
+    )
+}
+
+#[tokio::test]
+async fn markdown_bibliography_links_each_title() {
+    let harness = Harness::new(batch_client());
+
+    let contents = markdown_bibliography(&harness)
+        .execute(Some(json!({
+            "paper_ids": [ATTENTION_ID, MISSING_ID],
+            "max_authors": 2,
+        })))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        text(&contents),
+        format!(
+            "1. [Attention is All you Need](https://www.semanticscholar.org/paper/{}). Ashish Vaswani, Noam M. Shazeer et al. *Neural Information Processing Systems*, 2017.",
+            ATTENTION_ID
+        )
+    );
+    assert_eq!(
+        text(&contents[1..]),
+        format!("Could not resolve 1 paper ID(s): {}", MISSING_ID)
+    );
+}
+
+#[tokio::test]
+async fn markdown_bibliography_orders_by_year_or_author() {
+    let harness = Harness::new(MockHttpClient::new().with_route(
+        Route::post("/graph/v1/paper/batch").respond(MockResponse::json(&bert_and_attention())),
+    ));
+
+    let contents = markdown_bibliography(&harness)
+        .execute(Some(
+            json!({ "paper_ids": [BERT_ID, ATTENTION_ID], "order": "year" }),
+        ))
+        .await
+        .unwrap();
+
+    let by_year = text(&contents);
+    assert!(by_year.starts_with("1. Attention is All you Need. Ashish Vaswani."));
+    assert!(
+        by_year.contains("DOI: [10.5555/3295222.3295349](https://doi.org/10.5555/3295222.3295349)")
+    );
+    assert!(by_year.contains(
+        "2. BERT: Pre-training of Deep Bidirectional Transformers. Jacob Devlin. *NAACL*, 2019."
+    ));
+
+    let contents = markdown_bibliography(&harness)
+        .execute(Some(
+            json!({ "paper_ids": [ATTENTION_ID, BERT_ID], "order": "author" }),
+        ))
+        .await
+        .unwrap();
+
+    assert!(text(&contents).starts_with("1. BERT"));
+}
+
+fn format_citation(harness: &Harness) -> FormatCitationTool {
+    FormatCitationTool::new(
+        harness.http_client(),
+        harness.rate_limiter.clone(),
+        harness.cache.clone(),
+    )
+}
+
+#[tokio::test]
+async fn format_citation_renders_apa_by_default() {
+    let harness = Harness::new(batch_client());
+
+    let contents = format_citation(&harness)
+        .execute(Some(json!({ "paper_ids": [ATTENTION_ID, MISSING_ID] })))
+        .await
+        .unwrap();
+
+    let text = text(&contents);
+    assert!(text.contains("Vaswani"));
+    assert!(text.contains("2017"));
+    assert!(text.contains("Attention is All you Need"));
+    assert!(text.contains("   In-text: "));
+    assert!(text.contains(&format!("   Paper ID: {}", ATTENTION_ID)));
+    assert!(text.ends_with(&format!("Could not resolve 1 paper ID(s): {}", MISSING_ID)));
+}
+
+#[tokio::test]
+async fn format_citation_numbers_ieee_references_in_order() {
+    let harness = Harness::new(MockHttpClient::new().with_route(
+        Route::post("/graph/v1/paper/batch").respond(MockResponse::json(&bert_and_attention())),
+    ));
+
+    let contents = format_citation(&harness)
+        .execute(Some(
+            json!({ "paper_ids": [BERT_ID, ATTENTION_ID], "style": "ieee" }),
+        ))
+        .await
+        .unwrap();
+
+    let text = text(&contents);
+    assert!(text.starts_with("[1] "));
+    assert!(text.contains("   In-text: [1]"));
+    assert!(text.contains("   In-text: [2]"));
+    let bert = text.find("Devlin").unwrap();
+    let attention = text.find("Vaswani").unwrap();
+    assert!(bert < attention);
+}
+
+#[tokio::test]
+async fn zotero_push_creates_an_item_per_paper() {
+    // No other test reads the Zotero settings
+    unsafe {
+        std::env::set_var("ZOTERO_API_KEY", "zotero-test-key");
+        std::env::set_var("ZOTERO_USER_ID", "123456");
+    }
+    let harness = Harness::new(batch_client().with_route(
+        Route::post("/users/123456/items").respond(MockResponse::json(&json!({
+            "success": { "0": "ABCD2345" },
+            "unchanged": {},
+            "failed": {},
+        }))),
+    ));
+
+    let contents = ZoteroPushTool::new(
+        harness.http_client(),
+        harness.rate_limiter.clone(),
+        harness.cache.clone(),
+    )
+    .execute(Some(json!({
+        "paper_ids": [ATTENTION_ID, MISSING_ID],
+        "collection": "COLL1234",
+        "tags": ["transformers"],
+    })))
+    .await
+    .unwrap();
+
+    let text = text(&contents);
+    assert!(text.starts_with("Added 1 of 1 papers to the Zotero library:"));
+    assert!(text.contains("1. Attention is All you Need (Zotero key: ABCD2345)"));
+    assert!(text.contains(&format!("Could not resolve 1 paper ID(s): {}", MISSING_ID)));
+
+    let requests = harness.client.requests();
+    let push = &requests[1];
+    assert_eq!(push.path, "/users/123456/items");
+    assert!(
+        push.headers
+            .iter()
+            .any(|(name, value)| name.eq_ignore_ascii_case("Zotero-API-Key")
+                && value == "zotero-test-key")
+    );
+    let item = &push.body.as_ref().unwrap()[0];
+    assert_eq!(item["title"], "Attention is All you Need");
+    assert_eq!(
+        item["creators"][0],
+        json!({ "creatorType": "author", "firstName": "Ashish", "lastName": "Vaswani" })
+    );
+    assert_eq!(item["date"], "2017");
+    assert_eq!(item["tags"], json!([{ "tag": "transformers" }]));
+    assert_eq!(item["collections"], json!(["COLL1234"]));
+    assert!(
+        item["extra"]
+            .as_str()
+            .unwrap()
+            .contains(&format!("Semantic Scholar ID: {}", ATTENTION_ID))
+    );
+}
@@ -0,0 +1,323 @@
+mod common;
+
+use context_server::ToolExecutor;
+use mock_http_client::{MockHttpClient, MockResponse, Route, fixture};
+use semantic_scholar_mcp_tools::{
+    CitationPathTool, GraphExpandTool, GraphExportTool, GraphRankTool, SnowballTool,
+};
+use serde_json::json;
+
+use crate::common::{Harness, structured, text};
+
+const ATTENTION_ID: &str = "204e3073870fae3d05bcbc2f6a8e263d9b72e776";
+const BERT_ID: &str = "df2b0e26d0599ce3e70df8a9da02e51594e0e992";
+const GPT3_ID: &str = "90abbc2cf38462b954ae1b772fac9532e2ccd8b0";
+const ADAM_ID: &str = "a6cb366736791bcccc5c8639de5a8f9636bf87e8";
+const MISSING_ID: &str = "0000000000000000000000000000000000000000";
+
+fn graph_client() -> MockHttpClient {
+    MockHttpClient::new()
+        .with_route(
+            Route::post("/graph/v1/paper/batch").respond(MockResponse::fixture("paper_batch")),
+        )
+        .with_route(
+            Route::get(&format!("/graph/v1/paper/{}/citations", ATTENTION_ID))
+                .respond(MockResponse::fixture("paper_citations")),
+        )
+        .with_route(
+            Route::get(&format!("/graph/v1/paper/{}/references", ATTENTION_ID))
+                .respond(MockResponse::fixture("paper_references")),
+        )
+}
+
+fn graph_expand(harness: &Harness) -> GraphExpandTool {
+    GraphExpandTool::new(
+        harness.http_client(),
+        harness.rate_limiter.clone(),
+        harness.cache.clone(),
+        harness.embed.clone(),
+    )
+}
+
+#[tokio::test]
+async fn graph_expand_keeps_the_most_cited_papers_of_a_hop() {
+    let harness = Harness::new(graph_client());
+
+    let contents = graph_expand(&harness)
+        .execute(Some(json!({
+            "paper_ids": [ATTENTION_ID, MISSING_ID],
+            "per_hop_limit": 3,
+        })))
+        .await
+        .unwrap();
+
+    let graph = &structured(&contents)["data"];
+    assert_eq!(graph["seeds"], json!([ATTENTION_ID]));
+    assert_eq!(graph["unresolved"], json!([MISSING_ID]));
+
+    let nodes: Vec<(&str, u64)> = graph["nodes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|node| {
+            (
+                node["paperId"].as_str().unwrap(),
+                node["hop"].as_u64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        nodes,
+        [(ATTENTION_ID, 0), (ADAM_ID, 1), (BERT_ID, 1), (GPT3_ID, 1)]
+    );
+    assert_eq!(
+        graph["edges"],
+        json!([
+            { "source": ATTENTION_ID, "target": ADAM_ID },
+            { "source": BERT_ID, "target": ATTENTION_ID },
+            { "source": GPT3_ID, "target": ATTENTION_ID },
+        ])
+    );
+
+    let text = text(&contents);
+    assert!(text.contains("Citation graph of 4 papers and 3 citations"));
+    assert!(text.contains("Hop 1 (3 papers):"));
+    assert!(!text.contains("Sequence to Sequence Learning"));
+    assert!(text.contains(&format!("Could not resolve 1 paper ID(s): {}", MISSING_ID)));
+
+    let requests = harness.client.requests();
+    assert_eq!(requests.len(), 3);
+    assert_eq!(
+        requests[0].query_param("fields"),
+        Some("paperId,title,citationCount,year")
+    );
+}
+
+#[tokio::test]
+async fn graph_expand_follows_one_direction() {
+    let harness = Harness::new(graph_client());
+
+    let contents = graph_expand(&harness)
+        .execute(Some(
+            json!({ "paper_ids": [ATTENTION_ID], "direction": "citations" }),
+        ))
+        .await
+        .unwrap();
+
+    let graph = &structured(&contents)["data"];
+    assert_eq!(graph["nodes"].as_array().unwrap().len(), 3);
+    assert!(
+        graph["edges"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .all(|edge| edge["target"] == ATTENTION_ID)
+    );
+    assert!(
+        harness
+            .client
+            .requests()
+            .iter()
+            .all(|request| !request.path.ends_with("/references"))
+    );
+}
+
+fn graph_export(harness: &Harness) -> GraphExportTool {
+    GraphExportTool::new(
+        harness.http_client(),
+        harness.rate_limiter.clone(),
+        harness.cache.clone(),
+    )
+}
+
+#[tokio::test]
+async fn graph_export_writes_graphml() {
+    let harness = Harness::new(graph_client());
+
+    let contents = graph_export(&harness)
+        .execute(Some(json!({ "paper_ids": [ATTENTION_ID, MISSING_ID] })))
+        .await
+        .unwrap();
+
+    let graphml = text(&contents);
+    assert!(graphml.starts_with("<?xml"));
+    assert!(graphml.contains("<graph id=\"G\" edgedefault=\"directed\">"));
+    assert!(graphml.contains(&format!("<node id=\"{}\">", ATTENTION_ID)));
+    assert!(graphml.contains("<data key=\"label\">Attention is All you Need</data>"));
+    assert!(graphml.contains(&format!(
+        "<edge source=\"{}\" target=\"{}\"/>",
+        BERT_ID, ATTENTION_ID
+    )));
+    assert!(graphml.trim_end().ends_with("</graphml>"));
+
+    // Unresolved IDs are reported outside the export
+    assert_eq!(contents.len(), 2);
+}
+
+#[tokio::test]
+async fn graph_export_writes_the_coauthorship_graph_as_dot() {
+    let harness = Harness::new(graph_client());
+
+    let contents = graph_export(&harness)
+        .execute(Some(json!({
+            "paper_ids": [ATTENTION_ID],
+            "graph": "coauthorship",
+            "format": "dot",
+        })))
+        .await
+        .unwrap();
+
+    let dot = text(&contents);
+    assert!(dot.starts_with("graph G {"));
+    assert!(dot.contains("\"40348417\" [label=\"Ashish Vaswani\", papers=\"1\"];"));
+    assert!(dot.contains("\"1846258\" -- \"40348417\" [weight=1];"));
+    assert_eq!(
+        harness.client.requests()[0].query_param("fields"),
+        Some("paperId,title,citationCount,authors")
+    );
+}
+
+fn citation_path(harness: &Harness) -> CitationPathTool {
+    CitationPathTool::new(
+        harness.http_client(),
+        harness.rate_limiter.clone(),
+        harness.cache.clone(),
+        harness.embed.clone(),
+    )
+}
+
+#[tokio::test]
+async fn citation_path_finds_the_chain_between_two_papers() {
+    let batch = json!([
+        fixture("paper_batch")[0],
+        { "paperId": ADAM_ID, "title": "Adam: A Method for Stochastic Optimization", "citationCount": 100000 },
+    ]);
+    let client = MockHttpClient::new()
+        .with_route(Route::post("/graph/v1/paper/batch").respond(MockResponse::json(&batch)))
+        .with_route(
+            Route::get(&format!("/graph/v1/paper/{}/references", ATTENTION_ID))
+                .respond(MockResponse::fixture("paper_references")),
+        );
+    let harness = Harness::new(client);
+
+    let contents = citation_path(&harness)
+        .execute(Some(json!({
+            "from_paper_id": ATTENTION_ID,
+            "to_paper_id": ADAM_ID,
+        })))
+        .await
+        .unwrap();
+
+    let result = &structured(&contents)["data"];
+    let path: Vec<&str> = result["path"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|paper| paper["paperId"].as_str().unwrap())
+        .collect();
+    assert_eq!(path, [ATTENTION_ID, ADAM_ID]);
+
+    let text = text(&contents);
+    assert!(text.contains("Citation chain of 1 step(s)"));
+    assert!(text.contains("Cites: Adam: A Method for Stochastic Optimization"));
+
+    // Meeting after one step, the citations of Adam are never fetched
+    assert_eq!(harness.client.requests().len(), 2);
+}
+
+fn graph_rank(harness: &Harness) -> GraphRankTool {
+    GraphRankTool::new(
+        harness.http_client(),
+        harness.rate_limiter.clone(),
+        harness.cache.clone(),
+        harness.embed.clone(),
+    )
+}
+
+#[tokio::test]
+async fn graph_rank_puts_the_paper_everyone_cites_first() {
+    let harness = Harness::new(graph_client());
+
+    let contents = graph_rank(&harness)
+        .execute(Some(
+            json!({ "paper_ids": [ATTENTION_ID], "direction": "citations" }),
+        ))
+        .await
+        .unwrap();
+
+    let ranking = &structured(&contents)["data"];
+    let ranked = ranking["ranked"].as_array().unwrap();
+    assert_eq!(
+        ranked.len(),
+        ranking["nodeCount"].as_u64().unwrap() as usize
+    );
+    assert_eq!(ranked[0]["paperId"], ATTENTION_ID);
+    assert_eq!(ranked[0]["citedInGraph"], ranking["edgeCount"]);
+
+    let total: f64 = ranked
+        .iter()
+        .map(|paper| paper["score"].as_f64().unwrap())
+        .sum();
+    assert!((total - 1.0).abs() < 1e-6);
+
+    assert!(text(&contents).contains("by PageRank:"));
+}
+
+#[tokio::test]
+async fn graph_rank_scores_hubs_and_authorities() {
+    let harness = Harness::new(graph_client());
+
+    let contents = graph_rank(&harness)
+        .execute(Some(json!({
+            "paper_ids": [ATTENTION_ID],
+            "direction": "citations",
+            "algorithm": "hits",
+            "top": 2,
+        })))
+        .await
+        .unwrap();
+
+    let ranked = structured(&contents)["data"]["ranked"]
+        .as_array()
+        .unwrap()
+        .clone();
+    assert_eq!(ranked.len(), 2);
+    assert_eq!(ranked[0]["paperId"], ATTENTION_ID);
+    assert_eq!(ranked[0]["hubScore"], 0.0);
+    assert!(ranked[1]["hubScore"].as_f64().unwrap() > 0.0);
+}
+
+#[tokio::test]
+async fn snowball_search_reports_the_screening_funnel() {
+    let harness = Harness::new(graph_client());
+
+    let contents = SnowballTool::new(
+        harness.http_client(),
+        harness.rate_limiter.clone(),
+        harness.cache.clone(),
+        harness.embed.clone(),
+    )
+    .execute(Some(json!({
+        "paper_ids": [ATTENTION_ID],
+        "year": "-2019",
+        "venue": ["Neural Information"],
+    })))
+    .await
+    .unwrap();
+
+    let result = &structured(&contents)["data"];
+    assert_eq!(
+        result["funnel"],
+        json!([{
+            "round": 1,
+            "fetched": 4,
+            "new": 4,
+            "inYears": 3,
+            "inVenues": 1,
+            "matchingKeywords": 1,
+            "included": 1,
+        }])
+    );
+    assert_eq!(result["papers"].as_array().unwrap().len(), 1);
+    assert!(text(&contents).contains("Sequence to Sequence Learning"));
+}
@@ -0,0 +1,120 @@
+mod common;
+
+use context_server::ToolExecutor;
+use mock_http_client::MockHttpClient;
+use semantic_scholar_mcp_tools::{AuthorDetailsTool, PaperDetailsTool};
+use serde_json::json;
+
+use crate::common::Harness;
+
+/// The path `paper_details` requests for a paper identifier.
+async fn paper_path(paper_id: &str) -> String {
+    let harness = Harness::new(MockHttpClient::new());
+    let _ = PaperDetailsTool::new(
+        harness.http_client(),
+        harness.rate_limiter.clone(),
+        harness.cache.clone(),
+        harness.embed.clone(),
+    )
+    .execute(Some(json!({ "paper_id": paper_id })))
+    .await;
+    harness.client.requests()[0].path.clone()
+}
+
+#[tokio::test]
+async fn paper_ids_are_normalized() {
+    let cases = [
+        (
+            "204e3073870fae3d05bcbc2f6a8e263d9b72e776",
+            "/graph/v1/paper/204e3073870fae3d05bcbc2f6a8e263d9b72e776",
+        ),
+        (
+            "doi:10.18653/v1/N19-1423",
+            "/graph/v1/paper/DOI:10.18653%2Fv1%2FN19-1423",
+        ),
+        (
+            " 10.18653/v1/N19-1423 ",
+            "/graph/v1/paper/DOI:10.18653%2Fv1%2FN19-1423",
+        ),
+        (
+            "https://doi.org/10.18653/v1/N19-1423",
+            "/graph/v1/paper/DOI:10.18653%2Fv1%2FN19-1423",
+        ),
+        (
+            " DOI: 10.18653/v1/N19-1423",
+            "/graph/v1/paper/DOI:10.18653%2Fv1%2FN19-1423",
+        ),
+        (
+            "doi:https://doi.org/10.18653/v1/N19-1423",
+            "/graph/v1/paper/DOI:10.18653%2Fv1%2FN19-1423",
+        ),
+        ("1706.03762v7", "/graph/v1/paper/ARXIV:1706.03762"),
+        ("arXiv:2106.09685v2", "/graph/v1/paper/ARXIV:2106.09685"),
+        ("ARXIV: 2106.09685 ", "/graph/v1/paper/ARXIV:2106.09685"),
+        (
+            "arxiv:https://arxiv.org/abs/2106.09685v2",
+            "/graph/v1/paper/ARXIV:2106.09685",
+        ),
+        (
+            "arXiv:hep-th/9901001v3",
+            "/graph/v1/paper/ARXIV:hep-th%2F9901001",
+        ),
+        (
+            "https://arxiv.org/abs/2106.09685v2",
+            "/graph/v1/paper/ARXIV:2106.09685",
+        ),
+        (
+            "https://arxiv.org/abs/1706.03762",
+            "/graph/v1/paper/ARXIV:1706.03762",
+        ),
+        (
+            "arxiv.org/pdf/1706.03762v5.pdf",
+            "/graph/v1/paper/ARXIV:1706.03762",
+        ),
+        ("hep-th/9901001", "/graph/v1/paper/ARXIV:hep-th%2F9901001"),
+        (
+            "https://www.semanticscholar.org/paper/Attention-is-All-you-Need-Vaswani-Shazeer/204e3073870fae3d05bcbc2f6a8e263d9b72e776",
+            "/graph/v1/paper/204e3073870fae3d05bcbc2f6a8e263d9b72e776",
+        ),
+        (
+            "https://pubmed.ncbi.nlm.nih.gov/19872477/",
+            "/graph/v1/paper/PMID:19872477",
+        ),
+        (
+            "https://www.ncbi.nlm.nih.gov/pmc/articles/PMC2323736/",
+            "/graph/v1/paper/PMCID:2323736",
+        ),
+        (
+            "https://aclanthology.org/N19-1423.pdf",
+            "/graph/v1/paper/ACL:N19-1423",
+        ),
+        (
+            "https://www.biorxiv.org/content/10.1101/2020.04.16.045708v1",
+            "/graph/v1/paper/URL:https:%2F%2Fwww.biorxiv.org%2Fcontent%2F10.1101%2F2020.04.16.045708v1",
+        ),
+    ];
+
+    for (paper_id, path) in cases {
+        assert_eq!(paper_path(paper_id).await, path, "for {}", paper_id);
+    }
+}
+
+#[tokio::test]
+async fn author_links_are_normalized() {
+    let harness = Harness::new(MockHttpClient::new());
+    let _ = AuthorDetailsTool::new(
+        harness.http_client(),
+        harness.rate_limiter.clone(),
+        harness.cache.clone(),
+        harness.embed.clone(),
+    )
+    .execute(Some(
+        json!({ "author_id": "https://www.semanticscholar.org/author/Ashish-Vaswani/40348417" }),
+    ))
+    .await;
+
+    assert_eq!(
+        harness.client.requests()[0].path,
+        "/graph/v1/author/40348417"
+    );
+}
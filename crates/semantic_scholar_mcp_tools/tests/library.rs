@@ -0,0 +1,602 @@
+mod common;
+
+use std::sync::Arc;
+
+use context_server::{ToolContent, ToolExecutor};
+use library::{Library, LibraryEntry, MatchMethod};
+use mock_http_client::{MockHttpClient, MockResponse, Route};
+use semantic_scholar_mcp_tools::{
+    LibraryAddTool, LibraryExportTool, LibraryImportBibtexTool, LibraryListTool, LibraryRemoveTool,
+};
+use serde_json::{Value, json};
+
+use crate::common::{Harness, error_kind, library, structured, text};
+
+const ATTENTION_ID: &str = "204e3073870fae3d05bcbc2f6a8e263d9b72e776";
+const BERT_ID: &str = "df2b0e26d0599ce3e70df8a9da02e51594e0e992";
+const MISSING_ID: &str = "0000000000000000000000000000000000000000";
+
+fn attention() -> Value {
+    json!({
+        "paperId": ATTENTION_ID,
+        "title": "Attention is All you Need",
+        "year": 2017,
+        "venue": "Neural Information Processing Systems",
+        "publicationTypes": ["Conference"],
+        "externalIds": { "DOI": "10.5555/3295222.3295349" },
+        "url": "https://www.semanticscholar.org/paper/204e3073870fae3d05bcbc2f6a8e263d9b72e776",
+        "authors": [
+            { "authorId": "40348417", "name": "Ashish Vaswani" },
+            { "authorId": "1846258", "name": "Noam M. Shazeer" },
+        ],
+    })
+}
+
+fn bert() -> Value {
+    json!({
+        "paperId": BERT_ID,
+        "title": "BERT: Pre-training of Deep Bidirectional Transformers",
+        "year": 2019,
+        "venue": "North American Chapter of the Association for Computational Linguistics",
+        "publicationTypes": ["JournalArticle"],
+        "journal": { "name": "NAACL", "pages": "4171-4186" },
+        "authors": [{ "authorId": "39172707", "name": "Jacob Devlin" }],
+    })
+}
+
+fn batch_client(papers: Value) -> MockHttpClient {
+    MockHttpClient::new()
+        .with_route(Route::post("/graph/v1/paper/batch").respond(MockResponse::json(&papers)))
+}
+
+fn entry(key: &str, paper_id: Option<&str>, tags: &[&str]) -> LibraryEntry {
+    LibraryEntry {
+        key: key.to_string(),
+        paper_id: paper_id.map(String::from),
+        title: None,
+        doi: None,
+        entry_type: None,
+        matched_by: None,
+        match_score: None,
+        tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        added_at: chrono::NaiveDate::from_ymd_opt(2026, 1, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap(),
+    }
+}
+
+fn library_add(harness: &Harness, library: &Arc<Library>) -> LibraryAddTool {
+    LibraryAddTool::new(
+        harness.http_client(),
+        harness.rate_limiter.clone(),
+        harness.cache.clone(),
+        library.clone(),
+    )
+}
+
+fn library_remove(harness: &Harness, library: &Arc<Library>) -> LibraryRemoveTool {
+    LibraryRemoveTool::new(
+        harness.http_client(),
+        harness.rate_limiter.clone(),
+        harness.cache.clone(),
+        library.clone(),
+    )
+}
+
+fn library_export(harness: &Harness, library: &Arc<Library>) -> LibraryExportTool {
+    LibraryExportTool::new(
+        harness.http_client(),
+        harness.rate_limiter.clone(),
+        harness.cache.clone(),
+        library.clone(),
+    )
+}
+
+#[tokio::test]
+async fn library_export_writes_bibtex_under_the_library_keys() {
+    let harness = Harness::new(batch_client(json!([bert(), attention()])));
+    let library = library("export_bibtex");
+    library
+        .put(&[
+            entry("devlin2019bert", Some(BERT_ID), &["pretraining"]),
+            entry(
+                "vaswani2017attention",
+                Some(ATTENTION_ID),
+                &["transformers", "nlp"],
+            ),
+        ])
+        .unwrap();
+
+    let contents = library_export(&harness, &library)
+        .execute(Some(json!({})))
+        .await
+        .unwrap();
+
+    assert_eq!(contents.len(), 1);
+    let bibtex = text(&contents);
+    assert!(bibtex.contains("@article{devlin2019bert,\n"));
+    assert!(bibtex.contains("  journal = {NAACL}"));
+    assert!(bibtex.contains("  pages = {4171--4186}"));
+    assert!(bibtex.contains("@inproceedings{vaswani2017attention,\n"));
+    assert!(bibtex.contains("  title = {{Attention is All you Need}}"));
+    assert!(bibtex.contains("  author = {Vaswani, Ashish and Shazeer, Noam M.}"));
+    assert!(bibtex.contains("  booktitle = {Neural Information Processing Systems}"));
+    assert!(bibtex.contains("  doi = {10.5555/3295222.3295349}"));
+    assert!(bibtex.contains("  keywords = {transformers, nlp}"));
+
+    let requests = harness.client.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(
+        requests[0].body,
+        Some(json!({ "ids": [BERT_ID, ATTENTION_ID] }))
+    );
+}
+
+#[tokio::test]
+async fn library_export_writes_ris_with_tags_as_keywords() {
+    let harness = Harness::new(batch_client(json!([attention()])));
+    let library = library("export_ris");
+    library
+        .put(&[entry(
+            "vaswani2017attention",
+            Some(ATTENTION_ID),
+            &["transformers"],
+        )])
+        .unwrap();
+
+    let contents = library_export(&harness, &library)
+        .execute(Some(json!({ "format": "ris" })))
+        .await
+        .unwrap();
+
+    let ris = text(&contents);
+    assert!(ris.starts_with("TY  - CONF\n"));
+    assert!(ris.contains("AU  - Vaswani, Ashish\n"));
+    assert!(ris.contains("DO  - 10.5555/3295222.3295349\n"));
+    assert!(ris.contains("KW  - transformers\n"));
+    assert!(ris.ends_with("ER  - \n"));
+}
+
+#[tokio::test]
+async fn library_export_only_writes_the_tagged_papers() {
+    let harness = Harness::new(batch_client(json!([attention()])));
+    let library = library("export_tag");
+    library
+        .put(&[
+            entry("devlin2019bert", Some(BERT_ID), &["pretraining"]),
+            entry(
+                "vaswani2017attention",
+                Some(ATTENTION_ID),
+                &["transformers"],
+            ),
+        ])
+        .unwrap();
+    let tool = library_export(&harness, &library);
+
+    let contents = tool
+        .execute(Some(json!({ "tag": " transformers " })))
+        .await
+        .unwrap();
+
+    let bibtex = text(&contents);
+    assert!(bibtex.contains("@inproceedings{vaswani2017attention,"));
+    assert!(!bibtex.contains("devlin2019bert"));
+    assert_eq!(
+        harness.client.requests()[0].body,
+        Some(json!({ "ids": [ATTENTION_ID] }))
+    );
+
+    let contents = tool
+        .execute(Some(json!({ "tag": "vision" })))
+        .await
+        .unwrap();
+    assert_eq!(
+        text(&contents),
+        "No papers in the library are tagged 'vision'."
+    );
+    assert_eq!(harness.client.request_count(), 1);
+}
+
+#[tokio::test]
+async fn library_export_falls_back_to_the_stored_entry() {
+    let harness = Harness::new(batch_client(json!([null])));
+    let library = library("export_fallback");
+    let mut unresolved = entry("smith2020unknown", None, &[]);
+    unresolved.title = Some("An Unresolved Paper".to_string());
+    unresolved.doi = Some("10.1000/xyz_123".to_string());
+    unresolved.entry_type = Some("Article".to_string());
+    let mut missing = entry("vaswani2017attention", Some(ATTENTION_ID), &[]);
+    missing.title = Some("Attention is All you Need".to_string());
+    library.put(&[unresolved, missing]).unwrap();
+
+    let contents = library_export(&harness, &library)
+        .execute(Some(json!({})))
+        .await
+        .unwrap();
+
+    let bibtex = text(&contents);
+    assert!(bibtex.contains("@article{smith2020unknown,\n"));
+    assert!(bibtex.contains("  title = {{An Unresolved Paper}}"));
+    assert!(bibtex.contains("  doi = {10.1000/xyz_123}"));
+    assert!(bibtex.contains("@misc{vaswani2017attention,\n"));
+    assert!(bibtex.contains("  title = {{Attention is All you Need}}"));
+
+    match contents.get(1) {
+        Some(ToolContent::Text { text }) => assert!(text.starts_with(
+            "2 entries were written from what the library stored, as their papers are unresolved or could not be fetched: smith2020unknown, vaswani2017attention"
+        )),
+        _ => panic!("Expected a note on the incomplete entries"),
+    }
+}
+
+#[tokio::test]
+async fn library_add_saves_papers_under_citation_keys() {
+    let harness = Harness::new(batch_client(json!([attention(), null])));
+    let library = library("add");
+    let tool = library_add(&harness, &library);
+
+    let contents = tool
+        .execute(Some(json!({
+            "paper_ids": [ATTENTION_ID, MISSING_ID],
+            "tags": ["transformers", " ", "transformers"],
+        })))
+        .await
+        .unwrap();
+
+    let text = text(&contents);
+    assert!(text.starts_with(
+        "Saved 1 papers to the library: 1 new, 0 already saved.\nTagged: transformers\n"
+    ));
+    assert!(text.contains("- vaswani2017attention: Attention is All you Need"));
+    assert!(text.contains(&format!("Could not resolve 1 paper ID(s): {}", MISSING_ID)));
+    assert_eq!(
+        structured(&contents)["data"],
+        json!({
+            "added": ["vaswani2017attention"],
+            "already_saved": [],
+            "tags": ["transformers"],
+            "missing": [MISSING_ID],
+        })
+    );
+
+    let entry = library.get("vaswani2017attention").unwrap().unwrap();
+    assert_eq!(entry.paper_id.as_deref(), Some(ATTENTION_ID));
+    assert_eq!(entry.doi.as_deref(), Some("10.5555/3295222.3295349"));
+    assert_eq!(entry.tags, ["transformers"]);
+
+    let contents = tool
+        .execute(Some(
+            json!({ "paper_ids": [ATTENTION_ID], "tags": ["nlp"] }),
+        ))
+        .await
+        .unwrap();
+
+    assert!(text(&contents).starts_with("Saved 1 papers to the library: 0 new, 1 already saved."));
+    assert_eq!(
+        structured(&contents)["data"]["already_saved"],
+        json!(["vaswani2017attention"])
+    );
+    assert_eq!(library.list().unwrap().len(), 1);
+    let entry = library.get("vaswani2017attention").unwrap().unwrap();
+    assert_eq!(entry.tags, ["transformers", "nlp"]);
+}
+
+#[tokio::test]
+async fn library_add_appends_a_letter_to_taken_keys() {
+    let harness = Harness::new(batch_client(json!([attention()])));
+    let library = library("add_taken_key");
+    library
+        .put(&[entry("vaswani2017attention", Some(BERT_ID), &[])])
+        .unwrap();
+
+    let contents = library_add(&harness, &library)
+        .execute(Some(json!({ "paper_ids": [ATTENTION_ID] })))
+        .await
+        .unwrap();
+
+    assert!(text(&contents).contains("- vaswani2017attentiona: Attention is All you Need"));
+    let entry = library.get("vaswani2017attentiona").unwrap().unwrap();
+    assert_eq!(entry.paper_id.as_deref(), Some(ATTENTION_ID));
+}
+
+#[tokio::test]
+async fn library_list_shows_the_newest_papers_first() {
+    let library = library("list");
+    let mut older = entry("devlin2019bert", Some(BERT_ID), &["pretraining"]);
+    older.title = Some("BERT".to_string());
+    let mut newer = entry(
+        "vaswani2017attention",
+        Some(ATTENTION_ID),
+        &["transformers", "pretraining"],
+    );
+    newer.title = Some("Attention is All you Need".to_string());
+    newer.added_at += chrono::Duration::days(1);
+    library.put(&[older, newer]).unwrap();
+    let tool = LibraryListTool::new(library.clone());
+
+    let contents = tool.execute(Some(json!({}))).await.unwrap();
+
+    let text = text(&contents);
+    assert!(text.contains("2 papers in the library:"));
+    assert!(text.find("vaswani2017attention").unwrap() < text.find("devlin2019bert").unwrap());
+    assert!(text.contains("transformers, pretraining"));
+    assert!(text.contains("2026-01-02 00:00"));
+    let keys: Vec<&str> = structured(&contents)["data"]["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|entry| entry["key"].as_str().unwrap())
+        .collect();
+    assert_eq!(keys, ["vaswani2017attention", "devlin2019bert"]);
+
+    let contents = tool
+        .execute(Some(json!({ "tag": "transformers" })))
+        .await
+        .unwrap();
+    assert!(text(&contents).contains("1 papers in the library tagged 'transformers':"));
+    assert_eq!(
+        structured(&contents)["data"]["data"]
+            .as_array()
+            .unwrap()
+            .len(),
+        1
+    );
+
+    let contents = tool
+        .execute(Some(json!({ "tag": "vision" })))
+        .await
+        .unwrap();
+    assert!(text(&contents).contains("No papers in the library are tagged 'vision'."));
+}
+
+#[tokio::test]
+async fn library_remove_finds_entries_by_key_and_doi() {
+    let harness = Harness::new(MockHttpClient::new());
+    let library = library("remove");
+    let mut attention = entry("vaswani2017attention", Some(ATTENTION_ID), &[]);
+    attention.doi = Some("10.5555/3295222.3295349".to_string());
+    library
+        .put(&[attention, entry("devlin2019bert", Some(BERT_ID), &[])])
+        .unwrap();
+
+    let contents = library_remove(&harness, &library)
+        .execute(Some(json!({
+            "entries": ["devlin2019bert", "doi:10.5555/3295222.3295349", BERT_ID],
+        })))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        text(&contents),
+        "Removed 2 entries from the library: devlin2019bert, vaswani2017attention"
+    );
+    assert_eq!(
+        structured(&contents)["data"],
+        json!({
+            "removed": ["devlin2019bert", "vaswani2017attention"],
+            "missing": [],
+        })
+    );
+    assert!(library.list().unwrap().is_empty());
+    assert_eq!(harness.client.request_count(), 0);
+}
+
+#[tokio::test]
+async fn library_remove_resolves_other_identifiers() {
+    let harness = Harness::new(batch_client(json!([attention(), null])));
+    let library = library("remove_resolved");
+    library
+        .put(&[entry("vaswani2017attention", Some(ATTENTION_ID), &[])])
+        .unwrap();
+
+    let contents = library_remove(&harness, &library)
+        .execute(Some(json!({
+            "entries": ["arXiv:1706.03762", MISSING_ID],
+        })))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        text(&contents),
+        format!(
+            "Removed 1 entries from the library: vaswani2017attention\n\nNot in the library: {}",
+            MISSING_ID
+        )
+    );
+    assert_eq!(
+        structured(&contents)["data"]["missing"],
+        json!([MISSING_ID])
+    );
+    assert_eq!(
+        harness.client.requests()[0].body,
+        Some(json!({ "ids": ["ARXIV:1706.03762", MISSING_ID] }))
+    );
+}
+
+#[tokio::test]
+async fn library_remove_takes_only_tags_off() {
+    let harness = Harness::new(MockHttpClient::new());
+    let library = library("remove_tags");
+    library
+        .put(&[entry(
+            "vaswani2017attention",
+            Some(ATTENTION_ID),
+            &["transformers", "nlp"],
+        )])
+        .unwrap();
+
+    let contents = library_remove(&harness, &library)
+        .execute(Some(json!({
+            "entries": ["vaswani2017attention"],
+            "tags": ["nlp"],
+        })))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        text(&contents),
+        "Removed the tags nlp from 1 entries: vaswani2017attention"
+    );
+    let entry = library.get("vaswani2017attention").unwrap().unwrap();
+    assert_eq!(entry.tags, ["transformers"]);
+
+    let contents = library_remove(&harness, &library)
+        .execute(Some(json!({
+            "entries": ["vaswani2017attention"],
+            "tags": ["nlp"],
+        })))
+        .await
+        .unwrap();
+    assert!(text(&contents).ends_with("Not tagged with them: vaswani2017attention"));
+    assert_eq!(
+        structured(&contents)["data"],
+        json!({
+            "tags": ["nlp"],
+            "untagged": [],
+            "not_tagged": ["vaswani2017attention"],
+            "missing": [],
+        })
+    );
+}
+
+#[tokio::test]
+async fn library_remove_needs_a_tag_to_take_off() {
+    let harness = Harness::new(MockHttpClient::new());
+    let library = library("remove_no_tags");
+    library
+        .put(&[entry(
+            "vaswani2017attention",
+            Some(ATTENTION_ID),
+            &["transformers"],
+        )])
+        .unwrap();
+
+    for tags in [json!([]), json!([" ", ""])] {
+        let err = library_remove(&harness, &library)
+            .execute(Some(json!({
+                "entries": ["vaswani2017attention"],
+                "tags": tags,
+            })))
+            .await
+            .unwrap_err();
+        assert_eq!(error_kind(&err), "invalid_argument");
+    }
+    let entry = library.get("vaswani2017attention").unwrap().unwrap();
+    assert_eq!(entry.tags, ["transformers"]);
+}
+
+const BIBTEX: &str = r#"
+@inproceedings{vaswani2017attention,
+  title = {Attention is All you Need},
+  doi = {https://doi.org/10.5555/3295222.3295349},
+}
+
+@article{devlin2019bert,
+  title = {BERT: Pre-training of Deep Bidirectional Transformers},
+  eprint = {1810.04805},
+  eprinttype = {arxiv},
+}
+
+@article{brown2020language,
+  title = {Language Models are Few-Shot Learners},
+}
+
+@misc{nobody2020,
+  title = {A Paper Nobody Wrote},
+}
+"#;
+
+fn library_import(harness: &Harness, library: &Arc<Library>) -> LibraryImportBibtexTool {
+    LibraryImportBibtexTool::new(
+        harness.http_client(),
+        harness.rate_limiter.clone(),
+        harness.cache.clone(),
+        library.clone(),
+    )
+}
+
+#[tokio::test]
+async fn library_import_bibtex_resolves_by_id_then_title() {
+    let gpt3_id = "90abbc2cf38462b954ae1b772fac9532e2ccd8b0";
+    let harness = Harness::new(
+        batch_client(json!([attention(), bert()])).with_route(
+            Route::get("/graph/v1/paper/search/match")
+                .query("query", "Language Models are Few-Shot Learners")
+                .respond(MockResponse::json(&json!({
+                    "data": [{
+                        "paperId": gpt3_id,
+                        "title": "Language Models are Few-Shot Learners",
+                        "matchScore": 180.5,
+                    }],
+                }))),
+        ),
+    );
+    let library = library("import_bibtex");
+    library
+        .put(&[entry("vaswani2017attention", None, &["transformers"])])
+        .unwrap();
+
+    let contents = library_import(&harness, &library)
+        .execute(Some(json!({ "content": BIBTEX })))
+        .await
+        .unwrap();
+
+    let text = text(&contents);
+    assert!(text.starts_with(
+        "Imported 4 BibTeX entries into the library: 1 matched by DOI, 1 by arXiv ID, 1 by title, 1 unresolved."
+    ));
+    assert!(text.contains("- brown2020language: Language Models are Few-Shot Learners"));
+    assert!(text.contains("Unresolved entries are kept without a paper ID: nobody2020"));
+
+    let vaswani = library.get("vaswani2017attention").unwrap().unwrap();
+    assert_eq!(vaswani.paper_id.as_deref(), Some(ATTENTION_ID));
+    assert_eq!(vaswani.doi.as_deref(), Some("10.5555/3295222.3295349"));
+    assert_eq!(vaswani.matched_by, Some(MatchMethod::Doi));
+    // Re-imported entries keep their tags
+    assert_eq!(vaswani.tags, vec!["transformers"]);
+    let devlin = library.get("devlin2019bert").unwrap().unwrap();
+    assert_eq!(devlin.paper_id.as_deref(), Some(BERT_ID));
+    assert_eq!(devlin.matched_by, Some(MatchMethod::Arxiv));
+    let brown = library.get("brown2020language").unwrap().unwrap();
+    assert_eq!(brown.paper_id.as_deref(), Some(gpt3_id));
+    assert_eq!(brown.match_score, Some(180.5));
+    let nobody = library.get("nobody2020").unwrap().unwrap();
+    assert_eq!(nobody.paper_id, None);
+    assert_eq!(nobody.entry_type.as_deref(), Some("misc"));
+
+    let requests = harness.client.requests();
+    assert_eq!(
+        requests[0].body,
+        Some(json!({ "ids": ["DOI:10.5555/3295222.3295349", "ARXIV:1810.04805"] }))
+    );
+}
+
+#[tokio::test]
+async fn library_import_bibtex_can_skip_title_matching() {
+    let harness = Harness::new(batch_client(json!([attention(), bert()])));
+    let library = library("import_bibtex_no_titles");
+
+    let contents = library_import(&harness, &library)
+        .execute(Some(json!({ "content": BIBTEX, "match_titles": false })))
+        .await
+        .unwrap();
+
+    assert!(text(&contents).contains("2 unresolved"));
+    assert_eq!(harness.client.request_count(), 1);
+    assert_eq!(library.list().unwrap().len(), 4);
+}
+
+#[tokio::test]
+async fn library_import_bibtex_needs_path_or_content() {
+    let harness = Harness::new(MockHttpClient::new());
+    let library = library("import_bibtex_no_source");
+
+    let err = library_import(&harness, &library)
+        .execute(Some(json!({})))
+        .await
+        .unwrap_err();
+
+    assert_eq!(error_kind(&err), "invalid_argument");
+    assert!(library.list().unwrap().is_empty());
+}
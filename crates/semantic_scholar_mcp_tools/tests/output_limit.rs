@@ -0,0 +1,120 @@
+mod common;
+
+use std::sync::Arc;
+
+use context_server::ToolExecutor;
+use mock_http_client::{MockHttpClient, MockResponse, Route, fixture};
+use semantic_scholar_mcp_tools::{PaperSearchTool, ServerSettings, with_settings};
+use serde_json::{Value, json};
+
+use crate::common::{Harness, structured, text};
+
+async fn search(arguments: Value) -> Vec<context_server::ToolContent> {
+    let harness = Harness::new(MockHttpClient::new().with_route(
+        Route::get("/graph/v1/paper/search").respond(MockResponse::fixture("paper_search")),
+    ));
+
+    PaperSearchTool::new(
+        harness.http_client(),
+        harness.rate_limiter.clone(),
+        harness.cache.clone(),
+        harness.embed.clone(),
+    )
+    .execute(Some(arguments))
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn text_output_stops_listing_results_at_the_limit() {
+    let contents = search(json!({ "query": "transformers", "max_output_chars": 300 })).await;
+
+    let text = text(&contents);
+    assert!(text.contains("Attention is All you Need"));
+    assert!(!text.contains("BERT: Pre-training"));
+    assert!(text.contains("For more results, use offset=2"));
+    assert!(text.contains("Output truncated to 300 characters: 1 more results not shown"));
+
+    let structured = structured(&contents);
+    assert_eq!(structured["data"]["data"].as_array().unwrap().len(), 1);
+    assert_eq!(
+        structured["truncated"],
+        json!({ "max_output_chars": 300, "omitted": 1 })
+    );
+}
+
+#[tokio::test]
+async fn json_output_drops_the_last_records() {
+    let contents = search(json!({
+        "query": "transformers",
+        "output_format": "json",
+        "max_output_chars": 1200,
+    }))
+    .await;
+
+    let response: Value = serde_json::from_str(text(&contents)).unwrap();
+    assert_eq!(response["data"].as_array().unwrap().len(), 1);
+    assert_eq!(structured(&contents)["truncated"]["omitted"], 1);
+}
+
+/// The text of a search whose first paper has `abstract_text` as abstract.
+async fn search_with_abstract(abstract_text: &str) -> String {
+    let mut response = fixture("paper_search");
+    response["data"][0]["abstract"] = json!(abstract_text);
+    let harness =
+        Harness::new(MockHttpClient::new().with_route(
+            Route::get("/graph/v1/paper/search").respond(MockResponse::json(&response)),
+        ));
+
+    let contents = PaperSearchTool::new(
+        harness.http_client(),
+        harness.rate_limiter.clone(),
+        harness.cache.clone(),
+        harness.embed.clone(),
+    )
+    .execute(Some(
+        json!({ "query": "transformers", "limit": 1, "max_output_chars": 400 }),
+    ))
+    .await
+    .unwrap();
+    text(&contents).to_string()
+}
+
+#[tokio::test]
+async fn the_limit_counts_characters_rather_than_bytes() {
+    let ascii = search_with_abstract(&"e".repeat(2000)).await;
+    let accented = search_with_abstract(&"é".repeat(2000)).await;
+
+    assert!(accented.contains("éé..."));
+    assert_eq!(accented.chars().count(), ascii.chars().count());
+}
+
+#[tokio::test]
+async fn output_under_the_limit_is_untouched() {
+    let contents = search(json!({ "query": "transformers", "max_output_chars": 100000 })).await;
+
+    assert!(text(&contents).contains("BERT: Pre-training"));
+    assert!(!text(&contents).contains("Output truncated"));
+    assert!(structured(&contents).get("truncated").is_none());
+}
+
+#[tokio::test]
+async fn the_configured_limit_applies_within_its_settings_alone() {
+    let limited = Arc::new(ServerSettings::new().with_max_output_chars(300));
+    let (limited, unlimited) = tokio::join!(
+        with_settings(limited, search(json!({ "query": "transformers" }))),
+        search(json!({ "query": "transformers" })),
+    );
+
+    assert!(text(&limited).contains("Output truncated to 300 characters"));
+    assert!(!text(&unlimited).contains("Output truncated"));
+
+    // A call's own limit still wins over the configured one
+    let settings = Arc::new(ServerSettings::new().with_max_output_chars(300));
+    let contents = with_settings(
+        settings,
+        search(json!({ "query": "transformers", "max_output_chars": 100000 })),
+    )
+    .await;
+    assert!(!text(&contents).contains("Output truncated"));
+}
@@ -0,0 +1,590 @@
+mod common;
+
+use context_server::ToolExecutor;
+use mock_http_client::{MockHttpClient, MockResponse, Route, fixture};
+use semantic_scholar_mcp_tools::{
+    FieldsOfStudyTool, OpenAccessPdfTool, PaperBatchTool, PaperCitationsTool, PaperDetailsTool,
+    PaperReferencesTool, PaperSearchTool, PaperSimilarityRankTool, PaperTitleMatchTool,
+    VenueDetailsTool,
+};
+use serde_json::json;
+
+use crate::common::{Harness, structured, text};
+
+const ATTENTION_ID: &str = "204e3073870fae3d05bcbc2f6a8e263d9b72e776";
+
+fn paper_search(harness: &Harness) -> PaperSearchTool {
+    PaperSearchTool::new(
+        harness.http_client(),
+        harness.rate_limiter.clone(),
+        harness.cache.clone(),
+        harness.embed.clone(),
+    )
+}
+
+#[tokio::test]
+async fn paper_search_lists_the_first_page() {
+    let harness = Harness::new(MockHttpClient::new().with_route(
+        Route::get("/graph/v1/paper/search").respond(MockResponse::fixture("paper_search")),
+    ));
+
+    let contents = paper_search(&harness)
+        .execute(Some(json!({ "query": "transformers", "limit": 2 })))
+        .await
+        .unwrap();
+
+    let text = text(&contents);
+    assert!(text.contains("Found 8412 total papers matching your query. Showing results 1-2"));
+    assert!(text.contains("Attention is All you Need"));
+    assert!(text.contains("BERT: Pre-training"));
+    assert!(text.contains("For more results, use offset=2"));
+
+    let structured = structured(&contents);
+    assert_eq!(structured["data"], fixture("paper_search"));
+    assert_eq!(structured["pagination"]["returned"], 2);
+    assert_eq!(structured["pagination"]["next_offset"], 2);
+    assert_eq!(structured["pagination"]["has_more"], true);
+
+    let requests = harness.client.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].query_param("query"), Some("transformers"));
+    assert_eq!(requests[0].query_param("limit"), Some("2"));
+    assert_eq!(requests[0].query_param("offset"), Some("0"));
+}
+
+#[tokio::test]
+async fn paper_search_continues_from_an_offset() {
+    let mut last_page = fixture("paper_search");
+    last_page["offset"] = json!(8410);
+    last_page.as_object_mut().unwrap().remove("next");
+
+    let harness = Harness::new(
+        MockHttpClient::new().with_route(
+            Route::get("/graph/v1/paper/search")
+                .query("offset", "8410")
+                .respond(MockResponse::json(&last_page)),
+        ),
+    );
+
+    let contents = paper_search(&harness)
+        .execute(Some(
+            json!({ "query": "transformers", "offset": 8410, "limit": 2 }),
+        ))
+        .await
+        .unwrap();
+
+    let text = text(&contents);
+    assert!(text.contains("Showing results 8411-8412"));
+    assert!(!text.contains("For more results"));
+
+    let pagination = &structured(&contents)["pagination"];
+    assert_eq!(pagination["offset"], 8410);
+    assert_eq!(pagination["next_offset"], json!(null));
+    assert_eq!(pagination["has_more"], false);
+}
+
+#[tokio::test]
+async fn paper_search_reports_no_results() {
+    let harness = Harness::new(MockHttpClient::new().with_route(
+        Route::get("/graph/v1/paper/search").respond(MockResponse::fixture("paper_search_empty")),
+    ));
+
+    let contents = paper_search(&harness)
+        .execute(Some(json!({ "query": "qwxzv plorth" })))
+        .await
+        .unwrap();
+
+    assert!(text(&contents).contains("No papers found matching your criteria."));
+    assert_eq!(structured(&contents)["pagination"]["returned"], 0);
+    assert_eq!(structured(&contents)["pagination"]["has_more"], false);
+}
+
+#[tokio::test]
+async fn paper_search_rejects_an_empty_query() {
+    let harness = Harness::new(MockHttpClient::new());
+
+    let err = paper_search(&harness)
+        .execute(Some(json!({ "query": "  " })))
+        .await
+        .unwrap_err();
+
+    assert_eq!(common::error_kind(&err), "invalid_argument");
+    assert_eq!(harness.client.request_count(), 0);
+}
+
+#[tokio::test]
+async fn paper_details_renders_the_paper() {
+    let harness = Harness::new(
+        MockHttpClient::new().with_route(
+            Route::get(&format!("/graph/v1/paper/{}", ATTENTION_ID))
+                .respond(MockResponse::fixture("paper_details")),
+        ),
+    );
+
+    let contents = PaperDetailsTool::new(
+        harness.http_client(),
+        harness.rate_limiter.clone(),
+        harness.cache.clone(),
+        harness.embed.clone(),
+    )
+    .execute(Some(json!({ "paper_id": ATTENTION_ID })))
+    .await
+    .unwrap();
+
+    let text = text(&contents);
+    assert!(text.contains("Paper Details: Attention is All you Need"));
+    assert!(text.contains("Ashish Vaswani"));
+    assert!(text.contains("2017"));
+    assert_eq!(structured(&contents)["data"], fixture("paper_details"));
+}
+
+#[tokio::test]
+async fn paper_citations_lists_citing_papers() {
+    let harness = Harness::new(
+        MockHttpClient::new().with_route(
+            Route::get(&format!("/graph/v1/paper/{}/citations", ATTENTION_ID))
+                .respond(MockResponse::fixture("paper_citations")),
+        ),
+    );
+
+    let contents = PaperCitationsTool::new(
+        harness.http_client(),
+        harness.rate_limiter.clone(),
+        harness.cache.clone(),
+        harness.embed.clone(),
+    )
+    .execute(Some(json!({ "paper_id": ATTENTION_ID, "limit": 2 })))
+    .await
+    .unwrap();
+
+    let text = text(&contents);
+    assert!(text.contains("BERT: Pre-training"));
+    assert!(text.contains("Language Models are Few-Shot Learners"));
+    assert!(text.contains("We build on the Transformer architecture"));
+    assert!(text.contains("For more citations, use offset=2"));
+    assert_eq!(structured(&contents)["pagination"]["next_offset"], 2);
+}
+
+#[tokio::test]
+async fn paper_citations_reports_no_citations() {
+    let harness = Harness::new(
+        MockHttpClient::new().with_route(
+            Route::get(&format!("/graph/v1/paper/{}/citations", ATTENTION_ID))
+                .respond(MockResponse::json(&json!({ "offset": 0, "data": [] }))),
+        ),
+    );
+
+    let contents = PaperCitationsTool::new(
+        harness.http_client(),
+        harness.rate_limiter.clone(),
+        harness.cache.clone(),
+        harness.embed.clone(),
+    )
+    .execute(Some(json!({ "paper_id": ATTENTION_ID })))
+    .await
+    .unwrap();
+
+    assert!(text(&contents).contains("No citations found for this paper."));
+}
+
+#[tokio::test]
+async fn paper_references_sorts_and_filters_a_page() {
+    let harness = Harness::new(
+        MockHttpClient::new().with_route(
+            Route::get(&format!("/graph/v1/paper/{}/references", ATTENTION_ID))
+                .respond(MockResponse::fixture("paper_references")),
+        ),
+    );
+    let tool = PaperReferencesTool::new(
+        harness.http_client(),
+        harness.rate_limiter.clone(),
+        harness.cache.clone(),
+        harness.embed.clone(),
+    );
+
+    let contents = tool
+        .execute(Some(
+            json!({ "paper_id": ATTENTION_ID, "sort": "citationCount" }),
+        ))
+        .await
+        .unwrap();
+    let text = text(&contents);
+    let adam = text.find("Adam: A Method").unwrap();
+    let seq2seq = text.find("Sequence to Sequence Learning").unwrap();
+    assert!(adam < seq2seq);
+    assert!(text.contains("Influential reference"));
+
+    let contents = tool
+        .execute(Some(json!({ "paper_id": ATTENTION_ID, "min_year": 2015 })))
+        .await
+        .unwrap();
+    let text = text(&contents);
+    assert!(text.contains("No references found for this paper."));
+    assert!(text.contains("2 reference(s) on this page were outside the requested year range"));
+
+    // Both calls were answered by the same cached page
+    assert_eq!(harness.client.request_count(), 1);
+}
+
+#[tokio::test]
+async fn paper_batch_notes_unresolved_ids() {
+    let missing_id = "0000000000000000000000000000000000000000";
+    let harness = Harness::new(MockHttpClient::new().with_route(
+        Route::post("/graph/v1/paper/batch").respond(MockResponse::fixture("paper_batch")),
+    ));
+
+    let contents = PaperBatchTool::new(
+        harness.http_client(),
+        harness.rate_limiter.clone(),
+        harness.cache.clone(),
+        harness.embed.clone(),
+    )
+    .execute(Some(json!({ "paper_ids": [ATTENTION_ID, missing_id] })))
+    .await
+    .unwrap();
+
+    let text = text(&contents);
+    assert!(text.contains("Retrieved 1 of 2 requested papers"));
+    assert!(text.contains(&format!("Could not resolve 1 paper ID(s): {}", missing_id)));
+
+    let requests = harness.client.requests();
+    assert_eq!(
+        requests[0].body,
+        Some(json!({ "ids": [ATTENTION_ID, missing_id] }))
+    );
+}
+
+#[tokio::test]
+async fn paper_title_match_shows_the_best_match() {
+    let harness = Harness::new(
+        MockHttpClient::new().with_route(
+            Route::get("/graph/v1/paper/search/match")
+                .query("query", "attention is all you need")
+                .respond(MockResponse::fixture("paper_title_match")),
+        ),
+    );
+
+    let contents = PaperTitleMatchTool::new(
+        harness.http_client(),
+        harness.rate_limiter.clone(),
+        harness.cache.clone(),
+        harness.embed.clone(),
+    )
+    .execute(Some(json!({ "title": "attention is all you need" })))
+    .await
+    .unwrap();
+
+    let text = text(&contents);
+    assert!(text.contains("Best match: Attention is All you Need"));
+    assert!(text.contains(ATTENTION_ID));
+    assert!(text.contains("182.41"));
+}
+
+#[tokio::test]
+async fn open_access_pdf_links_the_pdf() {
+    let harness = Harness::new(MockHttpClient::new().with_route(
+        Route::get(&format!("/graph/v1/paper/{}", ATTENTION_ID)).respond(MockResponse::json(
+            &json!({
+                "paperId": ATTENTION_ID,
+                "title": "Attention is All you Need",
+                "isOpenAccess": true,
+                "openAccessPdf": {
+                    "url": "https://arxiv.org/pdf/1706.03762",
+                    "status": "GREEN",
+                    "license": "CCBY",
+                },
+            }),
+        )),
+    ));
+
+    let contents = OpenAccessPdfTool::new(
+        harness.http_client(),
+        harness.rate_limiter.clone(),
+        harness.cache.clone(),
+        harness.embed.clone(),
+    )
+    .execute(Some(json!({ "paper_id": ATTENTION_ID })))
+    .await
+    .unwrap();
+
+    let text = text(&contents);
+    assert!(text.contains("Paper: Attention is All you Need"));
+    assert!(text.contains("Open Access: Yes"));
+    assert!(text.contains("PDF URL: https://arxiv.org/pdf/1706.03762"));
+    assert!(text.contains("Status: GREEN"));
+    assert!(text.contains("License: CCBY"));
+
+    let requests = harness.client.requests();
+    assert_eq!(
+        requests[0].query_param("fields"),
+        Some("title,isOpenAccess,openAccessPdf,externalIds")
+    );
+}
+
+#[tokio::test]
+async fn open_access_pdf_points_to_the_publisher_without_a_pdf() {
+    let harness = Harness::new(MockHttpClient::new().with_route(
+        Route::get(&format!("/graph/v1/paper/{}", ATTENTION_ID)).respond(MockResponse::json(
+            &json!({
+                "paperId": ATTENTION_ID,
+                "title": "Attention is All you Need",
+                "isOpenAccess": false,
+                "openAccessPdf": null,
+                "externalIds": { "DOI": "10.5555/3295222.3295349" },
+            }),
+        )),
+    ));
+
+    let contents = OpenAccessPdfTool::new(
+        harness.http_client(),
+        harness.rate_limiter.clone(),
+        harness.cache.clone(),
+        harness.embed.clone(),
+    )
+    .execute(Some(json!({ "paper_id": ATTENTION_ID })))
+    .await
+    .unwrap();
+
+    let text = text(&contents);
+    assert!(text.contains("Open Access: No"));
+    assert!(text.contains("https://doi.org/10.5555/3295222.3295349"));
+}
+
+fn venue_sample() -> MockResponse {
+    let neurips = json!({
+        "id": "d9720b90-d60b-48bc-9df8-87a30b9a60dd",
+        "name": "Neural Information Processing Systems",
+        "type": "conference",
+        "alternate_names": ["NeurIPS", "NIPS"],
+        "url": "http://neurips.cc/",
+    });
+    let iclr = json!({
+        "id": "939c6e1d-0d17-4d6e-8a82-66d960df0e40",
+        "name": "International Conference on Learning Representations",
+        "type": "conference",
+    });
+    MockResponse::json(&json!({
+        "total": 3,
+        "data": [
+            {
+                "paperId": ATTENTION_ID,
+                "publicationVenue": neurips,
+                "year": 2017,
+                "citationCount": 30,
+                "influentialCitationCount": 3,
+                "isOpenAccess": false,
+            },
+            {
+                "paperId": "1db9bd18681b96473f3c82b21edc9240b44dc329",
+                "publicationVenue": neurips,
+                "year": 2019,
+                "citationCount": 10,
+                "influentialCitationCount": 1,
+                "isOpenAccess": true,
+            },
+            {
+                "paperId": "a6cb366736791bcccc5c8639de5a8f9636bf87e8",
+                "publicationVenue": iclr,
+                "year": 2015,
+                "citationCount": 100,
+                "influentialCitationCount": 20,
+                "isOpenAccess": true,
+            },
+        ],
+    }))
+}
+
+#[tokio::test]
+async fn venue_details_summarises_the_most_common_venue() {
+    let harness = Harness::new(
+        MockHttpClient::new().with_route(
+            Route::get("/graph/v1/paper/search/bulk")
+                .query("venue", "NeurIPS")
+                .respond(venue_sample()),
+        ),
+    );
+
+    let contents = VenueDetailsTool::new(
+        harness.http_client(),
+        harness.rate_limiter.clone(),
+        harness.cache.clone(),
+        harness.embed.clone(),
+    )
+    .execute(Some(json!({ "venue": "NeurIPS" })))
+    .await
+    .unwrap();
+
+    let text = text(&contents);
+    assert!(text.contains("Venue: Neural Information Processing Systems"));
+    assert!(text.contains("Venue ID: d9720b90-d60b-48bc-9df8-87a30b9a60dd"));
+    assert!(text.contains("Also known as: NeurIPS, NIPS"));
+    assert!(text.contains("Statistics (from a sample of 2 papers):"));
+    assert!(text.contains("Years covered: 2017-2019"));
+    assert!(text.contains("Mean citations per paper: 20.0"));
+    assert!(text.contains("Median citations per paper: 30"));
+    assert!(text.contains("Most cited paper in sample: 30 citations"));
+    assert!(text.contains("Influential citations per paper: 2.0"));
+    assert!(text.contains("Open access share: 50%"));
+}
+
+#[tokio::test]
+async fn venue_details_picks_the_requested_venue_id() {
+    let harness = Harness::new(
+        MockHttpClient::new()
+            .with_route(Route::get("/graph/v1/paper/search/bulk").respond(venue_sample())),
+    );
+
+    let contents = VenueDetailsTool::new(
+        harness.http_client(),
+        harness.rate_limiter.clone(),
+        harness.cache.clone(),
+        harness.embed.clone(),
+    )
+    .execute(Some(json!({
+        "venue": "ICLR",
+        "venue_id": "939c6e1d-0d17-4d6e-8a82-66d960df0e40",
+        "year": "2015-2019",
+    })))
+    .await
+    .unwrap();
+
+    let text = text(&contents);
+    assert!(text.contains("Venue: International Conference on Learning Representations"));
+    assert!(text.contains("Statistics (from a sample of 1 papers):"));
+    assert!(text.contains("Open access share: 100%"));
+
+    let requests = harness.client.requests();
+    assert_eq!(requests[0].query_param("year"), Some("2015-2019"));
+}
+
+#[tokio::test]
+async fn fields_of_study_lists_every_field() {
+    let contents = FieldsOfStudyTool::new().execute(None).await.unwrap();
+
+    let text = text(&contents);
+    assert!(text.starts_with("23 valid fields of study:"));
+    assert!(text.contains("- Computer Science\n"));
+    assert!(text.contains("- Agricultural and Food Sciences\n"));
+}
+
+#[tokio::test]
+async fn fields_of_study_suggests_corrections() {
+    let contents = FieldsOfStudyTool::new()
+        .execute(Some(
+            json!({ "validate": ["Medicine", "Computer Sciense", "Xyzzy"] }),
+        ))
+        .await
+        .unwrap();
+
+    let text = text(&contents);
+    assert!(text.contains("- Medicine: valid"));
+    assert!(text.contains("- Computer Sciense: invalid (did you mean 'Computer Science'?)"));
+    assert!(text.contains("- Xyzzy: invalid\n"));
+}
+
+fn similarity_rank(harness: &Harness) -> PaperSimilarityRankTool {
+    PaperSimilarityRankTool::new(
+        harness.http_client(),
+        harness.rate_limiter.clone(),
+        harness.cache.clone(),
+        harness.embed.clone(),
+    )
+}
+
+#[tokio::test]
+async fn paper_similarity_rank_orders_candidates_by_similarity() {
+    let close_id = "1db9bd18681b96473f3c82b21edc9240b44dc329";
+    let far_id = "a6cb366736791bcccc5c8639de5a8f9636bf87e8";
+    let missing_id = "f0000000000000000000000000000000000000ff";
+    let harness = Harness::new(MockHttpClient::new().with_route(
+        Route::post("/graph/v1/paper/batch").respond(
+            // The seed first, then the candidates in sorted order
+            MockResponse::json(&json!([
+                {
+                    "paperId": ATTENTION_ID,
+                    "title": "Attention is All you Need",
+                    "embedding": { "model": "specter_v2", "vector": [1.0, 0.0] },
+                },
+                {
+                    "paperId": close_id,
+                    "title": "Close paper",
+                    "year": 2019,
+                    "embedding": { "model": "specter_v2", "vector": [0.9, 0.1] },
+                },
+                {
+                    "paperId": far_id,
+                    "title": "Far paper",
+                    "year": 2014,
+                    "embedding": { "model": "specter_v2", "vector": [0.0, 1.0] },
+                },
+                null,
+            ])),
+        ),
+    ));
+
+    let contents = similarity_rank(&harness)
+        .execute(Some(json!({
+            "paper_id": ATTENTION_ID,
+            "candidate_paper_ids": [far_id, missing_id, close_id, far_id],
+        })))
+        .await
+        .unwrap();
+
+    let text = text(&contents);
+    assert!(text.contains(
+        "Ranked 2 candidate papers by specter_v2 similarity to \"Attention is All you Need\":"
+    ));
+    let close = text.find("Close paper").unwrap();
+    let far = text.find("Far paper").unwrap();
+    assert!(close < far);
+    assert!(text.contains("Similarity: 0.0000"));
+    assert!(text.contains("1 candidate(s) were skipped"));
+
+    let requests = harness.client.requests();
+    assert_eq!(
+        requests[0].query_param("fields"),
+        Some("title,year,embedding.specter_v2")
+    );
+    assert_eq!(
+        requests[0].body,
+        Some(json!({ "ids": [ATTENTION_ID, close_id, far_id, missing_id] }))
+    );
+}
+
+#[tokio::test]
+async fn paper_similarity_rank_needs_the_seed_embedding() {
+    let harness = Harness::new(MockHttpClient::new().with_route(
+        Route::post("/graph/v1/paper/batch").respond(MockResponse::json(&json!([
+            { "paperId": ATTENTION_ID, "title": "Attention is All you Need", "embedding": null },
+            {
+                "paperId": "1db9bd18681b96473f3c82b21edc9240b44dc329",
+                "embedding": { "model": "specter_v2", "vector": [1.0, 0.0] },
+            },
+        ]))),
+    ));
+
+    let contents = similarity_rank(&harness)
+        .execute(Some(json!({
+            "paper_id": ATTENTION_ID,
+            "candidate_paper_ids": ["1db9bd18681b96473f3c82b21edc9240b44dc329"],
+        })))
+        .await
+        .unwrap();
+
+    assert!(text(&contents).contains("No SPECTER embedding is available for the seed paper."));
+}
+
+#[tokio::test]
+async fn paper_similarity_rank_rejects_no_candidates() {
+    let harness = Harness::new(MockHttpClient::new());
+
+    let err = similarity_rank(&harness)
+        .execute(Some(
+            json!({ "paper_id": ATTENTION_ID, "candidate_paper_ids": [] }),
+        ))
+        .await
+        .unwrap_err();
+
+    assert!(err.to_string().contains("at least one candidate paper ID"));
+    assert_eq!(harness.client.request_count(), 0);
+}
@@ -0,0 +1,162 @@
+mod common;
+
+use context_server::ToolExecutor;
+use mock_http_client::{MockHttpClient, MockResponse, Route};
+use semantic_scholar_mcp_tools::{
+    PaperRecommendationMultiTool, PaperRecommendationSingleTool, TopicRecommendationTool,
+};
+use serde_json::json;
+
+use crate::common::{Harness, text};
+
+const ATTENTION_ID: &str = "204e3073870fae3d05bcbc2f6a8e263d9b72e776";
+const ADAM_ID: &str = "a6cb366736791bcccc5c8639de5a8f9636bf87e8";
+const BERT_ID: &str = "df2b0e26d0599ce3e70df8a9da02e51594e0e992";
+
+#[tokio::test]
+async fn recommendations_for_one_paper() {
+    let harness = Harness::new(
+        MockHttpClient::new().with_route(
+            Route::get(&format!(
+                "/recommendations/v1/papers/forpaper/{}",
+                ATTENTION_ID
+            ))
+            .respond(MockResponse::fixture("recommendations")),
+        ),
+    );
+
+    let contents = PaperRecommendationSingleTool::new(
+        harness.http_client(),
+        harness.rate_limiter.clone(),
+        harness.cache.clone(),
+        harness.embed.clone(),
+    )
+    .execute(Some(json!({ "paper_id": ATTENTION_ID, "limit": 2 })))
+    .await
+    .unwrap();
+
+    let text = text(&contents);
+    assert!(text.contains("Found 2 recommended papers:"));
+    assert!(text.contains("BERT: Pre-training"));
+    assert!(text.contains("Language Models are Few-Shot Learners"));
+}
+
+#[tokio::test]
+async fn recommendations_for_one_paper_can_be_empty() {
+    let harness = Harness::new(
+        MockHttpClient::new().with_route(
+            Route::get(&format!(
+                "/recommendations/v1/papers/forpaper/{}",
+                ATTENTION_ID
+            ))
+            .respond(MockResponse::json(&json!({ "recommendedPapers": [] }))),
+        ),
+    );
+
+    let contents = PaperRecommendationSingleTool::new(
+        harness.http_client(),
+        harness.rate_limiter.clone(),
+        harness.cache.clone(),
+        harness.embed.clone(),
+    )
+    .execute(Some(json!({ "paper_id": ATTENTION_ID })))
+    .await
+    .unwrap();
+
+    assert!(text(&contents).contains("No recommendations found for this paper."));
+}
+
+#[tokio::test]
+async fn recommendations_for_several_papers_send_positive_and_negative_ids() {
+    let harness = Harness::new(MockHttpClient::new().with_route(
+        Route::post("/recommendations/v1/papers").respond(MockResponse::fixture("recommendations")),
+    ));
+
+    let contents = PaperRecommendationMultiTool::new(
+        harness.http_client(),
+        harness.rate_limiter.clone(),
+        harness.cache.clone(),
+        harness.embed.clone(),
+    )
+    .execute(Some(json!({
+        "positive_paper_ids": [ATTENTION_ID],
+        "negative_paper_ids": [ADAM_ID],
+    })))
+    .await
+    .unwrap();
+
+    assert!(text(&contents).contains("Found 2 recommended papers based on your input papers:"));
+
+    let requests = harness.client.requests();
+    assert_eq!(
+        requests[0].body,
+        Some(json!({
+            "positivePaperIds": [ATTENTION_ID],
+            "negativePaperIds": [ADAM_ID],
+        }))
+    );
+}
+
+fn topic_recommendations(harness: &Harness) -> TopicRecommendationTool {
+    TopicRecommendationTool::new(
+        harness.http_client(),
+        harness.rate_limiter.clone(),
+        harness.cache.clone(),
+        harness.embed.clone(),
+    )
+}
+
+#[tokio::test]
+async fn topic_recommendations_are_seeded_by_a_search() {
+    let harness = Harness::new(
+        MockHttpClient::new()
+            .with_route(
+                Route::get("/graph/v1/paper/search")
+                    .query("query", "transformer language models")
+                    .respond(MockResponse::fixture("paper_search")),
+            )
+            .with_route(
+                Route::post("/recommendations/v1/papers")
+                    .respond(MockResponse::fixture("recommendations")),
+            ),
+    );
+
+    let contents = topic_recommendations(&harness)
+        .execute(Some(
+            json!({ "topic": "transformer language models", "seed_count": 2 }),
+        ))
+        .await
+        .unwrap();
+
+    let text = text(&contents);
+    assert!(text.contains("Seed papers (2 found for this topic)"));
+    assert!(text.contains(&format!("Attention is All you Need (ID: {})", ATTENTION_ID)));
+    // BERT is a seed, so only GPT-3 is left to recommend
+    assert!(text.contains("Found 1 recommended papers:"));
+    assert!(text.contains("Language Models are Few-Shot Learners"));
+
+    let requests = harness.client.requests();
+    assert_eq!(requests[0].query_param("limit"), Some("2"));
+    assert_eq!(
+        requests[1].body,
+        Some(json!({
+            "positivePaperIds": [ATTENTION_ID, BERT_ID],
+            "negativePaperIds": [],
+        }))
+    );
+}
+
+#[tokio::test]
+async fn topic_recommendations_without_seeds_skip_recommending() {
+    let harness = Harness::new(MockHttpClient::new().with_route(
+        Route::get("/graph/v1/paper/search").respond(MockResponse::fixture("paper_search_empty")),
+    ));
+
+    let contents = topic_recommendations(&harness)
+        .execute(Some(json!({ "topic": "nothing matches this" })))
+        .await
+        .unwrap();
+
+    assert!(text(&contents).contains("No papers found matching this topic."));
+    assert_eq!(harness.client.request_count(), 1);
+}
@@ -0,0 +1,512 @@
+use std::{
+    fs,
+    path::Path,
+    sync::{Mutex, MutexGuard},
+    time::Duration,
+};
+
+use anyhow::{Result, anyhow};
+use cache::{
+    Cache, CacheEntry, CachedResponse, ClearFilter, ClearedEntries, DEFAULT_TTL, Query,
+    RESPONSE_RETENTION, merge_record, similarity, unit_vector,
+};
+use chrono::NaiveDateTime;
+use rusqlite::{Connection, OptionalExtension, params};
+use serde_json::Value;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS queries (
+    id INTEGER PRIMARY KEY,
+    created_at TEXT NOT NULL,
+    value TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS responses (
+    key TEXT PRIMARY KEY,
+    created_at TEXT NOT NULL,
+    value TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS papers (
+    id TEXT PRIMARY KEY,
+    created_at TEXT NOT NULL,
+    value TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS papers_created_at ON papers (created_at);
+CREATE TABLE IF NOT EXISTS authors (
+    id TEXT PRIMARY KEY,
+    created_at TEXT NOT NULL,
+    value TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS authors_created_at ON authors (created_at);
+CREATE TABLE IF NOT EXISTS author_papers (
+    author_id TEXT PRIMARY KEY,
+    paper_ids TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS embeddings (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
+";
+
+/// A cache in a single SQLite file, which other tools can open and query,
+/// and which several processes can share.
+pub struct SqliteCache {
+    connection: Mutex<Connection>,
+    ttl: Duration,
+    /// Whether entries past `ttl` or [`RESPONSE_RETENTION`] are kept
+    keep_expired: bool,
+}
+
+/// Tables holding records merged by ID.
+#[derive(Clone, Copy)]
+enum Records {
+    Papers,
+    Authors,
+}
+
+impl Records {
+    fn table(&self) -> &'static str {
+        match self {
+            Self::Papers => "papers",
+            Self::Authors => "authors",
+        }
+    }
+}
+
+impl SqliteCache {
+    pub fn new<P: AsRef<Path>>(path: P, ttl: Option<Duration>) -> Result<Self> {
+        if let Some(parent) = path.as_ref().parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let connection = Connection::open(path.as_ref())?;
+        // Lets readers in other processes proceed while one of them writes
+        connection.pragma_update(None, "journal_mode", "WAL")?;
+        connection.busy_timeout(Duration::from_secs(5))?;
+        connection.execute_batch(SCHEMA)?;
+
+        tracing::debug!("Opened SQLite cache at {}", path.as_ref().display());
+
+        Ok(Self {
+            connection: Mutex::new(connection),
+            ttl: ttl.unwrap_or(DEFAULT_TTL),
+            keep_expired: false,
+        })
+    }
+
+    /// Serves entries however old they are and never deletes them for their
+    /// age, for offline use where nothing expired can be fetched again.
+    pub fn keep_expired(mut self) -> Self {
+        self.keep_expired = true;
+        self
+    }
+
+    /// The time entries older than `max_age` were stored before, or `None`
+    /// when expired entries are kept.
+    fn expired_before(&self, max_age: Duration) -> Result<Option<NaiveDateTime>> {
+        if self.keep_expired {
+            return Ok(None);
+        }
+        Ok(Some(now() - chrono::Duration::from_std(max_age)?))
+    }
+
+    fn connection(&self) -> Result<MutexGuard<'_, Connection>> {
+        self.connection
+            .lock()
+            .map_err(|_| anyhow!("SQLite cache poisoned"))
+    }
+
+    fn merge_records(&self, records: Records, id_field: &str, new: &[Value]) -> Result<()> {
+        let mut connection = self.connection()?;
+        let transaction = connection.transaction()?;
+        let now = now();
+
+        for record in new {
+            let (Some(id), Some(fields)) = (
+                record.get(id_field).and_then(Value::as_str),
+                record.as_object(),
+            ) else {
+                continue;
+            };
+
+            let existing: Option<String> = transaction
+                .query_row(
+                    &format!("SELECT value FROM {} WHERE id = ?1", records.table()),
+                    params![id],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            let existing = existing
+                .map(|value| serde_json::from_str(&value))
+                .transpose()?;
+
+            transaction.execute(
+                &format!(
+                    "INSERT OR REPLACE INTO {} (id, created_at, value) VALUES (?1, ?2, ?3)",
+                    records.table()
+                ),
+                params![
+                    id,
+                    now,
+                    serde_json::to_string(&merge_record(existing, fields))?
+                ],
+            )?;
+        }
+
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn get_record(&self, records: Records, id: &str) -> Result<Option<CacheEntry<Value>>> {
+        let row: Option<(NaiveDateTime, String)> = self
+            .connection()?
+            .query_row(
+                &format!(
+                    "SELECT created_at, value FROM {} WHERE id = ?1",
+                    records.table()
+                ),
+                params![id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+
+        row.map(|(created_at, value)| {
+            Ok(CacheEntry {
+                value: serde_json::from_str(&value)?,
+                created_at,
+            })
+        })
+        .transpose()
+    }
+
+    fn recent_records(&self, records: Records, limit: usize) -> Result<Vec<CacheEntry<Value>>> {
+        let connection = self.connection()?;
+        let mut statement = connection.prepare(&format!(
+            "SELECT created_at, value FROM {} ORDER BY created_at DESC LIMIT ?1",
+            records.table()
+        ))?;
+        let rows = statement.query_map(params![limit as i64], |row| {
+            Ok((row.get::<_, NaiveDateTime>(0)?, row.get::<_, String>(1)?))
+        })?;
+
+        let mut entries = Vec::new();
+        for row in rows {
+            let (created_at, value) = row?;
+            entries.push(CacheEntry {
+                value: serde_json::from_str(&value)?,
+                created_at,
+            });
+        }
+        Ok(entries)
+    }
+}
+
+fn now() -> NaiveDateTime {
+    chrono::Utc::now().naive_utc()
+}
+
+impl Cache for SqliteCache {
+    fn store(&self, mut query: Query) -> Result<()> {
+        query.normalize();
+        self.connection()?.execute(
+            "INSERT INTO queries (created_at, value) VALUES (?1, ?2)",
+            params![now(), serde_json::to_string(&query)?],
+        )?;
+        Ok(())
+    }
+
+    fn search_similarity(&self, embedding: &[f32]) -> Result<Vec<(Query, f32)>> {
+        let connection = self.connection()?;
+        if let Some(expired_before) = self.expired_before(self.ttl)? {
+            let purged = connection.execute(
+                "DELETE FROM queries WHERE created_at < ?1",
+                params![expired_before],
+            )?;
+            if purged > 0 {
+                tracing::debug!("Purged {} expired cached queries", purged);
+            }
+        }
+
+        let Some(unit) = unit_vector(embedding) else {
+            return Ok(Vec::new());
+        };
+        let mut statement = connection.prepare("SELECT value FROM queries")?;
+        let rows = statement.query_map([], |row| row.get::<_, String>(0))?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            let query: Query = serde_json::from_str(&row?)?;
+            if let Some(similarity) = similarity(&unit, &query) {
+                results.push((query, similarity));
+            }
+        }
+
+        results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        Ok(results)
+    }
+
+    fn get_query(&self, action: &str, params: &Value) -> Result<Option<Query>> {
+        let connection = self.connection()?;
+        let expired_before = self.expired_before(self.ttl)?;
+        let mut statement = connection.prepare(
+            "SELECT value FROM queries
+             WHERE (?1 IS NULL OR created_at >= ?1) AND json_extract(value, '$.action') = ?2
+             ORDER BY id DESC",
+        )?;
+        let rows = statement.query_map(params![expired_before, action], |row| {
+            row.get::<_, String>(0)
+        })?;
+
+        for row in rows {
+            let query: Query = serde_json::from_str(&row?)?;
+            if query.params.as_ref() == Some(params) {
+                return Ok(Some(query));
+            }
+        }
+        Ok(None)
+    }
+
+    fn get_response(&self, key: &str) -> Result<Option<CacheEntry<CachedResponse>>> {
+        let connection = self.connection()?;
+        let row: Option<(NaiveDateTime, String)> = connection
+            .query_row(
+                "SELECT created_at, value FROM responses WHERE key = ?1",
+                params![key],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+
+        let Some((created_at, value)) = row else {
+            return Ok(None);
+        };
+
+        if self
+            .expired_before(RESPONSE_RETENTION)?
+            .is_some_and(|expired_before| created_at < expired_before)
+        {
+            connection.execute("DELETE FROM responses WHERE key = ?1", params![key])?;
+            return Ok(None);
+        }
+
+        Ok(Some(CacheEntry {
+            value: serde_json::from_str(&value)?,
+            created_at,
+        }))
+    }
+
+    fn store_response(&self, key: &str, response: CachedResponse) -> Result<()> {
+        self.connection()?.execute(
+            "INSERT OR REPLACE INTO responses (key, created_at, value) VALUES (?1, ?2, ?3)",
+            params![key, now(), serde_json::to_string(&response)?],
+        )?;
+        Ok(())
+    }
+
+    fn store_papers(&self, papers: &[Value]) -> Result<()> {
+        self.merge_records(Records::Papers, "paperId", papers)
+    }
+
+    fn get_paper(&self, paper_id: &str) -> Result<Option<CacheEntry<Value>>> {
+        self.get_record(Records::Papers, paper_id)
+    }
+
+    fn recent_papers(&self, limit: usize) -> Result<Vec<CacheEntry<Value>>> {
+        self.recent_records(Records::Papers, limit)
+    }
+
+    fn store_authors(&self, authors: &[Value]) -> Result<()> {
+        self.merge_records(Records::Authors, "authorId", authors)
+    }
+
+    fn get_author(&self, author_id: &str) -> Result<Option<CacheEntry<Value>>> {
+        self.get_record(Records::Authors, author_id)
+    }
+
+    fn recent_authors(&self, limit: usize) -> Result<Vec<CacheEntry<Value>>> {
+        self.recent_records(Records::Authors, limit)
+    }
+
+    fn store_author_papers(&self, author_id: &str, paper_ids: &[String]) -> Result<()> {
+        let mut connection = self.connection()?;
+        let transaction = connection.transaction()?;
+
+        let known: Option<String> = transaction
+            .query_row(
+                "SELECT paper_ids FROM author_papers WHERE author_id = ?1",
+                params![author_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let mut known: Vec<String> = match known {
+            Some(known) => serde_json::from_str(&known)?,
+            None => Vec::new(),
+        };
+
+        for paper_id in paper_ids {
+            if !known.contains(paper_id) {
+                known.push(paper_id.clone());
+            }
+        }
+
+        transaction.execute(
+            "INSERT OR REPLACE INTO author_papers (author_id, paper_ids) VALUES (?1, ?2)",
+            params![author_id, serde_json::to_string(&known)?],
+        )?;
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn get_author_papers(&self, author_id: &str) -> Result<Vec<String>> {
+        let known: Option<String> = self
+            .connection()?
+            .query_row(
+                "SELECT paper_ids FROM author_papers WHERE author_id = ?1",
+                params![author_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        match known {
+            Some(known) => Ok(serde_json::from_str(&known)?),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    fn store_embedding(&self, key: &str, embedding: &[f32]) -> Result<()> {
+        self.connection()?.execute(
+            "INSERT OR REPLACE INTO embeddings (key, value) VALUES (?1, ?2)",
+            params![key, serde_json::to_string(embedding)?],
+        )?;
+        Ok(())
+    }
+
+    fn get_embedding(&self, key: &str) -> Result<Option<Vec<f32>>> {
+        let embedding: Option<String> = self
+            .connection()?
+            .query_row(
+                "SELECT value FROM embeddings WHERE key = ?1",
+                params![key],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        match embedding {
+            Some(embedding) => Ok(Some(serde_json::from_str(&embedding)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn clear(&self, filter: &ClearFilter) -> Result<ClearedEntries> {
+        let mut connection = self.connection()?;
+        let transaction = connection.transaction()?;
+
+        // Filters look inside the stored JSON, so matching happens here
+        // rather than in SQL
+        let matching = |table: &str,
+                        key: &str,
+                        matches: &dyn Fn(&str, NaiveDateTime, &str) -> Result<bool>|
+         -> Result<Vec<String>> {
+            let mut statement = transaction
+                .prepare(&format!("SELECT {}, created_at, value FROM {}", key, table))?;
+            let rows = statement.query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, NaiveDateTime>(1)?,
+                    row.get::<_, String>(2)?,
+                ))
+            })?;
+
+            let mut keys = Vec::new();
+            for row in rows {
+                let (key, created_at, value) = row?;
+                if matches(&key, created_at, &value)? {
+                    keys.push(key);
+                }
+            }
+            Ok(keys)
+        };
+
+        let queries = matching("queries", "CAST(id AS TEXT)", &|_, created_at, value| {
+            Ok(filter.matches_query(&CacheEntry {
+                value: serde_json::from_str(value)?,
+                created_at,
+            }))
+        })?;
+        let responses = matching("responses", "key", &|_, created_at, value| {
+            Ok(filter.matches_response(&CacheEntry {
+                value: serde_json::from_str(value)?,
+                created_at,
+            }))
+        })?;
+        let record = |id: &str, created_at, value: &str| -> Result<bool> {
+            Ok(filter.matches_record(
+                id,
+                &CacheEntry {
+                    value: serde_json::from_str(value)?,
+                    created_at,
+                },
+            ))
+        };
+        let papers = matching("papers", "id", &record)?;
+        let authors = matching("authors", "id", &record)?;
+        let author_papers = {
+            let mut statement = transaction.prepare("SELECT author_id FROM author_papers")?;
+            let rows = statement.query_map([], |row| row.get::<_, String>(0))?;
+            let mut author_ids = Vec::new();
+            for row in rows {
+                let author_id = row?;
+                if filter.matches_author_papers(&author_id) {
+                    author_ids.push(author_id);
+                }
+            }
+            author_ids
+        };
+
+        for (statement, keys) in [
+            (
+                "DELETE FROM queries WHERE id = CAST(?1 AS INTEGER)",
+                &queries,
+            ),
+            ("DELETE FROM responses WHERE key = ?1", &responses),
+            ("DELETE FROM papers WHERE id = ?1", &papers),
+            ("DELETE FROM authors WHERE id = ?1", &authors),
+            (
+                "DELETE FROM author_papers WHERE author_id = ?1",
+                &author_papers,
+            ),
+        ] {
+            for key in keys {
+                transaction.execute(statement, params![key])?;
+            }
+        }
+        if filter.matches_embeddings() {
+            transaction.execute("DELETE FROM embeddings", [])?;
+        }
+
+        transaction.commit()?;
+        Ok(ClearedEntries {
+            queries: queries.len(),
+            responses: responses.len(),
+            papers: papers.len(),
+            authors: authors.len(),
+        })
+    }
+
+    fn purge_expired(&self) -> Result<usize> {
+        let (Some(queries_before), Some(responses_before)) = (
+            self.expired_before(self.ttl)?,
+            self.expired_before(RESPONSE_RETENTION)?,
+        ) else {
+            return Ok(0);
+        };
+        let connection = self.connection()?;
+        let queries = connection.execute(
+            "DELETE FROM queries WHERE created_at < ?1",
+            params![queries_before],
+        )?;
+        let responses = connection.execute(
+            "DELETE FROM responses WHERE created_at < ?1",
+            params![responses_before],
+        )?;
+        Ok(queries + responses)
+    }
+}
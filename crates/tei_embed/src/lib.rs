@@ -0,0 +1,165 @@
+use std::sync::Arc;
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use embed::Embed;
+use http_client::{HttpClient, Request, RequestBuilderExt, ResponseAsyncBodyExt};
+use serde_json::{Value, json};
+
+const DEFAULT_URL: &str = "http://localhost:8080";
+
+/// Embeds texts with a Hugging Face text-embeddings-inference server.
+pub struct TeiEmbed {
+    http_client: Arc<dyn HttpClient>,
+    url: String,
+    api_key: Option<String>,
+    model: Option<String>,
+    dimensions: Option<usize>,
+}
+
+pub struct TeiEmbedBuilder {
+    http_client: Option<Arc<dyn HttpClient>>,
+    url: Option<String>,
+    api_key: Option<String>,
+    model: Option<String>,
+    dimensions: Option<usize>,
+}
+
+impl TeiEmbed {
+    pub fn builder() -> TeiEmbedBuilder {
+        TeiEmbedBuilder {
+            http_client: None,
+            url: None,
+            api_key: None,
+            model: None,
+            dimensions: None,
+        }
+    }
+
+    /// Where the server listens.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Asks the server which model it serves, as named on Hugging Face.
+    pub async fn served_model(&self) -> Result<String> {
+        let mut request = Request::builder()
+            .method("GET")
+            .uri(format!("{}/info", self.url))
+            .header("Accept", "application/json");
+        if let Some(api_key) = &self.api_key {
+            request = request.header("Authorization", format!("Bearer {}", api_key));
+        }
+
+        let response = self.http_client.send(request.end()?).await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(anyhow!("HTTP error {}", status));
+        }
+        let info: Value = response
+            .json()
+            .await
+            .map_err(|e| anyhow!("Failed to parse JSON response: {}", e))?;
+        info["model_id"]
+            .as_str()
+            .map(String::from)
+            .ok_or_else(|| anyhow!("TEI did not name the model it serves"))
+    }
+}
+
+impl TeiEmbedBuilder {
+    pub fn with_http_client(&mut self, http_client: Arc<dyn HttpClient>) -> &mut Self {
+        self.http_client = Some(http_client);
+        self
+    }
+
+    /// Where the server listens, `http://localhost:8080` by default.
+    pub fn with_url(&mut self, url: impl Into<String>) -> &mut Self {
+        self.url = Some(url.into());
+        self
+    }
+
+    /// Sent as a bearer token, for servers started with `--api-key`.
+    pub fn with_api_key(&mut self, api_key: impl Into<String>) -> &mut Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Names the model the server was started with. The server decides the
+    /// model, so this only labels its embeddings.
+    pub fn with_model(&mut self, model: impl Into<String>) -> &mut Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    /// Length of the vectors the served model returns.
+    pub fn with_dimensions(&mut self, dimensions: usize) -> &mut Self {
+        self.dimensions = Some(dimensions);
+        self
+    }
+
+    pub fn build(&self) -> Result<TeiEmbed> {
+        Ok(TeiEmbed {
+            http_client: self
+                .http_client
+                .clone()
+                .ok_or_else(|| anyhow!("TEI requires an HTTP client"))?,
+            url: self
+                .url
+                .as_deref()
+                .unwrap_or(DEFAULT_URL)
+                .trim_end_matches('/')
+                .to_string(),
+            api_key: self.api_key.clone(),
+            model: self.model.clone(),
+            dimensions: self.dimensions,
+        })
+    }
+}
+
+#[async_trait]
+impl Embed for TeiEmbed {
+    async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        tracing::debug!("Embedding {} characters with {}", text.len(), self.url);
+        let mut request = Request::builder()
+            .method("POST")
+            .uri(format!("{}/embed", self.url))
+            .header("Accept", "application/json")
+            .header("Content-Type", "application/json");
+        if let Some(api_key) = &self.api_key {
+            request = request.header("Authorization", format!("Bearer {}", api_key));
+        }
+        let request = request.body(serde_json::to_vec(&json!({ "inputs": text }))?.into())?;
+
+        let response = self.http_client.send(request).await?;
+        let status = response.status();
+        if !status.is_success() {
+            let error_body = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            let err = anyhow!("HTTP error {}: {}", status, error_body);
+            tracing::warn!("TEI failed to embed text: {}", err);
+            return Err(err);
+        }
+
+        // One input gets a list holding one embedding back
+        let embeddings: Vec<Vec<f32>> = response
+            .json()
+            .await
+            .map_err(|e| anyhow!("Failed to parse JSON response: {}", e))?;
+        embeddings
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("TEI returned no embedding"))
+    }
+
+    /// Named after the server's URL when no model was given.
+    fn model_id(&self) -> String {
+        format!("tei/{}", self.model.as_deref().unwrap_or(&self.url))
+    }
+
+    fn dimensions(&self) -> Option<usize> {
+        self.dimensions
+    }
+}
@@ -0,0 +1,44 @@
+use std::time::Duration;
+
+use anyhow::{Context, Result, bail};
+use local_cache::LocalCache;
+
+use crate::{
+    config::{CacheBackend, Config},
+    data_dir, database_dir,
+};
+
+/// Rewrites the local cache database without expired entries and free pages,
+/// printing its size before and after.
+pub fn run_compact(config: &Config) -> Result<()> {
+    if config.cache.backend != CacheBackend::Local {
+        bail!(
+            "Only the local cache backend can be compacted, not {}",
+            config.cache.backend.name()
+        );
+    }
+
+    if config.cache.read_only {
+        bail!("The cache is read-only; compact it where it is maintained");
+    }
+
+    let path = database_dir(&data_dir(config)?);
+    let ttl = config.cache.ttl_secs.map(Duration::from_secs);
+    let report = LocalCache::new(&path, ttl)
+        .and_then(LocalCache::compact)
+        .with_context(|| {
+            format!(
+                "Failed to compact {}; stop servers using it and try again",
+                path.display()
+            )
+        })?;
+
+    println!(
+        "Compacted {} from {:.1} to {:.1} MiB, removing {} expired entries",
+        path.display(),
+        report.before_bytes as f64 / (1024.0 * 1024.0),
+        report.after_bytes as f64 / (1024.0 * 1024.0),
+        report.purged
+    );
+    Ok(())
+}
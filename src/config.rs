@@ -0,0 +1,231 @@
+use std::{
+    env, fs,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use semantic_scholar_mcp_tools::{AuthorField, PaperField, RateLimitSettings, SimilaritySettings};
+use serde::Deserialize;
+
+use crate::{TransportKind, project_dirs};
+
+/// Settings read from `config.toml` in the user's configuration directory,
+/// such as `~/.config/semantic-scholar-mcp/config.toml` on Linux. Every
+/// setting is optional. Environment variables take precedence over the file,
+/// and command line flags over both.
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub cache: CacheConfig,
+    pub rate_limit: RateLimitSettings,
+    pub embedding: EmbeddingConfig,
+    pub api: ApiConfig,
+    pub defaults: DefaultsConfig,
+    pub log: LogConfig,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub transport: Option<TransportKind>,
+    pub listen: Option<SocketAddr>,
+    pub instructions: Option<PathBuf>,
+    pub disabled_groups: Vec<String>,
+    /// Answer from the cache alone, without contacting Semantic Scholar
+    pub offline: bool,
+}
+
+/// Where tool results, raw responses and seen papers are cached.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CacheBackend {
+    /// An LMDB database under the data directory
+    #[default]
+    Local,
+    /// A SQLite file under the data directory
+    Sqlite,
+    /// A Redis instance at `url`
+    Redis,
+    /// Process memory, lost on exit
+    Memory,
+    /// No caching
+    None,
+}
+
+impl CacheBackend {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Local => "local",
+            Self::Sqlite => "sqlite",
+            Self::Redis => "redis",
+            Self::Memory => "memory",
+            Self::None => "none",
+        }
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CacheConfig {
+    /// Directory holding the cache, library and dataset databases
+    pub dir: Option<PathBuf>,
+    /// How long cached tool results are reused, in seconds
+    pub ttl_secs: Option<u64>,
+    /// How often expired entries are deleted in the background, in seconds,
+    /// hourly by default. 0 leaves them to be purged by lookups
+    pub sweep_interval_secs: Option<u64>,
+    /// How long requests that found nothing, such as an unknown paper ID or
+    /// a search without results, are answered from the cache, in seconds.
+    /// 0 turns this off
+    pub negative_ttl_secs: Option<u64>,
+    pub backend: CacheBackend,
+    /// Address of the Redis instance, such as `redis://localhost:6379/0`
+    pub url: Option<String>,
+    /// Most entries the local cache holds before evicting the least
+    /// recently used
+    pub max_entries: Option<u64>,
+    /// Most compressed bytes of entries the local cache holds before evicting
+    /// the least recently used, half its database's map size by default
+    pub max_bytes: Option<u64>,
+    /// Size in bytes the local cache's database is opened with, 10 MiB by
+    /// default. It doubles whenever a write finds it full
+    pub map_size: Option<u64>,
+    /// How close a query must be to a cached one for its result to be reused
+    pub similarity: SimilaritySettings,
+    /// Consult the cache without writing to it, such as a snapshot shared
+    /// by a team
+    pub read_only: bool,
+    /// Directory of a local cache that takes the writes of a read-only one,
+    /// which are dropped otherwise
+    pub overlay_dir: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EmbeddingProvider {
+    Ollama,
+    Fastembed,
+    Candle,
+    Tei,
+    Hash,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EmbeddingConfig {
+    /// Ollama when it answers at startup, fastembed otherwise, unless set
+    pub provider: Option<EmbeddingProvider>,
+    pub model: Option<String>,
+    /// Where the embedding server listens, such as `http://localhost:11434`
+    pub url: Option<String>,
+    /// Length of the vectors an Ollama or TEI model returns, which other
+    /// providers know, or of those the hash embedder produces
+    pub dimensions: Option<usize>,
+    /// Run candle models on a GPU, when built with the `cuda` or `metal`
+    /// feature
+    pub gpu: bool,
+    /// Seconds an attempt to embed a text may take, 10 by default
+    pub timeout_secs: Option<u64>,
+    /// Attempts made after a failed or timed out one, 1 by default
+    pub retries: Option<u32>,
+    /// Providers tried in order when the one above fails to embed a text,
+    /// from `[[embedding.fallback]]` sections
+    pub fallback: Vec<EmbeddingConfig>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ApiConfig {
+    /// Root of the Semantic Scholar API, `https://api.semanticscholar.org`
+    /// unless requests go through a proxy
+    pub base_url: Option<String>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DefaultsConfig {
+    /// Fields returned by paper searches and batches that name none
+    pub paper_fields: Option<Vec<PaperField>>,
+    /// Fields returned by author batches that name none
+    pub author_fields: Option<Vec<AuthorField>>,
+    /// Characters of output after which tool calls that set no
+    /// `max_output_chars` leave out further results
+    pub max_output_chars: Option<usize>,
+}
+
+/// How often the JSON log file starts afresh.
+#[derive(Debug, Clone, Copy, Default, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum LogRotation {
+    Hourly,
+    #[default]
+    Daily,
+    Never,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogConfig {
+    /// Whether to write JSON logs to the `logs` directory under the data
+    /// directory
+    pub json_file: bool,
+    pub rotation: LogRotation,
+    /// Which events are logged, as in `RUST_LOG`, which takes precedence
+    pub filter: Option<String>,
+    /// File recording every request sent upstream, for accounting for API
+    /// usage. Nothing is recorded unless set
+    pub audit_file: Option<PathBuf>,
+}
+
+impl Config {
+    pub fn default_path() -> Result<PathBuf> {
+        Ok(project_dirs()?.config_dir().join("config.toml"))
+    }
+
+    /// Reads the configuration from `path`, or from the default location
+    /// when there is a file there, then applies environment variables.
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let path = match path {
+            Some(path) => Some(path.to_path_buf()),
+            None => Some(Self::default_path()?).filter(|path| path.exists()),
+        };
+
+        let mut config = match path {
+            Some(path) => {
+                let contents = fs::read_to_string(&path).with_context(|| {
+                    format!("Failed to read configuration from {}", path.display())
+                })?;
+                let config: Self = toml::from_str(&contents)
+                    .with_context(|| format!("Invalid configuration in {}", path.display()))?;
+                config
+                    .rate_limit
+                    .validate()
+                    .with_context(|| format!("Invalid configuration in {}", path.display()))?;
+                config
+            }
+            None => Self::default(),
+        };
+
+        config.apply_env();
+        Ok(config)
+    }
+
+    /// Environment variables override the file. Rate limits are read from
+    /// the environment by the rate limiter itself.
+    fn apply_env(&mut self) {
+        let read = |name: &str| env::var(name).ok().filter(|value| !value.trim().is_empty());
+
+        if let Some(url) = read("SEMANTIC_SCHOLAR_API_BASE_URL") {
+            self.api.base_url = Some(url);
+        }
+        if let Some(url) = read("OLLAMA_HOST") {
+            self.embedding.url = Some(url);
+        }
+        if let Some(model) = read("SEMANTIC_SCHOLAR_MCP_EMBEDDING_MODEL") {
+            self.embedding.model = Some(model);
+        }
+    }
+}
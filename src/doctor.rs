@@ -0,0 +1,250 @@
+use std::{env, sync::Arc};
+
+use anyhow::{Result, bail};
+use embed::Embed;
+use http_client::{HttpClient, Request, RequestBuilderExt};
+use local_cache::LocalCache;
+use semantic_scholar_mcp_tools::ClientSettings;
+
+use crate::{
+    config::{CacheBackend, Config, EmbeddingProvider},
+    data_dir, database_dir, embedder, open_cache,
+};
+
+/// A paper every deployment of the API knows.
+const PROBE_PAPER_ID: &str = "649def34f8be52c8b66281af98ae884c09aef38b";
+
+/// Share of the cache's memory map past which its headroom is reported.
+const CACHE_HEADROOM_WARNING: f64 = 0.8;
+
+enum Outcome {
+    Ok(String),
+    Warning { detail: String, fix: String },
+    Failure { detail: String, fix: String },
+}
+
+struct Check {
+    name: &'static str,
+    outcome: Outcome,
+}
+
+/// Checks everything the server depends on and prints what is wrong with a
+/// suggested fix for each. Fails when any check does.
+pub async fn run_doctor(config: &Config, http_client: Arc<dyn HttpClient>) -> Result<()> {
+    let mut client_settings = ClientSettings::new();
+    if let Some(base_url) = &config.api.base_url {
+        client_settings = client_settings.with_api_base_url(base_url);
+    }
+    let checks = vec![
+        Check {
+            name: "Semantic Scholar API key",
+            outcome: check_api_key(http_client.as_ref(), client_settings.api_base_url()).await,
+        },
+        Check {
+            name: "Embedding model",
+            outcome: check_embeddings(config, http_client).await,
+        },
+        Check {
+            name: "Cache database",
+            outcome: check_cache(config),
+        },
+    ];
+
+    let mut failures = 0;
+    for check in &checks {
+        match &check.outcome {
+            Outcome::Ok(detail) => println!("[ok]   {}: {}", check.name, detail),
+            Outcome::Warning { detail, fix } => {
+                println!("[warn] {}: {}", check.name, detail);
+                println!("       fix: {}", fix);
+            }
+            Outcome::Failure { detail, fix } => {
+                failures += 1;
+                println!("[fail] {}: {}", check.name, detail);
+                println!("       fix: {}", fix);
+            }
+        }
+    }
+
+    if failures > 0 {
+        bail!("{} of {} checks failed", failures, checks.len());
+    }
+
+    Ok(())
+}
+
+async fn check_api_key(http_client: &dyn HttpClient, api_base_url: &str) -> Outcome {
+    let Ok(api_key) = env::var("SEMANTIC_SCHOLAR_API_KEY") else {
+        return Outcome::Warning {
+            detail: "SEMANTIC_SCHOLAR_API_KEY is not set, so requests share the anonymous quota"
+                .into(),
+            fix: "request a key at https://www.semanticscholar.org/product/api and export SEMANTIC_SCHOLAR_API_KEY".into(),
+        };
+    };
+
+    let request = Request::builder()
+        .method("GET")
+        .uri(format!(
+            "{}/graph/v1/paper/{}?fields=title",
+            api_base_url, PROBE_PAPER_ID
+        ))
+        .header("x-api-key", api_key.trim())
+        .header("Accept", "application/json")
+        .end();
+
+    let response = match request {
+        Ok(request) => http_client.send(request).await,
+        Err(err) => {
+            return failure(
+                format!("could not build the request: {}", err),
+                "check the API base URL in the configuration",
+            );
+        }
+    };
+
+    match response {
+        Ok(response) if response.status().is_success() => Outcome::Ok(format!(
+            "authenticated request to {} succeeded",
+            api_base_url
+        )),
+        Ok(response) if response.status() == 401 || response.status() == 403 => failure(
+            format!("the API rejected the key (HTTP {})", response.status()),
+            "check SEMANTIC_SCHOLAR_API_KEY for typos, or request a new key",
+        ),
+        Ok(response) if response.status() == 429 => Outcome::Warning {
+            detail: "the key is rate limited right now (HTTP 429)".into(),
+            fix: "wait a moment, or lower the rate limits in the configuration".into(),
+        },
+        Ok(response) => failure(
+            format!("the API answered HTTP {}", response.status()),
+            "check https://status.semanticscholar.org and the API base URL",
+        ),
+        Err(err) => failure(
+            format!("could not reach {}: {}", api_base_url, err),
+            "check the network connection and any proxy settings",
+        ),
+    }
+}
+
+async fn check_embeddings(config: &Config, http_client: Arc<dyn HttpClient>) -> Outcome {
+    let embedder = match embedder(config, http_client).await {
+        Ok(embedder) => embedder,
+        Err(err) => {
+            return failure(
+                err.to_string(),
+                "fix `provider`, `model` and `url` in the [embedding] section of the configuration",
+            );
+        }
+    };
+
+    match embedder.embed.embed("semantic scholar doctor").await {
+        Ok(embedding) => match embedder.embed.dimensions() {
+            Some(expected) if embedding.len() != expected => failure(
+                format!(
+                    "the model returned {} dimensions, {} expected",
+                    embedding.len(),
+                    expected
+                ),
+                "set `model` and `dimensions` in the [embedding] section to match, and clear the cache embedded with the old model",
+            ),
+            _ => Outcome::Ok(format!(
+                "{} returned a {}-dimensional embedding",
+                embedder.embed.model_id(),
+                embedding.len()
+            )),
+        },
+        Err(err) => failure(
+            format!(
+                "{} did not return an embedding: {}",
+                embedder.embed.model_id(),
+                err
+            ),
+            &match embedder.provider {
+                EmbeddingProvider::Ollama => format!(
+                    "start Ollama and run `ollama pull {}`, or disable the embeddings tool group",
+                    config
+                        .embedding
+                        .model
+                        .as_deref()
+                        .unwrap_or("nomic-embed-text")
+                ),
+                EmbeddingProvider::Tei => {
+                    "start the text-embeddings-inference server, or fix `url` in the [embedding] section"
+                        .to_string()
+                }
+                EmbeddingProvider::Hash => "report the error, as hashing cannot fail".to_string(),
+                EmbeddingProvider::Fastembed | EmbeddingProvider::Candle => {
+                    "delete the models directory in the data directory to download the model again"
+                        .to_string()
+                }
+            },
+        ),
+    }
+}
+
+fn check_cache(config: &Config) -> Outcome {
+    match config.cache.backend {
+        CacheBackend::Local => {}
+        CacheBackend::None => return Outcome::Ok("caching is turned off".into()),
+        backend => {
+            return match open_cache(config) {
+                Ok(_) => Outcome::Ok(format!("the {} backend opened", backend.name())),
+                Err(err) => failure(
+                    format!("could not open the {} backend: {}", backend.name(), err),
+                    "check `backend` and `url` in the [cache] section of the configuration",
+                ),
+            };
+        }
+    }
+
+    let path = match data_dir(config) {
+        Ok(data_dir) => database_dir(&data_dir),
+        Err(err) => {
+            return failure(
+                err.to_string(),
+                "pass --data-dir or set SEMANTIC_SCHOLAR_MCP_CACHE_DIR",
+            );
+        }
+    };
+
+    let usage = LocalCache::new(&path, None)
+        .and_then(|cache| match config.cache.map_size {
+            Some(map_size) => cache.with_map_size(map_size as usize),
+            None => Ok(cache),
+        })
+        .and_then(|cache| cache.usage());
+    match usage {
+        Ok(usage) => {
+            let share = usage.used_bytes as f64 / usage.map_size as f64;
+            let detail = format!(
+                "{} uses {:.1} of {:.1} MiB",
+                path.display(),
+                usage.used_bytes as f64 / (1024.0 * 1024.0),
+                usage.map_size as f64 / (1024.0 * 1024.0)
+            );
+            if share > CACHE_HEADROOM_WARNING {
+                Outcome::Warning {
+                    detail,
+                    fix: format!(
+                        "the cache is {:.0}% full and grows on the next writes; raise `map_size` in [cache] to grow it ahead of time, or delete {} to start afresh",
+                        share * 100.0,
+                        path.display()
+                    ),
+                }
+            } else {
+                Outcome::Ok(detail)
+            }
+        }
+        Err(err) => failure(
+            format!("could not open {}: {}", path.display(), err),
+            "check the directory is writable, and stop other servers holding it open",
+        ),
+    }
+}
+
+fn failure(detail: String, fix: &str) -> Outcome {
+    Outcome::Failure {
+        detail,
+        fix: fix.to_string(),
+    }
+}
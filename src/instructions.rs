@@ -0,0 +1,56 @@
+use std::{fs, path::Path};
+
+use anyhow::{Context, Result};
+use context_server::Tool;
+
+const DEFAULT_TEMPLATE: &str = include_str!("instructions.md");
+
+/// The instructions sent to clients in the `initialize` result, which teach
+/// the model how the tools fit together. The template may refer to:
+/// - `{{name}}` and `{{version}}`: the server's name and version
+/// - `{{tools}}`: the names of the registered tools
+/// - `{{rate_limit}}`: a note on the rate limit that applies without an API key
+pub struct Instructions {
+    template: String,
+}
+
+impl Instructions {
+    /// Reads the template from `path`, or uses the built-in one. An empty
+    /// template sends no instructions.
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let template = match path {
+            Some(path) => fs::read_to_string(path)
+                .with_context(|| format!("Failed to read instructions from {}", path.display()))?,
+            None => DEFAULT_TEMPLATE.to_string(),
+        };
+
+        Ok(Self { template })
+    }
+
+    pub fn render(&self, tools: &[Tool], has_api_key: bool) -> Option<String> {
+        if self.template.trim().is_empty() {
+            return None;
+        }
+
+        let tools = tools
+            .iter()
+            .map(|tool| tool.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let rate_limit = if has_api_key {
+            ""
+        } else {
+            " (about one request per second, since no API key is configured)"
+        };
+
+        Some(
+            self.template
+                .replace("{{name}}", env!("CARGO_PKG_NAME"))
+                .replace("{{version}}", env!("CARGO_PKG_VERSION"))
+                .replace("{{tools}}", &tools)
+                .replace("{{rate_limit}}", rate_limit)
+                .trim()
+                .to_string(),
+        )
+    }
+}
@@ -0,0 +1,9 @@
+mod instructions;
+mod server;
+mod transport;
+
+pub use crate::{
+    instructions::Instructions,
+    server::{ContextServerState, ContextServerStateBuilder},
+    transport::{SseTransport, StdioTransport, StreamableHttpTransport, Transport},
+};
@@ -0,0 +1,88 @@
+use std::{env, path::Path};
+
+use anyhow::{Context, Result};
+use semantic_scholar_mcp_tools::{McpLayer, set_stderr_level, show_on_stderr};
+use tracing_appender::{
+    non_blocking::WorkerGuard,
+    rolling::{RollingFileAppender, Rotation},
+};
+use tracing_subscriber::{
+    EnvFilter, Layer,
+    filter::{LevelFilter, filter_fn},
+    fmt,
+    layer::SubscriberExt,
+    util::SubscriberInitExt,
+};
+
+use crate::config::LogRotation;
+
+/// Debug events from this workspace's crates, and only warnings from
+/// dependencies such as the HTTP client.
+const DEFAULT_FILTER: &str = "warn,semantic_scholar_mcp=debug,semantic_scholar_mcp_tools=debug,\
+semantic_scholar_client=debug,cache=debug,local_cache=debug,embed=debug,ollama_embed=debug,fastembed_embed=debug,candle_embed=debug,tei_embed=debug,dataset_sync=debug,library=debug";
+
+/// Routes events to the client of the request they were raised in, to
+/// stderr, and, when `json_dir` is given, as JSON lines to a rotating file
+/// in that directory. Events from dependencies still using `log` are
+/// included. The returned guard flushes the file when dropped.
+///
+/// `RUST_LOG`, or else `filter`, limits the events every destination gets,
+/// using the same directives as `env_logger`, such as
+/// `info,local_cache=debug`. Clients narrow what they receive further with
+/// `logging/setLevel`. When a filter is given, stderr shows every event it
+/// lets through that the current client doesn't receive.
+pub fn init(
+    json_dir: Option<&Path>,
+    rotation: LogRotation,
+    filter: Option<&str>,
+) -> Result<Option<WorkerGuard>> {
+    let directives = env::var("RUST_LOG")
+        .ok()
+        .filter(|directives| !directives.trim().is_empty())
+        .or_else(|| filter.map(str::to_string));
+    let env_filter = match &directives {
+        Some(directives) => {
+            let env_filter = EnvFilter::try_new(directives)
+                .with_context(|| format!("Invalid log filter '{}'", directives))?;
+            set_stderr_level(env_filter.max_level_hint().unwrap_or(LevelFilter::TRACE));
+            env_filter
+        }
+        None => EnvFilter::new(DEFAULT_FILTER),
+    };
+
+    let stderr = fmt::layer()
+        .with_writer(std::io::stderr)
+        .with_filter(filter_fn(|metadata| show_on_stderr(metadata.level())));
+
+    let (json, guard) = match json_dir {
+        Some(dir) => {
+            let appender = RollingFileAppender::builder()
+                .rotation(match rotation {
+                    LogRotation::Hourly => Rotation::HOURLY,
+                    LogRotation::Daily => Rotation::DAILY,
+                    LogRotation::Never => Rotation::NEVER,
+                })
+                .filename_prefix("semantic-scholar-mcp")
+                .filename_suffix("log")
+                .build(dir)?;
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            let layer = fmt::layer()
+                .json()
+                .with_current_span(true)
+                .with_span_list(true)
+                .with_writer(writer)
+                .with_filter(LevelFilter::DEBUG);
+            (Some(layer), Some(guard))
+        }
+        None => (None, None),
+    };
+
+    tracing_subscriber::registry()
+        .with(env_filter)
+        .with(McpLayer)
+        .with(stderr)
+        .with(json)
+        .try_init()?;
+
+    Ok(guard)
+}
@@ -1,153 +1,601 @@
-use std::{env, path::PathBuf, sync::Arc};
+mod compact;
+mod config;
+mod doctor;
+mod logging;
+mod repl;
+mod run;
 
-use anyhow::{Result, anyhow};
-use context_server::{ContextServer, ContextServerRpcRequest, ContextServerRpcResponse};
-use context_server_utils::{
-    prompt_registry::PromptRegistry, resource_registry::ResourceRegistry,
-    tool_registry::ToolRegistry,
+use std::{
+    env,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
 };
+
+use anyhow::{Context, Result, anyhow, bail};
+use cache::{Cache, NoCache, ReadOnlyCache};
+use candle_embed::CandleEmbed;
+use clap::{Parser, Subcommand, ValueEnum};
+use config::{CacheBackend, Config, EmbeddingConfig, EmbeddingProvider, LogRotation};
+use dataset_sync::DatasetSync;
 use directories::ProjectDirs;
-use http_client::HttpClient;
+use embed::{Embed, FallbackEmbed, HashEmbed, TimeoutEmbed};
+use fastembed_embed::FastEmbed;
+use http_client::{HttpClient, http::Uri};
 use http_client_reqwest::HttpClientReqwest;
-use local_cache::LocalCache;
+use library::Library;
+use local_cache::{CacheLimits, DEFAULT_MAP_SIZE, LocalCache};
+use memory_cache::MemoryCache;
 use ollama_embed::OllamaEmbed;
-use semantic_scholar_mcp_tools::{
-    AuthorDetailsTool, AuthorPapersTool, AuthorSearchTool, PaperCitationsTool, PaperDetailsTool,
-    PaperRecommendationMultiTool, PaperRecommendationSingleTool, PaperReferencesTool,
-    PaperSearchTool, RateLimiter,
+use redis_cache::RedisCache;
+use semantic_scholar_mcp::{
+    ContextServerState, Instructions, SseTransport, StdioTransport, StreamableHttpTransport,
+    Transport,
 };
-use tokio::io::{self, AsyncBufReadExt, AsyncWriteExt, BufReader};
+use semantic_scholar_mcp_tools::{RateLimiter, set_audit_log};
+use serde::Deserialize;
+use sqlite_cache::SqliteCache;
+use tei_embed::TeiEmbed;
+
+/// How clients connect to the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum TransportKind {
+    /// A single client that runs the server as a child process
+    Stdio,
+    /// Any number of clients over HTTP, with server messages streamed as
+    /// server-sent events
+    Sse,
+    /// Any number of clients over HTTP on a single endpoint, as in the
+    /// current MCP specification
+    StreamableHttp,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Runs a single tool and prints its result, without an MCP client
+    Run {
+        /// Name of the tool, such as paper_search
+        tool: String,
+        /// Arguments of the tool as a JSON object, or - to read them from stdin
+        #[arg(long, default_value = "{}")]
+        args: String,
+    },
+    /// Opens an interactive prompt for calling tools, with tool name
+    /// completion and history kept across sessions
+    Repl,
+    /// Checks the API key, the embedding model and the cache, suggesting
+    /// fixes for whatever is wrong
+    Doctor,
+    /// Maintains the cache
+    Cache {
+        #[command(subcommand)]
+        command: CacheCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum CacheCommand {
+    /// Rewrites the local cache database without expired entries and the
+    /// space deleted ones left behind. Stop servers using it first
+    Compact,
+}
 
-struct ContextServerState {
-    rpc: ContextServer,
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// Configuration file, in place of config.toml in the user's
+    /// configuration directory
+    #[arg(long, env = "SEMANTIC_SCHOLAR_MCP_CONFIG")]
+    config: Option<PathBuf>,
+    /// Directory holding the cache, library and dataset databases, in place
+    /// of the user's data directory
+    #[arg(long, env = "SEMANTIC_SCHOLAR_MCP_CACHE_DIR")]
+    data_dir: Option<PathBuf>,
+    /// How clients connect [default: stdio]
+    #[arg(long, value_enum)]
+    transport: Option<TransportKind>,
+    /// Address the HTTP transports listen on [default: 127.0.0.1:8000]
+    #[arg(long)]
+    listen: Option<SocketAddr>,
+    /// File with the instructions sent to clients on initialize, in place of
+    /// the built-in ones. An empty file sends none
+    #[arg(long)]
+    instructions: Option<PathBuf>,
+    /// Tool group to disable: embeddings, recommendations, datasets or sync.
+    /// May be repeated
+    #[arg(long = "disable-group", value_name = "GROUP")]
+    disabled_groups: Vec<String>,
+    /// Write JSON logs to the logs directory under the data directory
+    #[arg(long)]
+    log_file: bool,
+    /// How often the JSON log file is rotated [default: daily]
+    #[arg(long, value_enum)]
+    log_rotation: Option<LogRotation>,
+    /// Append a JSON line for every request sent upstream to this file
+    #[arg(long, value_name = "FILE")]
+    audit_log: Option<PathBuf>,
+    /// Answer from the cache alone, without contacting Semantic Scholar
+    #[arg(long)]
+    offline: bool,
 }
 
+const DEFAULT_LISTEN_ADDR: ([u8; 4], u16) = ([127, 0, 0, 1], 8000);
+
+/// How often expired cache entries are deleted unless configured otherwise.
+const DEFAULT_SWEEP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// How long an attempt to embed a text may take unless configured otherwise.
+const DEFAULT_EMBED_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Attempts made after a failed one unless configured otherwise.
+const DEFAULT_EMBED_RETRIES: u32 = 1;
+
+/// How long Ollama has to answer at startup before fastembed is used in its
+/// place, when no embedding provider is configured.
+const OLLAMA_PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
 fn project_dirs() -> Result<ProjectDirs> {
     ProjectDirs::from("code", "fdionisi", "semantic-scholar-mcp")
         .ok_or_else(|| anyhow!("unable to find project directory"))
 }
 
-fn database_dir() -> Result<PathBuf> {
-    Ok(project_dirs()?.data_dir().join("cache.db"))
-}
-
-impl ContextServerState {
-    fn new(http_client: Arc<dyn HttpClient>) -> Result<Self> {
-        let resource_registry = Arc::new(ResourceRegistry::default());
-
-        let tool_registry = Arc::new(ToolRegistry::default());
-
-        let rate_limiter = Arc::new(RateLimiter::new());
-        let local_cache = Arc::new(LocalCache::new(database_dir()?, None)?);
-        let ollama_embed = Arc::new(
-            OllamaEmbed::builder()
-                .with_http_client(http_client.clone())
-                .build(),
-        );
-        tool_registry.register(Arc::new(AuthorDetailsTool::new(
-            http_client.clone(),
-            rate_limiter.clone(),
-            local_cache.clone(),
-            ollama_embed.clone(),
-        )));
-        tool_registry.register(Arc::new(AuthorPapersTool::new(
-            http_client.clone(),
-            rate_limiter.clone(),
-            local_cache.clone(),
-            ollama_embed.clone(),
-        )));
-        tool_registry.register(Arc::new(AuthorSearchTool::new(
-            http_client.clone(),
-            rate_limiter.clone(),
-            local_cache.clone(),
-            ollama_embed.clone(),
-        )));
-        tool_registry.register(Arc::new(PaperSearchTool::new(
-            http_client.clone(),
-            rate_limiter.clone(),
-            local_cache.clone(),
-            ollama_embed.clone(),
-        )));
-        tool_registry.register(Arc::new(PaperDetailsTool::new(
-            http_client.clone(),
-            rate_limiter.clone(),
-            local_cache.clone(),
-            ollama_embed.clone(),
-        )));
-        tool_registry.register(Arc::new(PaperCitationsTool::new(
-            http_client.clone(),
-            rate_limiter.clone(),
-            local_cache.clone(),
-            ollama_embed.clone(),
-        )));
-        tool_registry.register(Arc::new(PaperReferencesTool::new(
-            http_client.clone(),
-            rate_limiter.clone(),
-            local_cache.clone(),
-            ollama_embed.clone(),
-        )));
-        tool_registry.register(Arc::new(PaperRecommendationSingleTool::new(
-            http_client.clone(),
-            rate_limiter.clone(),
-            local_cache.clone(),
-            ollama_embed.clone(),
-        )));
-        tool_registry.register(Arc::new(PaperRecommendationMultiTool::new(
-            http_client.clone(),
-            rate_limiter.clone(),
-            local_cache.clone(),
-            ollama_embed.clone(),
-        )));
-
-        let prompt_registry = Arc::new(PromptRegistry::default());
-
-        Ok(Self {
-            rpc: ContextServer::builder()
-                .with_server_info((env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")))
-                .with_resources(resource_registry)
-                .with_tools(tool_registry)
-                .with_prompts(prompt_registry)
-                .build()?,
-        })
-    }
-
-    async fn process_request(
-        &self,
-        request: ContextServerRpcRequest,
-    ) -> Result<Option<ContextServerRpcResponse>> {
-        self.rpc.handle_incoming_message(request).await
+fn data_dir(config: &Config) -> Result<PathBuf> {
+    match &config.cache.dir {
+        Some(dir) => Ok(dir.clone()),
+        None => Ok(project_dirs()?.data_dir().to_path_buf()),
+    }
+}
+
+fn database_dir(data_dir: &Path) -> PathBuf {
+    data_dir.join("cache.db")
+}
+
+fn sqlite_path(data_dir: &Path) -> PathBuf {
+    data_dir.join("cache.sqlite3")
+}
+
+fn datasets_dir(data_dir: &Path) -> PathBuf {
+    data_dir.join("datasets.db")
+}
+
+fn library_dir(data_dir: &Path) -> PathBuf {
+    data_dir.join("library.db")
+}
+
+fn models_dir(data_dir: &Path) -> PathBuf {
+    data_dir.join("models")
+}
+
+/// Accepts `host:port` as Ollama does, as well as full URLs.
+fn embedding_url(url: &str) -> String {
+    if url.contains("://") {
+        url.to_string()
+    } else {
+        format!("http://{}", url)
+    }
+}
+
+/// The embedding model tools compare queries with.
+struct Embedder {
+    embed: Arc<dyn Embed>,
+    provider: EmbeddingProvider,
+}
+
+/// The main embedding provider, followed by the fallbacks configured for
+/// it, if any, each bounded by its timeout and retries.
+async fn embedder(config: &Config, http_client: Arc<dyn HttpClient>) -> Result<Embedder> {
+    let models_dir = models_dir(&data_dir(config)?);
+    let main = bounded(
+        &config.embedding,
+        provider_embedder(&config.embedding, &models_dir, http_client.clone()).await?,
+    );
+    if config.embedding.fallback.is_empty() {
+        return Ok(main);
+    }
+
+    let mut providers = vec![main.embed];
+    for fallback in &config.embedding.fallback {
+        if fallback.provider.is_none() {
+            bail!("Every fallback in the [embedding] section must name its provider");
+        }
+        let embedder = provider_embedder(fallback, &models_dir, http_client.clone())
+            .await
+            .context("Failed to set up a fallback embedding provider")?;
+        providers.push(bounded(fallback, embedder).embed);
+    }
+
+    Ok(Embedder {
+        embed: Arc::new(FallbackEmbed::new(providers)?),
+        provider: main.provider,
+    })
+}
+
+fn bounded(embedding: &EmbeddingConfig, embedder: Embedder) -> Embedder {
+    let timeout = embedding
+        .timeout_secs
+        .map_or(DEFAULT_EMBED_TIMEOUT, Duration::from_secs);
+    let retries = embedding.retries.unwrap_or(DEFAULT_EMBED_RETRIES);
+
+    Embedder {
+        embed: Arc::new(TimeoutEmbed::new(embedder.embed, timeout, retries)),
+        provider: embedder.provider,
+    }
+}
+
+/// Uses the configured provider or, when none is, Ollama if it answers
+/// and the bundled fastembed model otherwise.
+async fn provider_embedder(
+    embedding: &EmbeddingConfig,
+    models_dir: &Path,
+    http_client: Arc<dyn HttpClient>,
+) -> Result<Embedder> {
+    let model = embedding.model.as_deref();
+    match embedding.provider {
+        Some(EmbeddingProvider::Ollama) => ollama_embedder(embedding, http_client),
+        Some(EmbeddingProvider::Fastembed) => fastembed_embedder(models_dir, model).await,
+        Some(EmbeddingProvider::Candle) => candle_embedder(embedding, models_dir).await,
+        Some(EmbeddingProvider::Tei) => tei_embedder(embedding, http_client).await,
+        Some(EmbeddingProvider::Hash) => Ok(Embedder {
+            embed: Arc::new(match embedding.dimensions {
+                Some(dimensions) => HashEmbed::with_dimensions(dimensions),
+                None => HashEmbed::new(),
+            }),
+            provider: EmbeddingProvider::Hash,
+        }),
+        None => {
+            let ollama = ollama_embedder(embedding, http_client)?;
+            match tokio::time::timeout(OLLAMA_PROBE_TIMEOUT, ollama.embed.embed("probe")).await {
+                Ok(Ok(_)) => Ok(ollama),
+                _ => {
+                    tracing::info!("Ollama is not answering, embedding with fastembed instead");
+                    // A configured model names an Ollama one
+                    fastembed_embedder(models_dir, None).await
+                }
+            }
+        }
+    }
+}
+
+fn ollama_embedder(
+    embedding: &EmbeddingConfig,
+    http_client: Arc<dyn HttpClient>,
+) -> Result<Embedder> {
+    let mut builder = OllamaEmbed::builder();
+    builder.with_http_client(http_client);
+    if let Some(url) = &embedding.url {
+        let uri: Uri = embedding_url(url)
+            .parse()
+            .map_err(|err| anyhow!("Invalid embedding URL {}: {}", url, err))?;
+        builder.with_uri(uri);
+    }
+    if let Some(model) = &embedding.model {
+        builder.with_model(model);
+    }
+    if let Some(dimensions) = embedding.dimensions {
+        builder.with_dimensions(dimensions);
+    }
+
+    Ok(Embedder {
+        embed: Arc::new(builder.build()),
+        provider: EmbeddingProvider::Ollama,
+    })
+}
+
+async fn fastembed_embedder(models_dir: &Path, model: Option<&str>) -> Result<Embedder> {
+    let mut builder = FastEmbed::builder();
+    builder.with_cache_dir(models_dir);
+    if let Some(model) = model {
+        builder.with_model(model);
+    }
+    // Loading the model reads, and the first time downloads, its files
+    let embed = tokio::task::spawn_blocking(move || builder.build())
+        .await?
+        .context("Failed to load the fastembed model")?;
+
+    Ok(Embedder {
+        embed: Arc::new(embed),
+        provider: EmbeddingProvider::Fastembed,
+    })
+}
+
+async fn candle_embedder(embedding: &EmbeddingConfig, models_dir: &Path) -> Result<Embedder> {
+    let mut builder = CandleEmbed::builder();
+    builder.with_cache_dir(models_dir).with_gpu(embedding.gpu);
+    if let Some(model) = &embedding.model {
+        builder.with_model(model);
     }
+    // Loading the model reads, and the first time downloads, its files
+    let embed = tokio::task::spawn_blocking(move || builder.build())
+        .await?
+        .context("Failed to load the candle model")?;
+
+    Ok(Embedder {
+        embed: Arc::new(embed),
+        provider: EmbeddingProvider::Candle,
+    })
+}
+
+async fn tei_embedder(
+    embedding: &EmbeddingConfig,
+    http_client: Arc<dyn HttpClient>,
+) -> Result<Embedder> {
+    let mut builder = TeiEmbed::builder();
+    builder.with_http_client(http_client);
+    if let Some(url) = &embedding.url {
+        builder.with_url(embedding_url(url));
+    }
+    if let Ok(api_key) = env::var("SEMANTIC_SCHOLAR_MCP_EMBEDDING_API_KEY") {
+        builder.with_api_key(api_key);
+    }
+    if let Some(dimensions) = embedding.dimensions {
+        builder.with_dimensions(dimensions);
+    }
+
+    match &embedding.model {
+        Some(model) => {
+            builder.with_model(model);
+        }
+        None => match builder.build()?.served_model().await {
+            Ok(model) => {
+                builder.with_model(model);
+            }
+            Err(err) => tracing::warn!(
+                "Could not ask TEI which model it serves, naming it after its URL: {}",
+                err
+            ),
+        },
+    }
+
+    Ok(Embedder {
+        embed: Arc::new(builder.build()?),
+        provider: EmbeddingProvider::Tei,
+    })
+}
+
+/// Deletes expired cache entries every `interval`, so lookups scan fewer of
+/// them and the cache doesn't keep them on disk until the next lookup.
+async fn sweep_cache(cache: Arc<dyn Cache>, interval: Duration) {
+    let mut ticker = tokio::time::interval(interval);
+    // The first tick completes at once, and the cache was just opened
+    ticker.tick().await;
+    loop {
+        ticker.tick().await;
+        let cache = cache.clone();
+        // Scanning every entry blocks, so it stays off the async workers
+        match tokio::task::spawn_blocking(move || cache.purge_expired()).await {
+            Ok(Ok(0)) => {}
+            Ok(Ok(purged)) => tracing::debug!("Swept {} expired cache entries", purged),
+            Ok(Err(err)) => tracing::warn!("Failed to sweep expired cache entries: {}", err),
+            Err(err) => tracing::warn!("Cache sweep panicked: {}", err),
+        }
+    }
+}
+
+/// Opens the cache backend chosen in the configuration.
+/// Offline, entries are kept however old they are, as the cache is all there
+/// is to answer from.
+fn open_cache(config: &Config) -> Result<Arc<dyn Cache>> {
+    let ttl = config.cache.ttl_secs.map(Duration::from_secs);
+    let offline = config.server.offline;
+    let cache: Arc<dyn Cache> = match config.cache.backend {
+        // A shared snapshot is opened read-only, so reading it changes nothing
+        CacheBackend::Local if config.cache.read_only => {
+            let cache = LocalCache::open_read_only(database_dir(&data_dir(config)?), ttl)?;
+            Arc::new(if offline { cache.keep_expired() } else { cache })
+        }
+        CacheBackend::Local => {
+            let mut cache = LocalCache::new(database_dir(&data_dir(config)?), ttl)?;
+            if offline {
+                cache = cache.keep_expired();
+            }
+            if let Some(map_size) = config.cache.map_size {
+                cache = cache.with_map_size(map_size as usize)?;
+            }
+            let defaults = CacheLimits::for_map_size(
+                config
+                    .cache
+                    .map_size
+                    .map_or(DEFAULT_MAP_SIZE, |map_size| map_size as usize),
+            );
+            let limits = CacheLimits {
+                max_entries: config.cache.max_entries.or(defaults.max_entries),
+                max_bytes: config.cache.max_bytes.or(defaults.max_bytes),
+            };
+            Arc::new(cache.with_limits(limits))
+        }
+        CacheBackend::Sqlite => {
+            let cache = SqliteCache::new(sqlite_path(&data_dir(config)?), ttl)?;
+            Arc::new(if offline { cache.keep_expired() } else { cache })
+        }
+        CacheBackend::Redis => {
+            let url = config
+                .cache
+                .url
+                .as_deref()
+                .ok_or_else(|| anyhow!("The redis cache backend needs `url` in [cache]"))?;
+            let cache = RedisCache::new(url, ttl)?;
+            Arc::new(if offline { cache.keep_expired() } else { cache })
+        }
+        CacheBackend::Memory => Arc::new(MemoryCache::new(ttl)),
+        CacheBackend::None => Arc::new(NoCache),
+    };
+    if !config.cache.read_only {
+        return Ok(cache);
+    }
+
+    let overlay = match &config.cache.overlay_dir {
+        Some(dir) => {
+            let overlay = LocalCache::new(dir, ttl)?;
+            let overlay = if offline {
+                overlay.keep_expired()
+            } else {
+                overlay
+            };
+            Some(Arc::new(overlay) as Arc<dyn Cache>)
+        }
+        None => None,
+    };
+    Ok(Arc::new(ReadOnlyCache::new(cache, overlay)))
+}
+
+/// Builds the server from the configuration.
+async fn build_server(
+    http_client: Arc<dyn HttpClient>,
+    config: &Config,
+    instructions: Instructions,
+    disabled_groups: &[String],
+    cache: Arc<dyn Cache>,
+) -> Result<ContextServerState> {
+    let data_dir = data_dir(config)?;
+    let mut dataset_sync = DatasetSync::new(datasets_dir(&data_dir), http_client.clone())?;
+    if let Some(base_url) = &config.api.base_url {
+        dataset_sync = dataset_sync.with_api_base_url(base_url);
+    }
+    let library = Library::new(library_dir(&data_dir))?;
+    let embedder = embedder(config, http_client.clone()).await?;
+
+    let mut builder = ContextServerState::builder()
+        .with_http_client(http_client)
+        .with_rate_limiter(Arc::new(RateLimiter::from_settings(&config.rate_limit)))
+        .with_cache(cache)
+        .with_embed(embedder.embed)
+        .with_dataset_sync(Arc::new(dataset_sync))
+        .with_library(Arc::new(library))
+        .with_instructions(instructions)
+        .with_offline(config.server.offline)
+        .with_similarity(config.cache.similarity.clone());
+    if let Some(base_url) = &config.api.base_url {
+        builder = builder.with_api_base_url(base_url);
+    }
+    if let Some(fields) = &config.defaults.paper_fields {
+        builder = builder.with_default_paper_fields(fields.clone());
+    }
+    if let Some(fields) = &config.defaults.author_fields {
+        builder = builder.with_default_author_fields(fields.clone());
+    }
+    if let Some(max_output_chars) = config.defaults.max_output_chars {
+        builder = builder.with_max_output_chars(max_output_chars);
+    }
+    if let Some(negative_ttl_secs) = config.cache.negative_ttl_secs {
+        builder = builder.with_negative_cache_ttl(Duration::from_secs(negative_ttl_secs));
+    }
+    for group in disabled_groups {
+        builder = builder.with_disabled_group(group);
+    }
+    builder.build()
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    let mut config = Config::load(cli.config.as_deref())?;
+    if let Some(data_dir) = &cli.data_dir {
+        config.cache.dir = Some(data_dir.clone());
+    }
+    if cli.log_file {
+        config.log.json_file = true;
+    }
+    if let Some(rotation) = cli.log_rotation {
+        config.log.rotation = rotation;
+    }
+    if let Some(audit_log) = &cli.audit_log {
+        config.log.audit_file = Some(audit_log.clone());
+    }
+    if cli.offline {
+        config.server.offline = true;
+    }
+
+    let log_dir = data_dir(&config)?.join("logs");
+    let _log_guard = logging::init(
+        config.log.json_file.then_some(log_dir.as_path()),
+        config.log.rotation,
+        config.log.filter.as_deref(),
+    )?;
+
+    if let Some(audit_file) = &config.log.audit_file {
+        set_audit_log(audit_file)
+            .with_context(|| format!("Failed to open the audit log at {}", audit_file.display()))?;
+    }
+
     let http_client = Arc::new(HttpClientReqwest::default());
 
-    if env::var("SEMANTIC_SCHOLAR_API_KEY").is_err() {
-        eprintln!("SEMANTIC_SCHOLAR_API_KEY environment variable is not defined");
+    if let Some(Command::Doctor) = &cli.command {
+        return doctor::run_doctor(&config, http_client).await;
+    }
+    if let Some(Command::Cache {
+        command: CacheCommand::Compact,
+    }) = &cli.command
+    {
+        return compact::run_compact(&config);
     }
 
-    let state = ContextServerState::new(http_client)?;
+    if env::var("SEMANTIC_SCHOLAR_API_KEY").is_err() {
+        tracing::warn!("SEMANTIC_SCHOLAR_API_KEY environment variable is not defined");
+    }
+    let instructions = Instructions::load(
+        cli.instructions
+            .as_deref()
+            .or(config.server.instructions.as_deref()),
+    )?;
+    let disabled_groups: Vec<String> = config
+        .server
+        .disabled_groups
+        .iter()
+        .chain(&cli.disabled_groups)
+        .cloned()
+        .collect();
+    let cache = open_cache(&config)?;
+    let state = Arc::new(
+        build_server(
+            http_client,
+            &config,
+            instructions,
+            &disabled_groups,
+            cache.clone(),
+        )
+        .await?,
+    );
 
-    let mut stdin = BufReader::new(io::stdin()).lines();
-    let mut stdout = io::stdout();
+    if let Some(Command::Run { tool, args }) = &cli.command {
+        return run::run_tool(&state, tool, args).await;
+    }
+    if let Some(Command::Repl) = &cli.command {
+        return repl::run_repl(&state, &data_dir(&config)?.join("repl_history.txt")).await;
+    }
 
-    while let Some(line) = stdin.next_line().await? {
-        let request: ContextServerRpcRequest = match serde_json::from_str(&line) {
-            Ok(req) => req,
-            Err(e) => {
-                eprintln!("Error parsing request: {}", e);
-                continue;
-            }
-        };
+    // Offline, the health checks would find every service down
+    if !config.server.offline {
+        let monitored = state.clone();
+        tokio::spawn(async move { monitored.monitor_tool_groups().await });
+    }
 
-        if let Some(response) = state.process_request(request).await? {
-            let response_json = serde_json::to_string(&response)?;
-            stdout.write_all(response_json.as_bytes()).await?;
-            stdout.write_all(b"\n").await?;
-            stdout.flush().await?;
-        }
+    let sweep_interval = config
+        .cache
+        .sweep_interval_secs
+        .map_or(DEFAULT_SWEEP_INTERVAL, Duration::from_secs);
+    // Offline, nothing swept could be fetched again
+    if !sweep_interval.is_zero() && !config.server.offline {
+        tokio::spawn(sweep_cache(cache, sweep_interval));
     }
 
-    Ok(())
+    let listen = cli
+        .listen
+        .or(config.server.listen)
+        .unwrap_or(DEFAULT_LISTEN_ADDR.into());
+    let transport: Box<dyn Transport> = match cli
+        .transport
+        .or(config.server.transport)
+        .unwrap_or(TransportKind::Stdio)
+    {
+        TransportKind::Stdio => Box::new(StdioTransport),
+        TransportKind::Sse => Box::new(SseTransport::new(listen)),
+        TransportKind::StreamableHttp => Box::new(StreamableHttpTransport::new(listen)),
+    };
+
+    transport.serve(state).await
 }
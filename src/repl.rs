@@ -0,0 +1,251 @@
+use std::{collections::BTreeMap, path::Path, sync::Arc};
+
+use anyhow::{Context as _, Result, anyhow, bail};
+use futures::channel::mpsc;
+use rustyline::{
+    Context, Editor, Helper, Highlighter, Hinter, Validator, completion::Completer,
+    error::ReadlineError, history::FileHistory,
+};
+use semantic_scholar_mcp::ContextServerState;
+use semantic_scholar_mcp_tools::Session;
+use serde_json::{Map, Value, json};
+
+use crate::run::call_tool;
+
+/// Lines remembered across sessions.
+const HISTORY_SIZE: usize = 1000;
+
+const HELP: &str = "\
+Call a tool with its arguments as key=value pairs or as a JSON object:
+
+  paper_search query=\"graph neural networks\" limit=5
+  paper_details {\"paper_id\": \"arXiv:1706.03762\"}
+
+Commands:
+  :tools           list the available tools
+  :help <tool>     describe a tool and its arguments
+  :quit            leave, as does Ctrl-D";
+
+/// A tool as listed by the server, with the JSON type of each argument.
+struct ToolInfo {
+    description: String,
+    arguments: BTreeMap<String, String>,
+}
+
+/// Completes tool names at the start of a line, and argument names after.
+#[derive(Helper, Hinter, Highlighter, Validator)]
+struct ToolCompleter {
+    tools: BTreeMap<String, ToolInfo>,
+}
+
+impl Completer for ToolCompleter {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _context: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let line = &line[..pos];
+        let start = line.rfind(char::is_whitespace).map_or(0, |index| index + 1);
+        let word = &line[start..];
+
+        let candidates = if start == 0 {
+            self.tools
+                .keys()
+                .filter(|name| name.starts_with(word))
+                .cloned()
+                .collect()
+        } else {
+            let tool = line.split_whitespace().next().unwrap_or_default();
+            match self.tools.get(tool) {
+                Some(info) if !word.contains('=') => info
+                    .arguments
+                    .keys()
+                    .filter(|name| name.starts_with(word))
+                    .map(|name| format!("{}=", name))
+                    .collect(),
+                _ => Vec::new(),
+            }
+        };
+
+        Ok((start, candidates))
+    }
+}
+
+/// Runs an interactive prompt calling tools without an MCP client. Lines are
+/// kept in `history_path` across sessions, and every call shares one session
+/// so paper and author IDs seen in results can be reused.
+pub async fn run_repl(server: &ContextServerState, history_path: &Path) -> Result<()> {
+    let session = server.open_session("repl", None);
+    let tools = list_tools(server, &session).await?;
+
+    let mut editor: Editor<ToolCompleter, FileHistory> = Editor::new()?;
+    editor.set_max_history_size(HISTORY_SIZE)?;
+    editor.set_helper(Some(ToolCompleter { tools }));
+    // The file doesn't exist until the first session ends
+    let _ = editor.load_history(history_path);
+
+    println!(
+        "semantic-scholar-mcp {}. Type :help for help, Tab to complete tool names.",
+        env!("CARGO_PKG_VERSION")
+    );
+
+    loop {
+        let line = match tokio::task::block_in_place(|| editor.readline("s2> ")) {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(err) => return Err(err.into()),
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        editor.add_history_entry(line)?;
+
+        let tools = &editor.helper().expect("helper is set").tools;
+        let (command, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        match command {
+            ":quit" | ":exit" => break,
+            ":help" if rest.is_empty() => println!("{}", HELP),
+            ":help" => match tools.get(rest.trim()) {
+                Some(info) => print_tool(rest.trim(), info),
+                None => eprintln!("Unknown tool '{}'", rest.trim()),
+            },
+            ":tools" => {
+                for name in tools.keys() {
+                    println!("{}", name);
+                }
+            }
+            _ if command.starts_with(':') => {
+                eprintln!("Unknown command '{}'. Type :help for help", command)
+            }
+            tool => {
+                let Some(info) = tools.get(tool) else {
+                    eprintln!("Unknown tool '{}'. Type :tools to list them", tool);
+                    continue;
+                };
+                let arguments = match parse_arguments(rest, info) {
+                    Ok(arguments) => arguments,
+                    Err(err) => {
+                        eprintln!("{}", err);
+                        continue;
+                    }
+                };
+                match call_tool(server, &session, tool, arguments).await {
+                    Ok(text) => println!("{}", pretty(&text)),
+                    Err(err) => eprintln!("Error: {}", err),
+                }
+            }
+        }
+    }
+
+    if let Some(parent) = history_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    editor
+        .save_history(history_path)
+        .with_context(|| format!("Failed to save history to {}", history_path.display()))
+}
+
+/// Asks the server for its tools, so disabled groups are left out.
+async fn list_tools(
+    server: &ContextServerState,
+    session: &Arc<Session>,
+) -> Result<BTreeMap<String, ToolInfo>> {
+    let (outgoing, _outgoing_rx) = mpsc::unbounded::<Value>();
+    let response = server
+        .handle_message(
+            session,
+            json!({ "jsonrpc": "2.0", "id": 1, "method": "tools/list" }),
+            &outgoing,
+        )
+        .await
+        .ok_or_else(|| anyhow!("No response to tools/list"))?;
+
+    let tools = response["result"]["tools"]
+        .as_array()
+        .ok_or_else(|| anyhow!("Failed to list tools"))?;
+
+    Ok(tools
+        .iter()
+        .filter_map(|tool| {
+            let name = tool["name"].as_str()?.to_string();
+            let arguments = tool["inputSchema"]["properties"]
+                .as_object()
+                .map(|properties| {
+                    properties
+                        .iter()
+                        .map(|(name, schema)| {
+                            // Optional arguments are typed as, say, ["string", "null"]
+                            let kind = match &schema["type"] {
+                                Value::String(kind) => kind.as_str(),
+                                Value::Array(kinds) => kinds
+                                    .iter()
+                                    .filter_map(Value::as_str)
+                                    .find(|kind| *kind != "null")
+                                    .unwrap_or("any"),
+                                _ => "any",
+                            };
+                            (name.clone(), kind.to_string())
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            let info = ToolInfo {
+                description: tool["description"].as_str().unwrap_or_default().to_string(),
+                arguments,
+            };
+            Some((name, info))
+        })
+        .collect())
+}
+
+/// Reads arguments given as a JSON object or as `key=value` pairs. Values
+/// of arguments that aren't strings are read as JSON, so `limit=5` is a
+/// number and `fields=["title","year"]` a list.
+fn parse_arguments(input: &str, info: &ToolInfo) -> Result<Value> {
+    let input = input.trim();
+    if input.starts_with('{') {
+        let arguments: Value =
+            serde_json::from_str(input).context("Arguments must be a valid JSON object")?;
+        if !arguments.is_object() {
+            bail!("Arguments must be a JSON object");
+        }
+        return Ok(arguments);
+    }
+
+    let words = shlex::split(input).ok_or_else(|| anyhow!("Unbalanced quotes in arguments"))?;
+    let mut arguments = Map::new();
+    for word in words {
+        let Some((name, value)) = word.split_once('=') else {
+            bail!("Expected key=value, found '{}'", word);
+        };
+        let value = match info.arguments.get(name).map(String::as_str) {
+            Some("string") => Value::String(value.to_string()),
+            _ => serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_string())),
+        };
+        arguments.insert(name.to_string(), value);
+    }
+
+    Ok(Value::Object(arguments))
+}
+
+fn print_tool(name: &str, info: &ToolInfo) {
+    println!("{}\n\n{}\n", name, info.description);
+    for (argument, kind) in &info.arguments {
+        println!("  {} ({})", argument, kind);
+    }
+}
+
+/// Indents results that are JSON, leaving text and Markdown as they are.
+fn pretty(text: &str) -> String {
+    match serde_json::from_str::<Value>(text) {
+        Ok(value @ (Value::Object(_) | Value::Array(_))) => {
+            serde_json::to_string_pretty(&value).unwrap_or_else(|_| text.to_string())
+        }
+        _ => text.to_string(),
+    }
+}
@@ -0,0 +1,106 @@
+use std::{
+    io::{self, Read},
+    sync::Arc,
+};
+
+use anyhow::{Context, Result, anyhow, bail};
+use futures::{StreamExt, channel::mpsc};
+use semantic_scholar_mcp::ContextServerState;
+use semantic_scholar_mcp_tools::Session;
+use serde_json::{Value, json};
+
+/// Runs a single tool without an MCP client and prints its result to stdout.
+/// Log messages raised on the way go to stderr, so the output can be piped.
+/// `args` is the tool's arguments as a JSON object, or `-` to read them from
+/// stdin.
+pub async fn run_tool(server: &ContextServerState, tool: &str, args: &str) -> Result<()> {
+    let args = if args == "-" {
+        let mut args = String::new();
+        io::stdin().read_to_string(&mut args)?;
+        args
+    } else {
+        args.to_string()
+    };
+    let arguments: Value =
+        serde_json::from_str(&args).context("Tool arguments must be a JSON object")?;
+    if !arguments.is_object() {
+        bail!("Tool arguments must be a JSON object");
+    }
+
+    let session = server.open_session("cli", None);
+    let text = call_tool(server, &session, tool, arguments).await?;
+    println!("{}", text);
+    Ok(())
+}
+
+/// Calls a tool in `session` and returns the text of its result, printing log
+/// messages raised on the way to stderr. Fails when the tool does.
+pub async fn call_tool(
+    server: &ContextServerState,
+    session: &Arc<Session>,
+    tool: &str,
+    arguments: Value,
+) -> Result<String> {
+    let (outgoing, mut outgoing_rx) = mpsc::unbounded::<Value>();
+    let logs = tokio::spawn(async move {
+        while let Some(notification) = outgoing_rx.next().await {
+            if notification["method"] == "notifications/message" {
+                let params = &notification["params"];
+                eprintln!(
+                    "[{}] {}: {}",
+                    params["level"].as_str().unwrap_or_default(),
+                    params["logger"].as_str().unwrap_or_default(),
+                    params["data"].as_str().unwrap_or_default()
+                );
+            }
+        }
+    });
+
+    let response = server
+        .handle_message(
+            session,
+            json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "tools/call",
+                "params": {
+                    "name": tool,
+                    "arguments": arguments,
+                },
+            }),
+            &outgoing,
+        )
+        .await;
+
+    drop(outgoing);
+    logs.await?;
+
+    let response = response.ok_or_else(|| anyhow!("No response from tool {}", tool))?;
+    if let Some(error) = response.get("error") {
+        bail!(
+            "{}",
+            error["message"].as_str().unwrap_or("Tool call failed")
+        );
+    }
+
+    let result = &response["result"];
+    let text = result["content"]
+        .as_array()
+        .map(|content| {
+            content
+                .iter()
+                .map(|item| match item["text"].as_str() {
+                    Some(text) => text.to_string(),
+                    None => item.to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .unwrap_or_default();
+
+    if result["isError"].as_bool().unwrap_or(false) {
+        bail!("{}", text);
+    }
+
+    Ok(text)
+}
@@ -0,0 +1,823 @@
+use std::{
+    env,
+    sync::{Arc, Mutex, Weak},
+    time::Duration,
+};
+
+use anyhow::{Result, anyhow};
+use cache::{Cache, NoCache};
+use context_server::{
+    ContextServer, ContextServerRpcRequest, ContextServerRpcResponse, PromptExecutor,
+    ResourceExecutor, ToolExecutor,
+};
+use context_server_utils::{
+    prompt_registry::PromptRegistry, resource_registry::ResourceRegistry,
+    tool_registry::ToolRegistry,
+};
+use dataset_sync::DatasetSync;
+use embed::{Embed, HashEmbed};
+use futures::channel::mpsc;
+use http_client::HttpClient;
+use http_client_reqwest::HttpClientReqwest;
+use library::Library;
+use semantic_scholar_mcp_tools::{
+    ApiStatusTool, AuthorBatchTool, AuthorCitationNetworkTool, AuthorDetailsTool, AuthorField,
+    AuthorPapersTool, AuthorResources, AuthorSearchTool, CacheClearTool, CitationPathTool,
+    CitationTrendTool, Completions, DatasetDownloadLinksTool, DatasetListTool, DatasetReleasesTool,
+    DefaultEntryRenderer, EMBEDDINGS, EntryRenderer, ExportRisTool, FieldsOfStudyTool,
+    FormatCitationTool, GraphExpandTool, GraphExportTool, GraphRankTool, LibraryAddTool,
+    LibraryExportTool, LibraryImportBibtexTool, LibraryListTool, LibraryRemoveTool,
+    MarkdownBibliographyTool, MemoizedEmbed, Notifier, OpenAccessPdfTool, PaperBatchTool,
+    PaperCitationsTool, PaperDetailsTool, PaperField, PaperRecommendationMultiTool,
+    PaperRecommendationSingleTool, PaperReferencesTool, PaperResources, PaperSearchTool,
+    PaperSimilarityRankTool, PaperTitleMatchTool, PeerReviewPrompt, RECOMMENDATIONS, RateLimiter,
+    RelatedWorkPrompt, ServerSettings, Session, SimilaritySettings, SnowballTool, SyncRunTool,
+    SyncStatusTool, ToolGroups, TopicRecommendationTool, VenueDetailsTool, ZoteroPushTool,
+    rpc_error, set_client_level, track_cache_age, with_notifier, with_settings,
+};
+use serde_json::{Value, json};
+use tracing::Instrument;
+use uuid::Uuid;
+
+use crate::instructions::Instructions;
+
+/// How often the services behind tool groups are checked.
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(300);
+
+/// The Semantic Scholar MCP server: its tools, resources and prompts, and the
+/// clients connected to it. Built with [`ContextServerState::builder`] and
+/// served by a [`Transport`](crate::Transport).
+pub struct ContextServerState {
+    rpc: ContextServer,
+    completions: Completions,
+    instructions: Option<String>,
+    tool_groups: ToolGroups,
+    /// Every client connected, to notify when the tool list changes.
+    sessions: Mutex<Vec<Weak<Session>>>,
+    http_client: Arc<dyn HttpClient>,
+    rate_limiter: Arc<RateLimiter>,
+    settings: Arc<ServerSettings>,
+}
+
+/// Builds a [`ContextServerState`], choosing which Semantic Scholar tools it
+/// serves and what they share. Other servers can add their own tools,
+/// resources and prompts to serve them alongside. Settings such as the API
+/// base URL apply to this server's requests alone, so servers in the same
+/// process can each have their own.
+pub struct ContextServerStateBuilder {
+    http_client: Option<Arc<dyn HttpClient>>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    cache: Option<Arc<dyn Cache>>,
+    embed: Option<Arc<dyn Embed>>,
+    renderer: Option<Arc<dyn EntryRenderer>>,
+    dataset_sync: Option<Arc<DatasetSync>>,
+    library: Option<Arc<Library>>,
+    instructions: Option<Instructions>,
+    enabled_tools: Option<Vec<String>>,
+    disabled_groups: Vec<String>,
+    tools: Vec<Arc<dyn ToolExecutor>>,
+    resources: Vec<Arc<dyn ResourceExecutor>>,
+    prompts: Vec<Arc<dyn PromptExecutor>>,
+    settings: ServerSettings,
+}
+
+impl ContextServerStateBuilder {
+    /// The client requests are sent with. Defaults to a reqwest client.
+    pub fn with_http_client(mut self, http_client: Arc<dyn HttpClient>) -> Self {
+        self.http_client = Some(http_client);
+        self
+    }
+
+    /// Defaults to the limits set by the environment.
+    pub fn with_rate_limiter(mut self, rate_limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    /// Defaults to no cache.
+    pub fn with_cache(mut self, cache: Arc<dyn Cache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// The model embedding queries for the semantic cache. Its embeddings are
+    /// memoized in the cache. Defaults to [`HashEmbed`], which needs no model.
+    pub fn with_embed(mut self, embed: Arc<dyn Embed>) -> Self {
+        self.embed = Some(embed);
+        self
+    }
+
+    /// Writes the papers and authors listed by the tools, such as to hide
+    /// URLs. Defaults to [`DefaultEntryRenderer`].
+    pub fn with_renderer(mut self, renderer: Arc<dyn EntryRenderer>) -> Self {
+        self.renderer = Some(renderer);
+        self
+    }
+
+    /// Serves the `sync_status` and `sync_run` tools, which are left out
+    /// otherwise.
+    pub fn with_dataset_sync(mut self, dataset_sync: Arc<DatasetSync>) -> Self {
+        self.dataset_sync = Some(dataset_sync);
+        self
+    }
+
+    /// Serves the `library_*` tools, which are left out otherwise.
+    pub fn with_library(mut self, library: Arc<Library>) -> Self {
+        self.library = Some(library);
+        self
+    }
+
+    /// Defaults to the built-in instructions.
+    pub fn with_instructions(mut self, instructions: Instructions) -> Self {
+        self.instructions = Some(instructions);
+        self
+    }
+
+    /// Sends requests to another host serving the Semantic Scholar API, such
+    /// as a caching proxy. Defaults to `https://api.semanticscholar.org`.
+    pub fn with_api_base_url(mut self, url: &str) -> Self {
+        self.settings = self.settings.with_api_base_url(url);
+        self
+    }
+
+    /// Answers from the cache alone, without sending anything to Semantic
+    /// Scholar. Cached results are served however old they are, and queries
+    /// are matched with a looser similarity threshold.
+    pub fn with_offline(mut self, offline: bool) -> Self {
+        self.settings = self.settings.with_offline(offline);
+        self
+    }
+
+    /// How long requests that found nothing are answered from the cache.
+    /// Zero turns negative caching off. Defaults to 5 minutes.
+    pub fn with_negative_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.settings = self.settings.with_negative_cache_ttl(ttl);
+        self
+    }
+
+    /// The fields paper listings return when a call names none. Defaults to
+    /// each tool's own.
+    pub fn with_default_paper_fields(mut self, fields: Vec<PaperField>) -> Self {
+        self.settings = self.settings.with_default_paper_fields(fields);
+        self
+    }
+
+    /// The fields author listings return when a call names none. Defaults
+    /// to each tool's own.
+    pub fn with_default_author_fields(mut self, fields: Vec<AuthorField>) -> Self {
+        self.settings = self.settings.with_default_author_fields(fields);
+        self
+    }
+
+    /// Limits the output of tool calls that set no limit of their own.
+    /// Defaults to no limit.
+    pub fn with_max_output_chars(mut self, max_chars: usize) -> Self {
+        self.settings = self.settings.with_max_output_chars(max_chars);
+        self
+    }
+
+    /// How cached queries are matched. Its thresholds are checked when
+    /// building.
+    pub fn with_similarity(mut self, similarity: SimilaritySettings) -> Self {
+        self.settings = self.settings.with_similarity(similarity);
+        self
+    }
+
+    /// Serves only the named Semantic Scholar tools, such as `paper_search`.
+    /// Tools added with [`with_tool`](Self::with_tool) are always served.
+    pub fn with_enabled_tools<I, S>(mut self, tools: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.enabled_tools = Some(tools.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Keeps the tools of a group, such as `recommendations`, disabled
+    /// whatever the health checks find.
+    pub fn with_disabled_group(mut self, group: impl Into<String>) -> Self {
+        self.disabled_groups.push(group.into());
+        self
+    }
+
+    /// Serves another tool alongside the Semantic Scholar ones.
+    pub fn with_tool(mut self, tool: Arc<dyn ToolExecutor>) -> Self {
+        self.tools.push(tool);
+        self
+    }
+
+    /// Serves another resource alongside the Semantic Scholar ones.
+    pub fn with_resource(mut self, resource: Arc<dyn ResourceExecutor>) -> Self {
+        self.resources.push(resource);
+        self
+    }
+
+    /// Serves another prompt alongside the Semantic Scholar ones.
+    pub fn with_prompt(mut self, prompt: Arc<dyn PromptExecutor>) -> Self {
+        self.prompts.push(prompt);
+        self
+    }
+
+    pub fn build(self) -> Result<ContextServerState> {
+        self.settings.similarity().validate()?;
+        let http_client = self
+            .http_client
+            .unwrap_or_else(|| Arc::new(HttpClientReqwest::default()));
+        let rate_limiter = self
+            .rate_limiter
+            .unwrap_or_else(|| Arc::new(RateLimiter::from_env()));
+        let cache = self.cache.unwrap_or_else(|| Arc::new(NoCache));
+        let embed: Arc<dyn Embed> = Arc::new(MemoizedEmbed::new(
+            self.embed.unwrap_or_else(|| Arc::new(HashEmbed::new())),
+            cache.clone(),
+        ));
+        let renderer = self
+            .renderer
+            .unwrap_or_else(|| Arc::new(DefaultEntryRenderer::new()));
+
+        let resource_registry = Arc::new(ResourceRegistry::default());
+        resource_registry.register(Arc::new(PaperResources::new(
+            http_client.clone(),
+            rate_limiter.clone(),
+            cache.clone(),
+        )));
+        resource_registry.register(Arc::new(AuthorResources::new(
+            http_client.clone(),
+            rate_limiter.clone(),
+            cache.clone(),
+        )));
+        for resource in self.resources {
+            resource_registry.register(resource);
+        }
+
+        // Every tool built with the embedding model, which skips the cache
+        // without it
+        let embedding_tools: Vec<Arc<dyn ToolExecutor>> = vec![
+            Arc::new(AuthorDetailsTool::new(
+                http_client.clone(),
+                rate_limiter.clone(),
+                cache.clone(),
+                embed.clone(),
+            )),
+            Arc::new(
+                AuthorPapersTool::new(
+                    http_client.clone(),
+                    rate_limiter.clone(),
+                    cache.clone(),
+                    embed.clone(),
+                )
+                .with_renderer(renderer.clone()),
+            ),
+            Arc::new(
+                AuthorSearchTool::new(
+                    http_client.clone(),
+                    rate_limiter.clone(),
+                    cache.clone(),
+                    embed.clone(),
+                )
+                .with_renderer(renderer.clone()),
+            ),
+            Arc::new(
+                PaperSearchTool::new(
+                    http_client.clone(),
+                    rate_limiter.clone(),
+                    cache.clone(),
+                    embed.clone(),
+                )
+                .with_renderer(renderer.clone()),
+            ),
+            Arc::new(PaperDetailsTool::new(
+                http_client.clone(),
+                rate_limiter.clone(),
+                cache.clone(),
+                embed.clone(),
+            )),
+            Arc::new(
+                PaperBatchTool::new(
+                    http_client.clone(),
+                    rate_limiter.clone(),
+                    cache.clone(),
+                    embed.clone(),
+                )
+                .with_renderer(renderer.clone()),
+            ),
+            Arc::new(
+                PaperCitationsTool::new(
+                    http_client.clone(),
+                    rate_limiter.clone(),
+                    cache.clone(),
+                    embed.clone(),
+                )
+                .with_renderer(renderer.clone()),
+            ),
+            Arc::new(
+                PaperReferencesTool::new(
+                    http_client.clone(),
+                    rate_limiter.clone(),
+                    cache.clone(),
+                    embed.clone(),
+                )
+                .with_renderer(renderer.clone()),
+            ),
+            Arc::new(
+                PaperRecommendationSingleTool::new(
+                    http_client.clone(),
+                    rate_limiter.clone(),
+                    cache.clone(),
+                    embed.clone(),
+                )
+                .with_renderer(renderer.clone()),
+            ),
+            Arc::new(
+                PaperRecommendationMultiTool::new(
+                    http_client.clone(),
+                    rate_limiter.clone(),
+                    cache.clone(),
+                    embed.clone(),
+                )
+                .with_renderer(renderer.clone()),
+            ),
+            Arc::new(
+                AuthorBatchTool::new(
+                    http_client.clone(),
+                    rate_limiter.clone(),
+                    cache.clone(),
+                    embed.clone(),
+                )
+                .with_renderer(renderer.clone()),
+            ),
+            Arc::new(
+                PaperTitleMatchTool::new(
+                    http_client.clone(),
+                    rate_limiter.clone(),
+                    cache.clone(),
+                    embed.clone(),
+                )
+                .with_renderer(renderer.clone()),
+            ),
+            Arc::new(PaperSimilarityRankTool::new(
+                http_client.clone(),
+                rate_limiter.clone(),
+                cache.clone(),
+                embed.clone(),
+            )),
+            Arc::new(VenueDetailsTool::new(
+                http_client.clone(),
+                rate_limiter.clone(),
+                cache.clone(),
+                embed.clone(),
+            )),
+            Arc::new(TopicRecommendationTool::new(
+                http_client.clone(),
+                rate_limiter.clone(),
+                cache.clone(),
+                embed.clone(),
+            )),
+            Arc::new(OpenAccessPdfTool::new(
+                http_client.clone(),
+                rate_limiter.clone(),
+                cache.clone(),
+                embed.clone(),
+            )),
+            Arc::new(AuthorCitationNetworkTool::new(
+                http_client.clone(),
+                rate_limiter.clone(),
+                cache.clone(),
+                embed.clone(),
+            )),
+            Arc::new(
+                GraphExpandTool::new(
+                    http_client.clone(),
+                    rate_limiter.clone(),
+                    cache.clone(),
+                    embed.clone(),
+                )
+                .with_renderer(renderer.clone()),
+            ),
+            Arc::new(
+                GraphRankTool::new(
+                    http_client.clone(),
+                    rate_limiter.clone(),
+                    cache.clone(),
+                    embed.clone(),
+                )
+                .with_renderer(renderer.clone()),
+            ),
+            Arc::new(
+                SnowballTool::new(
+                    http_client.clone(),
+                    rate_limiter.clone(),
+                    cache.clone(),
+                    embed.clone(),
+                )
+                .with_renderer(renderer.clone()),
+            ),
+            Arc::new(
+                CitationPathTool::new(
+                    http_client.clone(),
+                    rate_limiter.clone(),
+                    cache.clone(),
+                    embed.clone(),
+                )
+                .with_renderer(renderer.clone()),
+            ),
+            Arc::new(
+                CitationTrendTool::new(
+                    http_client.clone(),
+                    rate_limiter.clone(),
+                    cache.clone(),
+                    embed.clone(),
+                )
+                .with_renderer(renderer.clone()),
+            ),
+        ];
+        let mut other_tools: Vec<Arc<dyn ToolExecutor>> = vec![
+            Arc::new(ExportRisTool::new(
+                http_client.clone(),
+                rate_limiter.clone(),
+                cache.clone(),
+            )),
+            Arc::new(MarkdownBibliographyTool::new(
+                http_client.clone(),
+                rate_limiter.clone(),
+                cache.clone(),
+            )),
+            Arc::new(FormatCitationTool::new(
+                http_client.clone(),
+                rate_limiter.clone(),
+                cache.clone(),
+            )),
+            Arc::new(GraphExportTool::new(
+                http_client.clone(),
+                rate_limiter.clone(),
+                cache.clone(),
+            )),
+            Arc::new(ZoteroPushTool::new(
+                http_client.clone(),
+                rate_limiter.clone(),
+                cache.clone(),
+            )),
+            Arc::new(DatasetReleasesTool::new(
+                http_client.clone(),
+                rate_limiter.clone(),
+            )),
+            Arc::new(DatasetListTool::new(
+                http_client.clone(),
+                rate_limiter.clone(),
+            )),
+            Arc::new(DatasetDownloadLinksTool::new(
+                http_client.clone(),
+                rate_limiter.clone(),
+            )),
+            Arc::new(FieldsOfStudyTool::new()),
+            Arc::new(ApiStatusTool::new(rate_limiter.clone())),
+            Arc::new(CacheClearTool::new(cache.clone())),
+        ];
+
+        if let Some(library) = &self.library {
+            other_tools.push(Arc::new(LibraryImportBibtexTool::new(
+                http_client.clone(),
+                rate_limiter.clone(),
+                cache.clone(),
+                library.clone(),
+            )));
+            other_tools.push(Arc::new(LibraryAddTool::new(
+                http_client.clone(),
+                rate_limiter.clone(),
+                cache.clone(),
+                library.clone(),
+            )));
+            other_tools.push(Arc::new(LibraryListTool::new(library.clone())));
+            other_tools.push(Arc::new(LibraryRemoveTool::new(
+                http_client.clone(),
+                rate_limiter.clone(),
+                cache.clone(),
+                library.clone(),
+            )));
+            other_tools.push(Arc::new(LibraryExportTool::new(
+                http_client.clone(),
+                rate_limiter.clone(),
+                cache.clone(),
+                library.clone(),
+            )));
+        }
+        if let Some(dataset_sync) = &self.dataset_sync {
+            other_tools.push(Arc::new(SyncStatusTool::new(dataset_sync.clone())));
+            other_tools.push(Arc::new(SyncRunTool::new(dataset_sync.clone())));
+        }
+
+        // Tools left out are dropped before grouping, so the health checks
+        // only ever toggle tools that are served
+        let enabled = |tool: &Arc<dyn ToolExecutor>| {
+            self.enabled_tools
+                .as_ref()
+                .is_none_or(|enabled| enabled.contains(&tool.to_tool().name))
+        };
+        let embedding_tools: Vec<_> = embedding_tools.into_iter().filter(enabled).collect();
+        let other_tools: Vec<_> = other_tools.into_iter().filter(enabled).collect();
+
+        let mut tool_groups = ToolGroups::new();
+        tool_groups
+            .add(
+                EMBEDDINGS,
+                embedding_tools.iter().map(|tool| tool.to_tool().name),
+            )
+            .add(
+                RECOMMENDATIONS,
+                [
+                    "paper_recommendations_single",
+                    "paper_recommendations_multi",
+                    "topic_recommendations",
+                ],
+            )
+            .add(
+                "datasets",
+                ["dataset_releases", "dataset_list", "dataset_download_links"],
+            )
+            .add("sync", ["sync_status", "sync_run"]);
+        for group in &self.disabled_groups {
+            tool_groups.pin_disabled(group)?;
+        }
+
+        let tool_registry = Arc::new(ToolRegistry::default());
+        let tools: Vec<_> = embedding_tools
+            .into_iter()
+            .chain(other_tools)
+            .chain(self.tools)
+            .collect();
+        for tool in &tools {
+            tool_registry.register(tool.clone());
+        }
+
+        let tool_definitions: Vec<_> = tools.iter().map(|tool| tool.to_tool()).collect();
+        let instructions = self
+            .instructions
+            .map_or_else(|| Instructions::load(None), Ok)?
+            .render(
+                &tool_definitions,
+                env::var("SEMANTIC_SCHOLAR_API_KEY").is_ok(),
+            );
+        let completions = Completions::new(cache.clone(), tool_definitions);
+
+        let prompt_registry = Arc::new(PromptRegistry::default());
+        prompt_registry.register(Arc::new(RelatedWorkPrompt::new(
+            http_client.clone(),
+            rate_limiter.clone(),
+            cache.clone(),
+        )));
+        prompt_registry.register(Arc::new(PeerReviewPrompt::new(
+            http_client.clone(),
+            rate_limiter.clone(),
+            cache.clone(),
+        )));
+        for prompt in self.prompts {
+            prompt_registry.register(prompt);
+        }
+
+        Ok(ContextServerState {
+            rpc: ContextServer::builder()
+                .with_server_info((env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")))
+                .with_resources(resource_registry)
+                .with_tools(tool_registry)
+                .with_prompts(prompt_registry)
+                .build()?,
+            completions,
+            instructions,
+            tool_groups,
+            sessions: Mutex::new(Vec::new()),
+            http_client,
+            rate_limiter,
+            settings: Arc::new(self.settings),
+        })
+    }
+}
+
+impl ContextServerState {
+    pub fn builder() -> ContextServerStateBuilder {
+        ContextServerStateBuilder {
+            http_client: None,
+            rate_limiter: None,
+            cache: None,
+            embed: None,
+            renderer: None,
+            dataset_sync: None,
+            library: None,
+            instructions: None,
+            enabled_tools: None,
+            disabled_groups: Vec::new(),
+            tools: Vec::new(),
+            resources: Vec::new(),
+            prompts: Vec::new(),
+            settings: ServerSettings::new(),
+        }
+    }
+
+    /// Starts the session of a newly connected client. Transports that keep
+    /// a stream open to the client pass it as `outgoing`, so the client
+    /// hears about changes to the tool list.
+    pub fn open_session(
+        &self,
+        id: impl Into<String>,
+        outgoing: Option<mpsc::UnboundedSender<Value>>,
+    ) -> Arc<Session> {
+        let session = Arc::new(Session::new(id, outgoing));
+        if let Ok(mut sessions) = self.sessions.lock() {
+            sessions.retain(|session| session.strong_count() > 0);
+            sessions.push(Arc::downgrade(&session));
+        }
+        session
+    }
+
+    /// Sends a notification to every connected client.
+    fn notify_all(&self, method: &str) {
+        let sessions: Vec<_> = match self.sessions.lock() {
+            Ok(sessions) => sessions.iter().filter_map(Weak::upgrade).collect(),
+            Err(_) => return,
+        };
+
+        for session in sessions {
+            session.notify(method, json!({}));
+        }
+    }
+
+    /// Checks the services tool groups depend on every few minutes, telling
+    /// clients to list the tools again when groups are enabled or disabled.
+    pub async fn monitor_tool_groups(&self) {
+        loop {
+            let health_check = self
+                .tool_groups
+                .check_health(&self.http_client, &self.rate_limiter);
+            if with_settings(self.settings.clone(), health_check).await {
+                self.notify_all("notifications/tools/list_changed");
+            }
+            tokio::time::sleep(HEALTH_CHECK_INTERVAL).await;
+        }
+    }
+
+    async fn process_request(
+        &self,
+        request: ContextServerRpcRequest,
+    ) -> Result<Option<ContextServerRpcResponse>> {
+        self.rpc.handle_incoming_message(request).await
+    }
+
+    /// Handles one JSON-RPC message from a client, returning the response to
+    /// send back, if any. Notifications sent while the request is handled,
+    /// such as progress and log messages, go to `outgoing`. Transports keep
+    /// one session per client; everything else is shared between clients.
+    pub async fn handle_message(
+        &self,
+        session: &Arc<Session>,
+        message: Value,
+        outgoing: &mpsc::UnboundedSender<Value>,
+    ) -> Option<Value> {
+        // Every event raised while the message is handled carries the
+        // request it belongs to, and the tool for tool calls
+        let method = message
+            .get("method")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let span = tracing::info_span!(
+            "request",
+            request_id = %Uuid::new_v4(),
+            session = session.id(),
+            method,
+            tool = tracing::field::Empty,
+        );
+        if method == "tools/call" {
+            if let Some(tool) = message.pointer("/params/name").and_then(Value::as_str) {
+                span.record("tool", tool);
+            }
+        }
+
+        self.dispatch(session, message, outgoing)
+            .instrument(span)
+            .await
+    }
+
+    async fn dispatch(
+        &self,
+        session: &Arc<Session>,
+        message: Value,
+        outgoing: &mpsc::UnboundedSender<Value>,
+    ) -> Option<Value> {
+        // Kept aside so a failed request can still be answered with its id
+        let id = message.get("id").cloned();
+        let method = message
+            .get("method")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        let notifier = Notifier::new(
+            session.clone(),
+            outgoing.clone(),
+            message.pointer("/params/_meta/progressToken").cloned(),
+        );
+
+        // Logging and completions are handled here, since the server
+        // library doesn't know them
+        match method.as_deref() {
+            Some("tools/call") => {
+                let name = message
+                    .pointer("/params/name")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default();
+                if !self.tool_groups.is_enabled(name) {
+                    return respond(
+                        id,
+                        Err(anyhow!(
+                            "Tool {} is unavailable while a service it depends on is down or disabled",
+                            name
+                        )),
+                    );
+                }
+            }
+            Some("logging/setLevel") => {
+                let level = message
+                    .pointer("/params/level")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default();
+                return respond(id, set_client_level(session, level).map(|_| json!({})));
+            }
+            Some("completion/complete") => {
+                return respond(id, self.completions.complete(session, &message["params"]));
+            }
+            _ => {}
+        }
+
+        let request: ContextServerRpcRequest = match serde_json::from_value(message) {
+            Ok(req) => req,
+            Err(e) => {
+                tracing::error!("Error parsing request: {}", e);
+                return None;
+            }
+        };
+
+        let handler = with_notifier(notifier, self.process_request(request));
+        let (response, cached_at) =
+            track_cache_age(with_settings(self.settings.clone(), handler)).await;
+        let response = match response {
+            Ok(Some(response)) => serde_json::to_value(&response).map_err(Into::into),
+            Ok(None) => return None,
+            Err(err) => Err(err),
+        };
+
+        match response {
+            Ok(mut response) => {
+                if method.as_deref() == Some("initialize") {
+                    let capabilities = &mut response["result"]["capabilities"];
+                    capabilities["logging"] = json!({});
+                    capabilities["completions"] = json!({});
+                    capabilities["tools"]["listChanged"] = json!(true);
+                    if let Some(instructions) = &self.instructions {
+                        response["result"]["instructions"] = json!(instructions);
+                    }
+                }
+                // Offline answers say how old the data behind them is
+                if let Some(cached_at) = cached_at.filter(|_| self.settings.client().is_offline()) {
+                    if let Some(content) = response["result"]["content"].as_array_mut() {
+                        content.push(json!({
+                            "type": "text",
+                            "text": format!(
+                                "Answered offline from data cached at {} UTC.",
+                                cached_at.format("%Y-%m-%d %H:%M:%S")
+                            ),
+                        }));
+                    }
+                }
+                if method.as_deref() == Some("tools/list") {
+                    if let Some(tools) = response["result"]["tools"].as_array_mut() {
+                        tools.retain(|tool| {
+                            tool["name"]
+                                .as_str()
+                                .is_none_or(|name| self.tool_groups.is_enabled(name))
+                        });
+                    }
+                }
+                Some(response)
+            }
+            Err(err) => respond(id, Err(err)),
+        }
+    }
+}
+
+/// A JSON-RPC response to a request answered outside the server library.
+/// Notifications, which have no id, get no response.
+fn respond(id: Option<Value>, result: Result<Value>) -> Option<Value> {
+    let id = id?;
+    match result {
+        Ok(result) => Some(json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "result": result,
+        })),
+        Err(err) => {
+            tracing::error!("Error handling request: {:#}", err);
+            Some(json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": rpc_error(&err),
+            }))
+        }
+    }
+}
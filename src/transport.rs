@@ -0,0 +1,22 @@
+mod sse;
+mod stdio;
+mod streamable_http;
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+pub use self::{
+    sse::SseTransport, stdio::StdioTransport, streamable_http::StreamableHttpTransport,
+};
+use crate::ContextServerState;
+
+/// Carries JSON-RPC messages between clients and the server. Transports
+/// hand every message to [`ContextServerState::handle_message`] and deliver
+/// its response, along with the notifications sent while it was handled.
+#[async_trait]
+pub trait Transport: Send + Sync {
+    /// Serves clients until the transport is closed.
+    async fn serve(&self, server: Arc<ContextServerState>) -> Result<()>;
+}
@@ -0,0 +1,136 @@
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use axum::{
+    Json, Router,
+    extract::{Query, State},
+    http::StatusCode,
+    response::sse::{Event, KeepAlive, Sse},
+    routing::{get, post},
+};
+use futures::{Stream, StreamExt, channel::mpsc, stream};
+use semantic_scholar_mcp_tools::Session;
+use serde::Deserialize;
+use serde_json::Value;
+use uuid::Uuid;
+
+use super::Transport;
+use crate::ContextServerState;
+
+/// Open event streams and their sessions, keyed by the session ID in their
+/// message endpoint.
+struct SseState {
+    server: Arc<ContextServerState>,
+    sessions: Mutex<HashMap<String, (Arc<Session>, mpsc::UnboundedSender<Value>)>>,
+}
+
+/// Forgets a session once its event stream is closed.
+struct SessionGuard {
+    state: Arc<SseState>,
+    session_id: String,
+}
+
+impl Drop for SessionGuard {
+    fn drop(&mut self) {
+        if let Ok(mut sessions) = self.state.sessions.lock() {
+            sessions.remove(&self.session_id);
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct MessageQuery {
+    session_id: String,
+}
+
+/// Serves any number of clients over HTTP with server-sent events: clients
+/// open an event stream on `/sse`, which first names the endpoint to POST
+/// their messages to. Responses and notifications arrive on the stream.
+pub struct SseTransport {
+    addr: SocketAddr,
+}
+
+impl SseTransport {
+    pub fn new(addr: SocketAddr) -> Self {
+        Self { addr }
+    }
+}
+
+#[async_trait]
+impl Transport for SseTransport {
+    async fn serve(&self, server: Arc<ContextServerState>) -> Result<()> {
+        let state = Arc::new(SseState {
+            server,
+            sessions: Mutex::new(HashMap::new()),
+        });
+
+        let app = Router::new()
+            .route("/sse", get(connect))
+            .route("/messages", post(message))
+            .with_state(state);
+
+        let listener = tokio::net::TcpListener::bind(self.addr).await?;
+        tracing::info!("Listening for SSE clients on http://{}/sse", self.addr);
+        axum::serve(listener, app).await?;
+
+        Ok(())
+    }
+}
+
+async fn connect(
+    State(state): State<Arc<SseState>>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let session_id = Uuid::new_v4().to_string();
+    let (sender, receiver) = mpsc::unbounded::<Value>();
+
+    if let Ok(mut sessions) = state.sessions.lock() {
+        let session = state
+            .server
+            .open_session(session_id.clone(), Some(sender.clone()));
+        sessions.insert(session_id.clone(), (session, sender));
+    }
+
+    let endpoint = Event::default()
+        .event("endpoint")
+        .data(format!("/messages?session_id={}", session_id));
+
+    let guard = SessionGuard { state, session_id };
+    let messages = receiver.map(move |message| {
+        let _ = &guard;
+        Ok(Event::default().event("message").data(message.to_string()))
+    });
+
+    Sse::new(stream::once(async { Ok(endpoint) }).chain(messages)).keep_alive(KeepAlive::default())
+}
+
+async fn message(
+    State(state): State<Arc<SseState>>,
+    Query(query): Query<MessageQuery>,
+    Json(message): Json<Value>,
+) -> StatusCode {
+    let session = state
+        .sessions
+        .lock()
+        .ok()
+        .and_then(|sessions| sessions.get(&query.session_id).cloned());
+
+    let Some((session, sender)) = session else {
+        return StatusCode::NOT_FOUND;
+    };
+
+    // Requests are handled concurrently, answering on the event stream
+    let server = state.server.clone();
+    tokio::spawn(async move {
+        if let Some(response) = server.handle_message(&session, message, &sender).await {
+            let _ = sender.unbounded_send(response);
+        }
+    });
+
+    StatusCode::ACCEPTED
+}
@@ -0,0 +1,74 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::{StreamExt, channel::mpsc};
+use serde_json::Value;
+use tokio::{
+    io::{self, AsyncBufReadExt, AsyncWriteExt, BufReader},
+    task::JoinSet,
+};
+
+use super::Transport;
+use crate::ContextServerState;
+
+/// Serves a single client that runs the server as a child process, with one
+/// JSON-RPC message per line on stdin and stdout. Requests are handled
+/// concurrently, so a slow tool call doesn't hold up the ones after it.
+pub struct StdioTransport;
+
+#[async_trait]
+impl Transport for StdioTransport {
+    async fn serve(&self, server: Arc<ContextServerState>) -> Result<()> {
+        // Responses and the notifications sent while a request is handled
+        // share one writer, so lines never interleave
+        let (outgoing, mut outgoing_rx) = mpsc::unbounded::<Value>();
+        let writer = tokio::spawn(async move {
+            let mut stdout = io::stdout();
+            while let Some(message) = outgoing_rx.next().await {
+                stdout
+                    .write_all(serde_json::to_string(&message)?.as_bytes())
+                    .await?;
+                stdout.write_all(b"\n").await?;
+                stdout.flush().await?;
+            }
+            anyhow::Ok(())
+        });
+
+        let session = server.open_session("stdio", Some(outgoing.clone()));
+        let mut stdin = BufReader::new(io::stdin()).lines();
+        let mut handlers = JoinSet::new();
+
+        while let Some(line) = stdin.next_line().await? {
+            let message: Value = match serde_json::from_str(&line) {
+                Ok(message) => message,
+                Err(e) => {
+                    tracing::warn!("Error parsing request: {}", e);
+                    continue;
+                }
+            };
+
+            let server = server.clone();
+            let session = session.clone();
+            let outgoing = outgoing.clone();
+            handlers.spawn(async move {
+                if let Some(response) = server.handle_message(&session, message, &outgoing).await {
+                    let _ = outgoing.unbounded_send(response);
+                }
+            });
+
+            // Forget the requests answered so far
+            while handlers.try_join_next().is_some() {}
+        }
+
+        // Requests still running when stdin closes are answered first
+        while let Some(handled) = handlers.join_next().await {
+            handled?;
+        }
+
+        // The session holds a sender too; the writer stops once all are gone
+        drop(session);
+        drop(outgoing);
+        writer.await?
+    }
+}
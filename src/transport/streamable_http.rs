@@ -0,0 +1,206 @@
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use axum::{
+    Json, Router,
+    extract::State,
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    response::{
+        IntoResponse, Response,
+        sse::{Event, KeepAlive, Sse},
+    },
+    routing::post,
+};
+use futures::{StreamExt, channel::mpsc, future::join_all};
+use semantic_scholar_mcp_tools::Session;
+use serde_json::Value;
+use uuid::Uuid;
+
+use super::Transport;
+use crate::ContextServerState;
+
+const SESSION_HEADER: &str = "Mcp-Session-Id";
+
+/// Sessions handed out in response to `initialize`, by ID.
+struct StreamableHttpState {
+    server: Arc<ContextServerState>,
+    sessions: Mutex<HashMap<String, Arc<Session>>>,
+}
+
+/// Serves any number of clients over HTTP on a single `/mcp` endpoint, as in
+/// the streamable HTTP transport of the MCP specification. Clients POST
+/// messages and get the responses back either as a JSON body or, when they
+/// accept `text/event-stream`, as a stream that also carries the
+/// notifications sent while the requests are handled. Sessions start with
+/// `initialize` and are named in the `Mcp-Session-Id` header.
+pub struct StreamableHttpTransport {
+    addr: SocketAddr,
+}
+
+impl StreamableHttpTransport {
+    pub fn new(addr: SocketAddr) -> Self {
+        Self { addr }
+    }
+}
+
+#[async_trait]
+impl Transport for StreamableHttpTransport {
+    async fn serve(&self, server: Arc<ContextServerState>) -> Result<()> {
+        let state = Arc::new(StreamableHttpState {
+            server,
+            sessions: Mutex::new(HashMap::new()),
+        });
+
+        // Server-initiated streams (GET) are optional and not offered
+        let app = Router::new()
+            .route("/mcp", post(message).delete(close_session))
+            .with_state(state);
+
+        let listener = tokio::net::TcpListener::bind(self.addr).await?;
+        tracing::info!(
+            "Listening for streamable HTTP clients on http://{}/mcp",
+            self.addr
+        );
+        axum::serve(listener, app).await?;
+
+        Ok(())
+    }
+}
+
+fn header_session_id(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(SESSION_HEADER)
+        .and_then(|value| value.to_str().ok())
+}
+
+fn find_session(state: &StreamableHttpState, session_id: &str) -> Option<Arc<Session>> {
+    state
+        .sessions
+        .lock()
+        .ok()
+        .and_then(|sessions| sessions.get(session_id).cloned())
+}
+
+async fn message(
+    State(state): State<Arc<StreamableHttpState>>,
+    headers: HeaderMap,
+    Json(body): Json<Value>,
+) -> Response {
+    let is_batch = body.is_array();
+    let messages = match body {
+        Value::Array(messages) => messages,
+        message => vec![message],
+    };
+
+    let is_initialize = messages
+        .iter()
+        .any(|message| message["method"] == "initialize");
+
+    let session = if is_initialize {
+        // Without a standing stream, there is nowhere to send notifications
+        // outside of a request
+        let session = state.server.open_session(Uuid::new_v4().to_string(), None);
+        if let Ok(mut sessions) = state.sessions.lock() {
+            sessions.insert(session.id().to_string(), session.clone());
+        }
+        session
+    } else {
+        let Some(session_id) = header_session_id(&headers) else {
+            return (StatusCode::BAD_REQUEST, "Missing Mcp-Session-Id header").into_response();
+        };
+        match find_session(&state, session_id) {
+            Some(session) => session,
+            None => return (StatusCode::NOT_FOUND, "Unknown session").into_response(),
+        }
+    };
+
+    let has_requests = messages
+        .iter()
+        .any(|message| message.get("method").is_some() && message.get("id").is_some());
+
+    let (outgoing, outgoing_rx) = mpsc::unbounded::<Value>();
+
+    // Requests in a batch are handled concurrently, each response sent as
+    // soon as it is ready. The stream ends once every request is answered
+    let server = state.server.clone();
+    let request_session = session.clone();
+    tokio::spawn(async move {
+        join_all(messages.into_iter().map(|message| {
+            let outgoing = outgoing.clone();
+            let server = server.clone();
+            let session = request_session.clone();
+            async move {
+                if let Some(response) = server.handle_message(&session, message, &outgoing).await {
+                    let _ = outgoing.unbounded_send(response);
+                }
+            }
+        }))
+        .await;
+    });
+
+    let mut response = if !has_requests {
+        // Notifications and responses from the client are only acknowledged
+        StatusCode::ACCEPTED.into_response()
+    } else if accepts_event_stream(&headers) {
+        let events = outgoing_rx
+            .map(|message| Ok::<_, Infallible>(Event::default().data(message.to_string())));
+        Sse::new(events)
+            .keep_alive(KeepAlive::default())
+            .into_response()
+    } else {
+        // Without a stream, notifications have nowhere to go
+        let responses: Vec<Value> = outgoing_rx
+            .filter(|message| futures::future::ready(message.get("method").is_none()))
+            .collect()
+            .await;
+
+        if is_batch {
+            Json(Value::Array(responses)).into_response()
+        } else {
+            match responses.into_iter().next() {
+                Some(response) => Json(response).into_response(),
+                None => StatusCode::ACCEPTED.into_response(),
+            }
+        }
+    };
+
+    if let Ok(value) = HeaderValue::from_str(session.id()) {
+        response.headers_mut().insert(SESSION_HEADER, value);
+    }
+
+    response
+}
+
+fn accepts_event_stream(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(header::ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .any(|value| value.contains("text/event-stream"))
+}
+
+async fn close_session(
+    State(state): State<Arc<StreamableHttpState>>,
+    headers: HeaderMap,
+) -> StatusCode {
+    let Some(session_id) = header_session_id(&headers) else {
+        return StatusCode::BAD_REQUEST;
+    };
+
+    let removed = state
+        .sessions
+        .lock()
+        .is_ok_and(|mut sessions| sessions.remove(session_id).is_some());
+
+    if removed {
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}